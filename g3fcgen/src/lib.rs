@@ -94,6 +94,8 @@ pub async fn run(proc_args: &ProcArgs) -> anyhow::Result<()> {
 
     let frontend_stats = Arc::new(FrontendStats::default());
     if let Some(stats_config) = g3_daemon::stat::config::get_global_stat_config() {
+        g3_daemon::stat::prometheus::spawn_exporter_thread()?;
+        g3_daemon::stat::otlp::spawn_exporter_thread()?;
         stat::spawn_working_thread(
             stats_config,
             backend_stats,
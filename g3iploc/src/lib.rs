@@ -31,6 +31,8 @@ use frontend::{FrontendStats, UdpDgramFrontend};
 pub async fn run(proc_args: &ProcArgs) -> anyhow::Result<()> {
     let frontend_stats = Arc::new(FrontendStats::default());
     if let Some(stats_config) = g3_daemon::stat::config::get_global_stat_config() {
+        g3_daemon::stat::prometheus::spawn_exporter_thread()?;
+        g3_daemon::stat::otlp::spawn_exporter_thread()?;
         stat::spawn_working_thread(stats_config, frontend_stats.clone())?;
     }
 
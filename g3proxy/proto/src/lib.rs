@@ -38,3 +38,7 @@ pub mod escaper_capnp {
 pub mod server_capnp {
     include!(concat!(env!("OUT_DIR"), "/server_capnp.rs"));
 }
+
+pub mod http_cache_capnp {
+    include!(concat!(env!("OUT_DIR"), "/http_cache_capnp.rs"));
+}
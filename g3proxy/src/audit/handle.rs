@@ -19,8 +19,9 @@ use std::sync::Arc;
 use slog::Logger;
 
 use g3_dpi::{
-    H1InterceptionConfig, H2InterceptionConfig, ProtocolInspectPolicy, ProtocolInspectionConfig,
-    ProtocolPortMap, SmtpInterceptionConfig,
+    H1InterceptionConfig, H2InterceptionConfig, ImapInterceptionConfig, Pop3InterceptionConfig,
+    ProtocolInspectPolicy, ProtocolInspectionConfig, ProtocolPortMap, SmtpInterceptionConfig,
+    WebSocketInterceptionConfig,
 };
 use g3_icap_client::reqmod::IcapReqmodClient;
 use g3_icap_client::respmod::IcapRespmodClient;
@@ -121,11 +122,36 @@ impl AuditHandle {
         self.auditor_config.smtp_inspect_policy
     }
 
+    #[inline]
+    pub(crate) fn imap_inspect_policy(&self) -> ProtocolInspectPolicy {
+        self.auditor_config.imap_inspect_policy
+    }
+
+    #[inline]
+    pub(crate) fn pop3_inspect_policy(&self) -> ProtocolInspectPolicy {
+        self.auditor_config.pop3_inspect_policy
+    }
+
     #[inline]
     pub(crate) fn smtp_interception(&self) -> &SmtpInterceptionConfig {
         &self.auditor_config.smtp_interception
     }
 
+    #[inline]
+    pub(crate) fn imap_interception(&self) -> &ImapInterceptionConfig {
+        &self.auditor_config.imap_interception
+    }
+
+    #[inline]
+    pub(crate) fn pop3_interception(&self) -> &Pop3InterceptionConfig {
+        &self.auditor_config.pop3_interception
+    }
+
+    #[inline]
+    pub(crate) fn websocket_interception(&self) -> &WebSocketInterceptionConfig {
+        &self.auditor_config.websocket_interception
+    }
+
     #[inline]
     pub(crate) fn icap_reqmod_client(&self) -> Option<&IcapReqmodClient> {
         self.icap_reqmod_client.as_ref()
@@ -142,4 +168,13 @@ impl AuditHandle {
         let mut rng = rand::thread_rng();
         self.auditor_config.task_audit_ratio.sample(&mut rng)
     }
+
+    pub(crate) fn do_websocket_message_audit(&self) -> bool {
+        use rand::distributions::Distribution;
+
+        let mut rng = rand::thread_rng();
+        self.auditor_config
+            .websocket_message_audit_ratio
+            .sample(&mut rng)
+    }
 }
@@ -0,0 +1,146 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::Write;
+
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::x509::{X509Ref, X509};
+use rustls::ServerConnection;
+
+use crate::config::auth::{ClientCertUserField, UserClientCertConfig};
+
+/// get the verified client certificate of a rustls server connection
+pub(crate) fn rustls_peer_cert(conn: &ServerConnection) -> Option<X509> {
+    let cert = conn.peer_certificates()?.first()?;
+    X509::from_der(cert.as_ref()).ok()
+}
+
+fn strip_prefix(config: &UserClientCertConfig, value: &str) -> Option<String> {
+    match &config.strip_prefix {
+        Some(prefix) => value.strip_prefix(prefix).map(|s| s.to_string()),
+        None => Some(value.to_string()),
+    }
+}
+
+/// get the username from the configured field of the client certificate
+pub(crate) fn get_username(config: &UserClientCertConfig, cert: &X509Ref) -> Option<String> {
+    let username = match config.field {
+        ClientCertUserField::SubjectCn => {
+            let entry = cert.subject_name().entries_by_nid(Nid::COMMONNAME).next()?;
+            let cn = entry.data().as_utf8().ok()?;
+            strip_prefix(config, &cn)?
+        }
+        ClientCertUserField::SanEmail => {
+            let names = cert.subject_alt_names()?;
+            names
+                .iter()
+                .filter_map(|n| n.email())
+                .find_map(|v| strip_prefix(config, v))?
+        }
+        ClientCertUserField::SanUri => {
+            let names = cert.subject_alt_names()?;
+            names
+                .iter()
+                .filter_map(|n| n.uri())
+                .find_map(|v| strip_prefix(config, v))?
+        }
+        ClientCertUserField::Sha256Fingerprint => {
+            let digest = cert.digest(MessageDigest::sha256()).ok()?;
+            let mut s = String::with_capacity(digest.len() * 2);
+            for b in digest.iter() {
+                let _ = write!(s, "{b:02x}");
+            }
+            s
+        }
+    };
+    if username.is_empty() {
+        None
+    } else {
+        Some(username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::pkey::PKey;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::X509NameBuilder;
+
+    fn build_cert() -> X509 {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "svc-a").unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .email("svc-a@example.net")
+            .uri("spiffe://cluster.local/ns/default/sa/svc-a")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    fn config(field: ClientCertUserField, prefix: Option<&str>) -> UserClientCertConfig {
+        UserClientCertConfig {
+            field,
+            strip_prefix: prefix.map(|s| s.to_string()),
+            require_basic_auth: false,
+        }
+    }
+
+    #[test]
+    fn username() {
+        let cert = build_cert();
+
+        let c = config(ClientCertUserField::SubjectCn, None);
+        assert_eq!(get_username(&c, &cert).unwrap(), "svc-a");
+
+        let c = config(ClientCertUserField::SanEmail, None);
+        assert_eq!(get_username(&c, &cert).unwrap(), "svc-a@example.net");
+
+        let c = config(
+            ClientCertUserField::SanUri,
+            Some("spiffe://cluster.local/ns/default/sa/"),
+        );
+        assert_eq!(get_username(&c, &cert).unwrap(), "svc-a");
+
+        let c = config(ClientCertUserField::SanUri, Some("spiffe://other/"));
+        assert!(get_username(&c, &cert).is_none());
+
+        let c = config(ClientCertUserField::Sha256Fingerprint, None);
+        let fingerprint = get_username(&c, &cert).unwrap();
+        assert_eq!(fingerprint.len(), 64);
+    }
+}
@@ -0,0 +1,294 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use base64::prelude::*;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::error::ErrorStack;
+use openssl::hash::{Hasher, MessageDigest};
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey, Public};
+use openssl::rsa::{Padding, Rsa};
+use openssl::sign::{RsaPssSaltlen, Verifier};
+use serde_json::{Map, Value};
+
+use crate::config::auth::JwtAlgorithm;
+
+pub(super) fn decode_base64url(s: &str) -> anyhow::Result<Vec<u8>> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(s.trim_end_matches('='))
+        .map_err(|e| anyhow!("invalid base64url value: {e}"))
+}
+
+fn get_base64url(map: &Map<String, Value>, k: &str) -> anyhow::Result<Vec<u8>> {
+    match map.get(k) {
+        Some(Value::String(s)) => decode_base64url(s).context(format!("invalid value for key {k}")),
+        Some(_) => Err(anyhow!("invalid string value for key {k}")),
+        None => Err(anyhow!("no key {k} found")),
+    }
+}
+
+fn get_str<'a>(map: &'a Map<String, Value>, k: &str) -> anyhow::Result<Option<&'a str>> {
+    match map.get(k) {
+        Some(Value::String(s)) => Ok(Some(s.as_str())),
+        Some(_) => Err(anyhow!("invalid string value for key {k}")),
+        None => Ok(None),
+    }
+}
+
+/// HMAC as described in rfc2104, as `PKey::hmac` is not available in all openssl variants
+fn hmac(md: MessageDigest, key: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let block_size = md.block_size();
+    let mut key_block = if key.len() > block_size {
+        openssl::hash::hash(md, key)?.to_vec()
+    } else {
+        key.to_vec()
+    };
+    key_block.resize(block_size, 0);
+
+    let mut inner = Hasher::new(md)?;
+    let ipad = key_block.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>();
+    inner.update(&ipad)?;
+    inner.update(data)?;
+    let inner_hash = inner.finish()?;
+
+    let mut outer = Hasher::new(md)?;
+    let opad = key_block.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>();
+    outer.update(&opad)?;
+    outer.update(&inner_hash)?;
+    Ok(outer.finish()?.to_vec())
+}
+
+enum JwkKeyMaterial {
+    Oct(Vec<u8>),
+    Rsa(PKey<Public>),
+    Ec(PKey<Public>, Nid),
+    Okp(PKey<Public>),
+}
+
+pub(super) struct JwkKey {
+    kid: Option<String>,
+    alg: Option<JwtAlgorithm>,
+    material: JwkKeyMaterial,
+}
+
+impl JwkKey {
+    /// return None if the key is not used for signature
+    fn parse_json(map: &Map<String, Value>) -> anyhow::Result<Option<Self>> {
+        if let Some(key_use) = get_str(map, "use")? {
+            if key_use != "sig" {
+                return Ok(None);
+            }
+        }
+        let kid = get_str(map, "kid")?.map(|s| s.to_string());
+        let alg = match get_str(map, "alg")? {
+            Some(s) => match s.parse::<JwtAlgorithm>() {
+                Ok(alg) => Some(alg),
+                Err(_) => return Ok(None),
+            },
+            None => None,
+        };
+
+        let kty = get_str(map, "kty")?.ok_or_else(|| anyhow!("no key kty found"))?;
+        let material = match kty {
+            "oct" => {
+                let k = get_base64url(map, "k")?;
+                JwkKeyMaterial::Oct(k)
+            }
+            "RSA" => {
+                let n = BigNum::from_slice(&get_base64url(map, "n")?)?;
+                let e = BigNum::from_slice(&get_base64url(map, "e")?)?;
+                let rsa = Rsa::from_public_components(n, e)?;
+                JwkKeyMaterial::Rsa(PKey::from_rsa(rsa)?)
+            }
+            "EC" => {
+                let nid = match get_str(map, "crv")? {
+                    Some("P-256") => Nid::X9_62_PRIME256V1,
+                    Some("P-384") => Nid::SECP384R1,
+                    Some("P-521") => Nid::SECP521R1,
+                    Some(s) => return Err(anyhow!("unsupported EC curve {s}")),
+                    None => return Err(anyhow!("no key crv found")),
+                };
+                let group = EcGroup::from_curve_name(nid)?;
+                let x = BigNum::from_slice(&get_base64url(map, "x")?)?;
+                let y = BigNum::from_slice(&get_base64url(map, "y")?)?;
+                let ec_key = EcKey::from_public_key_affine_coordinates(&group, &x, &y)?;
+                JwkKeyMaterial::Ec(PKey::from_ec_key(ec_key)?, nid)
+            }
+            "OKP" => {
+                let id = match get_str(map, "crv")? {
+                    Some("Ed25519") => Id::ED25519,
+                    Some(s) => return Err(anyhow!("unsupported OKP curve {s}")),
+                    None => return Err(anyhow!("no key crv found")),
+                };
+                let x = get_base64url(map, "x")?;
+                JwkKeyMaterial::Okp(PKey::public_key_from_raw_bytes(&x, id)?)
+            }
+            _ => return Err(anyhow!("unsupported key type {kty}")),
+        };
+
+        Ok(Some(JwkKey { kid, alg, material }))
+    }
+
+    pub(super) fn match_kid(&self, kid: Option<&str>) -> bool {
+        match (kid, &self.kid) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+
+    pub(super) fn support(&self, alg: JwtAlgorithm) -> bool {
+        if let Some(key_alg) = self.alg {
+            if key_alg != alg {
+                return false;
+            }
+        }
+        match (&self.material, alg) {
+            (
+                JwkKeyMaterial::Oct(_),
+                JwtAlgorithm::HS256 | JwtAlgorithm::HS384 | JwtAlgorithm::HS512,
+            ) => true,
+            (
+                JwkKeyMaterial::Rsa(_),
+                JwtAlgorithm::RS256
+                | JwtAlgorithm::RS384
+                | JwtAlgorithm::RS512
+                | JwtAlgorithm::PS256
+                | JwtAlgorithm::PS384
+                | JwtAlgorithm::PS512,
+            ) => true,
+            (JwkKeyMaterial::Ec(_, nid), JwtAlgorithm::ES256) => *nid == Nid::X9_62_PRIME256V1,
+            (JwkKeyMaterial::Ec(_, nid), JwtAlgorithm::ES384) => *nid == Nid::SECP384R1,
+            (JwkKeyMaterial::Ec(_, nid), JwtAlgorithm::ES512) => *nid == Nid::SECP521R1,
+            (JwkKeyMaterial::Okp(_), JwtAlgorithm::EdDSA) => true,
+            _ => false,
+        }
+    }
+
+    pub(super) fn verify(
+        &self,
+        alg: JwtAlgorithm,
+        data: &[u8],
+        signature: &[u8],
+    ) -> anyhow::Result<bool> {
+        let md = match alg {
+            JwtAlgorithm::HS256
+            | JwtAlgorithm::RS256
+            | JwtAlgorithm::PS256
+            | JwtAlgorithm::ES256 => MessageDigest::sha256(),
+            JwtAlgorithm::HS384
+            | JwtAlgorithm::RS384
+            | JwtAlgorithm::PS384
+            | JwtAlgorithm::ES384 => MessageDigest::sha384(),
+            JwtAlgorithm::HS512
+            | JwtAlgorithm::RS512
+            | JwtAlgorithm::PS512
+            | JwtAlgorithm::ES512 => MessageDigest::sha512(),
+            JwtAlgorithm::EdDSA => {
+                let JwkKeyMaterial::Okp(pkey) = &self.material else {
+                    return Ok(false);
+                };
+                let mut verifier = Verifier::new_without_digest(pkey)?;
+                return Ok(verifier.verify_oneshot(signature, data)?);
+            }
+        };
+
+        match &self.material {
+            JwkKeyMaterial::Oct(k) => {
+                let expected = hmac(md, k, data)?;
+                Ok(expected.len() == signature.len() && openssl::memcmp::eq(&expected, signature))
+            }
+            JwkKeyMaterial::Rsa(pkey) => {
+                let mut verifier = Verifier::new(md, pkey)?;
+                if matches!(
+                    alg,
+                    JwtAlgorithm::PS256 | JwtAlgorithm::PS384 | JwtAlgorithm::PS512
+                ) {
+                    verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+                    verifier.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+                    verifier.set_rsa_mgf1_md(md)?;
+                }
+                Ok(verifier.verify_oneshot(signature, data)?)
+            }
+            JwkKeyMaterial::Ec(pkey, _) => {
+                // the signature is in raw R || S format
+                let bits = pkey.bits() as usize;
+                let n = (bits + 7) / 8;
+                if signature.len() != n * 2 {
+                    return Ok(false);
+                }
+                let r = BigNum::from_slice(&signature[..n])?;
+                let s = BigNum::from_slice(&signature[n..])?;
+                let der = EcdsaSig::from_private_components(r, s)?.to_der()?;
+                let mut verifier = Verifier::new(md, pkey)?;
+                Ok(verifier.verify_oneshot(&der, data)?)
+            }
+            JwkKeyMaterial::Okp(_) => Ok(false),
+        }
+    }
+}
+
+pub(super) struct JwkSet {
+    keys: Vec<JwkKey>,
+}
+
+impl JwkSet {
+    pub(super) fn load_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read file {}: {e}", path.display()))?;
+        let doc = serde_json::from_str::<Value>(&content)
+            .map_err(|e| anyhow!("invalid json in file {}: {e}", path.display()))?;
+        JwkSet::parse_json(&doc)
+    }
+
+    pub(super) fn parse_json(doc: &Value) -> anyhow::Result<Self> {
+        let Value::Object(map) = doc else {
+            return Err(anyhow!("the jwks should be a json object"));
+        };
+
+        let mut keys = Vec::new();
+        if let Some(v) = map.get("keys") {
+            let Value::Array(seq) = v else {
+                return Err(anyhow!("invalid array value for key keys"));
+            };
+            for (i, v) in seq.iter().enumerate() {
+                let Value::Object(map) = v else {
+                    return Err(anyhow!("invalid object value for keys#{i}"));
+                };
+                if let Some(key) =
+                    JwkKey::parse_json(map).context(format!("invalid jwk keys#{i}"))?
+                {
+                    keys.push(key);
+                }
+            }
+        } else if let Some(key) = JwkKey::parse_json(map).context("invalid jwk")? {
+            keys.push(key);
+        }
+
+        if keys.is_empty() {
+            return Err(anyhow!("no usable signature key found"));
+        }
+        Ok(JwkSet { keys })
+    }
+
+    pub(super) fn keys(&self) -> &[JwkKey] {
+        &self.keys
+    }
+}
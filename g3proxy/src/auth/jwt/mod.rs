@@ -0,0 +1,353 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use serde_json::Value;
+use thiserror::Error;
+
+use g3_types::auth::UserAuthError;
+
+use crate::config::auth::{JwtAlgorithm, UserJwtConfig};
+
+mod jwk;
+use jwk::JwkSet;
+
+#[derive(Debug, Error)]
+pub(crate) enum JwtVerifyError {
+    #[error("invalid token format")]
+    InvalidFormat,
+    #[error("unsupported algorithm")]
+    UnsupportedAlgorithm,
+    #[error("no matched key found")]
+    NoMatchedKey,
+    #[error("signature not match")]
+    SignatureNotMatch,
+    #[error("token has been expired")]
+    Expired,
+    #[error("token is not valid yet")]
+    NotValidYet,
+    #[error("issuer not match")]
+    IssuerNotMatch,
+    #[error("audience not match")]
+    AudienceNotMatch,
+    #[error("no valid username claim found")]
+    NoUsername,
+}
+
+impl From<JwtVerifyError> for UserAuthError {
+    fn from(e: JwtVerifyError) -> Self {
+        match e {
+            JwtVerifyError::Expired => UserAuthError::ExpiredUser,
+            _ => UserAuthError::TokenNotMatch,
+        }
+    }
+}
+
+const VERIFIED_CACHE_SIZE: usize = 1024;
+const VERIFIED_CACHE_TTL: i64 = 60;
+
+struct VerifiedToken {
+    username: String,
+    expire: i64,
+}
+
+pub(crate) struct JwtVerifier {
+    config: Arc<UserJwtConfig>,
+    jwks: JwkSet,
+    /// the recently verified tokens, keyed by the sha256 hash of the token
+    verified: Mutex<LruCache<[u8; 32], VerifiedToken, ahash::RandomState>>,
+}
+
+impl JwtVerifier {
+    pub(crate) fn new(config: &Arc<UserJwtConfig>) -> anyhow::Result<Self> {
+        let jwks = JwkSet::load_file(&config.jwks_file)?;
+        Ok(JwtVerifier::with_jwks(config, jwks))
+    }
+
+    fn with_jwks(config: &Arc<UserJwtConfig>, jwks: JwkSet) -> Self {
+        let cache_size = NonZeroUsize::new(VERIFIED_CACHE_SIZE).unwrap();
+        JwtVerifier {
+            config: Arc::clone(config),
+            jwks,
+            verified: Mutex::new(LruCache::with_hasher(cache_size, ahash::RandomState::new())),
+        }
+    }
+
+    /// verify the token and return the username in it
+    ///
+    /// The verified tokens will be cached until they expire, or for at most
+    /// [VERIFIED_CACHE_TTL] seconds, so the signature won't be checked again for each request.
+    pub(crate) fn verify(&self, token: &str, now: i64) -> Result<String, JwtVerifyError> {
+        let key = openssl::sha::sha256(token.as_bytes());
+        {
+            let mut cache = self.verified.lock().unwrap();
+            if let Some(v) = cache.get(&key) {
+                if now <= v.expire {
+                    return Ok(v.username.clone());
+                }
+                cache.pop(&key);
+            }
+        }
+
+        let (username, exp) = self.verify_uncached(token, now)?;
+        let mut expire = now + VERIFIED_CACHE_TTL;
+        if let Some(exp) = exp {
+            expire = expire.min(exp + self.config.leeway.as_secs() as i64);
+        }
+        self.verified.lock().unwrap().put(
+            key,
+            VerifiedToken {
+                username: username.clone(),
+                expire,
+            },
+        );
+        Ok(username)
+    }
+
+    /// verify the token and return the username and the expire time in it
+    fn verify_uncached(
+        &self,
+        token: &str,
+        now: i64,
+    ) -> Result<(String, Option<i64>), JwtVerifyError> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(JwtVerifyError::InvalidFormat);
+        };
+
+        let header = decode_json_object(header)?;
+        let alg = match header.get("alg") {
+            Some(Value::String(s)) => {
+                JwtAlgorithm::from_str(s).map_err(|_| JwtVerifyError::UnsupportedAlgorithm)?
+            }
+            _ => return Err(JwtVerifyError::InvalidFormat),
+        };
+        if !self.config.algorithms.is_empty() && !self.config.algorithms.contains(&alg) {
+            return Err(JwtVerifyError::UnsupportedAlgorithm);
+        }
+        if header.contains_key("crit") {
+            // no extension is supported
+            return Err(JwtVerifyError::UnsupportedAlgorithm);
+        }
+        let kid = match header.get("kid") {
+            Some(Value::String(s)) => Some(s.as_str()),
+            Some(_) => return Err(JwtVerifyError::InvalidFormat),
+            None => None,
+        };
+
+        let signature =
+            jwk::decode_base64url(signature).map_err(|_| JwtVerifyError::InvalidFormat)?;
+        let signing_input = &token.as_bytes()[..header_payload_len(token)];
+        let mut key_found = false;
+        let mut verified = false;
+        for key in self.jwks.keys() {
+            if !key.match_kid(kid) || !key.support(alg) {
+                continue;
+            }
+            key_found = true;
+            if let Ok(true) = key.verify(alg, signing_input, &signature) {
+                verified = true;
+                break;
+            }
+        }
+        if !key_found {
+            return Err(JwtVerifyError::NoMatchedKey);
+        }
+        if !verified {
+            return Err(JwtVerifyError::SignatureNotMatch);
+        }
+
+        let claims = decode_json_object(payload)?;
+        self.check_claims(&claims, now)?;
+
+        let exp = claims.get("exp").and_then(Value::as_f64).map(|v| v as i64);
+        match claims.get(&self.config.username_claim) {
+            Some(Value::String(s)) if !s.is_empty() => Ok((s.to_string(), exp)),
+            _ => Err(JwtVerifyError::NoUsername),
+        }
+    }
+
+    fn check_claims(
+        &self,
+        claims: &serde_json::Map<String, Value>,
+        now: i64,
+    ) -> Result<(), JwtVerifyError> {
+        let leeway = self.config.leeway.as_secs() as i64;
+
+        match claims.get("exp").and_then(Value::as_f64) {
+            Some(exp) => {
+                if now > exp as i64 + leeway {
+                    return Err(JwtVerifyError::Expired);
+                }
+            }
+            None => {
+                if self.config.require_exp {
+                    return Err(JwtVerifyError::Expired);
+                }
+            }
+        }
+        if let Some(nbf) = claims.get("nbf").and_then(Value::as_f64) {
+            if now + leeway < nbf as i64 {
+                return Err(JwtVerifyError::NotValidYet);
+            }
+        }
+
+        if !self.config.issuers.is_empty() {
+            let Some(Value::String(iss)) = claims.get("iss") else {
+                return Err(JwtVerifyError::IssuerNotMatch);
+            };
+            if !self.config.issuers.contains(iss) {
+                return Err(JwtVerifyError::IssuerNotMatch);
+            }
+        }
+
+        if !self.config.audiences.is_empty() {
+            let matched = match claims.get("aud") {
+                Some(Value::String(aud)) => self.config.audiences.contains(aud),
+                Some(Value::Array(seq)) => seq.iter().any(|v| {
+                    v.as_str()
+                        .map(|aud| self.config.audiences.iter().any(|s| s == aud))
+                        .unwrap_or(false)
+                }),
+                _ => false,
+            };
+            if !matched {
+                return Err(JwtVerifyError::AudienceNotMatch);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn header_payload_len(token: &str) -> usize {
+    token.rfind('.').unwrap_or(0)
+}
+
+fn decode_json_object(s: &str) -> Result<serde_json::Map<String, Value>, JwtVerifyError> {
+    let data = jwk::decode_base64url(s).map_err(|_| JwtVerifyError::InvalidFormat)?;
+    match serde_json::from_slice::<Value>(&data) {
+        Ok(Value::Object(map)) => Ok(map),
+        _ => Err(JwtVerifyError::InvalidFormat),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::prelude::*;
+    use std::time::Duration;
+
+    fn hs256_verifier() -> JwtVerifier {
+        // the key is "secret"
+        let doc = serde_json::json!({
+            "keys": [{"kty": "oct", "kid": "k1", "alg": "HS256", "k": "c2VjcmV0"}]
+        });
+        let config = UserJwtConfig {
+            jwks_file: Default::default(),
+            issuers: vec!["https://sso.example.net".to_string()],
+            audiences: vec!["g3proxy".to_string()],
+            algorithms: Vec::new(),
+            username_claim: "sub".to_string(),
+            leeway: Duration::from_secs(60),
+            require_exp: true,
+        };
+        JwtVerifier::with_jwks(&Arc::new(config), JwkSet::parse_json(&doc).unwrap())
+    }
+
+    fn sign_hs256(claims: &Value) -> String {
+        let header = BASE64_URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT","kid":"k1"}"#);
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(claims.to_string());
+        let input = format!("{header}.{payload}");
+        let pkey = openssl::pkey::PKey::hmac(b"secret").unwrap();
+        let mut signer =
+            openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &pkey).unwrap();
+        let signature = signer.sign_oneshot_to_vec(input.as_bytes()).unwrap();
+        format!("{input}.{}", BASE64_URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    #[test]
+    fn hs256() {
+        let verifier = hs256_verifier();
+        let claims = serde_json::json!({
+            "sub": "alice",
+            "iss": "https://sso.example.net",
+            "aud": ["other", "g3proxy"],
+            "exp": 1700000000,
+        });
+        let token = sign_hs256(&claims);
+        assert_eq!(verifier.verify(&token, 1700000000).unwrap(), "alice");
+        assert!(matches!(
+            verifier.verify(&token, 1700000100),
+            Err(JwtVerifyError::Expired)
+        ));
+
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let other = sign_hs256(&serde_json::json!({"sub": "bob"}));
+        let (other_input, _) = other.rsplit_once('.').unwrap();
+        let tampered = format!("{other_input}.{signature}");
+        assert!(matches!(
+            verifier.verify(&tampered, 1700000000),
+            Err(JwtVerifyError::SignatureNotMatch)
+        ));
+
+        let claims = serde_json::json!({
+            "sub": "alice",
+            "iss": "https://sso.example.net",
+            "aud": "other",
+            "exp": 1700000000,
+        });
+        let token = sign_hs256(&claims);
+        assert!(matches!(
+            verifier.verify(&token, 1700000000),
+            Err(JwtVerifyError::AudienceNotMatch)
+        ));
+    }
+
+    #[test]
+    fn cached() {
+        let verifier = hs256_verifier();
+        let claims = serde_json::json!({
+            "sub": "alice",
+            "iss": "https://sso.example.net",
+            "aud": "g3proxy",
+            "exp": 1700000000,
+        });
+        let token = sign_hs256(&claims);
+        let key = openssl::sha::sha256(token.as_bytes());
+
+        assert_eq!(verifier.verify(&token, 1699990000).unwrap(), "alice");
+        let expire = verifier.verified.lock().unwrap().get(&key).unwrap().expire;
+        assert_eq!(expire, 1699990000 + VERIFIED_CACHE_TTL);
+        assert_eq!(verifier.verify(&token, 1699990030).unwrap(), "alice");
+
+        // the cache entry should not outlive the token
+        assert_eq!(verifier.verify(&token, 1700000030).unwrap(), "alice");
+        let expire = verifier.verified.lock().unwrap().get(&key).unwrap().expire;
+        assert_eq!(expire, 1700000060);
+        assert!(matches!(
+            verifier.verify(&token, 1700000061),
+            Err(JwtVerifyError::Expired)
+        ));
+        assert!(verifier.verified.lock().unwrap().get(&key).is_none());
+    }
+}
@@ -19,12 +19,15 @@ use std::sync::Arc;
 
 use ahash::AHashMap;
 use anyhow::anyhow;
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use chrono::Utc;
 use futures_util::future::AbortHandle;
-use log::{info, warn};
+use log::{debug, info, warn};
+use openssl::x509::X509Ref;
 
-use g3_types::metrics::MetricsName;
+use g3_types::auth::UserAuthError;
+use g3_types::metrics::{MetricsName, StaticMetricsTags};
+use g3_types::net::HttpAuth;
 
 use crate::config::auth::UserGroupConfig;
 
@@ -51,6 +54,18 @@ pub(crate) use stats::{
 
 mod source;
 
+mod jwt;
+use jwt::JwtVerifier;
+
+mod quota;
+use quota::{UserQuotaState, UserQuotaStore};
+
+mod password;
+use password::UserPasswordCache;
+
+mod client_cert;
+pub(crate) use client_cert::rustls_peer_cert;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum UserType {
     Static,
@@ -75,6 +90,9 @@ pub(crate) struct UserGroup {
     /// the dynamic job is for both dynamic fetch and expire check
     dynamic_job_handler: Option<AbortHandle>,
     anonymous_user: Option<Arc<User>>,
+    jwt_verifier: Option<JwtVerifier>,
+    quota_store: Arc<UserQuotaStore>,
+    quota_job_handler: Option<AbortHandle>,
 }
 
 impl Drop for UserGroup {
@@ -82,6 +100,9 @@ impl Drop for UserGroup {
         if let Some(handler) = self.dynamic_job_handler.take() {
             handler.abort();
         }
+        if let Some(handler) = self.quota_job_handler.take() {
+            handler.abort();
+        }
     }
 }
 
@@ -93,6 +114,9 @@ impl UserGroup {
             dynamic_users: Arc::new(ArcSwap::from_pointee(AHashMap::new())),
             dynamic_job_handler: None,
             anonymous_user: None,
+            jwt_verifier: None,
+            quota_store: Arc::new(UserQuotaStore::default()),
+            quota_job_handler: None,
         }
     }
 
@@ -102,25 +126,43 @@ impl UserGroup {
     }
 
     async fn new_with_config(config: UserGroupConfig) -> anyhow::Result<Arc<Self>> {
+        let quota_store = match UserQuotaStore::load(&config.quota_state).await {
+            Ok(store) => store,
+            Err(e) => {
+                warn!(
+                    "failed to load quota state for user-group {}: {e:?}",
+                    config.name()
+                );
+                UserQuotaStore::default()
+            }
+        };
+
         let datetime_now = Utc::now();
         let mut users = AHashMap::new();
         for (username, user_config) in &config.static_users {
-            let user = User::new(config.name(), user_config, &datetime_now)?;
+            let user = User::new(config.name(), user_config, &datetime_now, &quota_store)?;
             users.insert(username.to_string(), Arc::new(user));
         }
 
         let anonymous_user = match &config.anonymous_user {
             Some(user_config) => {
-                let user = User::new(config.name(), user_config, &datetime_now)?;
+                let user = User::new(config.name(), user_config, &datetime_now, &quota_store)?;
                 Some(Arc::new(user))
             }
             None => None,
         };
 
+        let jwt_verifier = match &config.jwt {
+            Some(jwt_config) => Some(JwtVerifier::new(jwt_config)?),
+            None => None,
+        };
+
         let mut group = Self::new_without_users(config);
         group.static_users = Arc::new(users);
+        group.jwt_verifier = jwt_verifier;
+        group.quota_store = Arc::new(quota_store);
         if let Some(source) = &group.config.dynamic_source {
-            match source::load_initial_users(&group.config, source).await {
+            match source::load_initial_users(&group.config, source, &group.quota_store).await {
                 Ok(cached_users) => {
                     if cached_users.is_empty() {
                         info!(
@@ -144,6 +186,14 @@ impl UserGroup {
             &group.config,
             &group.static_users,
             &group.dynamic_users,
+            &group.quota_store,
+        ));
+        group.quota_job_handler = Some(quota::new_job(
+            &group.config,
+            &group.quota_store,
+            &group.static_users,
+            &group.dynamic_users,
+            group.anonymous_user.as_ref(),
         ));
 
         Ok(Arc::new(group))
@@ -154,9 +204,9 @@ impl UserGroup {
         let mut static_users = AHashMap::new();
         for (username, user_config) in &config.static_users {
             let user = if let Some(user) = self.static_users.get(username) {
-                user.new_for_reload(user_config, &datetime_now)?
+                user.new_for_reload(user_config, &datetime_now, &self.quota_store)?
             } else {
-                User::new(config.name(), user_config, &datetime_now)?
+                User::new(config.name(), user_config, &datetime_now, &self.quota_store)?
             };
             static_users.insert(username.to_string(), Arc::new(user));
         }
@@ -164,9 +214,9 @@ impl UserGroup {
         let anonymous_user = match &config.anonymous_user {
             Some(user_config) => {
                 let user = if let Some(old) = &self.anonymous_user {
-                    old.new_for_reload(user_config, &datetime_now)?
+                    old.new_for_reload(user_config, &datetime_now, &self.quota_store)?
                 } else {
-                    User::new(config.name(), user_config, &datetime_now)?
+                    User::new(config.name(), user_config, &datetime_now, &self.quota_store)?
                 };
                 Some(Arc::new(user))
             }
@@ -182,8 +232,16 @@ impl UserGroup {
             }
         }
 
+        let jwt_verifier = match &config.jwt {
+            Some(jwt_config) => Some(JwtVerifier::new(jwt_config)?),
+            None => None,
+        };
+
         let mut group = Self::new_without_users(config);
         group.static_users = Arc::new(static_users);
+        group.jwt_verifier = jwt_verifier;
+        // keep the quota states of all users
+        group.quota_store = Arc::clone(&self.quota_store);
         if !dynamic_users.is_empty() {
             group.dynamic_users.store(Arc::new(dynamic_users));
         }
@@ -194,6 +252,14 @@ impl UserGroup {
             &group.config,
             &group.static_users,
             &group.dynamic_users,
+            &group.quota_store,
+        ));
+        group.quota_job_handler = Some(quota::new_job(
+            &group.config,
+            &group.quota_store,
+            &group.static_users,
+            &group.dynamic_users,
+            group.anonymous_user.as_ref(),
         ));
 
         Ok(Arc::new(group))
@@ -211,6 +277,35 @@ impl UserGroup {
     }
 
     pub(crate) fn get_user(&self, username: &str) -> Option<(Arc<User>, UserType)> {
+        self.get_named_user(username)
+            .or_else(|| self.get_anonymous_user())
+    }
+
+    /// verify the bearer token and get the user mapped from the claims in it
+    pub(crate) fn get_bearer_user(
+        &self,
+        token: &str,
+    ) -> Result<(String, Arc<User>, UserType), UserAuthError> {
+        let Some(verifier) = &self.jwt_verifier else {
+            return Err(UserAuthError::TokenNotMatch);
+        };
+        let username = verifier
+            .verify(token, Utc::now().timestamp())
+            .map_err(|e| {
+                debug!(
+                    "bearer token verify failed in user-group {}: {e}",
+                    self.config.name()
+                );
+                UserAuthError::from(e)
+            })?;
+        match self.get_user(&username) {
+            Some((user, user_type)) => Ok((username, user, user_type)),
+            None => Err(UserAuthError::NoSuchUser),
+        }
+    }
+
+    /// get the user from static and dynamic users, without fallback to the anonymous user
+    fn get_named_user(&self, username: &str) -> Option<(Arc<User>, UserType)> {
         if let Some(user) = self.static_users.get(username) {
             return Some((Arc::clone(user), UserType::Static));
         }
@@ -222,7 +317,58 @@ impl UserGroup {
             }
         }
 
-        self.get_anonymous_user()
+        None
+    }
+
+    /// get the user context mapped from the verified tls client certificate
+    ///
+    /// `Ok(None)` will be returned if client certificate auth is not enabled
+    pub(crate) async fn check_client_cert(
+        &self,
+        cert: &X509Ref,
+        auth_info: &HttpAuth,
+        server: &MetricsName,
+        server_extra_tags: &Arc<ArcSwapOption<StaticMetricsTags>>,
+    ) -> Result<Option<UserContext>, UserAuthError> {
+        let Some(config) = &self.config.client_cert else {
+            return Ok(None);
+        };
+        let Some(username) = client_cert::get_username(config, cert) else {
+            return Err(UserAuthError::NoUserSupplied);
+        };
+        let Some((user, user_type)) = self.get_named_user(&username) else {
+            return Err(UserAuthError::NoSuchUser);
+        };
+
+        if config.require_basic_auth {
+            let HttpAuth::Basic(basic) = auth_info else {
+                return Err(UserAuthError::NoUserSupplied);
+            };
+            if basic.username.as_original() != username {
+                return Err(UserAuthError::TokenNotMatch);
+            }
+            let user_ctx =
+                UserContext::new(Some(username), user, user_type, server, server_extra_tags);
+            user_ctx
+                .check_password(basic.password.as_original())
+                .await?;
+            Ok(Some(user_ctx))
+        } else {
+            let user_ctx =
+                UserContext::new(Some(username), user, user_type, server, server_extra_tags);
+            user_ctx.check_available()?;
+            Ok(Some(user_ctx))
+        }
+    }
+
+    pub(crate) fn query_user_quota(&self, username: &str) -> anyhow::Result<String> {
+        let (user, _) = self
+            .get_named_user(username)
+            .ok_or_else(|| anyhow!("no user {username} found"))?;
+        let status = user
+            .quota_status(&Utc::now())
+            .ok_or_else(|| anyhow!("no quota set for user {username}"))?;
+        Ok(status.to_string())
     }
 
     pub(crate) fn foreach_user<F>(&self, mut f: F)
@@ -279,6 +425,11 @@ impl UserGroup {
             }
         }
 
-        source::publish_dynamic_users(self.config.as_ref(), user_config, &self.dynamic_users)
+        source::publish_dynamic_users(
+            self.config.as_ref(),
+            user_config,
+            &self.dynamic_users,
+            &self.quota_store,
+        )
     }
 }
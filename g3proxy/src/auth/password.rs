@@ -0,0 +1,176 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use openssl::hash::{DigestBytes, MessageDigest};
+use tokio::sync::Semaphore;
+
+const FAILED_CACHE_MAX_SIZE: usize = 8;
+const FAILED_CACHE_EXPIRE: Duration = Duration::from_secs(60);
+
+/// Cache of the last password that passed the verification of a slow xcrypt hash,
+/// so the expensive hash function won't be called again for each request.
+///
+/// The slow verification will be run in the blocking thread pool, one at a time for each user,
+/// and the recently failed passwords will be rejected directly without verification.
+pub(super) struct UserPasswordCache {
+    salt: [u8; 16],
+    verified: Mutex<Option<DigestBytes>>,
+    failed: Mutex<VecDeque<(DigestBytes, Instant)>>,
+    slow_verify_semaphore: Semaphore,
+}
+
+impl UserPasswordCache {
+    pub(super) fn new() -> Self {
+        UserPasswordCache {
+            salt: rand::random(),
+            verified: Mutex::new(None),
+            failed: Mutex::new(VecDeque::with_capacity(FAILED_CACHE_MAX_SIZE)),
+            slow_verify_semaphore: Semaphore::new(1),
+        }
+    }
+
+    fn digest(&self, password: &str) -> Option<DigestBytes> {
+        let mut data = Vec::with_capacity(self.salt.len() + password.len());
+        data.extend_from_slice(&self.salt);
+        data.extend_from_slice(password.as_bytes());
+        openssl::hash::hash(MessageDigest::sha256(), &data).ok()
+    }
+
+    /// check the cached verify result of the password
+    fn check_cached(&self, digest: &DigestBytes) -> Option<bool> {
+        if let Some(verified) = &*self.verified.lock().unwrap() {
+            if verified.as_ref().eq(digest.as_ref()) {
+                return Some(true);
+            }
+        }
+
+        let mut failed = self.failed.lock().unwrap();
+        while let Some((_, time)) = failed.front() {
+            if time.elapsed() < FAILED_CACHE_EXPIRE {
+                break;
+            }
+            failed.pop_front();
+        }
+        if failed.iter().any(|(v, _)| v.as_ref().eq(digest.as_ref())) {
+            return Some(false);
+        }
+        None
+    }
+
+    fn add_failed(&self, digest: DigestBytes) {
+        let mut failed = self.failed.lock().unwrap();
+        if failed.len() >= FAILED_CACHE_MAX_SIZE {
+            failed.pop_front();
+        }
+        failed.push_back((digest, Instant::now()));
+    }
+
+    async fn run_slow_verify<F>(password: &str, slow_verify: F) -> bool
+    where
+        F: FnOnce(&str) -> bool + Send + 'static,
+    {
+        let password = password.to_string();
+        tokio::task::spawn_blocking(move || slow_verify(&password))
+            .await
+            .unwrap_or(false)
+    }
+
+    pub(super) async fn verify<F>(&self, password: &str, slow_verify: F) -> bool
+    where
+        F: FnOnce(&str) -> bool + Send + 'static,
+    {
+        let Some(digest) = self.digest(password) else {
+            let Ok(_permit) = self.slow_verify_semaphore.acquire().await else {
+                return false;
+            };
+            return UserPasswordCache::run_slow_verify(password, slow_verify).await;
+        };
+        if let Some(verified) = self.check_cached(&digest) {
+            return verified;
+        }
+
+        let Ok(_permit) = self.slow_verify_semaphore.acquire().await else {
+            return false;
+        };
+        // check again as the same password may be verified while waiting
+        if let Some(verified) = self.check_cached(&digest) {
+            return verified;
+        }
+
+        if UserPasswordCache::run_slow_verify(password, slow_verify).await {
+            *self.verified.lock().unwrap() = Some(digest);
+            true
+        } else {
+            self.add_failed(digest);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    async fn verify(cache: &UserPasswordCache, password: &str, count: &Arc<AtomicUsize>) -> bool {
+        let count = Arc::clone(count);
+        cache
+            .verify(password, move |p| {
+                count.fetch_add(1, Ordering::Relaxed);
+                p == "right"
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn cached() {
+        let cache = UserPasswordCache::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        assert!(verify(&cache, "right", &count).await);
+        assert!(verify(&cache, "right", &count).await);
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        assert!(!verify(&cache, "wrong", &count).await);
+        assert!(!verify(&cache, "wrong", &count).await);
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+
+        assert!(verify(&cache, "right", &count).await);
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_cache_size() {
+        let cache = UserPasswordCache::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..=FAILED_CACHE_MAX_SIZE {
+            assert!(!verify(&cache, &format!("wrong{i}"), &count).await);
+        }
+        assert_eq!(count.load(Ordering::Relaxed), FAILED_CACHE_MAX_SIZE + 1);
+
+        // the oldest one should be evicted
+        assert!(!verify(&cache, "wrong0", &count).await);
+        assert_eq!(count.load(Ordering::Relaxed), FAILED_CACHE_MAX_SIZE + 2);
+        assert!(!verify(&cache, "wrong2", &count).await);
+        assert_eq!(count.load(Ordering::Relaxed), FAILED_CACHE_MAX_SIZE + 2);
+    }
+}
@@ -0,0 +1,350 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+use futures_util::future::{AbortHandle, Abortable};
+use log::warn;
+use serde_json::{Map, Value};
+
+use super::User;
+use crate::config::auth::{UserGroupConfig, UserQuotaConfig};
+
+#[derive(Default)]
+pub(crate) struct UserQuotaState {
+    period_start: AtomicI64,
+    upload: AtomicU64,
+    download: AtomicU64,
+    requests: AtomicU64,
+}
+
+pub(super) struct UserQuotaSnapshot {
+    pub(crate) period_start: i64,
+    pub(crate) upload: u64,
+    pub(crate) download: u64,
+    pub(crate) requests: u64,
+}
+
+impl UserQuotaState {
+    /// reset all counters if a new period begins
+    pub(crate) fn check_period(&self, config: &UserQuotaConfig, datetime_now: &DateTime<Utc>) {
+        let period_start = config.period.start_of(datetime_now);
+        let old = self.period_start.swap(period_start, Ordering::Relaxed);
+        if old != period_start {
+            self.upload.store(0, Ordering::Relaxed);
+            self.download.store(0, Ordering::Relaxed);
+            self.requests.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn add_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_traffic(&self, upload: u64, download: u64) {
+        self.upload.fetch_add(upload, Ordering::Relaxed);
+        self.download.fetch_add(download, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> UserQuotaSnapshot {
+        UserQuotaSnapshot {
+            period_start: self.period_start.load(Ordering::Relaxed),
+            upload: self.upload.load(Ordering::Relaxed),
+            download: self.download.load(Ordering::Relaxed),
+            requests: self.requests.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn is_exceeded(&self, config: &UserQuotaConfig) -> bool {
+        self.snapshot().is_exceeded(config)
+    }
+
+    pub(crate) fn is_traffic_exceeded(&self, config: &UserQuotaConfig) -> bool {
+        self.snapshot().is_traffic_exceeded(config)
+    }
+
+    fn parse_json(map: &Map<String, Value>) -> anyhow::Result<Self> {
+        let state = UserQuotaState::default();
+        for (k, v) in map {
+            let n = v
+                .as_u64()
+                .ok_or_else(|| anyhow!("invalid u64 value for key {k}"))?;
+            match k.as_str() {
+                "period_start" => {
+                    let n = i64::try_from(n).map_err(|e| anyhow!("invalid i64 value: {e}"))?;
+                    state.period_start.store(n, Ordering::Relaxed);
+                }
+                "upload" => state.upload.store(n, Ordering::Relaxed),
+                "download" => state.download.store(n, Ordering::Relaxed),
+                "requests" => state.requests.store(n, Ordering::Relaxed),
+                _ => return Err(anyhow!("invalid key {k}")),
+            }
+        }
+        Ok(state)
+    }
+
+    fn to_json(&self) -> Value {
+        let snap = self.snapshot();
+        serde_json::json!({
+            "period_start": snap.period_start,
+            "upload": snap.upload,
+            "download": snap.download,
+            "requests": snap.requests,
+        })
+    }
+}
+
+impl UserQuotaSnapshot {
+    pub(crate) fn is_exceeded(&self, config: &UserQuotaConfig) -> bool {
+        if let Some(limit) = config.requests {
+            if self.requests >= limit {
+                return true;
+            }
+        }
+        self.is_traffic_exceeded(config)
+    }
+
+    /// check only the traffic quota, which should also stop the running tasks
+    pub(crate) fn is_traffic_exceeded(&self, config: &UserQuotaConfig) -> bool {
+        if let Some(limit) = config.upload_bytes {
+            if self.upload >= limit {
+                return true;
+            }
+        }
+        if let Some(limit) = config.download_bytes {
+            if self.download >= limit {
+                return true;
+            }
+        }
+        if let Some(limit) = config.total_bytes {
+            if self.upload.saturating_add(self.download) >= limit {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn to_json(&self, config: &UserQuotaConfig) -> Value {
+        let remaining = |limit: Option<u64>, used: u64| match limit {
+            Some(limit) => Value::from(limit.saturating_sub(used)),
+            None => Value::Null,
+        };
+        let total = self.upload.saturating_add(self.download);
+        serde_json::json!({
+            "period_start": self.period_start,
+            "exceeded": self.is_exceeded(config),
+            "upload": {
+                "used": self.upload,
+                "remaining": remaining(config.upload_bytes, self.upload),
+            },
+            "download": {
+                "used": self.download,
+                "remaining": remaining(config.download_bytes, self.download),
+            },
+            "total": {
+                "used": total,
+                "remaining": remaining(config.total_bytes, total),
+            },
+            "requests": {
+                "used": self.requests,
+                "remaining": remaining(config.requests, self.requests),
+            },
+        })
+    }
+}
+
+/// The quota states of all users in a user group, which will be kept across reload
+#[derive(Default)]
+pub(crate) struct UserQuotaStore {
+    users: Mutex<AHashMap<String, Arc<UserQuotaState>>>,
+}
+
+impl UserQuotaStore {
+    pub(crate) async fn load(path: &Path) -> anyhow::Result<Self> {
+        let store = UserQuotaStore::default();
+        if path.as_os_str().is_empty() {
+            return Ok(store);
+        }
+        let contents = match tokio::fs::read_to_string(path).await {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(store),
+            Err(e) => {
+                return Err(anyhow!(
+                    "failed to read quota state file {}: {e}",
+                    path.display()
+                ))
+            }
+        };
+        if contents.trim().is_empty() {
+            return Ok(store);
+        }
+        let doc = serde_json::from_str::<Value>(&contents)
+            .map_err(|e| anyhow!("invalid json contents: {e}"))?;
+        let Value::Object(map) = doc else {
+            return Err(anyhow!("the quota state should be a json map"));
+        };
+        let mut users = store.users.lock().unwrap();
+        for (name, v) in map {
+            let Value::Object(v) = v else {
+                return Err(anyhow!("invalid quota state value for user {name}"));
+            };
+            let state = UserQuotaState::parse_json(&v)
+                .context(format!("invalid quota state value for user {name}"))?;
+            users.insert(name, Arc::new(state));
+        }
+        drop(users);
+        Ok(store)
+    }
+
+    pub(crate) fn fetch(&self, user: &str) -> Arc<UserQuotaState> {
+        let mut users = self.users.lock().unwrap();
+        let state = users.entry(user.to_string()).or_default();
+        Arc::clone(state)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut users = self.users.lock().unwrap();
+        // drop the states that are no longer referenced by any user
+        users.retain(|_, state| Arc::strong_count(state) > 1);
+        let mut map = Map::with_capacity(users.len());
+        for (name, state) in users.iter() {
+            map.insert(name.to_string(), state.to_json());
+        }
+        Value::Object(map)
+    }
+
+    pub(crate) async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if path.as_os_str().is_empty() {
+            return Ok(());
+        }
+        let contents = self.to_json().to_string();
+        // we should avoid corrupt write at process exit
+        if let Some(r) = crate::control::run_protected_io(tokio::fs::write(path, contents)).await {
+            r.map_err(|e| anyhow!("failed to write to file {}: {e}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn new_job(
+    group_config: &Arc<UserGroupConfig>,
+    quota_store: &Arc<UserQuotaStore>,
+    static_users: &Arc<AHashMap<String, Arc<User>>>,
+    dynamic_users_container: &Arc<ArcSwap<AHashMap<String, Arc<User>>>>,
+    anonymous_user: Option<&Arc<User>>,
+) -> AbortHandle {
+    let group_config = Arc::clone(group_config);
+    let quota_store = Arc::clone(quota_store);
+    let static_users = Arc::clone(static_users);
+    let dynamic_users_container = Arc::clone(dynamic_users_container);
+    let anonymous_user = anonymous_user.cloned();
+
+    let f = async move {
+        let mut interval = tokio::time::interval(group_config.quota_sync_interval);
+        loop {
+            interval.tick().await;
+
+            let datetime_now = Utc::now();
+            for user in static_users.values() {
+                user.sync_quota(&datetime_now);
+            }
+            let dynamic_users = dynamic_users_container.load();
+            for user in dynamic_users.values() {
+                user.sync_quota(&datetime_now);
+            }
+            if let Some(user) = &anonymous_user {
+                user.sync_quota(&datetime_now);
+            }
+
+            if let Err(e) = quota_store.save(&group_config.quota_state).await {
+                warn!(
+                    "failed to save quota state for user-group {}: {e:?}",
+                    group_config.name()
+                );
+            }
+        }
+    };
+
+    let (abort_handle, abort_registration) = AbortHandle::new_pair();
+    let future = Abortable::new(f, abort_registration);
+    tokio::spawn(future);
+    abort_handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    use crate::config::auth::UserQuotaPeriod;
+
+    #[test]
+    fn exceed() {
+        let config = UserQuotaConfig {
+            period: UserQuotaPeriod::Daily,
+            upload_bytes: None,
+            download_bytes: Some(1000),
+            total_bytes: Some(1500),
+            requests: Some(2),
+        };
+        let now = Utc.with_ymd_and_hms(2024, 5, 16, 13, 24, 35).unwrap();
+
+        let state = UserQuotaState::default();
+        state.check_period(&config, &now);
+        assert!(!state.is_exceeded(&config));
+
+        state.add_request();
+        state.add_traffic(600, 800);
+        assert!(!state.is_exceeded(&config));
+
+        state.add_traffic(0, 100);
+        assert!(state.is_exceeded(&config));
+        assert!(state.is_traffic_exceeded(&config));
+
+        let next_day = Utc.with_ymd_and_hms(2024, 5, 17, 0, 0, 1).unwrap();
+        state.check_period(&config, &next_day);
+        assert!(!state.is_exceeded(&config));
+
+        state.add_request();
+        state.add_request();
+        assert!(state.is_exceeded(&config));
+        assert!(!state.is_traffic_exceeded(&config));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let state = UserQuotaState::default();
+        state.period_start.store(1715817600, Ordering::Relaxed);
+        state.add_request();
+        state.add_traffic(10, 20);
+
+        let Value::Object(map) = state.to_json() else {
+            unreachable!()
+        };
+        let parsed = UserQuotaState::parse_json(&map).unwrap();
+        let snap = parsed.snapshot();
+        assert_eq!(snap.period_start, 1715817600);
+        assert_eq!(snap.upload, 10);
+        assert_eq!(snap.download, 20);
+        assert_eq!(snap.requests, 1);
+    }
+}
@@ -0,0 +1,304 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use log::warn;
+use rustls::ServerName;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use url::Host;
+
+use g3_http::client::HttpFetchRequest;
+
+use crate::config::auth::source::http::UserDynamicHttpSource;
+use crate::config::auth::UserConfig;
+
+const RESPONSE_MAX_HEADER_SIZE: usize = 8192;
+
+/// The cache validators of the last successful fetch
+#[derive(Default)]
+pub(super) struct HttpFetchState {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+struct HttpFetchResult {
+    contents: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Fetch users from the remote http server, return None if not modified
+pub(super) async fn fetch_records(
+    source: &Arc<UserDynamicHttpSource>,
+    cache: &Path,
+    state: &mut HttpFetchState,
+) -> anyhow::Result<Option<Vec<UserConfig>>> {
+    let r = tokio::time::timeout(source.fetch_timeout, fetch_contents(source, state))
+        .await
+        .map_err(|_| anyhow!("timed out to fetch users from {}", source.url))??;
+    let Some(r) = r else {
+        return Ok(None);
+    };
+
+    let doc = serde_json::Value::from_str(&r.contents)
+        .map_err(|e| anyhow!("response from {} is not valid json: {e}", source.url))?;
+    let all_config = crate::config::auth::source::cache::parse_json(&doc)?;
+
+    state.etag = r.etag;
+    state.last_modified = r.last_modified;
+
+    let cache_file = source.real_cache_path(cache);
+    if !cache_file.as_os_str().is_empty() {
+        // we should avoid corrupt write at process exit
+        if let Some(Err(e)) =
+            crate::control::run_protected_io(tokio::fs::write(cache_file, r.contents)).await
+        {
+            warn!(
+                "failed to cache dynamic users to file {} ({e:?}),\
+                 this may lead to auth error during restart",
+                cache_file.display()
+            );
+        }
+    }
+
+    Ok(Some(all_config))
+}
+
+async fn fetch_contents(
+    source: &UserDynamicHttpSource,
+    state: &HttpFetchState,
+) -> anyhow::Result<Option<HttpFetchResult>> {
+    let url = &source.url;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| anyhow!("no port found in url {url}"))?;
+    let stream = match url.host() {
+        Some(Host::Domain(domain)) => {
+            tokio::time::timeout(source.connect_timeout, TcpStream::connect((domain, port))).await
+        }
+        Some(Host::Ipv4(ip)) => {
+            tokio::time::timeout(source.connect_timeout, TcpStream::connect((ip, port))).await
+        }
+        Some(Host::Ipv6(ip)) => {
+            tokio::time::timeout(source.connect_timeout, TcpStream::connect((ip, port))).await
+        }
+        None => return Err(anyhow!("no host found in url {url}")),
+    }
+    .map_err(|_| anyhow!("timed out to connect to {url}"))?
+    .map_err(|e| anyhow!("failed to connect to {url}: {e}"))?;
+
+    if let Some(tls_client) = &source.tls_client {
+        let tls_name = match &source.tls_name {
+            Some(name) => name.clone(),
+            None => match url.host() {
+                Some(Host::Domain(domain)) => ServerName::try_from(domain)
+                    .map_err(|e| anyhow!("invalid tls server name {domain}: {e}"))?,
+                Some(Host::Ipv4(ip)) => ServerName::IpAddress(ip.into()),
+                Some(Host::Ipv6(ip)) => ServerName::IpAddress(ip.into()),
+                None => return Err(anyhow!("no host found in url {url}")),
+            },
+        };
+        let connector = TlsConnector::from(tls_client.driver.clone());
+        let tls_stream = tokio::time::timeout(
+            tls_client.handshake_timeout,
+            connector.connect(tls_name, stream),
+        )
+        .await
+        .map_err(|_| anyhow!("tls handshake timed out"))?
+        .map_err(|e| anyhow!("tls handshake failed: {e}"))?;
+        http_get(tls_stream, source, state).await
+    } else {
+        http_get(stream, source, state).await
+    }
+}
+
+async fn http_get<T>(
+    stream: T,
+    source: &UserDynamicHttpSource,
+    state: &HttpFetchState,
+) -> anyhow::Result<Option<HttpFetchResult>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let url = &source.url;
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    let mut host = url.host_str().unwrap_or_default().to_string();
+    if let Some(port) = url.port() {
+        host.push(':');
+        host.push_str(&port.to_string());
+    }
+
+    let mut req = HttpFetchRequest::new(&path, &host, "application/json");
+    if let Some(etag) = &state.etag {
+        req.set_etag(etag);
+    }
+    if let Some(last_modified) = &state.last_modified {
+        req.set_last_modified(last_modified);
+    }
+    req.set_rsp_header_max_size(RESPONSE_MAX_HEADER_SIZE);
+    req.set_rsp_body_max_size(source.max_body_size);
+    let Some(rsp) = req
+        .send(stream)
+        .await
+        .map_err(|e| anyhow!("failed to fetch from {url}: {e}"))?
+    else {
+        return Ok(None);
+    };
+
+    let contents = String::from_utf8(rsp.body)
+        .map_err(|e| anyhow!("response body is not valid utf-8: {e}"))?;
+    Ok(Some(HttpFetchResult {
+        contents,
+        etag: rsp.etag,
+        last_modified: rsp.last_modified,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+    use url::Url;
+
+    const USERS_JSON: &str = r#"[{"name": "root"}]"#;
+
+    /// serve one request with the given response, and return the request header
+    async fn serve_once(response: String) -> (SocketAddr, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                stream.read_line(&mut line).await.unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            stream.write_all(response.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+            head
+        });
+        (addr, handle)
+    }
+
+    fn new_source(addr: SocketAddr, cache_file: &str) -> Arc<UserDynamicHttpSource> {
+        let url = Url::parse(&format!("http://{addr}/users")).unwrap();
+        let mut source = UserDynamicHttpSource::parse_url(&url).unwrap();
+        if !cache_file.is_empty() {
+            source.cache_file = std::env::temp_dir().join(cache_file);
+        }
+        Arc::new(source)
+    }
+
+    #[tokio::test]
+    async fn fetch_with_etag() {
+        let rsp = format!(
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\n\r\n{USERS_JSON}",
+            USERS_JSON.len()
+        );
+        let (addr, server) = serve_once(rsp).await;
+        let source = new_source(addr, "");
+        let mut state = HttpFetchState::default();
+        let users = fetch_records(&source, Path::new(""), &mut state)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name(), "root");
+        assert_eq!(state.etag.as_deref(), Some("\"v1\""));
+        let req = server.await.unwrap();
+        assert!(!req.contains("If-None-Match"));
+
+        let rsp = "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\n\r\n".to_string();
+        let (addr, server) = serve_once(rsp).await;
+        let source = new_source(addr, "");
+        let r = fetch_records(&source, Path::new(""), &mut state)
+            .await
+            .unwrap();
+        assert!(r.is_none());
+        let req = server.await.unwrap();
+        assert!(req.contains("If-None-Match: \"v1\"\r\n"));
+        assert_eq!(state.etag.as_deref(), Some("\"v1\""));
+    }
+
+    #[tokio::test]
+    async fn fetch_body_too_large() {
+        let rsp = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{USERS_JSON}",
+            USERS_JSON.len()
+        );
+        let (addr, _server) = serve_once(rsp).await;
+        let url = Url::parse(&format!("http://{addr}/users")).unwrap();
+        let mut source = UserDynamicHttpSource::parse_url(&url).unwrap();
+        source.max_body_size = USERS_JSON.len() - 1;
+        let source = Arc::new(source);
+        let mut state = HttpFetchState::default();
+        assert!(fetch_records(&source, Path::new(""), &mut state)
+            .await
+            .is_err());
+        assert!(state.etag.is_none());
+    }
+
+    #[tokio::test]
+    async fn fallback_to_cache_file() {
+        let cache_file = "g3proxy-test-http-fetch-cache.json";
+        let _ = std::fs::remove_file(std::env::temp_dir().join(cache_file));
+
+        let rsp = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{USERS_JSON}",
+            USERS_JSON.len()
+        );
+        let (addr, _server) = serve_once(rsp).await;
+        let source = new_source(addr, cache_file);
+        let mut state = HttpFetchState::default();
+        let users = fetch_records(&source, Path::new(""), &mut state)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(users.len(), 1);
+
+        // the fetch will fail as the server returns an error
+        let rsp = "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n".to_string();
+        let (addr, _server) = serve_once(rsp).await;
+        let source = new_source(addr, cache_file);
+        assert!(fetch_records(&source, Path::new(""), &mut state)
+            .await
+            .is_err());
+
+        let users = source.fetch_cached_records(Path::new("")).await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name(), "root");
+
+        let _ = std::fs::remove_file(std::env::temp_dir().join(cache_file));
+    }
+}
@@ -22,9 +22,11 @@ use chrono::{DateTime, Utc};
 use futures_util::future::{AbortHandle, Abortable};
 use log::warn;
 
-use super::{User, UserGroupConfig};
+use super::{User, UserGroupConfig, UserQuotaStore};
 use crate::config::auth::{UserConfig, UserDynamicSource};
 
+mod http;
+
 #[cfg(feature = "lua")]
 mod lua;
 
@@ -34,9 +36,15 @@ mod python;
 pub(super) async fn load_initial_users(
     group_config: &UserGroupConfig,
     source: &UserDynamicSource,
+    quota_store: &UserQuotaStore,
 ) -> anyhow::Result<AHashMap<String, Arc<User>>> {
     let r = match source {
         UserDynamicSource::File(config) => config.fetch_records().await?,
+        UserDynamicSource::Http(config) => {
+            config
+                .fetch_cached_records(&group_config.dynamic_cache)
+                .await?
+        }
         #[cfg(feature = "lua")]
         UserDynamicSource::Lua(config) => {
             config
@@ -56,7 +64,12 @@ pub(super) async fn load_initial_users(
     for user_config in r {
         let user_config = Arc::new(user_config);
         let username = user_config.name();
-        let user = User::new(group_config.name(), &user_config, &datetime_now)?;
+        let user = User::new(
+            group_config.name(),
+            &user_config,
+            &datetime_now,
+            quota_store,
+        )?;
         dynamic_users.insert(username.to_string(), Arc::new(user));
     }
 
@@ -67,41 +80,54 @@ pub(super) fn new_job(
     group_config: &Arc<UserGroupConfig>,
     static_users: &Arc<AHashMap<String, Arc<User>>>,
     dynamic_users_container: &Arc<ArcSwap<AHashMap<String, Arc<User>>>>,
+    quota_store: &Arc<UserQuotaStore>,
 ) -> AbortHandle {
     let group_config = Arc::clone(group_config);
+    let quota_store = Arc::clone(quota_store);
     let static_users = Arc::clone(static_users);
     let dynamic_users_container = Arc::clone(dynamic_users_container);
 
     let f = async move {
         let mut interval = tokio::time::interval(group_config.refresh_interval);
+        let mut http_state = http::HttpFetchState::default();
         interval.tick().await; // will tick immediately
         loop {
-            let new_dynamic_config: Option<Vec<UserConfig>> =
-                if let Some(source) = &group_config.dynamic_source {
-                    let r = match source {
-                        UserDynamicSource::File(config) => config.fetch_records().await,
-                        #[cfg(feature = "lua")]
-                        UserDynamicSource::Lua(config) => {
-                            lua::fetch_records(config, &group_config.dynamic_cache).await
-                        }
-                        #[cfg(feature = "python")]
-                        UserDynamicSource::Python(config) => {
-                            python::fetch_records(config, &group_config.dynamic_cache).await
-                        }
-                    };
-                    match r {
-                        Ok(users) => Some(users),
-                        Err(e) => {
-                            warn!(
-                                "failed to fetch dynamic user for group {}: {e:?}",
-                                group_config.name(),
-                            );
-                            None
-                        }
+            let new_dynamic_config: Option<Vec<UserConfig>> = if let Some(source) =
+                &group_config.dynamic_source
+            {
+                let r = match source {
+                    UserDynamicSource::File(config) => config.fetch_records().await.map(Some),
+                    UserDynamicSource::Http(config) => {
+                        http::fetch_records(config, &group_config.dynamic_cache, &mut http_state)
+                            .await
+                    }
+                    #[cfg(feature = "lua")]
+                    UserDynamicSource::Lua(config) => {
+                        lua::fetch_records(config, &group_config.dynamic_cache)
+                            .await
+                            .map(Some)
+                    }
+                    #[cfg(feature = "python")]
+                    UserDynamicSource::Python(config) => {
+                        python::fetch_records(config, &group_config.dynamic_cache)
+                            .await
+                            .map(Some)
                     }
-                } else {
-                    None
                 };
+                match r {
+                    // None if not modified
+                    Ok(users) => users,
+                    Err(e) => {
+                        warn!(
+                            "failed to fetch dynamic user for group {}: {e:?}",
+                            group_config.name(),
+                        );
+                        None
+                    }
+                }
+            } else {
+                None
+            };
 
             let datetime_now = Utc::now();
 
@@ -112,6 +138,7 @@ pub(super) fn new_job(
                     &datetime_now,
                     dynamic_config,
                     &dynamic_users_container,
+                    &quota_store,
                 ) {
                     warn!("failed to update dynamic users: {e:?}");
                 }
@@ -136,6 +163,7 @@ pub(super) fn publish_dynamic_users(
     group_config: &UserGroupConfig,
     dynamic_config: Vec<UserConfig>,
     dynamic_users_container: &Arc<ArcSwap<AHashMap<String, Arc<User>>>>,
+    quota_store: &UserQuotaStore,
 ) -> anyhow::Result<()> {
     let datetime_now = Utc::now();
     update_dynamic_users(
@@ -143,6 +171,7 @@ pub(super) fn publish_dynamic_users(
         &datetime_now,
         dynamic_config,
         dynamic_users_container,
+        quota_store,
     )
 }
 
@@ -151,6 +180,7 @@ fn update_dynamic_users(
     datetime_now: &DateTime<Utc>,
     dynamic_config: Vec<UserConfig>,
     dynamic_users_container: &Arc<ArcSwap<AHashMap<String, Arc<User>>>>,
+    quota_store: &UserQuotaStore,
 ) -> anyhow::Result<()> {
     let old_dynamic_users = dynamic_users_container.load();
     let mut new_dynamic_users = AHashMap::new();
@@ -158,9 +188,9 @@ fn update_dynamic_users(
         let user_config = Arc::new(user_config);
         let username = user_config.name();
         let user = if let Some(old_user) = old_dynamic_users.get(username) {
-            old_user.new_for_reload(&user_config, datetime_now)?
+            old_user.new_for_reload(&user_config, datetime_now, quota_store)?
         } else {
-            User::new(group_config.name(), &user_config, datetime_now)?
+            User::new(group_config.name(), &user_config, datetime_now, quota_store)?
         };
         new_dynamic_users.insert(username.to_string(), Arc::new(user));
     }
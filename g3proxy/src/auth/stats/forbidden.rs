@@ -36,11 +36,13 @@ pub(crate) struct UserForbiddenStats {
     user_blocked: AtomicU64,
     fully_loaded: AtomicU64,
     rate_limited: AtomicU64,
+    quota_exceeded: AtomicU64,
     proto_banned: AtomicU64,
     src_blocked: AtomicU64,
     dest_denied: AtomicU64,
     ip_blocked: AtomicU64,
     ua_blocked: AtomicU64,
+    url_blocked: AtomicU64,
     log_skipped: AtomicU64,
 }
 
@@ -51,11 +53,13 @@ pub(crate) struct UserForbiddenSnapshot {
     pub(crate) user_blocked: u64,
     pub(crate) fully_loaded: u64,
     pub(crate) rate_limited: u64,
+    pub(crate) quota_exceeded: u64,
     pub(crate) proto_banned: u64,
     pub(crate) src_blocked: u64,
     pub(crate) dest_denied: u64,
     pub(crate) ip_blocked: u64,
     pub(crate) ua_blocked: u64,
+    pub(crate) url_blocked: u64,
     pub(crate) log_skipped: u64,
 }
 
@@ -79,11 +83,13 @@ impl UserForbiddenStats {
             user_blocked: Default::default(),
             fully_loaded: Default::default(),
             rate_limited: Default::default(),
+            quota_exceeded: Default::default(),
             proto_banned: Default::default(),
             src_blocked: Default::default(),
             dest_denied: Default::default(),
             ip_blocked: Default::default(),
             ua_blocked: Default::default(),
+            url_blocked: Default::default(),
             log_skipped: Default::default(),
         }
     }
@@ -125,11 +131,13 @@ impl UserForbiddenStats {
             user_blocked: self.user_blocked.load(Ordering::Relaxed),
             fully_loaded: self.fully_loaded.load(Ordering::Relaxed),
             rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            quota_exceeded: self.quota_exceeded.load(Ordering::Relaxed),
             proto_banned: self.proto_banned.load(Ordering::Relaxed),
             src_blocked: self.src_blocked.load(Ordering::Relaxed),
             dest_denied: self.dest_denied.load(Ordering::Relaxed),
             ip_blocked: self.ip_blocked.load(Ordering::Relaxed),
             ua_blocked: self.ua_blocked.load(Ordering::Relaxed),
+            url_blocked: self.url_blocked.load(Ordering::Relaxed),
             log_skipped: self.log_skipped.load(Ordering::Relaxed),
         }
     }
@@ -154,6 +162,10 @@ impl UserForbiddenStats {
         self.rate_limited.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub(crate) fn add_quota_exceeded(&self) {
+        self.quota_exceeded.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub(crate) fn add_proto_banned(&self) {
         self.proto_banned.fetch_add(1, Ordering::Relaxed);
     }
@@ -174,6 +186,10 @@ impl UserForbiddenStats {
         self.ua_blocked.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub(crate) fn add_url_blocked(&self) {
+        self.url_blocked.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub(crate) fn add_log_skipped(&self) {
         self.log_skipped.fetch_add(1, Ordering::Relaxed);
     }
@@ -26,17 +26,18 @@ use chrono::{DateTime, Utc};
 use governor::{clock::DefaultClock, state::InMemoryState, state::NotKeyed, RateLimiter};
 use tokio::time::Instant;
 
-use g3_types::acl::{AclAction, AclNetworkRule};
+use g3_types::acl::{AclAction, AclNetworkRule, AclUrlRule};
 use g3_types::acl_set::AclDstHostRuleSet;
 use g3_types::auth::UserAuthError;
 use g3_types::limit::{GaugeSemaphore, GaugeSemaphorePermit};
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
-use g3_types::net::{HttpHeaderMap, ProxyRequestType, UpstreamAddr};
+use g3_types::net::{Host, HttpHeaderMap, ProxyRequestType, UpstreamAddr};
 use g3_types::resolve::{ResolveRedirection, ResolveStrategy};
 
 use super::{
-    UserForbiddenStats, UserRequestStats, UserSite, UserSiteDurationRecorder, UserSiteStats,
-    UserSites, UserTrafficStats, UserType, UserUpstreamTrafficStats,
+    UserForbiddenStats, UserPasswordCache, UserQuotaState, UserQuotaStore, UserRequestStats,
+    UserSite, UserSiteDurationRecorder, UserSiteStats, UserSites, UserTrafficStats, UserType,
+    UserUpstreamTrafficStats,
 };
 use crate::config::auth::{UserAuditConfig, UserConfig};
 
@@ -50,8 +51,15 @@ pub(crate) struct User {
     tcp_conn_rate_limit: Option<Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>,
     ingress_net_filter: Option<Arc<AclNetworkRule>>,
     dst_host_filter: Option<Arc<AclDstHostRuleSet>>,
+    http_url_filter: Option<Arc<AclUrlRule>>,
     resolve_redirection: Option<ResolveRedirection>,
     log_rate_limit: Option<Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>,
+    quota: Option<Arc<UserQuotaState>>,
+    /// set if the traffic quota has been used up when synced, running tasks should be canceled
+    quota_exceeded: AtomicBool,
+    /// the total (upload, download) traffic that has been added to quota
+    quota_io_synced: Arc<Mutex<(u64, u64)>>,
+    password_cache: Option<Arc<UserPasswordCache>>,
     forbid_stats: Arc<Mutex<AHashMap<String, Arc<UserForbiddenStats>>>>,
     req_stats: Arc<Mutex<AHashMap<String, Arc<UserRequestStats>>>>,
     io_stats: Arc<Mutex<AHashMap<String, Arc<UserTrafficStats>>>>,
@@ -82,6 +90,14 @@ impl User {
             .map(|builder| Arc::new(builder.build()));
     }
 
+    fn update_http_url_filter(&mut self) {
+        self.http_url_filter = self
+            .config
+            .http_url_filter
+            .as_ref()
+            .map(|builder| Arc::new(builder.build()));
+    }
+
     fn update_resolve_redirection(&mut self) {
         self.resolve_redirection = self
             .config
@@ -94,6 +110,7 @@ impl User {
         group: &MetricsName,
         config: &Arc<UserConfig>,
         datetime_now: &DateTime<Utc>,
+        quota_store: &UserQuotaStore,
     ) -> anyhow::Result<Self> {
         let request_rate_limit = config
             .request_rate_limit
@@ -108,6 +125,15 @@ impl User {
             .as_ref()
             .map(|quota| Arc::new(RateLimiter::direct(quota.get_inner())));
 
+        let quota = config
+            .quota
+            .as_ref()
+            .map(|_| quota_store.fetch(config.name()));
+
+        let password_cache = config
+            .slow_password_hash()
+            .map(|_| Arc::new(UserPasswordCache::new()));
+
         let is_expired = AtomicBool::new(config.is_expired(datetime_now));
         let is_blocked = Arc::new(AtomicBool::new(config.block_and_delay.is_some()));
 
@@ -124,8 +150,13 @@ impl User {
             tcp_conn_rate_limit,
             ingress_net_filter: None,
             dst_host_filter: None,
+            http_url_filter: None,
             resolve_redirection: None,
             log_rate_limit,
+            quota,
+            quota_exceeded: AtomicBool::new(false),
+            quota_io_synced: Arc::new(Mutex::new((0, 0))),
+            password_cache,
             forbid_stats: Arc::new(Mutex::new(AHashMap::new())),
             req_stats: Arc::new(Mutex::new(AHashMap::new())),
             io_stats: Arc::new(Mutex::new(AHashMap::new())),
@@ -135,6 +166,7 @@ impl User {
         };
         user.update_ingress_net_filter();
         user.update_dst_host_filter();
+        user.update_http_url_filter();
         user.update_resolve_redirection();
         Ok(user)
     }
@@ -143,6 +175,7 @@ impl User {
         &self,
         config: &Arc<UserConfig>,
         datetime_now: &DateTime<Utc>,
+        quota_store: &UserQuotaStore,
     ) -> anyhow::Result<Self> {
         let request_rate_limit = if let Some(quota) = &config.request_rate_limit {
             if let Some(old_limiter) = &self.request_rate_limit {
@@ -201,6 +234,33 @@ impl User {
             None
         };
 
+        let quota = if config.quota.is_some() {
+            if self.quota.is_none() {
+                // only count in traffic after the quota is set
+                let mut synced = self.quota_io_synced.lock().unwrap();
+                *synced = self.io_total_bytes();
+            }
+            Some(quota_store.fetch(config.name()))
+        } else {
+            None
+        };
+
+        let password_cache = match (
+            self.config.slow_password_hash(),
+            config.slow_password_hash(),
+        ) {
+            (Some(old_hash), Some(new_hash)) => {
+                if old_hash.eq(new_hash) {
+                    // the verified password is still valid if the hash is not changed
+                    self.password_cache.clone()
+                } else {
+                    Some(Arc::new(UserPasswordCache::new()))
+                }
+            }
+            (None, Some(_)) => Some(Arc::new(UserPasswordCache::new())),
+            (_, None) => None,
+        };
+
         // always use the expired state in new config
         let is_expired = AtomicBool::new(config.is_expired(datetime_now));
 
@@ -227,8 +287,13 @@ impl User {
             tcp_conn_rate_limit,
             ingress_net_filter: None,
             dst_host_filter: None,
+            http_url_filter: None,
             resolve_redirection: None,
             log_rate_limit,
+            quota,
+            quota_exceeded: AtomicBool::new(false),
+            quota_io_synced: Arc::clone(&self.quota_io_synced),
+            password_cache,
             forbid_stats: Arc::clone(&self.forbid_stats),
             req_stats: Arc::clone(&self.req_stats),
             io_stats: Arc::clone(&self.io_stats),
@@ -250,13 +315,19 @@ impl User {
         } else {
             user.dst_host_filter.clone_from(&self.dst_host_filter);
         }
+        if self.config.http_url_filter.ne(&config.http_url_filter) {
+            user.update_http_url_filter();
+        } else {
+            user.http_url_filter.clone_from(&self.http_url_filter);
+        }
         user.update_resolve_redirection();
         Ok(user)
     }
 
-    /// for user blocked check in idle checking
+    /// for user blocked check in idle checking, running tasks will also be canceled if the
+    /// traffic quota has been used up
     pub(crate) fn is_blocked(&self) -> bool {
-        self.is_blocked.load(Ordering::Relaxed)
+        self.is_blocked.load(Ordering::Relaxed) || self.quota_exceeded.load(Ordering::Relaxed)
     }
 
     #[inline]
@@ -275,15 +346,66 @@ impl User {
         }
     }
 
-    fn check_password(
+    fn io_total_bytes(&self) -> (u64, u64) {
+        let map = self.io_stats.lock().unwrap();
+        let mut upload = 0u64;
+        let mut download = 0u64;
+        for stats in map.values() {
+            let (in_bytes, out_bytes) = stats.io.total_bytes();
+            upload = upload.wrapping_add(in_bytes);
+            download = download.wrapping_add(out_bytes);
+        }
+        (upload, download)
+    }
+
+    /// add the new client side traffic to quota, and reset the quota if a new period begins
+    pub(super) fn sync_quota(&self, datetime_now: &DateTime<Utc>) {
+        let (Some(config), Some(state)) = (&self.config.quota, &self.quota) else {
+            return;
+        };
+        state.check_period(config, datetime_now);
+
+        let (upload, download) = self.io_total_bytes();
+        let mut synced = self.quota_io_synced.lock().unwrap();
+        state.add_traffic(
+            upload.wrapping_sub(synced.0),
+            download.wrapping_sub(synced.1),
+        );
+        *synced = (upload, download);
+        self.quota_exceeded
+            .store(state.is_traffic_exceeded(config), Ordering::Relaxed);
+    }
+
+    /// get the used and remaining quota in json format
+    pub(super) fn quota_status(&self, datetime_now: &DateTime<Utc>) -> Option<serde_json::Value> {
+        self.sync_quota(datetime_now);
+        let (Some(config), Some(state)) = (&self.config.quota, &self.quota) else {
+            return None;
+        };
+        Some(state.snapshot().to_json(config))
+    }
+
+    async fn check_password(
         &self,
         password: &str,
         forbid_stats: &Arc<UserForbiddenStats>,
     ) -> Result<(), UserAuthError> {
-        if !self.config.check_password(password) {
+        let verified = if let Some(cache) = &self.password_cache {
+            let config = Arc::clone(&self.config);
+            cache
+                .verify(password, move |p| config.check_password(p))
+                .await
+        } else {
+            self.config.check_password(password)
+        };
+        if !verified {
             forbid_stats.add_auth_failed();
             return Err(UserAuthError::TokenNotMatch);
         }
+        self.check_available(forbid_stats)
+    }
+
+    fn check_available(&self, forbid_stats: &Arc<UserForbiddenStats>) -> Result<(), UserAuthError> {
         if self.is_expired() {
             forbid_stats.add_user_expired();
             return Err(UserAuthError::ExpiredUser);
@@ -439,6 +561,17 @@ impl User {
         Ok(())
     }
 
+    fn check_quota(&self, forbid_stats: &Arc<UserForbiddenStats>) -> Result<(), ()> {
+        if let (Some(config), Some(state)) = (&self.config.quota, &self.quota) {
+            if state.is_exceeded(config) {
+                forbid_stats.add_quota_exceeded();
+                return Err(());
+            }
+            state.add_request();
+        }
+        Ok(())
+    }
+
     fn acquire_request_semaphore(
         &self,
         forbid_stats: &Arc<UserForbiddenStats>,
@@ -534,6 +667,21 @@ impl User {
         }
     }
 
+    pub(crate) fn check_http_url(
+        &self,
+        scheme: &str,
+        host: &Host,
+        path_and_query: &str,
+        forbid_stats: &Arc<UserForbiddenStats>,
+    ) -> Option<AclAction> {
+        let filter = self.http_url_filter.as_ref()?;
+        let (_, action) = filter.check(scheme, host, path_and_query);
+        if action.forbid_early() {
+            forbid_stats.add_url_blocked();
+        }
+        Some(action)
+    }
+
     #[inline]
     pub(crate) fn resolve_redirection(&self) -> Option<&ResolveRedirection> {
         self.resolve_redirection.as_ref()
@@ -544,6 +692,11 @@ impl User {
         self.config.http_rsp_hdr_recv_timeout
     }
 
+    #[inline]
+    pub(crate) fn http_cache_bypass(&self) -> bool {
+        self.config.http_cache_bypass
+    }
+
     pub(crate) fn audit(&self) -> &UserAuditConfig {
         &self.config.audit
     }
@@ -728,8 +881,14 @@ impl UserContext {
     }
 
     #[inline]
-    pub(crate) fn check_password(&self, password: &str) -> Result<(), UserAuthError> {
-        self.user.check_password(password, &self.forbid_stats)
+    pub(crate) async fn check_password(&self, password: &str) -> Result<(), UserAuthError> {
+        self.user.check_password(password, &self.forbid_stats).await
+    }
+
+    /// check the user state only, the bearer token should be verified already
+    #[inline]
+    pub(crate) fn check_available(&self) -> Result<(), UserAuthError> {
+        self.user.check_available(&self.forbid_stats)
     }
 
     #[inline]
@@ -743,6 +902,11 @@ impl UserContext {
             .check_rate_limit(self.reused_client_connection, &self.forbid_stats)
     }
 
+    #[inline]
+    pub(crate) fn check_quota(&self) -> Result<(), ()> {
+        self.user.check_quota(&self.forbid_stats)
+    }
+
     #[inline]
     pub(crate) fn acquire_request_semaphore(&self) -> Result<GaugeSemaphorePermit, ()> {
         self.user.acquire_request_semaphore(&self.forbid_stats)
@@ -768,6 +932,17 @@ impl UserContext {
         self.user.check_http_user_agent(headers, &self.forbid_stats)
     }
 
+    #[inline]
+    pub(crate) fn check_http_url(
+        &self,
+        scheme: &str,
+        host: &Host,
+        path_and_query: &str,
+    ) -> Option<AclAction> {
+        self.user
+            .check_http_url(scheme, host, path_and_query, &self.forbid_stats)
+    }
+
     #[inline]
     pub(crate) fn add_dest_denied(&self) {
         self.forbid_stats.add_dest_denied();
@@ -21,8 +21,9 @@ use rand::distributions::Bernoulli;
 use yaml_rust::{yaml, Yaml};
 
 use g3_dpi::{
-    H1InterceptionConfig, H2InterceptionConfig, ProtocolInspectPolicy, ProtocolInspectionConfig,
-    ProtocolPortMap, SmtpInterceptionConfig,
+    H1InterceptionConfig, H2InterceptionConfig, ImapInterceptionConfig, Pop3InterceptionConfig,
+    ProtocolInspectPolicy, ProtocolInspectionConfig, ProtocolPortMap, SmtpInterceptionConfig,
+    WebSocketInterceptionConfig,
 };
 use g3_icap_client::IcapServiceConfig;
 use g3_tls_cert::agent::CertAgentConfig;
@@ -50,9 +51,15 @@ pub(crate) struct AuditorConfig {
     pub(crate) h2_interception: H2InterceptionConfig,
     pub(crate) smtp_inspect_policy: ProtocolInspectPolicy,
     pub(crate) smtp_interception: SmtpInterceptionConfig,
+    pub(crate) imap_inspect_policy: ProtocolInspectPolicy,
+    pub(crate) imap_interception: ImapInterceptionConfig,
+    pub(crate) pop3_inspect_policy: ProtocolInspectPolicy,
+    pub(crate) pop3_interception: Pop3InterceptionConfig,
+    pub(crate) websocket_interception: WebSocketInterceptionConfig,
     pub(crate) icap_reqmod_service: Option<Arc<IcapServiceConfig>>,
     pub(crate) icap_respmod_service: Option<Arc<IcapServiceConfig>>,
     pub(crate) task_audit_ratio: Bernoulli,
+    pub(crate) websocket_message_audit_ratio: Bernoulli,
 }
 
 impl AuditorConfig {
@@ -81,9 +88,15 @@ impl AuditorConfig {
             h2_interception: Default::default(),
             smtp_inspect_policy: ProtocolInspectPolicy::Intercept,
             smtp_interception: Default::default(),
+            imap_inspect_policy: ProtocolInspectPolicy::Intercept,
+            imap_interception: Default::default(),
+            pop3_inspect_policy: ProtocolInspectPolicy::Intercept,
+            pop3_interception: Default::default(),
+            websocket_interception: Default::default(),
             icap_reqmod_service: None,
             icap_respmod_service: None,
             task_audit_ratio: Bernoulli::new(1.0).unwrap(),
+            websocket_message_audit_ratio: Bernoulli::new(0.0).unwrap(),
         }
     }
 
@@ -192,6 +205,32 @@ impl AuditorConfig {
                     .context(format!("invalid smtp interception value for key {k}"))?;
                 Ok(())
             }
+            "imap_inspect_policy" => {
+                self.imap_inspect_policy = g3_yaml::value::as_protocol_inspect_policy(v)
+                    .context(format!("invalid protocol inspect policy value for key {k}"))?;
+                Ok(())
+            }
+            "imap_interception" => {
+                self.imap_interception = g3_yaml::value::as_imap_interception_config(v)
+                    .context(format!("invalid imap interception value for key {k}"))?;
+                Ok(())
+            }
+            "pop3_inspect_policy" => {
+                self.pop3_inspect_policy = g3_yaml::value::as_protocol_inspect_policy(v)
+                    .context(format!("invalid protocol inspect policy value for key {k}"))?;
+                Ok(())
+            }
+            "pop3_interception" => {
+                self.pop3_interception = g3_yaml::value::as_pop3_interception_config(v)
+                    .context(format!("invalid pop3 interception value for key {k}"))?;
+                Ok(())
+            }
+            "websocket_interception" => {
+                self.websocket_interception =
+                    g3_yaml::value::as_websocket_interception_config(v)
+                        .context(format!("invalid websocket interception value for key {k}"))?;
+                Ok(())
+            }
             "icap_reqmod_service" => {
                 let service = g3_yaml::value::as_icap_reqmod_service_config(v).context(format!(
                     "invalid icap reqmod service config value for key {k}"
@@ -211,6 +250,11 @@ impl AuditorConfig {
                     .context(format!("invalid random ratio value for key {k}"))?;
                 Ok(())
             }
+            "websocket_message_audit_ratio" => {
+                self.websocket_message_audit_ratio = g3_yaml::value::as_random_ratio(v)
+                    .context(format!("invalid random ratio value for key {k}"))?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -0,0 +1,112 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ClientCertUserField {
+    SubjectCn,
+    SanEmail,
+    SanUri,
+    Sha256Fingerprint,
+}
+
+impl FromStr for ClientCertUserField {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "subject_cn" | "cn" | "common_name" => Ok(ClientCertUserField::SubjectCn),
+            "san_email" | "email" => Ok(ClientCertUserField::SanEmail),
+            "san_uri" | "uri" => Ok(ClientCertUserField::SanUri),
+            "sha256_fingerprint" | "fingerprint" => Ok(ClientCertUserField::Sha256Fingerprint),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct UserClientCertConfig {
+    pub(crate) field: ClientCertUserField,
+    pub(crate) strip_prefix: Option<String>,
+    pub(crate) require_basic_auth: bool,
+}
+
+impl UserClientCertConfig {
+    fn new(field: ClientCertUserField) -> Self {
+        UserClientCertConfig {
+            field,
+            strip_prefix: None,
+            require_basic_auth: false,
+        }
+    }
+
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut config = UserClientCertConfig::new(ClientCertUserField::SubjectCn);
+                g3_yaml::foreach_kv(map, |k, v| config.set_yaml(k, v))?;
+                config.check()?;
+                Ok(config)
+            }
+            Yaml::String(_) => {
+                let field = Self::parse_field(v)?;
+                Ok(UserClientCertConfig::new(field))
+            }
+            _ => Err(anyhow!("invalid yaml value type")),
+        }
+    }
+
+    fn parse_field(v: &Yaml) -> anyhow::Result<ClientCertUserField> {
+        let s = g3_yaml::value::as_string(v)?;
+        ClientCertUserField::from_str(&g3_yaml::key::normalize(&s))
+            .map_err(|_| anyhow!("unsupported client cert field {s}"))
+    }
+
+    fn set_yaml(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "field" | "username_field" => {
+                self.field = Self::parse_field(v)
+                    .context(format!("invalid cert field value for key {k}"))?;
+                Ok(())
+            }
+            "strip_prefix" => {
+                let prefix = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                self.strip_prefix = Some(prefix);
+                Ok(())
+            }
+            "require_basic_auth" | "require_basic" => {
+                self.require_basic_auth = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.strip_prefix.is_some() && self.field == ClientCertUserField::Sha256Fingerprint {
+            return Err(anyhow!(
+                "strip_prefix can not be used with sha256 fingerprint field"
+            ));
+        }
+        Ok(())
+    }
+}
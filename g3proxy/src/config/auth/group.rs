@@ -25,9 +25,10 @@ use yaml_rust::{yaml, Yaml};
 use g3_types::metrics::MetricsName;
 use g3_yaml::YamlDocPosition;
 
-use super::{UserConfig, UserDynamicSource};
+use super::{UserClientCertConfig, UserConfig, UserDynamicSource, UserJwtConfig};
 
 const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_QUOTA_SYNC_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 pub(crate) struct UserGroupConfig {
@@ -38,6 +39,10 @@ pub(crate) struct UserGroupConfig {
     pub(crate) dynamic_cache: PathBuf,
     pub(crate) refresh_interval: Duration,
     pub(crate) anonymous_user: Option<Arc<UserConfig>>,
+    pub(crate) jwt: Option<Arc<UserJwtConfig>>,
+    pub(crate) client_cert: Option<UserClientCertConfig>,
+    pub(crate) quota_state: PathBuf,
+    pub(crate) quota_sync_interval: Duration,
 }
 
 impl UserGroupConfig {
@@ -58,6 +63,10 @@ impl UserGroupConfig {
             dynamic_cache: PathBuf::default(),
             refresh_interval: DEFAULT_REFRESH_INTERVAL,
             anonymous_user: None,
+            jwt: None,
+            client_cert: None,
+            quota_state: PathBuf::default(),
+            quota_sync_interval: DEFAULT_QUOTA_SYNC_INTERVAL,
         }
     }
 
@@ -70,6 +79,10 @@ impl UserGroupConfig {
             dynamic_cache: PathBuf::default(),
             refresh_interval: DEFAULT_REFRESH_INTERVAL,
             anonymous_user: None,
+            jwt: None,
+            client_cert: None,
+            quota_state: PathBuf::default(),
+            quota_sync_interval: DEFAULT_QUOTA_SYNC_INTERVAL,
         }
     }
 
@@ -145,6 +158,31 @@ impl UserGroupConfig {
                     Err(anyhow!("invalid hash value for key {k}"))
                 }
             }
+            "jwt" | "bearer_jwt" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let jwt = UserJwtConfig::parse_yaml(v, lookup_dir)
+                    .context(format!("invalid jwt config value for key {k}"))?;
+                self.jwt = Some(Arc::new(jwt));
+                Ok(())
+            }
+            "client_cert" | "tls_client_cert" => {
+                let client_cert = UserClientCertConfig::parse_yaml(v)
+                    .context(format!("invalid client cert config value for key {k}"))?;
+                self.client_cert = Some(client_cert);
+                Ok(())
+            }
+            "quota_state" | "quota_state_file" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let state_file = g3_yaml::value::as_file_path(v, lookup_dir, true)
+                    .context(format!("invalid file path value for key {k}"))?;
+                self.quota_state = state_file;
+                Ok(())
+            }
+            "quota_sync_interval" => {
+                self.quota_sync_interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid duration value for key {k}"))?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
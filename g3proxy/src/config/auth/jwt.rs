@@ -0,0 +1,160 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+const DEFAULT_USERNAME_CLAIM: &str = "sub";
+const DEFAULT_LEEWAY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum JwtAlgorithm {
+    HS256,
+    HS384,
+    HS512,
+    RS256,
+    RS384,
+    RS512,
+    PS256,
+    PS384,
+    PS512,
+    ES256,
+    ES384,
+    ES512,
+    EdDSA,
+}
+
+impl FromStr for JwtAlgorithm {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HS256" => Ok(JwtAlgorithm::HS256),
+            "HS384" => Ok(JwtAlgorithm::HS384),
+            "HS512" => Ok(JwtAlgorithm::HS512),
+            "RS256" => Ok(JwtAlgorithm::RS256),
+            "RS384" => Ok(JwtAlgorithm::RS384),
+            "RS512" => Ok(JwtAlgorithm::RS512),
+            "PS256" => Ok(JwtAlgorithm::PS256),
+            "PS384" => Ok(JwtAlgorithm::PS384),
+            "PS512" => Ok(JwtAlgorithm::PS512),
+            "ES256" => Ok(JwtAlgorithm::ES256),
+            "ES384" => Ok(JwtAlgorithm::ES384),
+            "ES512" => Ok(JwtAlgorithm::ES512),
+            "EdDSA" => Ok(JwtAlgorithm::EdDSA),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct UserJwtConfig {
+    pub(crate) jwks_file: PathBuf,
+    pub(crate) issuers: Vec<String>,
+    pub(crate) audiences: Vec<String>,
+    pub(crate) algorithms: Vec<JwtAlgorithm>,
+    pub(crate) username_claim: String,
+    pub(crate) leeway: Duration,
+    pub(crate) require_exp: bool,
+}
+
+impl UserJwtConfig {
+    fn new(jwks_file: PathBuf) -> Self {
+        UserJwtConfig {
+            jwks_file,
+            issuers: Vec::new(),
+            audiences: Vec::new(),
+            algorithms: Vec::new(),
+            username_claim: DEFAULT_USERNAME_CLAIM.to_string(),
+            leeway: DEFAULT_LEEWAY,
+            require_exp: true,
+        }
+    }
+
+    pub(crate) fn parse_yaml(v: &Yaml, lookup_dir: &Path) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut config = UserJwtConfig::new(PathBuf::new());
+                g3_yaml::foreach_kv(map, |k, v| config.set_yaml(k, v, lookup_dir))?;
+                config.check()?;
+                Ok(config)
+            }
+            Yaml::String(_) => {
+                let path = g3_yaml::value::as_file_path(v, lookup_dir, false)?;
+                Ok(UserJwtConfig::new(path))
+            }
+            _ => Err(anyhow!("invalid yaml value type")),
+        }
+    }
+
+    fn set_yaml(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "jwks_file" | "jwks" => {
+                self.jwks_file = g3_yaml::value::as_file_path(v, lookup_dir, false)
+                    .context(format!("invalid file path value for key {k}"))?;
+                Ok(())
+            }
+            "issuer" | "issuers" => {
+                self.issuers = g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?;
+                Ok(())
+            }
+            "audience" | "audiences" => {
+                self.audiences = g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?;
+                Ok(())
+            }
+            "algorithm" | "algorithms" => {
+                self.algorithms = g3_yaml::value::as_list(v, |v| {
+                    let s = g3_yaml::value::as_string(v)?;
+                    JwtAlgorithm::from_str(&s).map_err(|_| anyhow!("unsupported algorithm {s}"))
+                })
+                .context(format!("invalid jwt algorithm list value for key {k}"))?;
+                Ok(())
+            }
+            "username_claim" => {
+                self.username_claim = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                Ok(())
+            }
+            "leeway" => {
+                self.leeway = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "require_exp" => {
+                self.require_exp = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.jwks_file.as_os_str().is_empty() {
+            return Err(anyhow!("no jwks file set"));
+        }
+        if self.username_claim.is_empty() {
+            return Err(anyhow!("empty username claim"));
+        }
+        Ok(())
+    }
+}
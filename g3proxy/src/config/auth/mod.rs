@@ -30,9 +30,18 @@ pub(crate) use site::UserSiteConfig;
 mod audit;
 pub(crate) use audit::UserAuditConfig;
 
+mod quota;
+pub(crate) use quota::{UserQuotaConfig, UserQuotaPeriod};
+
 mod user;
 pub(crate) use user::UserConfig;
 
+mod jwt;
+pub(crate) use jwt::{JwtAlgorithm, UserJwtConfig};
+
+mod client_cert;
+pub(crate) use client_cert::{ClientCertUserField, UserClientCertConfig};
+
 mod group;
 pub(crate) use group::UserGroupConfig;
 
@@ -0,0 +1,79 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use super::{UserQuotaConfig, UserQuotaPeriod};
+
+impl UserQuotaPeriod {
+    fn parse_json(v: &Value) -> anyhow::Result<Self> {
+        if let Value::String(s) = v {
+            match g3_json::key::normalize(s).as_str() {
+                "daily" | "day" => Ok(UserQuotaPeriod::Daily),
+                "weekly" | "week" => Ok(UserQuotaPeriod::Weekly),
+                "monthly" | "month" => Ok(UserQuotaPeriod::Monthly),
+                _ => Err(anyhow!("invalid quota period {s}")),
+            }
+        } else {
+            Err(anyhow!(
+                "json value type for 'user quota period' should be 'string'"
+            ))
+        }
+    }
+}
+
+impl UserQuotaConfig {
+    pub(crate) fn parse_json(v: &Value) -> anyhow::Result<Self> {
+        if let Value::Object(map) = v {
+            let mut config = UserQuotaConfig::default();
+            for (k, v) in map {
+                match g3_json::key::normalize(k).as_str() {
+                    "period" => {
+                        config.period = UserQuotaPeriod::parse_json(v)
+                            .context(format!("invalid quota period value for key {k}"))?;
+                    }
+                    "upload" | "upload_bytes" => {
+                        let size = g3_json::humanize::as_u64(v)
+                            .context(format!("invalid humanize u64 value for key {k}"))?;
+                        config.upload_bytes = Some(size);
+                    }
+                    "download" | "download_bytes" => {
+                        let size = g3_json::humanize::as_u64(v)
+                            .context(format!("invalid humanize u64 value for key {k}"))?;
+                        config.download_bytes = Some(size);
+                    }
+                    "total" | "total_bytes" => {
+                        let size = g3_json::humanize::as_u64(v)
+                            .context(format!("invalid humanize u64 value for key {k}"))?;
+                        config.total_bytes = Some(size);
+                    }
+                    "requests" | "request_count" => {
+                        let count = g3_json::value::as_u64(v)
+                            .context(format!("invalid u64 value for key {k}"))?;
+                        config.requests = Some(count);
+                    }
+                    _ => return Err(anyhow!("invalid key {k}")),
+                }
+            }
+            Ok(config)
+        } else {
+            Err(anyhow!(
+                "json value type for 'user quota config' should be 'map'"
+            ))
+        }
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+
+mod json;
+mod yaml;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum UserQuotaPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl UserQuotaPeriod {
+    fn start_date(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            UserQuotaPeriod::Daily => date,
+            UserQuotaPeriod::Weekly => {
+                let offset = date.weekday().num_days_from_monday();
+                date - Days::new(offset as u64)
+            }
+            UserQuotaPeriod::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// get the unix timestamp of the start of the period the datetime is in
+    pub(crate) fn start_of(&self, datetime: &DateTime<Utc>) -> i64 {
+        self.start_date(datetime.date_naive())
+            .and_hms_opt(0, 0, 0)
+            .map(|dt| dt.and_utc().timestamp())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct UserQuotaConfig {
+    pub(crate) period: UserQuotaPeriod,
+    pub(crate) upload_bytes: Option<u64>,
+    pub(crate) download_bytes: Option<u64>,
+    pub(crate) total_bytes: Option<u64>,
+    pub(crate) requests: Option<u64>,
+}
+
+impl Default for UserQuotaConfig {
+    fn default() -> Self {
+        UserQuotaConfig {
+            period: UserQuotaPeriod::Monthly,
+            upload_bytes: None,
+            download_bytes: None,
+            total_bytes: None,
+            requests: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn period_start() {
+        let dt = Utc.with_ymd_and_hms(2024, 5, 16, 13, 24, 35).unwrap();
+
+        let start = Utc.with_ymd_and_hms(2024, 5, 16, 0, 0, 0).unwrap();
+        assert_eq!(UserQuotaPeriod::Daily.start_of(&dt), start.timestamp());
+
+        let start = Utc.with_ymd_and_hms(2024, 5, 13, 0, 0, 0).unwrap();
+        assert_eq!(UserQuotaPeriod::Weekly.start_of(&dt), start.timestamp());
+
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        assert_eq!(UserQuotaPeriod::Monthly.start_of(&dt), start.timestamp());
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use super::{UserQuotaConfig, UserQuotaPeriod};
+
+impl UserQuotaPeriod {
+    fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::String(s) = v {
+            match g3_yaml::key::normalize(s).as_str() {
+                "daily" | "day" => Ok(UserQuotaPeriod::Daily),
+                "weekly" | "week" => Ok(UserQuotaPeriod::Weekly),
+                "monthly" | "month" => Ok(UserQuotaPeriod::Monthly),
+                _ => Err(anyhow!("invalid quota period {s}")),
+            }
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'user quota period' should be 'string'"
+            ))
+        }
+    }
+}
+
+impl UserQuotaConfig {
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        if let Yaml::Hash(map) = v {
+            let mut config = UserQuotaConfig::default();
+            g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                "period" => {
+                    config.period = UserQuotaPeriod::parse_yaml(v)
+                        .context(format!("invalid quota period value for key {k}"))?;
+                    Ok(())
+                }
+                "upload" | "upload_bytes" => {
+                    let size = g3_yaml::humanize::as_u64(v)
+                        .context(format!("invalid humanize u64 value for key {k}"))?;
+                    config.upload_bytes = Some(size);
+                    Ok(())
+                }
+                "download" | "download_bytes" => {
+                    let size = g3_yaml::humanize::as_u64(v)
+                        .context(format!("invalid humanize u64 value for key {k}"))?;
+                    config.download_bytes = Some(size);
+                    Ok(())
+                }
+                "total" | "total_bytes" => {
+                    let size = g3_yaml::humanize::as_u64(v)
+                        .context(format!("invalid humanize u64 value for key {k}"))?;
+                    config.total_bytes = Some(size);
+                    Ok(())
+                }
+                "requests" | "request_count" => {
+                    let count = g3_yaml::value::as_u64(v)
+                        .context(format!("invalid u64 value for key {k}"))?;
+                    config.requests = Some(count);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+            Ok(config)
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'user quota config' should be 'map'"
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn parse() {
+        let doc = YamlLoader::load_from_str(
+            r#"
+            period: weekly
+            download: 10GiB
+            total: 20GiB
+            requests: 1000
+            "#,
+        )
+        .unwrap();
+        let config = UserQuotaConfig::parse_yaml(&doc[0]).unwrap();
+        assert_eq!(config.period, UserQuotaPeriod::Weekly);
+        assert_eq!(config.upload_bytes, None);
+        assert_eq!(config.download_bytes, Some(10 << 30));
+        assert_eq!(config.total_bytes, Some(20 << 30));
+        assert_eq!(config.requests, Some(1000));
+    }
+}
@@ -0,0 +1,249 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use rustls::ServerName;
+use url::Url;
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::fs::ConfigFileFormat;
+use g3_types::net::{RustlsClientConfig, RustlsClientConfigBuilder};
+
+use super::file::UserDynamicFileSource;
+use crate::config::auth::UserConfig;
+
+const CONFIG_KEY_SOURCE_URL: &str = "url";
+
+#[derive(Clone)]
+pub(crate) struct UserDynamicHttpSource {
+    pub(crate) url: Url,
+    pub(crate) tls_client: Option<RustlsClientConfig>,
+    pub(crate) tls_name: Option<ServerName>,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) fetch_timeout: Duration,
+    pub(crate) max_body_size: usize,
+    pub(crate) cache_file: PathBuf,
+}
+
+impl UserDynamicHttpSource {
+    fn new(url: Url) -> Self {
+        UserDynamicHttpSource {
+            url,
+            tls_client: None,
+            tls_name: None,
+            connect_timeout: Duration::from_secs(10),
+            fetch_timeout: Duration::from_secs(30),
+            max_body_size: 64 * 1024 * 1024,
+            cache_file: PathBuf::default(),
+        }
+    }
+
+    pub(super) fn parse_map(map: &yaml::Hash, lookup_dir: &Path) -> anyhow::Result<Self> {
+        let v = g3_yaml::hash_get_required(map, CONFIG_KEY_SOURCE_URL)?;
+        let url = g3_yaml::value::as_url(v)
+            .context(format!("invalid url value for key {CONFIG_KEY_SOURCE_URL}"))?;
+        let mut config = UserDynamicHttpSource::new(url);
+
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v, lookup_dir))?;
+
+        config.check()?;
+        Ok(config)
+    }
+
+    pub(super) fn parse_url(url: &Url) -> anyhow::Result<Self> {
+        let mut config = UserDynamicHttpSource::new(url.clone());
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, lookup_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            super::CONFIG_KEY_SOURCE_TYPE => Ok(()),
+            CONFIG_KEY_SOURCE_URL => Ok(()),
+            "tls_client" => {
+                let builder = g3_yaml::value::as_rustls_client_config_builder(v, Some(lookup_dir))
+                    .context(format!(
+                        "invalid rustls tls client config value for key {k}"
+                    ))?;
+                let tls_client = builder
+                    .build()
+                    .context("failed to build tls client config")?;
+                self.tls_client = Some(tls_client);
+                Ok(())
+            }
+            "tls_name" => {
+                let tls_name = g3_yaml::value::as_rustls_server_name(v)
+                    .context(format!("invalid rustls server name value for key {k}"))?;
+                self.tls_name = Some(tls_name);
+                Ok(())
+            }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "fetch_timeout" | "timeout" => {
+                self.fetch_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "max_body_size" => {
+                self.max_body_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            "cache_file" => {
+                let cache_file = g3_yaml::value::as_file_path(v, lookup_dir, true)
+                    .context(format!("invalid file path value for key {k}"))?;
+                self.cache_file = cache_file;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&mut self) -> anyhow::Result<()> {
+        match self.url.scheme() {
+            "http" => {
+                if self.tls_client.is_some() {
+                    return Err(anyhow!("tls_client is set but the url scheme is http"));
+                }
+            }
+            "https" => {
+                if self.tls_client.is_none() {
+                    let tls_client = RustlsClientConfigBuilder::default()
+                        .build()
+                        .context("failed to build default tls client config")?;
+                    self.tls_client = Some(tls_client);
+                }
+            }
+            s => return Err(anyhow!("unsupported url scheme {s}")),
+        }
+        if self.url.host().is_none() {
+            return Err(anyhow!("no host set in url"));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn real_cache_path<'a>(&'a self, cache: &'a Path) -> &'a Path {
+        if cache.as_os_str().is_empty() {
+            self.cache_file.as_path()
+        } else {
+            cache
+        }
+    }
+
+    pub(crate) async fn fetch_cached_records(
+        &self,
+        cache: &Path,
+    ) -> anyhow::Result<Vec<UserConfig>> {
+        let path = self.real_cache_path(cache);
+        if path.as_os_str().is_empty() {
+            return Ok(Vec::new());
+        }
+        let file_source = UserDynamicFileSource {
+            path: path.to_path_buf(),
+            format: ConfigFileFormat::Json,
+        };
+        file_source.fetch_records().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse_yaml(s: &str) -> anyhow::Result<UserDynamicHttpSource> {
+        let doc = YamlLoader::load_from_str(s).unwrap();
+        let map = doc[0].as_hash().unwrap();
+        UserDynamicHttpSource::parse_map(map, &std::env::temp_dir())
+    }
+
+    #[test]
+    fn parse_http() {
+        let config = parse_yaml(
+            r#"
+            type: http
+            url: http://127.0.0.1:8080/users
+            connect_timeout: 5s
+            timeout: 10s
+            max_body_size: 1MiB
+            cache_file: g3proxy-test-http-source.json
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.url.as_str(), "http://127.0.0.1:8080/users");
+        assert!(config.tls_client.is_none());
+        assert_eq!(config.connect_timeout, Duration::from_secs(5));
+        assert_eq!(config.fetch_timeout, Duration::from_secs(10));
+        assert_eq!(config.max_body_size, 1 << 20);
+        assert_eq!(
+            config.cache_file,
+            std::env::temp_dir().join("g3proxy-test-http-source.json")
+        );
+    }
+
+    #[test]
+    fn parse_https() {
+        let config = parse_yaml(
+            r#"
+            type: http
+            url: https://example.com/users
+            "#,
+        )
+        .unwrap();
+        assert!(config.tls_client.is_some());
+        assert!(config.cache_file.as_os_str().is_empty());
+
+        let url = Url::parse("https://example.com/users").unwrap();
+        let config = UserDynamicHttpSource::parse_url(&url).unwrap();
+        assert!(config.tls_client.is_some());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_yaml(
+            r#"
+            type: http
+            url: http://127.0.0.1/users
+            tls_client: {}
+            "#,
+        )
+        .is_err());
+
+        assert!(parse_yaml(
+            r#"
+            type: http
+            url: ftp://127.0.0.1/users
+            "#,
+        )
+        .is_err());
+
+        assert!(parse_yaml(
+            r#"
+            type: http
+            url: http://127.0.0.1/users
+            unknown_key: 1
+            "#,
+        )
+        .is_err());
+    }
+}
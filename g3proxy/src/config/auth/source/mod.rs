@@ -23,6 +23,7 @@ use yaml_rust::Yaml;
 
 pub(crate) mod cache;
 pub(crate) mod file;
+pub(crate) mod http;
 
 #[cfg(feature = "lua")]
 pub(crate) mod lua;
@@ -35,6 +36,7 @@ const CONFIG_KEY_SOURCE_TYPE: &str = "type";
 #[derive(Clone)]
 pub(crate) enum UserDynamicSource {
     File(Arc<file::UserDynamicFileSource>),
+    Http(Arc<http::UserDynamicHttpSource>),
     #[cfg(feature = "lua")]
     Lua(Arc<lua::UserDynamicLuaSource>),
     #[cfg(feature = "python")]
@@ -52,6 +54,10 @@ impl UserDynamicSource {
                         let source = file::UserDynamicFileSource::parse_map(map, lookup_dir)?;
                         Ok(UserDynamicSource::File(Arc::new(source)))
                     }
+                    "http" | "https" => {
+                        let source = http::UserDynamicHttpSource::parse_map(map, lookup_dir)?;
+                        Ok(UserDynamicSource::Http(Arc::new(source)))
+                    }
                     #[cfg(feature = "lua")]
                     "lua" => {
                         let source = lua::UserDynamicLuaSource::parse_map(map, lookup_dir)?;
@@ -74,6 +80,10 @@ impl UserDynamicSource {
                         let source = file::UserDynamicFileSource::parse_url(&url)?;
                         Ok(UserDynamicSource::File(Arc::new(source)))
                     }
+                    "http" | "https" => {
+                        let source = http::UserDynamicHttpSource::parse_url(&url)?;
+                        Ok(UserDynamicSource::Http(Arc::new(source)))
+                    }
                     _ => Err(anyhow!("unsupported url scheme: {scheme}")),
                 }
             }
@@ -22,7 +22,8 @@ use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 
 use g3_types::acl::{
-    AclExactPortRule, AclNetworkRuleBuilder, AclProxyRequestRule, AclUserAgentRule,
+    AclExactPortRule, AclNetworkRuleBuilder, AclProxyRequestRule, AclUrlRuleBuilder,
+    AclUserAgentRule,
 };
 use g3_types::acl_set::AclDstHostRuleSetBuilder;
 use g3_types::limit::RateLimitQuotaConfig;
@@ -32,8 +33,9 @@ use g3_types::net::{
     TcpSockSpeedLimitConfig, UdpMiscSockOpts, UdpSockSpeedLimitConfig,
 };
 use g3_types::resolve::{ResolveRedirectionBuilder, ResolveStrategy};
+use g3_xcrypt::XCryptHash;
 
-use super::{PasswordToken, UserAuditConfig, UserSiteConfig};
+use super::{PasswordToken, UserAuditConfig, UserQuotaConfig, UserSiteConfig};
 use crate::escape::EgressPathSelection;
 
 mod json;
@@ -54,11 +56,13 @@ pub(crate) struct UserConfig {
     udp_client_misc_opts: Option<UdpMiscSockOpts>,
     pub(crate) http_upstream_keepalive: HttpKeepAliveConfig,
     pub(crate) http_rsp_hdr_recv_timeout: Option<Duration>,
+    pub(crate) http_cache_bypass: bool,
     pub(crate) request_alive_max: usize,
     pub(crate) request_rate_limit: Option<RateLimitQuotaConfig>,
     pub(crate) tcp_conn_rate_limit: Option<RateLimitQuotaConfig>,
     pub(crate) tcp_sock_speed_limit: TcpSockSpeedLimitConfig,
     pub(crate) udp_sock_speed_limit: UdpSockSpeedLimitConfig,
+    pub(crate) quota: Option<UserQuotaConfig>,
     pub(crate) log_rate_limit: Option<RateLimitQuotaConfig>,
     pub(crate) log_uri_max_chars: Option<usize>,
     pub(crate) ingress_net_filter: Option<AclNetworkRuleBuilder>,
@@ -66,6 +70,7 @@ pub(crate) struct UserConfig {
     pub(crate) dst_host_filter: Option<AclDstHostRuleSetBuilder>,
     pub(crate) dst_port_filter: Option<AclExactPortRule>,
     pub(crate) http_user_agent_filter: Option<AclUserAgentRule>,
+    pub(crate) http_url_filter: Option<AclUrlRuleBuilder>,
     pub(crate) resolve_strategy: Option<ResolveStrategy>,
     pub(crate) resolve_redirection: Option<ResolveRedirectionBuilder>,
     pub(crate) task_idle_max_count: i32,
@@ -90,11 +95,13 @@ impl Default for UserConfig {
             udp_client_misc_opts: None,
             http_upstream_keepalive: Default::default(),
             http_rsp_hdr_recv_timeout: None,
+            http_cache_bypass: false,
             request_alive_max: 0,
             request_rate_limit: None,
             tcp_conn_rate_limit: None,
             tcp_sock_speed_limit: Default::default(),
             udp_sock_speed_limit: Default::default(),
+            quota: None,
             log_rate_limit: None,
             log_uri_max_chars: None,
             ingress_net_filter: None,
@@ -102,6 +109,7 @@ impl Default for UserConfig {
             dst_host_filter: None,
             dst_port_filter: None,
             http_user_agent_filter: None,
+            http_url_filter: None,
             resolve_strategy: None,
             resolve_redirection: None,
             task_idle_max_count: 1,
@@ -134,6 +142,14 @@ impl UserConfig {
         }
     }
 
+    /// get the xcrypt hash if it's too slow to be verified for each request
+    pub(crate) fn slow_password_hash(&self) -> Option<&XCryptHash> {
+        match &self.password_token {
+            PasswordToken::XCrypt(xcrypt_hash) if xcrypt_hash.is_slow() => Some(xcrypt_hash),
+            _ => None,
+        }
+    }
+
     pub(super) fn set_no_password(&mut self) {
         self.password_token = PasswordToken::SkipVerify;
     }
@@ -20,7 +20,7 @@ use yaml_rust::{yaml, Yaml};
 
 use g3_yaml::YamlDocPosition;
 
-use super::{PasswordToken, UserConfig, UserSiteConfig};
+use super::{PasswordToken, UserConfig, UserQuotaConfig, UserSiteConfig};
 use crate::escape::EgressPathSelection;
 
 impl UserConfig {
@@ -167,6 +167,12 @@ impl UserConfig {
                 self.http_user_agent_filter = Some(filter);
                 Ok(())
             }
+            "http_url_filter" => {
+                let builder = g3_yaml::value::acl::as_url_rule_builder(v)
+                    .context(format!("invalid url acl rule value for key {k}"))?;
+                self.http_url_filter = Some(builder);
+                Ok(())
+            }
             "resolve_strategy" => {
                 let strategy = g3_yaml::value::as_resolve_strategy(v)
                     .context(format!("invalid resolve strategy value for key {k}"))?;
@@ -179,6 +185,12 @@ impl UserConfig {
                 self.resolve_redirection = Some(builder);
                 Ok(())
             }
+            "quota" | "traffic_quota" => {
+                let quota = UserQuotaConfig::parse_yaml(v)
+                    .context(format!("invalid user quota config value for key {k}"))?;
+                self.quota = Some(quota);
+                Ok(())
+            }
             "log_rate_limit" | "log_limit_quota" => {
                 let quota = g3_yaml::value::as_rate_limit_quota(v)
                     .context(format!("invalid request quota value for key {k}"))?;
@@ -196,6 +208,11 @@ impl UserConfig {
                     g3_yaml::value::as_i32(v).context(format!("invalid i32 value for key {k}"))?;
                 Ok(())
             }
+            "http_cache_bypass" => {
+                self.http_cache_bypass = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
             "socks_use_udp_associate" => {
                 self.socks_use_udp_associate = g3_yaml::value::as_bool(v)
                     .context(format!("invalid bool value for key {k}"))?;
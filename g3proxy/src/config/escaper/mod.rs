@@ -52,6 +52,9 @@ pub(crate) use registry::clear;
 mod verify;
 use verify::EscaperConfigVerifier;
 
+mod peer_health;
+pub(crate) use peer_health::{PeerHealthCheckConfig, PeerHealthCheckMethod};
+
 const CONFIG_KEY_ESCAPER_TYPE: &str = "type";
 const CONFIG_KEY_ESCAPER_NAME: &str = "name";
 
@@ -0,0 +1,154 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+use g3_types::net::UpstreamAddr;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum PeerHealthCheckMethod {
+    /// only check if a tcp connection can be established
+    TcpConnect,
+    /// send a HTTP CONNECT request to the canary target
+    HttpConnect(UpstreamAddr),
+    /// complete the SOCKS5 method negotiation (and auth) stage,
+    /// and send a CONNECT request to the canary target if set
+    Socks5Handshake(Option<UpstreamAddr>),
+}
+
+impl PeerHealthCheckMethod {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            PeerHealthCheckMethod::TcpConnect => "tcp_connect",
+            PeerHealthCheckMethod::HttpConnect(_) => "http_connect",
+            PeerHealthCheckMethod::Socks5Handshake(_) => "socks5_handshake",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PeerHealthCheckConfig {
+    pub(crate) method: PeerHealthCheckMethod,
+    pub(crate) interval: Duration,
+    pub(crate) timeout: Duration,
+    pub(crate) rise: usize,
+    pub(crate) fall: usize,
+}
+
+impl Default for PeerHealthCheckConfig {
+    fn default() -> Self {
+        PeerHealthCheckConfig {
+            method: PeerHealthCheckMethod::TcpConnect,
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(4),
+            rise: 2,
+            fall: 3,
+        }
+    }
+}
+
+impl PeerHealthCheckConfig {
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::String(s) => {
+                let mut config = PeerHealthCheckConfig::default();
+                config.set_method(s, None)?;
+                Ok(config)
+            }
+            Yaml::Hash(map) => Self::parse_map(map),
+            _ => Err(anyhow!(
+                "yaml value type for 'peer health check' should be 'string' or 'map'"
+            )),
+        }
+    }
+
+    fn parse_map(map: &yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = PeerHealthCheckConfig::default();
+        let mut method = String::from("tcp_connect");
+        let mut target: Option<UpstreamAddr> = None;
+
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "method" | "type" => {
+                method = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "target" | "canary" | "canary_target" => {
+                let addr = g3_yaml::value::as_upstream_addr(v, 0)
+                    .context(format!("invalid upstream addr value for key {k}"))?;
+                target = Some(addr);
+                Ok(())
+            }
+            "interval" => {
+                config.interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "timeout" => {
+                config.timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "rise" | "healthy_threshold" => {
+                config.rise = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "fall" | "unhealthy_threshold" => {
+                config.fall = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        config.set_method(&method, target)?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set_method(&mut self, method: &str, target: Option<UpstreamAddr>) -> anyhow::Result<()> {
+        self.method = match g3_yaml::key::normalize(method).as_str() {
+            "tcp_connect" | "tcp" => PeerHealthCheckMethod::TcpConnect,
+            "http_connect" | "http" => {
+                let target =
+                    target.ok_or_else(|| anyhow!("canary target is required for http connect"))?;
+                PeerHealthCheckMethod::HttpConnect(target)
+            }
+            "socks5_handshake" | "socks5" => PeerHealthCheckMethod::Socks5Handshake(target),
+            _ => return Err(anyhow!("unsupported peer health check method {method}")),
+        };
+        Ok(())
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.interval.is_zero() {
+            return Err(anyhow!("interval should not be zero"));
+        }
+        if self.timeout.is_zero() {
+            return Err(anyhow!("timeout should not be zero"));
+        }
+        if self.rise == 0 {
+            return Err(anyhow!("rise count should not be zero"));
+        }
+        if self.fall == 0 {
+            return Err(anyhow!("fall count should not be zero"));
+        }
+        Ok(())
+    }
+}
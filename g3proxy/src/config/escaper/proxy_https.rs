@@ -32,7 +32,10 @@ use g3_types::net::{
 use g3_types::resolve::{QueryStrategy, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
-use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig};
+use super::{
+    AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig,
+    PeerHealthCheckConfig, PeerHealthCheckMethod,
+};
 
 const ESCAPER_CONFIG_TYPE: &str = "ProxyHttps";
 
@@ -63,6 +66,7 @@ pub(crate) struct ProxyHttpsEscaperConfig {
     pub(crate) pass_proxy_userid: bool,
     pub(crate) use_proxy_protocol: Option<ProxyProtocolVersion>,
     pub(crate) peer_negotiation_timeout: Duration,
+    pub(crate) peer_health_check: Option<PeerHealthCheckConfig>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
 
@@ -94,6 +98,7 @@ impl ProxyHttpsEscaperConfig {
             pass_proxy_userid: false,
             use_proxy_protocol: None,
             peer_negotiation_timeout: Duration::from_secs(10),
+            peer_health_check: None,
             extra_metrics_tags: None,
         }
     }
@@ -245,6 +250,13 @@ impl ProxyHttpsEscaperConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "peer_health_check" => {
+                let config = PeerHealthCheckConfig::parse_yaml(v).context(format!(
+                    "invalid peer health check config value for key {k}"
+                ))?;
+                self.peer_health_check = Some(config);
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -260,6 +272,14 @@ impl ProxyHttpsEscaperConfig {
         if self.no_ipv4 && self.no_ipv6 {
             return Err(anyhow!("both ipv4 and ipv6 are disabled"));
         }
+        if let Some(health_check) = &self.peer_health_check {
+            if let PeerHealthCheckMethod::Socks5Handshake(_) = health_check.method {
+                return Err(anyhow!(
+                    "peer health check method {} is not supported by this escaper",
+                    health_check.method.as_str()
+                ));
+            }
+        }
 
         let mut disable_ipv4 = true;
         let mut disable_ipv6 = true;
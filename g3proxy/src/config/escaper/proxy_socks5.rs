@@ -33,7 +33,10 @@ use g3_types::net::{
 use g3_types::resolve::{QueryStrategy, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
-use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig};
+use super::{
+    AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig,
+    PeerHealthCheckConfig, PeerHealthCheckMethod,
+};
 
 const ESCAPER_CONFIG_TYPE: &str = "ProxySocks5";
 
@@ -59,6 +62,7 @@ pub(crate) struct ProxySocks5EscaperConfig {
     pub(crate) udp_misc_opts: UdpMiscSockOpts,
     pub(crate) auth_info: SocksAuth,
     pub(crate) peer_negotiation_timeout: Duration,
+    pub(crate) peer_health_check: Option<PeerHealthCheckConfig>,
     transmute_udp_peer_ip: Option<AHashMap<IpAddr, IpAddr>>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
@@ -86,6 +90,7 @@ impl ProxySocks5EscaperConfig {
             udp_misc_opts: Default::default(),
             auth_info: SocksAuth::None,
             peer_negotiation_timeout: Duration::from_secs(10),
+            peer_health_check: None,
             transmute_udp_peer_ip: None,
             extra_metrics_tags: None,
         }
@@ -213,6 +218,13 @@ impl ProxySocks5EscaperConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "peer_health_check" => {
+                let config = PeerHealthCheckConfig::parse_yaml(v).context(format!(
+                    "invalid peer health check config value for key {k}"
+                ))?;
+                self.peer_health_check = Some(config);
+                Ok(())
+            }
             "transmute_udp_peer_ip" => {
                 if let Yaml::Hash(_) = v {
                     let map = g3_yaml::value::as_hashmap(
@@ -245,6 +257,14 @@ impl ProxySocks5EscaperConfig {
         if self.no_ipv4 && self.no_ipv6 {
             return Err(anyhow!("both ipv4 and ipv6 are disabled"));
         }
+        if let Some(health_check) = &self.peer_health_check {
+            if let PeerHealthCheckMethod::HttpConnect(_) = health_check.method {
+                return Err(anyhow!(
+                    "peer health check method {} is not supported by this escaper",
+                    health_check.method.as_str()
+                ));
+            }
+        }
 
         let mut disable_ipv4 = true;
         let mut disable_ipv6 = true;
@@ -0,0 +1,121 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use once_cell::sync::OnceCell;
+use yaml_rust::{yaml, Yaml};
+
+static HTTP_CACHE_CONFIG: OnceCell<HttpCacheConfig> = OnceCell::new();
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HttpCacheConfig {
+    pub(crate) memory_capacity: u64,
+    pub(crate) memory_object_max_size: u64,
+    pub(crate) disk_dir: Option<PathBuf>,
+    pub(crate) disk_capacity: u64,
+    pub(crate) object_max_size: u64,
+    pub(crate) heuristic_max_lifetime: Duration,
+}
+
+impl Default for HttpCacheConfig {
+    fn default() -> Self {
+        HttpCacheConfig {
+            memory_capacity: 64 * 1024 * 1024,
+            memory_object_max_size: 1024 * 1024,
+            disk_dir: None,
+            disk_capacity: 1024 * 1024 * 1024,
+            object_max_size: 64 * 1024 * 1024,
+            heuristic_max_lifetime: Duration::from_secs(86400),
+        }
+    }
+}
+
+impl HttpCacheConfig {
+    fn parse(map: &yaml::Hash, conf_dir: &Path) -> anyhow::Result<Self> {
+        let mut config = HttpCacheConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| config.set(k, v, conf_dir))?;
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "memory_capacity" | "memory_size" => {
+                self.memory_capacity = g3_yaml::humanize::as_u64(v)
+                    .context(format!("invalid humanize u64 value for key {k}"))?;
+                Ok(())
+            }
+            "memory_object_max_size" => {
+                self.memory_object_max_size = g3_yaml::humanize::as_u64(v)
+                    .context(format!("invalid humanize u64 value for key {k}"))?;
+                Ok(())
+            }
+            "disk_dir" | "disk_path" => {
+                let dir = g3_yaml::value::as_dir_path(v, conf_dir, true)
+                    .context(format!("invalid dir path value for key {k}"))?;
+                self.disk_dir = Some(dir);
+                Ok(())
+            }
+            "disk_capacity" | "disk_size" => {
+                self.disk_capacity = g3_yaml::humanize::as_u64(v)
+                    .context(format!("invalid humanize u64 value for key {k}"))?;
+                Ok(())
+            }
+            "object_max_size" => {
+                self.object_max_size = g3_yaml::humanize::as_u64(v)
+                    .context(format!("invalid humanize u64 value for key {k}"))?;
+                Ok(())
+            }
+            "heuristic_max_lifetime" => {
+                self.heuristic_max_lifetime = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    fn check(&mut self) -> anyhow::Result<()> {
+        if self.memory_object_max_size > self.object_max_size {
+            self.memory_object_max_size = self.object_max_size;
+        }
+        if self.memory_capacity == 0 && self.disk_dir.is_none() {
+            return Err(anyhow!(
+                "at least one of memory or disk storage should be enabled"
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
+    let config = match v {
+        Yaml::Hash(map) => HttpCacheConfig::parse(map, conf_dir)?,
+        Yaml::Boolean(true) => HttpCacheConfig::default(),
+        Yaml::Boolean(false) | Yaml::Null => return Ok(()),
+        _ => return Err(anyhow!("invalid value type")),
+    };
+    HTTP_CACHE_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("http cache config has already been set"))
+}
+
+pub(crate) fn get() -> Option<&'static HttpCacheConfig> {
+    HTTP_CACHE_CONFIG.get()
+}
@@ -31,6 +31,7 @@ pub use plantuml::plantuml_graph;
 pub(crate) mod audit;
 pub(crate) mod auth;
 pub(crate) mod escaper;
+pub(crate) mod http_cache;
 pub(crate) mod log;
 pub(crate) mod resolver;
 pub(crate) mod server;
@@ -78,7 +79,7 @@ fn reload_doc(map: &yaml::Hash) -> anyhow::Result<()> {
     let conf_dir =
         g3_daemon::opts::config_dir().ok_or_else(|| anyhow!("no valid config dir has been set"))?;
     g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
-        "runtime" | "worker" | "log" | "stat" | "controller" => Ok(()),
+        "runtime" | "worker" | "log" | "stat" | "controller" | "http_cache" => Ok(()),
         "escaper" => escaper::load_all(v, conf_dir),
         "server" => server::load_all(v, conf_dir),
         "resolver" => resolver::load_all(v, conf_dir),
@@ -98,6 +99,7 @@ fn load_doc(map: &yaml::Hash) -> anyhow::Result<()> {
         "log" => log::load(v, conf_dir),
         "stat" => g3_daemon::stat::config::load(v, crate::build::PKG_NAME),
         "controller" => g3_daemon::control::config::load(v),
+        "http_cache" => http_cache::load(v, conf_dir),
         "escaper" => escaper::load_all(v, conf_dir),
         "server" => server::load_all(v, conf_dir),
         "resolver" => resolver::load_all(v, conf_dir),
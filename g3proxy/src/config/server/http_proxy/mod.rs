@@ -24,13 +24,13 @@ use http::HeaderName;
 use yaml_rust::{yaml, Yaml};
 
 use g3_ftp_client::FtpClientConfig;
-use g3_io_ext::LimitedCopyConfig;
+use g3_io_ext::{LimitedCopyConfig, LimitedUdpRelayConfig};
 use g3_types::acl::{AclExactPortRule, AclNetworkRuleBuilder};
 use g3_types::acl_set::AclDstHostRuleSetBuilder;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::net::{
     HttpKeepAliveConfig, HttpServerId, OpensslClientConfigBuilder, RustlsServerConfigBuilder,
-    TcpListenConfig, TcpMiscSockOpts, TcpSockSpeedLimitConfig,
+    SocketBufferConfig, TcpListenConfig, TcpMiscSockOpts, TcpSockSpeedLimitConfig,
 };
 use g3_yaml::YamlDocPosition;
 
@@ -39,6 +39,11 @@ use super::{
     IDLE_CHECK_MAXIMUM_DURATION,
 };
 
+mod pac;
+pub(crate) use pac::HttpProxyPacFileConfig;
+#[cfg(test)]
+pub(crate) use pac::HttpProxyPacSubnetVars;
+
 const SERVER_CONFIG_TYPE: &str = "HttpProxy";
 
 /// collection of timeout config
@@ -70,6 +75,8 @@ pub(crate) struct HttpProxyServerConfig {
     pub(crate) listen: Option<TcpListenConfig>,
     pub(crate) listen_in_worker: bool,
     pub(crate) server_tls_config: Option<RustlsServerConfigBuilder>,
+    pub(crate) enable_h2: bool,
+    pub(crate) h2_max_concurrent_streams: u32,
     pub(crate) client_tls_config: OpensslClientConfigBuilder,
     pub(crate) ftp_client_config: Arc<FtpClientConfig>,
     pub(crate) ingress_net_filter: Option<AclNetworkRuleBuilder>,
@@ -83,6 +90,9 @@ pub(crate) struct HttpProxyServerConfig {
     pub(crate) task_idle_max_count: i32,
     pub(crate) tcp_copy: LimitedCopyConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
+    pub(crate) enable_connect_udp: bool,
+    pub(crate) udp_socket_buffer: SocketBufferConfig,
+    pub(crate) udp_relay: LimitedUdpRelayConfig,
     pub(crate) req_hdr_max_size: usize,
     pub(crate) rsp_hdr_max_size: usize,
     pub(crate) log_uri_max_chars: usize,
@@ -97,6 +107,7 @@ pub(crate) struct HttpProxyServerConfig {
     pub(crate) untrusted_read_limit: Option<TcpSockSpeedLimitConfig>,
     pub(crate) egress_path_selection_header: Option<HeaderName>,
     pub(crate) steal_forwarded_for: bool,
+    pub(crate) pac_file: Option<HttpProxyPacFileConfig>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
 
@@ -112,6 +123,8 @@ impl HttpProxyServerConfig {
             listen: None,
             listen_in_worker: false,
             server_tls_config: None,
+            enable_h2: false,
+            h2_max_concurrent_streams: 100,
             client_tls_config: Default::default(),
             ftp_client_config: Arc::new(Default::default()),
             ingress_net_filter: None,
@@ -125,6 +138,9 @@ impl HttpProxyServerConfig {
             task_idle_max_count: 1,
             tcp_copy: Default::default(),
             tcp_misc_opts: Default::default(),
+            enable_connect_udp: false,
+            udp_socket_buffer: SocketBufferConfig::default(),
+            udp_relay: Default::default(),
             req_hdr_max_size: 65536, // 64KiB
             rsp_hdr_max_size: 65536, // 64KiB
             log_uri_max_chars: 1024,
@@ -139,6 +155,7 @@ impl HttpProxyServerConfig {
             untrusted_read_limit: None,
             egress_path_selection_header: None,
             steal_forwarded_for: false,
+            pac_file: None,
             extra_metrics_tags: None,
         }
     }
@@ -202,6 +219,16 @@ impl HttpProxyServerConfig {
                 self.server_tls_config = Some(builder);
                 Ok(())
             }
+            "enable_h2" | "enable_http2" => {
+                self.enable_h2 = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid boolean value for key {k}"))?;
+                Ok(())
+            }
+            "h2_max_concurrent_streams" => {
+                self.h2_max_concurrent_streams =
+                    g3_yaml::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
+                Ok(())
+            }
             "tls_client" => {
                 let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
                 self.client_tls_config =
@@ -272,6 +299,33 @@ impl HttpProxyServerConfig {
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
                 Ok(())
             }
+            "enable_connect_udp" | "enable_masque_udp" => {
+                self.enable_connect_udp = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid boolean value for key {k}"))?;
+                Ok(())
+            }
+            "udp_socket_buffer" => {
+                self.udp_socket_buffer = g3_yaml::value::as_socket_buffer_config(v)
+                    .context(format!("invalid socket buffer config value for key {k}"))?;
+                Ok(())
+            }
+            "udp_relay_packet_size" => {
+                let packet_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                self.udp_relay.set_packet_size(packet_size);
+                Ok(())
+            }
+            "udp_relay_yield_size" => {
+                let yield_size = g3_yaml::humanize::as_usize(v)
+                    .context(format!("invalid humanize usize value for key {k}"))?;
+                self.udp_relay.set_yield_size(yield_size);
+                Ok(())
+            }
+            "udp_relay_batch_size" => {
+                let batch_size = g3_yaml::value::as_usize(v)?;
+                self.udp_relay.set_batch_size(batch_size);
+                Ok(())
+            }
             "task_idle_check_duration" => {
                 self.task_idle_check_duration = g3_yaml::humanize::as_duration(v)
                     .context(format!("invalid humanize duration value for key {k}"))?;
@@ -366,6 +420,13 @@ impl HttpProxyServerConfig {
                     .context(format!("invalid boolean value for key {k}"))?;
                 Ok(())
             }
+            "pac_file" | "pac" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let pac_file = HttpProxyPacFileConfig::parse(v, lookup_dir)
+                    .context(format!("invalid pac file config value for key {k}"))?;
+                self.pac_file = Some(pac_file);
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -386,6 +447,9 @@ impl HttpProxyServerConfig {
                 "server_id is required as http_forward_mark_upstream is on"
             ));
         }
+        if self.h2_max_concurrent_streams == 0 {
+            return Err(anyhow!("h2_max_concurrent_streams should not be zero"));
+        }
         if self.task_idle_check_duration > IDLE_CHECK_MAXIMUM_DURATION {
             self.task_idle_check_duration = IDLE_CHECK_MAXIMUM_DURATION;
         }
@@ -0,0 +1,264 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use http::{Method, Uri};
+use ip_network::IpNetwork;
+use yaml_rust::Yaml;
+
+const DEFAULT_PAC_PATHS: &[&str] = &["/proxy.pac", "/wpad.dat"];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HttpProxyPacSubnetVars {
+    pub(crate) subnets: BTreeSet<IpNetwork>,
+    pub(crate) vars: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HttpProxyPacFileConfig {
+    pub(crate) paths: BTreeSet<String>,
+    pub(crate) template: String,
+    pub(crate) max_age: Duration,
+    pub(crate) vars: BTreeMap<String, String>,
+    pub(crate) subnet_vars: Vec<HttpProxyPacSubnetVars>,
+}
+
+impl HttpProxyPacFileConfig {
+    pub(super) fn parse(v: &Yaml, lookup_dir: &Path) -> anyhow::Result<Self> {
+        match v {
+            Yaml::Hash(map) => {
+                let mut paths = BTreeSet::new();
+                let mut template_path = None;
+                let mut max_age = Duration::from_secs(300);
+                let mut vars = BTreeMap::new();
+                let mut subnet_vars = Vec::new();
+
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "path" | "paths" => {
+                        let list = g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                            .context(format!("invalid string list value for key {k}"))?;
+                        for path in list {
+                            if !path.starts_with('/') {
+                                return Err(anyhow!("the path {path} should start with '/'"));
+                            }
+                            paths.insert(path);
+                        }
+                        Ok(())
+                    }
+                    "template" | "file" => {
+                        let path = g3_yaml::value::as_file_path(v, lookup_dir, false)
+                            .context(format!("invalid file path value for key {k}"))?;
+                        template_path = Some(path);
+                        Ok(())
+                    }
+                    "max_age" => {
+                        max_age = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    "vars" | "variables" => {
+                        vars = as_vars(v).context(format!("invalid vars value for key {k}"))?;
+                        Ok(())
+                    }
+                    "subnet_vars" | "subnet_variables" => {
+                        subnet_vars = g3_yaml::value::as_list(v, as_subnet_vars)
+                            .context(format!("invalid subnet vars value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+
+                let Some(template_path) = template_path else {
+                    return Err(anyhow!("no template file set"));
+                };
+                let template = load_template(&template_path)?;
+                if paths.is_empty() {
+                    paths.extend(DEFAULT_PAC_PATHS.iter().map(|s| s.to_string()));
+                }
+
+                Ok(HttpProxyPacFileConfig {
+                    paths,
+                    template,
+                    max_age,
+                    vars,
+                    subnet_vars,
+                })
+            }
+            Yaml::String(_) => {
+                let template_path = g3_yaml::value::as_file_path(v, lookup_dir, false)?;
+                let template = load_template(&template_path)?;
+                Ok(HttpProxyPacFileConfig {
+                    paths: DEFAULT_PAC_PATHS.iter().map(|s| s.to_string()).collect(),
+                    template,
+                    max_age: Duration::from_secs(300),
+                    vars: BTreeMap::new(),
+                    subnet_vars: Vec::new(),
+                })
+            }
+            _ => Err(anyhow!("invalid value type")),
+        }
+    }
+
+    /// Check if the request is a direct request to the PAC file
+    pub(crate) fn match_request(&self, method: &Method, uri: &Uri) -> bool {
+        if !matches!(*method, Method::GET | Method::HEAD) {
+            return false;
+        }
+        if uri.scheme().is_some() {
+            // requests to be proxied
+            return false;
+        }
+        self.paths.contains(uri.path())
+    }
+}
+
+fn load_template(path: &Path) -> anyhow::Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read template file {}: {e}", path.display()))
+}
+
+fn as_vars(v: &Yaml) -> anyhow::Result<BTreeMap<String, String>> {
+    if let Yaml::Hash(map) = v {
+        let mut vars = BTreeMap::new();
+        g3_yaml::foreach_kv(map, |k, v| {
+            let value = g3_yaml::value::as_string(v)
+                .context(format!("invalid string value for var {k}"))?;
+            vars.insert(k.to_string(), value);
+            Ok(())
+        })?;
+        Ok(vars)
+    } else {
+        Err(anyhow!("the yaml value should be a 'map'"))
+    }
+}
+
+fn as_subnet_vars(v: &Yaml) -> anyhow::Result<HttpProxyPacSubnetVars> {
+    if let Yaml::Hash(map) = v {
+        let mut subnets = BTreeSet::new();
+        let mut vars = BTreeMap::new();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "subnets" | "subnet" => {
+                let list = g3_yaml::value::as_list(v, g3_yaml::value::as_ip_network)
+                    .context(format!("invalid ip network list value for key {k}"))?;
+                subnets.extend(list);
+                Ok(())
+            }
+            "vars" | "variables" => {
+                vars = as_vars(v).context(format!("invalid vars value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        if subnets.is_empty() {
+            return Err(anyhow!("no subnet set"));
+        }
+        Ok(HttpProxyPacSubnetVars { subnets, vars })
+    } else {
+        Err(anyhow!("the yaml value should be a 'map'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    const TEMPLATE: &str = "function FindProxyForURL(url, host) { return \"{{proxy}}\"; }";
+
+    fn parse_yaml(s: &str, template_file: &str) -> anyhow::Result<HttpProxyPacFileConfig> {
+        let lookup_dir = std::env::temp_dir();
+        std::fs::write(lookup_dir.join(template_file), TEMPLATE).unwrap();
+        let doc = YamlLoader::load_from_str(s).unwrap();
+        HttpProxyPacFileConfig::parse(&doc[0], &lookup_dir)
+    }
+
+    #[test]
+    fn parse_map() {
+        let config = parse_yaml(
+            r#"
+            template: g3proxy-test-pac-map.js
+            paths:
+              - /proxy.pac
+              - /internal.pac
+            max_age: 1m
+            vars:
+              proxy: PROXY 192.0.2.1:3128
+            subnet_vars:
+              - subnets: 10.0.0.0/8
+                vars:
+                  proxy: PROXY 10.0.0.1:3128
+            "#,
+            "g3proxy-test-pac-map.js",
+        )
+        .unwrap();
+        assert_eq!(config.template, TEMPLATE);
+        assert_eq!(config.paths.len(), 2);
+        assert!(config.paths.contains("/internal.pac"));
+        assert_eq!(config.max_age, Duration::from_secs(60));
+        assert_eq!(config.vars.get("proxy").unwrap(), "PROXY 192.0.2.1:3128");
+        assert_eq!(config.subnet_vars.len(), 1);
+        assert_eq!(
+            config.subnet_vars[0].vars.get("proxy").unwrap(),
+            "PROXY 10.0.0.1:3128"
+        );
+
+        assert!(parse_yaml(
+            r#"
+            template: g3proxy-test-pac-map.js
+            path: proxy.pac
+            "#,
+            "g3proxy-test-pac-map.js",
+        )
+        .is_err());
+        assert!(parse_yaml("max_age: 1m", "g3proxy-test-pac-map.js").is_err());
+    }
+
+    #[test]
+    fn parse_default_paths() {
+        let config =
+            parse_yaml("g3proxy-test-pac-default.js", "g3proxy-test-pac-default.js").unwrap();
+        assert_eq!(config.template, TEMPLATE);
+        assert_eq!(config.max_age, Duration::from_secs(300));
+        assert!(config.paths.iter().eq(["/proxy.pac", "/wpad.dat"]));
+
+        let config = parse_yaml(
+            "template: g3proxy-test-pac-default.js",
+            "g3proxy-test-pac-default.js",
+        )
+        .unwrap();
+        assert!(config.paths.iter().eq(["/proxy.pac", "/wpad.dat"]));
+    }
+
+    #[test]
+    fn match_request() {
+        let config = parse_yaml("g3proxy-test-pac-match.js", "g3proxy-test-pac-match.js").unwrap();
+
+        let uri = Uri::from_static("/proxy.pac");
+        assert!(config.match_request(&Method::GET, &uri));
+        assert!(config.match_request(&Method::HEAD, &uri));
+        assert!(!config.match_request(&Method::POST, &uri));
+        assert!(config.match_request(&Method::GET, &Uri::from_static("/wpad.dat")));
+        assert!(!config.match_request(&Method::GET, &Uri::from_static("/other.pac")));
+
+        // absolute-form requests should be proxied
+        let uri = Uri::from_static("http://192.0.2.1:3128/proxy.pac");
+        assert!(!config.match_request(&Method::GET, &uri));
+    }
+}
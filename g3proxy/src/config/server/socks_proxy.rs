@@ -47,6 +47,8 @@ pub(crate) struct SocksProxyServerTimeoutConfig {
     pub(crate) negotiation: Duration,
     /// only for udp associate: client must send first udp packet before this timeout
     pub(crate) udp_client_initial: Duration,
+    /// only for tcp bind: the remote peer must connect in before this timeout
+    pub(crate) tcp_bind_accept: Duration,
 }
 
 impl Default for SocksProxyServerTimeoutConfig {
@@ -55,6 +57,7 @@ impl Default for SocksProxyServerTimeoutConfig {
         SocksProxyServerTimeoutConfig {
             negotiation: Duration::from_secs(4),
             udp_client_initial: Duration::from_secs(30),
+            tcp_bind_accept: Duration::from_secs(60),
         }
     }
 }
@@ -74,6 +77,10 @@ pub(crate) struct SocksProxyServerConfig {
     pub(crate) udp_bind6: Vec<IpAddr>,
     pub(crate) udp_bind_port_range: Option<PortRange>,
     pub(crate) udp_socket_buffer: SocketBufferConfig,
+    pub(crate) enable_tcp_bind: bool,
+    pub(crate) tcp_bind4: Vec<IpAddr>,
+    pub(crate) tcp_bind6: Vec<IpAddr>,
+    pub(crate) tcp_bind_port_range: Option<PortRange>,
     pub(crate) ingress_net_filter: Option<AclNetworkRuleBuilder>,
     pub(crate) dst_host_filter: Option<AclDstHostRuleSetBuilder>,
     pub(crate) dst_port_filter: Option<AclExactPortRule>,
@@ -106,6 +113,10 @@ impl SocksProxyServerConfig {
             udp_bind6: Vec::new(),
             udp_bind_port_range: None,
             udp_socket_buffer: SocketBufferConfig::default(),
+            enable_tcp_bind: false,
+            tcp_bind4: Vec::new(),
+            tcp_bind6: Vec::new(),
+            tcp_bind_port_range: None,
             ingress_net_filter: None,
             dst_host_filter: None,
             dst_port_filter: None,
@@ -204,6 +215,30 @@ impl SocksProxyServerConfig {
                     .context(format!("invalid socket buffer config value for key {k}"))?;
                 Ok(())
             }
+            "enable_tcp_bind" | "tcp_bind_enabled" => {
+                self.enable_tcp_bind = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "tcp_bind_ipv4" => {
+                self.tcp_bind4 = g3_yaml::value::as_list(v, |v| {
+                    let ip4 = g3_yaml::value::as_ipv4addr(v)?;
+                    Ok(IpAddr::V4(ip4))
+                })?;
+                Ok(())
+            }
+            "tcp_bind_ipv6" => {
+                self.tcp_bind6 = g3_yaml::value::as_list(v, |v| {
+                    let ip6 = g3_yaml::value::as_ipv6addr(v)?;
+                    Ok(IpAddr::V6(ip6))
+                })?;
+                Ok(())
+            }
+            "tcp_bind_port_range" => {
+                let range = g3_yaml::value::as_port_range(v)
+                    .context(format!("invalid port range value for key {k}"))?;
+                self.tcp_bind_port_range = Some(range);
+                Ok(())
+            }
             "ingress_network_filter" | "ingress_net_filter" => {
                 let filter = g3_yaml::value::acl::as_ingress_network_rule_builder(v).context(
                     format!("invalid ingress network acl rule value for key {k}"),
@@ -285,6 +320,11 @@ impl SocksProxyServerConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "tcp_bind_accept_timeout" => {
+                self.timeout.tcp_bind_accept = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
             "task_idle_check_duration" => {
                 self.task_idle_check_duration = g3_yaml::humanize::as_duration(v)
                     .context(format!("invalid humanize duration value for key {k}"))?;
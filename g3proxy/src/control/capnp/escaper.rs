@@ -51,4 +51,31 @@ impl escaper_control::Server for EscaperControlImpl {
             Ok(())
         })
     }
+
+    fn peer_health(
+        &mut self,
+        _params: escaper_control::PeerHealthParams,
+        mut results: escaper_control::PeerHealthResults,
+    ) -> Promise<(), capnp::Error> {
+        let Some(peers) = self.escaper.get_peer_health() else {
+            let mut ev = results.get().init_result().init_err();
+            ev.set_code(-1);
+            ev.set_reason("peer health check is not enabled on this escaper");
+            return Promise::ok(());
+        };
+
+        let mut builder = results.get().init_result().init_peers(peers.len() as u32);
+        for (i, peer) in peers.into_iter().enumerate() {
+            let mut peer_builder = builder.reborrow().get(i as u32);
+            peer_builder.set_addr(peer.addr.to_string().as_str());
+            peer_builder.set_weight(peer.weight);
+            peer_builder.set_healthy(peer.healthy);
+            peer_builder.set_consecutive_success(peer.consecutive_success as u64);
+            peer_builder.set_consecutive_failure(peer.consecutive_failure as u64);
+            if let Some(e) = peer.last_error {
+                peer_builder.set_last_error(e.as_str());
+            }
+        }
+        Promise::ok(())
+    }
 }
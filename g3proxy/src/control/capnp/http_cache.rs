@@ -0,0 +1,97 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use capnp::capability::Promise;
+use capnp_rpc::pry;
+
+use g3proxy_proto::http_cache_capnp::http_cache_control;
+
+use super::set_operation_result;
+use crate::module::http_cache::{self, HttpCache};
+
+pub(super) struct HttpCacheControlImpl {
+    cache: Arc<HttpCache>,
+}
+
+impl HttpCacheControlImpl {
+    pub(super) fn new_client() -> anyhow::Result<http_cache_control::Client> {
+        let cache =
+            http_cache::get_global_cache().ok_or_else(|| anyhow!("http cache is not enabled"))?;
+        Ok(capnp_rpc::new_client(HttpCacheControlImpl {
+            cache: cache.clone(),
+        }))
+    }
+}
+
+impl http_cache_control::Server for HttpCacheControlImpl {
+    fn status(
+        &mut self,
+        _params: http_cache_control::StatusParams,
+        mut results: http_cache_control::StatusResults,
+    ) -> Promise<(), capnp::Error> {
+        let usage = self.cache.usage();
+        let stats = self.cache.stats().snapshot();
+
+        let mut builder = results.get().init_stats();
+        builder.set_memory_capacity(usage.memory_capacity);
+        builder.set_memory_used(usage.memory_used);
+        builder.set_memory_entries(usage.memory_entries as u64);
+        builder.set_disk_capacity(usage.disk_capacity);
+        builder.set_disk_used(usage.disk_used);
+        builder.set_disk_entries(usage.disk_entries as u64);
+        builder.set_hit(stats.hit);
+        builder.set_miss(stats.miss);
+        builder.set_revalidated(stats.revalidated);
+        builder.set_expired(stats.expired);
+        builder.set_stored(stats.stored);
+        builder.set_evicted(stats.evicted);
+        Promise::ok(())
+    }
+
+    fn set_capacity(
+        &mut self,
+        params: http_cache_control::SetCapacityParams,
+        mut results: http_cache_control::SetCapacityResults,
+    ) -> Promise<(), capnp::Error> {
+        let params = pry!(params.get());
+        let r = self
+            .cache
+            .set_capacity(params.get_memory(), params.get_disk());
+        set_operation_result(results.get().init_result(), r);
+        Promise::ok(())
+    }
+
+    fn purge(
+        &mut self,
+        params: http_cache_control::PurgeParams,
+        mut results: http_cache_control::PurgeResults,
+    ) -> Promise<(), capnp::Error> {
+        let url = pry!(pry!(pry!(params.get()).get_url()).to_str());
+        let count = if url.is_empty() {
+            self.cache.purge(None)
+        } else {
+            self.cache.purge(Some(url))
+        };
+        results
+            .get()
+            .init_result()
+            .set_ok(format!("purged {count} entries").as_str());
+        Promise::ok(())
+    }
+}
@@ -21,6 +21,7 @@ use common::set_operation_result;
 mod proc;
 
 mod escaper;
+mod http_cache;
 mod resolver;
 mod server;
 mod user_group;
@@ -20,6 +20,7 @@ use capnp_rpc::pry;
 use g3_types::metrics::MetricsName;
 
 use g3proxy_proto::escaper_capnp::escaper_control;
+use g3proxy_proto::http_cache_capnp::http_cache_control;
 use g3proxy_proto::proc_capnp::proc_control;
 use g3proxy_proto::resolver_capnp::resolver_control;
 use g3proxy_proto::server_capnp::server_control;
@@ -256,6 +257,18 @@ impl proc_control::Server for ProcControlImpl {
         results.get().init_result().set_ok("success");
         Promise::ok(())
     }
+
+    fn get_http_cache(
+        &mut self,
+        _params: proc_control::GetHttpCacheParams,
+        mut results: proc_control::GetHttpCacheResults,
+    ) -> Promise<(), capnp::Error> {
+        pry!(set_fetch_result::<http_cache_control::Owned>(
+            results.get().init_http_cache(),
+            super::http_cache::HttpCacheControlImpl::new_client(),
+        ));
+        Promise::ok(())
+    }
 }
 
 fn set_fetch_result<'a, T>(
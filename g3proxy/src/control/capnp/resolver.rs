@@ -17,13 +17,16 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use anyhow::anyhow;
+use base64::prelude::*;
 use capnp::capability::Promise;
 use capnp_rpc::pry;
 
+use g3_resolver::{ResolveJob, SrvRecord, SvcbRecord, TxtRecord};
 use g3_types::metrics::MetricsName;
 use g3_types::resolve::{QueryStrategy as ResolveQueryStrategy, ResolveStrategy};
 
-use g3proxy_proto::resolver_capnp::{resolver_control, QueryStrategy};
+use g3proxy_proto::resolver_capnp::{resolver_control, QueryStrategy, QueryType};
 
 use crate::resolve::{ArcIntegratedResolverHandle, HappyEyeballsResolveJob};
 
@@ -52,8 +55,30 @@ impl resolver_control::Server for ResolverControlImpl {
         let resolution_delay = params.get_resolution_delay() as u64;
         let query_strategy = pry!(params.get_strategy());
         let resolver_strategy = get_resolver_strategy(query_strategy);
+        let query_type = pry!(params.get_query_type());
         let resolver_handler = Arc::clone(&self.resolver_handler);
 
+        if query_type != QueryType::Ip {
+            return Promise::from_future(async move {
+                match query_record(&resolver_handler, Arc::from(domain), query_type).await {
+                    Ok(records) => {
+                        let mut records_builder = results
+                            .get()
+                            .init_result()
+                            .init_record(records.len() as u32);
+                        for (i, r) in records.iter().enumerate() {
+                            records_builder.set(i as u32, r.as_str());
+                        }
+                    }
+                    Err(e) => results
+                        .get()
+                        .init_result()
+                        .set_err(format!("{e:?}").as_str()),
+                }
+                Ok(())
+            });
+        }
+
         Promise::from_future(async move {
             let mut job = match HappyEyeballsResolveJob::new_dyn(
                 resolver_strategy,
@@ -101,3 +126,86 @@ fn get_resolver_strategy(q: QueryStrategy) -> ResolveStrategy {
         pick: Default::default(),
     }
 }
+
+async fn query_record(
+    resolver_handler: &ArcIntegratedResolverHandle,
+    domain: Arc<str>,
+    query_type: QueryType,
+) -> anyhow::Result<Vec<String>> {
+    let Some(handle) = resolver_handler.clone_inner() else {
+        return Err(anyhow!("no running resolver found"));
+    };
+    let records = match query_type {
+        QueryType::Ip => unreachable!(),
+        QueryType::Srv => recv_records(handle.get_srv(domain)?)
+            .await?
+            .iter()
+            .map(format_srv)
+            .collect(),
+        QueryType::Https => recv_records(handle.get_https(domain)?)
+            .await?
+            .iter()
+            .map(format_svcb)
+            .collect(),
+        QueryType::Svcb => recv_records(handle.get_svcb(domain)?)
+            .await?
+            .iter()
+            .map(format_svcb)
+            .collect(),
+        QueryType::Txt => recv_records(handle.get_txt(domain)?)
+            .await?
+            .iter()
+            .map(TxtRecord::to_text)
+            .collect(),
+    };
+    Ok(records)
+}
+
+async fn recv_records<T: Clone>(mut job: ResolveJob<T>) -> anyhow::Result<Vec<T>> {
+    let (record, _) = job.recv().await?;
+    match &record.result {
+        Ok(v) => Ok(v.clone()),
+        Err(e) => Err(anyhow!("{e}")),
+    }
+}
+
+fn format_srv(r: &SrvRecord) -> String {
+    format!("{} {} {} {}.", r.priority, r.weight, r.port, r.target)
+}
+
+fn format_svcb(r: &SvcbRecord) -> String {
+    let mut s = format!("{} {}.", r.priority, r.target);
+    if !r.alpn.is_empty() {
+        s.push_str(" alpn=");
+        s.push_str(&r.alpn.join(","));
+    }
+    if r.no_default_alpn {
+        s.push_str(" no-default-alpn");
+    }
+    if let Some(port) = r.port {
+        s.push_str(&format!(" port={port}"));
+    }
+    if !r.ipv4_hint.is_empty() {
+        let ips = r
+            .ipv4_hint
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>();
+        s.push_str(" ipv4hint=");
+        s.push_str(&ips.join(","));
+    }
+    if let Some(ech) = &r.ech_config {
+        s.push_str(" ech=");
+        s.push_str(&BASE64_STANDARD.encode(ech));
+    }
+    if !r.ipv6_hint.is_empty() {
+        let ips = r
+            .ipv6_hint
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>();
+        s.push_str(" ipv6hint=");
+        s.push_str(&ips.join(","));
+    }
+    s
+}
@@ -78,4 +78,15 @@ impl user_group_control::Server for UserGroupControlImpl {
             Ok(())
         })
     }
+
+    fn query_user_quota(
+        &mut self,
+        params: user_group_control::QueryUserQuotaParams,
+        mut results: user_group_control::QueryUserQuotaResults,
+    ) -> Promise<(), capnp::Error> {
+        let user = pry!(pry!(pry!(params.get()).get_user()).to_string());
+        let r = self.user_group.query_user_quota(&user);
+        set_operation_result(results.get().init_result(), r);
+        Promise::ok(())
+    }
 }
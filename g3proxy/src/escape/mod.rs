@@ -48,13 +48,16 @@ pub(crate) use registry::{foreach as foreach_escaper, get_names, get_or_insert_d
 mod stats;
 pub(crate) use stats::{
     ArcEscaperInternalStats, ArcEscaperStats, EscaperForbiddenSnapshot, EscaperForbiddenStats,
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpStats, EscaperUdpStats,
-    RouteEscaperSnapshot, RouteEscaperStats,
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerHealthSnapshot, EscaperStats,
+    EscaperTcpStats, EscaperUdpStats, RouteEscaperSnapshot, RouteEscaperStats,
 };
 
 mod egress_path;
 pub(crate) use egress_path::EgressPathSelection;
 
+mod peer_health;
+pub(crate) use peer_health::{PeerHealthProbe, PeerHealthSet, PeerHealthSnapshot};
+
 mod direct_fixed;
 mod direct_float;
 mod divert_tcp;
@@ -151,6 +154,10 @@ pub(crate) trait Escaper: EscaperInternal {
     fn ref_route_stats(&self) -> Option<&Arc<RouteEscaperStats>> {
         None
     }
+    /// get the health status of next proxy peers, if health check is enabled
+    fn get_peer_health(&self) -> Option<Vec<PeerHealthSnapshot>> {
+        None
+    }
 
     async fn publish(&self, data: String) -> anyhow::Result<()>;
 
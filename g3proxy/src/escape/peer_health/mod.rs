@@ -0,0 +1,196 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use arc_swap::{ArcSwap, Guard};
+use futures_util::future::{AbortHandle, Abortable};
+use log::{info, warn};
+
+use g3_types::collection::{
+    build_available_selection, RiseFallHealth, SelectiveVec, SelectiveVecBuilder,
+};
+use g3_types::metrics::MetricsName;
+use g3_types::net::{UpstreamAddr, WeightedUpstreamAddr};
+
+use crate::config::escaper::PeerHealthCheckConfig;
+
+mod probe;
+pub(crate) use probe::PeerHealthProbe;
+
+pub(crate) struct PeerHealthSnapshot {
+    pub(crate) addr: UpstreamAddr,
+    pub(crate) weight: f64,
+    pub(crate) healthy: bool,
+    pub(crate) consecutive_success: usize,
+    pub(crate) consecutive_failure: usize,
+    pub(crate) last_error: Option<String>,
+}
+
+struct PeerHealthState {
+    node: WeightedUpstreamAddr,
+    health: RiseFallHealth,
+    last_error: Mutex<Option<String>>,
+}
+
+impl PeerHealthState {
+    fn new(node: WeightedUpstreamAddr) -> Self {
+        PeerHealthState {
+            node,
+            health: RiseFallHealth::default(),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    /// return true if the health status changed
+    fn add_result(&self, result: anyhow::Result<()>, rise: usize, fall: usize) -> bool {
+        if let Err(e) = &result {
+            let mut last_error = self.last_error.lock().unwrap();
+            *last_error = Some(format!("{e:?}"));
+        }
+        self.health.add_result(result.is_ok(), rise, fall)
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.health.is_healthy()
+    }
+
+    fn snapshot(&self) -> PeerHealthSnapshot {
+        PeerHealthSnapshot {
+            addr: self.node.inner().clone(),
+            weight: self.node.weight(),
+            healthy: self.is_healthy(),
+            consecutive_success: self.health.consecutive_success(),
+            consecutive_failure: self.health.consecutive_failure(),
+            last_error: self.last_error.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// The next proxy peers of a proxy escaper, with their health status.
+///
+/// Only healthy peers will be present in the selective vec used for selection,
+/// and all peers will be used if all of them are unhealthy.
+pub(crate) struct PeerHealthSet {
+    peers: Vec<PeerHealthState>,
+    selection: ArcSwap<SelectiveVec<WeightedUpstreamAddr>>,
+}
+
+impl PeerHealthSet {
+    pub(crate) fn new(nodes: &[WeightedUpstreamAddr]) -> anyhow::Result<Self> {
+        let mut nodes_builder = SelectiveVecBuilder::new();
+        for node in nodes {
+            nodes_builder.insert(node.clone());
+        }
+        let selection = nodes_builder
+            .build()
+            .ok_or_else(|| anyhow!("no next proxy node set"))?;
+
+        let peers = nodes
+            .iter()
+            .map(|node| PeerHealthState::new(node.clone()))
+            .collect();
+        Ok(PeerHealthSet {
+            peers,
+            selection: ArcSwap::new(Arc::new(selection)),
+        })
+    }
+
+    #[inline]
+    pub(crate) fn load_selection(&self) -> Guard<Arc<SelectiveVec<WeightedUpstreamAddr>>> {
+        self.selection.load()
+    }
+
+    pub(crate) fn healthy_count(&self) -> usize {
+        self.peers.iter().filter(|p| p.is_healthy()).count()
+    }
+
+    pub(crate) fn total_count(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<PeerHealthSnapshot> {
+        self.peers.iter().map(|p| p.snapshot()).collect()
+    }
+
+    fn rebuild_selection(&self) {
+        let nodes = self
+            .peers
+            .iter()
+            .map(|peer| (peer.node.clone(), peer.is_healthy()));
+        if let Some(selection) = build_available_selection(nodes) {
+            self.selection.store(Arc::new(selection));
+        }
+    }
+
+    async fn check_all(
+        &self,
+        escaper: &MetricsName,
+        config: &PeerHealthCheckConfig,
+        probe: &PeerHealthProbe,
+    ) {
+        let results = futures_util::future::join_all(self.peers.iter().map(|peer| async {
+            match tokio::time::timeout(config.timeout, probe.probe(peer.node.inner())).await {
+                Ok(r) => r,
+                Err(_) => Err(anyhow!("timed out")),
+            }
+        }))
+        .await;
+
+        let mut changed = false;
+        for (peer, result) in self.peers.iter().zip(results) {
+            if peer.add_result(result, config.rise, config.fall) {
+                changed = true;
+                if peer.is_healthy() {
+                    info!(
+                        "escaper {escaper}: next proxy peer {} is healthy again",
+                        peer.node.inner()
+                    );
+                } else {
+                    warn!(
+                        "escaper {escaper}: next proxy peer {} is unhealthy and will be removed from selection",
+                        peer.node.inner()
+                    );
+                }
+            }
+        }
+        if changed {
+            self.rebuild_selection();
+        }
+    }
+
+    pub(crate) fn spawn_check_job(
+        self: &Arc<Self>,
+        escaper: MetricsName,
+        config: PeerHealthCheckConfig,
+        probe: PeerHealthProbe,
+    ) -> AbortHandle {
+        let peers = Arc::clone(self);
+        let f = async move {
+            let mut interval = tokio::time::interval(config.interval);
+            loop {
+                interval.tick().await;
+                peers.check_all(&escaper, &config, &probe).await;
+            }
+        };
+
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let future = Abortable::new(f, abort_registration);
+        tokio::spawn(future);
+        abort_handle
+    }
+}
@@ -0,0 +1,210 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::future::poll_fn;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use g3_http::connect::{HttpConnectRequest, HttpConnectResponse};
+use g3_openssl::SslConnector;
+use g3_types::net::{
+    Host, OpensslClientConfig, ProxyProtocolEncoder, ProxyProtocolVersion, SocksAuth,
+    TcpKeepAliveConfig, TcpMiscSockOpts, UpstreamAddr,
+};
+use g3_types::resolve::ResolveStrategy;
+
+use crate::config::escaper::PeerHealthCheckMethod;
+use crate::resolve::{ArcIntegratedResolverHandle, ArriveFirstResolveJob};
+
+struct PeerTlsProbeConfig {
+    config: OpensslClientConfig,
+    tls_name: Option<Host>,
+}
+
+/// The probe used to check the health of next proxy peers.
+///
+/// It should only hold the config needed by the probe,
+/// so the escaper itself won't be referenced by the check job.
+pub(crate) struct PeerHealthProbe {
+    method: PeerHealthCheckMethod,
+    bind_v4: Option<Ipv4Addr>,
+    bind_v6: Option<Ipv6Addr>,
+    resolver: Option<(ArcIntegratedResolverHandle, ResolveStrategy)>,
+    tls: Option<PeerTlsProbeConfig>,
+    use_proxy_protocol: Option<ProxyProtocolVersion>,
+    http_connect_headers: Vec<String>,
+    http_connect_rsp_hdr_max_size: usize,
+    socks_auth: SocksAuth,
+}
+
+impl PeerHealthProbe {
+    pub(crate) fn new(method: PeerHealthCheckMethod) -> Self {
+        PeerHealthProbe {
+            method,
+            bind_v4: None,
+            bind_v6: None,
+            resolver: None,
+            tls: None,
+            use_proxy_protocol: None,
+            http_connect_headers: Vec::new(),
+            http_connect_rsp_hdr_max_size: 4096,
+            socks_auth: SocksAuth::None,
+        }
+    }
+
+    pub(crate) fn set_bind(&mut self, bind_v4: Option<Ipv4Addr>, bind_v6: Option<Ipv6Addr>) {
+        self.bind_v4 = bind_v4;
+        self.bind_v6 = bind_v6;
+    }
+
+    pub(crate) fn set_resolver(
+        &mut self,
+        handle: Option<ArcIntegratedResolverHandle>,
+        strategy: ResolveStrategy,
+    ) {
+        self.resolver = handle.map(|h| (h, strategy));
+    }
+
+    pub(crate) fn set_tls(&mut self, config: OpensslClientConfig, tls_name: Option<Host>) {
+        self.tls = Some(PeerTlsProbeConfig { config, tls_name });
+    }
+
+    pub(crate) fn set_proxy_protocol(&mut self, version: Option<ProxyProtocolVersion>) {
+        self.use_proxy_protocol = version;
+    }
+
+    pub(crate) fn set_http_connect(&mut self, headers: &[String], rsp_hdr_max_size: usize) {
+        self.http_connect_headers = headers.to_vec();
+        self.http_connect_rsp_hdr_max_size = rsp_hdr_max_size;
+    }
+
+    pub(crate) fn set_socks_auth(&mut self, auth: SocksAuth) {
+        self.socks_auth = auth;
+    }
+
+    async fn resolve(&self, domain: &str) -> anyhow::Result<IpAddr> {
+        let Some((handle, strategy)) = &self.resolver else {
+            return Err(anyhow!("no resolver set"));
+        };
+        let mut job = ArriveFirstResolveJob::new(handle, *strategy, Arc::from(domain))
+            .map_err(|e| anyhow!("failed to create resolve job: {e}"))?;
+        poll_fn(|cx| job.poll_best_addr(cx))
+            .await
+            .map_err(|e| anyhow!("failed to resolve {domain}: {e}"))
+    }
+
+    async fn tcp_connect(&self, peer: &UpstreamAddr) -> anyhow::Result<TcpStream> {
+        let ip = match peer.host() {
+            Host::Ip(ip) => *ip,
+            Host::Domain(domain) => self.resolve(domain).await?,
+        };
+        let bind_ip = match ip {
+            IpAddr::V4(_) => self.bind_v4.map(IpAddr::V4),
+            IpAddr::V6(_) => self.bind_v6.map(IpAddr::V6),
+        };
+        let sock = g3_socket::tcp::new_socket_to(
+            ip,
+            bind_ip,
+            &TcpKeepAliveConfig::default(),
+            &TcpMiscSockOpts::default(),
+            true,
+        )
+        .context("failed to setup socket")?;
+        let mut stream = sock
+            .connect(SocketAddr::new(ip, peer.port()))
+            .await
+            .context("failed to connect")?;
+
+        if let Some(version) = self.use_proxy_protocol {
+            let local_addr = stream.local_addr()?;
+            let peer_addr = stream.peer_addr()?;
+            let mut encoder = ProxyProtocolEncoder::new(version);
+            let bytes = encoder
+                .encode_tcp(local_addr, peer_addr)
+                .context("failed to encode PROXY protocol header")?;
+            stream
+                .write_all(bytes)
+                .await
+                .context("failed to send PROXY protocol header")?;
+        }
+
+        Ok(stream)
+    }
+
+    pub(crate) async fn probe(&self, peer: &UpstreamAddr) -> anyhow::Result<()> {
+        let stream = self.tcp_connect(peer).await?;
+
+        if matches!(self.method, PeerHealthCheckMethod::TcpConnect) {
+            return Ok(());
+        }
+
+        if let Some(tls) = &self.tls {
+            let tls_name = tls.tls_name.as_ref().unwrap_or_else(|| peer.host());
+            let ssl = tls.config.build_ssl(tls_name, peer.port())?;
+            let connector = SslConnector::new(ssl, stream)
+                .map_err(|e| anyhow!("failed to create tls connector: {e}"))?;
+            let stream = connector
+                .connect()
+                .await
+                .map_err(|e| anyhow!("tls handshake failed: {e}"))?;
+            self.negotiate(stream).await
+        } else {
+            self.negotiate(stream).await
+        }
+    }
+
+    async fn negotiate<S>(&self, stream: S) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (mut r, mut w) = tokio::io::split(stream);
+        match &self.method {
+            PeerHealthCheckMethod::TcpConnect => Ok(()),
+            PeerHealthCheckMethod::HttpConnect(target) => {
+                let req = HttpConnectRequest::new(target, &self.http_connect_headers);
+                req.send(&mut w)
+                    .await
+                    .context("failed to send CONNECT request")?;
+                let mut r = BufReader::new(r);
+                HttpConnectResponse::recv(&mut r, self.http_connect_rsp_hdr_max_size)
+                    .await
+                    .context("CONNECT request failed")?;
+                Ok(())
+            }
+            PeerHealthCheckMethod::Socks5Handshake(target) => {
+                if let Some(target) = target {
+                    g3_socks::v5::client::socks5_connect_to(
+                        &mut r,
+                        &mut w,
+                        &self.socks_auth,
+                        target,
+                    )
+                    .await
+                    .context("socks5 connect failed")?;
+                } else {
+                    g3_socks::v5::client::socks5_login(&mut r, &mut w, &self.socks_auth)
+                        .await
+                        .context("socks5 login failed")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
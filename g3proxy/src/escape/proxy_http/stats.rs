@@ -23,13 +23,17 @@ use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot};
 
-use crate::escape::{EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpStats};
+use crate::escape::{
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerHealthSnapshot, EscaperStats,
+    EscaperTcpStats, PeerHealthSet,
+};
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
 
 pub(crate) struct ProxyHttpEscaperStats {
     name: MetricsName,
     id: StatId,
     extra_metrics_tags: Arc<ArcSwapOption<StaticMetricsTags>>,
+    peer_health: ArcSwapOption<PeerHealthSet>,
     pub(super) interface: EscaperInterfaceStats,
     pub(super) tcp: EscaperTcpStats,
 }
@@ -40,6 +44,7 @@ impl ProxyHttpEscaperStats {
             name: name.clone(),
             id: StatId::new(),
             extra_metrics_tags: Arc::new(ArcSwapOption::new(None)),
+            peer_health: ArcSwapOption::new(None),
             interface: EscaperInterfaceStats::default(),
             tcp: EscaperTcpStats::default(),
         }
@@ -48,6 +53,10 @@ impl ProxyHttpEscaperStats {
     pub(super) fn set_extra_tags(&self, tags: Option<Arc<StaticMetricsTags>>) {
         self.extra_metrics_tags.store(tags);
     }
+
+    pub(super) fn set_peer_health(&self, peer_health: Option<Arc<PeerHealthSet>>) {
+        self.peer_health.store(peer_health);
+    }
 }
 
 impl EscaperInternalStats for ProxyHttpEscaperStats {
@@ -94,6 +103,17 @@ impl EscaperStats for ProxyHttpEscaperStats {
     fn tcp_io_snapshot(&self) -> Option<TcpIoSnapshot> {
         Some(self.tcp.io.snapshot())
     }
+
+    fn peer_health_snapshot(&self) -> Option<EscaperPeerHealthSnapshot> {
+        let peer_health = self.peer_health.load();
+        peer_health.as_ref().map(|set| {
+            let healthy = set.healthy_count();
+            EscaperPeerHealthSnapshot {
+                healthy,
+                unhealthy: set.total_count() - healthy,
+            }
+        })
+    }
 }
 
 impl LimitedReaderStats for ProxyHttpEscaperStats {
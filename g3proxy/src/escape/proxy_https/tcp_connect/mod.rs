@@ -268,9 +268,7 @@ impl ProxyHttpsEscaper {
         tcp_notes: &'a mut TcpConnectTaskNotes,
         task_notes: &'a ServerTaskNotes,
     ) -> Result<(UpstreamAddr, TcpStream), TcpConnectError> {
-        let peer_proxy = self
-            .get_next_proxy(task_notes, tcp_notes.upstream.host())
-            .clone();
+        let peer_proxy = self.get_next_proxy(task_notes, tcp_notes.upstream.host());
 
         let stream = match peer_proxy.host() {
             Host::Ip(ip) => {
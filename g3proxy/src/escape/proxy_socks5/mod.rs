@@ -19,17 +19,17 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
+use futures_util::future::AbortHandle;
 use slog::Logger;
 
 use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
 use g3_resolver::{ResolveError, ResolveLocalError};
-use g3_types::collection::{SelectiveVec, SelectiveVecBuilder};
 use g3_types::metrics::MetricsName;
-use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr, WeightedUpstreamAddr};
+use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr};
 
 use super::{
     ArcEscaper, ArcEscaperInternalStats, ArcEscaperStats, Escaper, EscaperExt, EscaperInternal,
-    EscaperStats,
+    EscaperStats, PeerHealthProbe, PeerHealthSet, PeerHealthSnapshot,
 };
 use crate::auth::UserUpstreamTrafficStats;
 use crate::config::escaper::proxy_socks5::ProxySocks5EscaperConfig;
@@ -65,23 +65,26 @@ pub(crate) mod udp_relay;
 pub(super) struct ProxySocks5Escaper {
     config: Arc<ProxySocks5EscaperConfig>,
     stats: Arc<ProxySocks5EscaperStats>,
-    proxy_nodes: SelectiveVec<WeightedUpstreamAddr>,
+    proxy_nodes: Arc<PeerHealthSet>,
+    peer_health_job: Option<AbortHandle>,
     resolver_handle: Option<ArcIntegratedResolverHandle>,
     escape_logger: Logger,
 }
 
+impl Drop for ProxySocks5Escaper {
+    fn drop(&mut self) {
+        if let Some(handler) = self.peer_health_job.take() {
+            handler.abort();
+        }
+    }
+}
+
 impl ProxySocks5Escaper {
     fn new_obj(
         config: ProxySocks5EscaperConfig,
         stats: Arc<ProxySocks5EscaperStats>,
     ) -> anyhow::Result<ArcEscaper> {
-        let mut nodes_builder = SelectiveVecBuilder::new();
-        for node in &config.proxy_nodes {
-            nodes_builder.insert(node.clone());
-        }
-        let proxy_nodes = nodes_builder
-            .build()
-            .ok_or_else(|| anyhow!("no next proxy node set"))?;
+        let proxy_nodes = Arc::new(PeerHealthSet::new(&config.proxy_nodes)?);
 
         let escape_logger = config.get_escape_logger();
 
@@ -94,10 +97,23 @@ impl ProxySocks5Escaper {
 
         stats.set_extra_tags(config.extra_metrics_tags.clone());
 
+        let peer_health_job = if let Some(health_check) = &config.peer_health_check {
+            let mut probe = PeerHealthProbe::new(health_check.method.clone());
+            probe.set_bind(config.bind_v4, config.bind_v6);
+            probe.set_resolver(resolver_handle.clone(), config.resolve_strategy);
+            probe.set_socks_auth(config.auth_info.clone());
+            stats.set_peer_health(Some(Arc::clone(&proxy_nodes)));
+            Some(proxy_nodes.spawn_check_job(config.name.clone(), health_check.clone(), probe))
+        } else {
+            stats.set_peer_health(None);
+            None
+        };
+
         let escaper = ProxySocks5Escaper {
             config: Arc::new(config),
             stats,
             proxy_nodes,
+            peer_health_job,
             resolver_handle,
             escape_logger,
         };
@@ -121,18 +137,16 @@ impl ProxySocks5Escaper {
         }
     }
 
-    fn get_next_proxy<'a>(
-        &'a self,
-        task_notes: &'a ServerTaskNotes,
-        target_host: &'a Host,
-    ) -> &'a UpstreamAddr {
+    fn get_next_proxy(&self, task_notes: &ServerTaskNotes, target_host: &Host) -> UpstreamAddr {
+        let proxy_nodes = self.proxy_nodes.load_selection();
         self.select_consistent(
-            &self.proxy_nodes,
+            &proxy_nodes,
             self.config.proxy_pick_policy,
             task_notes,
             target_host,
         )
         .inner()
+        .clone()
     }
 
     fn resolve_happy(&self, domain: &str) -> Result<HappyEyeballsResolveJob, ResolveError> {
@@ -174,6 +188,12 @@ impl Escaper for ProxySocks5Escaper {
         Some(Arc::clone(&self.stats) as ArcEscaperStats)
     }
 
+    fn get_peer_health(&self) -> Option<Vec<PeerHealthSnapshot>> {
+        self.peer_health_job
+            .as_ref()
+            .map(|_| self.proxy_nodes.snapshot())
+    }
+
     async fn publish(&self, _data: String) -> anyhow::Result<()> {
         Err(anyhow!("not implemented"))
     }
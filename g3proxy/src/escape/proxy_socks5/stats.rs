@@ -24,7 +24,8 @@ use g3_types::metrics::{MetricsName, StaticMetricsTags};
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
 use crate::escape::{
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpStats, EscaperUdpStats,
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerHealthSnapshot, EscaperStats,
+    EscaperTcpStats, EscaperUdpStats, PeerHealthSet,
 };
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
 use crate::module::udp_connect::UdpConnectTaskRemoteStats;
@@ -34,6 +35,7 @@ pub(super) struct ProxySocks5EscaperStats {
     name: MetricsName,
     id: StatId,
     extra_metrics_tags: Arc<ArcSwapOption<StaticMetricsTags>>,
+    peer_health: ArcSwapOption<PeerHealthSet>,
     pub(super) interface: EscaperInterfaceStats,
     pub(super) udp: EscaperUdpStats,
     pub(super) tcp: EscaperTcpStats,
@@ -45,6 +47,7 @@ impl ProxySocks5EscaperStats {
             name: name.clone(),
             id: StatId::new(),
             extra_metrics_tags: Arc::new(ArcSwapOption::new(None)),
+            peer_health: ArcSwapOption::new(None),
             interface: EscaperInterfaceStats::default(),
             udp: EscaperUdpStats::default(),
             tcp: EscaperTcpStats::default(),
@@ -54,6 +57,10 @@ impl ProxySocks5EscaperStats {
     pub(super) fn set_extra_tags(&self, tags: Option<Arc<StaticMetricsTags>>) {
         self.extra_metrics_tags.store(tags);
     }
+
+    pub(super) fn set_peer_health(&self, peer_health: Option<Arc<PeerHealthSet>>) {
+        self.peer_health.store(peer_health);
+    }
 }
 
 impl EscaperInternalStats for ProxySocks5EscaperStats {
@@ -101,6 +108,17 @@ impl EscaperStats for ProxySocks5EscaperStats {
         Some(self.tcp.io.snapshot())
     }
 
+    fn peer_health_snapshot(&self) -> Option<EscaperPeerHealthSnapshot> {
+        let peer_health = self.peer_health.load();
+        peer_health.as_ref().map(|set| {
+            let healthy = set.healthy_count();
+            EscaperPeerHealthSnapshot {
+                healthy,
+                unhealthy: set.total_count() - healthy,
+            }
+        })
+    }
+
     fn udp_io_snapshot(&self) -> Option<UdpIoSnapshot> {
         Some(self.udp.io.snapshot())
     }
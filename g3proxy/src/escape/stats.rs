@@ -51,6 +51,10 @@ pub(crate) trait EscaperStats: EscaperInternalStats {
     fn forbidden_snapshot(&self) -> Option<EscaperForbiddenSnapshot> {
         None
     }
+
+    fn peer_health_snapshot(&self) -> Option<EscaperPeerHealthSnapshot> {
+        None
+    }
 }
 
 pub(crate) type ArcEscaperInternalStats = Arc<dyn EscaperInternalStats + Send + Sync>;
@@ -61,6 +65,11 @@ pub(crate) struct EscaperForbiddenSnapshot {
     pub(crate) ip_blocked: u64,
 }
 
+pub(crate) struct EscaperPeerHealthSnapshot {
+    pub(crate) healthy: usize,
+    pub(crate) unhealthy: usize,
+}
+
 #[derive(Default)]
 pub(crate) struct EscaperForbiddenStats {
     ip_blocked: AtomicU64,
@@ -42,8 +42,13 @@ use g3_types::net::HttpHeaderMap;
 use super::{HttpRequest, HttpRequestIo, HttpResponseIo};
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
+use crate::module::http_cache::{
+    HttpCacheForward, HttpCacheHit, HttpCacheRecordReader, HttpCacheRecorder, HttpCacheStatus,
+};
 use crate::module::http_forward::HttpProxyClientResponse;
-use crate::serve::{ServerIdleChecker, ServerTaskError, ServerTaskResult};
+use crate::serve::{
+    ServerIdleChecker, ServerTaskError, ServerTaskForbiddenError, ServerTaskResult,
+};
 
 mod adaptation;
 pub(crate) use adaptation::HttpRequestWriterForAdaptation;
@@ -60,6 +65,7 @@ macro_rules! intercept_log {
             "uri" => LtHttpUri::new(&$obj.req.uri, $obj.ctx.log_uri_max_chars()),
             "rsp_status" => $obj.http_notes.rsp_status,
             "origin_status" => $obj.http_notes.origin_status,
+            "cache_status" => $obj.http_notes.cache_status.map(|v| v.as_str()),
             "dur_req_send_hdr" => LtDuration($obj.http_notes.dur_req_send_hdr),
             "dur_req_pipeline" => LtDuration($obj.http_notes.dur_req_pipeline),
             "dur_req_send_all" => LtDuration($obj.http_notes.dur_req_send_all),
@@ -72,6 +78,7 @@ macro_rules! intercept_log {
 struct HttpForwardTaskNotes {
     rsp_status: u16,
     origin_status: u16,
+    cache_status: Option<HttpCacheStatus>,
     receive_ins: Instant,
     receive_datetime: DateTime<Utc>,
     dur_req_send_hdr: Duration,
@@ -91,6 +98,7 @@ impl HttpForwardTaskNotes {
         HttpForwardTaskNotes {
             rsp_status: 0,
             origin_status: 0,
+            cache_status: None,
             receive_datetime: datetime_received,
             receive_ins: time_received,
             dur_req_send_hdr,
@@ -129,6 +137,7 @@ pub(super) struct H1ForwardTask<'a, SC: ServerConfig> {
     send_error_response: bool,
     should_close: bool,
     http_notes: HttpForwardTaskNotes,
+    cache_forward: Option<HttpCacheForward>,
 }
 
 impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
@@ -146,6 +155,7 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
             send_error_response: true,
             should_close,
             http_notes,
+            cache_forward: None,
         }
     }
 
@@ -180,6 +190,109 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
         }
     }
 
+    pub(super) async fn reply_url_blocked<UR, CW>(&mut self, rsp_io: &mut HttpResponseIo<UR, CW>)
+    where
+        UR: AsyncRead + Unpin,
+        CW: AsyncWrite + Send + Unpin,
+    {
+        let e = ServerTaskError::ForbiddenByRule(ServerTaskForbiddenError::UrlBlocked);
+        self.reply_task_err(&e, &mut rsp_io.clt_w).await;
+        intercept_log!(self, "{e}");
+    }
+
+    pub(super) fn set_cache_forward(&mut self, cache_forward: Option<HttpCacheForward>) {
+        if let Some(forward) = &cache_forward {
+            self.http_notes.cache_status = forward.status();
+        }
+        self.cache_forward = cache_forward;
+    }
+
+    pub(super) async fn reply_cache_hit<UR, CW>(
+        &mut self,
+        rsp_io: &mut HttpResponseIo<UR, CW>,
+        hit: HttpCacheHit,
+    ) where
+        UR: AsyncRead + Unpin,
+        CW: AsyncWrite + Send + Unpin,
+    {
+        self.http_notes.mark_req_no_body();
+        if let Err(e) = self.send_cached_response(&mut rsp_io.clt_w, hit).await {
+            self.should_close = true;
+            intercept_log!(self, "{e}");
+        } else {
+            intercept_log!(self, "ok");
+        }
+    }
+
+    async fn send_cached_response<CW>(
+        &mut self,
+        clt_w: &mut CW,
+        hit: HttpCacheHit,
+    ) -> ServerTaskResult<()>
+    where
+        CW: AsyncWrite + Unpin,
+    {
+        self.send_error_response = false;
+        self.http_notes.cache_status = Some(hit.status());
+        self.http_notes.rsp_status = hit.code();
+        let header = hit.serialize_head(self.req.version, !self.should_close);
+        if !hit.has_body() {
+            return self.send_response_header(clt_w, header.into()).await;
+        }
+
+        let mut body_reader = hit.body_reader().await.map_err(|_| {
+            ServerTaskError::InternalServerError("failed to open cached response body")
+        })?;
+        let mut cache_to_clt = LimitedCopy::with_data(
+            &mut body_reader,
+            clt_w,
+            &self.ctx.server_config.limited_copy_config(),
+            header,
+        );
+
+        let idle_duration = self.ctx.server_config.task_idle_check_duration();
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        let max_idle_count = self.ctx.task_max_idle_count();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                r = &mut cache_to_clt => {
+                    return match r {
+                        Ok(_) => Ok(()),
+                        Err(LimitedCopyError::ReadFailed(_)) => {
+                            let _ = cache_to_clt.write_flush().await;
+                            Err(ServerTaskError::InternalServerError("failed to read cached response body"))
+                        }
+                        Err(LimitedCopyError::WriteFailed(e)) => Err(ServerTaskError::ClientTcpWriteFailed(e)),
+                    };
+                }
+                _ = idle_interval.tick() => {
+                    if cache_to_clt.is_idle() {
+                        idle_count += 1;
+                        if idle_count >= max_idle_count {
+                            return Err(ServerTaskError::ClientAppTimeout("idle while sending cached response"));
+                        }
+                    } else {
+                        idle_count = 0;
+                        cache_to_clt.reset_active();
+                    }
+
+                    if self.ctx.belongs_to_blocked_user() {
+                        return Err(ServerTaskError::CanceledAsUserBlocked);
+                    }
+
+                    if self.ctx.server_force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit)
+                    }
+                }
+            }
+        }
+    }
+
     pub(super) async fn forward_without_body<UR, CW>(&mut self, rsp_io: &mut HttpResponseIo<UR, CW>)
     where
         UR: AsyncRead + Unpin,
@@ -740,15 +853,33 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
         UR: AsyncRead + Unpin,
         CW: AsyncWrite + Unpin,
     {
+        let body_type = rsp.body_type(&self.req.method);
+        let mut cache_recorder = None;
+        if let Some(cache_forward) = &mut self.cache_forward {
+            if let Some(hit) = cache_forward.not_modified(rsp.code, &rsp.end_to_end_headers) {
+                self.http_notes.mark_rsp_no_body();
+                return self.send_cached_response(&mut rsp_io.clt_w, hit).await;
+            }
+            cache_recorder = cache_forward.recorder(
+                rsp.code,
+                &rsp.reason,
+                &rsp.end_to_end_headers,
+                body_type,
+                self.ctx.h1_interception().body_line_max_len,
+            );
+            self.http_notes.cache_status = cache_forward.status();
+        }
+
         self.send_error_response = false;
 
-        if let Some(body_type) = rsp.body_type(&self.req.method) {
+        if let Some(body_type) = body_type {
             self.http_notes.rsp_status = self.http_notes.origin_status; // the following function must send rsp header out
             self.send_response_body(
                 rsp_head.into(),
                 &mut rsp_io.ups_r,
                 &mut rsp_io.clt_w,
                 body_type,
+                cache_recorder,
             )
             .await
         } else {
@@ -756,6 +887,9 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
                 .await?;
             self.http_notes.rsp_status = self.http_notes.origin_status;
             self.http_notes.mark_rsp_no_body();
+            if let Some(recorder) = cache_recorder {
+                recorder.finish();
+            }
             Ok(())
         }
     }
@@ -784,16 +918,18 @@ impl<'a, SC: ServerConfig> H1ForwardTask<'a, SC> {
         ups_r: &mut UR,
         clt_w: &mut CW,
         body_type: HttpBodyType,
+        cache_recorder: Option<HttpCacheRecorder>,
     ) -> ServerTaskResult<()>
     where
         UR: AsyncBufRead + Unpin,
         CW: AsyncWrite + Unpin,
     {
-        let mut body_reader = HttpBodyReader::new(
+        let body_reader = HttpBodyReader::new(
             ups_r,
             body_type,
             self.ctx.h1_interception().body_line_max_len,
         );
+        let mut body_reader = HttpCacheRecordReader::new(body_reader, cache_recorder);
 
         let mut ups_to_clt = LimitedCopy::with_data(
             &mut body_reader,
@@ -175,15 +175,31 @@ where
                     // just close the connection
                     return Err(e);
                 }
-                HttpRecvRequest::RequestWithoutIo(r) => {
+                HttpRecvRequest::RequestUrlBlocked(r) => {
                     let mut forward_task = H1ForwardTask::new(self.ctx.clone(), &r, self.req_id);
+                    forward_task.reply_url_blocked(&mut rsp_io).await;
+                    pipeline_stats.del_task();
+                    req_acceptor.close();
+                }
+                HttpRecvRequest::RequestCacheHit(r, hit) => {
+                    let mut forward_task = H1ForwardTask::new(self.ctx.clone(), &r, self.req_id);
+                    forward_task.reply_cache_hit(&mut rsp_io, hit).await;
+                    pipeline_stats.del_task();
+                    if forward_task.should_close() {
+                        req_acceptor.close();
+                    }
+                }
+                HttpRecvRequest::RequestWithoutIo(mut r) => {
+                    let cache_forward = r.cache_forward.take();
+                    let mut forward_task = H1ForwardTask::new(self.ctx.clone(), &r, self.req_id);
+                    forward_task.set_cache_forward(cache_forward);
                     forward_task.forward_without_body(&mut rsp_io).await;
                     pipeline_stats.del_task();
                     if forward_task.should_close() {
                         req_acceptor.close();
                     }
                 }
-                HttpRecvRequest::RequestWithIO(r, mut req_io, io_sender) => {
+                HttpRecvRequest::RequestWithIO(mut r, mut req_io, io_sender) => {
                     if r.inner.method == Method::CONNECT {
                         let mut connect_task = H1ConnectTask::new(self.ctx.clone(), r, self.req_id);
                         let r = if let Some(reqmod_client) =
@@ -232,8 +248,10 @@ where
                             pipeline_stats.del_task();
                         }
                     } else {
+                        let cache_forward = r.cache_forward.take();
                         let mut forward_task =
                             H1ForwardTask::new(self.ctx.clone(), &r, self.req_id);
+                        forward_task.set_cache_forward(cache_forward);
                         if let Some(reqmod_client) = self.ctx.audit_handle.icap_reqmod_client() {
                             forward_task
                                 .adapt_with_io(&mut req_io, &mut rsp_io, reqmod_client)
@@ -17,23 +17,28 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
+use http::Method;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::sync::mpsc;
 use tokio::time::Instant;
 
 use g3_http::server::{HttpRequestParseError, HttpTransparentRequest};
 use g3_io_ext::LimitedBufReadExt;
+use g3_types::net::UpstreamAddr;
 
 use super::{H1InterceptionError, HttpRequestIo, PipelineStats};
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
+use crate::module::http_cache::{self, HttpCacheForward, HttpCacheHit, HttpCacheLookup};
 
 pub(crate) struct HttpRequest {
     pub(crate) inner: HttpTransparentRequest,
     pub(crate) time_received: Instant,
     pub(crate) datetime_received: DateTime<Utc>,
     pub(crate) dur_req_send_hdr: Duration,
+    pub(crate) cache_forward: Option<HttpCacheForward>,
 }
 
 pub(crate) enum HttpRecvRequest<R: AsyncRead, W: AsyncWrite> {
@@ -46,6 +51,8 @@ pub(crate) enum HttpRecvRequest<R: AsyncRead, W: AsyncWrite> {
         mpsc::Sender<HttpRequestIo<R, W>>,
     ),
     RequestWithoutIo(HttpRequest),
+    RequestUrlBlocked(HttpRequest),
+    RequestCacheHit(HttpRequest, HttpCacheHit),
 }
 
 pub(crate) struct HttpRequestAcceptor<R: AsyncRead, W: AsyncWrite> {
@@ -107,6 +114,48 @@ where
         }
     }
 
+    fn url_blocked(&self, req: &HttpTransparentRequest) -> bool {
+        if req.method == Method::CONNECT {
+            return false;
+        }
+        let Some(upstream) = &req.host else {
+            return false;
+        };
+        let path_and_query = req.uri.path_and_query().map(|v| v.as_str()).unwrap_or("/");
+        self.ctx
+            .check_http_url(self.ctx.http_url_scheme(), upstream.host(), path_and_query)
+            .map(|action| action.forbid_early())
+            .unwrap_or(false)
+    }
+
+    fn lookup_http_cache(&self, req: &HttpTransparentRequest) -> Option<HttpCacheLookup> {
+        if self.ctx.audit_handle.icap_respmod_client().is_some() {
+            // the cached response should never bypass the auditor
+            return None;
+        }
+        let cache = http_cache::get_global_cache()?;
+        if self.ctx.http_cache_bypass() {
+            return None;
+        }
+        let host = req.host.as_ref()?;
+
+        let scheme = self.ctx.http_url_scheme();
+        let upstream = if host.port() == 0 {
+            let port = if scheme == "https" { 443 } else { 80 };
+            UpstreamAddr::new(host.host().clone(), port)
+        } else {
+            host.clone()
+        };
+        let path_and_query = req.uri.path_and_query().map(|v| v.as_str()).unwrap_or("/");
+        let url = format!("{scheme}://{upstream}{path_and_query}");
+        cache.lookup(
+            &req.method,
+            url,
+            &req.end_to_end_headers,
+            req.body_type().is_some(),
+        )
+    }
+
     async fn run(&mut self) {
         let (io_sender, mut io_receiver) = mpsc::channel(1);
         let http_config = self.ctx.h1_interception();
@@ -172,6 +221,20 @@ where
                             req.disable_keep_alive();
                         }
 
+                        if self.url_blocked(&req) {
+                            let recv_req = HttpRecvRequest::RequestUrlBlocked(HttpRequest {
+                                inner: req,
+                                time_received,
+                                datetime_received,
+                                dur_req_send_hdr: Duration::ZERO,
+                                cache_forward: None,
+                            });
+                            let _ = self.send_request.send(recv_req).await;
+                            self.stats.add_task();
+                            // the connection will be closed after the forbidden response
+                            break;
+                        }
+
                         if self.ctx.audit_handle.icap_reqmod_client().is_some() {
                             // skip the fast send of header if audit is needed
                             let recv_req = HttpRecvRequest::RequestWithIO(
@@ -180,6 +243,7 @@ where
                                     time_received,
                                     datetime_received,
                                     dur_req_send_hdr: Duration::ZERO,
+                                    cache_forward: None,
                                 },
                                 io,
                                 io_sender.clone(),
@@ -190,6 +254,32 @@ where
                             continue;
                         }
 
+                        let cache_forward = match self.lookup_http_cache(&req) {
+                            Some(HttpCacheLookup::Hit(hit)) => {
+                                // reply from cache, no need to send the request to upstream
+                                let recv_req = HttpRecvRequest::RequestCacheHit(
+                                    HttpRequest {
+                                        inner: req,
+                                        time_received,
+                                        datetime_received,
+                                        dur_req_send_hdr: Duration::ZERO,
+                                        cache_forward: None,
+                                    },
+                                    hit,
+                                );
+                                self.io = Some(io);
+                                let _ = self.send_request.send(recv_req).await;
+                                self.stats.add_task();
+                                continue;
+                            }
+                            Some(HttpCacheLookup::Forward(forward)) => Some(*forward),
+                            None => None,
+                        };
+                        let head_bytes = match &cache_forward {
+                            Some(forward) => add_cache_validators(head_bytes, forward),
+                            None => head_bytes,
+                        };
+
                         // do a fast send of request
                         match io.ups_w.write_all(&head_bytes).await {
                             Ok(_) => {
@@ -201,6 +291,7 @@ where
                                         time_received,
                                         datetime_received,
                                         dur_req_send_hdr,
+                                        cache_forward,
                                     })
                                 } else {
                                     HttpRecvRequest::RequestWithIO(
@@ -209,6 +300,7 @@ where
                                             time_received,
                                             datetime_received,
                                             dur_req_send_hdr,
+                                            cache_forward,
                                         },
                                         io,
                                         io_sender.clone(),
@@ -261,3 +353,24 @@ where
         }
     }
 }
+
+fn add_cache_validators(head_bytes: Bytes, forward: &HttpCacheForward) -> Bytes {
+    let validators = forward.validators();
+    if validators.is_empty() {
+        return head_bytes;
+    }
+
+    // insert the header lines before the ending empty line
+    let end_len = if head_bytes.ends_with(b"\r\n\r\n") {
+        2
+    } else {
+        1
+    };
+    let mut buf = Vec::with_capacity(head_bytes.len() + 256);
+    buf.extend_from_slice(&head_bytes[..head_bytes.len() - end_len]);
+    for (name, value) in validators {
+        value.write_to_buf(&name, &mut buf);
+    }
+    buf.extend_from_slice(b"\r\n");
+    Bytes::from(buf)
+}
@@ -111,6 +111,7 @@ pub(super) struct H1UpgradeTask<SC: ServerConfig> {
     send_error_response: bool,
     should_close: bool,
     http_notes: HttpForwardTaskNotes,
+    websocket_extensions: Vec<String>,
 }
 
 impl<SC> H1UpgradeTask<SC>
@@ -130,6 +131,7 @@ where
             send_error_response: true,
             should_close: false,
             http_notes,
+            websocket_extensions: Vec::new(),
         }
     }
 
@@ -406,6 +408,15 @@ where
                 }
             };
 
+            if matches!(upgrade_protocol, HttpUpgradeToken::Websocket) {
+                self.websocket_extensions = rsp
+                    .end_to_end_headers
+                    .get_all(http::header::SEC_WEBSOCKET_EXTENSIONS)
+                    .iter()
+                    .map(|v| v.to_str().to_string())
+                    .collect();
+            }
+
             let upstream = if matches!(upgrade_protocol, HttpUpgradeToken::ConnectUdp) {
                 self.req
                     .uri
@@ -528,6 +539,8 @@ where
                 StreamInspectLog::new(&ctx).log(InspectSource::HttpUpgrade, Protocol::Websocket);
                 let mut websocket_obj =
                     crate::inspect::websocket::H1WebsocketInterceptObject::new(ctx, upstream);
+                websocket_obj
+                    .set_response_extensions(self.websocket_extensions.iter().map(|v| v.as_str()));
                 websocket_obj.set_io(clt_r, clt_w, ups_r, ups_w);
                 Ok(StreamInspection::Websocket(websocket_obj))
             }
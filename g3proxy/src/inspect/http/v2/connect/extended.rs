@@ -165,6 +165,7 @@ where
         let mut exchange_head = ExchangeHead::new(&self.ctx, &mut self.http_notes);
         let exchange_head_result = exchange_head.run(clt_req, clt_send_rsp, h2s).await;
         self.ups_stream_id = exchange_head.ups_stream_id.take();
+        let websocket_extensions = std::mem::take(&mut exchange_head.websocket_extensions);
         match exchange_head_result {
             Ok(Some((clt_r, clt_w, ups_r, ups_w))) => {
                 intercept_log!(self, "ok");
@@ -172,8 +173,10 @@ where
                 self.ctx.increase_inspection_depth();
                 StreamInspectLog::new(&self.ctx)
                     .log(InspectSource::H2ExtendedConnect, Protocol::Websocket);
-                let websocket_obj =
+                let mut websocket_obj =
                     crate::inspect::websocket::H2WebsocketInterceptObject::new(self.ctx, upstream);
+                websocket_obj
+                    .set_response_extensions(websocket_extensions.iter().map(|v| v.as_str()));
                 websocket_obj.intercept(clt_r, clt_w, ups_r, ups_w).await;
             }
             Ok(None) => {
@@ -86,6 +86,7 @@ struct ExchangeHead<'a, SC: ServerConfig> {
     ups_stream_id: Option<StreamId>,
     send_error_response: bool,
     http_notes: &'a mut HttpForwardTaskNotes,
+    websocket_extensions: Vec<String>,
 }
 
 impl<'a, SC: ServerConfig> ExchangeHead<'a, SC> {
@@ -95,6 +96,7 @@ impl<'a, SC: ServerConfig> ExchangeHead<'a, SC> {
             ups_stream_id: None,
             send_error_response: false,
             http_notes,
+            websocket_extensions: Vec::new(),
         }
     }
 
@@ -340,6 +342,12 @@ impl<'a, SC: ServerConfig> ExchangeHead<'a, SC> {
         H2StreamTransferError,
     > {
         let (parts, ups_r) = ups_rsp.into_parts();
+        self.websocket_extensions = parts
+            .headers
+            .get_all(http::header::SEC_WEBSOCKET_EXTENSIONS)
+            .iter()
+            .filter_map(|v| v.to_str().ok().map(|s| s.to_string()))
+            .collect();
         let ups_rsp = Response::from_parts(parts, ());
 
         if ups_r.is_end_stream() {
@@ -26,6 +26,8 @@ use g3_icap_client::reqmod::h2::H2ReqmodAdaptationError;
 use g3_icap_client::respmod::h2::H2RespmodAdaptationError;
 use g3_io_ext::IdleForceQuitReason;
 
+use crate::serve::ServerTaskForbiddenError;
+
 #[derive(Debug, Error)]
 pub(crate) enum H2InterceptionError {
     #[error("upstream io error during handshake: {0:?}")]
@@ -96,6 +98,8 @@ pub(crate) enum H2StreamTransferError {
     RequestHeadSendFailed(h2::Error),
     #[error("invalid Host header")]
     InvalidHostHeader,
+    #[error("forbidden by rule: {0}")]
+    ForbiddenByRule(ServerTaskForbiddenError),
     #[error("failed to recv response head: {0}")]
     ResponseHeadRecvFailed(h2::Error),
     #[error("timeout to recv response head")]
@@ -132,6 +136,7 @@ impl H2StreamTransferError {
             }
             H2StreamTransferError::RequestHeadSendFailed(_) => StatusCode::BAD_GATEWAY,
             H2StreamTransferError::InvalidHostHeader => StatusCode::BAD_REQUEST,
+            H2StreamTransferError::ForbiddenByRule(_) => StatusCode::FORBIDDEN,
             H2StreamTransferError::ResponseHeadRecvFailed(_) => StatusCode::BAD_GATEWAY,
             H2StreamTransferError::ResponseHeadRecvTimeout => StatusCode::GATEWAY_TIMEOUT,
             _ => return None,
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::anyhow;
@@ -37,12 +38,12 @@ use g3_icap_client::respmod::h2::{
 use g3_slog_types::{
     LtDateTime, LtDuration, LtH2StreamId, LtHttpHeaderValue, LtHttpMethod, LtHttpUri, LtUuid,
 };
-use g3_types::net::HttpHeaderMap;
+use g3_types::net::{HttpHeaderMap, UpstreamAddr};
 
 use super::{H2BodyTransfer, H2StreamTransferError};
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
-use crate::serve::ServerIdleChecker;
+use crate::serve::{ServerIdleChecker, ServerTaskForbiddenError};
 
 macro_rules! intercept_log {
     ($obj:tt, $($args:tt)+) => {
@@ -190,6 +191,35 @@ where
         Ok(())
     }
 
+    fn url_blocked(&self, parts: &http::request::Parts) -> bool {
+        let authority = match parts.uri.authority() {
+            Some(authority) => authority.as_str(),
+            None => match parts.headers.get(http::header::HOST) {
+                Some(v) => match v.to_str() {
+                    Ok(s) => s,
+                    Err(_) => return false,
+                },
+                None => return false,
+            },
+        };
+        let Ok(upstream) = UpstreamAddr::from_str(authority) else {
+            return false;
+        };
+        let scheme = parts
+            .uri
+            .scheme_str()
+            .unwrap_or_else(|| self.ctx.http_url_scheme());
+        let path_and_query = parts
+            .uri
+            .path_and_query()
+            .map(|v| v.as_str())
+            .unwrap_or("/");
+        self.ctx
+            .check_http_url(scheme, upstream.host(), path_and_query)
+            .map(|action| action.forbid_early())
+            .unwrap_or(false)
+    }
+
     pub(crate) async fn forward(
         mut self,
         clt_req: Request<RecvStream>,
@@ -213,6 +243,12 @@ where
         h2s: SendRequest<Bytes>,
     ) -> Result<(), H2StreamTransferError> {
         let (mut parts, clt_body) = clt_req.into_parts();
+        if self.url_blocked(&parts) {
+            self.send_error_response = true;
+            return Err(H2StreamTransferError::ForbiddenByRule(
+                ServerTaskForbiddenError::UrlBlocked,
+            ));
+        }
         if self.ctx.h2_interception().silent_drop_expect_header {
             // just drop the Expect header to avoid 100-continue response, which currently is not supported by h2
             parts.headers.remove(http::header::EXPECT);
@@ -0,0 +1,71 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use slog::slog_info;
+
+use g3_slog_types::LtUuid;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::StreamInspectContext;
+
+macro_rules! intercept_log {
+    ($obj:tt, $ctx:expr, $user:expr, $mailbox:expr, $($args:tt)+) => {
+        slog_info!($ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "ImapFetch",
+            "task_id" => LtUuid($ctx.server_task_id()),
+            "depth" => $ctx.inspection_depth,
+            "user" => $user,
+            "mailbox" => $mailbox,
+            "uid" => $obj.uid,
+            "sequence" => &$obj.sequence,
+            "message_count" => $obj.message_count,
+            "literal_size" => $obj.literal_size,
+        )
+    };
+}
+
+pub(super) struct Fetch {
+    uid: bool,
+    sequence: String,
+    message_count: usize,
+    literal_size: u64,
+}
+
+impl Fetch {
+    pub(super) fn new(uid: bool, sequence: String) -> Self {
+        Fetch {
+            uid,
+            sequence,
+            message_count: 0,
+            literal_size: 0,
+        }
+    }
+
+    pub(super) fn add_message(&mut self, literal_size: u64) {
+        self.message_count += 1;
+        self.literal_size += literal_size;
+    }
+
+    pub(super) fn log<SC: ServerConfig>(
+        &self,
+        ctx: &StreamInspectContext<SC>,
+        user: Option<&str>,
+        mailbox: Option<&str>,
+        result: &str,
+    ) {
+        intercept_log!(self, ctx, user, mailbox, "{result}");
+    }
+}
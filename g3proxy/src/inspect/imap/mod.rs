@@ -0,0 +1,622 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use slog::slog_info;
+use tokio::io::{AsyncBufRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use g3_dpi::ProtocolInspectPolicy;
+use g3_imap_proto::command::{Command, ParsedCommand};
+use g3_imap_proto::response::{Response, ServerStatus};
+use g3_imap_proto::Literal;
+use g3_io_ext::{LimitedBufReadExt, OnceBufReader};
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use super::tls::{StartTlsProtocol, TlsInterceptObject};
+use crate::config::server::ServerConfig;
+use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext, StreamInspection};
+use crate::serve::{ServerTaskError, ServerTaskResult};
+
+mod relay;
+use relay::ResponseEnd;
+
+mod fetch;
+use fetch::Fetch;
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "IMAP",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&$obj.upstream),
+            "user" => $obj.user.as_deref(),
+            "mailbox" => $obj.mailbox.as_deref(),
+        )
+    };
+}
+
+/// the max size of the literal that will be buffered to get the user or mailbox name
+const MAX_CAPTURE_LITERAL_SIZE: u64 = 1024;
+
+struct ImapIo {
+    pub(crate) clt_r: BoxAsyncRead,
+    pub(crate) clt_w: BoxAsyncWrite,
+    pub(crate) ups_r: OnceBufReader<BoxAsyncRead>,
+    pub(crate) ups_w: BoxAsyncWrite,
+}
+
+enum SessionEnd {
+    Logout,
+    StartTls,
+    /// the session can not be parsed any more, just relay the left data
+    Transparent,
+}
+
+enum CommandEnd {
+    Status(ServerStatus),
+    Transparent,
+}
+
+pub(crate) struct ImapInterceptObject<SC: ServerConfig> {
+    io: Option<ImapIo>,
+    pub(crate) ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    from_starttls: bool,
+    user: Option<String>,
+    mailbox: Option<String>,
+}
+
+impl<SC: ServerConfig> ImapInterceptObject<SC> {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
+        ImapInterceptObject {
+            io: None,
+            ctx,
+            upstream,
+            from_starttls: false,
+            user: None,
+            mailbox: None,
+        }
+    }
+
+    pub(crate) fn set_from_starttls(&mut self) {
+        self.from_starttls = true;
+    }
+
+    pub(crate) fn set_io(
+        &mut self,
+        clt_r: BoxAsyncRead,
+        clt_w: BoxAsyncWrite,
+        ups_r: OnceBufReader<BoxAsyncRead>,
+        ups_w: BoxAsyncWrite,
+    ) {
+        let io = ImapIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        };
+        self.io = Some(io);
+    }
+
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        match self.ctx.imap_inspect_policy() {
+            ProtocolInspectPolicy::Bypass => {
+                self.do_bypass().await?;
+                Ok(None)
+            }
+            ProtocolInspectPolicy::Intercept => match self.do_intercept().await {
+                Ok(Some(obj)) => {
+                    intercept_log!(self, "starttls");
+                    Ok(Some(obj))
+                }
+                Ok(None) => {
+                    intercept_log!(self, "finished");
+                    Ok(None)
+                }
+                Err(e) => {
+                    intercept_log!(self, "{e}");
+                    Err(e)
+                }
+            },
+            ProtocolInspectPolicy::Block => {
+                self.do_block().await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn do_bypass(&mut self) -> ServerTaskResult<()> {
+        let ImapIo {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        } = self.io.take().unwrap();
+
+        crate::inspect::stream::transit_transparent(
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+            &self.ctx.server_config,
+            &self.ctx.server_quit_policy,
+            self.ctx.user(),
+        )
+        .await
+    }
+
+    async fn do_block(&mut self) -> ServerTaskResult<()> {
+        let ImapIo {
+            clt_r: _,
+            mut clt_w,
+            ups_r: _,
+            mut ups_w,
+        } = self.io.take().unwrap();
+
+        tokio::spawn(async move {
+            let _ = ups_w.shutdown().await;
+        });
+
+        relay::send_rsp(&mut clt_w, b"* BYE [UNAVAILABLE] service blocked\r\n").await?;
+        let _ = clt_w.shutdown().await;
+        Ok(())
+    }
+
+    async fn do_intercept(&mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        let ImapIo {
+            clt_r,
+            mut clt_w,
+            ups_r,
+            mut ups_w,
+        } = self.io.take().unwrap();
+
+        let mut clt_r = BufReader::new(clt_r);
+        let mut ups_r = BufReader::new(ups_r);
+
+        let next = if self.from_starttls || self.relay_greeting(&mut ups_r, &mut clt_w).await? {
+            self.relay_commands(&mut clt_r, &mut clt_w, &mut ups_r, &mut ups_w)
+                .await?
+        } else {
+            SessionEnd::Transparent
+        };
+
+        match next {
+            SessionEnd::Logout => return Ok(None),
+            SessionEnd::StartTls => {
+                if !ups_r.buffer().is_empty() {
+                    return Err(ServerTaskError::UpstreamAppError(anyhow!(
+                        "unexpected data after IMAP STARTTLS response"
+                    )));
+                }
+                if !clt_r.buffer().is_empty() {
+                    return Err(ServerTaskError::ClientAppError(anyhow!(
+                        "unexpected pipelined data after IMAP STARTTLS command"
+                    )));
+                }
+                if let Some(tls_interception) = self.ctx.tls_interception() {
+                    let mut tls_obj = TlsInterceptObject::new(
+                        self.ctx.clone(),
+                        self.upstream.clone(),
+                        tls_interception,
+                    );
+                    tls_obj.set_start_tls_protocol(StartTlsProtocol::Imap);
+                    tls_obj.set_io(
+                        OnceBufReader::with_no_buf(clt_r.into_inner()),
+                        clt_w,
+                        Box::new(ups_r.into_inner()),
+                        ups_w,
+                    );
+                    return Ok(Some(StreamInspection::TlsModern(tls_obj)));
+                }
+            }
+            SessionEnd::Transparent => {}
+        }
+
+        crate::inspect::stream::transit_transparent(
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+            &self.ctx.server_config,
+            &self.ctx.server_quit_policy,
+            self.ctx.user(),
+        )
+        .await?;
+        Ok(None)
+    }
+
+    /// Relay the greeting message, return `false` if the session should not be parsed
+    async fn relay_greeting<UR, CW>(&self, ups_r: &mut UR, clt_w: &mut CW) -> ServerTaskResult<bool>
+    where
+        UR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.imap_interception();
+
+        let mut line_buf = Vec::with_capacity(Response::MAX_LINE_SIZE);
+        let complete = relay::recv_rsp_line(ups_r, &mut line_buf, config.greeting_timeout).await?;
+        relay::send_rsp(clt_w, &line_buf).await?;
+        if !complete {
+            return Ok(false);
+        }
+        match Response::parse_line(&line_buf) {
+            Ok(Response::UntaggedStatus(ServerStatus::Ok | ServerStatus::PreAuth)) => Ok(true),
+            Ok(Response::UntaggedStatus(ServerStatus::Bye)) => Ok(false),
+            _ => Err(ServerTaskError::UpstreamAppError(anyhow!(
+                "invalid IMAP greeting line"
+            ))),
+        }
+    }
+
+    async fn relay_commands<CR, CW, UR, UW>(
+        &mut self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+    ) -> ServerTaskResult<SessionEnd>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncBufRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let command_wait_timeout = self.ctx.imap_interception().command_wait_timeout;
+
+        let mut cmd_line_buf = Vec::with_capacity(Command::MAX_LINE_SIZE);
+        let mut rsp_line_buf = Vec::with_capacity(Response::MAX_LINE_SIZE);
+        loop {
+            let complete = relay::recv_cmd_line(
+                clt_r,
+                &mut cmd_line_buf,
+                Command::MAX_LINE_SIZE,
+                command_wait_timeout,
+            )
+            .await?;
+            if !complete {
+                relay::send_cmd(ups_w, &cmd_line_buf).await?;
+                return Ok(SessionEnd::Transparent);
+            }
+            let Ok(mut cmd) = Command::parse_line(&cmd_line_buf) else {
+                // let the upstream server reply the error
+                relay::send_cmd(ups_w, &cmd_line_buf).await?;
+                return Ok(SessionEnd::Transparent);
+            };
+
+            let end = self
+                .relay_command(
+                    &mut cmd,
+                    clt_r,
+                    clt_w,
+                    ups_r,
+                    ups_w,
+                    &mut cmd_line_buf,
+                    &mut rsp_line_buf,
+                )
+                .await?;
+            let status = match end {
+                CommandEnd::Status(status) => status,
+                CommandEnd::Transparent => return Ok(SessionEnd::Transparent),
+            };
+
+            match cmd.parsed {
+                ParsedCommand::Login(user) => {
+                    if status == ServerStatus::Ok {
+                        self.user = user;
+                        intercept_log!(self, "logged in");
+                    }
+                }
+                ParsedCommand::Authenticate(mechanism) => {
+                    if status == ServerStatus::Ok {
+                        intercept_log!(self, "authenticated with {mechanism}");
+                    }
+                }
+                ParsedCommand::Select(mailbox) | ParsedCommand::Examine(mailbox) => {
+                    // the selected mailbox will be closed even if the command failed
+                    self.mailbox = None;
+                    if status == ServerStatus::Ok {
+                        self.mailbox = mailbox;
+                        intercept_log!(self, "mailbox selected");
+                    }
+                }
+                ParsedCommand::Other(ref name) if name == "CLOSE" || name == "UNSELECT" => {
+                    if status == ServerStatus::Ok {
+                        self.mailbox = None;
+                    }
+                }
+                ParsedCommand::Logout => return Ok(SessionEnd::Logout),
+                ParsedCommand::StartTls => {
+                    if status == ServerStatus::Ok {
+                        return if self.from_starttls {
+                            Ok(SessionEnd::Transparent)
+                        } else {
+                            Ok(SessionEnd::StartTls)
+                        };
+                    }
+                }
+                ParsedCommand::Compress => {
+                    if status == ServerStatus::Ok {
+                        // the data after this will be compressed
+                        return Ok(SessionEnd::Transparent);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Relay a single command and all the responses to it
+    #[allow(clippy::too_many_arguments)]
+    async fn relay_command<CR, CW, UR, UW>(
+        &self,
+        cmd: &mut Command,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        cmd_line_buf: &mut Vec<u8>,
+        rsp_line_buf: &mut Vec<u8>,
+    ) -> ServerTaskResult<CommandEnd>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncBufRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.imap_interception();
+
+        relay::send_cmd(ups_w, cmd_line_buf).await?;
+        let mut capture_literal = matches!(
+            cmd.parsed,
+            ParsedCommand::Login(None) | ParsedCommand::Select(None) | ParsedCommand::Examine(None)
+        );
+        while let Some(literal) = Literal::parse_line_end(cmd_line_buf) {
+            if literal.wait_continuation {
+                match relay::relay_rsp(
+                    ups_r,
+                    clt_w,
+                    &cmd.tag,
+                    rsp_line_buf,
+                    config.response_wait_timeout,
+                    None,
+                )
+                .await?
+                {
+                    ResponseEnd::Continuation => {}
+                    ResponseEnd::Tagged(status) => return Ok(CommandEnd::Status(status)),
+                    ResponseEnd::Unknown => return Ok(CommandEnd::Transparent),
+                }
+            }
+
+            if capture_literal && literal.size <= MAX_CAPTURE_LITERAL_SIZE {
+                let value =
+                    capture_cmd_literal(clt_r, literal.size, config.command_wait_timeout).await?;
+                relay::send_cmd(ups_w, &value).await?;
+                let value = String::from_utf8(value).ok();
+                match &mut cmd.parsed {
+                    ParsedCommand::Login(user) => *user = value,
+                    ParsedCommand::Select(mailbox) | ParsedCommand::Examine(mailbox) => {
+                        *mailbox = value
+                    }
+                    _ => {}
+                }
+            } else {
+                relay::relay_cmd_literal(clt_r, ups_w, literal.size, config.command_wait_timeout)
+                    .await?;
+            }
+            capture_literal = false;
+
+            // the command continues after the literal data
+            let complete = relay::recv_cmd_line(
+                clt_r,
+                cmd_line_buf,
+                Command::MAX_LINE_SIZE,
+                config.command_wait_timeout,
+            )
+            .await?;
+            relay::send_cmd(ups_w, cmd_line_buf).await?;
+            if !complete {
+                return Ok(CommandEnd::Transparent);
+            }
+        }
+
+        let end = match &cmd.parsed {
+            ParsedCommand::Fetch { uid, sequence } => {
+                let mut fetch = Fetch::new(*uid, sequence.clone());
+                let r = relay::relay_rsp(
+                    ups_r,
+                    clt_w,
+                    &cmd.tag,
+                    rsp_line_buf,
+                    config.response_wait_timeout,
+                    Some(&mut fetch),
+                )
+                .await;
+                let result = match &r {
+                    Ok(ResponseEnd::Tagged(status)) => status.as_str(),
+                    Ok(_) => "unknown",
+                    Err(_) => "aborted",
+                };
+                fetch.log(
+                    &self.ctx,
+                    self.user.as_deref(),
+                    self.mailbox.as_deref(),
+                    result,
+                );
+                r?
+            }
+            ParsedCommand::Authenticate(_) => loop {
+                let end = relay::relay_rsp(
+                    ups_r,
+                    clt_w,
+                    &cmd.tag,
+                    rsp_line_buf,
+                    config.response_wait_timeout,
+                    None,
+                )
+                .await?;
+                let ResponseEnd::Continuation = end else {
+                    break end;
+                };
+                let complete = relay::recv_cmd_line(
+                    clt_r,
+                    cmd_line_buf,
+                    Command::MAX_CONTINUE_LINE_SIZE,
+                    config.command_wait_timeout,
+                )
+                .await?;
+                relay::send_cmd(ups_w, cmd_line_buf).await?;
+                if !complete {
+                    return Ok(CommandEnd::Transparent);
+                }
+            },
+            ParsedCommand::Idle => {
+                let end = relay::relay_rsp(
+                    ups_r,
+                    clt_w,
+                    &cmd.tag,
+                    rsp_line_buf,
+                    config.response_wait_timeout,
+                    None,
+                )
+                .await?;
+                let ResponseEnd::Continuation = end else {
+                    return Ok(command_end(end));
+                };
+                if !self
+                    .relay_idle(clt_r, clt_w, ups_r, ups_w, cmd_line_buf, rsp_line_buf)
+                    .await?
+                {
+                    return Ok(CommandEnd::Transparent);
+                }
+                relay::relay_rsp(
+                    ups_r,
+                    clt_w,
+                    &cmd.tag,
+                    rsp_line_buf,
+                    config.response_wait_timeout,
+                    None,
+                )
+                .await?
+            }
+            _ => {
+                relay::relay_rsp(
+                    ups_r,
+                    clt_w,
+                    &cmd.tag,
+                    rsp_line_buf,
+                    config.response_wait_timeout,
+                    None,
+                )
+                .await?
+            }
+        };
+        Ok(command_end(end))
+    }
+
+    /// Relay the unsolicited responses until the client send the DONE line,
+    /// return `false` if the session should not be parsed any more
+    async fn relay_idle<CR, CW, UR, UW>(
+        &self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        cmd_line_buf: &mut Vec<u8>,
+        rsp_line_buf: &mut Vec<u8>,
+    ) -> ServerTaskResult<bool>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncBufRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.imap_interception();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                r = clt_r.fill_wait_data() => {
+                    match r {
+                        Ok(true) => {}
+                        Ok(false) => return Err(ServerTaskError::ClosedByClient),
+                        Err(e) => return Err(ServerTaskError::ClientTcpReadFailed(e)),
+                    }
+                    let complete = relay::recv_cmd_line(
+                        clt_r,
+                        cmd_line_buf,
+                        Command::MAX_CONTINUE_LINE_SIZE,
+                        config.command_wait_timeout,
+                    )
+                    .await?;
+                    relay::send_cmd(ups_w, cmd_line_buf).await?;
+                    return Ok(complete && cmd_line_buf.eq_ignore_ascii_case(b"DONE\r\n"));
+                }
+                r = ups_r.fill_wait_data() => {
+                    match r {
+                        Ok(true) => {}
+                        Ok(false) => return Err(ServerTaskError::ClosedByUpstream),
+                        Err(e) => return Err(ServerTaskError::UpstreamReadFailed(e)),
+                    }
+                    let rsp = relay::relay_single_rsp(
+                        ups_r,
+                        clt_w,
+                        rsp_line_buf,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                    match rsp {
+                        Some((Response::UntaggedData(_) | Response::UntaggedStatus(_), _)) => {}
+                        _ => return Ok(false),
+                    }
+                }
+                _ = tokio::time::sleep(config.command_wait_timeout) => {
+                    return Err(ServerTaskError::ClientAppTimeout(
+                        "timeout to wait IMAP IDLE done",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn command_end(end: ResponseEnd) -> CommandEnd {
+    match end {
+        ResponseEnd::Tagged(status) => CommandEnd::Status(status),
+        ResponseEnd::Continuation | ResponseEnd::Unknown => CommandEnd::Transparent,
+    }
+}
+
+async fn capture_cmd_literal<CR>(
+    clt_r: &mut CR,
+    size: u64,
+    timeout: std::time::Duration,
+) -> ServerTaskResult<Vec<u8>>
+where
+    CR: AsyncBufRead + Unpin,
+{
+    let mut buf = vec![0u8; size as usize];
+    match tokio::time::timeout(timeout, clt_r.read_exact(&mut buf)).await {
+        Ok(Ok(_)) => Ok(buf),
+        Ok(Err(e)) => Err(ServerTaskError::ClientTcpReadFailed(e)),
+        Err(_) => Err(ServerTaskError::ClientAppTimeout(
+            "timeout to wait IMAP literal data",
+        )),
+    }
+}
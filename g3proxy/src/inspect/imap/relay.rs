@@ -0,0 +1,289 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use g3_imap_proto::response::{Response, ServerStatus};
+use g3_imap_proto::Literal;
+use g3_io_ext::LimitedBufReadExt;
+
+use super::Fetch;
+use crate::serve::{ServerTaskError, ServerTaskResult};
+
+pub(super) enum ResponseEnd {
+    Tagged(ServerStatus),
+    Continuation,
+    /// the following data can not be parsed as IMAP responses
+    Unknown,
+}
+
+enum LiteralCopyError {
+    ReadFailed(io::Error),
+    WriteFailed(io::Error),
+    ReadTimeout,
+    ClosedEarly,
+}
+
+pub(super) async fn send_cmd<UW>(ups_w: &mut UW, line: &[u8]) -> ServerTaskResult<()>
+where
+    UW: AsyncWrite + Unpin,
+{
+    ups_w
+        .write_all(line)
+        .await
+        .map_err(ServerTaskError::UpstreamWriteFailed)?;
+    ups_w
+        .flush()
+        .await
+        .map_err(ServerTaskError::UpstreamWriteFailed)
+}
+
+pub(super) async fn send_rsp<CW>(clt_w: &mut CW, line: &[u8]) -> ServerTaskResult<()>
+where
+    CW: AsyncWrite + Unpin,
+{
+    clt_w
+        .write_all(line)
+        .await
+        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+    clt_w
+        .flush()
+        .await
+        .map_err(ServerTaskError::ClientTcpWriteFailed)
+}
+
+/// Receive a command line from the client, the line will be put into `line_buf`.
+/// `false` will be returned if the line is too long, and the received data will be kept in `line_buf`
+pub(super) async fn recv_cmd_line<CR>(
+    clt_r: &mut CR,
+    line_buf: &mut Vec<u8>,
+    max_size: usize,
+    timeout: Duration,
+) -> ServerTaskResult<bool>
+where
+    CR: AsyncBufRead + Unpin,
+{
+    line_buf.clear();
+    match tokio::time::timeout(timeout, clt_r.limited_read_until(b'\n', max_size, line_buf)).await {
+        Ok(Ok((true, _))) => Ok(true),
+        Ok(Ok((false, nr))) => {
+            if nr > max_size {
+                Ok(false)
+            } else {
+                Err(ServerTaskError::ClosedByClient)
+            }
+        }
+        Ok(Err(e)) => Err(ServerTaskError::ClientTcpReadFailed(e)),
+        Err(_) => Err(ServerTaskError::ClientAppTimeout(
+            "timeout to wait IMAP command",
+        )),
+    }
+}
+
+/// Receive a response line from the upstream server, the line will be put into `line_buf`.
+/// `false` will be returned if the line is too long, and the received data will be kept in `line_buf`
+pub(super) async fn recv_rsp_line<UR>(
+    ups_r: &mut UR,
+    line_buf: &mut Vec<u8>,
+    timeout: Duration,
+) -> ServerTaskResult<bool>
+where
+    UR: AsyncBufRead + Unpin,
+{
+    line_buf.clear();
+    match tokio::time::timeout(
+        timeout,
+        ups_r.limited_read_until(b'\n', Response::MAX_LINE_SIZE, line_buf),
+    )
+    .await
+    {
+        Ok(Ok((true, _))) => Ok(true),
+        Ok(Ok((false, nr))) => {
+            if nr > Response::MAX_LINE_SIZE {
+                Ok(false)
+            } else {
+                Err(ServerTaskError::ClosedByUpstream)
+            }
+        }
+        Ok(Err(e)) => Err(ServerTaskError::UpstreamReadFailed(e)),
+        Err(_) => Err(ServerTaskError::UpstreamAppTimeout(
+            "timeout to wait IMAP response",
+        )),
+    }
+}
+
+async fn copy_literal<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    size: u64,
+    timeout: Duration,
+) -> Result<(), LiteralCopyError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut left = size;
+    while left > 0 {
+        let buf = match tokio::time::timeout(timeout, reader.fill_buf()).await {
+            Ok(Ok(buf)) => buf,
+            Ok(Err(e)) => return Err(LiteralCopyError::ReadFailed(e)),
+            Err(_) => return Err(LiteralCopyError::ReadTimeout),
+        };
+        if buf.is_empty() {
+            return Err(LiteralCopyError::ClosedEarly);
+        }
+        let to_copy = usize::try_from(left).unwrap_or(usize::MAX).min(buf.len());
+        writer
+            .write_all(&buf[..to_copy])
+            .await
+            .map_err(LiteralCopyError::WriteFailed)?;
+        reader.consume(to_copy);
+        left -= to_copy as u64;
+    }
+    writer.flush().await.map_err(LiteralCopyError::WriteFailed)
+}
+
+/// Relay the literal data sent by the client
+pub(super) async fn relay_cmd_literal<CR, UW>(
+    clt_r: &mut CR,
+    ups_w: &mut UW,
+    size: u64,
+    timeout: Duration,
+) -> ServerTaskResult<()>
+where
+    CR: AsyncBufRead + Unpin,
+    UW: AsyncWrite + Unpin,
+{
+    copy_literal(clt_r, ups_w, size, timeout)
+        .await
+        .map_err(|e| match e {
+            LiteralCopyError::ReadFailed(e) => ServerTaskError::ClientTcpReadFailed(e),
+            LiteralCopyError::WriteFailed(e) => ServerTaskError::UpstreamWriteFailed(e),
+            LiteralCopyError::ReadTimeout => {
+                ServerTaskError::ClientAppTimeout("timeout to wait IMAP literal data")
+            }
+            LiteralCopyError::ClosedEarly => ServerTaskError::ClosedByClient,
+        })
+}
+
+/// Relay the literal data sent by the upstream server
+async fn relay_rsp_literal<UR, CW>(
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    size: u64,
+    timeout: Duration,
+) -> ServerTaskResult<()>
+where
+    UR: AsyncBufRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    copy_literal(ups_r, clt_w, size, timeout)
+        .await
+        .map_err(|e| match e {
+            LiteralCopyError::ReadFailed(e) => ServerTaskError::UpstreamReadFailed(e),
+            LiteralCopyError::WriteFailed(e) => ServerTaskError::ClientTcpWriteFailed(e),
+            LiteralCopyError::ReadTimeout => {
+                ServerTaskError::UpstreamAppTimeout("timeout to wait IMAP literal data")
+            }
+            LiteralCopyError::ClosedEarly => ServerTaskError::UpstreamAppError(anyhow!(
+                "upstream closed before the end of IMAP literal data"
+            )),
+        })
+}
+
+/// Relay a single response, including all the literal data in it.
+/// The parsed response and the total size of the literal data will be returned,
+/// `None` will be returned if the response is not valid
+pub(super) async fn relay_single_rsp<UR, CW>(
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    line_buf: &mut Vec<u8>,
+    timeout: Duration,
+) -> ServerTaskResult<Option<(Response, u64)>>
+where
+    UR: AsyncBufRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    let complete = recv_rsp_line(ups_r, line_buf, timeout).await?;
+    send_rsp(clt_w, line_buf).await?;
+    if !complete {
+        return Ok(None);
+    }
+    let Ok(rsp) = Response::parse_line(line_buf) else {
+        return Ok(None);
+    };
+
+    let mut literal_size = 0u64;
+    while let Some(literal) = Literal::parse_line_end(line_buf) {
+        relay_rsp_literal(ups_r, clt_w, literal.size, timeout).await?;
+        literal_size += literal.size;
+
+        // the response continues after the literal data
+        let complete = recv_rsp_line(ups_r, line_buf, timeout).await?;
+        send_rsp(clt_w, line_buf).await?;
+        if !complete {
+            return Ok(None);
+        }
+    }
+    Ok(Some((rsp, literal_size)))
+}
+
+/// Relay the responses until the tagged response for `tag` or a continuation request
+pub(super) async fn relay_rsp<UR, CW>(
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    tag: &str,
+    line_buf: &mut Vec<u8>,
+    timeout: Duration,
+    mut fetch: Option<&mut Fetch>,
+) -> ServerTaskResult<ResponseEnd>
+where
+    UR: AsyncBufRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    loop {
+        let Some((rsp, literal_size)) = relay_single_rsp(ups_r, clt_w, line_buf, timeout).await?
+        else {
+            return Ok(ResponseEnd::Unknown);
+        };
+
+        match rsp {
+            Response::Continuation => return Ok(ResponseEnd::Continuation),
+            Response::Tagged {
+                tag: rsp_tag,
+                status,
+            } => {
+                return if rsp_tag == tag {
+                    Ok(ResponseEnd::Tagged(status))
+                } else {
+                    Ok(ResponseEnd::Unknown)
+                };
+            }
+            Response::UntaggedData(keyword) => {
+                if keyword == "FETCH" {
+                    if let Some(fetch) = &mut fetch {
+                        fetch.add_message(literal_size);
+                    }
+                }
+            }
+            Response::UntaggedStatus(_) => {}
+        }
+    }
+}
@@ -24,10 +24,12 @@ use uuid::Uuid;
 
 use g3_daemon::server::ServerQuitPolicy;
 use g3_dpi::{
-    H1InterceptionConfig, H2InterceptionConfig, MaybeProtocol, ProtocolInspectPolicy,
-    ProtocolInspector, SmtpInterceptionConfig,
+    H1InterceptionConfig, H2InterceptionConfig, ImapInterceptionConfig, MaybeProtocol,
+    Pop3InterceptionConfig, ProtocolInspectPolicy, ProtocolInspector, SmtpInterceptionConfig,
+    WebSocketInterceptionConfig,
 };
-use g3_types::net::OpensslClientConfig;
+use g3_types::acl::AclAction;
+use g3_types::net::{Host, OpensslClientConfig};
 
 use crate::audit::AuditHandle;
 use crate::auth::{User, UserForbiddenStats, UserSite};
@@ -45,6 +47,8 @@ use tls::TlsInterceptionContext;
 pub(crate) mod http;
 mod websocket;
 
+pub(crate) mod imap;
+pub(crate) mod pop3;
 pub(crate) mod smtp;
 
 #[derive(Clone)]
@@ -97,6 +101,7 @@ pub(crate) struct StreamInspectContext<SC: ServerConfig> {
     server_quit_policy: Arc<ServerQuitPolicy>,
     task_notes: StreamInspectTaskNotes,
     inspection_depth: usize,
+    inside_tls: bool,
 
     task_max_idle_count: i32,
 }
@@ -110,6 +115,7 @@ impl<SC: ServerConfig> Clone for StreamInspectContext<SC> {
             server_quit_policy: self.server_quit_policy.clone(),
             task_notes: self.task_notes.clone(),
             inspection_depth: self.inspection_depth,
+            inside_tls: self.inside_tls,
             task_max_idle_count: self.task_max_idle_count,
         }
     }
@@ -135,6 +141,7 @@ impl<SC: ServerConfig> StreamInspectContext<SC> {
             server_quit_policy,
             task_notes: StreamInspectTaskNotes::from(task_notes),
             inspection_depth: 0,
+            inside_tls: false,
             task_max_idle_count,
         }
     }
@@ -208,6 +215,27 @@ impl<SC: ServerConfig> StreamInspectContext<SC> {
         self.inspection_depth += 1;
     }
 
+    #[inline]
+    fn mark_inside_tls(&mut self) {
+        self.inside_tls = true;
+    }
+
+    /// the url scheme for http requests that are inspected in the current context
+    fn http_url_scheme(&self) -> &'static str {
+        if self.inside_tls {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    fn check_http_url(&self, scheme: &str, host: &Host, path_and_query: &str) -> Option<AclAction> {
+        let user_ctx = self.task_notes.user_ctx.as_ref()?;
+        user_ctx
+            .user
+            .check_http_url(scheme, host, path_and_query, &user_ctx.forbidden_stats)
+    }
+
     #[inline]
     pub(crate) fn tls_interception(&self) -> Option<TlsInterceptionContext> {
         self.audit_handle.tls_interception()
@@ -221,6 +249,14 @@ impl<SC: ServerConfig> StreamInspectContext<SC> {
             .and_then(|v| v.tls_client())
     }
 
+    fn http_cache_bypass(&self) -> bool {
+        self.task_notes
+            .user_ctx
+            .as_ref()
+            .map(|cx| cx.user.http_cache_bypass())
+            .unwrap_or(false)
+    }
+
     fn log_uri_max_chars(&self) -> usize {
         self.task_notes
             .user_ctx
@@ -270,6 +306,31 @@ impl<SC: ServerConfig> StreamInspectContext<SC> {
         self.audit_handle.smtp_interception()
     }
 
+    #[inline]
+    fn imap_inspect_policy(&self) -> ProtocolInspectPolicy {
+        self.audit_handle.imap_inspect_policy()
+    }
+
+    #[inline]
+    fn imap_interception(&self) -> &ImapInterceptionConfig {
+        self.audit_handle.imap_interception()
+    }
+
+    #[inline]
+    fn pop3_inspect_policy(&self) -> ProtocolInspectPolicy {
+        self.audit_handle.pop3_inspect_policy()
+    }
+
+    #[inline]
+    fn pop3_interception(&self) -> &Pop3InterceptionConfig {
+        self.audit_handle.pop3_interception()
+    }
+
+    #[inline]
+    fn websocket_interception(&self) -> &WebSocketInterceptionConfig {
+        self.audit_handle.websocket_interception()
+    }
+
     #[inline]
     fn task_max_idle_count(&self) -> i32 {
         self.task_max_idle_count
@@ -295,6 +356,8 @@ pub(crate) enum StreamInspection<SC: ServerConfig> {
     H2(http::H2InterceptObject<SC>),
     Websocket(websocket::H1WebsocketInterceptObject<SC>),
     Smtp(smtp::SmtpInterceptObject<SC>),
+    Imap(imap::ImapInterceptObject<SC>),
+    Pop3(pop3::Pop3InterceptObject<SC>),
 }
 
 type BoxAsyncRead = Box<dyn AsyncRead + Send + Unpin + 'static>;
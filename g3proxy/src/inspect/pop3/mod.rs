@@ -0,0 +1,373 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use slog::slog_info;
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use g3_dpi::ProtocolInspectPolicy;
+use g3_io_ext::OnceBufReader;
+use g3_pop3_proto::command::Command;
+use g3_pop3_proto::response::Response;
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use super::tls::{StartTlsProtocol, TlsInterceptObject};
+use crate::config::server::ServerConfig;
+use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext, StreamInspection};
+use crate::serve::{ServerTaskError, ServerTaskResult};
+
+mod relay;
+
+mod retrieve;
+use retrieve::Retrieve;
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "POP3",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr(&$obj.upstream),
+            "user" => $obj.user.as_deref(),
+        )
+    };
+}
+
+struct Pop3Io {
+    pub(crate) clt_r: BoxAsyncRead,
+    pub(crate) clt_w: BoxAsyncWrite,
+    pub(crate) ups_r: OnceBufReader<BoxAsyncRead>,
+    pub(crate) ups_w: BoxAsyncWrite,
+}
+
+enum SessionEnd {
+    Quit,
+    StartTls,
+    /// the session can not be parsed any more, just relay the left data
+    Transparent,
+}
+
+pub(crate) struct Pop3InterceptObject<SC: ServerConfig> {
+    io: Option<Pop3Io>,
+    pub(crate) ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    from_starttls: bool,
+    user: Option<String>,
+}
+
+impl<SC: ServerConfig> Pop3InterceptObject<SC> {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
+        Pop3InterceptObject {
+            io: None,
+            ctx,
+            upstream,
+            from_starttls: false,
+            user: None,
+        }
+    }
+
+    pub(crate) fn set_from_starttls(&mut self) {
+        self.from_starttls = true;
+    }
+
+    pub(crate) fn set_io(
+        &mut self,
+        clt_r: BoxAsyncRead,
+        clt_w: BoxAsyncWrite,
+        ups_r: OnceBufReader<BoxAsyncRead>,
+        ups_w: BoxAsyncWrite,
+    ) {
+        let io = Pop3Io {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        };
+        self.io = Some(io);
+    }
+
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        match self.ctx.pop3_inspect_policy() {
+            ProtocolInspectPolicy::Bypass => {
+                self.do_bypass().await?;
+                Ok(None)
+            }
+            ProtocolInspectPolicy::Intercept => match self.do_intercept().await {
+                Ok(Some(obj)) => {
+                    intercept_log!(self, "starttls");
+                    Ok(Some(obj))
+                }
+                Ok(None) => {
+                    intercept_log!(self, "finished");
+                    Ok(None)
+                }
+                Err(e) => {
+                    intercept_log!(self, "{e}");
+                    Err(e)
+                }
+            },
+            ProtocolInspectPolicy::Block => {
+                self.do_block().await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn do_bypass(&mut self) -> ServerTaskResult<()> {
+        let Pop3Io {
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+        } = self.io.take().unwrap();
+
+        crate::inspect::stream::transit_transparent(
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+            &self.ctx.server_config,
+            &self.ctx.server_quit_policy,
+            self.ctx.user(),
+        )
+        .await
+    }
+
+    async fn do_block(&mut self) -> ServerTaskResult<()> {
+        let Pop3Io {
+            clt_r: _,
+            mut clt_w,
+            ups_r: _,
+            mut ups_w,
+        } = self.io.take().unwrap();
+
+        tokio::spawn(async move {
+            let _ = ups_w.shutdown().await;
+        });
+
+        relay::send_rsp(&mut clt_w, b"-ERR service blocked\r\n").await?;
+        let _ = clt_w.shutdown().await;
+        Ok(())
+    }
+
+    async fn do_intercept(&mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
+        let Pop3Io {
+            clt_r,
+            mut clt_w,
+            ups_r,
+            mut ups_w,
+        } = self.io.take().unwrap();
+
+        let mut clt_r = BufReader::new(clt_r);
+        let mut ups_r = BufReader::new(ups_r);
+
+        let next = if self.from_starttls || self.relay_greeting(&mut ups_r, &mut clt_w).await? {
+            self.relay_commands(&mut clt_r, &mut clt_w, &mut ups_r, &mut ups_w)
+                .await?
+        } else {
+            SessionEnd::Transparent
+        };
+
+        match next {
+            SessionEnd::Quit => return Ok(None),
+            SessionEnd::StartTls => {
+                if !ups_r.buffer().is_empty() {
+                    return Err(ServerTaskError::UpstreamAppError(anyhow!(
+                        "unexpected data after POP3 STLS response"
+                    )));
+                }
+                if !clt_r.buffer().is_empty() {
+                    return Err(ServerTaskError::ClientAppError(anyhow!(
+                        "unexpected pipelined data after POP3 STLS command"
+                    )));
+                }
+                if let Some(tls_interception) = self.ctx.tls_interception() {
+                    let mut tls_obj = TlsInterceptObject::new(
+                        self.ctx.clone(),
+                        self.upstream.clone(),
+                        tls_interception,
+                    );
+                    tls_obj.set_start_tls_protocol(StartTlsProtocol::Pop3);
+                    tls_obj.set_io(
+                        OnceBufReader::with_no_buf(clt_r.into_inner()),
+                        clt_w,
+                        Box::new(ups_r.into_inner()),
+                        ups_w,
+                    );
+                    return Ok(Some(StreamInspection::TlsModern(tls_obj)));
+                }
+            }
+            SessionEnd::Transparent => {}
+        }
+
+        crate::inspect::stream::transit_transparent(
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+            &self.ctx.server_config,
+            &self.ctx.server_quit_policy,
+            self.ctx.user(),
+        )
+        .await?;
+        Ok(None)
+    }
+
+    /// Relay the greeting message, return `false` if the session should not be parsed
+    async fn relay_greeting<UR, CW>(&self, ups_r: &mut UR, clt_w: &mut CW) -> ServerTaskResult<bool>
+    where
+        UR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.pop3_interception();
+
+        let mut line_buf = Vec::with_capacity(Response::MAX_LINE_SIZE);
+        match relay::relay_status(ups_r, clt_w, &mut line_buf, config.greeting_timeout).await? {
+            Some(Response::Ok) => Ok(true),
+            Some(Response::Err) => Ok(false),
+            _ => Err(ServerTaskError::UpstreamAppError(anyhow!(
+                "invalid POP3 greeting line"
+            ))),
+        }
+    }
+
+    async fn relay_commands<CR, CW, UR, UW>(
+        &mut self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+    ) -> ServerTaskResult<SessionEnd>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncBufRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let command_wait_timeout = self.ctx.pop3_interception().command_wait_timeout;
+        let response_wait_timeout = self.ctx.pop3_interception().response_wait_timeout;
+
+        let mut cmd_line_buf = Vec::with_capacity(Command::MAX_LINE_SIZE);
+        let mut rsp_line_buf = Vec::with_capacity(Response::MAX_LINE_SIZE);
+        let mut pending_user: Option<String> = None;
+        loop {
+            let complete = relay::recv_cmd_line(
+                clt_r,
+                &mut cmd_line_buf,
+                Command::MAX_LINE_SIZE,
+                command_wait_timeout,
+            )
+            .await?;
+            relay::send_cmd(ups_w, &cmd_line_buf).await?;
+            if !complete {
+                return Ok(SessionEnd::Transparent);
+            }
+            let Ok(cmd) = Command::parse_line(&cmd_line_buf) else {
+                return Ok(SessionEnd::Transparent);
+            };
+
+            let mut rsp =
+                relay::relay_status(ups_r, clt_w, &mut rsp_line_buf, response_wait_timeout).await?;
+            if let Command::Auth(_) = cmd {
+                // relay the SASL exchange
+                while let Some(Response::Continuation) = rsp {
+                    let complete = relay::recv_cmd_line(
+                        clt_r,
+                        &mut cmd_line_buf,
+                        Command::MAX_CONTINUE_LINE_SIZE,
+                        command_wait_timeout,
+                    )
+                    .await?;
+                    relay::send_cmd(ups_w, &cmd_line_buf).await?;
+                    if !complete {
+                        return Ok(SessionEnd::Transparent);
+                    }
+                    rsp =
+                        relay::relay_status(ups_r, clt_w, &mut rsp_line_buf, response_wait_timeout)
+                            .await?;
+                }
+            }
+            let ok = match rsp {
+                Some(Response::Ok) => true,
+                Some(Response::Err) => false,
+                _ => return Ok(SessionEnd::Transparent),
+            };
+
+            if ok && cmd.has_multi_line_response() {
+                let retrieve = match &cmd {
+                    Command::Retrieve(msg) => Some(Retrieve::new("RETR", *msg)),
+                    Command::Top(msg) => Some(Retrieve::new("TOP", *msg)),
+                    _ => None,
+                };
+                let r =
+                    relay::relay_multi_line(ups_r, clt_w, &mut rsp_line_buf, response_wait_timeout)
+                        .await;
+                if let Some(mut retrieve) = retrieve {
+                    match &r {
+                        Ok(size) => {
+                            retrieve.set_data_size(*size);
+                            retrieve.log(&self.ctx, self.user.as_deref(), "ok");
+                        }
+                        Err(_) => retrieve.log(&self.ctx, self.user.as_deref(), "aborted"),
+                    }
+                }
+                r?;
+                continue;
+            }
+
+            match cmd {
+                Command::User(user) => {
+                    pending_user = ok.then_some(user);
+                }
+                Command::Pass => {
+                    if ok {
+                        self.user = pending_user.take();
+                        intercept_log!(self, "logged in");
+                    }
+                }
+                Command::Apop(user) => {
+                    if ok {
+                        self.user = Some(user);
+                        intercept_log!(self, "logged in");
+                    }
+                }
+                Command::Auth(Some(mechanism)) => {
+                    if ok {
+                        intercept_log!(self, "authenticated with {mechanism}");
+                    }
+                }
+                Command::Retrieve(msg) => {
+                    Retrieve::new("RETR", msg).log(&self.ctx, self.user.as_deref(), "err");
+                }
+                Command::Top(msg) => {
+                    Retrieve::new("TOP", msg).log(&self.ctx, self.user.as_deref(), "err");
+                }
+                Command::StartTls => {
+                    if ok {
+                        return if self.from_starttls {
+                            Ok(SessionEnd::Transparent)
+                        } else {
+                            Ok(SessionEnd::StartTls)
+                        };
+                    }
+                }
+                Command::Quit => return Ok(SessionEnd::Quit),
+                _ => {}
+            }
+        }
+    }
+}
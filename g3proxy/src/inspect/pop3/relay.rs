@@ -0,0 +1,155 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt};
+
+use g3_io_ext::LimitedBufReadExt;
+use g3_pop3_proto::response::Response;
+
+use crate::serve::{ServerTaskError, ServerTaskResult};
+
+/// the max line size in multi-line responses, longer lines will be relayed in pieces
+const MAX_DATA_LINE_SIZE: usize = 4096;
+
+pub(super) async fn send_cmd<UW>(ups_w: &mut UW, line: &[u8]) -> ServerTaskResult<()>
+where
+    UW: AsyncWrite + Unpin,
+{
+    ups_w
+        .write_all(line)
+        .await
+        .map_err(ServerTaskError::UpstreamWriteFailed)?;
+    ups_w
+        .flush()
+        .await
+        .map_err(ServerTaskError::UpstreamWriteFailed)
+}
+
+pub(super) async fn send_rsp<CW>(clt_w: &mut CW, line: &[u8]) -> ServerTaskResult<()>
+where
+    CW: AsyncWrite + Unpin,
+{
+    clt_w
+        .write_all(line)
+        .await
+        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+    clt_w
+        .flush()
+        .await
+        .map_err(ServerTaskError::ClientTcpWriteFailed)
+}
+
+/// Receive a command line from the client, the line will be put into `line_buf`.
+/// `false` will be returned if the line is too long, and the received data will be kept in `line_buf`
+pub(super) async fn recv_cmd_line<CR>(
+    clt_r: &mut CR,
+    line_buf: &mut Vec<u8>,
+    max_size: usize,
+    timeout: Duration,
+) -> ServerTaskResult<bool>
+where
+    CR: AsyncBufRead + Unpin,
+{
+    line_buf.clear();
+    match tokio::time::timeout(timeout, clt_r.limited_read_until(b'\n', max_size, line_buf)).await {
+        Ok(Ok((true, _))) => Ok(true),
+        Ok(Ok((false, nr))) => {
+            if nr > max_size {
+                Ok(false)
+            } else {
+                Err(ServerTaskError::ClosedByClient)
+            }
+        }
+        Ok(Err(e)) => Err(ServerTaskError::ClientTcpReadFailed(e)),
+        Err(_) => Err(ServerTaskError::ClientAppTimeout(
+            "timeout to wait POP3 command",
+        )),
+    }
+}
+
+/// Receive a response line from the upstream server, the line will be put into `line_buf`.
+/// `false` will be returned if the line is too long, and the received data will be kept in `line_buf`
+pub(super) async fn recv_rsp_line<UR>(
+    ups_r: &mut UR,
+    line_buf: &mut Vec<u8>,
+    max_size: usize,
+    timeout: Duration,
+) -> ServerTaskResult<bool>
+where
+    UR: AsyncBufRead + Unpin,
+{
+    line_buf.clear();
+    match tokio::time::timeout(timeout, ups_r.limited_read_until(b'\n', max_size, line_buf)).await {
+        Ok(Ok((true, _))) => Ok(true),
+        Ok(Ok((false, nr))) => {
+            if nr > max_size {
+                Ok(false)
+            } else {
+                Err(ServerTaskError::ClosedByUpstream)
+            }
+        }
+        Ok(Err(e)) => Err(ServerTaskError::UpstreamReadFailed(e)),
+        Err(_) => Err(ServerTaskError::UpstreamAppTimeout(
+            "timeout to wait POP3 response",
+        )),
+    }
+}
+
+/// Relay the status line of a response, `None` will be returned if it's not valid
+pub(super) async fn relay_status<UR, CW>(
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    line_buf: &mut Vec<u8>,
+    timeout: Duration,
+) -> ServerTaskResult<Option<Response>>
+where
+    UR: AsyncBufRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    let complete = recv_rsp_line(ups_r, line_buf, Response::MAX_LINE_SIZE, timeout).await?;
+    send_rsp(clt_w, line_buf).await?;
+    if !complete {
+        return Ok(None);
+    }
+    Ok(Response::parse_line(line_buf).ok())
+}
+
+/// Relay the lines after the status line of a multi-line response, until the termination line.
+/// The size of the data, not including the termination line, will be returned
+pub(super) async fn relay_multi_line<UR, CW>(
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    line_buf: &mut Vec<u8>,
+    timeout: Duration,
+) -> ServerTaskResult<u64>
+where
+    UR: AsyncBufRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    let mut data_size = 0u64;
+    let mut line_start = true;
+    loop {
+        let complete = recv_rsp_line(ups_r, line_buf, MAX_DATA_LINE_SIZE, timeout).await?;
+        send_rsp(clt_w, line_buf).await?;
+        if line_start && Response::is_multi_line_end(line_buf) {
+            return Ok(data_size);
+        }
+        data_size += line_buf.len() as u64;
+        line_start = complete;
+    }
+}
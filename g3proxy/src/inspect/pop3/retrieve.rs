@@ -0,0 +1,65 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use slog::slog_info;
+
+use g3_slog_types::LtUuid;
+
+use crate::config::server::ServerConfig;
+use crate::inspect::StreamInspectContext;
+
+macro_rules! intercept_log {
+    ($obj:tt, $ctx:expr, $user:expr, $($args:tt)+) => {
+        slog_info!($ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "Pop3Retrieve",
+            "task_id" => LtUuid($ctx.server_task_id()),
+            "depth" => $ctx.inspection_depth,
+            "user" => $user,
+            "command" => $obj.command,
+            "message" => $obj.message,
+            "data_size" => $obj.data_size,
+        )
+    };
+}
+
+pub(super) struct Retrieve {
+    command: &'static str,
+    message: u32,
+    data_size: u64,
+}
+
+impl Retrieve {
+    pub(super) fn new(command: &'static str, message: u32) -> Self {
+        Retrieve {
+            command,
+            message,
+            data_size: 0,
+        }
+    }
+
+    pub(super) fn set_data_size(&mut self, size: u64) {
+        self.data_size = size;
+    }
+
+    pub(super) fn log<SC: ServerConfig>(
+        &self,
+        ctx: &StreamInspectContext<SC>,
+        user: Option<&str>,
+        result: &str,
+    ) {
+        intercept_log!(self, ctx, user, "{result}");
+    }
+}
@@ -15,26 +15,30 @@
  */
 
 use std::net::IpAddr;
+use std::time::Duration;
 
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use anyhow::anyhow;
+use tokio::io::{AsyncRead, AsyncWrite};
 
 use g3_io_ext::LineRecvBuf;
 use g3_smtp_proto::command::Command;
-use g3_smtp_proto::response::{ReplyCode, ResponseParser};
 use g3_types::net::Host;
 
-use super::{CommandLineRecvExt, ResponseLineRecvExt, ResponseParseExt};
+use super::relay::{self, ResponseRecvBuf};
+use super::CommandLineRecvExt;
 use crate::serve::{ServerTaskError, ServerTaskResult};
 
 pub struct Initiation {
     local_ip: IpAddr,
+    allow_chunking: bool,
     client_host: Host,
 }
 
 impl Initiation {
-    pub(super) fn new(local_ip: IpAddr) -> Self {
+    pub(super) fn new(local_ip: IpAddr, allow_chunking: bool) -> Self {
         Initiation {
             local_ip,
+            allow_chunking,
             client_host: Host::empty(),
         }
     }
@@ -49,6 +53,8 @@ impl Initiation {
         clt_w: &mut CW,
         ups_r: &mut UR,
         ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+        rsp_timeout: Duration,
     ) -> ServerTaskResult<()>
     where
         CR: AsyncRead + Unpin,
@@ -57,10 +63,9 @@ impl Initiation {
         UW: AsyncWrite + Unpin,
     {
         let mut cmd_recv_buf = LineRecvBuf::<{ Command::MAX_LINE_SIZE }>::default();
-        let mut rsp_recv_buf = LineRecvBuf::<{ ResponseParser::MAX_LINE_SIZE }>::default();
 
         loop {
-            let Some(_cmd_line) = cmd_recv_buf
+            let relayed = cmd_recv_buf
                 .recv_cmd_and_relay(
                     clt_r,
                     clt_w,
@@ -79,73 +84,32 @@ impl Initiation {
                     self.local_ip,
                 )
                 .await?
-            else {
+                .is_some();
+            cmd_recv_buf.consume_line();
+            if !relayed {
                 continue;
-            };
+            }
 
-            if self
-                .relay_rsp(&mut rsp_recv_buf, ups_r, clt_w)
-                .await?
-                .is_some()
-            {
-                return Ok(());
+            let code = relay::relay_hello_rsp(
+                rsp_recv_buf,
+                ups_r,
+                clt_w,
+                self.local_ip,
+                self.allow_chunking,
+                rsp_timeout,
+            )
+            .await?;
+            if code.is_positive_completion() {
+                break;
             }
         }
-    }
 
-    async fn relay_rsp<CW, UR>(
-        &mut self,
-        rsp_recv_buf: &mut LineRecvBuf<{ ResponseParser::MAX_LINE_SIZE }>,
-        ups_r: &mut UR,
-        clt_w: &mut CW,
-    ) -> ServerTaskResult<Option<()>>
-    where
-        CW: AsyncWrite + Unpin,
-        UR: AsyncRead + Unpin,
-    {
-        let mut rsp = ResponseParser::default();
-        loop {
-            let line = rsp_recv_buf
-                .read_rsp_line_with_feedback(ups_r, clt_w, self.local_ip)
-                .await?;
-            rsp.feed_line_with_feedback(line, clt_w, self.local_ip)
-                .await?;
-
-            if rsp.code() == ReplyCode::OK {
-                if rsp.is_first_line() {
-                    clt_w
-                        .write_all(line)
-                        .await
-                        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
-                } else {
-                    // TODO filter out extension
-                    clt_w
-                        .write_all(line)
-                        .await
-                        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
-                }
-
-                if rsp.finished() {
-                    clt_w
-                        .flush()
-                        .await
-                        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
-                    return Ok(Some(()));
-                }
-            } else {
-                clt_w
-                    .write_all(line)
-                    .await
-                    .map_err(ServerTaskError::ClientTcpWriteFailed)?;
-
-                if rsp.finished() {
-                    clt_w
-                        .flush()
-                        .await
-                        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
-                    return Ok(None);
-                }
-            }
+        if !cmd_recv_buf.is_empty() {
+            // the client should wait for the response before sending more commands
+            return Err(ServerTaskError::ClientAppError(anyhow!(
+                "unexpected pipelined data after SMTP hello command"
+            )));
         }
+        Ok(())
     }
 }
@@ -14,17 +14,20 @@
  * limitations under the License.
  */
 
+use anyhow::anyhow;
 use slog::slog_info;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
 
 use g3_dpi::ProtocolInspectPolicy;
 use g3_io_ext::OnceBufReader;
 use g3_slog_types::{LtHost, LtUuid};
+use g3_smtp_proto::command::{Command, CommandLineError};
 use g3_smtp_proto::response::{ReplyCode, ResponseEncoder};
-use g3_types::net::Host;
+use g3_types::net::{Host, UpstreamAddr};
 
+use super::tls::{StartTlsProtocol, TlsInterceptObject};
 use crate::config::server::ServerConfig;
-use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext};
+use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext, StreamInspection};
 use crate::serve::{ServerTaskError, ServerTaskResult};
 
 mod ext;
@@ -39,6 +42,12 @@ use ending::{EndQuitServer, EndWaitClient};
 mod initiation;
 use initiation::Initiation;
 
+mod relay;
+use relay::ResponseRecvBuf;
+
+mod transaction;
+use transaction::Transaction;
+
 macro_rules! intercept_log {
     ($obj:tt, $($args:tt)+) => {
         slog_info!($obj.ctx.intercept_logger(), $($args)+;
@@ -61,20 +70,28 @@ struct SmtpIo {
 pub(crate) struct SmtpInterceptObject<SC: ServerConfig> {
     io: Option<SmtpIo>,
     pub(crate) ctx: StreamInspectContext<SC>,
+    upstream: UpstreamAddr,
+    from_starttls: bool,
     upstream_host: Option<Host>,
     client_host: Option<Host>,
 }
 
 impl<SC: ServerConfig> SmtpInterceptObject<SC> {
-    pub(crate) fn new(ctx: StreamInspectContext<SC>) -> Self {
+    pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
         SmtpInterceptObject {
             io: None,
             ctx,
+            upstream,
+            from_starttls: false,
             upstream_host: None,
             client_host: None,
         }
     }
 
+    pub(crate) fn set_from_starttls(&mut self) {
+        self.from_starttls = true;
+    }
+
     pub(crate) fn set_io(
         &mut self,
         clt_r: BoxAsyncRead,
@@ -91,19 +108,30 @@ impl<SC: ServerConfig> SmtpInterceptObject<SC> {
         self.io = Some(io);
     }
 
-    pub(crate) async fn intercept(mut self) -> ServerTaskResult<()> {
+    pub(crate) async fn intercept(mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
         match self.ctx.smtp_inspect_policy() {
-            ProtocolInspectPolicy::Bypass => self.do_bypass().await,
-            ProtocolInspectPolicy::Intercept => {
-                if let Err(e) = self.do_intercept().await {
+            ProtocolInspectPolicy::Bypass => {
+                self.do_bypass().await?;
+                Ok(None)
+            }
+            ProtocolInspectPolicy::Intercept => match self.do_intercept().await {
+                Ok(Some(obj)) => {
+                    intercept_log!(self, "starttls");
+                    Ok(Some(obj))
+                }
+                Ok(None) => {
+                    intercept_log!(self, "finished");
+                    Ok(None)
+                }
+                Err(e) => {
                     intercept_log!(self, "{e}");
                     Err(e)
-                } else {
-                    intercept_log!(self, "finished");
-                    Ok(())
                 }
+            },
+            ProtocolInspectPolicy::Block => {
+                self.do_block().await?;
+                Ok(None)
             }
-            ProtocolInspectPolicy::Block => self.do_block().await,
         }
     }
 
@@ -148,7 +176,7 @@ impl<SC: ServerConfig> SmtpInterceptObject<SC> {
         EndWaitClient::new(local_ip).run_to_end(clt_r, clt_w).await
     }
 
-    async fn do_intercept(&mut self) -> ServerTaskResult<()> {
+    async fn do_intercept(&mut self) -> ServerTaskResult<Option<StreamInspection<SC>>> {
         let SmtpIo {
             clt_r,
             mut clt_w,
@@ -156,6 +184,13 @@ impl<SC: ServerConfig> SmtpInterceptObject<SC> {
             ups_w,
         } = self.io.take().unwrap();
 
+        if self.from_starttls {
+            // the greeting has already been sent before STARTTLS
+            return self
+                .start_initiation(clt_r, clt_w, ups_r.into_inner(), ups_w)
+                .await;
+        }
+
         let interception_config = self.ctx.smtp_interception();
         let local_ip = self.ctx.task_notes.server_addr.ip();
 
@@ -177,7 +212,10 @@ impl<SC: ServerConfig> SmtpInterceptObject<SC> {
             tokio::spawn(async move {
                 let _ = EndQuitServer::run_to_end(ups_r, ups_w, timeout).await;
             });
-            return EndWaitClient::new(local_ip).run_to_end(clt_r, clt_w).await;
+            EndWaitClient::new(local_ip)
+                .run_to_end(clt_r, clt_w)
+                .await?;
+            return Ok(None);
         }
 
         self.start_initiation(clt_r, clt_w, ups_r, ups_w).await
@@ -189,24 +227,373 @@ impl<SC: ServerConfig> SmtpInterceptObject<SC> {
         mut clt_w: BoxAsyncWrite,
         mut ups_r: BoxAsyncRead,
         mut ups_w: BoxAsyncWrite,
-    ) -> ServerTaskResult<()> {
+    ) -> ServerTaskResult<Option<StreamInspection<SC>>> {
         let local_ip = self.ctx.task_notes.server_addr.ip();
+        let interception_config = self.ctx.smtp_interception();
+        // BDAT data can not be sent to the ICAP server, so disable CHUNKING if ICAP is enabled
+        let allow_chunking = self.ctx.audit_handle.icap_reqmod_client().is_none();
 
-        let mut initiation = Initiation::new(local_ip);
+        let mut rsp_recv_buf = ResponseRecvBuf::default();
+        let mut initiation = Initiation::new(local_ip, allow_chunking);
         initiation
-            .relay(&mut clt_r, &mut clt_w, &mut ups_r, &mut ups_w)
+            .relay(
+                &mut clt_r,
+                &mut clt_w,
+                &mut ups_r,
+                &mut ups_w,
+                &mut rsp_recv_buf,
+                interception_config.response_wait_timeout,
+            )
             .await?;
         self.client_host = Some(initiation.into_parts());
 
-        crate::inspect::stream::transit_transparent(
-            clt_r,
-            clt_w,
-            ups_r,
-            ups_w,
-            &self.ctx.server_config,
-            &self.ctx.server_quit_policy,
-            self.ctx.user(),
-        )
+        let mut clt_r = BufReader::new(clt_r);
+        let next = self
+            .run_session(
+                &mut clt_r,
+                &mut clt_w,
+                &mut ups_r,
+                &mut ups_w,
+                &mut rsp_recv_buf,
+                allow_chunking,
+            )
+            .await?;
+        match next {
+            SessionEnd::Quit => Ok(None),
+            SessionEnd::StartTls => {
+                if !rsp_recv_buf.is_empty() {
+                    return Err(ServerTaskError::UpstreamAppError(anyhow!(
+                        "unexpected data after SMTP STARTTLS response"
+                    )));
+                }
+                if !clt_r.buffer().is_empty() {
+                    return Err(ServerTaskError::ClientAppError(anyhow!(
+                        "unexpected pipelined data after SMTP STARTTLS command"
+                    )));
+                }
+                let clt_r = clt_r.into_inner();
+                if !self.from_starttls {
+                    if let Some(tls_interception) = self.ctx.tls_interception() {
+                        let mut tls_obj = TlsInterceptObject::new(
+                            self.ctx.clone(),
+                            self.upstream.clone(),
+                            tls_interception,
+                        );
+                        tls_obj.set_start_tls_protocol(StartTlsProtocol::Smtp);
+                        tls_obj.set_io(OnceBufReader::with_no_buf(clt_r), clt_w, ups_r, ups_w);
+                        return Ok(Some(StreamInspection::TlsModern(tls_obj)));
+                    }
+                }
+                crate::inspect::stream::transit_transparent(
+                    clt_r,
+                    clt_w,
+                    ups_r,
+                    ups_w,
+                    &self.ctx.server_config,
+                    &self.ctx.server_quit_policy,
+                    self.ctx.user(),
+                )
+                .await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn run_session<CR, CW, UR, UW>(
+        &self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+        allow_chunking: bool,
+    ) -> ServerTaskResult<SessionEnd>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let mut transaction: Option<Transaction<SC>> = None;
+        let mut transaction_id = 0usize;
+
+        let r = self
+            .relay_commands(
+                clt_r,
+                clt_w,
+                ups_r,
+                ups_w,
+                rsp_recv_buf,
+                allow_chunking,
+                &mut transaction,
+                &mut transaction_id,
+            )
+            .await;
+        if let Some(t) = transaction.take() {
+            t.end("aborted");
+        }
+        r
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn relay_commands<'a, CR, CW, UR, UW>(
+        &'a self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+        allow_chunking: bool,
+        transaction: &mut Option<Transaction<'a, SC>>,
+        transaction_id: &mut usize,
+    ) -> ServerTaskResult<SessionEnd>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let local_ip = self.ctx.task_notes.server_addr.ip();
+        let config = self.ctx.smtp_interception();
+
+        let mut line_buf = Vec::with_capacity(Command::MAX_LINE_SIZE);
+        loop {
+            relay::recv_cmd_line(
+                clt_r,
+                clt_w,
+                &mut line_buf,
+                Command::MAX_LINE_SIZE,
+                config.command_wait_timeout,
+            )
+            .await?;
+
+            let cmd = match Command::parse_line(&line_buf) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    ResponseEncoder::from(&e)
+                        .write(clt_w)
+                        .await
+                        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                    if matches!(e, CommandLineError::InvalidChunkSize) {
+                        // we can not skip the chunk data
+                        return Err(ServerTaskError::ClientAppError(anyhow!(
+                            "invalid SMTP command line: {e}"
+                        )));
+                    }
+                    continue;
+                }
+            };
+
+            match cmd {
+                Command::QUIT => {
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    relay::relay_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        config.quit_wait_timeout,
+                    )
+                    .await?;
+                    return Ok(SessionEnd::Quit);
+                }
+                Command::StartTls => {
+                    if let Some(t) = transaction.take() {
+                        t.end("reset by STARTTLS");
+                    }
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    let code = relay::relay_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                    if code == ReplyCode::SERVICE_READY {
+                        return Ok(SessionEnd::StartTls);
+                    }
+                }
+                Command::Auth => {
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    let mut code = relay::relay_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                    while code == ReplyCode::AUTH_CONTINUE {
+                        relay::recv_cmd_line(
+                            clt_r,
+                            clt_w,
+                            &mut line_buf,
+                            Command::MAX_CONTINUE_LINE_SIZE,
+                            config.command_wait_timeout,
+                        )
+                        .await?;
+                        relay::send_cmd(ups_w, &line_buf).await?;
+                        code = relay::relay_rsp(
+                            rsp_recv_buf,
+                            ups_r,
+                            clt_w,
+                            local_ip,
+                            config.response_wait_timeout,
+                        )
+                        .await?;
+                    }
+                }
+                Command::ExtendHello(_) | Command::Hello(_) => {
+                    if let Some(t) = transaction.take() {
+                        t.end("reset by hello command");
+                    }
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    relay::relay_hello_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        allow_chunking,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                }
+                Command::MailFrom(from) => {
+                    if transaction.is_some() {
+                        ResponseEncoder::BAD_SEQUENCE_OF_COMMANDS
+                            .write(clt_w)
+                            .await
+                            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                        continue;
+                    }
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    let code = relay::relay_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                    if code.is_positive_completion() {
+                        *transaction_id += 1;
+                        *transaction = Some(Transaction::new(&self.ctx, *transaction_id, from));
+                    }
+                }
+                Command::Recipient(to) => {
+                    let Some(t) = transaction else {
+                        ResponseEncoder::BAD_SEQUENCE_OF_COMMANDS
+                            .write(clt_w)
+                            .await
+                            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                        continue;
+                    };
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    let code = relay::relay_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                    if code.is_positive_completion() {
+                        t.add_recipient(to);
+                    }
+                }
+                Command::Data => {
+                    let Some(t) = transaction.as_mut().filter(|t| t.has_recipient()) else {
+                        ResponseEncoder::BAD_SEQUENCE_OF_COMMANDS
+                            .write(clt_w)
+                            .await
+                            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                        continue;
+                    };
+                    if t.relay_data(&line_buf, clt_r, clt_w, ups_r, ups_w, rsp_recv_buf)
+                        .await?
+                    {
+                        *transaction = None;
+                    }
+                }
+                Command::BinaryData(size) | Command::LastBinaryData(size) => {
+                    let last = matches!(cmd, Command::LastBinaryData(_));
+                    let t = match transaction.as_mut() {
+                        Some(t) if allow_chunking => t,
+                        _ => {
+                            skip_chunk(clt_r, size).await?;
+                            let rsp = if allow_chunking {
+                                ResponseEncoder::BAD_SEQUENCE_OF_COMMANDS
+                            } else {
+                                ResponseEncoder::COMMAND_NOT_IMPLEMENTED
+                            };
+                            rsp.write(clt_w)
+                                .await
+                                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                            continue;
+                        }
+                    };
+                    if t.relay_chunk(
+                        &line_buf,
+                        size,
+                        last,
+                        clt_r,
+                        clt_w,
+                        ups_r,
+                        ups_w,
+                        rsp_recv_buf,
+                    )
+                    .await?
+                    {
+                        *transaction = None;
+                    }
+                }
+                Command::Reset => {
+                    if let Some(t) = transaction.take() {
+                        t.end("reset");
+                    }
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    relay::relay_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                }
+                Command::Unknown(_) => {
+                    relay::send_cmd(ups_w, &line_buf).await?;
+                    relay::relay_rsp(
+                        rsp_recv_buf,
+                        ups_r,
+                        clt_w,
+                        local_ip,
+                        config.response_wait_timeout,
+                    )
+                    .await?;
+                }
+            }
+        }
+    }
+}
+
+enum SessionEnd {
+    Quit,
+    StartTls,
+}
+
+async fn skip_chunk<R>(clt_r: &mut R, size: usize) -> ServerTaskResult<()>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut chunk_reader = clt_r.take(size as u64);
+    let nr = tokio::io::copy(&mut chunk_reader, &mut tokio::io::sink())
         .await
+        .map_err(ServerTaskError::ClientTcpReadFailed)?;
+    if nr < size as u64 {
+        Err(ServerTaskError::ClosedByClient)
+    } else {
+        Ok(())
     }
 }
@@ -0,0 +1,240 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use g3_io_ext::{LimitedBufReadExt, LineRecvBuf, RecvLineError};
+use g3_smtp_proto::response::{ReplyCode, ResponseEncoder, ResponseParser};
+
+use super::{ResponseLineRecvExt, ResponseParseExt};
+use crate::serve::{ServerTaskError, ServerTaskResult};
+
+pub(super) type ResponseRecvBuf = LineRecvBuf<{ ResponseParser::MAX_LINE_SIZE }>;
+
+pub(super) async fn send_cmd<UW>(ups_w: &mut UW, line: &[u8]) -> ServerTaskResult<()>
+where
+    UW: AsyncWrite + Unpin,
+{
+    ups_w
+        .write_all(line)
+        .await
+        .map_err(ServerTaskError::UpstreamWriteFailed)?;
+    ups_w
+        .flush()
+        .await
+        .map_err(ServerTaskError::UpstreamWriteFailed)
+}
+
+/// Receive a command line from the client, the line will be put into `line_buf`
+pub(super) async fn recv_cmd_line<CR, CW>(
+    clt_r: &mut CR,
+    clt_w: &mut CW,
+    line_buf: &mut Vec<u8>,
+    max_size: usize,
+    timeout: Duration,
+) -> ServerTaskResult<()>
+where
+    CR: AsyncBufRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    line_buf.clear();
+    match tokio::time::timeout(timeout, clt_r.limited_read_until(b'\n', max_size, line_buf)).await {
+        Ok(Ok((true, _))) => Ok(()),
+        Ok(Ok((false, nr))) => {
+            if nr > max_size {
+                let _ = ResponseEncoder::COMMAND_LINE_TOO_LONG.write(clt_w).await;
+                Err(ServerTaskError::ClientAppError(anyhow!(
+                    "SMTP command line too long"
+                )))
+            } else {
+                Err(ServerTaskError::ClosedByClient)
+            }
+        }
+        Ok(Err(e)) => Err(ServerTaskError::ClientTcpReadFailed(e)),
+        Err(_) => Err(ServerTaskError::ClientAppTimeout(
+            "timeout to wait SMTP command",
+        )),
+    }
+}
+
+/// Relay the response from the upstream server to the client
+pub(super) async fn relay_rsp<UR, CW>(
+    rsp_recv_buf: &mut ResponseRecvBuf,
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    local_ip: IpAddr,
+    timeout: Duration,
+) -> ServerTaskResult<ReplyCode>
+where
+    UR: AsyncRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    relay_hello_rsp(rsp_recv_buf, ups_r, clt_w, local_ip, true, timeout).await
+}
+
+/// Relay the response to EHLO / HELO command, the CHUNKING and BINARYMIME extensions will be
+/// removed if `allow_chunking` is false
+pub(super) async fn relay_hello_rsp<UR, CW>(
+    rsp_recv_buf: &mut ResponseRecvBuf,
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    local_ip: IpAddr,
+    allow_chunking: bool,
+    timeout: Duration,
+) -> ServerTaskResult<ReplyCode>
+where
+    UR: AsyncRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    match tokio::time::timeout(
+        timeout,
+        relay_rsp_lines(rsp_recv_buf, ups_r, clt_w, local_ip, allow_chunking),
+    )
+    .await
+    {
+        Ok(r) => r,
+        Err(_) => {
+            let _ = ResponseEncoder::upstream_service_not_ready(local_ip, "response timeout")
+                .write(clt_w)
+                .await;
+            Err(ServerTaskError::UpstreamAppTimeout(
+                "timeout to wait SMTP response",
+            ))
+        }
+    }
+}
+
+async fn relay_rsp_lines<UR, CW>(
+    rsp_recv_buf: &mut ResponseRecvBuf,
+    ups_r: &mut UR,
+    clt_w: &mut CW,
+    local_ip: IpAddr,
+    allow_chunking: bool,
+) -> ServerTaskResult<ReplyCode>
+where
+    UR: AsyncRead + Unpin,
+    CW: AsyncWrite + Unpin,
+{
+    let mut rsp = ResponseParser::default();
+    // the message of the last kept line, which is delayed as we may need to change the delimiter
+    let mut pending_msg: Option<Vec<u8>> = None;
+    loop {
+        let line = rsp_recv_buf
+            .read_rsp_line_with_feedback(ups_r, clt_w, local_ip)
+            .await?;
+        let msg = rsp.feed_line_with_feedback(line, clt_w, local_ip).await?;
+
+        if !allow_chunking && rsp.code() == ReplyCode::OK {
+            if rsp.is_first_line() || !is_chunking_extension(msg) {
+                if let Some(prev) = pending_msg.replace(msg.to_vec()) {
+                    write_ok_line(clt_w, &prev, false).await?;
+                }
+            }
+            if rsp.finished() {
+                if let Some(last) = pending_msg.take() {
+                    write_ok_line(clt_w, &last, true).await?;
+                }
+            }
+        } else {
+            clt_w
+                .write_all(line)
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+        }
+        rsp_recv_buf.consume_line();
+
+        if rsp.finished() {
+            clt_w
+                .flush()
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+            return Ok(rsp.code());
+        }
+    }
+}
+
+fn is_chunking_extension(msg: &[u8]) -> bool {
+    let keyword = match memchr::memchr(b' ', msg) {
+        Some(p) => &msg[..p],
+        None => msg,
+    };
+    keyword.eq_ignore_ascii_case(b"CHUNKING") || keyword.eq_ignore_ascii_case(b"BINARYMIME")
+}
+
+async fn write_ok_line<W>(clt_w: &mut W, msg: &[u8], last: bool) -> ServerTaskResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = Vec::with_capacity(msg.len() + 6);
+    buf.extend_from_slice(if last { b"250 " } else { b"250-" });
+    buf.extend_from_slice(msg);
+    buf.extend_from_slice(b"\r\n");
+    clt_w
+        .write_all(&buf)
+        .await
+        .map_err(ServerTaskError::ClientTcpWriteFailed)
+}
+
+/// Receive the response from the upstream server without relaying it to the client,
+/// the raw response lines will also be returned
+pub(super) async fn recv_rsp<UR>(
+    rsp_recv_buf: &mut ResponseRecvBuf,
+    ups_r: &mut UR,
+    timeout: Duration,
+) -> ServerTaskResult<(ReplyCode, Vec<u8>)>
+where
+    UR: AsyncRead + Unpin,
+{
+    match tokio::time::timeout(timeout, recv_rsp_lines(rsp_recv_buf, ups_r)).await {
+        Ok(r) => r,
+        Err(_) => Err(ServerTaskError::UpstreamAppTimeout(
+            "timeout to wait SMTP response",
+        )),
+    }
+}
+
+async fn recv_rsp_lines<UR>(
+    rsp_recv_buf: &mut ResponseRecvBuf,
+    ups_r: &mut UR,
+) -> ServerTaskResult<(ReplyCode, Vec<u8>)>
+where
+    UR: AsyncRead + Unpin,
+{
+    let mut rsp = ResponseParser::default();
+    let mut raw = Vec::new();
+    loop {
+        let line = rsp_recv_buf.read_line(ups_r).await.map_err(|e| match e {
+            RecvLineError::IoError(e) => ServerTaskError::UpstreamReadFailed(e),
+            RecvLineError::IoClosed => ServerTaskError::ClosedByUpstream,
+            RecvLineError::LineTooLong => {
+                ServerTaskError::UpstreamAppError(anyhow!("SMTP response line too long"))
+            }
+        })?;
+        rsp.feed_line(line).map_err(|e| {
+            ServerTaskError::UpstreamAppError(anyhow!("invalid SMTP response line: {e}"))
+        })?;
+        raw.extend_from_slice(line);
+        rsp_recv_buf.consume_line();
+
+        if rsp.finished() {
+            return Ok((rsp.code(), raw));
+        }
+    }
+}
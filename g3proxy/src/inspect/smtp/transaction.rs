@@ -0,0 +1,494 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::IpAddr;
+
+use anyhow::anyhow;
+use slog::slog_info;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::time::Instant;
+
+use g3_icap_client::reqmod::mail::{
+    SmtpAdaptationEndState, SmtpAdaptedMessageBody, SmtpMessageAdapter,
+};
+use g3_io_ext::{LimitedCopy, LimitedCopyError};
+use g3_slog_types::LtUuid;
+use g3_smtp_proto::io::{TextDataEncoder, TextDataReader};
+use g3_smtp_proto::response::{ReplyCode, ResponseEncoder};
+
+use super::relay::{self, ResponseRecvBuf};
+use crate::config::server::ServerConfig;
+use crate::inspect::StreamInspectContext;
+use crate::serve::{ServerIdleChecker, ServerTaskError, ServerTaskResult};
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => "SmtpTransaction",
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "transaction_id" => $obj.transaction_id,
+            "mail_from" => &$obj.mail_from,
+            "mail_to" => $obj.mail_to.join(","),
+            "data_size" => $obj.data_size,
+            "data_reply" => $obj.data_reply.map(|c| c.as_u16()),
+        )
+    };
+}
+
+pub(super) struct Transaction<'a, SC: ServerConfig> {
+    ctx: &'a StreamInspectContext<SC>,
+    transaction_id: usize,
+    local_ip: IpAddr,
+    mail_from: String,
+    mail_to: Vec<String>,
+    data_size: u64,
+    data_reply: Option<ReplyCode>,
+}
+
+impl<'a, SC: ServerConfig> Transaction<'a, SC> {
+    pub(super) fn new(
+        ctx: &'a StreamInspectContext<SC>,
+        transaction_id: usize,
+        mail_from: String,
+    ) -> Self {
+        Transaction {
+            ctx,
+            transaction_id,
+            local_ip: ctx.task_notes.server_addr.ip(),
+            mail_from,
+            mail_to: Vec::new(),
+            data_size: 0,
+            data_reply: None,
+        }
+    }
+
+    pub(super) fn add_recipient(&mut self, to: String) {
+        self.mail_to.push(to);
+    }
+
+    pub(super) fn has_recipient(&self) -> bool {
+        !self.mail_to.is_empty()
+    }
+
+    /// End the transaction without a complete mail data transfer
+    pub(super) fn end(self, reason: &str) {
+        intercept_log!(self, "{reason}");
+    }
+
+    /// Relay the mail data after a DATA command.
+    ///
+    /// Return true if the transaction is ended.
+    pub(super) async fn relay_data<CR, CW, UR, UW>(
+        &mut self,
+        cmd_line: &[u8],
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+    ) -> ServerTaskResult<bool>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        if let Some(reqmod_client) = self.ctx.audit_handle.icap_reqmod_client() {
+            match reqmod_client
+                .smtp_message_adapter(
+                    self.ctx.server_config.limited_copy_config(),
+                    self.ctx.idle_checker(),
+                )
+                .await
+            {
+                Ok(mut adapter) => {
+                    adapter.set_client_addr(self.ctx.task_notes.client_addr);
+                    if let Some(username) = self.ctx.raw_user_name() {
+                        adapter.set_client_username(username);
+                    }
+                    self.relay_data_with_adaptation(
+                        adapter,
+                        clt_r,
+                        clt_w,
+                        ups_r,
+                        ups_w,
+                        rsp_recv_buf,
+                    )
+                    .await
+                }
+                Err(e) => {
+                    if reqmod_client.bypass() {
+                        self.relay_data_transparent(
+                            cmd_line,
+                            clt_r,
+                            clt_w,
+                            ups_r,
+                            ups_w,
+                            rsp_recv_buf,
+                        )
+                        .await
+                    } else {
+                        intercept_log!(self, "failed to create icap adapter: {e:?}");
+                        ResponseEncoder::local_processing_error(
+                            self.local_ip,
+                            "message adaptation unavailable",
+                        )
+                        .write(clt_w)
+                        .await
+                        .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                        Ok(false)
+                    }
+                }
+            }
+        } else {
+            self.relay_data_transparent(cmd_line, clt_r, clt_w, ups_r, ups_w, rsp_recv_buf)
+                .await
+        }
+    }
+
+    async fn relay_data_transparent<CR, CW, UR, UW>(
+        &mut self,
+        cmd_line: &[u8],
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+    ) -> ServerTaskResult<bool>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.smtp_interception();
+
+        relay::send_cmd(ups_w, cmd_line).await?;
+        let code = relay::relay_rsp(
+            rsp_recv_buf,
+            ups_r,
+            clt_w,
+            self.local_ip,
+            config.data_initiation_timeout,
+        )
+        .await?;
+        if code != ReplyCode::START_MAIL_INPUT {
+            return Ok(false);
+        }
+
+        let mut data_reader = TextDataReader::new(clt_r);
+        let r = self.copy_data(&mut data_reader, ups_w).await;
+        self.data_size = data_reader.read_size();
+        r?;
+
+        let code = relay::relay_rsp(
+            rsp_recv_buf,
+            ups_r,
+            clt_w,
+            self.local_ip,
+            config.data_termination_timeout,
+        )
+        .await?;
+        self.data_reply = Some(code);
+        intercept_log!(self, "finished");
+        Ok(true)
+    }
+
+    async fn relay_data_with_adaptation<CR, CW, UR, UW>(
+        &mut self,
+        adapter: SmtpMessageAdapter<ServerIdleChecker>,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+    ) -> ServerTaskResult<bool>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.smtp_interception();
+
+        ResponseEncoder::START_MAIL_INPUT
+            .write(clt_w)
+            .await
+            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+
+        let mut msg_reader = BufReader::new(TextDataReader::new_decoded(clt_r));
+        let adapt_result = adapter.xfer(&mut msg_reader).await;
+        let mut data_reader = msg_reader.into_inner();
+        if !data_reader.finished() {
+            // drain all the left mail data, so we can continue the session
+            let r = tokio::time::timeout(
+                config.data_termination_timeout,
+                tokio::io::copy(&mut data_reader, &mut tokio::io::sink()),
+            )
+            .await;
+            self.data_size = data_reader.read_size();
+            match r {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(ServerTaskError::ClientTcpReadFailed(e)),
+                Err(_) => {
+                    return Err(ServerTaskError::ClientAppTimeout(
+                        "timeout to read SMTP mail data",
+                    ))
+                }
+            }
+        } else {
+            self.data_size = data_reader.read_size();
+        }
+
+        match adapt_result {
+            Ok(SmtpAdaptationEndState::AdaptedMessage(body)) => {
+                self.send_adapted_message(body, clt_w, ups_r, ups_w, rsp_recv_buf)
+                    .await?;
+                intercept_log!(self, "finished");
+            }
+            Ok(SmtpAdaptationEndState::HttpErrResponse(rsp)) => {
+                let reason = format!("{} {}", rsp.status.as_u16(), rsp.reason);
+                ResponseEncoder::message_blocked(self.local_ip, &reason)
+                    .write(clt_w)
+                    .await
+                    .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                intercept_log!(self, "blocked by icap server: {reason}");
+                self.reset_upstream(ups_r, ups_w, rsp_recv_buf).await?;
+            }
+            Err(e) => {
+                let e = ServerTaskError::from(e);
+                if !matches!(e, ServerTaskError::InternalAdapterError(_)) {
+                    return Err(e);
+                }
+                // the original message has been consumed, so we can only reply an error here
+                intercept_log!(self, "{e}");
+                ResponseEncoder::local_processing_error(self.local_ip, "message adaptation failed")
+                    .write(clt_w)
+                    .await
+                    .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                self.reset_upstream(ups_r, ups_w, rsp_recv_buf).await?;
+            }
+        }
+        Ok(true)
+    }
+
+    async fn send_adapted_message<CW, UR, UW>(
+        &mut self,
+        mut body: SmtpAdaptedMessageBody,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+    ) -> ServerTaskResult<()>
+    where
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.smtp_interception();
+
+        relay::send_cmd(ups_w, b"DATA\r\n").await?;
+        let (code, rsp) =
+            relay::recv_rsp(rsp_recv_buf, ups_r, config.data_initiation_timeout).await?;
+        if code != ReplyCode::START_MAIL_INPUT {
+            // use the upstream reply as the final reply to the client
+            self.data_reply = Some(code);
+            clt_w
+                .write_all(&rsp)
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+            return clt_w
+                .flush()
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed);
+        }
+
+        let mut body_reader = body.body_reader();
+        let mut encoder = TextDataEncoder::default();
+        let mut buf = vec![0u8; self.ctx.server_config.limited_copy_config().buffer_size()];
+        let mut encoded = Vec::with_capacity(buf.len() + 64);
+        let send_all = async move {
+            loop {
+                let nr = body_reader.read(&mut buf).await.map_err(|e| {
+                    ServerTaskError::InternalAdapterError(anyhow!(
+                        "read adapted message from icap server failed: {e:?}"
+                    ))
+                })?;
+                encoded.clear();
+                if nr == 0 {
+                    encoder.finish(&mut encoded);
+                } else {
+                    encoder.encode(&buf[..nr], &mut encoded);
+                }
+                ups_w
+                    .write_all(&encoded)
+                    .await
+                    .map_err(ServerTaskError::UpstreamWriteFailed)?;
+                if nr == 0 {
+                    break;
+                }
+            }
+            ups_w
+                .flush()
+                .await
+                .map_err(ServerTaskError::UpstreamWriteFailed)
+        };
+        match tokio::time::timeout(config.data_termination_timeout, send_all).await {
+            Ok(r) => r?,
+            Err(_) => {
+                return Err(ServerTaskError::UpstreamAppTimeout(
+                    "timeout to send SMTP mail data",
+                ))
+            }
+        }
+
+        let code = relay::relay_rsp(
+            rsp_recv_buf,
+            ups_r,
+            clt_w,
+            self.local_ip,
+            config.data_termination_timeout,
+        )
+        .await?;
+        self.data_reply = Some(code);
+        body.save_connection().await;
+        Ok(())
+    }
+
+    async fn reset_upstream<UR, UW>(
+        &self,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+    ) -> ServerTaskResult<()>
+    where
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        relay::send_cmd(ups_w, b"RSET\r\n").await?;
+        let (code, _) = relay::recv_rsp(
+            rsp_recv_buf,
+            ups_r,
+            self.ctx.smtp_interception().response_wait_timeout,
+        )
+        .await?;
+        if code.is_positive_completion() {
+            Ok(())
+        } else {
+            Err(ServerTaskError::UpstreamAppError(anyhow!(
+                "unexpected SMTP RSET response code: {code}"
+            )))
+        }
+    }
+
+    /// Relay a BDAT chunk of `size` bytes to the upstream server.
+    ///
+    /// Return true if the transaction is ended.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn relay_chunk<CR, CW, UR, UW>(
+        &mut self,
+        cmd_line: &[u8],
+        size: usize,
+        last: bool,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+        rsp_recv_buf: &mut ResponseRecvBuf,
+    ) -> ServerTaskResult<bool>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let config = self.ctx.smtp_interception();
+
+        relay::send_cmd(ups_w, cmd_line).await?;
+        let mut chunk_reader = clt_r.take(size as u64);
+        let copied = self.copy_data(&mut chunk_reader, ups_w).await?;
+        self.data_size += copied;
+        if copied < size as u64 {
+            return Err(ServerTaskError::ClosedByClient);
+        }
+
+        let timeout = if last {
+            config.data_termination_timeout
+        } else {
+            config.response_wait_timeout
+        };
+        let code = relay::relay_rsp(rsp_recv_buf, ups_r, clt_w, self.local_ip, timeout).await?;
+        if last {
+            self.data_reply = Some(code);
+            intercept_log!(self, "finished");
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn copy_data<R, W>(&self, reader: &mut R, ups_w: &mut W) -> ServerTaskResult<u64>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut clt_to_ups =
+            LimitedCopy::new(reader, ups_w, &self.ctx.server_config.limited_copy_config());
+
+        let idle_duration = self.ctx.server_config.task_idle_check_duration();
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        let max_idle_count = self.ctx.task_max_idle_count();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                r = &mut clt_to_ups => {
+                    return r.map_err(|e| match e {
+                        LimitedCopyError::ReadFailed(e) => ServerTaskError::ClientTcpReadFailed(e),
+                        LimitedCopyError::WriteFailed(e) => ServerTaskError::UpstreamWriteFailed(e),
+                    });
+                }
+                _ = idle_interval.tick() => {
+                    if clt_to_ups.is_idle() {
+                        idle_count += 1;
+                        if idle_count >= max_idle_count {
+                            return if clt_to_ups.no_cached_data() {
+                                Err(ServerTaskError::ClientAppTimeout("idle while reading mail data"))
+                            } else {
+                                Err(ServerTaskError::UpstreamAppTimeout("idle while sending mail data"))
+                            };
+                        }
+                    } else {
+                        idle_count = 0;
+                        clt_to_ups.reset_active();
+                    }
+
+                    if self.ctx.belongs_to_blocked_user() {
+                        return Err(ServerTaskError::CanceledAsUserBlocked);
+                    }
+
+                    if self.ctx.server_force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit)
+                    }
+                }
+            }
+        }
+    }
+}
@@ -304,9 +304,27 @@ where
                 StreamInspection::Websocket(websocket) => {
                     return websocket.intercept().await;
                 }
-                StreamInspection::Smtp(smtp) => {
-                    return smtp.intercept().await;
-                }
+                StreamInspection::Smtp(smtp) => match smtp.intercept().await? {
+                    Some(new_obj) => {
+                        obj = new_obj;
+                        inspector.reset_state();
+                    }
+                    None => break,
+                },
+                StreamInspection::Imap(imap) => match imap.intercept().await? {
+                    Some(new_obj) => {
+                        obj = new_obj;
+                        inspector.reset_state();
+                    }
+                    None => break,
+                },
+                StreamInspection::Pop3(pop3) => match pop3.intercept().await? {
+                    Some(new_obj) => {
+                        obj = new_obj;
+                        inspector.reset_state();
+                    }
+                    None => break,
+                },
                 StreamInspection::End => break,
             }
         }
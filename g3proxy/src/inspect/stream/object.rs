@@ -196,10 +196,23 @@ where
                 return Ok(StreamInspection::H2(h2_obj));
             }
             Protocol::Smtp => {
-                let mut smtp_obj = crate::inspect::smtp::SmtpInterceptObject::new(self.ctx);
+                let mut smtp_obj =
+                    crate::inspect::smtp::SmtpInterceptObject::new(self.ctx, self.upstream);
                 smtp_obj.set_io(clt_r, clt_w, OnceBufReader::new(ups_r, ups_r_buf), ups_w);
                 return Ok(StreamInspection::Smtp(smtp_obj));
             }
+            Protocol::Imap => {
+                let mut imap_obj =
+                    crate::inspect::imap::ImapInterceptObject::new(self.ctx, self.upstream);
+                imap_obj.set_io(clt_r, clt_w, OnceBufReader::new(ups_r, ups_r_buf), ups_w);
+                return Ok(StreamInspection::Imap(imap_obj));
+            }
+            Protocol::Pop3 => {
+                let mut pop3_obj =
+                    crate::inspect::pop3::Pop3InterceptObject::new(self.ctx, self.upstream);
+                pop3_obj.set_io(clt_r, clt_w, OnceBufReader::new(ups_r, ups_r_buf), ups_w);
+                return Ok(StreamInspection::Pop3(pop3_obj));
+            }
             _ => {}
         }
 
@@ -26,7 +26,8 @@ use g3_io_ext::{FlexBufReader, OnceBufReader};
 use g3_slog_types::{LtUpstreamAddr, LtUuid};
 use g3_tls_cert::agent::CertAgentHandle;
 use g3_types::net::{
-    AlpnProtocol, OpensslInterceptionClientConfig, OpensslInterceptionServerConfig, UpstreamAddr,
+    AlpnProtocol, OpensslInterceptionClientConfig, OpensslInterceptionServerConfig, TlsServiceType,
+    UpstreamAddr,
 };
 use g3_udpdump::{ExportedPduDissectorHint, StreamDumpConfig, StreamDumper};
 
@@ -102,6 +103,32 @@ impl TlsInterceptionContext {
     }
 }
 
+/// The plaintext protocol which is upgraded to TLS by the STARTTLS (or STLS) command
+#[derive(Clone, Copy)]
+pub(crate) enum StartTlsProtocol {
+    Smtp,
+    Imap,
+    Pop3,
+}
+
+impl StartTlsProtocol {
+    fn protocol(&self) -> Protocol {
+        match self {
+            StartTlsProtocol::Smtp => Protocol::Smtp,
+            StartTlsProtocol::Imap => Protocol::Imap,
+            StartTlsProtocol::Pop3 => Protocol::Pop3,
+        }
+    }
+
+    fn tls_service_type(&self) -> TlsServiceType {
+        match self {
+            StartTlsProtocol::Smtp => TlsServiceType::Smtp,
+            StartTlsProtocol::Imap => TlsServiceType::Imap,
+            StartTlsProtocol::Pop3 => TlsServiceType::Pop3,
+        }
+    }
+}
+
 struct TlsInterceptIo {
     pub(super) clt_r: OnceBufReader<BoxAsyncRead>,
     pub(super) clt_w: BoxAsyncWrite,
@@ -114,6 +141,7 @@ pub(crate) struct TlsInterceptObject<SC: ServerConfig> {
     ctx: StreamInspectContext<SC>,
     upstream: UpstreamAddr,
     tls_interception: TlsInterceptionContext,
+    start_tls_protocol: Option<StartTlsProtocol>,
 }
 
 macro_rules! intercept_log {
@@ -138,9 +166,20 @@ impl<SC: ServerConfig> TlsInterceptObject<SC> {
             ctx,
             upstream,
             tls_interception: tls,
+            start_tls_protocol: None,
         }
     }
 
+    pub(crate) fn set_start_tls_protocol(&mut self, protocol: StartTlsProtocol) {
+        self.start_tls_protocol = Some(protocol);
+    }
+
+    fn tls_service_type(&self) -> TlsServiceType {
+        self.start_tls_protocol
+            .map(|p| p.tls_service_type())
+            .unwrap_or(TlsServiceType::Http)
+    }
+
     pub(crate) fn set_io(
         &mut self,
         clt_r: OnceBufReader<BoxAsyncRead>,
@@ -166,13 +205,13 @@ impl<SC: ServerConfig> TlsInterceptObject<SC> {
     }
 
     fn retain_alpn_protocol(&self, p: &[u8]) -> bool {
-        if self.ctx.h2_inspect_policy() == ProtocolInspectPolicy::Block {
-            return p != AlpnProtocol::Http2.identification_sequence();
-        }
-        if self.ctx.smtp_inspect_policy() == ProtocolInspectPolicy::Block {
-            return p != AlpnProtocol::Smtp.identification_sequence();
-        }
-        true
+        let blocked = |policy: ProtocolInspectPolicy, protocol: AlpnProtocol| {
+            policy == ProtocolInspectPolicy::Block && p == protocol.identification_sequence()
+        };
+        !(blocked(self.ctx.h2_inspect_policy(), AlpnProtocol::Http2)
+            || blocked(self.ctx.smtp_inspect_policy(), AlpnProtocol::Smtp)
+            || blocked(self.ctx.imap_inspect_policy(), AlpnProtocol::Imap)
+            || blocked(self.ctx.pop3_inspect_policy(), AlpnProtocol::Pop3))
     }
 }
 
@@ -233,6 +272,11 @@ where
     {
         let mut ctx = self.ctx.clone();
         ctx.increase_inspection_depth();
+        ctx.mark_inside_tls();
+        if let Some(start_tls_protocol) = self.start_tls_protocol {
+            StreamInspectLog::new(&ctx).log(InspectSource::StartTls, start_tls_protocol.protocol());
+            return self.start_tls_inner(ctx, start_tls_protocol, clt_r, clt_w, ups_r, ups_w);
+        }
         StreamInspectLog::new(&ctx).log(InspectSource::TlsAlpn, protocol);
         match protocol {
             Protocol::Http1 => {
@@ -256,7 +300,8 @@ where
                 StreamInspection::H2(h2_obj)
             }
             Protocol::Smtp => {
-                let mut smtp_obj = crate::inspect::smtp::SmtpInterceptObject::new(ctx);
+                let mut smtp_obj =
+                    crate::inspect::smtp::SmtpInterceptObject::new(ctx, self.upstream.clone());
                 smtp_obj.set_io(
                     Box::new(clt_r),
                     Box::new(clt_w),
@@ -265,6 +310,28 @@ where
                 );
                 StreamInspection::Smtp(smtp_obj)
             }
+            Protocol::Imap => {
+                let mut imap_obj =
+                    crate::inspect::imap::ImapInterceptObject::new(ctx, self.upstream.clone());
+                imap_obj.set_io(
+                    Box::new(clt_r),
+                    Box::new(clt_w),
+                    OnceBufReader::with_no_buf(Box::new(ups_r)),
+                    Box::new(ups_w),
+                );
+                StreamInspection::Imap(imap_obj)
+            }
+            Protocol::Pop3 => {
+                let mut pop3_obj =
+                    crate::inspect::pop3::Pop3InterceptObject::new(ctx, self.upstream.clone());
+                pop3_obj.set_io(
+                    Box::new(clt_r),
+                    Box::new(clt_w),
+                    OnceBufReader::with_no_buf(Box::new(ups_r)),
+                    Box::new(ups_w),
+                );
+                StreamInspection::Pop3(pop3_obj)
+            }
             _ => {
                 let mut stream_obj =
                     crate::inspect::stream::StreamInspectObject::new(ctx, self.upstream.clone());
@@ -284,4 +351,60 @@ where
             }
         }
     }
+
+    /// The plaintext protocol will continue after the TLS handshake, without a new greeting
+    fn start_tls_inner<CR, CW, UR, UW>(
+        &self,
+        ctx: StreamInspectContext<SC>,
+        protocol: StartTlsProtocol,
+        clt_r: CR,
+        clt_w: CW,
+        ups_r: UR,
+        ups_w: UW,
+    ) -> StreamInspection<SC>
+    where
+        CR: AsyncRead + Send + Unpin + 'static,
+        CW: AsyncWrite + Send + Unpin + 'static,
+        UR: AsyncRead + Send + Unpin + 'static,
+        UW: AsyncWrite + Send + Unpin + 'static,
+    {
+        match protocol {
+            StartTlsProtocol::Smtp => {
+                let mut smtp_obj =
+                    crate::inspect::smtp::SmtpInterceptObject::new(ctx, self.upstream.clone());
+                smtp_obj.set_from_starttls();
+                smtp_obj.set_io(
+                    Box::new(clt_r),
+                    Box::new(clt_w),
+                    OnceBufReader::with_no_buf(Box::new(ups_r)),
+                    Box::new(ups_w),
+                );
+                StreamInspection::Smtp(smtp_obj)
+            }
+            StartTlsProtocol::Imap => {
+                let mut imap_obj =
+                    crate::inspect::imap::ImapInterceptObject::new(ctx, self.upstream.clone());
+                imap_obj.set_from_starttls();
+                imap_obj.set_io(
+                    Box::new(clt_r),
+                    Box::new(clt_w),
+                    OnceBufReader::with_no_buf(Box::new(ups_r)),
+                    Box::new(ups_w),
+                );
+                StreamInspection::Imap(imap_obj)
+            }
+            StartTlsProtocol::Pop3 => {
+                let mut pop3_obj =
+                    crate::inspect::pop3::Pop3InterceptObject::new(ctx, self.upstream.clone());
+                pop3_obj.set_from_starttls();
+                pop3_obj.set_io(
+                    Box::new(clt_r),
+                    Box::new(clt_w),
+                    OnceBufReader::with_no_buf(Box::new(ups_r)),
+                    Box::new(ups_w),
+                );
+                StreamInspection::Pop3(pop3_obj)
+            }
+        }
+    }
 }
@@ -22,7 +22,7 @@ use openssl::ssl::Ssl;
 use g3_dpi::{Protocol, ProtocolInspector};
 use g3_io_ext::AggregatedIo;
 use g3_openssl::{SslConnector, SslLazyAcceptor};
-use g3_types::net::{AlpnProtocol, Host, TlsCertUsage, TlsServiceType};
+use g3_types::net::{AlpnProtocol, Host, TlsCertUsage};
 
 use super::{TlsInterceptIo, TlsInterceptObject, TlsInterceptionError};
 use crate::config::server::ServerConfig;
@@ -138,9 +138,10 @@ where
         let cert_domain: Arc<str> = Arc::from(cert_domain);
         let cert_domain2 = cert_domain.clone();
         let cert_agent = self.tls_interception.cert_agent.clone();
+        let service_type = self.tls_service_type();
         let pre_fetch_handle = tokio::spawn(async move {
             cert_agent
-                .pre_fetch(TlsServiceType::Http, CERT_USAGE, cert_domain2)
+                .pre_fetch(service_type, CERT_USAGE, cert_domain2)
                 .await
         });
 
@@ -176,7 +177,7 @@ where
                 })?;
                 self.tls_interception
                     .cert_agent
-                    .fetch(TlsServiceType::Http, CERT_USAGE, cert_domain, upstream_cert)
+                    .fetch(service_type, CERT_USAGE, cert_domain, upstream_cert)
                     .await
                     .ok_or_else(|| {
                         TlsInterceptionError::NoFakeCertGenerated(anyhow!(
@@ -18,11 +18,14 @@ use slog::slog_info;
 
 use g3_slog_types::{LtUpstreamAddr, LtUuid};
 use g3_types::net::UpstreamAddr;
+use g3_websocket::{PerMessageDeflateConfig, PerMessageDeflateParseError};
 
 use crate::config::server::ServerConfig;
 use crate::inspect::{BoxAsyncRead, BoxAsyncWrite, StreamInspectContext};
 use crate::serve::ServerTaskResult;
 
+use super::relay::WebSocketRelay;
+
 macro_rules! intercept_log {
     ($obj:tt, $($args:tt)+) => {
         slog_info!($obj.ctx.intercept_logger(), $($args)+;
@@ -45,6 +48,7 @@ pub(crate) struct H1WebsocketInterceptObject<SC: ServerConfig> {
     io: Option<H1WebsocketIo>,
     pub(crate) ctx: StreamInspectContext<SC>,
     upstream: UpstreamAddr,
+    extensions: Result<Option<PerMessageDeflateConfig>, PerMessageDeflateParseError>,
 }
 
 impl<SC: ServerConfig> H1WebsocketInterceptObject<SC> {
@@ -53,9 +57,18 @@ impl<SC: ServerConfig> H1WebsocketInterceptObject<SC> {
             io: None,
             ctx,
             upstream,
+            extensions: Ok(None),
         }
     }
 
+    /// Set the `Sec-WebSocket-Extensions` header values in the handshake response
+    pub(crate) fn set_response_extensions<'a, I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.extensions = PerMessageDeflateConfig::parse_response_extensions(values);
+    }
+
     pub(crate) fn set_io(
         &mut self,
         clt_r: BoxAsyncRead,
@@ -84,12 +97,25 @@ impl<SC: ServerConfig> H1WebsocketInterceptObject<SC> {
 
     async fn do_intercept(&mut self) -> ServerTaskResult<()> {
         let H1WebsocketIo {
-            clt_r,
-            clt_w,
-            ups_r,
-            ups_w,
+            mut clt_r,
+            mut clt_w,
+            mut ups_r,
+            mut ups_w,
         } = self.io.take().unwrap();
 
+        match &self.extensions {
+            Ok(deflate) => {
+                let relay =
+                    WebSocketRelay::new(&self.ctx, &self.upstream, "H1Websocket", deflate.clone());
+                return relay
+                    .relay(&mut clt_r, &mut clt_w, &mut ups_r, &mut ups_w)
+                    .await;
+            }
+            Err(e) => {
+                intercept_log!(self, "unable to decode frames: {e}");
+            }
+        }
+
         crate::inspect::stream::transit_transparent(
             clt_r,
             clt_w,
@@ -21,11 +21,14 @@ use slog::slog_info;
 use g3_h2::{H2StreamReader, H2StreamWriter};
 use g3_slog_types::{LtUpstreamAddr, LtUuid};
 use g3_types::net::UpstreamAddr;
+use g3_websocket::{PerMessageDeflateConfig, PerMessageDeflateParseError};
 
 use crate::config::server::ServerConfig;
 use crate::inspect::StreamInspectContext;
 use crate::serve::ServerTaskResult;
 
+use super::relay::WebSocketRelay;
+
 macro_rules! intercept_log {
     ($obj:tt, $($args:tt)+) => {
         slog_info!($obj.ctx.intercept_logger(), $($args)+;
@@ -40,11 +43,24 @@ macro_rules! intercept_log {
 pub(crate) struct H2WebsocketInterceptObject<SC: ServerConfig> {
     ctx: StreamInspectContext<SC>,
     upstream: UpstreamAddr,
+    extensions: Result<Option<PerMessageDeflateConfig>, PerMessageDeflateParseError>,
 }
 
 impl<SC: ServerConfig> H2WebsocketInterceptObject<SC> {
     pub(crate) fn new(ctx: StreamInspectContext<SC>, upstream: UpstreamAddr) -> Self {
-        H2WebsocketInterceptObject { ctx, upstream }
+        H2WebsocketInterceptObject {
+            ctx,
+            upstream,
+            extensions: Ok(None),
+        }
+    }
+
+    /// Set the `Sec-WebSocket-Extensions` header values in the handshake response
+    pub(crate) fn set_response_extensions<'a, I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.extensions = PerMessageDeflateConfig::parse_response_extensions(values);
     }
 }
 
@@ -70,10 +86,23 @@ impl<SC: ServerConfig> H2WebsocketInterceptObject<SC> {
         ups_r: RecvStream,
         ups_w: SendStream<Bytes>,
     ) -> ServerTaskResult<()> {
-        let clt_r = H2StreamReader::new(clt_r);
-        let clt_w = H2StreamWriter::new(clt_w);
-        let ups_r = H2StreamReader::new(ups_r);
-        let ups_w = H2StreamWriter::new(ups_w);
+        let mut clt_r = H2StreamReader::new(clt_r);
+        let mut clt_w = H2StreamWriter::new(clt_w);
+        let mut ups_r = H2StreamReader::new(ups_r);
+        let mut ups_w = H2StreamWriter::new(ups_w);
+
+        match &self.extensions {
+            Ok(deflate) => {
+                let relay =
+                    WebSocketRelay::new(&self.ctx, &self.upstream, "H2Websocket", deflate.clone());
+                return relay
+                    .relay(&mut clt_r, &mut clt_w, &mut ups_r, &mut ups_w)
+                    .await;
+            }
+            Err(e) => {
+                intercept_log!(self, "unable to decode frames: {e}");
+            }
+        }
 
         crate::inspect::stream::transit_transparent(
             clt_r,
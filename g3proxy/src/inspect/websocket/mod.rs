@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+mod relay;
+
 mod h1;
 pub(crate) use h1::H1WebsocketInterceptObject;
 
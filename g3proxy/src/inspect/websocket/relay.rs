@@ -0,0 +1,619 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use bytes::{Bytes, BytesMut};
+use slog::slog_info;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::Instant;
+
+use g3_icap_client::reqmod::websocket::WebSocketAdaptationEndState;
+use g3_slog_types::{LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+use g3_websocket::{
+    CloseCode, CloseFrame, FrameHeader, MessageInflateError, MessageInflater, OpCode,
+    PerMessageDeflateConfig,
+};
+
+use crate::config::server::ServerConfig;
+use crate::inspect::StreamInspectContext;
+use crate::serve::{ServerTaskError, ServerTaskResult};
+
+const READ_BUFFER_RESERVE_SIZE: usize = 16384;
+
+macro_rules! intercept_log {
+    ($obj:tt, $($args:tt)+) => {
+        slog_info!($obj.ctx.intercept_logger(), $($args)+;
+            "intercept_type" => $obj.intercept_type,
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr($obj.upstream),
+        )
+    };
+}
+
+macro_rules! close_log {
+    ($obj:tt, $side:expr, $frame:expr) => {
+        slog_info!($obj.ctx.intercept_logger(), "closed by {}", $side.as_str();
+            "intercept_type" => $obj.intercept_type,
+            "task_id" => LtUuid($obj.ctx.server_task_id()),
+            "depth" => $obj.ctx.inspection_depth,
+            "upstream" => LtUpstreamAddr($obj.upstream),
+            "close_code" => $frame.code,
+            "close_reason" => $frame.reason.as_str(),
+        )
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Client,
+    Server,
+}
+
+impl Side {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Side::Client => "client",
+            Side::Server => "server",
+        }
+    }
+
+    fn peer_write_error(&self, e: std::io::Error) -> ServerTaskError {
+        match self {
+            Side::Client => ServerTaskError::UpstreamWriteFailed(e),
+            Side::Server => ServerTaskError::ClientTcpWriteFailed(e),
+        }
+    }
+
+    fn peer(&self) -> Side {
+        match self {
+            Side::Client => Side::Server,
+            Side::Server => Side::Client,
+        }
+    }
+
+    fn app_error(&self, e: FrameError) -> ServerTaskError {
+        let e = anyhow!("websocket failed with close code {}: {}", e.code, e.reason);
+        match self {
+            Side::Client => ServerTaskError::ClientAppError(e),
+            Side::Server => ServerTaskError::UpstreamAppError(e),
+        }
+    }
+}
+
+/// The error which will make us fail the websocket connection with a close frame
+struct FrameError {
+    code: u16,
+    reason: &'static str,
+}
+
+impl FrameError {
+    fn protocol(reason: &'static str) -> Self {
+        FrameError {
+            code: CloseCode::PROTOCOL_ERROR,
+            reason,
+        }
+    }
+
+    fn too_big() -> Self {
+        FrameError {
+            code: CloseCode::MESSAGE_TOO_BIG,
+            reason: "message too big",
+        }
+    }
+}
+
+impl From<MessageInflateError> for FrameError {
+    fn from(e: MessageInflateError) -> Self {
+        match e {
+            MessageInflateError::CorruptedData => FrameError {
+                code: CloseCode::INVALID_PAYLOAD,
+                reason: "corrupted compressed data",
+            },
+            MessageInflateError::TooLarge(_) => FrameError::too_big(),
+        }
+    }
+}
+
+struct DataMessage {
+    compressed: bool,
+    size: usize,
+    /// the unmasked and decompressed payload, only set if the message should be audited
+    audit_payload: Option<Vec<u8>>,
+    /// the frames held back until the audit of the message is done
+    held_frames: Vec<Bytes>,
+}
+
+enum FrameAction {
+    Forward(Bytes),
+    Hold,
+    Close(Bytes, CloseFrame),
+    Audit(Vec<Bytes>, Vec<u8>),
+}
+
+struct FrameStream {
+    side: Side,
+    buf: BytesMut,
+    inflater: Option<MessageInflater>,
+    message: Option<DataMessage>,
+    closed: bool,
+}
+
+impl FrameStream {
+    fn new(side: Side, inflater: Option<MessageInflater>) -> Self {
+        FrameStream {
+            side,
+            buf: BytesMut::with_capacity(READ_BUFFER_RESERVE_SIZE),
+            inflater,
+            message: None,
+            closed: false,
+        }
+    }
+
+    fn next_frame(
+        &mut self,
+        max_message_size: usize,
+    ) -> Result<Option<(FrameHeader, usize, Bytes)>, FrameError> {
+        let Some((header, header_len)) = FrameHeader::parse(&self.buf)
+            .map_err(|_| FrameError::protocol("invalid frame header"))?
+        else {
+            return Ok(None);
+        };
+        if header.payload_len > max_message_size as u64 {
+            return Err(FrameError::too_big());
+        }
+
+        let frame_len = header_len + header.payload_len as usize;
+        if self.buf.len() < frame_len {
+            self.buf.reserve(frame_len - self.buf.len());
+            return Ok(None);
+        }
+        let frame = self.buf.split_to(frame_len).freeze();
+        Ok(Some((header, header_len, frame)))
+    }
+
+    fn check_frame(
+        &mut self,
+        header: FrameHeader,
+        header_len: usize,
+        frame: Bytes,
+        max_message_size: usize,
+        audit: bool,
+    ) -> Result<FrameAction, FrameError> {
+        if self.closed {
+            return Err(FrameError::protocol("frame received after close"));
+        }
+        match self.side {
+            Side::Client if header.mask_key.is_none() => {
+                return Err(FrameError::protocol("unmasked client frame"));
+            }
+            Side::Server if header.mask_key.is_some() => {
+                return Err(FrameError::protocol("masked server frame"));
+            }
+            _ => {}
+        }
+        if header.rsv2 || header.rsv3 {
+            return Err(FrameError::protocol("reserved bits set"));
+        }
+        if header.rsv1
+            && (self.inflater.is_none() || !matches!(header.opcode, OpCode::Text | OpCode::Binary))
+        {
+            return Err(FrameError::protocol("unexpected compressed bit"));
+        }
+
+        match header.opcode {
+            OpCode::Ping | OpCode::Pong => Ok(FrameAction::Forward(frame)),
+            OpCode::Close => {
+                let mut payload = frame[header_len..].to_vec();
+                header.apply_mask(0, &mut payload);
+                let close_frame = CloseFrame::parse(&payload)
+                    .map_err(|_| FrameError::protocol("invalid close frame"))?;
+                self.closed = true;
+                // drop the incomplete message
+                self.message = None;
+                Ok(FrameAction::Close(frame, close_frame))
+            }
+            OpCode::Text | OpCode::Binary => {
+                if self.message.is_some() {
+                    return Err(FrameError::protocol("unfinished fragmented message"));
+                }
+                self.message = Some(DataMessage {
+                    compressed: header.rsv1,
+                    size: 0,
+                    audit_payload: audit.then(Vec::new),
+                    held_frames: Vec::new(),
+                });
+                self.add_data_frame(header, header_len, frame, max_message_size)
+            }
+            OpCode::Continuation => {
+                if self.message.is_none() {
+                    return Err(FrameError::protocol("unexpected continuation frame"));
+                }
+                self.add_data_frame(header, header_len, frame, max_message_size)
+            }
+        }
+    }
+
+    fn add_data_frame(
+        &mut self,
+        header: FrameHeader,
+        header_len: usize,
+        frame: Bytes,
+        max_message_size: usize,
+    ) -> Result<FrameAction, FrameError> {
+        let Some(msg) = self.message.as_mut() else {
+            return Err(FrameError::protocol("unexpected continuation frame"));
+        };
+
+        match self.inflater.as_mut() {
+            Some(inflater) if msg.compressed => {
+                let mut payload = frame[header_len..].to_vec();
+                header.apply_mask(0, &mut payload);
+                msg.size += inflater.inflate(
+                    &payload,
+                    max_message_size - msg.size,
+                    msg.audit_payload.as_mut(),
+                )?;
+                if header.fin {
+                    msg.size += inflater
+                        .finish_message(max_message_size - msg.size, msg.audit_payload.as_mut())?;
+                }
+            }
+            _ => {
+                msg.size += header.payload_len as usize;
+                if msg.size > max_message_size {
+                    return Err(FrameError::too_big());
+                }
+                if let Some(audit_payload) = &mut msg.audit_payload {
+                    let offset = audit_payload.len();
+                    audit_payload.extend_from_slice(&frame[header_len..]);
+                    header.apply_mask(0, &mut audit_payload[offset..]);
+                }
+            }
+        }
+
+        if msg.audit_payload.is_some() {
+            msg.held_frames.push(frame);
+            if header.fin {
+                if let Some(msg) = self.message.take() {
+                    let payload = msg.audit_payload.unwrap_or_default();
+                    return Ok(FrameAction::Audit(msg.held_frames, payload));
+                }
+            }
+            Ok(FrameAction::Hold)
+        } else {
+            if header.fin {
+                self.message = None;
+            }
+            Ok(FrameAction::Forward(frame))
+        }
+    }
+}
+
+fn encode_close_frame(code: u16, reason: &str, masked: bool) -> Vec<u8> {
+    let payload = CloseFrame::new(code, reason).encode_payload();
+    let mut header = FrameHeader::new(OpCode::Close, payload.len() as u64);
+    if masked {
+        header.mask_key = Some(fastrand::u32(..).to_be_bytes());
+    }
+    let mut buf = Vec::with_capacity(header.encoded_len() + payload.len());
+    header.encode(&mut buf);
+    let offset = buf.len();
+    buf.extend_from_slice(&payload);
+    header.apply_mask(0, &mut buf[offset..]);
+    buf
+}
+
+async fn send_close_frame<W>(writer: &mut W, to: Side, code: u16, reason: &str)
+where
+    W: AsyncWrite + Unpin,
+{
+    // frames sent to the server should be masked as we are acting as a client
+    let frame = encode_close_frame(code, reason, to == Side::Server);
+    let _ = writer.write_all(&frame).await;
+    let _ = writer.flush().await;
+}
+
+pub(super) struct WebSocketRelay<'a, SC: ServerConfig> {
+    ctx: &'a StreamInspectContext<SC>,
+    upstream: &'a UpstreamAddr,
+    intercept_type: &'static str,
+    deflate: Option<PerMessageDeflateConfig>,
+}
+
+impl<'a, SC: ServerConfig> WebSocketRelay<'a, SC> {
+    pub(super) fn new(
+        ctx: &'a StreamInspectContext<SC>,
+        upstream: &'a UpstreamAddr,
+        intercept_type: &'static str,
+        deflate: Option<PerMessageDeflateConfig>,
+    ) -> Self {
+        WebSocketRelay {
+            ctx,
+            upstream,
+            intercept_type,
+            deflate,
+        }
+    }
+
+    pub(super) async fn relay<CR, CW, UR, UW>(
+        &self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        ups_r: &mut UR,
+        ups_w: &mut UW,
+    ) -> ServerTaskResult<()>
+    where
+        CR: AsyncRead + Unpin,
+        CW: AsyncWrite + Unpin,
+        UR: AsyncRead + Unpin,
+        UW: AsyncWrite + Unpin,
+    {
+        let (mut clt, mut ups) = match &self.deflate {
+            Some(config) => {
+                intercept_log!(self, "opened with permessage-deflate");
+                (
+                    FrameStream::new(
+                        Side::Client,
+                        Some(MessageInflater::new(config.client_no_context_takeover)),
+                    ),
+                    FrameStream::new(
+                        Side::Server,
+                        Some(MessageInflater::new(config.server_no_context_takeover)),
+                    ),
+                )
+            }
+            None => {
+                intercept_log!(self, "opened");
+                (
+                    FrameStream::new(Side::Client, None),
+                    FrameStream::new(Side::Server, None),
+                )
+            }
+        };
+
+        let idle_duration = self.ctx.server_config.task_idle_check_duration();
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        let max_idle_count = self.ctx.task_max_idle_count();
+        let mut active = false;
+
+        loop {
+            clt.buf.reserve(READ_BUFFER_RESERVE_SIZE);
+            ups.buf.reserve(READ_BUFFER_RESERVE_SIZE);
+
+            tokio::select! {
+                biased;
+
+                r = clt_r.read_buf(&mut clt.buf) => {
+                    match r {
+                        Ok(0) => {
+                            return if clt.closed {
+                                Ok(())
+                            } else {
+                                Err(ServerTaskError::ClosedByClient)
+                            };
+                        }
+                        Ok(_) => {
+                            active = true;
+                            if !self.relay_frames(&mut clt, ups.closed, clt_w, ups_w).await? {
+                                return Ok(());
+                            }
+                        }
+                        Err(e) => return Err(ServerTaskError::ClientTcpReadFailed(e)),
+                    }
+                }
+                r = ups_r.read_buf(&mut ups.buf) => {
+                    match r {
+                        Ok(0) => {
+                            return if ups.closed {
+                                Ok(())
+                            } else {
+                                Err(ServerTaskError::ClosedByUpstream)
+                            };
+                        }
+                        Ok(_) => {
+                            active = true;
+                            if !self.relay_frames(&mut ups, clt.closed, ups_w, clt_w).await? {
+                                return Ok(());
+                            }
+                        }
+                        Err(e) => return Err(ServerTaskError::UpstreamReadFailed(e)),
+                    }
+                }
+                _ = idle_interval.tick() => {
+                    if active {
+                        idle_count = 0;
+                        active = false;
+                    } else {
+                        idle_count += 1;
+                        if idle_count >= max_idle_count {
+                            return Err(ServerTaskError::Idle(idle_duration, idle_count));
+                        }
+                    }
+
+                    if self.ctx.belongs_to_blocked_user() {
+                        return Err(ServerTaskError::CanceledAsUserBlocked);
+                    }
+
+                    if self.ctx.server_force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Relay all complete frames in the read buffer of `stream` to its peer.
+    ///
+    /// Return false if the websocket connection has been closed by us.
+    async fn relay_frames<SW, PW>(
+        &self,
+        stream: &mut FrameStream,
+        peer_closed: bool,
+        self_w: &mut SW,
+        peer_w: &mut PW,
+    ) -> ServerTaskResult<bool>
+    where
+        SW: AsyncWrite + Unpin,
+        PW: AsyncWrite + Unpin,
+    {
+        let max_message_size = self.ctx.websocket_interception().max_message_size;
+        let side = stream.side;
+
+        loop {
+            match self.next_frame_action(stream, max_message_size) {
+                Ok(Some(FrameAction::Forward(frame))) => {
+                    peer_w
+                        .write_all(&frame)
+                        .await
+                        .map_err(|e| side.peer_write_error(e))?;
+                }
+                Ok(Some(FrameAction::Hold)) => {}
+                Ok(Some(FrameAction::Close(frame, close_frame))) => {
+                    close_log!(self, side, close_frame);
+                    peer_w
+                        .write_all(&frame)
+                        .await
+                        .map_err(|e| side.peer_write_error(e))?;
+                }
+                Ok(Some(FrameAction::Audit(frames, payload))) => {
+                    match self.audit_message(&payload).await {
+                        Ok(None) => {
+                            for frame in frames {
+                                peer_w
+                                    .write_all(&frame)
+                                    .await
+                                    .map_err(|e| side.peer_write_error(e))?;
+                            }
+                        }
+                        Ok(Some(reason)) => {
+                            intercept_log!(self, "message blocked by icap server: {reason}");
+                            self.close_both(
+                                stream,
+                                peer_closed,
+                                self_w,
+                                peer_w,
+                                CloseCode::POLICY_VIOLATION,
+                                "message blocked",
+                            )
+                            .await;
+                            return Ok(false);
+                        }
+                        Err(e) => {
+                            self.close_both(
+                                stream,
+                                peer_closed,
+                                self_w,
+                                peer_w,
+                                CloseCode::INTERNAL_ERROR,
+                                "message adaptation failed",
+                            )
+                            .await;
+                            return Err(e);
+                        }
+                    }
+                }
+                Ok(None) => {
+                    peer_w.flush().await.map_err(|e| side.peer_write_error(e))?;
+                    return Ok(true);
+                }
+                Err(e) => {
+                    self.close_both(stream, peer_closed, self_w, peer_w, e.code, e.reason)
+                        .await;
+                    return Err(side.app_error(e));
+                }
+            }
+        }
+    }
+
+    fn next_frame_action(
+        &self,
+        stream: &mut FrameStream,
+        max_message_size: usize,
+    ) -> Result<Option<FrameAction>, FrameError> {
+        let Some((header, header_len, frame)) = stream.next_frame(max_message_size)? else {
+            return Ok(None);
+        };
+
+        // only text messages from the client will be sampled
+        let audit = stream.side == Side::Client
+            && header.opcode == OpCode::Text
+            && self.ctx.audit_handle.icap_reqmod_client().is_some()
+            && self.ctx.audit_handle.do_websocket_message_audit();
+        stream
+            .check_frame(header, header_len, frame, max_message_size, audit)
+            .map(Some)
+    }
+
+    async fn close_both<SW, PW>(
+        &self,
+        stream: &FrameStream,
+        peer_closed: bool,
+        self_w: &mut SW,
+        peer_w: &mut PW,
+        code: u16,
+        reason: &str,
+    ) where
+        SW: AsyncWrite + Unpin,
+        PW: AsyncWrite + Unpin,
+    {
+        let _ = peer_w.flush().await;
+        // skip the side which has already received a close frame
+        if !peer_closed {
+            send_close_frame(self_w, stream.side, code, reason).await;
+        }
+        if !stream.closed {
+            send_close_frame(peer_w, stream.side.peer(), code, reason).await;
+        }
+    }
+
+    /// Send the text message to the ICAP server, return the block reason if blocked
+    async fn audit_message(&self, payload: &[u8]) -> ServerTaskResult<Option<String>> {
+        let Some(reqmod_client) = self.ctx.audit_handle.icap_reqmod_client() else {
+            return Ok(None);
+        };
+
+        let mut adapter = match reqmod_client.websocket_message_adapter(self.upstream).await {
+            Ok(adapter) => adapter,
+            Err(e) => {
+                return if reqmod_client.bypass() {
+                    Ok(None)
+                } else {
+                    Err(ServerTaskError::InternalAdapterError(e))
+                };
+            }
+        };
+        adapter.set_client_addr(self.ctx.task_notes.client_addr);
+        if let Some(username) = self.ctx.raw_user_name() {
+            adapter.set_client_username(username);
+        }
+
+        let timeout = self.ctx.websocket_interception().message_adaptation_timeout;
+        match tokio::time::timeout(timeout, adapter.xfer(payload)).await {
+            Ok(Ok(WebSocketAdaptationEndState::OriginalMessage)) => Ok(None),
+            Ok(Ok(WebSocketAdaptationEndState::HttpErrResponse(rsp))) => {
+                Ok(Some(format!("{} {}", rsp.status.as_u16(), rsp.reason)))
+            }
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(ServerTaskError::InternalAdapterError(anyhow!(
+                "reqmod: websocket message adaptation timeout"
+            ))),
+        }
+    }
+}
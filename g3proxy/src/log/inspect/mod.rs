@@ -25,6 +25,7 @@ pub(crate) enum InspectSource {
     TlsAlpn,
     H2ExtendedConnect,
     HttpUpgrade,
+    StartTls,
 }
 
 impl InspectSource {
@@ -34,6 +35,7 @@ impl InspectSource {
             InspectSource::TlsAlpn => "tls alpn",
             InspectSource::H2ExtendedConnect => "h2 extended connect",
             InspectSource::HttpUpgrade => "http upgrade",
+            InspectSource::StartTls => "starttls",
         }
     }
 }
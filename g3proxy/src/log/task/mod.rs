@@ -20,6 +20,7 @@ use g3_types::metrics::MetricsName;
 
 pub(crate) mod ftp_over_http;
 pub(crate) mod http_forward;
+pub(crate) mod tcp_bind;
 pub(crate) mod tcp_connect;
 pub(crate) mod udp_associate;
 pub(crate) mod udp_connect;
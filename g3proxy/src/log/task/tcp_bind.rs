@@ -0,0 +1,68 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use slog::{slog_info, Logger};
+
+use g3_slog_types::{LtDateTime, LtDuration, LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::serve::{ServerTaskError, ServerTaskNotes};
+
+pub(crate) struct TaskLogForTcpBind<'a> {
+    pub(crate) task_notes: &'a ServerTaskNotes,
+    pub(crate) upstream: &'a UpstreamAddr,
+    pub(crate) bind_listen_addr: Option<SocketAddr>,
+    pub(crate) bind_peer_addr: Option<SocketAddr>,
+    pub(crate) total_time: Duration,
+    pub(crate) client_rd_bytes: u64,
+    pub(crate) client_wr_bytes: u64,
+    pub(crate) remote_rd_bytes: u64,
+    pub(crate) remote_wr_bytes: u64,
+}
+
+impl TaskLogForTcpBind<'_> {
+    pub(crate) fn log(&self, logger: &Logger, e: &ServerTaskError) {
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            if user_ctx.skip_log() {
+                return;
+            }
+        }
+
+        slog_info!(logger, "{}", e;
+            "task_type" => "TcpBind",
+            "task_id" => LtUuid(&self.task_notes.id),
+            "stage" => self.task_notes.stage.brief(),
+            "start_at" => LtDateTime(&self.task_notes.start_at),
+            "user" => self.task_notes.raw_user_name(),
+            "server_addr" => self.task_notes.server_addr(),
+            "client_addr" => self.task_notes.client_addr(),
+            "upstream" => LtUpstreamAddr(self.upstream),
+            "bind_listen_addr" => self.bind_listen_addr,
+            "bind_peer_addr" => self.bind_peer_addr,
+            "reason" => e.brief(),
+            "wait_time" => LtDuration(self.task_notes.wait_time),
+            "ready_time" => LtDuration(self.task_notes.ready_time),
+            "total_time" => LtDuration(self.total_time),
+            "c_rd_bytes" => self.client_rd_bytes,
+            "c_wr_bytes" => self.client_wr_bytes,
+            "r_rd_bytes" => self.remote_rd_bytes,
+            "r_wr_bytes" => self.remote_wr_bytes,
+        )
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, Cursor, Write};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::{BufMut, Bytes};
+use http::{header, HeaderName, Version};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use g3_types::net::HttpHeaderMap;
+
+use super::ResponseFreshness;
+
+/// headers that should be sent in a 304 response, see RFC 9110 Section 15.4.5
+const NOT_MODIFIED_HEADERS: &[HeaderName] = &[
+    header::CACHE_CONTROL,
+    header::CONTENT_LOCATION,
+    header::DATE,
+    header::ETAG,
+    header::EXPIRES,
+    header::LAST_MODIFIED,
+    header::VARY,
+];
+
+pub(super) struct HttpCacheDiskFile {
+    path: PathBuf,
+    size: u64,
+}
+
+impl HttpCacheDiskFile {
+    pub(super) fn new(path: PathBuf, size: u64) -> Self {
+        HttpCacheDiskFile { path, size }
+    }
+}
+
+impl Drop for HttpCacheDiskFile {
+    fn drop(&mut self) {
+        // the file is still readable by the opened handles on unix
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Clone)]
+pub(super) enum HttpCacheBody {
+    Memory(Bytes),
+    Disk(Arc<HttpCacheDiskFile>),
+}
+
+impl HttpCacheBody {
+    fn size(&self) -> u64 {
+        match self {
+            HttpCacheBody::Memory(b) => b.len() as u64,
+            HttpCacheBody::Disk(f) => f.size,
+        }
+    }
+}
+
+pub(crate) struct HttpCacheEntry {
+    pub(super) code: u16,
+    pub(super) reason: String,
+    pub(super) headers: HttpHeaderMap,
+    pub(super) freshness: ResponseFreshness,
+    pub(super) body: HttpCacheBody,
+}
+
+impl HttpCacheEntry {
+    #[inline]
+    pub(super) fn body_size(&self) -> u64 {
+        self.body.size()
+    }
+
+    pub(super) fn in_memory(&self) -> bool {
+        matches!(self.body, HttpCacheBody::Memory(_))
+    }
+
+    pub(super) fn with_body(&self, body: HttpCacheBody) -> Self {
+        HttpCacheEntry {
+            code: self.code,
+            reason: self.reason.clone(),
+            headers: self.headers.clone(),
+            freshness: self.freshness.clone(),
+            body,
+        }
+    }
+
+    pub(super) fn serialize_head(
+        &self,
+        version: Version,
+        age: u64,
+        keep_alive: bool,
+        not_modified: bool,
+    ) -> Vec<u8> {
+        let mut buf = Vec::<u8>::with_capacity(1024);
+        if not_modified {
+            let _ = write!(buf, "{version:?} 304 Not Modified\r\n");
+            for name in NOT_MODIFIED_HEADERS {
+                for value in self.headers.get_all(name) {
+                    value.write_to_buf(name, &mut buf);
+                }
+            }
+        } else {
+            let _ = write!(buf, "{version:?} {} {}\r\n", self.code, self.reason);
+            self.headers
+                .for_each(|name, value| value.write_to_buf(name, &mut buf));
+            if self.code != 204 {
+                let _ = write!(buf, "Content-Length: {}\r\n", self.body.size());
+            }
+        }
+        let _ = write!(buf, "Age: {age}\r\n");
+        if keep_alive {
+            buf.put_slice(b"Connection: keep-alive\r\n");
+        } else {
+            buf.put_slice(b"Connection: close\r\n");
+        }
+        buf.put_slice(b"\r\n");
+        buf
+    }
+
+    pub(super) async fn body_reader(&self) -> io::Result<HttpCacheBodyReader> {
+        match &self.body {
+            HttpCacheBody::Memory(b) => Ok(HttpCacheBodyReader::Memory(Cursor::new(b.clone()))),
+            HttpCacheBody::Disk(f) => {
+                let file = tokio::fs::File::open(&f.path).await?;
+                Ok(HttpCacheBodyReader::Disk(file))
+            }
+        }
+    }
+}
+
+pub(crate) enum HttpCacheBodyReader {
+    Memory(Cursor<Bytes>),
+    Disk(tokio::fs::File),
+}
+
+impl AsyncRead for HttpCacheBodyReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            HttpCacheBodyReader::Memory(c) => Pin::new(c).poll_read(cx, buf),
+            HttpCacheBodyReader::Disk(f) => Pin::new(f).poll_read(cx, buf),
+        }
+    }
+}
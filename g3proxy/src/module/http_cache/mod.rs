@@ -0,0 +1,256 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use http::HeaderName;
+use log::warn;
+use once_cell::sync::Lazy;
+
+use g3_types::net::HttpHeaderMap;
+
+use crate::config::http_cache::HttpCacheConfig;
+
+mod policy;
+use policy::{RequestCacheControl, ResponseFreshness};
+
+mod entry;
+pub(crate) use entry::HttpCacheBodyReader;
+use entry::{HttpCacheBody, HttpCacheDiskFile, HttpCacheEntry};
+
+mod store;
+use store::{EvictedEntries, HttpCacheStore};
+
+mod stats;
+pub(crate) use stats::{HttpCacheSnapshot, HttpCacheStats};
+
+mod task;
+pub(crate) use task::{
+    HttpCacheForward, HttpCacheHit, HttpCacheLookup, HttpCacheRecordReader, HttpCacheRecorder,
+    HttpCacheStatus,
+};
+
+const DISK_FILE_SUFFIX: &str = ".body";
+
+static GLOBAL_HTTP_CACHE: Lazy<Option<Arc<HttpCache>>> =
+    Lazy::new(|| crate::config::http_cache::get().map(|config| Arc::new(HttpCache::new(config))));
+
+/// Get the global http cache, which is only available if enabled in the main config
+pub(crate) fn get_global_cache() -> Option<&'static Arc<HttpCache>> {
+    GLOBAL_HTTP_CACHE.as_ref()
+}
+
+#[derive(Default)]
+pub(crate) struct HttpCacheUsage {
+    pub(crate) memory_capacity: u64,
+    pub(crate) memory_used: u64,
+    pub(crate) memory_entries: usize,
+    pub(crate) disk_capacity: u64,
+    pub(crate) disk_used: u64,
+    pub(crate) disk_entries: usize,
+}
+
+pub(crate) struct HttpCache {
+    config: &'static HttpCacheConfig,
+    store: Mutex<HttpCacheStore>,
+    stats: Arc<HttpCacheStats>,
+    next_file_id: AtomicU64,
+}
+
+impl HttpCache {
+    fn new(config: &'static HttpCacheConfig) -> Self {
+        let disk_capacity = match &config.disk_dir {
+            Some(dir) => {
+                clean_disk_dir(dir);
+                config.disk_capacity
+            }
+            None => 0,
+        };
+        let stats = Arc::new(HttpCacheStats::default());
+        HttpCache {
+            config,
+            store: Mutex::new(HttpCacheStore::new(
+                config.memory_capacity,
+                disk_capacity,
+                stats.clone(),
+            )),
+            stats,
+            next_file_id: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn stats(&self) -> &Arc<HttpCacheStats> {
+        &self.stats
+    }
+
+    pub(crate) fn usage(&self) -> HttpCacheUsage {
+        let store = self.store.lock().unwrap();
+        HttpCacheUsage {
+            memory_capacity: store.memory.capacity(),
+            memory_used: store.memory.used(),
+            memory_entries: store.memory.len(),
+            disk_capacity: store.disk.capacity(),
+            disk_used: store.disk.used(),
+            disk_entries: store.disk.len(),
+        }
+    }
+
+    /// Change the capacity of each tier, the value 0 means no change
+    pub(crate) fn set_capacity(self: &Arc<Self>, memory: u64, disk: u64) -> anyhow::Result<()> {
+        if disk > 0 && self.config.disk_dir.is_none() {
+            return Err(anyhow!("no disk dir has been set"));
+        }
+        let evicted = {
+            let mut store = self.store.lock().unwrap();
+            let memory = if memory > 0 {
+                memory
+            } else {
+                store.memory.capacity()
+            };
+            let disk = if disk > 0 {
+                disk
+            } else {
+                store.disk.capacity()
+            };
+            store.set_capacity(memory, disk)
+        };
+        self.demote(evicted);
+        Ok(())
+    }
+
+    /// Purge all variants of the url, or all entries if no url specified
+    pub(crate) fn purge(&self, url: Option<&str>) -> usize {
+        self.store.lock().unwrap().purge(url)
+    }
+
+    fn heuristic_max_lifetime(&self) -> u64 {
+        self.config.heuristic_max_lifetime.as_secs()
+    }
+
+    fn lookup_entry(
+        &self,
+        url: &str,
+        req_headers: &HttpHeaderMap,
+    ) -> Option<(String, Arc<HttpCacheEntry>)> {
+        self.store.lock().unwrap().lookup(url, req_headers)
+    }
+
+    fn update_entry(&self, key: &str, entry: Arc<HttpCacheEntry>) {
+        self.store.lock().unwrap().update(key, entry);
+    }
+
+    async fn store_entry(
+        self: &Arc<Self>,
+        url: &str,
+        vary: Vec<HeaderName>,
+        req_headers: &HttpHeaderMap,
+        entry: HttpCacheEntry,
+    ) {
+        let size = entry.body_size();
+        let entry = if size <= self.config.memory_object_max_size {
+            entry
+        } else {
+            let HttpCacheBody::Memory(body) = &entry.body else {
+                return;
+            };
+            match self.write_disk_file(body).await {
+                Ok(Some(body)) => entry.with_body(body),
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("failed to write http cache file: {e}");
+                    return;
+                }
+            }
+        };
+
+        let evicted = {
+            let mut store = self.store.lock().unwrap();
+            let key = store.prepare_key(url, vary, req_headers);
+            store.insert(key, Arc::new(entry))
+        };
+        self.stats.add_stored();
+        self.demote(evicted);
+    }
+
+    /// Move the entries evicted from the memory tier to the disk tier
+    fn demote(self: &Arc<Self>, evicted: EvictedEntries) {
+        if evicted.is_empty() {
+            return;
+        }
+        if self.config.disk_dir.is_none() {
+            self.store.lock().unwrap().forget_all(evicted);
+            return;
+        }
+
+        let cache = self.clone();
+        tokio::spawn(async move {
+            for (key, entry) in evicted {
+                let body = match &entry.body {
+                    HttpCacheBody::Memory(body) => cache.write_disk_file(body).await,
+                    HttpCacheBody::Disk(_) => Ok(None),
+                };
+                let mut store = cache.store.lock().unwrap();
+                match body {
+                    Ok(Some(body)) => store.insert_demoted(key, Arc::new(entry.with_body(body))),
+                    Ok(None) => store.forget_all(vec![(key, entry)]),
+                    Err(e) => {
+                        warn!("failed to write http cache file: {e}");
+                        store.forget_all(vec![(key, entry)]);
+                    }
+                }
+            }
+        });
+    }
+
+    async fn write_disk_file(&self, body: &Bytes) -> std::io::Result<Option<HttpCacheBody>> {
+        let Some(dir) = &self.config.disk_dir else {
+            return Ok(None);
+        };
+        let size = body.len() as u64;
+        if size > self.store.lock().unwrap().disk.capacity() {
+            return Ok(None);
+        }
+
+        let id = self.next_file_id.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("{id:016x}{DISK_FILE_SUFFIX}"));
+        tokio::fs::write(&path, body).await?;
+        let file = HttpCacheDiskFile::new(path, size);
+        Ok(Some(HttpCacheBody::Disk(Arc::new(file))))
+    }
+}
+
+fn clean_disk_dir(dir: &Path) {
+    let Ok(rd) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in rd.flatten() {
+        let path = entry.path();
+        if path.is_file()
+            && path
+                .file_name()
+                .and_then(|v| v.to_str())
+                .map(|v| v.ends_with(DISK_FILE_SUFFIX))
+                .unwrap_or(false)
+        {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
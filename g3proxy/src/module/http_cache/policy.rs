@@ -0,0 +1,557 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use chrono::{DateTime, NaiveDateTime};
+use http::{header, HeaderName};
+
+use g3_types::net::HttpHeaderMap;
+
+/// status codes that are heuristically cacheable, see RFC 9110 Section 15.1
+const HEURISTIC_CACHEABLE_STATUS: &[u16] = &[200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// headers that should not be updated by a 304 response, see RFC 9111 Section 3.2
+const NOT_UPDATED_HEADERS: &[HeaderName] = &[
+    header::CONTENT_LENGTH,
+    header::CONTENT_ENCODING,
+    header::CONTENT_TYPE,
+    header::CONTENT_RANGE,
+    header::TRANSFER_ENCODING,
+];
+
+pub(super) fn parse_http_date(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc2822(s) {
+        return Some(dt.timestamp());
+    }
+    // obsolete RFC 850 format
+    if let Ok(dt) = NaiveDateTime::parse_from_str(s, "%A, %d-%b-%y %H:%M:%S GMT") {
+        return Some(dt.and_utc().timestamp());
+    }
+    // ANSI C's asctime() format
+    if let Ok(dt) = NaiveDateTime::parse_from_str(s, "%a %b %e %H:%M:%S %Y") {
+        return Some(dt.and_utc().timestamp());
+    }
+    None
+}
+
+fn foreach_directive<F>(headers: &HttpHeaderMap, mut f: F)
+where
+    F: FnMut(&str, Option<&str>),
+{
+    for value in headers.get_all(header::CACHE_CONTROL) {
+        for d in value.to_str().split(',') {
+            let d = d.trim();
+            if d.is_empty() {
+                continue;
+            }
+            match d.split_once('=') {
+                Some((k, v)) => {
+                    let v = v.trim().trim_matches('"');
+                    f(&k.trim().to_ascii_lowercase(), Some(v))
+                }
+                None => f(&d.to_ascii_lowercase(), None),
+            }
+        }
+    }
+}
+
+fn parse_delta_seconds(v: Option<&str>) -> Option<u64> {
+    // values that overflow should be treated as the greatest positive integer
+    v.and_then(|v| {
+        if v.is_empty() || !v.bytes().all(|b| b.is_ascii_digit()) {
+            None
+        } else {
+            Some(v.parse::<u64>().unwrap_or(u64::MAX))
+        }
+    })
+}
+
+#[derive(Default)]
+pub(crate) struct RequestCacheControl {
+    pub(super) no_store: bool,
+    pub(super) no_cache: bool,
+    pub(super) max_age: Option<u64>,
+    pub(super) max_stale: Option<u64>,
+    pub(super) min_fresh: Option<u64>,
+}
+
+impl RequestCacheControl {
+    pub(super) fn parse(headers: &HttpHeaderMap) -> Self {
+        let mut cc = RequestCacheControl::default();
+        let mut has_cache_control = false;
+        foreach_directive(headers, |k, v| {
+            has_cache_control = true;
+            match k {
+                "no-store" => cc.no_store = true,
+                "no-cache" => cc.no_cache = true,
+                "max-age" => cc.max_age = parse_delta_seconds(v),
+                "max-stale" => cc.max_stale = Some(parse_delta_seconds(v).unwrap_or(u64::MAX)),
+                "min-fresh" => cc.min_fresh = parse_delta_seconds(v),
+                _ => {}
+            }
+        });
+        if !has_cache_control {
+            // see RFC 9111 Section 5.4
+            for v in headers.get_all(header::PRAGMA) {
+                if v.to_str().to_ascii_lowercase().contains("no-cache") {
+                    cc.no_cache = true;
+                }
+            }
+        }
+        cc
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct ResponseCacheControl {
+    pub(super) no_store: bool,
+    pub(super) no_cache: bool,
+    pub(super) private: bool,
+    pub(super) public: bool,
+    pub(super) must_revalidate: bool,
+    pub(super) proxy_revalidate: bool,
+    pub(super) max_age: Option<u64>,
+    pub(super) s_maxage: Option<u64>,
+}
+
+impl ResponseCacheControl {
+    pub(super) fn parse(headers: &HttpHeaderMap) -> Self {
+        let mut cc = ResponseCacheControl::default();
+        foreach_directive(headers, |k, v| match k {
+            "no-store" => cc.no_store = true,
+            // the qualified form is handled as the unqualified one
+            "no-cache" => cc.no_cache = true,
+            "private" => cc.private = true,
+            "public" => cc.public = true,
+            "must-revalidate" => cc.must_revalidate = true,
+            "proxy-revalidate" => cc.proxy_revalidate = true,
+            "max-age" => cc.max_age = parse_delta_seconds(v),
+            "s-maxage" => cc.s_maxage = parse_delta_seconds(v),
+            _ => {}
+        });
+        cc
+    }
+
+    fn stale_not_allowed(&self) -> bool {
+        self.must_revalidate || self.proxy_revalidate || self.s_maxage.is_some()
+    }
+}
+
+/// Parse the Vary header, the returned list is sorted and deduplicated
+///
+/// None will be returned if the response varies on `*`
+pub(super) fn parse_vary(headers: &HttpHeaderMap) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+    for value in headers.get_all(header::VARY) {
+        for name in value.to_str().split(',') {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            if name == "*" {
+                return None;
+            }
+            if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+                names.push(name);
+            }
+        }
+    }
+    names.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+    names.dedup();
+    Some(names)
+}
+
+/// Get the secondary cache key of the request, see RFC 9111 Section 4.1
+pub(super) fn vary_key(vary: &[HeaderName], req_headers: &HttpHeaderMap) -> String {
+    let mut key = String::new();
+    for name in vary {
+        key.push_str(name.as_str());
+        let mut values = req_headers.get_all(name).iter().peekable();
+        if values.peek().is_some() {
+            key.push(':');
+            for (i, v) in values.enumerate() {
+                if i > 0 {
+                    key.push(',');
+                }
+                // normalize whitespaces
+                for (j, s) in v.to_str().split(',').enumerate() {
+                    if j > 0 {
+                        key.push(',');
+                    }
+                    key.push_str(s.trim());
+                }
+            }
+        }
+        key.push('\n');
+    }
+    key
+}
+
+pub(super) fn is_storable(
+    code: u16,
+    req_cc: &RequestCacheControl,
+    req_has_auth: bool,
+    rsp_cc: &ResponseCacheControl,
+    rsp_headers: &HttpHeaderMap,
+) -> bool {
+    // see RFC 9111 Section 3
+    if !(200..600).contains(&code) || code == 206 || code == 304 {
+        return false;
+    }
+    if req_cc.no_store || rsp_cc.no_store || rsp_cc.private {
+        return false;
+    }
+    if req_has_auth && !(rsp_cc.public || rsp_cc.s_maxage.is_some() || rsp_cc.must_revalidate) {
+        // see RFC 9111 Section 3.5
+        return false;
+    }
+    if rsp_headers.contains_key(header::SET_COOKIE) {
+        // never share the cookies between different clients
+        return false;
+    }
+    rsp_cc.public
+        || rsp_cc.max_age.is_some()
+        || rsp_cc.s_maxage.is_some()
+        || rsp_headers.contains_key(header::EXPIRES)
+        || HEURISTIC_CACHEABLE_STATUS.contains(&code)
+}
+
+/// Cache related response metadata, all timestamps are in unix seconds
+#[derive(Clone)]
+pub(crate) struct ResponseFreshness {
+    pub(super) cc: ResponseCacheControl,
+    date: i64,
+    age_value: u64,
+    request_time: i64,
+    response_time: i64,
+    pub(super) lifetime: u64,
+    pub(super) etag: Option<String>,
+    pub(super) last_modified: Option<String>,
+    last_modified_time: Option<i64>,
+}
+
+impl ResponseFreshness {
+    pub(super) fn new(
+        code: u16,
+        headers: &HttpHeaderMap,
+        request_time: i64,
+        response_time: i64,
+        heuristic_max_lifetime: u64,
+    ) -> Self {
+        let cc = ResponseCacheControl::parse(headers);
+        let date = headers
+            .get(header::DATE)
+            .and_then(|v| parse_http_date(v.to_str()))
+            .unwrap_or(response_time);
+        let age_value = headers
+            .get(header::AGE)
+            .and_then(|v| parse_delta_seconds(Some(v.to_str().trim())))
+            .unwrap_or(0);
+        let etag = headers.get(header::ETAG).map(|v| v.to_str().to_string());
+        let last_modified = headers
+            .get(header::LAST_MODIFIED)
+            .map(|v| v.to_str().to_string());
+        let last_modified_time = last_modified.as_deref().and_then(parse_http_date);
+
+        // see RFC 9111 Section 4.2.1
+        let lifetime = if let Some(v) = cc.s_maxage {
+            v
+        } else if let Some(v) = cc.max_age {
+            v
+        } else if let Some(v) = headers.get(header::EXPIRES) {
+            // invalid date formats, especially "0", represent a time in the past
+            parse_http_date(v.to_str())
+                .map(|expires| expires.saturating_sub(date).max(0) as u64)
+                .unwrap_or(0)
+        } else if let Some(lm) = last_modified_time {
+            // see RFC 9111 Section 4.2.2
+            if cc.public || HEURISTIC_CACHEABLE_STATUS.contains(&code) {
+                let v = (date.saturating_sub(lm).max(0) / 10) as u64;
+                v.min(heuristic_max_lifetime)
+            } else {
+                0
+            }
+        } else {
+            0
+        };
+
+        ResponseFreshness {
+            cc,
+            date,
+            age_value,
+            request_time,
+            response_time,
+            lifetime,
+            etag,
+            last_modified,
+            last_modified_time,
+        }
+    }
+
+    /// see RFC 9111 Section 4.2.3
+    pub(super) fn current_age(&self, now: i64) -> u64 {
+        let apparent_age = self.response_time.saturating_sub(self.date).max(0) as u64;
+        let response_delay = self.response_time.saturating_sub(self.request_time).max(0) as u64;
+        let corrected_age_value = self.age_value.saturating_add(response_delay);
+        let corrected_initial_age = apparent_age.max(corrected_age_value);
+        let resident_time = now.saturating_sub(self.response_time).max(0) as u64;
+        corrected_initial_age.saturating_add(resident_time)
+    }
+
+    /// Check if the stored response can be used without revalidation
+    pub(super) fn is_fresh_for(&self, req_cc: &RequestCacheControl, now: i64) -> bool {
+        if req_cc.no_cache || self.cc.no_cache {
+            return false;
+        }
+
+        let age = self.current_age(now);
+        if let Some(max_age) = req_cc.max_age {
+            if age > max_age {
+                return false;
+            }
+        }
+        if let Some(min_fresh) = req_cc.min_fresh {
+            if self.lifetime < age.saturating_add(min_fresh) {
+                return false;
+            }
+        }
+        if self.lifetime > age {
+            return true;
+        }
+
+        // see RFC 9111 Section 4.2.4
+        if self.cc.stale_not_allowed() {
+            return false;
+        }
+        match req_cc.max_stale {
+            Some(max_stale) => age - self.lifetime <= max_stale,
+            None => false,
+        }
+    }
+
+    pub(super) fn has_validator(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    /// Check if the client's own conditional request matches the stored response,
+    /// see RFC 9110 Section 13.2.2
+    pub(super) fn match_client_condition(&self, req_headers: &HttpHeaderMap) -> bool {
+        let mut inm_values = req_headers.get_all(header::IF_NONE_MATCH).iter().peekable();
+        if inm_values.peek().is_some() {
+            let Some(etag) = &self.etag else {
+                return false;
+            };
+            let etag = strip_weak_prefix(etag);
+            for v in inm_values {
+                for tag in v.to_str().split(',') {
+                    let tag = tag.trim();
+                    if tag == "*" || strip_weak_prefix(tag) == etag {
+                        return true;
+                    }
+                }
+            }
+            return false;
+        }
+
+        if let Some(ims) = req_headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| parse_http_date(v.to_str()))
+        {
+            let last_modified = self.last_modified_time.unwrap_or(self.date);
+            return last_modified <= ims;
+        }
+
+        false
+    }
+
+    /// Update the metadata by a 304 response, see RFC 9111 Section 4.3.4
+    pub(super) fn update_by_304(
+        &self,
+        code: u16,
+        stored_headers: &mut HttpHeaderMap,
+        headers: &HttpHeaderMap,
+        request_time: i64,
+        response_time: i64,
+        heuristic_max_lifetime: u64,
+    ) -> Self {
+        let mut updated = HttpHeaderMap::default();
+        headers.for_each(|name, value| {
+            if !NOT_UPDATED_HEADERS.contains(name) {
+                updated.append(name.clone(), value.clone());
+            }
+        });
+        updated.for_each(|name, _| {
+            stored_headers.remove(name);
+        });
+        updated.for_each(|name, value| {
+            stored_headers.append(name.clone(), value.clone());
+        });
+        let freshness = ResponseFreshness::new(
+            code,
+            stored_headers,
+            request_time,
+            response_time,
+            heuristic_max_lifetime,
+        );
+        stored_headers.remove(header::AGE);
+        freshness
+    }
+}
+
+fn strip_weak_prefix(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+pub(super) fn has_client_condition(req_headers: &HttpHeaderMap) -> bool {
+    req_headers.contains_key(header::IF_NONE_MATCH)
+        || req_headers.contains_key(header::IF_MODIFIED_SINCE)
+        || req_headers.contains_key(header::IF_MATCH)
+        || req_headers.contains_key(header::IF_UNMODIFIED_SINCE)
+        || req_headers.contains_key(header::IF_RANGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use g3_types::net::HttpHeaderValue;
+
+    fn headers(list: &[(&'static str, &str)]) -> HttpHeaderMap {
+        let mut map = HttpHeaderMap::default();
+        for (k, v) in list {
+            map.append(
+                HeaderName::from_static(k),
+                HttpHeaderValue::from_str(v).unwrap(),
+            );
+        }
+        map
+    }
+
+    #[test]
+    fn http_date() {
+        let t = 784111777;
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(t));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(t));
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), Some(t));
+        assert_eq!(parse_http_date("0"), None);
+    }
+
+    #[test]
+    fn storable() {
+        let req_cc = RequestCacheControl::default();
+
+        let h = headers(&[("cache-control", "max-age=60")]);
+        let cc = ResponseCacheControl::parse(&h);
+        assert!(is_storable(200, &req_cc, false, &cc, &h));
+        assert!(!is_storable(206, &req_cc, false, &cc, &h));
+        assert!(!is_storable(200, &req_cc, true, &cc, &h));
+
+        let h = headers(&[("cache-control", "private, max-age=60")]);
+        let cc = ResponseCacheControl::parse(&h);
+        assert!(!is_storable(200, &req_cc, false, &cc, &h));
+
+        let h = headers(&[("cache-control", "public, max-age=60")]);
+        let cc = ResponseCacheControl::parse(&h);
+        assert!(is_storable(200, &req_cc, true, &cc, &h));
+
+        let h = headers(&[]);
+        let cc = ResponseCacheControl::parse(&h);
+        assert!(is_storable(404, &req_cc, false, &cc, &h));
+        assert!(!is_storable(302, &req_cc, false, &cc, &h));
+
+        let h = headers(&[("expires", "Sun, 06 Nov 1994 08:49:37 GMT")]);
+        let cc = ResponseCacheControl::parse(&h);
+        assert!(is_storable(302, &req_cc, false, &cc, &h));
+
+        let h = headers(&[("pragma", "no-cache")]);
+        let req_cc = RequestCacheControl::parse(&h);
+        assert!(req_cc.no_cache);
+        let h = headers(&[("cache-control", "no-store")]);
+        let req_cc = RequestCacheControl::parse(&h);
+        let h = headers(&[("cache-control", "max-age=60")]);
+        let cc = ResponseCacheControl::parse(&h);
+        assert!(!is_storable(200, &req_cc, false, &cc, &h));
+    }
+
+    #[test]
+    fn freshness() {
+        let now = 1_700_000_000;
+        let h = headers(&[("cache-control", "max-age=60, s-maxage=120"), ("age", "10")]);
+        let f = ResponseFreshness::new(200, &h, now - 1, now, 86400);
+        assert_eq!(f.lifetime, 120);
+        assert_eq!(f.current_age(now), 11);
+        let req_cc = RequestCacheControl::default();
+        assert!(f.is_fresh_for(&req_cc, now + 100));
+        assert!(!f.is_fresh_for(&req_cc, now + 110));
+        let req_cc = RequestCacheControl {
+            max_stale: Some(u64::MAX),
+            ..Default::default()
+        };
+        // s-maxage disallows stale responses
+        assert!(!f.is_fresh_for(&req_cc, now + 110));
+
+        let h = headers(&[("last-modified", "Sun, 06 Nov 1994 08:49:37 GMT")]);
+        let f = ResponseFreshness::new(200, &h, now, now, 3600);
+        assert_eq!(f.lifetime, 3600);
+        let req_cc = RequestCacheControl {
+            max_age: Some(10),
+            ..Default::default()
+        };
+        assert!(f.is_fresh_for(&req_cc, now + 10));
+        assert!(!f.is_fresh_for(&req_cc, now + 11));
+
+        let h = headers(&[("expires", "0")]);
+        let f = ResponseFreshness::new(200, &h, now, now, 3600);
+        assert_eq!(f.lifetime, 0);
+        assert!(!f.is_fresh_for(&RequestCacheControl::default(), now));
+    }
+
+    #[test]
+    fn vary() {
+        let h = headers(&[("vary", "Accept-Encoding, accept"), ("vary", "accept")]);
+        let vary = parse_vary(&h).unwrap();
+        assert_eq!(vary, vec![header::ACCEPT, header::ACCEPT_ENCODING]);
+        let h = headers(&[("vary", "*")]);
+        assert!(parse_vary(&h).is_none());
+
+        let r1 = headers(&[("accept-encoding", "gzip,  br")]);
+        let r2 = headers(&[("accept-encoding", "gzip, br")]);
+        let r3 = headers(&[]);
+        assert_eq!(vary_key(&vary, &r1), vary_key(&vary, &r2));
+        assert_ne!(vary_key(&vary, &r1), vary_key(&vary, &r3));
+    }
+
+    #[test]
+    fn client_condition() {
+        let now = 1_700_000_000;
+        let h = headers(&[
+            ("etag", "\"abc\""),
+            ("last-modified", "Sun, 06 Nov 1994 08:49:37 GMT"),
+        ]);
+        let f = ResponseFreshness::new(200, &h, now, now, 3600);
+        assert!(f.match_client_condition(&headers(&[("if-none-match", "W/\"abc\"")])));
+        assert!(!f.match_client_condition(&headers(&[("if-none-match", "\"xyz\"")])));
+        assert!(f.match_client_condition(&headers(&[(
+            "if-modified-since",
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        )])));
+        assert!(!f.match_client_condition(&headers(&[(
+            "if-modified-since",
+            "Sun, 06 Nov 1994 08:49:36 GMT"
+        )])));
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Default)]
+pub(crate) struct HttpCacheStats {
+    hit: AtomicU64,
+    miss: AtomicU64,
+    revalidated: AtomicU64,
+    expired: AtomicU64,
+    stored: AtomicU64,
+    evicted: AtomicU64,
+}
+
+#[derive(Default)]
+pub(crate) struct HttpCacheSnapshot {
+    pub(crate) hit: u64,
+    pub(crate) miss: u64,
+    pub(crate) revalidated: u64,
+    pub(crate) expired: u64,
+    pub(crate) stored: u64,
+    pub(crate) evicted: u64,
+}
+
+impl HttpCacheStats {
+    pub(super) fn add_hit(&self) {
+        self.hit.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_miss(&self) {
+        self.miss.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_revalidated(&self) {
+        self.revalidated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_expired(&self) {
+        self.expired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_stored(&self) {
+        self.stored.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_evicted(&self, count: usize) {
+        self.evicted.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> HttpCacheSnapshot {
+        HttpCacheSnapshot {
+            hit: self.hit.load(Ordering::Relaxed),
+            miss: self.miss.load(Ordering::Relaxed),
+            revalidated: self.revalidated.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+            stored: self.stored.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use ahash::{AHashMap, AHashSet};
+use http::HeaderName;
+use lru::LruCache;
+
+use g3_types::net::HttpHeaderMap;
+
+use super::{policy, HttpCacheEntry, HttpCacheStats};
+
+pub(super) type EvictedEntries = Vec<(String, Arc<HttpCacheEntry>)>;
+
+fn full_key(url: &str, vary_key: &str) -> String {
+    // the url should not contain any line feed
+    format!("{url}\n{vary_key}")
+}
+
+fn url_of_key(key: &str) -> &str {
+    key.split_once('\n').map(|v| v.0).unwrap_or(key)
+}
+
+pub(super) struct HttpCacheTier {
+    lru: LruCache<String, Arc<HttpCacheEntry>>,
+    capacity: u64,
+    used: u64,
+}
+
+impl HttpCacheTier {
+    fn new(capacity: u64) -> Self {
+        HttpCacheTier {
+            lru: LruCache::unbounded(),
+            capacity,
+            used: 0,
+        }
+    }
+
+    #[inline]
+    pub(super) fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    #[inline]
+    pub(super) fn used(&self) -> u64 {
+        self.used
+    }
+
+    #[inline]
+    pub(super) fn len(&self) -> usize {
+        self.lru.len()
+    }
+
+    fn get(&mut self, key: &str) -> Option<Arc<HttpCacheEntry>> {
+        self.lru.get(key).cloned()
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.lru.contains(key)
+    }
+
+    fn insert(&mut self, key: String, entry: Arc<HttpCacheEntry>) -> EvictedEntries {
+        let size = entry.body_size();
+        if let Some(old) = self.lru.put(key, entry) {
+            self.used -= old.body_size();
+        }
+        self.used += size;
+        self.evict()
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Arc<HttpCacheEntry>> {
+        let entry = self.lru.pop(key)?;
+        self.used -= entry.body_size();
+        Some(entry)
+    }
+
+    fn evict(&mut self) -> EvictedEntries {
+        let mut evicted = Vec::new();
+        while self.used > self.capacity {
+            let Some((key, entry)) = self.lru.pop_lru() else {
+                break;
+            };
+            self.used -= entry.body_size();
+            evicted.push((key, entry));
+        }
+        evicted
+    }
+
+    fn set_capacity(&mut self, capacity: u64) -> EvictedEntries {
+        self.capacity = capacity;
+        self.evict()
+    }
+
+    fn clear(&mut self) -> usize {
+        let count = self.lru.len();
+        self.lru.clear();
+        self.used = 0;
+        count
+    }
+}
+
+struct HttpCacheVariants {
+    vary: Vec<HeaderName>,
+    keys: AHashSet<String>,
+}
+
+pub(super) struct HttpCacheStore {
+    variants: AHashMap<String, HttpCacheVariants>,
+    pub(super) memory: HttpCacheTier,
+    pub(super) disk: HttpCacheTier,
+    stats: Arc<HttpCacheStats>,
+}
+
+impl HttpCacheStore {
+    pub(super) fn new(
+        memory_capacity: u64,
+        disk_capacity: u64,
+        stats: Arc<HttpCacheStats>,
+    ) -> Self {
+        HttpCacheStore {
+            variants: AHashMap::new(),
+            memory: HttpCacheTier::new(memory_capacity),
+            disk: HttpCacheTier::new(disk_capacity),
+            stats,
+        }
+    }
+
+    /// Find the stored response for the request, the full key will also be returned
+    pub(super) fn lookup(
+        &mut self,
+        url: &str,
+        req_headers: &HttpHeaderMap,
+    ) -> Option<(String, Arc<HttpCacheEntry>)> {
+        let variants = self.variants.get(url)?;
+        let key = full_key(url, &policy::vary_key(&variants.vary, req_headers));
+        if let Some(entry) = self.memory.get(&key) {
+            return Some((key, entry));
+        }
+        let entry = self.disk.get(&key)?;
+        Some((key, entry))
+    }
+
+    /// Get the full key for a new response to store, all old variants will be dropped if
+    /// the vary header list changed
+    pub(super) fn prepare_key(
+        &mut self,
+        url: &str,
+        vary: Vec<HeaderName>,
+        req_headers: &HttpHeaderMap,
+    ) -> String {
+        let key = full_key(url, &policy::vary_key(&vary, req_headers));
+        if let Some(variants) = self.variants.get(url) {
+            if variants.vary != vary {
+                self.purge(Some(url));
+            }
+        }
+        self.variants
+            .entry(url.to_string())
+            .or_insert_with(|| HttpCacheVariants {
+                vary,
+                keys: AHashSet::new(),
+            });
+        key
+    }
+
+    /// Insert a new entry, the entries evicted from the memory tier will be returned
+    pub(super) fn insert(&mut self, key: String, entry: Arc<HttpCacheEntry>) -> EvictedEntries {
+        let url = url_of_key(&key);
+        let Some(variants) = self.variants.get_mut(url) else {
+            // the variants has been purged
+            return Vec::new();
+        };
+        variants.keys.insert(key.clone());
+
+        if entry.in_memory() {
+            self.disk.remove(&key);
+            self.memory.insert(key, entry)
+        } else {
+            self.memory.remove(&key);
+            let evicted = self.disk.insert(key, entry);
+            self.forget_all(evicted);
+            Vec::new()
+        }
+    }
+
+    /// Insert an entry that has been moved to disk, it will be dropped if no longer valid
+    pub(super) fn insert_demoted(&mut self, key: String, entry: Arc<HttpCacheEntry>) {
+        if self.memory.contains(&key) || self.disk.contains(&key) {
+            // there is a newer one
+            return;
+        }
+        let url = url_of_key(&key);
+        match self.variants.get(url) {
+            Some(variants) if variants.keys.contains(&key) => {}
+            _ => return,
+        }
+        let evicted = self.disk.insert(key, entry);
+        self.forget_all(evicted);
+    }
+
+    /// Replace an existing entry in place, which is used after revalidation
+    pub(super) fn update(&mut self, key: &str, entry: Arc<HttpCacheEntry>) {
+        if self.memory.contains(key) {
+            let evicted = self.memory.insert(key.to_string(), entry);
+            self.forget_all(evicted);
+        } else if self.disk.contains(key) {
+            let evicted = self.disk.insert(key.to_string(), entry);
+            self.forget_all(evicted);
+        }
+    }
+
+    pub(super) fn forget(&mut self, key: &str) {
+        let url = url_of_key(key);
+        if let Some(variants) = self.variants.get_mut(url) {
+            variants.keys.remove(key);
+            if variants.keys.is_empty() {
+                self.variants.remove(url);
+            }
+        }
+    }
+
+    /// Forget the evicted entries that won't be moved to another tier
+    pub(super) fn forget_all(&mut self, evicted: EvictedEntries) {
+        if evicted.is_empty() {
+            return;
+        }
+        self.stats.add_evicted(evicted.len());
+        for (key, _entry) in evicted {
+            self.forget(&key);
+        }
+    }
+
+    pub(super) fn purge(&mut self, url: Option<&str>) -> usize {
+        match url {
+            Some(url) => {
+                let Some(variants) = self.variants.remove(url) else {
+                    return 0;
+                };
+                let mut count = 0;
+                for key in variants.keys {
+                    if self.memory.remove(&key).is_some() {
+                        count += 1;
+                    }
+                    if self.disk.remove(&key).is_some() {
+                        count += 1;
+                    }
+                }
+                count
+            }
+            None => {
+                self.variants.clear();
+                self.memory.clear() + self.disk.clear()
+            }
+        }
+    }
+
+    pub(super) fn set_capacity(&mut self, memory: u64, disk: u64) -> EvictedEntries {
+        let evicted = self.disk.set_capacity(disk);
+        self.forget_all(evicted);
+        self.memory.set_capacity(memory)
+    }
+}
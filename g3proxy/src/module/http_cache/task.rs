@@ -0,0 +1,413 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use chrono::Utc;
+use http::{header, HeaderName, Method, Version};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+use g3_http::{ChunkedDataDecodeReader, HttpBodyType};
+use g3_types::net::{HttpHeaderMap, HttpHeaderValue};
+
+use super::{policy, HttpCache, HttpCacheBody, HttpCacheBodyReader, HttpCacheEntry};
+use super::{RequestCacheControl, ResponseFreshness};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum HttpCacheStatus {
+    Miss,
+    Hit,
+    Revalidated,
+    Expired,
+}
+
+impl HttpCacheStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            HttpCacheStatus::Miss => "miss",
+            HttpCacheStatus::Hit => "hit",
+            HttpCacheStatus::Revalidated => "revalidated",
+            HttpCacheStatus::Expired => "expired",
+        }
+    }
+}
+
+pub(crate) enum HttpCacheLookup {
+    /// the stored response can be sent to client directly
+    Hit(HttpCacheHit),
+    /// the request should be forwarded to upstream
+    Forward(Box<HttpCacheForward>),
+}
+
+impl HttpCache {
+    /// Lookup the cache for a request, None will be returned if the cache should be bypassed
+    pub(crate) fn lookup(
+        self: &Arc<Self>,
+        method: &Method,
+        url: String,
+        req_headers: &HttpHeaderMap,
+        has_body: bool,
+    ) -> Option<HttpCacheLookup> {
+        match *method {
+            Method::GET => {}
+            Method::POST | Method::PUT | Method::DELETE | Method::PATCH => {
+                // see RFC 9111 Section 4.4
+                return Some(HttpCacheLookup::Forward(Box::new(
+                    HttpCacheForward::new_invalidation(self.clone(), url),
+                )));
+            }
+            _ => return None,
+        }
+        if has_body || req_headers.contains_key(header::RANGE) {
+            return None;
+        }
+        let req_cc = RequestCacheControl::parse(req_headers);
+        if req_cc.no_store {
+            return None;
+        }
+
+        let now = Utc::now().timestamp();
+        let mut forward = HttpCacheForward {
+            cache: self.clone(),
+            url,
+            req_cc,
+            req_auth: req_headers.contains_key(header::AUTHORIZATION),
+            req_headers: req_headers.clone(),
+            stale: None,
+            request_time: now,
+            invalidate_only: false,
+            status: Some(HttpCacheStatus::Miss),
+        };
+        let Some((key, entry)) = self.lookup_entry(&forward.url, req_headers) else {
+            self.stats.add_miss();
+            return Some(HttpCacheLookup::Forward(Box::new(forward)));
+        };
+
+        if entry.freshness.is_fresh_for(&forward.req_cc, now) {
+            self.stats.add_hit();
+            let not_modified = entry.freshness.match_client_condition(req_headers);
+            return Some(HttpCacheLookup::Hit(HttpCacheHit {
+                age: entry.freshness.current_age(now),
+                entry,
+                not_modified,
+                status: HttpCacheStatus::Hit,
+            }));
+        }
+
+        forward.status = Some(HttpCacheStatus::Expired);
+        if entry.freshness.has_validator() && !policy::has_client_condition(req_headers) {
+            forward.stale = Some((key, entry));
+        }
+        Some(HttpCacheLookup::Forward(Box::new(forward)))
+    }
+}
+
+pub(crate) struct HttpCacheHit {
+    entry: Arc<HttpCacheEntry>,
+    age: u64,
+    not_modified: bool,
+    status: HttpCacheStatus,
+}
+
+impl HttpCacheHit {
+    #[inline]
+    pub(crate) fn status(&self) -> HttpCacheStatus {
+        self.status
+    }
+
+    pub(crate) fn code(&self) -> u16 {
+        if self.not_modified {
+            304
+        } else {
+            self.entry.code
+        }
+    }
+
+    pub(crate) fn serialize_head(&self, version: Version, keep_alive: bool) -> Vec<u8> {
+        self.entry
+            .serialize_head(version, self.age, keep_alive, self.not_modified)
+    }
+
+    pub(crate) fn has_body(&self) -> bool {
+        !self.not_modified && self.entry.body_size() > 0
+    }
+
+    pub(crate) async fn body_reader(&self) -> io::Result<HttpCacheBodyReader> {
+        self.entry.body_reader().await
+    }
+}
+
+pub(crate) struct HttpCacheForward {
+    cache: Arc<HttpCache>,
+    url: String,
+    req_cc: RequestCacheControl,
+    req_auth: bool,
+    req_headers: HttpHeaderMap,
+    stale: Option<(String, Arc<HttpCacheEntry>)>,
+    request_time: i64,
+    invalidate_only: bool,
+    status: Option<HttpCacheStatus>,
+}
+
+impl HttpCacheForward {
+    fn new_invalidation(cache: Arc<HttpCache>, url: String) -> Self {
+        HttpCacheForward {
+            cache,
+            url,
+            req_cc: RequestCacheControl::default(),
+            req_auth: false,
+            req_headers: HttpHeaderMap::default(),
+            stale: None,
+            request_time: 0,
+            invalidate_only: true,
+            status: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn status(&self) -> Option<HttpCacheStatus> {
+        self.status
+    }
+
+    /// Get the validators that should be added to the request sent to upstream
+    pub(crate) fn validators(&self) -> Vec<(HeaderName, HttpHeaderValue)> {
+        let mut headers = Vec::with_capacity(2);
+        if let Some((_, entry)) = &self.stale {
+            if let Some(etag) = &entry.freshness.etag {
+                if let Ok(v) = etag.parse() {
+                    headers.push((header::IF_NONE_MATCH, v));
+                }
+            }
+            if let Some(last_modified) = &entry.freshness.last_modified {
+                if let Ok(v) = last_modified.parse() {
+                    headers.push((header::IF_MODIFIED_SINCE, v));
+                }
+            }
+        }
+        headers
+    }
+
+    /// Use the stored response if upstream returned 304 for our own validators
+    pub(crate) fn not_modified(
+        &mut self,
+        code: u16,
+        rsp_headers: &HttpHeaderMap,
+    ) -> Option<HttpCacheHit> {
+        if code != 304 {
+            return None;
+        }
+        let (key, entry) = self.stale.take()?;
+
+        let now = Utc::now().timestamp();
+        let mut headers = entry.headers.clone();
+        let freshness = entry.freshness.update_by_304(
+            entry.code,
+            &mut headers,
+            rsp_headers,
+            self.request_time,
+            now,
+            self.cache.heuristic_max_lifetime(),
+        );
+        let entry = Arc::new(HttpCacheEntry {
+            code: entry.code,
+            reason: entry.reason.clone(),
+            headers,
+            freshness,
+            body: entry.body.clone(),
+        });
+        self.cache.update_entry(&key, entry.clone());
+        self.cache.stats.add_revalidated();
+        self.status = Some(HttpCacheStatus::Revalidated);
+
+        Some(HttpCacheHit {
+            age: entry.freshness.current_age(now),
+            entry,
+            not_modified: false,
+            status: HttpCacheStatus::Revalidated,
+        })
+    }
+
+    /// Get a recorder if the response should be stored
+    pub(crate) fn recorder(
+        &mut self,
+        code: u16,
+        reason: &str,
+        rsp_headers: &HttpHeaderMap,
+        body_type: Option<HttpBodyType>,
+        body_line_max_len: usize,
+    ) -> Option<HttpCacheRecorder> {
+        if self.invalidate_only {
+            if code < 400 {
+                self.cache.purge(Some(&self.url));
+            }
+            return None;
+        }
+        if self.stale.take().is_some() {
+            self.cache.stats.add_expired();
+        }
+
+        let rsp_cc = policy::ResponseCacheControl::parse(rsp_headers);
+        if !policy::is_storable(code, &self.req_cc, self.req_auth, &rsp_cc, rsp_headers) {
+            return None;
+        }
+        let vary = policy::parse_vary(rsp_headers)?;
+
+        let max_size = self.cache.config.object_max_size;
+        let chunked = match body_type {
+            Some(HttpBodyType::ContentLength(size)) => {
+                if size > max_size {
+                    return None;
+                }
+                false
+            }
+            Some(HttpBodyType::Chunked) => true,
+            Some(HttpBodyType::ReadUntilEnd) | None => false,
+        };
+
+        let now = Utc::now().timestamp();
+        let freshness = ResponseFreshness::new(
+            code,
+            rsp_headers,
+            self.request_time,
+            now,
+            self.cache.heuristic_max_lifetime(),
+        );
+        let mut headers = rsp_headers.clone();
+        headers.remove(header::CONTENT_LENGTH);
+        headers.remove(header::AGE);
+
+        Some(HttpCacheRecorder {
+            cache: self.cache.clone(),
+            url: std::mem::take(&mut self.url),
+            vary,
+            req_headers: std::mem::take(&mut self.req_headers),
+            code,
+            reason: reason.to_string(),
+            headers,
+            freshness,
+            chunked,
+            body_line_max_len,
+            max_size,
+            buf: Some(Vec::new()),
+        })
+    }
+}
+
+pub(crate) struct HttpCacheRecorder {
+    cache: Arc<HttpCache>,
+    url: String,
+    vary: Vec<HeaderName>,
+    req_headers: HttpHeaderMap,
+    code: u16,
+    reason: String,
+    headers: HttpHeaderMap,
+    freshness: ResponseFreshness,
+    chunked: bool,
+    body_line_max_len: usize,
+    max_size: u64,
+    buf: Option<Vec<u8>>,
+}
+
+impl HttpCacheRecorder {
+    fn record(&mut self, data: &[u8]) {
+        if let Some(buf) = &mut self.buf {
+            if (buf.len() + data.len()) as u64 > self.max_size {
+                self.buf = None;
+            } else {
+                buf.extend_from_slice(data);
+            }
+        }
+    }
+
+    /// Store the response in background after all body data recorded
+    pub(crate) fn finish(self) {
+        if self.buf.is_none() {
+            return;
+        }
+        tokio::spawn(async move {
+            let _ = self.store().await;
+        });
+    }
+
+    async fn store(mut self) -> io::Result<()> {
+        let Some(buf) = self.buf.take() else {
+            return Ok(());
+        };
+        let body = if self.chunked {
+            let mut reader = buf.as_slice();
+            let mut decoder = ChunkedDataDecodeReader::new(&mut reader, self.body_line_max_len);
+            let mut body = Vec::with_capacity(buf.len());
+            decoder.read_to_end(&mut body).await?;
+            body
+        } else {
+            buf
+        };
+
+        let entry = HttpCacheEntry {
+            code: self.code,
+            reason: self.reason,
+            headers: self.headers,
+            freshness: self.freshness,
+            body: HttpCacheBody::Memory(Bytes::from(body)),
+        };
+        self.cache
+            .store_entry(&self.url, self.vary, &self.req_headers, entry)
+            .await;
+        Ok(())
+    }
+}
+
+pub(crate) struct HttpCacheRecordReader<R> {
+    inner: R,
+    recorder: Option<HttpCacheRecorder>,
+}
+
+impl<R> HttpCacheRecordReader<R> {
+    /// The response will be stored if all body data has been read out
+    pub(crate) fn new(inner: R, recorder: Option<HttpCacheRecorder>) -> Self {
+        HttpCacheRecordReader { inner, recorder }
+    }
+}
+
+impl<R> AsyncRead for HttpCacheRecordReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let old_len = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let data = &buf.filled()[old_len..];
+        if data.is_empty() {
+            if buf.remaining() > 0 {
+                // end of body
+                if let Some(recorder) = self.recorder.take() {
+                    recorder.finish();
+                }
+            }
+        } else if let Some(recorder) = &mut self.recorder {
+            recorder.record(data);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
@@ -18,13 +18,13 @@ use std::io::{self, Write};
 use std::net::{IpAddr, SocketAddr};
 
 use ascii::AsciiStr;
-use http::{StatusCode, Version};
+use http::{HeaderName, HeaderValue, Response, StatusCode, Version};
 use mime::Mime;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use g3_ftp_client::FtpConnectError;
 use g3_http::server::HttpRequestParseError;
-use g3_types::net::ConnectError;
+use g3_types::net::{ConnectError, HttpUpgradeToken};
 
 use crate::module::http_header;
 use crate::module::tcp_connect::TcpConnectError;
@@ -101,7 +101,6 @@ impl HttpProxyClientResponse {
         HttpProxyClientResponse::from_standard(StatusCode::METHOD_NOT_ALLOWED, version, true)
     }
 
-    #[allow(unused)]
     #[inline]
     pub(crate) fn unimplemented(version: Version) -> Self {
         HttpProxyClientResponse::from_standard(StatusCode::NOT_IMPLEMENTED, version, true)
@@ -461,6 +460,58 @@ impl HttpProxyClientResponse {
             .unwrap_or_else(|| CustomStatusCode::canonical_reason(code))
     }
 
+    /// build the response head for HTTP/2 streams, with all extra headers set
+    pub(crate) fn to_h2_response(&self) -> Response<()> {
+        let mut rsp = Response::new(());
+        *rsp.status_mut() = self.status;
+        *rsp.version_mut() = Version::HTTP_2;
+        let headers = rsp.headers_mut();
+        for line in &self.extra_headers {
+            let Some((name, value)) = line.trim_end().split_once(':') else {
+                continue;
+            };
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.trim().as_bytes()),
+                HeaderValue::from_str(value.trim()),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        rsp
+    }
+
+    pub(crate) fn proxy_auth_required(version: Version, realm: &AsciiStr, close: bool) -> Self {
+        let mut response = HttpProxyClientResponse::from_standard(
+            StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+            version,
+            close,
+        );
+        let auth_header = g3_http::header::proxy_authenticate_basic(realm.as_str());
+        response.add_extra_header(auth_header);
+        response
+    }
+
+    /// the success response for connect-udp requests, see RFC 9298
+    pub(crate) fn connect_udp_ok(version: Version) -> Self {
+        let mut response = if version == Version::HTTP_2 {
+            HttpProxyClientResponse::from_standard(StatusCode::OK, version, false)
+        } else {
+            let mut response = HttpProxyClientResponse::from_standard(
+                StatusCode::SWITCHING_PROTOCOLS,
+                version,
+                false,
+            );
+            response.add_extra_header("Connection: Upgrade\r\n".to_string());
+            response.add_extra_header(format!("Upgrade: {}\r\n", HttpUpgradeToken::ConnectUdp));
+            response
+        };
+        response.add_extra_header(format!(
+            "Capsule-Protocol: {}\r\n",
+            g3_http::capsule::CAPSULE_PROTOCOL_HEADER_VALUE
+        ));
+        response
+    }
+
     pub(crate) async fn reply_ok_to_connect<W>(&self, writer: &mut W) -> io::Result<()>
     where
         W: AsyncWrite + Unpin,
@@ -567,13 +618,7 @@ impl HttpProxyClientResponse {
     where
         W: AsyncWrite + Unpin,
     {
-        let mut response = HttpProxyClientResponse::from_standard(
-            StatusCode::PROXY_AUTHENTICATION_REQUIRED,
-            version,
-            close,
-        );
-        let auth_header = g3_http::header::proxy_authenticate_basic(realm.as_str());
-        response.add_extra_header(auth_header);
+        let response = HttpProxyClientResponse::proxy_auth_required(version, realm, close);
         response.reply_err(writer).await
     }
 
@@ -17,6 +17,8 @@
 use http::{Method, Uri};
 use tokio::time::{Duration, Instant};
 
+use crate::module::http_cache::HttpCacheStatus;
+
 pub(crate) struct HttpForwardTaskNotes {
     pub(crate) method: Method,
     pub(crate) uri: Uri,
@@ -31,6 +33,7 @@ pub(crate) struct HttpForwardTaskNotes {
     pub(crate) dur_rsp_recv_hdr: Duration,
     pub(crate) dur_rsp_recv_all: Duration,
     pub(crate) retry_new_connection: bool,
+    pub(crate) cache_status: Option<HttpCacheStatus>,
 }
 
 impl HttpForwardTaskNotes {
@@ -55,6 +58,7 @@ impl HttpForwardTaskNotes {
             dur_rsp_recv_hdr: Duration::default(),
             dur_rsp_recv_all: Duration::default(),
             retry_new_connection: false,
+            cache_status: None,
         }
     }
 
@@ -15,6 +15,7 @@
  */
 
 pub(crate) mod ftp_over_http;
+pub(crate) mod http_cache;
 pub(crate) mod http_forward;
 pub(crate) mod http_header;
 pub(crate) mod tcp_connect;
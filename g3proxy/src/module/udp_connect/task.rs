@@ -44,7 +44,6 @@ impl UdpConnectTaskNotes {
         }
     }
 
-    #[allow(unused)]
     pub(crate) fn new(upstream: UpstreamAddr, buf_conf: SocketBufferConfig) -> Self {
         UdpConnectTaskNotes {
             buf_conf,
@@ -25,6 +25,8 @@ use g3_ftp_client::FtpConnectError;
 use g3_http::client::HttpResponseParseError;
 use g3_http::server::HttpRequestParseError;
 use g3_icap_client::reqmod::h1::H1ReqmodAdaptationError;
+use g3_icap_client::reqmod::mail::SmtpAdaptationError;
+use g3_icap_client::reqmod::websocket::WebSocketAdaptationError;
 use g3_icap_client::respmod::h1::H1RespmodAdaptationError;
 use g3_io_ext::{
     IdleForceQuitReason, UdpCopyClientError, UdpCopyError, UdpCopyRemoteError, UdpRelayClientError,
@@ -45,6 +47,8 @@ pub(crate) enum ServerTaskForbiddenError {
     ClientIpBlocked,
     #[error("request rate limited")]
     RateLimited,
+    #[error("user quota exceeded")]
+    QuotaExceeded,
     #[error("proxy request type banned")]
     ProtoBanned,
     #[error("source address blocked")]
@@ -57,6 +61,8 @@ pub(crate) enum ServerTaskForbiddenError {
     FullyLoaded,
     #[error("http ua blocked")]
     UaBlocked,
+    #[error("http url blocked")]
+    UrlBlocked,
     #[error("user blocked")]
     UserBlocked,
 }
@@ -389,6 +395,28 @@ impl From<H1ReqmodAdaptationError> for ServerTaskError {
     }
 }
 
+impl From<SmtpAdaptationError> for ServerTaskError {
+    fn from(e: SmtpAdaptationError) -> Self {
+        match e {
+            SmtpAdaptationError::SmtpClientReadFailed(e) => ServerTaskError::ClientTcpReadFailed(e),
+            SmtpAdaptationError::SmtpClientReadIdle => {
+                ServerTaskError::ClientAppTimeout("idle while reading")
+            }
+            SmtpAdaptationError::IdleForceQuit(reason) => match reason {
+                IdleForceQuitReason::UserBlocked => ServerTaskError::CanceledAsUserBlocked,
+                IdleForceQuitReason::ServerQuit => ServerTaskError::CanceledAsServerQuit,
+            },
+            e => ServerTaskError::InternalAdapterError(anyhow!("reqmod: {e}")),
+        }
+    }
+}
+
+impl From<WebSocketAdaptationError> for ServerTaskError {
+    fn from(e: WebSocketAdaptationError) -> Self {
+        ServerTaskError::InternalAdapterError(anyhow!("reqmod: {e}"))
+    }
+}
+
 impl From<H1RespmodAdaptationError> for ServerTaskError {
     fn from(e: H1RespmodAdaptationError) -> Self {
         match e {
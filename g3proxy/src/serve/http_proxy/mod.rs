@@ -17,6 +17,9 @@
 mod stats;
 use stats::HttpProxyServerStats;
 
+mod pac;
+use pac::HttpProxyPacFile;
+
 mod task;
 
 mod server;
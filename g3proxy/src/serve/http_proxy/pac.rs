@@ -0,0 +1,192 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use ip_network_table::IpNetworkTable;
+use mime::Mime;
+use once_cell::sync::Lazy;
+
+use crate::config::server::http_proxy::HttpProxyPacFileConfig;
+
+static PAC_CONTENT_TYPE: Lazy<Mime> =
+    Lazy::new(|| "application/x-ns-proxy-autoconfig".parse().unwrap());
+
+enum PacTemplatePart {
+    Text(String),
+    Var(String),
+}
+
+fn parse_template(s: &str) -> Vec<PacTemplatePart> {
+    let mut parts = Vec::new();
+    let mut left = s;
+    while let Some(start) = left.find("{{") {
+        let Some(len) = left[start + 2..].find("}}") else {
+            break;
+        };
+        if start > 0 {
+            parts.push(PacTemplatePart::Text(left[..start].to_string()));
+        }
+        let name = left[start + 2..start + 2 + len].trim();
+        parts.push(PacTemplatePart::Var(name.to_string()));
+        left = &left[start + 4 + len..];
+    }
+    if !left.is_empty() {
+        parts.push(PacTemplatePart::Text(left.to_string()));
+    }
+    parts
+}
+
+pub(crate) struct HttpProxyPacFile {
+    template: Vec<PacTemplatePart>,
+    max_age: u64,
+    vars: BTreeMap<String, String>,
+    subnet_vars: IpNetworkTable<Arc<BTreeMap<String, String>>>,
+}
+
+impl HttpProxyPacFile {
+    pub(crate) fn new(config: &HttpProxyPacFileConfig) -> Self {
+        let mut subnet_vars = IpNetworkTable::new();
+        for v in &config.subnet_vars {
+            let vars = Arc::new(v.vars.clone());
+            for subnet in &v.subnets {
+                subnet_vars.insert(*subnet, Arc::clone(&vars));
+            }
+        }
+        HttpProxyPacFile {
+            template: parse_template(&config.template),
+            max_age: config.max_age.as_secs(),
+            vars: config.vars.clone(),
+            subnet_vars,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn content_type(&self) -> &'static Mime {
+        &PAC_CONTENT_TYPE
+    }
+
+    #[inline]
+    pub(crate) fn max_age(&self) -> u64 {
+        self.max_age
+    }
+
+    /// Render the PAC file for the client, the vars set for the client subnet take precedence
+    pub(crate) fn render(&self, client_ip: IpAddr, server_addr: SocketAddr) -> String {
+        let subnet_vars = self
+            .subnet_vars
+            .longest_match(client_ip)
+            .map(|(_, vars)| vars.as_ref());
+
+        let mut s = String::with_capacity(4096);
+        for part in &self.template {
+            match part {
+                PacTemplatePart::Text(t) => s.push_str(t),
+                PacTemplatePart::Var(name) => {
+                    if let Some(v) = subnet_vars.and_then(|vars| vars.get(name)) {
+                        s.push_str(v);
+                    } else if let Some(v) = self.vars.get(name) {
+                        s.push_str(v);
+                    } else {
+                        match name.as_str() {
+                            "client_ip" => s.push_str(&client_ip.to_string()),
+                            "server_ip" => s.push_str(&server_addr.ip().to_string()),
+                            "server_port" => s.push_str(&server_addr.port().to_string()),
+                            "server_addr" => s.push_str(&server_addr.to_string()),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use ip_network::IpNetwork;
+
+    use crate::config::server::http_proxy::HttpProxyPacSubnetVars;
+
+    #[test]
+    fn render() {
+        let mut vars = BTreeMap::new();
+        vars.insert("proxy".to_string(), "PROXY 192.0.2.1:3128".to_string());
+        let mut local_vars = BTreeMap::new();
+        local_vars.insert("proxy".to_string(), "PROXY 10.0.0.1:3128".to_string());
+        let mut subnet_vars = IpNetworkTable::new();
+        subnet_vars.insert(
+            IpNetwork::from_str("10.0.0.0/8").unwrap(),
+            Arc::new(local_vars),
+        );
+
+        let pac = HttpProxyPacFile {
+            template: parse_template("return \"{{ proxy }}; {{unknown}}PROXY {{server_addr}}\";{{"),
+            max_age: 60,
+            vars,
+            subnet_vars,
+        };
+        let server_addr = SocketAddr::from_str("192.0.2.1:8080").unwrap();
+
+        let s = pac.render(IpAddr::from_str("192.0.2.10").unwrap(), server_addr);
+        assert_eq!(
+            s,
+            "return \"PROXY 192.0.2.1:3128; PROXY 192.0.2.1:8080\";{{"
+        );
+
+        let s = pac.render(IpAddr::from_str("10.1.1.1").unwrap(), server_addr);
+        assert_eq!(s, "return \"PROXY 10.0.0.1:3128; PROXY 192.0.2.1:8080\";{{");
+    }
+
+    #[test]
+    fn new_from_config() {
+        let mut vars = BTreeMap::new();
+        vars.insert("proxy".to_string(), "PROXY 192.0.2.1:3128".to_string());
+        let mut local_vars = BTreeMap::new();
+        local_vars.insert("proxy".to_string(), "DIRECT".to_string());
+        let config = HttpProxyPacFileConfig {
+            paths: BTreeSet::from(["/proxy.pac".to_string()]),
+            template: "{{proxy}}|{{client_ip}}".to_string(),
+            max_age: Duration::from_secs(60),
+            vars,
+            subnet_vars: vec![HttpProxyPacSubnetVars {
+                subnets: BTreeSet::from([
+                    IpNetwork::from_str("10.0.0.0/8").unwrap(),
+                    IpNetwork::from_str("2001:db8::/32").unwrap(),
+                ]),
+                vars: local_vars,
+            }],
+        };
+        let pac = HttpProxyPacFile::new(&config);
+        assert_eq!(pac.max_age(), 60);
+        let server_addr = SocketAddr::from_str("192.0.2.1:8080").unwrap();
+
+        let s = pac.render(IpAddr::from_str("192.0.2.10").unwrap(), server_addr);
+        assert_eq!(s, "PROXY 192.0.2.1:3128|192.0.2.10");
+        let s = pac.render(IpAddr::from_str("10.1.1.1").unwrap(), server_addr);
+        assert_eq!(s, "DIRECT|10.1.1.1");
+        let s = pac.render(IpAddr::from_str("2001:db8::1").unwrap(), server_addr);
+        assert_eq!(s, "DIRECT|2001:db8::1");
+    }
+}
@@ -22,6 +22,7 @@ use anyhow::{anyhow, Context};
 use arc_swap::{ArcSwap, ArcSwapOption};
 use async_trait::async_trait;
 use log::debug;
+use openssl::x509::X509;
 #[cfg(feature = "quic")]
 use quinn::Connection;
 use slog::Logger;
@@ -36,13 +37,13 @@ use g3_openssl::SslStream;
 use g3_types::acl::{AclAction, AclNetworkRule};
 use g3_types::acl_set::AclDstHostRuleSet;
 use g3_types::metrics::MetricsName;
-use g3_types::net::OpensslClientConfig;
+use g3_types::net::{AlpnProtocol, OpensslClientConfig};
 
 use super::task::{
-    CommonTaskContext, HttpProxyPipelineReaderTask, HttpProxyPipelineStats,
-    HttpProxyPipelineWriterTask,
+    CommonTaskContext, HttpProxyH2ConnectionTask, HttpProxyPipelineReaderTask,
+    HttpProxyPipelineStats, HttpProxyPipelineWriterTask,
 };
-use super::HttpProxyServerStats;
+use super::{HttpProxyPacFile, HttpProxyServerStats};
 use crate::audit::AuditHandle;
 use crate::auth::UserGroup;
 use crate::config::server::http_proxy::HttpProxyServerConfig;
@@ -61,6 +62,7 @@ pub(crate) struct HttpProxyServer {
     tls_client_config: Arc<OpensslClientConfig>,
     ingress_net_filter: Option<AclNetworkRule>,
     dst_host_filter: Option<Arc<AclDstHostRuleSet>>,
+    pac_file: Option<Arc<HttpProxyPacFile>>,
     reload_sender: broadcast::Sender<ServerReloadCommand>,
     task_logger: Logger,
 
@@ -82,9 +84,15 @@ impl HttpProxyServer {
 
         let mut tls_accept_timeout = Duration::from_secs(10);
         let tls_acceptor = if let Some(tls_config_builder) = &config.server_tls_config {
-            let tls_server_config = tls_config_builder
-                .build()
-                .context("failed to build tls server config")?;
+            let tls_server_config = if config.enable_h2 {
+                tls_config_builder.build_with_alpn_protocols(Some(vec![
+                    AlpnProtocol::Http2,
+                    AlpnProtocol::Http11,
+                ]))
+            } else {
+                tls_config_builder.build()
+            }
+            .context("failed to build tls server config")?;
             tls_accept_timeout = tls_server_config.accept_timeout;
             Some(TlsAcceptor::from(tls_server_config.driver))
         } else {
@@ -106,6 +114,11 @@ impl HttpProxyServer {
             .as_ref()
             .map(|builder| Arc::new(builder.build()));
 
+        let pac_file = config
+            .pac_file
+            .as_ref()
+            .map(|config| Arc::new(HttpProxyPacFile::new(config)));
+
         let task_logger = config.get_task_logger();
 
         // always update extra metrics tags
@@ -124,6 +137,7 @@ impl HttpProxyServer {
             tls_client_config: Arc::new(tls_client_config),
             ingress_net_filter,
             dst_host_filter,
+            pac_file,
             reload_sender,
             task_logger,
             escaper: ArcSwap::new(escaper),
@@ -163,7 +177,11 @@ impl HttpProxyServer {
         }
     }
 
-    fn get_common_task_context(&self, cc_info: ClientConnectionInfo) -> Arc<CommonTaskContext> {
+    fn get_common_task_context(
+        &self,
+        cc_info: ClientConnectionInfo,
+        client_cert: Option<X509>,
+    ) -> Arc<CommonTaskContext> {
         Arc::new(CommonTaskContext {
             server_config: Arc::clone(&self.config),
             server_stats: Arc::clone(&self.server_stats),
@@ -171,9 +189,11 @@ impl HttpProxyServer {
             escaper: self.escaper.load().as_ref().clone(),
             audit_handle: self.audit_handle.load_full(),
             cc_info,
+            client_cert,
             tls_client_config: self.tls_client_config.clone(),
             task_logger: self.task_logger.clone(),
             dst_host_filter: self.dst_host_filter.clone(),
+            pac_file: self.pac_file.clone(),
         })
     }
 
@@ -194,11 +214,15 @@ impl HttpProxyServer {
         false
     }
 
-    async fn spawn_stream_task<T>(&self, stream: T, cc_info: ClientConnectionInfo)
-    where
+    async fn spawn_stream_task<T>(
+        &self,
+        stream: T,
+        cc_info: ClientConnectionInfo,
+        client_cert: Option<X509>,
+    ) where
         T: AsyncRead + AsyncWrite + Send + Sync + 'static,
     {
-        let ctx = self.get_common_task_context(cc_info);
+        let ctx = self.get_common_task_context(cc_info, client_cert);
         let pipeline_stats = Arc::new(HttpProxyPipelineStats::default());
         let (task_sender, task_receiver) = mpsc::channel(ctx.server_config.pipeline_size);
 
@@ -218,8 +242,21 @@ impl HttpProxyServer {
         w_task.into_running().await
     }
 
+    async fn spawn_h2_task<T>(
+        &self,
+        stream: T,
+        cc_info: ClientConnectionInfo,
+        client_cert: Option<X509>,
+    ) where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let ctx = self.get_common_task_context(cc_info, client_cert);
+        let task = HttpProxyH2ConnectionTask::new(&ctx, self.user_group.load_full());
+        task.into_running(stream).await
+    }
+
     async fn spawn_tcp_task(&self, stream: TcpStream, cc_info: ClientConnectionInfo) {
-        let ctx = self.get_common_task_context(cc_info);
+        let ctx = self.get_common_task_context(cc_info, None);
         let pipeline_stats = Arc::new(HttpProxyPipelineStats::default());
         let (task_sender, task_receiver) = mpsc::channel(ctx.server_config.pipeline_size);
 
@@ -244,7 +281,7 @@ impl HttpProxyServer {
         recv_stream: quinn::RecvStream,
         cc_info: ClientConnectionInfo,
     ) {
-        let ctx = self.get_common_task_context(cc_info);
+        let ctx = self.get_common_task_context(cc_info, None);
         let pipeline_stats = Arc::new(HttpProxyPipelineStats::default());
         let (task_sender, task_receiver) = mpsc::channel(ctx.server_config.pipeline_size);
 
@@ -358,7 +395,15 @@ impl AcceptTcpServer for HttpProxyServer {
 
         if let Some(tls_acceptor) = &self.tls_acceptor {
             match tokio::time::timeout(self.tls_accept_timeout, tls_acceptor.accept(stream)).await {
-                Ok(Ok(tls_stream)) => self.spawn_stream_task(tls_stream, cc_info).await,
+                Ok(Ok(tls_stream)) => {
+                    let client_cert = crate::auth::rustls_peer_cert(tls_stream.get_ref().1);
+                    if tls_stream.get_ref().1.alpn_protocol() == Some(b"h2") {
+                        self.spawn_h2_task(tls_stream, cc_info, client_cert).await
+                    } else {
+                        self.spawn_stream_task(tls_stream, cc_info, client_cert)
+                            .await
+                    }
+                }
                 Ok(Err(e)) => {
                     self.listen_stats.add_failed();
                     debug!(
@@ -451,7 +496,12 @@ impl Server for HttpProxyServer {
             return;
         }
 
-        self.spawn_stream_task(stream, cc_info).await;
+        let client_cert = crate::auth::rustls_peer_cert(stream.get_ref().1);
+        if stream.get_ref().1.alpn_protocol() == Some(b"h2") {
+            self.spawn_h2_task(stream, cc_info, client_cert).await;
+        } else {
+            self.spawn_stream_task(stream, cc_info, client_cert).await;
+        }
     }
 
     async fn run_openssl_task(&self, stream: SslStream<TcpStream>, cc_info: ClientConnectionInfo) {
@@ -461,6 +511,11 @@ impl Server for HttpProxyServer {
             return;
         }
 
-        self.spawn_stream_task(stream, cc_info).await;
+        let client_cert = stream.ssl().peer_certificate();
+        if stream.ssl().selected_alpn_protocol() == Some(b"h2") {
+            self.spawn_h2_task(stream, cc_info, client_cert).await;
+        } else {
+            self.spawn_stream_task(stream, cc_info, client_cert).await;
+        }
     }
 }
@@ -21,7 +21,7 @@ use std::sync::Arc;
 use arc_swap::ArcSwapOption;
 
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
-use g3_types::stats::{StatId, TcpIoSnapshot, TcpIoStats};
+use g3_types::stats::{StatId, TcpIoSnapshot, TcpIoStats, UdpIoSnapshot, UdpIoStats};
 
 use crate::serve::{
     ServerForbiddenSnapshot, ServerForbiddenStats, ServerPerTaskStats, ServerStats,
@@ -43,10 +43,12 @@ pub(crate) struct HttpProxyServerStats {
     pub task_http_connect: ServerPerTaskStats,
     pub task_http_forward: ServerPerTaskStats,
     pub task_ftp_over_http: ServerPerTaskStats,
+    pub task_udp_connect: ServerPerTaskStats,
 
     pub io_http: TcpIoStats,
     pub io_connect: TcpIoStats,
     pub io_untrusted: TcpIoStats,
+    pub io_udp: UdpIoStats,
 }
 
 impl HttpProxyServerStats {
@@ -62,9 +64,11 @@ impl HttpProxyServerStats {
             task_http_connect: Default::default(),
             task_http_forward: Default::default(),
             task_ftp_over_http: Default::default(),
+            task_udp_connect: Default::default(),
             io_http: Default::default(),
             io_connect: Default::default(),
             io_untrusted: Default::default(),
+            io_udp: Default::default(),
         }
     }
 
@@ -119,6 +123,7 @@ impl ServerStats for HttpProxyServerStats {
         self.task_http_connect.get_task_total()
             + self.task_http_forward.get_task_total()
             + self.task_ftp_over_http.get_task_total()
+            + self.task_udp_connect.get_task_total()
     }
 
     fn get_alive_count(&self) -> i32 {
@@ -126,6 +131,7 @@ impl ServerStats for HttpProxyServerStats {
         self.task_http_connect.get_alive_count()
             + self.task_http_forward.get_alive_count()
             + self.task_ftp_over_http.get_alive_count()
+            + self.task_udp_connect.get_alive_count()
     }
 
     fn tcp_io_snapshot(&self) -> Option<TcpIoSnapshot> {
@@ -134,6 +140,11 @@ impl ServerStats for HttpProxyServerStats {
         Some(self.io_http.snapshot() + self.io_connect.snapshot())
     }
 
+    fn udp_io_snapshot(&self) -> Option<UdpIoSnapshot> {
+        // only the datagram payload of connect-udp tasks is counted in
+        Some(self.io_udp.snapshot())
+    }
+
     #[inline]
     fn forbidden_stats(&self) -> ServerForbiddenSnapshot {
         self.forbidden.snapshot()
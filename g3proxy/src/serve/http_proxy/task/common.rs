@@ -17,6 +17,7 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use openssl::x509::X509;
 use slog::Logger;
 
 use g3_daemon::server::ClientConnectionInfo;
@@ -25,7 +26,7 @@ use g3_types::acl::AclAction;
 use g3_types::acl_set::AclDstHostRuleSet;
 use g3_types::net::{OpensslClientConfig, UpstreamAddr};
 
-use super::{HttpProxyServerConfig, HttpProxyServerStats};
+use super::{HttpProxyPacFile, HttpProxyServerConfig, HttpProxyServerStats};
 use crate::audit::AuditHandle;
 use crate::escape::ArcEscaper;
 use crate::module::http_forward::HttpProxyClientResponse;
@@ -41,10 +42,12 @@ pub(crate) struct CommonTaskContext {
     pub(crate) escaper: ArcEscaper,
     pub(crate) audit_handle: Option<Arc<AuditHandle>>,
     pub(crate) cc_info: ClientConnectionInfo,
+    pub(crate) client_cert: Option<X509>,
     pub(crate) tls_client_config: Arc<OpensslClientConfig>,
     pub(crate) task_logger: Logger,
 
     pub(crate) dst_host_filter: Option<Arc<AclDstHostRuleSet>>,
+    pub(crate) pac_file: Option<Arc<HttpProxyPacFile>>,
 }
 
 impl CommonTaskContext {
@@ -20,4 +20,4 @@ mod task;
 pub(super) use task::HttpProxyConnectTask;
 
 mod stats;
-use stats::TcpConnectTaskCltWrapperStats;
+pub(super) use stats::TcpConnectTaskCltWrapperStats;
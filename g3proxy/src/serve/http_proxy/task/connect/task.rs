@@ -285,6 +285,13 @@ impl HttpProxyConnectTask {
                 ));
             }
 
+            if user_ctx.check_quota().is_err() {
+                self.reply_too_many_requests(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             match user_ctx.acquire_request_semaphore() {
                 Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
                 Err(_) => {
@@ -20,6 +20,6 @@ mod task;
 pub(super) use task::HttpProxyForwardTask;
 
 mod stats;
-use stats::{
+pub(super) use stats::{
     HttpForwardTaskCltWrapperStats, HttpForwardTaskStats, HttpsForwardTaskCltWrapperStats,
 };
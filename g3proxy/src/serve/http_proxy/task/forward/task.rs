@@ -44,6 +44,9 @@ use super::{
 };
 use crate::config::server::ServerConfig;
 use crate::log::task::http_forward::TaskLogForHttpForward;
+use crate::module::http_cache::{
+    self, HttpCacheForward, HttpCacheHit, HttpCacheLookup, HttpCacheRecordReader, HttpCacheRecorder,
+};
 use crate::module::http_forward::{
     BoxHttpForwardConnection, BoxHttpForwardContext, BoxHttpForwardReader, BoxHttpForwardWriter,
     HttpForwardTaskNotes, HttpProxyClientResponse,
@@ -58,6 +61,8 @@ use crate::serve::{
 pub(crate) struct HttpProxyForwardTask<'a> {
     ctx: Arc<CommonTaskContext>,
     req: &'a HttpProxyClientRequest,
+    cache_req: Option<HttpProxyClientRequest>,
+    cache_forward: Option<HttpCacheForward>,
     is_https: bool,
     should_close: bool,
     send_error_response: bool,
@@ -88,6 +93,8 @@ impl<'a> HttpProxyForwardTask<'a> {
         HttpProxyForwardTask {
             ctx: Arc::clone(ctx),
             req: &req.inner,
+            cache_req: None,
+            cache_forward: None,
             is_https,
             should_close: !req.inner.keep_alive(),
             send_error_response: true,
@@ -390,6 +397,24 @@ impl<'a> HttpProxyForwardTask<'a> {
         }
     }
 
+    async fn handle_user_url_acl_action<W>(
+        &mut self,
+        action: AclAction,
+        clt_w: &mut W,
+    ) -> ServerTaskResult<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        if action.forbid_early() {
+            self.reply_forbidden(clt_w).await;
+            Err(ServerTaskError::ForbiddenByRule(
+                ServerTaskForbiddenError::UrlBlocked,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     async fn handle_user_protocol_acl_action<W>(
         &mut self,
         action: AclAction,
@@ -539,6 +564,13 @@ impl<'a> HttpProxyForwardTask<'a> {
                 ));
             }
 
+            if user_ctx.check_quota().is_err() {
+                self.reply_too_many_requests(clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             match user_ctx.acquire_request_semaphore() {
                 Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
                 Err(_) => {
@@ -564,6 +596,19 @@ impl<'a> HttpProxyForwardTask<'a> {
                 self.handle_user_ua_acl_action(action, clt_w).await?;
             }
 
+            let scheme = if self.is_https { "https" } else { "http" };
+            let path_and_query = self
+                .req
+                .uri
+                .path_and_query()
+                .map(|v| v.as_str())
+                .unwrap_or("/");
+            if let Some(action) =
+                user_ctx.check_http_url(scheme, self.tcp_notes.upstream.host(), path_and_query)
+            {
+                self.handle_user_url_acl_action(action, clt_w).await?;
+            }
+
             let user_config = user_ctx.user_config();
 
             upstream_keepalive = upstream_keepalive.adjust_to(user_config.http_upstream_keepalive);
@@ -594,6 +639,27 @@ impl<'a> HttpProxyForwardTask<'a> {
 
         self.setup_clt_limit_and_stats(clt_r, clt_w);
 
+        match self.lookup_http_cache(audit_task) {
+            Some(HttpCacheLookup::Hit(hit)) => {
+                self.mark_relaying();
+                return match self.send_cached_response(clt_w, hit).await {
+                    Ok(_) => {
+                        self.task_notes.stage = ServerTaskStage::Finished;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        self.should_close = true;
+                        if self.send_error_response {
+                            self.reply_task_err(&e, clt_w).await;
+                        }
+                        Err(e)
+                    }
+                };
+            }
+            Some(HttpCacheLookup::Forward(forward)) => self.set_cache_forward(*forward),
+            None => {}
+        }
+
         fwd_ctx.prepare_connection(&self.tcp_notes.upstream, self.is_https);
 
         if let Some(connection) = fwd_ctx
@@ -676,6 +742,136 @@ impl<'a> HttpProxyForwardTask<'a> {
         }
     }
 
+    fn lookup_http_cache(&self, audit_task: bool) -> Option<HttpCacheLookup> {
+        if audit_task {
+            // the cached response should never bypass the auditor
+            return None;
+        }
+        let cache = http_cache::get_global_cache()?;
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            if user_ctx.user().http_cache_bypass() {
+                return None;
+            }
+        }
+
+        let scheme = if self.is_https { "https" } else { "http" };
+        let path_and_query = self
+            .req
+            .uri
+            .path_and_query()
+            .map(|v| v.as_str())
+            .unwrap_or("/");
+        let url = format!("{scheme}://{}{path_and_query}", self.tcp_notes.upstream);
+        cache.lookup(
+            &self.req.method,
+            url,
+            &self.req.end_to_end_headers,
+            self.req.body_type().is_some(),
+        )
+    }
+
+    fn set_cache_forward(&mut self, forward: HttpCacheForward) {
+        let validators = forward.validators();
+        if !validators.is_empty() {
+            let mut headers = self.req.end_to_end_headers.clone();
+            for (name, value) in validators {
+                headers.insert(name, value);
+            }
+            self.cache_req = Some(self.req.clone_with_headers(headers));
+        }
+        self.http_notes.cache_status = forward.status();
+        self.cache_forward = Some(forward);
+    }
+
+    async fn send_cached_response<W>(
+        &mut self,
+        clt_w: &mut W,
+        hit: HttpCacheHit,
+    ) -> ServerTaskResult<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.http_notes.cache_status = Some(hit.status());
+        self.http_notes.rsp_status = hit.code();
+        let header = hit.serialize_head(self.req.version, !self.should_close);
+        if !hit.has_body() {
+            self.send_error_response = false;
+            clt_w
+                .write_all(&header)
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+            return clt_w
+                .flush()
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed);
+        }
+
+        let mut body_reader = hit.body_reader().await.map_err(|_| {
+            ServerTaskError::InternalServerError("failed to open cached response body")
+        })?;
+        self.send_error_response = false;
+
+        let header_len = header.len() as u64;
+        let mut cache_to_clt = LimitedCopy::with_data(
+            &mut body_reader,
+            clt_w,
+            &self.ctx.server_config.tcp_copy,
+            header,
+        );
+
+        let idle_duration = self.ctx.server_config.task_idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        loop {
+            tokio::select! {
+                biased;
+
+                r = &mut cache_to_clt => {
+                    return match r {
+                        Ok(_) => Ok(()),
+                        Err(LimitedCopyError::ReadFailed(_)) => {
+                            if cache_to_clt.copied_size() < header_len {
+                                let _ = cache_to_clt.write_flush().await; // flush rsp header to client
+                            }
+                            Err(ServerTaskError::InternalServerError("failed to read cached response body"))
+                        }
+                        Err(LimitedCopyError::WriteFailed(e)) => Err(ServerTaskError::ClientTcpWriteFailed(e)),
+                    };
+                }
+                _ = idle_interval.tick() => {
+                    if cache_to_clt.is_idle() {
+                        idle_count += 1;
+
+                        let quit = if let Some(user_ctx) = self.task_notes.user_ctx() {
+                            idle_count >= user_ctx.user().task_max_idle_count()
+                        } else {
+                            idle_count >= self.ctx.server_config.task_idle_max_count
+                        };
+
+                        if quit {
+                            return Err(ServerTaskError::ClientAppTimeout("idle while sending cached response"));
+                        }
+                    } else {
+                        idle_count = 0;
+
+                        cache_to_clt.reset_active();
+                    }
+
+                    if let Some(user_ctx) = self.task_notes.user_ctx() {
+                        if user_ctx.user().is_blocked() {
+                            return Err(ServerTaskError::CanceledAsUserBlocked);
+                        }
+                    }
+
+                    if self.ctx.server_quit_policy.force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit)
+                    }
+                }
+            }
+        }
+    }
+
     async fn make_new_connection(
         &self,
         fwd_ctx: &mut BoxHttpForwardContext,
@@ -1230,7 +1426,7 @@ impl<'a> HttpProxyForwardTask<'a> {
 
     async fn send_request_header(&self, ups_w: &mut BoxHttpForwardWriter) -> ServerTaskResult<()> {
         ups_w
-            .send_request_header(self.req)
+            .send_request_header(self.cache_req.as_ref().unwrap_or(self.req))
             .await
             .map_err(ServerTaskError::UpstreamWriteFailed)?;
         ups_w
@@ -1370,17 +1566,40 @@ impl<'a> HttpProxyForwardTask<'a> {
         R: AsyncBufRead + Unpin,
         W: AsyncWrite + Unpin,
     {
+        let body_type = rsp_header.body_type(&self.req.method);
+        let mut cache_recorder = None;
+        if let Some(cache_forward) = &mut self.cache_forward {
+            if let Some(hit) =
+                cache_forward.not_modified(rsp_header.code, &rsp_header.end_to_end_headers)
+            {
+                self.http_notes.mark_rsp_no_body();
+                return self.send_cached_response(clt_w, hit).await;
+            }
+            cache_recorder = cache_forward.recorder(
+                rsp_header.code,
+                &rsp_header.reason,
+                &rsp_header.end_to_end_headers,
+                body_type,
+                self.ctx.server_config.body_line_max_len,
+            );
+            self.http_notes.cache_status = cache_forward.status();
+        }
+
         self.send_error_response = false;
 
-        if let Some(body_type) = rsp_header.body_type(&self.req.method) {
+        if let Some(body_type) = body_type {
             let mut buf = Vec::with_capacity(self.ctx.server_config.tcp_copy.buffer_size());
             rsp_header.serialize_to(&mut buf);
             self.http_notes.rsp_status = rsp_header.code; // the following function must send rsp header out
-            self.send_response_body(buf, clt_w, ups_r, body_type).await
+            self.send_response_body(buf, clt_w, ups_r, body_type, cache_recorder)
+                .await
         } else {
             self.send_response_header(clt_w, rsp_header).await?;
             self.http_notes.rsp_status = rsp_header.code;
             self.http_notes.mark_rsp_no_body();
+            if let Some(recorder) = cache_recorder {
+                recorder.finish();
+            }
             Ok(())
         }
     }
@@ -1391,14 +1610,16 @@ impl<'a> HttpProxyForwardTask<'a> {
         clt_w: &mut W,
         ups_r: &mut R,
         body_type: HttpBodyType,
+        cache_recorder: Option<HttpCacheRecorder>,
     ) -> ServerTaskResult<()>
     where
         R: AsyncBufRead + Unpin,
         W: AsyncWrite + Unpin,
     {
         let header_len = header.len() as u64;
-        let mut body_reader =
+        let body_reader =
             HttpBodyReader::new(ups_r, body_type, self.ctx.server_config.body_line_max_len);
+        let mut body_reader = HttpCacheRecordReader::new(body_reader, cache_recorder);
 
         let mut ups_to_clt = LimitedCopy::with_data(
             &mut body_reader,
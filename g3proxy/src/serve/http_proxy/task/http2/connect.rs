@@ -0,0 +1,352 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use h2::server::SendResponse;
+use h2::{RecvStream, SendStream};
+use http::{StatusCode, Version};
+use log::debug;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use g3_daemon::stat::task::TcpStreamTaskStats;
+use g3_h2::{H2StreamReader, H2StreamWriter};
+use g3_io_ext::{LimitedReader, LimitedWriter};
+use g3_types::acl::AclAction;
+use g3_types::net::{ProxyRequestType, UpstreamAddr};
+
+use super::{CommonTaskContext, TcpConnectTaskCltWrapperStats};
+use crate::config::server::ServerConfig;
+use crate::inspect::StreamInspectContext;
+use crate::log::task::tcp_connect::TaskLogForTcpConnect;
+use crate::module::http_forward::HttpProxyClientResponse;
+use crate::module::tcp_connect::TcpConnectTaskNotes;
+use crate::serve::{
+    ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
+    ServerTaskStage,
+};
+
+/// CONNECT request in a HTTP/2 stream, the DATA frames will be relayed as the tcp payload
+pub(super) struct H2ProxyConnectTask {
+    ctx: Arc<CommonTaskContext>,
+    task_notes: ServerTaskNotes,
+    tcp_notes: TcpConnectTaskNotes,
+    task_stats: Arc<TcpStreamTaskStats>,
+}
+
+impl H2ProxyConnectTask {
+    pub(super) fn new(
+        ctx: &Arc<CommonTaskContext>,
+        upstream: UpstreamAddr,
+        task_notes: ServerTaskNotes,
+    ) -> Self {
+        H2ProxyConnectTask {
+            ctx: Arc::clone(ctx),
+            task_notes,
+            tcp_notes: TcpConnectTaskNotes::new(upstream),
+            task_stats: Arc::new(TcpStreamTaskStats::default()),
+        }
+    }
+
+    fn reply_local(&self, send_rsp: &mut SendResponse<Bytes>, mut rsp: HttpProxyClientResponse) {
+        self.ctx
+            .set_custom_header_for_local_reply(&self.tcp_notes, &mut rsp);
+        super::send_local_response(send_rsp, &rsp);
+    }
+
+    fn reply_ok(&self, send_rsp: &mut SendResponse<Bytes>) -> ServerTaskResult<SendStream<Bytes>> {
+        let mut rsp =
+            HttpProxyClientResponse::from_standard(StatusCode::OK, Version::HTTP_2, false);
+        self.ctx
+            .set_custom_header_for_local_reply(&self.tcp_notes, &mut rsp);
+        send_rsp
+            .send_response(rsp.to_h2_response(), false)
+            .map_err(|e| ServerTaskError::ClientTcpWriteFailed(e.into()))
+    }
+
+    fn handle_acl_action(
+        &self,
+        action: AclAction,
+        send_rsp: &mut SendResponse<Bytes>,
+        e: ServerTaskForbiddenError,
+    ) -> ServerTaskResult<()> {
+        if action.forbid_early() {
+            let rsp = if matches!(e, ServerTaskForbiddenError::ProtoBanned) {
+                HttpProxyClientResponse::method_not_allowed(Version::HTTP_2)
+            } else {
+                HttpProxyClientResponse::forbidden(Version::HTTP_2)
+            };
+            // no custom header is set
+            super::send_local_response(send_rsp, &rsp);
+            Err(ServerTaskError::ForbiddenByRule(e))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(super) async fn run(mut self, clt_r: RecvStream, mut send_rsp: SendResponse<Bytes>) {
+        self.pre_start();
+        match self.run_connect(clt_r, &mut send_rsp).await {
+            Ok(()) => self
+                .get_log_context()
+                .log(&self.ctx.task_logger, &ServerTaskError::Finished),
+            Err(e) => self.get_log_context().log(&self.ctx.task_logger, &e),
+        }
+        self.pre_stop();
+    }
+
+    async fn run_connect(
+        &mut self,
+        clt_r: RecvStream,
+        send_rsp: &mut SendResponse<Bytes>,
+    ) -> ServerTaskResult<()> {
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            let user_ctx = user_ctx.clone();
+
+            let action = user_ctx.check_client_addr(self.task_notes.client_addr());
+            self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::SrcBlocked)?;
+
+            if user_ctx.check_rate_limit().is_err() {
+                let rsp = HttpProxyClientResponse::too_many_requests(Version::HTTP_2);
+                super::send_local_response(send_rsp, &rsp);
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::RateLimited,
+                ));
+            }
+
+            if user_ctx.check_quota().is_err() {
+                let rsp = HttpProxyClientResponse::too_many_requests(Version::HTTP_2);
+                super::send_local_response(send_rsp, &rsp);
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
+            match user_ctx.acquire_request_semaphore() {
+                Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
+                Err(_) => {
+                    let rsp = HttpProxyClientResponse::too_many_requests(Version::HTTP_2);
+                    super::send_local_response(send_rsp, &rsp);
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::FullyLoaded,
+                    ));
+                }
+            }
+
+            let action = user_ctx.check_proxy_request(ProxyRequestType::HttpConnect);
+            self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::ProtoBanned)?;
+
+            let action = user_ctx.check_upstream(&self.tcp_notes.upstream);
+            self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::DestDenied)?;
+        }
+
+        // server level dst host/port acl rules
+        let action = self.ctx.check_upstream(&self.tcp_notes.upstream);
+        if action.forbid_early() {
+            self.ctx.server_stats.forbidden.add_dest_denied();
+            if let Some(user_ctx) = self.task_notes.user_ctx() {
+                // also add to user level forbidden stats
+                user_ctx.add_dest_denied();
+            }
+        }
+        self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::DestDenied)?;
+
+        self.task_notes.stage = ServerTaskStage::Connecting;
+        let (ups_r, ups_w) = match self
+            .ctx
+            .escaper
+            .tcp_setup_connection(
+                &mut self.tcp_notes,
+                &self.task_notes,
+                self.task_stats.clone() as _,
+            )
+            .await
+        {
+            Ok(connection) => connection,
+            Err(e) => {
+                let rsp =
+                    HttpProxyClientResponse::from_tcp_connect_error(&e, Version::HTTP_2, false);
+                self.reply_local(send_rsp, rsp);
+                return Err(e.into());
+            }
+        };
+        self.task_notes.stage = ServerTaskStage::Connected;
+
+        self.task_notes.stage = ServerTaskStage::Replying;
+        let send_stream = self.reply_ok(send_rsp)?;
+
+        self.task_notes.mark_relaying();
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| {
+                s.req_ready.add_http_connect();
+            });
+        }
+
+        let clt_r = H2StreamReader::new(clt_r);
+        let clt_w = H2StreamWriter::new(send_stream);
+        self.relay(clt_r, clt_w, ups_r, ups_w).await
+    }
+
+    async fn relay<CDR, CDW, UR, UW>(
+        &mut self,
+        clt_r: CDR,
+        clt_w: CDW,
+        ups_r: UR,
+        ups_w: UW,
+    ) -> ServerTaskResult<()>
+    where
+        CDR: AsyncRead + Send + Sync + Unpin + 'static,
+        CDW: AsyncWrite + Send + Sync + Unpin + 'static,
+        UR: AsyncRead + Send + Sync + Unpin + 'static,
+        UW: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let (clt_r, clt_w) = self.update_clt(clt_r, clt_w);
+
+        if let Some(audit_handle) = &self.ctx.audit_handle {
+            let audit_task = self
+                .task_notes
+                .user_ctx()
+                .map(|ctx| {
+                    let user_config = &ctx.user_config().audit;
+                    user_config.enable_protocol_inspection
+                        && user_config
+                            .do_task_audit()
+                            .unwrap_or_else(|| audit_handle.do_task_audit())
+                })
+                .unwrap_or_else(|| audit_handle.do_task_audit());
+
+            if audit_task {
+                let ctx = StreamInspectContext::new(
+                    audit_handle.clone(),
+                    self.ctx.server_config.clone(),
+                    self.ctx.server_stats.clone(),
+                    self.ctx.server_quit_policy.clone(),
+                    &self.task_notes,
+                );
+                return crate::inspect::stream::transit_with_inspection(
+                    clt_r,
+                    clt_w,
+                    ups_r,
+                    ups_w,
+                    ctx,
+                    self.tcp_notes.upstream.clone(),
+                    None,
+                )
+                .await;
+            }
+        }
+
+        crate::inspect::stream::transit_transparent(
+            clt_r,
+            clt_w,
+            ups_r,
+            ups_w,
+            &self.ctx.server_config,
+            &self.ctx.server_quit_policy,
+            self.task_notes.user_ctx().map(|ctx| ctx.user()),
+        )
+        .await
+    }
+
+    fn update_clt<CDR, CDW>(
+        &self,
+        clt_r: CDR,
+        clt_w: CDW,
+    ) -> (LimitedReader<CDR>, LimitedWriter<CDW>)
+    where
+        CDR: AsyncRead + Unpin,
+        CDW: AsyncWrite + Unpin,
+    {
+        let mut wrapper_stats =
+            TcpConnectTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats);
+
+        let limit_config = if let Some(user_ctx) = self.task_notes.user_ctx() {
+            wrapper_stats.push_user_io_stats(user_ctx.fetch_traffic_stats(
+                self.ctx.server_config.name(),
+                self.ctx.server_stats.share_extra_tags(),
+            ));
+
+            user_ctx
+                .user_config()
+                .tcp_sock_speed_limit
+                .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit)
+        } else {
+            self.ctx.server_config.tcp_sock_speed_limit
+        };
+
+        let (clt_r_stats, clt_w_stats) = wrapper_stats.split();
+        (
+            LimitedReader::new(
+                clt_r,
+                limit_config.shift_millis,
+                limit_config.max_north,
+                clt_r_stats,
+            ),
+            LimitedWriter::new(
+                clt_w,
+                limit_config.shift_millis,
+                limit_config.max_south,
+                clt_w_stats,
+            ),
+        )
+    }
+
+    fn pre_start(&self) {
+        debug!(
+            "HttpProxy/H2/CONNECT: new client from {} to {} server {}, using escaper {}",
+            self.ctx.client_addr(),
+            self.ctx.server_config.server_type(),
+            self.ctx.server_config.name(),
+            self.ctx.server_config.escaper
+        );
+        self.ctx.server_stats.task_http_connect.add_task();
+        self.ctx.server_stats.task_http_connect.inc_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| {
+                s.req_total.add_http_connect();
+                s.req_alive.add_http_connect();
+            });
+        }
+    }
+
+    fn pre_stop(&mut self) {
+        self.ctx.server_stats.task_http_connect.dec_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| {
+                s.req_alive.del_http_connect();
+            });
+
+            if let Some(user_req_alive_permit) = self.task_notes.user_req_alive_permit.take() {
+                drop(user_req_alive_permit);
+            }
+        }
+    }
+
+    fn get_log_context(&self) -> TaskLogForTcpConnect {
+        TaskLogForTcpConnect {
+            task_notes: &self.task_notes,
+            tcp_notes: &self.tcp_notes,
+            total_time: self.task_notes.time_elapsed(),
+            client_rd_bytes: self.task_stats.clt.read.get_bytes(),
+            client_wr_bytes: self.task_stats.clt.write.get_bytes(),
+            remote_rd_bytes: self.task_stats.ups.read.get_bytes(),
+            remote_wr_bytes: self.task_stats.ups.write.get_bytes(),
+        }
+    }
+}
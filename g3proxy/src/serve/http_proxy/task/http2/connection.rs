@@ -0,0 +1,492 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use h2::ext::Protocol;
+use h2::server::{Connection, SendResponse};
+use h2::{Reason, RecvStream};
+use http::{Method, Request, Version};
+use log::debug;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+
+use g3_http::server::{HttpProxyClientRequest, HttpRequestParseError, UriExt};
+use g3_types::auth::UserAuthError;
+use g3_types::net::{HttpAuth, HttpBasicAuth, HttpHeaderMap, HttpUpgradeToken, UpstreamAddr};
+
+use super::protocol::HttpProxySubProtocol;
+use super::{
+    protocol, CommonTaskContext, H2ProxyConnectTask, H2ProxyForwardTask, HttpProxyUdpConnectTask,
+};
+use crate::auth::{UserContext, UserGroup, UserRequestStats};
+use crate::config::server::ServerConfig;
+use crate::escape::EgressPathSelection;
+use crate::module::http_forward::HttpProxyClientResponse;
+use crate::serve::{ServerStats, ServerTaskNotes};
+
+struct UserData {
+    req_stats: Arc<UserRequestStats>,
+    site_req_stats: Option<Arc<UserRequestStats>>,
+}
+
+impl Drop for UserData {
+    fn drop(&mut self) {
+        self.req_stats.l7_conn_alive.dec_http();
+        if let Some(site_req_stats) = &self.site_req_stats {
+            site_req_stats.l7_conn_alive.dec_http();
+        }
+    }
+}
+
+/// Serve a HTTP/2 client connection, each stream will be handled as a standalone proxy request.
+pub(crate) struct HttpProxyH2ConnectionTask {
+    ctx: Arc<CommonTaskContext>,
+    user_group: Option<Arc<UserGroup>>,
+    passed_users: Arc<Mutex<AHashMap<String, UserData>>>,
+    alive_streams: Arc<AtomicI32>,
+}
+
+impl HttpProxyH2ConnectionTask {
+    pub(crate) fn new(ctx: &Arc<CommonTaskContext>, user_group: Option<Arc<UserGroup>>) -> Self {
+        HttpProxyH2ConnectionTask {
+            ctx: Arc::clone(ctx),
+            user_group,
+            passed_users: Arc::new(Mutex::new(AHashMap::new())),
+            alive_streams: Arc::new(AtomicI32::new(0)),
+        }
+    }
+
+    pub(crate) async fn into_running<S>(mut self, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let server_config = Arc::clone(&self.ctx.server_config);
+
+        let mut server_builder = h2::server::Builder::new();
+        server_builder
+            .max_header_list_size(u32::try_from(server_config.req_hdr_max_size).unwrap_or(u32::MAX))
+            .max_concurrent_streams(server_config.h2_max_concurrent_streams);
+        if server_config.enable_connect_udp {
+            server_builder.enable_connect_protocol();
+        }
+
+        // NOTE the h2 framing traffic is not counted in (server/task/user) stats
+        let mut h2c = match tokio::time::timeout(
+            server_config.timeout.recv_req_header,
+            server_builder.handshake::<S, Bytes>(stream),
+        )
+        .await
+        {
+            Ok(Ok(h2c)) => h2c,
+            Ok(Err(e)) => {
+                debug!(
+                    "{} - {} h2 handshake error: {e}",
+                    self.ctx.cc_info.sock_local_addr(),
+                    self.ctx.cc_info.sock_peer_addr()
+                );
+                return;
+            }
+            Err(_) => {
+                debug!(
+                    "{} - {} h2 handshake timeout",
+                    self.ctx.cc_info.sock_local_addr(),
+                    self.ctx.cc_info.sock_peer_addr()
+                );
+                return;
+            }
+        };
+
+        let idle_duration = server_config.task_idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_since = Instant::now();
+        let mut graceful_shutdown = false;
+
+        loop {
+            tokio::select! {
+                biased;
+
+                r = h2c.accept() => {
+                    match r {
+                        Some(Ok((req, send_rsp))) => self.handle_stream(req, send_rsp),
+                        Some(Err(e)) => {
+                            debug!(
+                                "{} - {} h2 connection error: {e}",
+                                self.ctx.cc_info.sock_local_addr(),
+                                self.ctx.cc_info.sock_peer_addr()
+                            );
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+                _ = idle_interval.tick() => {
+                    if self.alive_streams.load(Ordering::Relaxed) > 0 {
+                        idle_since = Instant::now();
+                    } else if !graceful_shutdown
+                        && idle_since.elapsed() >= server_config.pipeline_read_idle_timeout
+                    {
+                        h2c.graceful_shutdown();
+                        graceful_shutdown = true;
+                    }
+
+                    if self.ctx.server_quit_policy.force_quit() {
+                        abrupt_shutdown(h2c, Reason::CANCEL).await;
+                        break;
+                    }
+
+                    if !graceful_shutdown && !self.ctx.server_stats.is_online() {
+                        h2c.graceful_shutdown();
+                        graceful_shutdown = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_egress_path_selection(
+        &self,
+        headers: &mut HttpHeaderMap,
+    ) -> Option<EgressPathSelection> {
+        if let Some(header) = &self.ctx.server_config.egress_path_selection_header {
+            // check and remove the custom header
+            if let Some(value) = headers.remove(header) {
+                if let Ok(egress) = EgressPathSelection::from_str(value.to_str()) {
+                    return Some(egress);
+                }
+            }
+        }
+        None
+    }
+
+    fn parse_request(
+        &self,
+        parts: &http::request::Parts,
+        has_body: bool,
+    ) -> Result<HttpProxyClientRequest, HttpRequestParseError> {
+        let steal_forwarded_for = self.ctx.server_config.steal_forwarded_for;
+        HttpProxyClientRequest::from_h2(parts, has_body, |req, name, header| {
+            match name.as_str() {
+                "proxy-authorization" => return req.parse_header_authorization(header.value),
+                "forwarded" | "x-forwarded-for" => {
+                    if steal_forwarded_for {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+            req.append_header(name, header)?;
+            Ok(())
+        })
+    }
+
+    fn handle_stream(&mut self, req: Request<RecvStream>, mut send_rsp: SendResponse<Bytes>) {
+        let time_accepted = Instant::now();
+        let (parts, clt_r) = req.into_parts();
+
+        let is_connect = parts.method == Method::CONNECT;
+        let mut is_connect_udp = false;
+        if is_connect {
+            if let Some(protocol) = parts.extensions.get::<Protocol>() {
+                // only connect-udp is supported for extended CONNECT
+                if self.ctx.server_config.enable_connect_udp
+                    && matches!(
+                        HttpUpgradeToken::from_str(protocol.as_str()),
+                        Ok(HttpUpgradeToken::ConnectUdp)
+                    )
+                {
+                    is_connect_udp = true;
+                } else {
+                    let rsp = HttpProxyClientResponse::unimplemented(Version::HTTP_2);
+                    super::send_local_response(&mut send_rsp, &rsp);
+                    return;
+                }
+            }
+        }
+
+        let has_body = !is_connect && !clt_r.is_end_stream();
+        let mut req = match self.parse_request(&parts, has_body) {
+            Ok(req) => req,
+            Err(e) => {
+                let rsp = HttpProxyClientResponse::from_request_error(&e, Version::HTTP_2)
+                    .unwrap_or_else(|| HttpProxyClientResponse::bad_request(Version::HTTP_2));
+                super::send_local_response(&mut send_rsp, &rsp);
+                return;
+            }
+        };
+        let time_received = Instant::now();
+
+        let upstream_and_protocol = if is_connect_udp {
+            req.uri
+                .get_connect_udp_upstream()
+                .map(|upstream| (upstream, HttpProxySubProtocol::UdpConnect))
+        } else if is_connect {
+            protocol::get_connect_upstream(&req.uri)
+                .map(|upstream| (upstream, HttpProxySubProtocol::TcpConnect))
+        } else {
+            protocol::get_forward_upstream_and_protocol(&req.uri)
+        };
+        let (upstream, sub_protocol) = match upstream_and_protocol {
+            Ok(v) => v,
+            Err(e) => {
+                let rsp = HttpProxyClientResponse::from_request_error(&e, Version::HTTP_2)
+                    .unwrap_or_else(|| HttpProxyClientResponse::bad_request(Version::HTTP_2));
+                super::send_local_response(&mut send_rsp, &rsp);
+                return;
+            }
+        };
+
+        // the authority is the proxy itself for connect-udp requests
+        if !self.ctx.server_config.allow_custom_host && !is_connect_udp {
+            if let Some(host) = &req.host {
+                if !host.host_eq(&upstream) {
+                    let rsp = HttpProxyClientResponse::bad_request(Version::HTTP_2);
+                    super::send_local_response(&mut send_rsp, &rsp);
+                    return;
+                }
+            }
+        }
+
+        if matches!(sub_protocol, HttpProxySubProtocol::FtpOverHttp) {
+            // ftp over http is only supported in HTTP/1.x
+            let rsp = HttpProxyClientResponse::unimplemented(Version::HTTP_2);
+            super::send_local_response(&mut send_rsp, &rsp);
+            return;
+        }
+
+        let path_selection = self.get_egress_path_selection(&mut req.end_to_end_headers);
+
+        let ctx = Arc::clone(&self.ctx);
+        let user_group = self.user_group.clone();
+        let passed_users = Arc::clone(&self.passed_users);
+        let alive_streams = Arc::clone(&self.alive_streams);
+        alive_streams.fetch_add(1, Ordering::Relaxed);
+        // the auth may take some time, so do it in the stream task to not block the connection
+        tokio::spawn(async move {
+            match do_auth(
+                &ctx,
+                user_group.as_deref(),
+                &passed_users,
+                &req.auth_info,
+                &upstream,
+            )
+            .await
+            {
+                Ok(user_ctx) => {
+                    let task_notes = ServerTaskNotes::with_path_selection(
+                        ctx.cc_info.clone(),
+                        user_ctx,
+                        time_accepted.elapsed(),
+                        path_selection,
+                    );
+                    run_stream_task(
+                        &ctx,
+                        req,
+                        upstream,
+                        sub_protocol,
+                        time_received,
+                        task_notes,
+                        clt_r,
+                        send_rsp,
+                    )
+                    .await;
+                }
+                Err(e) => reply_auth_err(&ctx, send_rsp, e.blocked_delay()).await,
+            }
+            alive_streams.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_stream_task(
+    ctx: &Arc<CommonTaskContext>,
+    req: HttpProxyClientRequest,
+    upstream: UpstreamAddr,
+    sub_protocol: HttpProxySubProtocol,
+    time_received: Instant,
+    task_notes: ServerTaskNotes,
+    clt_r: RecvStream,
+    send_rsp: SendResponse<Bytes>,
+) {
+    match sub_protocol {
+        HttpProxySubProtocol::TcpConnect => {
+            let task = H2ProxyConnectTask::new(ctx, upstream, task_notes);
+            task.run(clt_r, send_rsp).await;
+        }
+        HttpProxySubProtocol::UdpConnect => {
+            let task = HttpProxyUdpConnectTask::new(ctx, upstream, task_notes, Version::HTTP_2);
+            task.run_h2(clt_r, send_rsp).await;
+        }
+        HttpProxySubProtocol::HttpForward | HttpProxySubProtocol::HttpsForward => {
+            let is_https = matches!(sub_protocol, HttpProxySubProtocol::HttpsForward);
+            let task =
+                H2ProxyForwardTask::new(ctx, req, upstream, is_https, time_received, task_notes);
+            task.run(clt_r, send_rsp).await;
+        }
+        HttpProxySubProtocol::FtpOverHttp | HttpProxySubProtocol::PacFile => unreachable!(),
+    }
+}
+
+async fn do_auth(
+    ctx: &CommonTaskContext,
+    user_group: Option<&UserGroup>,
+    passed_users: &Mutex<AHashMap<String, UserData>>,
+    auth_info: &HttpAuth,
+    upstream: &UpstreamAddr,
+) -> Result<Option<UserContext>, UserAuthError> {
+    let Some(user_group) = user_group else {
+        return Ok(None);
+    };
+
+    let cert_user_ctx = match &ctx.client_cert {
+        Some(cert) => {
+            user_group
+                .check_client_cert(
+                    cert,
+                    auth_info,
+                    ctx.server_config.name(),
+                    ctx.server_stats.share_extra_tags(),
+                )
+                .await?
+        }
+        None => None,
+    };
+    let mut user_ctx = if let Some(user_ctx) = cert_user_ctx {
+        user_ctx
+    } else {
+        match auth_info {
+            HttpAuth::None => {
+                if let Some((user, user_type)) = user_group.get_anonymous_user() {
+                    UserContext::new(
+                        None,
+                        user,
+                        user_type,
+                        ctx.server_config.name(),
+                        ctx.server_stats.share_extra_tags(),
+                    )
+                } else {
+                    return Err(UserAuthError::NoUserSupplied);
+                }
+            }
+            HttpAuth::Basic(HttpBasicAuth {
+                username, password, ..
+            }) => match user_group.get_user(username.as_original()) {
+                Some((user, user_type)) => {
+                    let user_ctx = UserContext::new(
+                        Some(username.as_original().to_string()),
+                        user,
+                        user_type,
+                        ctx.server_config.name(),
+                        ctx.server_stats.share_extra_tags(),
+                    );
+                    user_ctx.check_password(password.as_original()).await?;
+                    user_ctx
+                }
+                None => return Err(UserAuthError::NoSuchUser),
+            },
+            HttpAuth::Bearer(bearer) => {
+                let (username, user, user_type) = user_group.get_bearer_user(bearer.token())?;
+                let user_ctx = UserContext::new(
+                    Some(username),
+                    user,
+                    user_type,
+                    ctx.server_config.name(),
+                    ctx.server_stats.share_extra_tags(),
+                );
+                user_ctx.check_available()?;
+                user_ctx
+            }
+        }
+    };
+
+    user_ctx.check_in_site(
+        ctx.server_config.name(),
+        ctx.server_stats.share_extra_tags(),
+        upstream,
+    );
+    let mut passed_users = passed_users.lock().unwrap();
+    if passed_users.contains_key(user_ctx.user_name()) {
+        user_ctx.mark_reused_client_connection();
+    } else {
+        let req_stats = user_ctx.req_stats().clone();
+        req_stats.conn_total.add_http();
+        req_stats.l7_conn_alive.inc_http();
+        let site_req_stats = if let Some(site_req_stats) = user_ctx.site_req_stats() {
+            site_req_stats.conn_total.add_http();
+            site_req_stats.l7_conn_alive.inc_http();
+            Some(Arc::clone(site_req_stats))
+        } else {
+            None
+        };
+        passed_users.insert(
+            user_ctx.user_name().to_string(),
+            UserData {
+                req_stats,
+                site_req_stats,
+            },
+        );
+    }
+    Ok(Some(user_ctx))
+}
+
+async fn reply_auth_err(
+    ctx: &CommonTaskContext,
+    mut send_rsp: SendResponse<Bytes>,
+    blocked_delay: Option<Duration>,
+) {
+    if let Some(duration) = blocked_delay {
+        ctx.server_stats.forbidden.add_user_blocked();
+
+        // delay some time before reply
+        tokio::time::sleep(duration).await;
+
+        let rsp = HttpProxyClientResponse::forbidden(Version::HTTP_2);
+        // no custom header is set
+        super::send_local_response(&mut send_rsp, &rsp);
+    } else {
+        ctx.server_stats.forbidden.add_auth_failed();
+
+        let rsp = HttpProxyClientResponse::proxy_auth_required(
+            Version::HTTP_2,
+            &ctx.server_config.auth_realm,
+            false,
+        );
+        super::send_local_response(&mut send_rsp, &rsp);
+    }
+}
+
+async fn abrupt_shutdown<T>(mut h2c: Connection<T, Bytes>, reason: Reason)
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    h2c.abrupt_shutdown(reason);
+
+    while let Some(r) = h2c.accept().await {
+        match r {
+            Ok((_req, mut send_rsp)) => {
+                send_rsp.send_reset(reason);
+            }
+            Err(_) => break,
+        }
+    }
+}
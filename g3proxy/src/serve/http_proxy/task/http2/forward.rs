@@ -0,0 +1,762 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use h2::server::SendResponse;
+use h2::{Reason, RecvStream};
+use http::{Response, StatusCode, Version};
+use log::debug;
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::time::Instant;
+
+use g3_h2::{H2StreamReader, H2StreamWriter};
+use g3_http::client::HttpForwardRemoteResponse;
+use g3_http::server::HttpProxyClientRequest;
+use g3_http::{ChunkedNoTrailerEncodeTransfer, HttpBodyReader, HttpBodyType};
+use g3_io_ext::{
+    ArcLimitedReaderStats, ArcLimitedWriterStats, LimitedCopy, LimitedCopyError, LimitedReader,
+    LimitedWriter,
+};
+use g3_types::acl::AclAction;
+use g3_types::net::{HttpHeaderMap, ProxyRequestType, TcpSockSpeedLimitConfig, UpstreamAddr};
+
+use super::{
+    CommonTaskContext, HttpForwardTaskCltWrapperStats, HttpForwardTaskStats,
+    HttpsForwardTaskCltWrapperStats,
+};
+use crate::config::server::ServerConfig;
+use crate::log::task::http_forward::TaskLogForHttpForward;
+use crate::module::http_forward::{
+    BoxHttpForwardConnection, BoxHttpForwardContext, BoxHttpForwardReader, HttpForwardTaskNotes,
+    HttpProxyClientResponse,
+};
+use crate::module::http_header;
+use crate::module::tcp_connect::{TcpConnectError, TcpConnectTaskNotes};
+use crate::serve::{
+    ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
+    ServerTaskStage,
+};
+
+/// Forward request in a HTTP/2 stream, the request will be sent to upstream by using HTTP/1.1.
+///
+/// The upstream connection won't be reused, and ICAP adaptation is not supported yet, so audited
+/// requests will be reset with HTTP_1_1_REQUIRED if ICAP services are configured in the auditor.
+pub(super) struct H2ProxyForwardTask {
+    ctx: Arc<CommonTaskContext>,
+    req: HttpProxyClientRequest,
+    is_https: bool,
+    task_notes: ServerTaskNotes,
+    http_notes: HttpForwardTaskNotes,
+    tcp_notes: TcpConnectTaskNotes,
+    task_stats: Arc<HttpForwardTaskStats>,
+}
+
+impl H2ProxyForwardTask {
+    pub(super) fn new(
+        ctx: &Arc<CommonTaskContext>,
+        req: HttpProxyClientRequest,
+        upstream: UpstreamAddr,
+        is_https: bool,
+        time_received: Instant,
+        task_notes: ServerTaskNotes,
+    ) -> Self {
+        let uri_log_max_chars = task_notes
+            .user_ctx()
+            .and_then(|c| c.user_config().log_uri_max_chars)
+            .unwrap_or(ctx.server_config.log_uri_max_chars);
+        let http_notes = HttpForwardTaskNotes::new(
+            time_received,
+            task_notes.task_created_instant(),
+            req.method.clone(),
+            req.uri.clone(),
+            uri_log_max_chars,
+        );
+        H2ProxyForwardTask {
+            ctx: Arc::clone(ctx),
+            req,
+            is_https,
+            task_notes,
+            http_notes,
+            tcp_notes: TcpConnectTaskNotes::new(upstream),
+            task_stats: Arc::new(HttpForwardTaskStats::default()),
+        }
+    }
+
+    fn reply_local(&mut self, send_rsp: &mut SendResponse<Bytes>, rsp: &HttpProxyClientResponse) {
+        if super::send_local_response(send_rsp, rsp) {
+            self.http_notes.rsp_status = rsp.status();
+        }
+    }
+
+    fn reply_connect_err(&mut self, e: &TcpConnectError, send_rsp: &mut SendResponse<Bytes>) {
+        let mut rsp = HttpProxyClientResponse::from_tcp_connect_error(e, Version::HTTP_2, false);
+        self.ctx
+            .set_custom_header_for_local_reply(&self.tcp_notes, &mut rsp);
+        self.reply_local(send_rsp, &rsp);
+    }
+
+    fn reply_task_err(&mut self, e: &ServerTaskError, send_rsp: &mut SendResponse<Bytes>) {
+        if let Some(mut rsp) = HttpProxyClientResponse::from_task_err(e, Version::HTTP_2, false) {
+            self.ctx
+                .set_custom_header_for_local_reply(&self.tcp_notes, &mut rsp);
+            self.reply_local(send_rsp, &rsp);
+        }
+    }
+
+    fn handle_acl_action(
+        &mut self,
+        action: AclAction,
+        send_rsp: &mut SendResponse<Bytes>,
+        e: ServerTaskForbiddenError,
+    ) -> ServerTaskResult<()> {
+        if action.forbid_early() {
+            let rsp = if matches!(e, ServerTaskForbiddenError::ProtoBanned) {
+                HttpProxyClientResponse::method_not_allowed(Version::HTTP_2)
+            } else {
+                HttpProxyClientResponse::forbidden(Version::HTTP_2)
+            };
+            // no custom header is set
+            self.reply_local(send_rsp, &rsp);
+            Err(ServerTaskError::ForbiddenByRule(e))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get_log_context(&self) -> TaskLogForHttpForward {
+        let http_user_agent = self
+            .req
+            .end_to_end_headers
+            .get(http::header::USER_AGENT)
+            .map(|v| v.to_str());
+        TaskLogForHttpForward {
+            task_notes: &self.task_notes,
+            http_notes: &self.http_notes,
+            http_user_agent,
+            tcp_notes: &self.tcp_notes,
+            total_time: self.task_notes.time_elapsed(),
+            client_rd_bytes: self.task_stats.clt.read.get_bytes(),
+            client_wr_bytes: self.task_stats.clt.write.get_bytes(),
+            remote_rd_bytes: self.task_stats.ups.read.get_bytes(),
+            remote_wr_bytes: self.task_stats.ups.write.get_bytes(),
+        }
+    }
+
+    pub(super) async fn run(mut self, clt_r: RecvStream, mut send_rsp: SendResponse<Bytes>) {
+        self.pre_start();
+        match self.run_forward(clt_r, &mut send_rsp).await {
+            Ok(()) => {
+                self.get_log_context()
+                    .log(&self.ctx.task_logger, &ServerTaskError::Finished);
+            }
+            Err(e) => {
+                self.get_log_context().log(&self.ctx.task_logger, &e);
+            }
+        }
+        self.pre_stop();
+    }
+
+    fn pre_start(&self) {
+        debug!(
+            "HttpProxy/H2/FORWARD: new client from {} to {} server {}, using escaper {}",
+            self.ctx.client_addr(),
+            self.ctx.server_config.server_type(),
+            self.ctx.server_config.name(),
+            self.ctx.server_config.escaper
+        );
+        self.ctx.server_stats.task_http_forward.add_task();
+        self.ctx.server_stats.task_http_forward.inc_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| {
+                s.req_total.add_http_forward(self.is_https);
+                s.req_alive.add_http_forward(self.is_https);
+            });
+        }
+    }
+
+    fn pre_stop(&mut self) {
+        self.ctx.server_stats.task_http_forward.dec_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| s.req_alive.del_http_forward(self.is_https));
+
+            if let Some(user_req_alive_permit) = self.task_notes.user_req_alive_permit.take() {
+                drop(user_req_alive_permit);
+            }
+        }
+    }
+
+    fn setup_clt_limit_and_stats(
+        &self,
+    ) -> (
+        ArcLimitedReaderStats,
+        ArcLimitedWriterStats,
+        TcpSockSpeedLimitConfig,
+    ) {
+        let origin_header_size = self.req.origin_header_size() as u64;
+        self.task_stats.clt.read.add_bytes(origin_header_size);
+
+        let user_io_stats = self.task_notes.user_ctx().map(|user_ctx| {
+            user_ctx.fetch_traffic_stats(
+                self.ctx.server_config.name(),
+                self.ctx.server_stats.share_extra_tags(),
+            )
+        });
+        let limit_config = match self.task_notes.user_ctx() {
+            Some(user_ctx) => user_ctx
+                .user_config()
+                .tcp_sock_speed_limit
+                .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit),
+            None => self.ctx.server_config.tcp_sock_speed_limit,
+        };
+
+        let (clt_r_stats, clt_w_stats) = if self.is_https {
+            let mut wrapper_stats =
+                HttpsForwardTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats);
+            if let Some(user_io_stats) = user_io_stats {
+                for s in &user_io_stats {
+                    s.io.https_forward.add_in_bytes(origin_header_size);
+                }
+                wrapper_stats.push_user_io_stats(user_io_stats);
+            }
+            wrapper_stats.split()
+        } else {
+            let mut wrapper_stats =
+                HttpForwardTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats);
+            if let Some(user_io_stats) = user_io_stats {
+                for s in &user_io_stats {
+                    s.io.http_forward.add_in_bytes(origin_header_size);
+                }
+                wrapper_stats.push_user_io_stats(user_io_stats);
+            }
+            wrapper_stats.split()
+        };
+        (clt_r_stats, clt_w_stats, limit_config)
+    }
+
+    async fn run_forward(
+        &mut self,
+        clt_r: RecvStream,
+        send_rsp: &mut SendResponse<Bytes>,
+    ) -> ServerTaskResult<()> {
+        let mut audit_task = false;
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            let user_ctx = user_ctx.clone();
+
+            let action = user_ctx.check_client_addr(self.task_notes.client_addr());
+            self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::SrcBlocked)?;
+
+            if user_ctx.check_rate_limit().is_err() {
+                let rsp = HttpProxyClientResponse::too_many_requests(Version::HTTP_2);
+                self.reply_local(send_rsp, &rsp);
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::RateLimited,
+                ));
+            }
+
+            if user_ctx.check_quota().is_err() {
+                let rsp = HttpProxyClientResponse::too_many_requests(Version::HTTP_2);
+                self.reply_local(send_rsp, &rsp);
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
+            match user_ctx.acquire_request_semaphore() {
+                Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
+                Err(_) => {
+                    let rsp = HttpProxyClientResponse::too_many_requests(Version::HTTP_2);
+                    self.reply_local(send_rsp, &rsp);
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::FullyLoaded,
+                    ));
+                }
+            }
+
+            let request_type = if self.is_https {
+                ProxyRequestType::HttpsForward
+            } else {
+                ProxyRequestType::HttpForward
+            };
+            let action = user_ctx.check_proxy_request(request_type);
+            self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::ProtoBanned)?;
+
+            let action = user_ctx.check_upstream(&self.tcp_notes.upstream);
+            self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::DestDenied)?;
+
+            if let Some(action) = user_ctx.check_http_user_agent(&self.req.end_to_end_headers) {
+                self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::UaBlocked)?;
+            }
+
+            let scheme = if self.is_https { "https" } else { "http" };
+            let path_and_query = self
+                .req
+                .uri
+                .path_and_query()
+                .map(|v| v.as_str())
+                .unwrap_or("/");
+            if let Some(action) =
+                user_ctx.check_http_url(scheme, self.tcp_notes.upstream.host(), path_and_query)
+            {
+                self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::UrlBlocked)?;
+            }
+
+            if let Some(audit_handle) = &self.ctx.audit_handle {
+                audit_task = user_ctx
+                    .user_config()
+                    .audit
+                    .do_task_audit()
+                    .unwrap_or_else(|| audit_handle.do_task_audit());
+            }
+        } else if let Some(audit_handle) = &self.ctx.audit_handle {
+            audit_task = audit_handle.do_task_audit();
+        }
+
+        // server level dst host/port acl rules
+        let action = self.ctx.check_upstream(&self.tcp_notes.upstream);
+        if action.forbid_early() {
+            self.ctx.server_stats.forbidden.add_dest_denied();
+            if let Some(user_ctx) = self.task_notes.user_ctx() {
+                // also add to user level forbidden stats
+                user_ctx.add_dest_denied();
+            }
+        }
+        self.handle_acl_action(action, send_rsp, ServerTaskForbiddenError::DestDenied)?;
+
+        if audit_task {
+            if let Some(audit_handle) = &self.ctx.audit_handle {
+                if audit_handle.icap_reqmod_client().is_some()
+                    || audit_handle.icap_respmod_client().is_some()
+                {
+                    // let the client retry this request over HTTP/1.1, where ICAP adaptation applies
+                    send_rsp.send_reset(Reason::HTTP_1_1_REQUIRED);
+                    return Err(ServerTaskError::InternalAdapterError(anyhow!(
+                        "ICAP adaptation is not supported for HTTP/2 forward requests"
+                    )));
+                }
+            }
+        }
+
+        let mut fwd_ctx = self
+            .ctx
+            .escaper
+            .new_http_forward_context(Arc::clone(&self.ctx.escaper));
+        let forward_capability = fwd_ctx
+            .check_in_final_escaper(&self.task_notes, &self.tcp_notes.upstream)
+            .await;
+        // the https request will be forwarded by the next proxy if supported
+        let remote_https = self.is_https && !forward_capability.forward_https();
+        fwd_ctx.prepare_connection(&self.tcp_notes.upstream, remote_https);
+
+        self.task_notes.stage = ServerTaskStage::Connecting;
+        match self.make_new_connection(&mut fwd_ctx, remote_https).await {
+            Ok(connection) => {
+                self.task_notes.stage = ServerTaskStage::Connected;
+                fwd_ctx.fetch_tcp_notes(&mut self.tcp_notes);
+
+                match self
+                    .run_with_connection(clt_r, send_rsp, connection, remote_https)
+                    .await
+                {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        if self.http_notes.rsp_status == 0 {
+                            self.reply_task_err(&e, send_rsp);
+                        }
+                        Err(e)
+                    }
+                }
+            }
+            Err(e) => {
+                fwd_ctx.fetch_tcp_notes(&mut self.tcp_notes);
+                self.reply_connect_err(&e, send_rsp);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn make_new_connection(
+        &self,
+        fwd_ctx: &mut BoxHttpForwardContext,
+        remote_https: bool,
+    ) -> Result<BoxHttpForwardConnection, TcpConnectError> {
+        if remote_https {
+            let tls_name = self
+                .req
+                .host
+                .as_ref()
+                .unwrap_or(&self.tcp_notes.upstream)
+                .host();
+
+            let tls_client = self
+                .task_notes
+                .user_ctx()
+                .and_then(|ctx| ctx.user_site())
+                .and_then(|site| site.tls_client())
+                .unwrap_or(&self.ctx.tls_client_config);
+
+            fwd_ctx
+                .make_new_https_connection(
+                    &self.task_notes,
+                    self.task_stats.clone() as _,
+                    tls_client,
+                    tls_name,
+                )
+                .await
+        } else {
+            fwd_ctx
+                .make_new_http_connection(&self.task_notes, self.task_stats.clone() as _)
+                .await
+        }
+    }
+
+    fn rsp_hdr_recv_timeout(&self) -> Duration {
+        self.task_notes
+            .user_ctx()
+            .and_then(|ctx| ctx.http_rsp_header_recv_timeout())
+            .unwrap_or(self.ctx.server_config.timeout.recv_rsp_header)
+    }
+
+    async fn run_with_connection(
+        &mut self,
+        clt_r: RecvStream,
+        send_rsp: &mut SendResponse<Bytes>,
+        mut ups_c: BoxHttpForwardConnection,
+        remote_https: bool,
+    ) -> ServerTaskResult<()> {
+        ups_c
+            .0
+            .prepare_new(&self.task_notes, &self.tcp_notes.upstream);
+
+        let (clt_r_stats, clt_w_stats, limit_config) = self.setup_clt_limit_and_stats();
+
+        self.task_notes.mark_relaying();
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| s.req_ready.add_http_forward(self.is_https));
+        }
+
+        let ups_w = &mut ups_c.0;
+        let ups_r = &mut ups_c.1;
+
+        ups_w
+            .send_request_header(&self.req)
+            .await
+            .map_err(ServerTaskError::UpstreamWriteFailed)?;
+        ups_w
+            .flush()
+            .await
+            .map_err(ServerTaskError::UpstreamWriteFailed)?;
+        self.http_notes.mark_req_send_hdr();
+
+        let mut rsp_header: Option<HttpForwardRemoteResponse> = None;
+        if self.req.body_type().is_none() {
+            self.http_notes.mark_req_no_body();
+        } else {
+            let clt_r = LimitedReader::new(
+                H2StreamReader::new(clt_r),
+                limit_config.shift_millis,
+                limit_config.max_north,
+                clt_r_stats,
+            );
+            let mut clt_r = BufReader::new(clt_r);
+            let mut clt_to_ups = ChunkedNoTrailerEncodeTransfer::new(
+                &mut clt_r,
+                ups_w,
+                self.ctx.server_config.tcp_copy.yield_size(),
+            );
+
+            let idle_duration = self.ctx.server_config.task_idle_check_duration;
+            let mut idle_interval =
+                tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+            let mut idle_count = 0;
+            loop {
+                tokio::select! {
+                    biased;
+
+                    r = ups_r.fill_wait_data() => {
+                        match r {
+                            Ok(true) => {
+                                // the upstream may send the final response before all body received
+                                let hdr = self.recv_final_response_header(ups_r).await?;
+                                rsp_header = Some(hdr);
+                                break;
+                            }
+                            Ok(false) => return Err(ServerTaskError::ClosedByUpstream),
+                            Err(e) => return Err(ServerTaskError::UpstreamReadFailed(e)),
+                        }
+                    }
+                    r = &mut clt_to_ups => {
+                        r.map_err(|e| match e {
+                            LimitedCopyError::ReadFailed(e) => ServerTaskError::ClientTcpReadFailed(e),
+                            LimitedCopyError::WriteFailed(e) => ServerTaskError::UpstreamWriteFailed(e),
+                        })?;
+                        self.http_notes.mark_req_send_all();
+                        break;
+                    }
+                    _ = idle_interval.tick() => {
+                        if clt_to_ups.is_idle() {
+                            idle_count += 1;
+
+                            let quit = if let Some(user_ctx) = self.task_notes.user_ctx() {
+                                let user = user_ctx.user();
+                                if user.is_blocked() {
+                                    return Err(ServerTaskError::CanceledAsUserBlocked);
+                                }
+                                idle_count >= user.task_max_idle_count()
+                            } else {
+                                idle_count >= self.ctx.server_config.task_idle_max_count
+                            };
+
+                            if quit {
+                                return if clt_to_ups.no_cached_data() {
+                                    Err(ServerTaskError::ClientAppTimeout("idle while reading request body"))
+                                } else {
+                                    Err(ServerTaskError::UpstreamAppTimeout("idle while sending request body"))
+                                };
+                            }
+                        } else {
+                            idle_count = 0;
+
+                            clt_to_ups.reset_active();
+                        }
+
+                        if let Some(user_ctx) = self.task_notes.user_ctx() {
+                            if user_ctx.user().is_blocked() {
+                                return Err(ServerTaskError::CanceledAsUserBlocked);
+                            }
+                        }
+
+                        if self.ctx.server_quit_policy.force_quit() {
+                            return Err(ServerTaskError::CanceledAsServerQuit)
+                        }
+                    }
+                }
+            }
+        }
+
+        let rsp_header = match rsp_header {
+            Some(header) => header,
+            None => match tokio::time::timeout(
+                self.rsp_hdr_recv_timeout(),
+                self.recv_final_response_header(ups_r),
+            )
+            .await
+            {
+                Ok(Ok(rsp_header)) => rsp_header,
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {
+                    return Err(ServerTaskError::UpstreamAppTimeout(
+                        "timeout to receive response header",
+                    ))
+                }
+            },
+        };
+        self.http_notes.mark_rsp_recv_hdr();
+
+        self.task_notes.stage = ServerTaskStage::Replying;
+        let body_type = rsp_header.body_type(&self.req.method);
+        let rsp = self.build_h2_response(&rsp_header)?;
+        let send_stream = send_rsp
+            .send_response(rsp, body_type.is_none())
+            .map_err(|e| ServerTaskError::ClientTcpWriteFailed(e.into()))?;
+        self.http_notes.rsp_status = rsp_header.code;
+
+        if let Some(body_type) = body_type {
+            let mut clt_w = LimitedWriter::new(
+                H2StreamWriter::new(send_stream),
+                limit_config.shift_millis,
+                limit_config.max_south,
+                clt_w_stats,
+            );
+            self.send_response_body(&mut clt_w, ups_r, body_type)
+                .await?;
+        } else {
+            self.http_notes.mark_rsp_no_body();
+        }
+
+        self.task_notes.stage = ServerTaskStage::Finished;
+        if remote_https {
+            // make sure we correctly shutdown tls connection, or the ticket won't be reused
+            let _ = ups_w.shutdown().await;
+        }
+        Ok(())
+    }
+
+    async fn recv_response_header(
+        &mut self,
+        ups_r: &mut BoxHttpForwardReader,
+    ) -> ServerTaskResult<HttpForwardRemoteResponse> {
+        ups_r
+            .recv_response_header(
+                &self.req.method,
+                self.req.keep_alive(),
+                self.ctx.server_config.rsp_hdr_max_size,
+                &mut self.http_notes,
+            )
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn recv_final_response_header(
+        &mut self,
+        ups_r: &mut BoxHttpForwardReader,
+    ) -> ServerTaskResult<HttpForwardRemoteResponse> {
+        loop {
+            let hdr = self.recv_response_header(ups_r).await?;
+            match hdr.code {
+                100 | 103 => {
+                    // interim responses are not sent to h2 client
+                }
+                _ => return Ok(hdr),
+            }
+        }
+    }
+
+    fn build_h2_response(&self, rsp: &HttpForwardRemoteResponse) -> ServerTaskResult<Response<()>> {
+        let status = StatusCode::from_u16(rsp.code).map_err(|_| {
+            ServerTaskError::InternalServerError("invalid status code in upstream response")
+        })?;
+
+        let mut custom_headers = HttpHeaderMap::default();
+        if let Some(server_id) = &self.ctx.server_config.server_id {
+            if self.ctx.server_config.http_forward_mark_upstream {
+                http_header::set_upstream_id(&mut custom_headers, server_id);
+            }
+
+            http_header::set_remote_connection_info(
+                &mut custom_headers,
+                server_id,
+                self.tcp_notes.bind,
+                self.tcp_notes.local,
+                self.tcp_notes.next,
+                &self.tcp_notes.expire,
+            );
+
+            if let Some(egress_info) = &self.tcp_notes.egress {
+                http_header::set_dynamic_egress_info(&mut custom_headers, server_id, egress_info);
+            }
+        }
+
+        if self.ctx.server_config.echo_chained_info {
+            if let Some(addr) = self.tcp_notes.chained.target_addr {
+                http_header::set_upstream_addr(&mut custom_headers, addr);
+            }
+
+            if let Some(addr) = self.tcp_notes.chained.outgoing_addr {
+                http_header::set_outgoing_ip(&mut custom_headers, addr);
+            }
+        }
+
+        let mut response = Response::new(());
+        *response.status_mut() = status;
+        *response.version_mut() = Version::HTTP_2;
+        let headers = response.headers_mut();
+        *headers = rsp.end_to_end_headers.to_h2_map();
+        let mut last_name = None;
+        for (name, value) in custom_headers.into_h2_map() {
+            if let Some(name) = name {
+                last_name = Some(name);
+            }
+            if let Some(name) = &last_name {
+                headers.append(name.clone(), value);
+            }
+        }
+        Ok(response)
+    }
+
+    async fn send_response_body(
+        &mut self,
+        clt_w: &mut LimitedWriter<H2StreamWriter>,
+        ups_r: &mut BoxHttpForwardReader,
+        body_type: HttpBodyType,
+    ) -> ServerTaskResult<()> {
+        let mut body_reader =
+            HttpBodyReader::new(ups_r, body_type, self.ctx.server_config.body_line_max_len);
+
+        let mut ups_to_clt =
+            LimitedCopy::new(&mut body_reader, clt_w, &self.ctx.server_config.tcp_copy);
+
+        let idle_duration = self.ctx.server_config.task_idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        loop {
+            tokio::select! {
+                biased;
+
+                r = &mut ups_to_clt => {
+                    match r {
+                        Ok(_) => {
+                            self.http_notes.mark_rsp_recv_all();
+                            break;
+                        }
+                        Err(LimitedCopyError::ReadFailed(e)) => return Err(ServerTaskError::UpstreamReadFailed(e)),
+                        Err(LimitedCopyError::WriteFailed(e)) => return Err(ServerTaskError::ClientTcpWriteFailed(e)),
+                    }
+                }
+                _ = idle_interval.tick() => {
+                    if ups_to_clt.is_idle() {
+                        idle_count += 1;
+
+                        let quit = if let Some(user_ctx) = self.task_notes.user_ctx() {
+                            let user = user_ctx.user();
+                            if user.is_blocked() {
+                                return Err(ServerTaskError::CanceledAsUserBlocked);
+                            }
+                            idle_count >= user.task_max_idle_count()
+                        } else {
+                            idle_count >= self.ctx.server_config.task_idle_max_count
+                        };
+
+                        if quit {
+                            return if ups_to_clt.no_cached_data() {
+                                Err(ServerTaskError::UpstreamAppTimeout("idle while reading response body"))
+                            } else {
+                                Err(ServerTaskError::ClientAppTimeout("idle while sending response with body"))
+                            };
+                        }
+                    } else {
+                        idle_count = 0;
+
+                        ups_to_clt.reset_active();
+                    }
+
+                    if let Some(user_ctx) = self.task_notes.user_ctx() {
+                        if user_ctx.user().is_blocked() {
+                            return Err(ServerTaskError::CanceledAsUserBlocked);
+                        }
+                    }
+
+                    if self.ctx.server_quit_policy.force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit)
+                    }
+                }
+            }
+        }
+
+        // send end of stream
+        clt_w
+            .shutdown()
+            .await
+            .map_err(ServerTaskError::ClientTcpWriteFailed)
+    }
+}
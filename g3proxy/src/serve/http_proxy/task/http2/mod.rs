@@ -0,0 +1,38 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use bytes::Bytes;
+use h2::server::SendResponse;
+
+use super::{
+    protocol, CommonTaskContext, HttpForwardTaskCltWrapperStats, HttpForwardTaskStats,
+    HttpProxyUdpConnectTask, HttpsForwardTaskCltWrapperStats, TcpConnectTaskCltWrapperStats,
+};
+use crate::module::http_forward::HttpProxyClientResponse;
+
+mod connection;
+pub(crate) use connection::HttpProxyH2ConnectionTask;
+
+mod connect;
+use connect::H2ProxyConnectTask;
+
+mod forward;
+use forward::H2ProxyForwardTask;
+
+/// send a local generated response without body, the stream will be closed after that
+fn send_local_response(send_rsp: &mut SendResponse<Bytes>, rsp: &HttpProxyClientResponse) -> bool {
+    send_rsp.send_response(rsp.to_h2_response(), true).is_ok()
+}
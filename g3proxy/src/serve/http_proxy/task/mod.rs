@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use super::HttpProxyServerStats;
+use super::{HttpProxyPacFile, HttpProxyServerStats};
 use crate::config::server::http_proxy::HttpProxyServerConfig;
 
 mod common;
@@ -26,12 +26,20 @@ mod connect;
 mod forward;
 mod ftp;
 mod pipeline;
+mod udp_connect;
 mod untrusted;
 
-use connect::HttpProxyConnectTask;
-use forward::HttpProxyForwardTask;
+mod http2;
+pub(super) use http2::HttpProxyH2ConnectionTask;
+
+use connect::{HttpProxyConnectTask, TcpConnectTaskCltWrapperStats};
+use forward::{
+    HttpForwardTaskCltWrapperStats, HttpForwardTaskStats, HttpProxyForwardTask,
+    HttpsForwardTaskCltWrapperStats,
+};
 use ftp::FtpOverHttpTask;
 pub(super) use pipeline::{
     HttpProxyPipelineReaderTask, HttpProxyPipelineStats, HttpProxyPipelineWriterTask,
 };
+use udp_connect::HttpProxyUdpConnectTask;
 use untrusted::HttpProxyUntrustedTask;
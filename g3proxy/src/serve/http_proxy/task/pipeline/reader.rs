@@ -115,6 +115,7 @@ where
                         self.ctx.server_config.req_hdr_max_size,
                         self.ctx.server_config.steal_forwarded_for,
                         self.ctx.server_config.allow_custom_host,
+                        self.ctx.server_config.pac_file.as_ref(),
                         &mut version,
                     ),
                 )
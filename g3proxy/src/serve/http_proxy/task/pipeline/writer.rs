@@ -19,7 +19,8 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use ahash::AHashMap;
-use tokio::io::{AsyncRead, AsyncWrite};
+use http::Method;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::sync::mpsc;
 
 use g3_io_ext::{ArcLimitedWriterStats, LimitedWriter};
@@ -29,7 +30,7 @@ use g3_types::net::{HttpAuth, HttpBasicAuth, HttpHeaderMap};
 use super::protocol::{HttpClientReader, HttpClientWriter, HttpProxyRequest, HttpProxySubProtocol};
 use super::{
     CommonTaskContext, FtpOverHttpTask, HttpProxyCltWrapperStats, HttpProxyConnectTask,
-    HttpProxyForwardTask, HttpProxyPipelineStats, HttpProxyUntrustedTask,
+    HttpProxyForwardTask, HttpProxyPipelineStats, HttpProxyUdpConnectTask, HttpProxyUntrustedTask,
 };
 use crate::auth::{UserContext, UserGroup, UserRequestStats};
 use crate::config::server::ServerConfig;
@@ -123,41 +124,71 @@ where
         }
     }
 
-    fn do_auth(
+    async fn do_auth(
         &mut self,
         req: &HttpProxyRequest<CDR>,
     ) -> Result<Option<UserContext>, UserAuthError> {
         if let Some(user_group) = &self.user_group {
-            let mut user_ctx = match &req.inner.auth_info {
-                HttpAuth::None => {
-                    if let Some((user, user_type)) = user_group.get_anonymous_user() {
-                        UserContext::new(
-                            None,
-                            user,
-                            user_type,
+            let cert_user_ctx = match &self.ctx.client_cert {
+                Some(cert) => {
+                    user_group
+                        .check_client_cert(
+                            cert,
+                            &req.inner.auth_info,
                             self.ctx.server_config.name(),
                             self.ctx.server_stats.share_extra_tags(),
                         )
-                    } else {
-                        return Err(UserAuthError::NoUserSupplied);
-                    }
+                        .await?
                 }
-                HttpAuth::Basic(HttpBasicAuth {
-                    username, password, ..
-                }) => match user_group.get_user(username.as_original()) {
-                    Some((user, user_type)) => {
+                None => None,
+            };
+            let mut user_ctx = if let Some(user_ctx) = cert_user_ctx {
+                user_ctx
+            } else {
+                match &req.inner.auth_info {
+                    HttpAuth::None => {
+                        if let Some((user, user_type)) = user_group.get_anonymous_user() {
+                            UserContext::new(
+                                None,
+                                user,
+                                user_type,
+                                self.ctx.server_config.name(),
+                                self.ctx.server_stats.share_extra_tags(),
+                            )
+                        } else {
+                            return Err(UserAuthError::NoUserSupplied);
+                        }
+                    }
+                    HttpAuth::Basic(HttpBasicAuth {
+                        username, password, ..
+                    }) => match user_group.get_user(username.as_original()) {
+                        Some((user, user_type)) => {
+                            let user_ctx = UserContext::new(
+                                Some(username.as_original().to_string()),
+                                user,
+                                user_type,
+                                self.ctx.server_config.name(),
+                                self.ctx.server_stats.share_extra_tags(),
+                            );
+                            user_ctx.check_password(password.as_original()).await?;
+                            user_ctx
+                        }
+                        None => return Err(UserAuthError::NoSuchUser),
+                    },
+                    HttpAuth::Bearer(bearer) => {
+                        let (username, user, user_type) =
+                            user_group.get_bearer_user(bearer.token())?;
                         let user_ctx = UserContext::new(
-                            Some(username.as_original().to_string()),
+                            Some(username),
                             user,
                             user_type,
                             self.ctx.server_config.name(),
                             self.ctx.server_stats.share_extra_tags(),
                         );
-                        user_ctx.check_password(password.as_original())?;
+                        user_ctx.check_available()?;
                         user_ctx
                     }
-                    None => return Err(UserAuthError::NoSuchUser),
-                },
+                }
             };
 
             user_ctx.check_in_site(
@@ -200,15 +231,20 @@ where
         loop {
             let res = match self.task_queue.recv().await {
                 Some(Ok(req)) => {
-                    let res = match self.do_auth(&req) {
-                        Ok(user_ctx) => {
-                            self.req_count.consequent_auth_failed = 0;
-                            self.run(req, user_ctx).await
-                        }
-                        Err(e) => {
-                            self.req_count.consequent_auth_failed += 1;
-                            self.req_count.auth_failed += 1;
-                            self.run_untrusted(req, e.blocked_delay()).await
+                    let res = if matches!(req.client_protocol, HttpProxySubProtocol::PacFile) {
+                        // no auth is required for pac files
+                        self.run_pac_file(req).await
+                    } else {
+                        match self.do_auth(&req).await {
+                            Ok(user_ctx) => {
+                                self.req_count.consequent_auth_failed = 0;
+                                self.run(req, user_ctx).await
+                            }
+                            Err(e) => {
+                                self.req_count.consequent_auth_failed += 1;
+                                self.req_count.auth_failed += 1;
+                                self.run_untrusted(req, e.blocked_delay()).await
+                            }
                         }
                     };
                     self.pipeline_stats.del_task();
@@ -271,6 +307,7 @@ where
             .await;
         let remote_protocol = match req.client_protocol {
             HttpProxySubProtocol::TcpConnect => HttpProxySubProtocol::TcpConnect,
+            HttpProxySubProtocol::UdpConnect => HttpProxySubProtocol::UdpConnect,
             HttpProxySubProtocol::HttpForward => HttpProxySubProtocol::HttpForward,
             HttpProxySubProtocol::HttpsForward => {
                 if forward_capability.forward_https() {
@@ -286,6 +323,7 @@ where
                     HttpProxySubProtocol::FtpOverHttp
                 }
             }
+            HttpProxySubProtocol::PacFile => unreachable!(),
         };
 
         match remote_protocol {
@@ -315,6 +353,29 @@ where
                     unreachable!()
                 }
             }
+            HttpProxySubProtocol::UdpConnect => {
+                if let (Some(mut stream_w), Some(stream_r)) =
+                    (self.stream_writer.take(), req.body_reader.take())
+                {
+                    // close read end
+                    let _ = req.stream_sender.send(None).await;
+                    if self.ctx.server_config.enable_connect_udp {
+                        let udp_connect_task = HttpProxyUdpConnectTask::new(
+                            &self.ctx,
+                            req.upstream,
+                            task_notes,
+                            req.inner.version,
+                        );
+                        udp_connect_task.into_running(stream_r, stream_w);
+                    } else {
+                        let rsp = HttpProxyClientResponse::unimplemented(req.inner.version);
+                        let _ = rsp.reply_err_to_request(&mut stream_w).await;
+                    }
+                    LoopAction::Break
+                } else {
+                    unreachable!()
+                }
+            }
             HttpProxySubProtocol::HttpForward | HttpProxySubProtocol::HttpsForward => {
                 if let Some(mut stream_w) = self.stream_writer.take() {
                     match self
@@ -349,6 +410,7 @@ where
                     unreachable!()
                 }
             }
+            HttpProxySubProtocol::PacFile => unreachable!(),
         }
     }
 
@@ -452,6 +514,49 @@ where
         }
     }
 
+    async fn run_pac_file(&mut self, mut req: HttpProxyRequest<CDR>) -> LoopAction {
+        // close the connection if there is a body
+        let close = req.body_reader.is_some() || !req.inner.keep_alive();
+
+        let r = if let (Some(clt_w), Some(pac_file)) = (&mut self.stream_writer, &self.ctx.pac_file)
+        {
+            let content =
+                pac_file.render(self.ctx.cc_info.client_ip(), self.ctx.cc_info.server_addr());
+            let mut rsp = HttpProxyClientResponse::sized_ok(
+                req.inner.version,
+                close,
+                content.len() as u64,
+                pac_file.content_type(),
+            );
+            rsp.add_extra_header(format!("Cache-Control: max-age={}\r\n", pac_file.max_age()));
+
+            let send_body = req.inner.method != Method::HEAD;
+            async {
+                rsp.reply_ok_header(clt_w).await?;
+                if send_body {
+                    clt_w.write_all(content.as_bytes()).await?;
+                }
+                clt_w.flush().await
+            }
+            .await
+            .is_ok()
+        } else {
+            // should be impossible
+            false
+        };
+
+        if r && !close {
+            return LoopAction::Continue;
+        }
+        if req.body_reader.take().is_some() {
+            // close read end
+            let _ = req.stream_sender.send(None).await;
+        } else {
+            self.notify_reader_to_close();
+        }
+        LoopAction::Break
+    }
+
     async fn run_forward(
         &mut self,
         clt_w: &mut HttpClientWriter<CDW>,
@@ -18,14 +18,18 @@ use g3_io_ext::{LimitedBufReader, LimitedWriter};
 
 mod request;
 
-pub(super) use request::HttpProxyRequest;
+pub(super) use request::{
+    get_connect_upstream, get_forward_upstream_and_protocol, HttpProxyRequest,
+};
 
 pub(super) type HttpClientReader<CDR> = LimitedBufReader<CDR>;
 pub(super) type HttpClientWriter<CDW> = LimitedWriter<CDW>;
 
 pub(crate) enum HttpProxySubProtocol {
     TcpConnect,
+    UdpConnect,
     HttpForward,
     HttpsForward,
     FtpOverHttp,
+    PacFile,
 }
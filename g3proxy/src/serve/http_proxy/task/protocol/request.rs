@@ -23,6 +23,7 @@ use g3_http::server::{HttpProxyClientRequest, HttpRequestParseError, UriExt};
 use g3_types::net::UpstreamAddr;
 
 use super::{HttpClientReader, HttpProxySubProtocol};
+use crate::config::server::http_proxy::HttpProxyPacFileConfig;
 
 pub(crate) struct HttpProxyRequest<CDR> {
     pub(crate) client_protocol: HttpProxySubProtocol,
@@ -44,6 +45,7 @@ where
         max_header_size: usize,
         steal_forwarder_for: bool,
         allow_custom_host: bool,
+        pac_file: Option<&HttpProxyPacFileConfig>,
         version: &mut Version,
     ) -> Result<(Self, bool), HttpRequestParseError> {
         let time_accepted = Instant::now();
@@ -74,11 +76,30 @@ where
                 get_connect_upstream(&req.uri)?,
                 HttpProxySubProtocol::TcpConnect,
             )
+        } else if req.is_connect_udp() {
+            if !matches!(&req.method, &Method::GET) {
+                return Err(HttpRequestParseError::UpgradeIsNotSupported);
+            }
+            (
+                req.uri.get_connect_udp_upstream()?,
+                HttpProxySubProtocol::UdpConnect,
+            )
+        } else if pac_file
+            .map(|c| c.match_request(&req.method, &req.uri))
+            .unwrap_or(false)
+        {
+            // direct request to the proxy itself
+            let upstream = req.host.clone().unwrap_or_else(UpstreamAddr::empty);
+            (upstream, HttpProxySubProtocol::PacFile)
         } else {
             get_forward_upstream_and_protocol(&req.uri)?
         };
 
-        if !allow_custom_host {
+        // the host header is the proxy itself for connect-udp and pac file requests
+        if !allow_custom_host
+            && !req.is_connect_udp()
+            && !matches!(sub_protocol, HttpProxySubProtocol::PacFile)
+        {
             if let Some(host) = &req.host {
                 if !host.host_eq(&upstream) {
                     return Err(HttpRequestParseError::UnmatchedHostAndAuthority);
@@ -97,13 +118,15 @@ where
         };
 
         match req.client_protocol {
-            HttpProxySubProtocol::TcpConnect => {
+            HttpProxySubProtocol::TcpConnect | HttpProxySubProtocol::UdpConnect => {
                 // just send to forward task, which will go into a connect task
                 // reader should be sent
                 return Ok((req, true));
             }
             HttpProxySubProtocol::FtpOverHttp => {}
-            HttpProxySubProtocol::HttpForward | HttpProxySubProtocol::HttpsForward => {
+            HttpProxySubProtocol::HttpForward
+            | HttpProxySubProtocol::HttpsForward
+            | HttpProxySubProtocol::PacFile => {
                 if req.inner.pipeline_safe() {
                     // reader should not be sent
                     return Ok((req, false));
@@ -116,11 +139,11 @@ where
     }
 }
 
-fn get_connect_upstream(uri: &http::Uri) -> Result<UpstreamAddr, HttpRequestParseError> {
+pub(super) fn get_connect_upstream(uri: &http::Uri) -> Result<UpstreamAddr, HttpRequestParseError> {
     uri.get_upstream_with_default_port(443)
 }
 
-fn get_forward_upstream_and_protocol(
+pub(super) fn get_forward_upstream_and_protocol(
     uri: &http::Uri,
 ) -> Result<(UpstreamAddr, HttpProxySubProtocol), HttpRequestParseError> {
     match uri.scheme() {
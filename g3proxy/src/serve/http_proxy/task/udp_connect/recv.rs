@@ -0,0 +1,199 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use g3_http::capsule::CapsuleHeader;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+))]
+use g3_io_ext::UdpCopyPacket;
+use g3_io_ext::{ArcLimitedRecvStats, UdpCopyClientError, UdpCopyClientRecv};
+
+/// reserved space for the capsule header, the max length of 3 varints
+const CAPSULE_HEADER_RESERVED_SIZE: usize = 24;
+
+/// Receive HTTP Datagrams from the capsule stream of a connect-udp request
+pub(super) struct CapsuleUdpConnectClientRecv<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    buf_off: usize,
+    buf_end: usize,
+    discard_left: u64,
+    stats: ArcLimitedRecvStats,
+    /// error deferred to the next poll, as some packets have been received before it
+    pending_error: Option<UdpCopyClientError>,
+}
+
+impl<R> CapsuleUdpConnectClientRecv<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub(super) fn new(inner: R, packet_size: usize, stats: ArcLimitedRecvStats) -> Self {
+        let buf_size = packet_size + CAPSULE_HEADER_RESERVED_SIZE;
+        CapsuleUdpConnectClientRecv {
+            inner,
+            buf: vec![0u8; buf_size].into_boxed_slice(),
+            buf_off: 0,
+            buf_end: 0,
+            discard_left: 0,
+            stats,
+            pending_error: None,
+        }
+    }
+
+    fn poll_fill_buf(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), UdpCopyClientError>> {
+        if self.buf_off >= self.buf_end {
+            self.buf_off = 0;
+            self.buf_end = 0;
+        } else if self.buf_end >= self.buf.len() {
+            self.buf.copy_within(self.buf_off..self.buf_end, 0);
+            self.buf_end -= self.buf_off;
+            self.buf_off = 0;
+        }
+
+        let mut read_buf = ReadBuf::new(&mut self.buf[self.buf_end..]);
+        ready!(Pin::new(&mut self.inner).poll_read(cx, &mut read_buf))
+            .map_err(UdpCopyClientError::RecvFailed)?;
+        let nr = read_buf.filled().len();
+        if nr == 0 {
+            return Poll::Ready(Err(UdpCopyClientError::RecvFailed(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "capsule stream closed",
+            ))));
+        }
+        self.buf_end += nr;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R> UdpCopyClientRecv for CapsuleUdpConnectClientRecv<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    fn max_hdr_len(&self) -> usize {
+        0
+    }
+
+    fn poll_recv_packet(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<(usize, usize), UdpCopyClientError>> {
+        if let Some(e) = self.pending_error.take() {
+            return Poll::Ready(Err(e));
+        }
+
+        loop {
+            if self.discard_left > 0 {
+                let buffered = (self.buf_end - self.buf_off) as u64;
+                if buffered == 0 {
+                    ready!(self.poll_fill_buf(cx))?;
+                    continue;
+                }
+                let n = buffered.min(self.discard_left);
+                self.buf_off += n as usize;
+                self.discard_left -= n;
+                continue;
+            }
+
+            let Some(header) = CapsuleHeader::parse(&self.buf[self.buf_off..self.buf_end]) else {
+                ready!(self.poll_fill_buf(cx))?;
+                continue;
+            };
+            let capsule_size = header.encoded_len as u64 + header.length;
+            if !header.is_datagram() || capsule_size > self.buf.len() as u64 {
+                // unknown capsules should be ignored, and too large datagrams should be dropped
+                self.buf_off += header.encoded_len;
+                self.discard_left = header.length;
+                continue;
+            }
+            let capsule_end = self.buf_off + capsule_size as usize;
+            if capsule_end > self.buf_end {
+                ready!(self.poll_fill_buf(cx))?;
+                continue;
+            }
+
+            let value_off = self.buf_off + header.encoded_len;
+            self.buf_off = capsule_end;
+            let value = &self.buf[value_off..capsule_end];
+            let Some((context_id, id_len)) = g3_http::capsule::decode_varint(value) else {
+                return Poll::Ready(Err(UdpCopyClientError::InvalidPacket(
+                    "no context id found in datagram capsule".to_string(),
+                )));
+            };
+            if context_id != 0 {
+                // no extension is supported, so drop all datagrams with other context id
+                continue;
+            }
+            let payload = &value[id_len..];
+            let len = payload.len();
+            if len > buf.len() {
+                continue;
+            }
+            buf[..len].copy_from_slice(payload);
+            self.stats.add_recv_bytes(len);
+            self.stats.add_recv_packet();
+            return Poll::Ready(Ok((0, len)));
+        }
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+    ))]
+    fn poll_recv_packets(
+        &mut self,
+        cx: &mut Context<'_>,
+        packets: &mut [UdpCopyPacket],
+    ) -> Poll<Result<usize, UdpCopyClientError>> {
+        let mut count = 0;
+        for packet in packets.iter_mut() {
+            match self.poll_recv_packet(cx, packet.buf_mut()) {
+                Poll::Pending => break,
+                Poll::Ready(Ok((off, end))) => {
+                    packet.set_offset(off);
+                    packet.set_length(end);
+                    count += 1;
+                }
+                Poll::Ready(Err(e)) => {
+                    if count > 0 {
+                        // return the received packets first, and report the error on the next poll
+                        self.pending_error = Some(e);
+                        break;
+                    }
+                    return Poll::Ready(Err(e));
+                }
+            }
+        }
+        if count > 0 {
+            Poll::Ready(Ok(count))
+        } else {
+            Poll::Pending
+        }
+    }
+}
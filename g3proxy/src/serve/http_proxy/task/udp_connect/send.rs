@@ -0,0 +1,133 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::AsyncWrite;
+
+use g3_http::capsule::DATAGRAM_HEADER_MAX_LEN;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+))]
+use g3_io_ext::UdpCopyPacket;
+use g3_io_ext::{ArcLimitedSendStats, UdpCopyClientError, UdpCopyClientSend};
+
+/// Send HTTP Datagrams to the capsule stream of a connect-udp request
+///
+/// The encoded capsules will be cached until all of them have been written out,
+/// so the caller should retry with the same packets if pending is returned.
+pub(super) struct CapsuleUdpConnectClientSend<W> {
+    inner: W,
+    buf: Vec<u8>,
+    buf_off: usize,
+    buf_packets: usize,
+    buf_payload_size: usize,
+    stats: ArcLimitedSendStats,
+}
+
+impl<W> CapsuleUdpConnectClientSend<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub(super) fn new(inner: W, packet_size: usize, stats: ArcLimitedSendStats) -> Self {
+        CapsuleUdpConnectClientSend {
+            inner,
+            buf: Vec::with_capacity(packet_size + DATAGRAM_HEADER_MAX_LEN),
+            buf_off: 0,
+            buf_packets: 0,
+            buf_payload_size: 0,
+            stats,
+        }
+    }
+
+    fn push_packet(&mut self, payload: &[u8]) {
+        let mut hdr_buf = [0u8; DATAGRAM_HEADER_MAX_LEN];
+        // the payload size is limited by the udp relay packet size
+        let hdr_len = g3_http::capsule::encode_datagram_header(payload.len(), &mut hdr_buf)
+            .expect("datagram payload too large");
+        self.buf.extend_from_slice(&hdr_buf[..hdr_len]);
+        self.buf.extend_from_slice(payload);
+        self.buf_packets += 1;
+        self.buf_payload_size += payload.len();
+    }
+
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize, UdpCopyClientError>> {
+        while self.buf_off < self.buf.len() {
+            let nw = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.buf[self.buf_off..]))
+                .map_err(UdpCopyClientError::SendFailed)?;
+            if nw == 0 {
+                return Poll::Ready(Err(UdpCopyClientError::SendFailed(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "write zero byte into sender",
+                ))));
+            }
+            self.buf_off += nw;
+        }
+        ready!(Pin::new(&mut self.inner).poll_flush(cx)).map_err(UdpCopyClientError::SendFailed)?;
+
+        let count = self.buf_packets;
+        self.stats.add_send_bytes(self.buf_payload_size);
+        self.stats.add_send_packets(count);
+        self.buf.clear();
+        self.buf_off = 0;
+        self.buf_packets = 0;
+        self.buf_payload_size = 0;
+        Poll::Ready(Ok(count))
+    }
+}
+
+impl<W> UdpCopyClientSend for CapsuleUdpConnectClientSend<W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    fn poll_send_packet(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, UdpCopyClientError>> {
+        if self.buf_packets == 0 {
+            self.push_packet(buf);
+        }
+        ready!(self.poll_write_buf(cx))?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+    ))]
+    fn poll_send_packets(
+        &mut self,
+        cx: &mut Context<'_>,
+        packets: &[UdpCopyPacket],
+    ) -> Poll<Result<usize, UdpCopyClientError>> {
+        if self.buf_packets == 0 {
+            for p in packets {
+                self.push_packet(p.payload());
+            }
+        }
+        self.poll_write_buf(cx)
+    }
+}
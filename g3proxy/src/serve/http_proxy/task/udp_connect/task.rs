@@ -0,0 +1,446 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use h2::server::SendResponse;
+use h2::RecvStream;
+use http::Version;
+use log::debug;
+use slog::Logger;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+
+use g3_h2::{H2StreamReader, H2StreamWriter};
+use g3_io_ext::{
+    UdpCopyClientError, UdpCopyClientToRemote, UdpCopyError, UdpCopyRemoteRecv, UdpCopyRemoteSend,
+    UdpCopyRemoteToClient,
+};
+use g3_types::acl::AclAction;
+use g3_types::net::{ProxyRequestType, UpstreamAddr};
+
+use super::protocol::{HttpClientReader, HttpClientWriter};
+use super::{
+    CapsuleUdpConnectClientRecv, CapsuleUdpConnectClientSend, CommonTaskContext,
+    UdpConnectTaskCltWrapperStats, UdpConnectTaskStats,
+};
+use crate::config::server::ServerConfig;
+use crate::log::escape::udp_sendto::EscapeLogForUdpConnectSendTo;
+use crate::log::task::udp_connect::TaskLogForUdpConnect;
+use crate::module::http_forward::HttpProxyClientResponse;
+use crate::module::udp_connect::UdpConnectTaskNotes;
+use crate::serve::{
+    ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
+    ServerTaskStage,
+};
+
+type BoxUdpCopyRemoteRecv = Box<dyn UdpCopyRemoteRecv + Unpin + Send + Sync>;
+type BoxUdpCopyRemoteSend = Box<dyn UdpCopyRemoteSend + Unpin + Send + Sync>;
+
+/// connect-udp request, see RFC 9298.
+///
+/// The UDP payloads are carried in HTTP Datagram capsules,
+/// either in the upgraded HTTP/1.1 connection or in the extended CONNECT HTTP/2 stream.
+pub(crate) struct HttpProxyUdpConnectTask {
+    ctx: Arc<CommonTaskContext>,
+    task_notes: ServerTaskNotes,
+    udp_notes: UdpConnectTaskNotes,
+    task_stats: Arc<UdpConnectTaskStats>,
+    http_version: Version,
+}
+
+impl HttpProxyUdpConnectTask {
+    pub(crate) fn new(
+        ctx: &Arc<CommonTaskContext>,
+        upstream: UpstreamAddr,
+        task_notes: ServerTaskNotes,
+        http_version: Version,
+    ) -> Self {
+        let buf_conf = ctx.server_config.udp_socket_buffer;
+        HttpProxyUdpConnectTask {
+            ctx: Arc::clone(ctx),
+            task_notes,
+            udp_notes: UdpConnectTaskNotes::new(upstream, buf_conf),
+            task_stats: Arc::new(UdpConnectTaskStats::default()),
+            http_version,
+        }
+    }
+
+    fn get_log_context(&self) -> TaskLogForUdpConnect {
+        TaskLogForUdpConnect {
+            task_notes: &self.task_notes,
+            tcp_server_addr: self.ctx.cc_info.server_addr(),
+            tcp_client_addr: self.ctx.client_addr(),
+            udp_listen_addr: None,
+            udp_client_addr: None,
+            udp_notes: &self.udp_notes,
+            total_time: self.task_notes.time_elapsed(),
+            client_rd_bytes: self.task_stats.clt.recv.get_bytes(),
+            client_rd_packets: self.task_stats.clt.recv.get_packets(),
+            client_wr_bytes: self.task_stats.clt.send.get_bytes(),
+            client_wr_packets: self.task_stats.clt.send.get_packets(),
+            remote_rd_bytes: self.task_stats.ups.recv.get_bytes(),
+            remote_rd_packets: self.task_stats.ups.recv.get_packets(),
+            remote_wr_bytes: self.task_stats.ups.send.get_bytes(),
+            remote_wr_packets: self.task_stats.ups.send.get_packets(),
+        }
+    }
+
+    fn log_result(&self, r: ServerTaskResult<()>) {
+        match r {
+            Ok(_) => self
+                .get_log_context()
+                .log(&self.ctx.task_logger, &ServerTaskError::ClosedByClient),
+            Err(e) => self.get_log_context().log(&self.ctx.task_logger, &e),
+        }
+    }
+
+    fn pre_start(&self) {
+        debug!(
+            "HttpProxy/CONNECT-UDP: new client from {} to {} server {}, using escaper {}",
+            self.ctx.client_addr(),
+            self.ctx.server_config.server_type(),
+            self.ctx.server_config.name(),
+            self.ctx.server_config.escaper
+        );
+        self.ctx.server_stats.task_udp_connect.add_task();
+        self.ctx.server_stats.task_udp_connect.inc_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| {
+                s.req_total.add_http_connect_udp();
+                s.req_alive.add_http_connect_udp();
+            });
+        }
+    }
+
+    fn pre_stop(&mut self) {
+        self.ctx.server_stats.task_udp_connect.dec_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| s.req_alive.del_http_connect_udp());
+
+            if let Some(user_req_alive_permit) = self.task_notes.user_req_alive_permit.take() {
+                drop(user_req_alive_permit);
+            }
+        }
+    }
+
+    fn mark_relaying(&mut self) {
+        self.task_notes.mark_relaying();
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| s.req_ready.add_http_connect_udp());
+        }
+    }
+
+    fn build_error_response(&self, e: &ServerTaskError) -> Option<HttpProxyClientResponse> {
+        match e {
+            ServerTaskError::ForbiddenByRule(
+                ServerTaskForbiddenError::RateLimited
+                | ServerTaskForbiddenError::QuotaExceeded
+                | ServerTaskForbiddenError::FullyLoaded,
+            ) => Some(HttpProxyClientResponse::too_many_requests(
+                self.http_version,
+            )),
+            ServerTaskError::ForbiddenByRule(ServerTaskForbiddenError::ProtoBanned) => Some(
+                HttpProxyClientResponse::method_not_allowed(self.http_version),
+            ),
+            _ => HttpProxyClientResponse::from_task_err(e, self.http_version, true),
+        }
+    }
+
+    fn handle_user_acl_action(
+        &self,
+        action: AclAction,
+        forbidden_error: ServerTaskForbiddenError,
+    ) -> ServerTaskResult<()> {
+        if action.forbid_early() {
+            Err(ServerTaskError::ForbiddenByRule(forbidden_error))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn handle_server_upstream_acl_action(&self, action: AclAction) -> ServerTaskResult<()> {
+        if action.forbid_early() {
+            self.ctx.server_stats.forbidden.add_dest_denied();
+            if let Some(user_ctx) = self.task_notes.user_ctx() {
+                // also add to user level forbidden stats
+                user_ctx.add_dest_denied();
+            }
+
+            Err(ServerTaskError::ForbiddenByRule(
+                ServerTaskForbiddenError::DestDenied,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn setup_remote(
+        &mut self,
+    ) -> ServerTaskResult<(BoxUdpCopyRemoteRecv, BoxUdpCopyRemoteSend, Logger)> {
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            let user_ctx = user_ctx.clone();
+
+            let action = user_ctx.check_client_addr(self.task_notes.client_addr());
+            self.handle_user_acl_action(action, ServerTaskForbiddenError::SrcBlocked)?;
+
+            if user_ctx.check_rate_limit().is_err() {
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::RateLimited,
+                ));
+            }
+
+            if user_ctx.check_quota().is_err() {
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
+            match user_ctx.acquire_request_semaphore() {
+                Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
+                Err(_) => {
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::FullyLoaded,
+                    ));
+                }
+            }
+
+            let action = user_ctx.check_proxy_request(ProxyRequestType::HttpConnectUdp);
+            self.handle_user_acl_action(action, ServerTaskForbiddenError::ProtoBanned)?;
+
+            if let Some(upstream) = &self.udp_notes.upstream {
+                let action = user_ctx.check_upstream(upstream);
+                self.handle_user_acl_action(action, ServerTaskForbiddenError::DestDenied)?;
+            }
+        }
+
+        if let Some(upstream) = &self.udp_notes.upstream {
+            // server level dst host/port acl rules
+            let action = self.ctx.check_upstream(upstream);
+            self.handle_server_upstream_acl_action(action)?;
+        }
+
+        self.task_notes.stage = ServerTaskStage::Connecting;
+        let (ups_r, ups_w, logger) = self
+            .ctx
+            .escaper
+            .udp_setup_connection(
+                &mut self.udp_notes,
+                &self.task_notes,
+                self.task_stats.clone() as _,
+            )
+            .await?;
+        self.task_notes.stage = ServerTaskStage::Connected;
+        Ok((ups_r, ups_w, logger))
+    }
+
+    pub(crate) fn into_running<CDR, CDW>(
+        mut self,
+        clt_r: HttpClientReader<CDR>,
+        mut clt_w: HttpClientWriter<CDW>,
+    ) where
+        CDR: AsyncRead + Send + Sync + Unpin + 'static,
+        CDW: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        tokio::spawn(async move {
+            self.pre_start();
+            let r = self.run_h1(clt_r, &mut clt_w).await;
+            self.log_result(r);
+            self.pre_stop();
+        });
+    }
+
+    async fn run_h1<CDR, CDW>(
+        &mut self,
+        clt_r: HttpClientReader<CDR>,
+        clt_w: &mut HttpClientWriter<CDW>,
+    ) -> ServerTaskResult<()>
+    where
+        CDR: AsyncRead + Send + Sync + Unpin + 'static,
+        CDW: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let (ups_r, ups_w, escape_logger) = match self.setup_remote().await {
+            Ok(v) => v,
+            Err(e) => {
+                if let Some(rsp) = self.build_error_response(&e) {
+                    let _ = rsp.reply_err_to_request(clt_w).await;
+                }
+                return Err(e);
+            }
+        };
+
+        self.task_notes.stage = ServerTaskStage::Replying;
+        HttpProxyClientResponse::connect_udp_ok(self.http_version)
+            .reply_ok_to_connect(clt_w)
+            .await
+            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+
+        self.mark_relaying();
+        // NOTE the capsule framing is still counted in the http io stats of the connection
+        self.run_relay(clt_r, clt_w, ups_r, ups_w, &escape_logger)
+            .await
+    }
+
+    pub(crate) async fn run_h2(mut self, clt_r: RecvStream, mut send_rsp: SendResponse<Bytes>) {
+        self.pre_start();
+        let r = self.run_h2_stream(clt_r, &mut send_rsp).await;
+        self.log_result(r);
+        self.pre_stop();
+    }
+
+    async fn run_h2_stream(
+        &mut self,
+        clt_r: RecvStream,
+        send_rsp: &mut SendResponse<Bytes>,
+    ) -> ServerTaskResult<()> {
+        let (ups_r, ups_w, escape_logger) = match self.setup_remote().await {
+            Ok(v) => v,
+            Err(e) => {
+                if let Some(rsp) = self.build_error_response(&e) {
+                    let _ = send_rsp.send_response(rsp.to_h2_response(), true);
+                }
+                return Err(e);
+            }
+        };
+
+        self.task_notes.stage = ServerTaskStage::Replying;
+        let rsp = HttpProxyClientResponse::connect_udp_ok(Version::HTTP_2);
+        let send_stream = send_rsp
+            .send_response(rsp.to_h2_response(), false)
+            .map_err(|e| ServerTaskError::ClientTcpWriteFailed(e.into()))?;
+
+        self.mark_relaying();
+        let clt_r = H2StreamReader::new(clt_r);
+        let clt_w = H2StreamWriter::new(send_stream);
+        self.run_relay(clt_r, clt_w, ups_r, ups_w, &escape_logger)
+            .await
+    }
+
+    async fn run_relay<R, W>(
+        &mut self,
+        clt_r: R,
+        clt_w: W,
+        mut ups_r: BoxUdpCopyRemoteRecv,
+        mut ups_w: BoxUdpCopyRemoteSend,
+        escape_logger: &Logger,
+    ) -> ServerTaskResult<()>
+    where
+        R: AsyncRead + Send + Unpin,
+        W: AsyncWrite + Send + Unpin,
+    {
+        let mut wrapper_stats =
+            UdpConnectTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats);
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            wrapper_stats.push_user_io_stats(user_ctx.fetch_traffic_stats(
+                self.ctx.server_config.name(),
+                self.ctx.server_stats.share_extra_tags(),
+            ));
+        }
+        let (clt_r_stats, clt_w_stats) = wrapper_stats.split();
+
+        let relay_config = self.ctx.server_config.udp_relay;
+        let packet_size = relay_config.packet_size();
+        let mut clt_r = CapsuleUdpConnectClientRecv::new(clt_r, packet_size, clt_r_stats);
+        let mut clt_w = CapsuleUdpConnectClientSend::new(clt_w, packet_size, clt_w_stats);
+
+        let task_id = &self.task_notes.id;
+
+        let mut c_to_r = UdpCopyClientToRemote::new(&mut clt_r, &mut *ups_w, relay_config);
+        let mut r_to_c = UdpCopyRemoteToClient::new(&mut clt_w, &mut *ups_r, relay_config);
+
+        let idle_duration = self.ctx.server_config.task_idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        loop {
+            tokio::select! {
+                biased;
+
+                r = &mut c_to_r => {
+                    return match r {
+                        Ok(_) => Ok(()),
+                        Err(UdpCopyError::RemoteError(e)) => {
+                            EscapeLogForUdpConnectSendTo {
+                                task_id,
+                                udp_notes: &self.udp_notes,
+                            }
+                            .log(escape_logger, &e);
+                            Err(e.into())
+                        }
+                        Err(UdpCopyError::ClientError(UdpCopyClientError::RecvFailed(e)))
+                            if e.kind() == io::ErrorKind::UnexpectedEof =>
+                        {
+                            Ok(())
+                        }
+                        Err(UdpCopyError::ClientError(e)) => Err(e.into()),
+                    };
+                }
+                r = &mut r_to_c => {
+                    return match r {
+                        Ok(_) => Ok(()),
+                        Err(UdpCopyError::RemoteError(e)) => {
+                            EscapeLogForUdpConnectSendTo {
+                                task_id,
+                                udp_notes: &self.udp_notes,
+                            }
+                            .log(escape_logger, &e);
+                            Err(e.into())
+                        }
+                        Err(UdpCopyError::ClientError(e)) => Err(e.into()),
+                    };
+                }
+                _ = idle_interval.tick() => {
+                    if c_to_r.is_idle() && r_to_c.is_idle() {
+                        idle_count += 1;
+
+                        let quit = if let Some(user_ctx) = self.task_notes.user_ctx() {
+                            let user = user_ctx.user();
+                            if user.is_blocked() {
+                                return Err(ServerTaskError::CanceledAsUserBlocked);
+                            }
+                            idle_count >= user.task_max_idle_count()
+                        } else {
+                            idle_count >= self.ctx.server_config.task_idle_max_count
+                        };
+
+                        if quit {
+                            return Err(ServerTaskError::Idle(idle_duration, idle_count));
+                        }
+                    } else {
+                        idle_count = 0;
+
+                        c_to_r.reset_active();
+                        r_to_c.reset_active();
+                    }
+
+                    if let Some(user_ctx) = self.task_notes.user_ctx() {
+                        if user_ctx.user().is_blocked() {
+                            return Err(ServerTaskError::CanceledAsUserBlocked);
+                        }
+                    }
+
+                    if self.ctx.server_quit_policy.force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit)
+                    }
+                }
+            }
+        }
+    }
+}
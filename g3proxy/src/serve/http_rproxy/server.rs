@@ -22,6 +22,7 @@ use anyhow::{anyhow, Context};
 use arc_swap::{ArcSwap, ArcSwapOption};
 use async_trait::async_trait;
 use log::debug;
+use openssl::x509::X509;
 #[cfg(feature = "quic")]
 use quinn::Connection;
 use slog::Logger;
@@ -156,13 +157,18 @@ impl HttpRProxyServer {
         }
     }
 
-    fn get_common_task_context(&self, cc_info: ClientConnectionInfo) -> Arc<CommonTaskContext> {
+    fn get_common_task_context(
+        &self,
+        cc_info: ClientConnectionInfo,
+        client_cert: Option<X509>,
+    ) -> Arc<CommonTaskContext> {
         Arc::new(CommonTaskContext {
             server_config: Arc::clone(&self.config),
             server_stats: Arc::clone(&self.server_stats),
             server_quit_policy: Arc::clone(&self.quit_policy),
             escaper: self.escaper.load().as_ref().clone(),
             cc_info,
+            client_cert,
             task_logger: self.task_logger.clone(),
         })
     }
@@ -184,11 +190,15 @@ impl HttpRProxyServer {
         false
     }
 
-    async fn spawn_stream_task<T>(&self, stream: T, cc_info: ClientConnectionInfo)
-    where
+    async fn spawn_stream_task<T>(
+        &self,
+        stream: T,
+        cc_info: ClientConnectionInfo,
+        client_cert: Option<X509>,
+    ) where
         T: AsyncRead + AsyncWrite + Send + Sync + 'static,
     {
-        let ctx = self.get_common_task_context(cc_info);
+        let ctx = self.get_common_task_context(cc_info, client_cert);
         let pipeline_stats = Arc::new(HttpRProxyPipelineStats::default());
         let (task_sender, task_receiver) = mpsc::channel(ctx.server_config.pipeline_size);
 
@@ -209,7 +219,7 @@ impl HttpRProxyServer {
     }
 
     async fn spawn_tcp_task(&self, stream: TcpStream, cc_info: ClientConnectionInfo) {
-        let ctx = self.get_common_task_context(cc_info);
+        let ctx = self.get_common_task_context(cc_info, None);
         let pipeline_stats = Arc::new(HttpRProxyPipelineStats::default());
         let (task_sender, task_receiver) = mpsc::channel(ctx.server_config.pipeline_size);
 
@@ -343,7 +353,11 @@ impl AcceptTcpServer for HttpRProxyServer {
                             )
                             .await
                             {
-                                Ok(Ok(stream)) => self.spawn_stream_task(stream, cc_info).await,
+                                Ok(Ok(stream)) => {
+                                    let client_cert =
+                                        crate::auth::rustls_peer_cert(stream.get_ref().1);
+                                    self.spawn_stream_task(stream, cc_info, client_cert).await
+                                }
                                 Ok(Err(e)) => {
                                     self.listen_stats.add_failed();
                                     debug!(
@@ -444,7 +458,8 @@ impl Server for HttpRProxyServer {
             return;
         }
 
-        self.spawn_stream_task(stream, cc_info).await;
+        let client_cert = crate::auth::rustls_peer_cert(stream.get_ref().1);
+        self.spawn_stream_task(stream, cc_info, client_cert).await;
     }
 
     async fn run_openssl_task(&self, stream: SslStream<TcpStream>, cc_info: ClientConnectionInfo) {
@@ -454,6 +469,7 @@ impl Server for HttpRProxyServer {
             return;
         }
 
-        self.spawn_stream_task(stream, cc_info).await;
+        let client_cert = stream.ssl().peer_certificate();
+        self.spawn_stream_task(stream, cc_info, client_cert).await;
     }
 }
@@ -17,6 +17,7 @@
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
+use openssl::x509::X509;
 use slog::Logger;
 
 use g3_daemon::server::ClientConnectionInfo;
@@ -32,6 +33,7 @@ pub(crate) struct CommonTaskContext {
     pub(crate) server_quit_policy: Arc<ServerQuitPolicy>,
     pub(crate) escaper: ArcEscaper,
     pub(crate) cc_info: ClientConnectionInfo,
+    pub(crate) client_cert: Option<X509>,
     pub(crate) task_logger: Logger,
 }
 
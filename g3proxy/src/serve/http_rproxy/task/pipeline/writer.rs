@@ -123,41 +123,71 @@ where
         }
     }
 
-    fn do_auth(
+    async fn do_auth(
         &mut self,
         req: &HttpRProxyRequest<CDR>,
     ) -> Result<Option<UserContext>, UserAuthError> {
         if let Some(user_group) = &self.user_group {
-            let mut user_ctx = match &req.inner.auth_info {
-                HttpAuth::None => {
-                    if let Some((user, user_type)) = user_group.get_anonymous_user() {
-                        UserContext::new(
-                            None,
-                            user,
-                            user_type,
+            let cert_user_ctx = match &self.ctx.client_cert {
+                Some(cert) => {
+                    user_group
+                        .check_client_cert(
+                            cert,
+                            &req.inner.auth_info,
                             self.ctx.server_config.name(),
                             self.ctx.server_stats.share_extra_tags(),
                         )
-                    } else {
-                        return Err(UserAuthError::NoUserSupplied);
-                    }
+                        .await?
                 }
-                HttpAuth::Basic(HttpBasicAuth {
-                    username, password, ..
-                }) => match user_group.get_user(username.as_original()) {
-                    Some((user, user_type)) => {
+                None => None,
+            };
+            let mut user_ctx = if let Some(user_ctx) = cert_user_ctx {
+                user_ctx
+            } else {
+                match &req.inner.auth_info {
+                    HttpAuth::None => {
+                        if let Some((user, user_type)) = user_group.get_anonymous_user() {
+                            UserContext::new(
+                                None,
+                                user,
+                                user_type,
+                                self.ctx.server_config.name(),
+                                self.ctx.server_stats.share_extra_tags(),
+                            )
+                        } else {
+                            return Err(UserAuthError::NoUserSupplied);
+                        }
+                    }
+                    HttpAuth::Basic(HttpBasicAuth {
+                        username, password, ..
+                    }) => match user_group.get_user(username.as_original()) {
+                        Some((user, user_type)) => {
+                            let user_ctx = UserContext::new(
+                                Some(username.as_original().to_string()),
+                                user,
+                                user_type,
+                                self.ctx.server_config.name(),
+                                self.ctx.server_stats.share_extra_tags(),
+                            );
+                            user_ctx.check_password(password.as_original()).await?;
+                            user_ctx
+                        }
+                        None => return Err(UserAuthError::NoSuchUser),
+                    },
+                    HttpAuth::Bearer(bearer) => {
+                        let (username, user, user_type) =
+                            user_group.get_bearer_user(bearer.token())?;
                         let user_ctx = UserContext::new(
-                            Some(username.as_original().to_string()),
+                            Some(username),
                             user,
                             user_type,
                             self.ctx.server_config.name(),
                             self.ctx.server_stats.share_extra_tags(),
                         );
-                        user_ctx.check_password(password.as_original())?;
+                        user_ctx.check_available()?;
                         user_ctx
                     }
-                    None => return Err(UserAuthError::NoSuchUser),
-                },
+                }
             };
 
             user_ctx.check_in_site(
@@ -201,7 +231,7 @@ where
         loop {
             let res = match self.task_queue.recv().await {
                 Some(Ok(req)) => {
-                    let res = match self.do_auth(&req) {
+                    let res = match self.do_auth(&req).await {
                         Ok(user_ctx) => {
                             self.req_count.consequent_auth_failed = 0;
 
@@ -41,6 +41,7 @@ pub(crate) struct SocksProxyServerStats {
     pub(crate) task_tcp_connect: ServerPerTaskStats,
     pub(crate) task_udp_associate: ServerPerTaskStats,
     pub(crate) task_udp_connect: ServerPerTaskStats,
+    pub(crate) task_tcp_bind: ServerPerTaskStats,
 
     pub(crate) io_tcp: TcpIoStats,
     pub(crate) io_udp: UdpIoStats,
@@ -58,6 +59,7 @@ impl SocksProxyServerStats {
             task_tcp_connect: Default::default(),
             task_udp_associate: Default::default(),
             task_udp_connect: Default::default(),
+            task_tcp_bind: Default::default(),
             io_tcp: TcpIoStats::default(),
             io_udp: UdpIoStats::default(),
         }
@@ -113,12 +115,14 @@ impl ServerStats for SocksProxyServerStats {
         self.task_tcp_connect.get_task_total()
             + self.task_udp_connect.get_task_total()
             + self.task_udp_associate.get_task_total()
+            + self.task_tcp_bind.get_task_total()
     }
 
     fn get_alive_count(&self) -> i32 {
         self.task_tcp_connect.get_alive_count()
             + self.task_udp_connect.get_alive_count()
             + self.task_udp_associate.get_alive_count()
+            + self.task_tcp_bind.get_alive_count()
     }
 
     #[inline]
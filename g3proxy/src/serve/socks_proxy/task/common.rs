@@ -18,12 +18,12 @@ use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 use slog::Logger;
-use tokio::net::UdpSocket;
+use tokio::net::{TcpListener, UdpSocket};
 
 use g3_daemon::server::ClientConnectionInfo;
 use g3_types::acl::{AclAction, AclNetworkRule};
 use g3_types::acl_set::AclDstHostRuleSet;
-use g3_types::net::UpstreamAddr;
+use g3_types::net::{Host, UpstreamAddr};
 
 use super::{SocksProxyServerConfig, SocksProxyServerStats};
 use crate::audit::AuditHandle;
@@ -169,4 +169,47 @@ impl CommonTaskContext {
         })?;
         Ok((listen_addr, socket))
     }
+
+    pub(super) fn select_tcp_bind_ip(&self, upstream: &UpstreamAddr) -> IpAddr {
+        let ref_ip = match upstream.host() {
+            Host::Ip(ip) if !ip.is_unspecified() => *ip,
+            _ => self.server_ip(),
+        };
+        let bind_ip = match ref_ip {
+            IpAddr::V4(_) => fastrand::choice(&self.server_config.tcp_bind4).copied(),
+            IpAddr::V6(_) => fastrand::choice(&self.server_config.tcp_bind6).copied(),
+        };
+        // the remote peer should be able to reach the server ip if it's in the same family
+        bind_ip.unwrap_or_else(|| self.server_ip())
+    }
+
+    pub(super) fn setup_tcp_bind_listen(
+        &self,
+        upstream: &UpstreamAddr,
+    ) -> ServerTaskResult<(SocketAddr, TcpListener)> {
+        let tcp_bind_ip = self.select_tcp_bind_ip(upstream);
+
+        let (listener, listen_addr) = if let Some(port_range) =
+            self.server_config.tcp_bind_port_range
+        {
+            g3_socket::tcp::new_std_in_range_bind_listen(tcp_bind_ip, port_range).map_err(|_| {
+                ServerTaskError::InternalServerError(
+                    "setup tcp listen socket with ranged port failed",
+                )
+            })?
+        } else {
+            g3_socket::tcp::new_std_bind_listen(tcp_bind_ip).map_err(|_| {
+                ServerTaskError::InternalServerError(
+                    "setup tcp listen socket with random port failed",
+                )
+            })?
+        };
+
+        let listener = TcpListener::from_std(listener).map_err(|_| {
+            ServerTaskError::InternalServerError(
+                "failed to convert std tcp listener to tokio tcp listener",
+            )
+        })?;
+        Ok((listen_addr, listener))
+    }
 }
@@ -24,6 +24,7 @@ use tokio::time::Instant;
 use g3_io_ext::{LimitedReader, LimitedWriter};
 use g3_socks::{v4a, v5, SocksAuthMethod, SocksCommand, SocksVersion};
 
+use super::tcp_bind::SocksProxyTcpBindTask;
 use super::tcp_connect::SocksProxyTcpConnectTask;
 use super::udp_associate::SocksProxyUdpAssociateTask;
 use super::udp_connect::SocksProxyUdpConnectTask;
@@ -148,6 +149,16 @@ impl SocksProxyNegotiationTask {
                 Ok(())
             }
             SocksCommand::TcpBind => {
+                if self.ctx.server_config.enable_tcp_bind {
+                    let task = SocksProxyTcpBindTask::new(
+                        SocksVersion::V4a,
+                        self.ctx,
+                        task_notes,
+                        req.upstream,
+                    );
+                    task.into_running(clt_r.into_inner(), clt_w);
+                    return Ok(());
+                }
                 let _ = v4a::SocksV4Reply::RequestRejectedOrFailed
                     .send(&mut clt_w)
                     .await;
@@ -222,7 +233,7 @@ impl SocksProxyNegotiationTask {
                             self.ctx.server_config.name(),
                             self.ctx.server_stats.share_extra_tags(),
                         );
-                        match user_ctx.check_password(password.as_original()) {
+                        match user_ctx.check_password(password.as_original()).await {
                             Ok(_) => {
                                 user_ctx.req_stats().conn_total.add_socks();
                                 v5::auth::send_user_auth_success(&mut clt_w)
@@ -303,6 +314,16 @@ impl SocksProxyNegotiationTask {
                 }
             }
             SocksCommand::TcpBind => {
+                if self.ctx.server_config.enable_tcp_bind {
+                    let task = SocksProxyTcpBindTask::new(
+                        SocksVersion::V5,
+                        self.ctx,
+                        task_notes,
+                        req.upstream,
+                    );
+                    task.into_running(clt_r.into_inner(), clt_w);
+                    return Ok(());
+                }
                 let _ = v5::Socks5Reply::CommandNotSupported.send(&mut clt_w).await;
                 Err(ServerTaskError::UnimplementedProtocol)
             }
@@ -0,0 +1,455 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use log::debug;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+
+use g3_daemon::stat::remote::TcpConnectionTaskRemoteStatsWrapper;
+use g3_daemon::stat::task::TcpStreamTaskStats;
+use g3_io_ext::{LimitedReader, LimitedWriter};
+use g3_socks::{v4a, v5, SocksVersion};
+use g3_types::acl::AclAction;
+use g3_types::net::{ConnectError, Host, ProxyRequestType, UpstreamAddr};
+
+use super::{CommonTaskContext, TcpBindTaskCltWrapperStats};
+use crate::config::server::ServerConfig;
+use crate::log::task::tcp_bind::TaskLogForTcpBind;
+use crate::serve::{
+    ServerStats, ServerTaskError, ServerTaskForbiddenError, ServerTaskNotes, ServerTaskResult,
+    ServerTaskStage,
+};
+
+/// socks BIND request, the remote peer will connect in to the listen address we replied
+pub(crate) struct SocksProxyTcpBindTask {
+    socks_version: SocksVersion,
+    ctx: CommonTaskContext,
+    task_notes: ServerTaskNotes,
+    upstream: UpstreamAddr,
+    task_stats: Arc<TcpStreamTaskStats>,
+    listen_addr: Option<SocketAddr>,
+    peer_addr: Option<SocketAddr>,
+}
+
+impl SocksProxyTcpBindTask {
+    pub(crate) fn new(
+        socks_version: SocksVersion,
+        ctx: CommonTaskContext,
+        mut task_notes: ServerTaskNotes,
+        upstream: UpstreamAddr,
+    ) -> Self {
+        if let Some(user_ctx) = task_notes.user_ctx_mut() {
+            user_ctx.check_in_site(
+                ctx.server_config.name(),
+                ctx.server_stats.share_extra_tags(),
+                &upstream,
+            );
+            if let Some(site_req_stats) = user_ctx.site_req_stats() {
+                site_req_stats.conn_total.add_socks();
+            }
+        }
+        SocksProxyTcpBindTask {
+            socks_version,
+            ctx,
+            task_notes,
+            upstream,
+            task_stats: Arc::new(TcpStreamTaskStats::default()),
+            listen_addr: None,
+            peer_addr: None,
+        }
+    }
+
+    fn get_log_context(&self) -> TaskLogForTcpBind {
+        TaskLogForTcpBind {
+            task_notes: &self.task_notes,
+            upstream: &self.upstream,
+            bind_listen_addr: self.listen_addr,
+            bind_peer_addr: self.peer_addr,
+            total_time: self.task_notes.time_elapsed(),
+            client_rd_bytes: self.task_stats.clt.read.get_bytes(),
+            client_wr_bytes: self.task_stats.clt.write.get_bytes(),
+            remote_rd_bytes: self.task_stats.ups.read.get_bytes(),
+            remote_wr_bytes: self.task_stats.ups.write.get_bytes(),
+        }
+    }
+
+    pub(crate) fn into_running<R, W>(mut self, clt_r: LimitedReader<R>, clt_w: LimitedWriter<W>)
+    where
+        R: AsyncRead + Send + Sync + Unpin + 'static,
+        W: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        tokio::spawn(async move {
+            self.pre_start();
+            match self.run(clt_r, clt_w).await {
+                Ok(_) => self
+                    .get_log_context()
+                    .log(&self.ctx.task_logger, &ServerTaskError::Finished),
+                Err(e) => self.get_log_context().log(&self.ctx.task_logger, &e),
+            }
+            self.pre_stop();
+        });
+    }
+
+    fn pre_start(&self) {
+        debug!(
+            "Socks/TcpBind: new client from {} to {} server {}",
+            self.ctx.client_addr(),
+            self.ctx.server_config.server_type(),
+            self.ctx.server_config.name(),
+        );
+        self.ctx.server_stats.task_tcp_bind.add_task();
+        self.ctx.server_stats.task_tcp_bind.inc_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| {
+                s.req_total.add_socks_tcp_bind();
+                s.req_alive.add_socks_tcp_bind();
+            });
+        }
+    }
+
+    fn pre_stop(&mut self) {
+        self.ctx.server_stats.task_tcp_bind.dec_alive_task();
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| s.req_alive.del_socks_tcp_bind());
+
+            if let Some(user_req_alive_permit) = self.task_notes.user_req_alive_permit.take() {
+                drop(user_req_alive_permit);
+            }
+        }
+    }
+
+    async fn reply_forbidden<W>(&self, clt_w: &mut W)
+    where
+        W: AsyncWrite + Unpin,
+    {
+        match self.socks_version {
+            SocksVersion::V4a => {
+                let _ = v4a::SocksV4Reply::RequestRejectedOrFailed.send(clt_w).await;
+            }
+            SocksVersion::V5 => {
+                let _ = v5::Socks5Reply::ForbiddenByRule.send(clt_w).await;
+            }
+            SocksVersion::V6 => {} // TODO socks v6
+        }
+    }
+
+    async fn reply_failed<W>(&self, clt_w: &mut W, v5_reply: v5::Socks5Reply)
+    where
+        W: AsyncWrite + Unpin,
+    {
+        match self.socks_version {
+            SocksVersion::V4a => {
+                let _ = v4a::SocksV4Reply::RequestRejectedOrFailed.send(clt_w).await;
+            }
+            SocksVersion::V5 => {
+                let _ = v5_reply.send(clt_w).await;
+            }
+            SocksVersion::V6 => {} // TODO socks v6
+        }
+    }
+
+    async fn reply_succeeded<W>(&self, clt_w: &mut W, addr: SocketAddr) -> ServerTaskResult<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        match self.socks_version {
+            SocksVersion::V4a => v4a::SocksV4Reply::RequestGranted(addr)
+                .send(clt_w)
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed),
+            SocksVersion::V5 => v5::Socks5Reply::Succeeded(addr)
+                .send(clt_w)
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed),
+            SocksVersion::V6 => Err(ServerTaskError::UnimplementedProtocol),
+        }
+    }
+
+    async fn handle_user_acl_action<W>(
+        &self,
+        action: AclAction,
+        clt_w: &mut W,
+        forbidden_error: ServerTaskForbiddenError,
+    ) -> ServerTaskResult<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        if action.forbid_early() {
+            self.reply_forbidden(clt_w).await;
+            Err(ServerTaskError::ForbiddenByRule(forbidden_error))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// apply the ingress network filter and the dst acl rules to the remote peer
+    fn check_peer(&self, peer_addr: SocketAddr) -> ServerTaskResult<()> {
+        if let Some(filter) = &self.ctx.ingress_net_filter {
+            let (_, action) = filter.check(peer_addr.ip());
+            if action.forbid_early() {
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::SrcBlocked,
+                ));
+            }
+        }
+
+        let peer = UpstreamAddr::from(peer_addr);
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            let action = user_ctx.check_upstream(&peer);
+            if action.forbid_early() {
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::DestDenied,
+                ));
+            }
+        }
+
+        // server level dst host/port acl rules
+        let action = self.ctx.check_upstream(&peer);
+        if action.forbid_early() {
+            self.ctx.server_stats.forbidden.add_dest_denied();
+            if let Some(user_ctx) = self.task_notes.user_ctx() {
+                // also add to user level forbidden stats
+                user_ctx.add_dest_denied();
+            }
+
+            return Err(ServerTaskError::ForbiddenByRule(
+                ServerTaskForbiddenError::DestDenied,
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn accept_peer(
+        &self,
+        listener: &TcpListener,
+    ) -> ServerTaskResult<(TcpStream, SocketAddr)> {
+        let expected_ip = match self.upstream.host() {
+            Host::Ip(ip) if !ip.is_unspecified() => Some(*ip),
+            _ => None,
+        };
+
+        loop {
+            let (stream, peer_addr) = listener
+                .accept()
+                .await
+                .map_err(|e| ServerTaskError::UpstreamNotConnected(ConnectError::from(e)))?;
+            if let Some(ip) = expected_ip {
+                // only the expected peer is allowed, see RFC 1928 Section 4
+                if peer_addr.ip() != ip {
+                    continue;
+                }
+            }
+            return Ok((stream, peer_addr));
+        }
+    }
+
+    async fn run<R, W>(
+        &mut self,
+        mut clt_r: LimitedReader<R>,
+        mut clt_w: LimitedWriter<W>,
+    ) -> ServerTaskResult<()>
+    where
+        R: AsyncRead + Send + Sync + Unpin + 'static,
+        W: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let mut tcp_client_misc_opts = self.ctx.server_config.tcp_misc_opts;
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            let user_ctx = user_ctx.clone();
+
+            let action = user_ctx.check_client_addr(self.task_notes.client_addr());
+            self.handle_user_acl_action(action, &mut clt_w, ServerTaskForbiddenError::SrcBlocked)
+                .await?;
+
+            if user_ctx.check_rate_limit().is_err() {
+                self.reply_forbidden(&mut clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::RateLimited,
+                ));
+            }
+
+            if user_ctx.check_quota().is_err() {
+                self.reply_forbidden(&mut clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
+            match user_ctx.acquire_request_semaphore() {
+                Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
+                Err(_) => {
+                    self.reply_forbidden(&mut clt_w).await;
+                    return Err(ServerTaskError::ForbiddenByRule(
+                        ServerTaskForbiddenError::FullyLoaded,
+                    ));
+                }
+            }
+
+            let action = user_ctx.check_proxy_request(ProxyRequestType::SocksTcpBind);
+            self.handle_user_acl_action(action, &mut clt_w, ServerTaskForbiddenError::ProtoBanned)
+                .await?;
+
+            tcp_client_misc_opts = user_ctx
+                .user_config()
+                .tcp_client_misc_opts(&tcp_client_misc_opts);
+        }
+
+        // set client side socket options
+        self.ctx
+            .cc_info
+            .tcp_sock_set_raw_opts(&tcp_client_misc_opts, true)
+            .map_err(|_| {
+                ServerTaskError::InternalServerError("failed to set client socket options")
+            })?;
+
+        self.task_notes.stage = ServerTaskStage::Preparing;
+        let listener = match self.ctx.setup_tcp_bind_listen(&self.upstream) {
+            Ok((listen_addr, listener)) => {
+                self.listen_addr = Some(listen_addr);
+                listener
+            }
+            Err(e) => {
+                self.reply_failed(&mut clt_w, v5::Socks5Reply::GeneralServerFailure)
+                    .await;
+                return Err(e);
+            }
+        };
+
+        self.task_notes.stage = ServerTaskStage::Replying;
+        // the first reply, tell the client where the remote peer should connect to
+        let listen_addr = self.listen_addr.unwrap();
+        self.reply_succeeded(&mut clt_w, listen_addr).await?;
+
+        self.task_notes.stage = ServerTaskStage::Connecting;
+        let accept_timeout = self.ctx.server_config.timeout.tcp_bind_accept;
+        let mut buf = [0u8; 1];
+        let r = tokio::select! {
+            biased;
+
+            r = clt_r.read(&mut buf) => {
+                match r {
+                    Ok(0) => Err(ServerTaskError::ClosedEarlyByClient),
+                    Ok(_) => Err(ServerTaskError::InvalidClientProtocol(
+                        "unexpected data received before the second reply",
+                    )),
+                    Err(e) => Err(ServerTaskError::ClientTcpReadFailed(e)),
+                }
+            }
+            r = tokio::time::timeout(accept_timeout, self.accept_peer(&listener)) => {
+                match r {
+                    Ok(Ok(v)) => Ok(v),
+                    Ok(Err(e)) => Err(e),
+                    Err(_) => Err(ServerTaskError::UpstreamNotConnected(ConnectError::TimedOut)),
+                }
+            }
+        };
+        drop(listener);
+        let (peer_stream, peer_addr) = match r {
+            Ok(v) => v,
+            Err(ServerTaskError::UpstreamNotConnected(e)) => {
+                let reply = match e {
+                    ConnectError::TimedOut => v5::Socks5Reply::ConnectionTimedOut,
+                    _ => v5::Socks5Reply::GeneralServerFailure,
+                };
+                self.reply_failed(&mut clt_w, reply).await;
+                return Err(ServerTaskError::UpstreamNotConnected(e));
+            }
+            Err(e) => return Err(e),
+        };
+        self.peer_addr = Some(peer_addr);
+        if let Err(e) = self.check_peer(peer_addr) {
+            self.reply_forbidden(&mut clt_w).await;
+            return Err(e);
+        }
+        self.task_notes.stage = ServerTaskStage::Connected;
+
+        // the second reply, tell the client the address of the remote peer
+        self.reply_succeeded(&mut clt_w, peer_addr).await?;
+
+        self.task_notes.mark_relaying();
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            user_ctx.foreach_req_stats(|s| s.req_ready.add_socks_tcp_bind());
+        }
+        self.relay(clt_r, clt_w, peer_stream).await
+    }
+
+    async fn relay<CR, CW>(
+        &mut self,
+        mut clt_r: LimitedReader<CR>,
+        mut clt_w: LimitedWriter<CW>,
+        peer_stream: TcpStream,
+    ) -> ServerTaskResult<()>
+    where
+        CR: AsyncRead + Send + Sync + Unpin + 'static,
+        CW: AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        self.update_clt(&mut clt_r, &mut clt_w);
+
+        let (peer_r, peer_w) = peer_stream.into_split();
+        let wrapper_stats = TcpConnectionTaskRemoteStatsWrapper::new(self.task_stats.clone() as _);
+        let wrapper_stats = Arc::new(wrapper_stats);
+        let peer_r = LimitedReader::new_unlimited(peer_r, wrapper_stats.clone() as _);
+        let peer_w = LimitedWriter::new_unlimited(peer_w, wrapper_stats as _);
+
+        crate::inspect::stream::transit_transparent(
+            clt_r,
+            clt_w,
+            peer_r,
+            peer_w,
+            &self.ctx.server_config,
+            &self.ctx.server_quit_policy,
+            self.task_notes.user_ctx().map(|ctx| ctx.user()),
+        )
+        .await
+    }
+
+    fn update_clt<CR, CW>(&mut self, clt_r: &mut LimitedReader<CR>, clt_w: &mut LimitedWriter<CW>)
+    where
+        CR: AsyncRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        let mut wrapper_stats =
+            TcpBindTaskCltWrapperStats::new(&self.ctx.server_stats, &self.task_stats);
+
+        if let Some(user_ctx) = self.task_notes.user_ctx() {
+            wrapper_stats.push_user_io_stats(user_ctx.fetch_traffic_stats(
+                self.ctx.server_config.name(),
+                self.ctx.server_stats.share_extra_tags(),
+            ));
+
+            let user_config = user_ctx.user_config();
+            if !user_config
+                .tcp_sock_speed_limit
+                .eq(&self.ctx.server_config.tcp_sock_speed_limit)
+            {
+                let limit_config = user_config
+                    .tcp_sock_speed_limit
+                    .shrink_as_smaller(&self.ctx.server_config.tcp_sock_speed_limit);
+                clt_r.reset_limit(limit_config.shift_millis, limit_config.max_north);
+                clt_w.reset_limit(limit_config.shift_millis, limit_config.max_south);
+            }
+        }
+        let (clt_r_stats, clt_w_stats) = wrapper_stats.split();
+        clt_r.reset_stats(clt_r_stats);
+        clt_w.reset_stats(clt_w_stats);
+    }
+}
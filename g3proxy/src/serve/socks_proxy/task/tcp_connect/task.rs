@@ -235,6 +235,13 @@ impl SocksProxyTcpConnectTask {
                 ));
             }
 
+            if user_ctx.check_quota().is_err() {
+                self.reply_forbidden(&mut clt_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             match user_ctx.acquire_request_semaphore() {
                 Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
                 Err(_) => {
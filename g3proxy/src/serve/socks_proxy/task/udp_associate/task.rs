@@ -201,6 +201,13 @@ impl SocksProxyUdpAssociateTask {
                 ));
             }
 
+            if user_ctx.check_quota().is_err() {
+                self.reply_forbidden(&mut clt_tcp_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             match user_ctx.acquire_request_semaphore() {
                 Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
                 Err(_) => {
@@ -251,6 +251,13 @@ impl SocksProxyUdpConnectTask {
                 ));
             }
 
+            if user_ctx.check_quota().is_err() {
+                self.reply_forbidden(&mut clt_tcp_w).await;
+                return Err(ServerTaskError::ForbiddenByRule(
+                    ServerTaskForbiddenError::QuotaExceeded,
+                ));
+            }
+
             match user_ctx.acquire_request_semaphore() {
                 Ok(permit) => self.task_notes.user_req_alive_permit = Some(permit),
                 Err(_) => {
@@ -39,6 +39,8 @@ const METRIC_NAME_ESCAPER_IO_IN_PACKETS: &str = "escaper.traffic.in.packets";
 const METRIC_NAME_ESCAPER_IO_OUT_BYTES: &str = "escaper.traffic.out.bytes";
 const METRIC_NAME_ESCAPER_IO_OUT_PACKETS: &str = "escaper.traffic.out.packets";
 const METRIC_NAME_ESCAPER_FORBIDDEN_IP_BLOCKED: &str = "escaper.forbidden.ip_blocked";
+const METRIC_NAME_ESCAPER_PEER_HEALTHY: &str = "escaper.peer.healthy";
+const METRIC_NAME_ESCAPER_PEER_UNHEALTHY: &str = "escaper.peer.unhealthy";
 
 const METRIC_NAME_ROUTE_REQUEST_PASSED: &str = "route.request.passed";
 const METRIC_NAME_ROUTE_REQUEST_FAILED: &str = "route.request.failed";
@@ -152,6 +154,23 @@ fn emit_escaper_stats(
         emit_forbidden_stats(client, forbidden_stats, &mut snap.forbidden, &common_tags);
     }
 
+    if let Some(peer_health) = stats.peer_health_snapshot() {
+        client
+            .gauge_with_tags(
+                METRIC_NAME_ESCAPER_PEER_HEALTHY,
+                peer_health.healthy,
+                &common_tags,
+            )
+            .send();
+        client
+            .gauge_with_tags(
+                METRIC_NAME_ESCAPER_PEER_UNHEALTHY,
+                peer_health.unhealthy,
+                &common_tags,
+            )
+            .send();
+    }
+
     if let Some(tcp_io_stats) = stats.tcp_io_snapshot() {
         emit_tcp_io_to_statsd(client, tcp_io_stats, &mut snap.tcp, &common_tags);
     }
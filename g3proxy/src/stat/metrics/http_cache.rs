@@ -0,0 +1,84 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use g3_statsd_client::StatsdClient;
+
+use crate::module::http_cache::{self, HttpCacheSnapshot};
+
+const METRIC_NAME_LOOKUP_HIT: &str = "http_cache.lookup.hit";
+const METRIC_NAME_LOOKUP_MISS: &str = "http_cache.lookup.miss";
+const METRIC_NAME_LOOKUP_REVALIDATED: &str = "http_cache.lookup.revalidated";
+const METRIC_NAME_LOOKUP_EXPIRED: &str = "http_cache.lookup.expired";
+const METRIC_NAME_STORE_TOTAL: &str = "http_cache.store.total";
+const METRIC_NAME_EVICTED: &str = "http_cache.evicted";
+const METRIC_NAME_MEMORY_CAPACITY: &str = "http_cache.memory.capacity";
+const METRIC_NAME_MEMORY_USED: &str = "http_cache.memory.used";
+const METRIC_NAME_MEMORY_ENTRIES: &str = "http_cache.memory.entries";
+const METRIC_NAME_DISK_CAPACITY: &str = "http_cache.disk.capacity";
+const METRIC_NAME_DISK_USED: &str = "http_cache.disk.used";
+const METRIC_NAME_DISK_ENTRIES: &str = "http_cache.disk.entries";
+
+static HTTP_CACHE_SNAPSHOT: Lazy<Mutex<HttpCacheSnapshot>> =
+    Lazy::new(|| Mutex::new(HttpCacheSnapshot::default()));
+
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let Some(cache) = http_cache::get_global_cache() else {
+        return;
+    };
+
+    let stats = cache.stats().snapshot();
+    let mut snap = HTTP_CACHE_SNAPSHOT.lock().unwrap();
+
+    macro_rules! emit_count {
+        ($id:ident, $name:expr) => {
+            let new_value = stats.$id;
+            if new_value != 0 || snap.$id != 0 {
+                let diff_value = new_value.wrapping_sub(snap.$id);
+                client.count($name, diff_value).send();
+                snap.$id = new_value;
+            }
+        };
+    }
+
+    emit_count!(hit, METRIC_NAME_LOOKUP_HIT);
+    emit_count!(miss, METRIC_NAME_LOOKUP_MISS);
+    emit_count!(revalidated, METRIC_NAME_LOOKUP_REVALIDATED);
+    emit_count!(expired, METRIC_NAME_LOOKUP_EXPIRED);
+    emit_count!(stored, METRIC_NAME_STORE_TOTAL);
+    emit_count!(evicted, METRIC_NAME_EVICTED);
+
+    let usage = cache.usage();
+    client
+        .gauge(METRIC_NAME_MEMORY_CAPACITY, usage.memory_capacity)
+        .send();
+    client
+        .gauge(METRIC_NAME_MEMORY_USED, usage.memory_used)
+        .send();
+    client
+        .gauge(METRIC_NAME_MEMORY_ENTRIES, usage.memory_entries)
+        .send();
+    client
+        .gauge(METRIC_NAME_DISK_CAPACITY, usage.disk_capacity)
+        .send();
+    client.gauge(METRIC_NAME_DISK_USED, usage.disk_used).send();
+    client
+        .gauge(METRIC_NAME_DISK_ENTRIES, usage.disk_entries)
+        .send();
+}
@@ -15,6 +15,7 @@
  */
 
 pub(super) mod escaper;
+pub(super) mod http_cache;
 pub(super) mod resolver;
 pub(super) mod server;
 
@@ -55,6 +56,8 @@ enum MetricUserRequestType {
     SocksTcpConnect,
     SocksUdpConnect,
     SocksUdpAssociate,
+    HttpConnectUdp,
+    SocksTcpBind,
 }
 
 impl MetricUserRequestType {
@@ -67,6 +70,8 @@ impl MetricUserRequestType {
             MetricUserRequestType::SocksTcpConnect => "socks_tcp_connect",
             MetricUserRequestType::SocksUdpConnect => "socks_udp_connect",
             MetricUserRequestType::SocksUdpAssociate => "socks_udp_associate",
+            MetricUserRequestType::HttpConnectUdp => "http_connect_udp",
+            MetricUserRequestType::SocksTcpBind => "socks_tcp_bind",
         }
     }
 }
@@ -107,6 +107,38 @@ fn emit_to_statsd(client: &mut StatsdClient, stats: &ResolverStats, snap: &mut R
         ResolveQueryType::Aaaa,
     );
 
+    emit_query_stats_to_statsd(
+        client,
+        &inner_stats.query_srv,
+        &mut snap.query_srv,
+        &common_tags,
+        ResolveQueryType::Srv,
+    );
+
+    emit_query_stats_to_statsd(
+        client,
+        &inner_stats.query_https,
+        &mut snap.query_https,
+        &common_tags,
+        ResolveQueryType::Https,
+    );
+
+    emit_query_stats_to_statsd(
+        client,
+        &inner_stats.query_svcb,
+        &mut snap.query_svcb,
+        &common_tags,
+        ResolveQueryType::Svcb,
+    );
+
+    emit_query_stats_to_statsd(
+        client,
+        &inner_stats.query_txt,
+        &mut snap.query_txt,
+        &common_tags,
+        ResolveQueryType::Txt,
+    );
+
     emit_memory_stats_to_statsd(
         client,
         &inner_stats.memory_a,
@@ -120,6 +152,18 @@ fn emit_to_statsd(client: &mut StatsdClient, stats: &ResolverStats, snap: &mut R
         &common_tags,
         ResolveQueryType::Aaaa,
     );
+
+    // the caches for the following types are allocated on demand
+    for (memory, rr_type) in [
+        (&inner_stats.memory_srv, ResolveQueryType::Srv),
+        (&inner_stats.memory_https, ResolveQueryType::Https),
+        (&inner_stats.memory_svcb, ResolveQueryType::Svcb),
+        (&inner_stats.memory_txt, ResolveQueryType::Txt),
+    ] {
+        if memory.cap_cache > 0 || memory.cap_doing > 0 {
+            emit_memory_stats_to_statsd(client, memory, &common_tags, rr_type);
+        }
+    }
 }
 
 fn emit_query_stats_to_statsd(
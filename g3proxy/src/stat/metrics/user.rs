@@ -48,12 +48,14 @@ const METRIC_NAME_FORBIDDEN_USER_EXPIRED: &str = "user.forbidden.user_expired";
 const METRIC_NAME_FORBIDDEN_USER_BLOCKED: &str = "user.forbidden.user_blocked";
 const METRIC_NAME_FORBIDDEN_FULLY_LOADED: &str = "user.forbidden.fully_loaded";
 const METRIC_NAME_FORBIDDEN_RATE_LIMITED: &str = "user.forbidden.rate_limited";
+const METRIC_NAME_FORBIDDEN_QUOTA_EXCEEDED: &str = "user.forbidden.quota_exceeded";
 const METRIC_NAME_FORBIDDEN_PROTO_BANNED: &str = "user.forbidden.proto_banned";
 const METRIC_NAME_FORBIDDEN_SRC_BLOCKED: &str = "user.forbidden.src_blocked";
 const METRIC_NAME_FORBIDDEN_DEST_DENIED: &str = "user.forbidden.dest_denied";
 const METRIC_NAME_FORBIDDEN_IP_BLOCKED: &str = "user.forbidden.ip_blocked";
 const METRIC_NAME_FORBIDDEN_LOG_SKIPPED: &str = "user.forbidden.log_skipped";
 const METRIC_NAME_FORBIDDEN_UA_BLOCKED: &str = "user.forbidden.ua_blocked";
+const METRIC_NAME_FORBIDDEN_URL_BLOCKED: &str = "user.forbidden.url_blocked";
 
 pub(super) struct RequestStatsNamesRef<'a> {
     pub(super) connection_total: &'a str,
@@ -296,11 +298,13 @@ fn emit_user_forbidden_stats(
     emit_forbid_stats_u64!(user_blocked, METRIC_NAME_FORBIDDEN_USER_BLOCKED);
     emit_forbid_stats_u64!(fully_loaded, METRIC_NAME_FORBIDDEN_FULLY_LOADED);
     emit_forbid_stats_u64!(rate_limited, METRIC_NAME_FORBIDDEN_RATE_LIMITED);
+    emit_forbid_stats_u64!(quota_exceeded, METRIC_NAME_FORBIDDEN_QUOTA_EXCEEDED);
     emit_forbid_stats_u64!(proto_banned, METRIC_NAME_FORBIDDEN_PROTO_BANNED);
     emit_forbid_stats_u64!(src_blocked, METRIC_NAME_FORBIDDEN_SRC_BLOCKED);
     emit_forbid_stats_u64!(dest_denied, METRIC_NAME_FORBIDDEN_DEST_DENIED);
     emit_forbid_stats_u64!(ip_blocked, METRIC_NAME_FORBIDDEN_IP_BLOCKED);
     emit_forbid_stats_u64!(ua_blocked, METRIC_NAME_FORBIDDEN_UA_BLOCKED);
+    emit_forbid_stats_u64!(url_blocked, METRIC_NAME_FORBIDDEN_URL_BLOCKED);
     emit_forbid_stats_u64!(log_skipped, METRIC_NAME_FORBIDDEN_LOG_SKIPPED);
 }
 
@@ -479,6 +483,8 @@ where
         socks_udp_associate,
         MetricUserRequestType::SocksUdpAssociate
     );
+    emit_field!(http_connect_udp, MetricUserRequestType::HttpConnectUdp);
+    emit_field!(socks_tcp_bind, MetricUserRequestType::SocksTcpBind);
 }
 
 fn find_req_alive_stat<F>(stats: &RequestAliveStats, mut emit: F)
@@ -501,6 +507,11 @@ where
         stats.socks_udp_associate(),
         MetricUserRequestType::SocksUdpAssociate,
     );
+    emit(
+        stats.http_connect_udp(),
+        MetricUserRequestType::HttpConnectUdp,
+    );
+    emit(stats.socks_tcp_bind(), MetricUserRequestType::SocksTcpBind);
 }
 
 fn find_keepalive_req_stat<F>(
@@ -550,6 +561,7 @@ fn find_io_stat<'a, F>(
     emit_tcp_field!(http_connect, MetricUserRequestType::HttpConnect);
     emit_tcp_field!(ftp_over_http, MetricUserRequestType::FtpOverHttp);
     emit_tcp_field!(socks_tcp_connect, MetricUserRequestType::SocksTcpConnect);
+    emit_tcp_field!(socks_tcp_bind, MetricUserRequestType::SocksTcpBind);
 
     macro_rules! emit_udp_field {
         ($field:ident, $request:expr) => {
@@ -568,6 +580,7 @@ fn find_io_stat<'a, F>(
         socks_udp_associate,
         MetricUserRequestType::SocksUdpAssociate
     );
+    emit_udp_field!(http_connect_udp, MetricUserRequestType::HttpConnectUdp);
 }
 
 fn find_tcp_io_stat<'a, F>(
@@ -57,6 +57,7 @@ fn spawn_main_thread(config: &StatsdClientConfig) -> anyhow::Result<JoinHandle<(
             metrics::server::emit_stats(&mut client);
             metrics::escaper::emit_stats(&mut client);
             metrics::resolver::emit_stats(&mut client);
+            metrics::http_cache::emit_stats(&mut client);
             metrics::user::emit_stats(&mut client);
             g3_daemon::log::metrics::emit_stats(&mut client);
 
@@ -25,6 +25,8 @@ pub(crate) struct RequestStats {
     socks_tcp_connect: AtomicU64,
     socks_udp_connect: AtomicU64,
     socks_udp_associate: AtomicU64,
+    http_connect_udp: AtomicU64,
+    socks_tcp_bind: AtomicU64,
 }
 
 #[derive(Default)]
@@ -36,6 +38,8 @@ pub(crate) struct RequestSnapshot {
     pub(crate) socks_tcp_connect: u64,
     pub(crate) socks_udp_connect: u64,
     pub(crate) socks_udp_associate: u64,
+    pub(crate) http_connect_udp: u64,
+    pub(crate) socks_tcp_bind: u64,
 }
 
 impl RequestStats {
@@ -94,6 +98,22 @@ impl RequestStats {
     pub(crate) fn socks_udp_associate(&self) -> u64 {
         self.socks_udp_associate.load(Ordering::Relaxed)
     }
+
+    pub(crate) fn add_http_connect_udp(&self) {
+        self.http_connect_udp.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn http_connect_udp(&self) -> u64 {
+        self.http_connect_udp.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_socks_tcp_bind(&self) {
+        self.socks_tcp_bind.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn socks_tcp_bind(&self) -> u64 {
+        self.socks_tcp_bind.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Default)]
@@ -135,6 +155,8 @@ pub(crate) struct RequestAliveStats {
     socks_tcp_connect: AtomicI32,
     socks_udp_connect: AtomicI32,
     socks_udp_associate: AtomicI32,
+    http_connect_udp: AtomicI32,
+    socks_tcp_bind: AtomicI32,
 }
 
 impl RequestAliveStats {
@@ -221,4 +243,28 @@ impl RequestAliveStats {
     pub(crate) fn socks_udp_associate(&self) -> i32 {
         self.socks_udp_associate.load(Ordering::Relaxed)
     }
+
+    pub(crate) fn add_http_connect_udp(&self) {
+        self.http_connect_udp.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn del_http_connect_udp(&self) {
+        self.http_connect_udp.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn http_connect_udp(&self) -> i32 {
+        self.http_connect_udp.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_socks_tcp_bind(&self) {
+        self.socks_tcp_bind.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn del_socks_tcp_bind(&self) {
+        self.socks_tcp_bind.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn socks_tcp_bind(&self) -> i32 {
+        self.socks_tcp_bind.load(Ordering::Relaxed)
+    }
 }
@@ -25,6 +25,38 @@ pub(crate) struct TrafficStats {
     pub(crate) socks_tcp_connect: TcpIoStats,
     pub(crate) socks_udp_connect: UdpIoStats,
     pub(crate) socks_udp_associate: UdpIoStats,
+    pub(crate) http_connect_udp: UdpIoStats,
+    pub(crate) socks_tcp_bind: TcpIoStats,
+}
+
+impl TrafficStats {
+    /// get the total (in, out) bytes of all protocols
+    pub(crate) fn total_bytes(&self) -> (u64, u64) {
+        let mut in_bytes = 0u64;
+        let mut out_bytes = 0u64;
+        for s in [
+            &self.http_forward,
+            &self.https_forward,
+            &self.http_connect,
+            &self.ftp_over_http,
+            &self.socks_tcp_connect,
+            &self.socks_tcp_bind,
+        ] {
+            let snap = s.snapshot();
+            in_bytes = in_bytes.wrapping_add(snap.in_bytes);
+            out_bytes = out_bytes.wrapping_add(snap.out_bytes);
+        }
+        for s in [
+            &self.socks_udp_connect,
+            &self.socks_udp_associate,
+            &self.http_connect_udp,
+        ] {
+            let snap = s.snapshot();
+            in_bytes = in_bytes.wrapping_add(snap.in_bytes);
+            out_bytes = out_bytes.wrapping_add(snap.out_bytes);
+        }
+        (in_bytes, out_bytes)
+    }
 }
 
 #[derive(Default)]
@@ -36,6 +68,8 @@ pub(crate) struct TrafficSnapshot {
     pub(crate) socks_tcp_connect: TcpIoSnapshot,
     pub(crate) socks_udp_connect: UdpIoSnapshot,
     pub(crate) socks_udp_associate: UdpIoSnapshot,
+    pub(crate) http_connect_udp: UdpIoSnapshot,
+    pub(crate) socks_tcp_bind: TcpIoSnapshot,
 }
 
 #[derive(Default)]
@@ -23,7 +23,7 @@ use futures_util::future::TryFutureExt;
 
 use g3_ctl::{CommandError, CommandResult};
 
-use g3proxy_proto::escaper_capnp::escaper_control;
+use g3proxy_proto::escaper_capnp::{escaper_control, peer_health_result};
 use g3proxy_proto::proc_capnp::proc_control;
 
 use crate::common::parse_operation_result;
@@ -36,6 +36,8 @@ const SUBCOMMAND_PUBLISH: &str = "publish";
 const SUBCOMMAND_PUBLISH_ARG_FILE: &str = "file";
 const SUBCOMMAND_PUBLISH_ARG_DATA: &str = "data";
 
+const SUBCOMMAND_PEER_HEALTH: &str = "peer-health";
+
 pub fn command() -> Command {
     Command::new(COMMAND)
         .arg(Arg::new(COMMAND_ARG_NAME).required(true).num_args(1))
@@ -61,6 +63,9 @@ pub fn command() -> Command {
                         .conflicts_with(SUBCOMMAND_PUBLISH_ARG_FILE),
                 ),
         )
+        .subcommand(
+            Command::new(SUBCOMMAND_PEER_HEALTH).about("Show health status of next proxy peers"),
+        )
 }
 
 async fn publish(client: &escaper_control::Client, args: &ArgMatches) -> CommandResult<()> {
@@ -89,6 +94,41 @@ async fn publish(client: &escaper_control::Client, args: &ArgMatches) -> Command
     parse_operation_result(rsp.get()?.get_result()?)
 }
 
+async fn peer_health(client: &escaper_control::Client) -> CommandResult<()> {
+    let req = client.peer_health_request();
+    let rsp = req.send().promise.await?;
+    match rsp.get()?.get_result()?.which().unwrap() {
+        peer_health_result::Which::Peers(peers) => {
+            for peer in peers?.iter() {
+                let status = if peer.get_healthy() {
+                    "healthy"
+                } else {
+                    "unhealthy"
+                };
+                println!(
+                    "{}\t{}\tweight: {}\tsuccess: {}\tfailure: {}",
+                    peer.get_addr()?.to_str().unwrap_or_default(),
+                    status,
+                    peer.get_weight(),
+                    peer.get_consecutive_success(),
+                    peer.get_consecutive_failure(),
+                );
+                if peer.has_last_error() {
+                    println!(
+                        "\tlast error: {}",
+                        peer.get_last_error()?.to_str().unwrap_or_default()
+                    );
+                }
+            }
+            Ok(())
+        }
+        peer_health_result::Which::Err(err) => {
+            let e = err?;
+            Err(CommandError::api_error(e.get_code(), e.get_reason()?))
+        }
+    }
+}
+
 pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
     let name = args.get_one::<String>(COMMAND_ARG_NAME).unwrap();
 
@@ -99,6 +139,11 @@ pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandRes
                 .and_then(|escaper| async move { publish(&escaper, args).await })
                 .await
         }
+        SUBCOMMAND_PEER_HEALTH => {
+            super::proc::get_escaper(client, name)
+                .and_then(|escaper| async move { peer_health(&escaper).await })
+                .await
+        }
         _ => unreachable!(),
     }
 }
@@ -0,0 +1,114 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+use futures_util::future::TryFutureExt;
+
+use g3_ctl::CommandResult;
+
+use g3proxy_proto::http_cache_capnp::http_cache_control;
+use g3proxy_proto::proc_capnp::proc_control;
+
+use super::common::parse_operation_result;
+
+pub const COMMAND: &str = "http-cache";
+
+const SUBCOMMAND_STATUS: &str = "status";
+const SUBCOMMAND_SET_CAPACITY: &str = "set-capacity";
+const SUBCOMMAND_SET_CAPACITY_ARG_MEMORY: &str = "memory";
+const SUBCOMMAND_SET_CAPACITY_ARG_DISK: &str = "disk";
+const SUBCOMMAND_PURGE: &str = "purge";
+const SUBCOMMAND_PURGE_ARG_URL: &str = "url";
+
+pub fn command() -> Command {
+    Command::new(COMMAND)
+        .subcommand_required(true)
+        .subcommand(Command::new(SUBCOMMAND_STATUS))
+        .subcommand(
+            Command::new(SUBCOMMAND_SET_CAPACITY)
+                .arg(
+                    Arg::new(SUBCOMMAND_SET_CAPACITY_ARG_MEMORY)
+                        .long(SUBCOMMAND_SET_CAPACITY_ARG_MEMORY)
+                        .num_args(1)
+                        .value_parser(value_parser!(u64))
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::new(SUBCOMMAND_SET_CAPACITY_ARG_DISK)
+                        .long(SUBCOMMAND_SET_CAPACITY_ARG_DISK)
+                        .num_args(1)
+                        .value_parser(value_parser!(u64))
+                        .default_value("0"),
+                ),
+        )
+        .subcommand(
+            Command::new(SUBCOMMAND_PURGE).arg(Arg::new(SUBCOMMAND_PURGE_ARG_URL).num_args(1)),
+        )
+}
+
+async fn status(client: &http_cache_control::Client) -> CommandResult<()> {
+    let req = client.status_request();
+    let rsp = req.send().promise.await?;
+    let stats = rsp.get()?.get_stats()?;
+    println!("memory capacity: {}", stats.get_memory_capacity());
+    println!("memory used: {}", stats.get_memory_used());
+    println!("memory entries: {}", stats.get_memory_entries());
+    println!("disk capacity: {}", stats.get_disk_capacity());
+    println!("disk used: {}", stats.get_disk_used());
+    println!("disk entries: {}", stats.get_disk_entries());
+    println!("lookup hit: {}", stats.get_hit());
+    println!("lookup miss: {}", stats.get_miss());
+    println!("lookup revalidated: {}", stats.get_revalidated());
+    println!("lookup expired: {}", stats.get_expired());
+    println!("stored: {}", stats.get_stored());
+    println!("evicted: {}", stats.get_evicted());
+    Ok(())
+}
+
+async fn set_capacity(client: &http_cache_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let mut req = client.set_capacity_request();
+    if let Some(memory) = args.get_one::<u64>(SUBCOMMAND_SET_CAPACITY_ARG_MEMORY) {
+        req.get().set_memory(*memory);
+    }
+    if let Some(disk) = args.get_one::<u64>(SUBCOMMAND_SET_CAPACITY_ARG_DISK) {
+        req.get().set_disk(*disk);
+    }
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
+async fn purge(client: &http_cache_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let mut req = client.purge_request();
+    if let Some(url) = args.get_one::<String>(SUBCOMMAND_PURGE_ARG_URL) {
+        req.get().set_url(url.as_str());
+    }
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
+pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let (subcommand, args) = args.subcommand().unwrap();
+    super::proc::get_http_cache(client)
+        .and_then(|http_cache| async move {
+            match subcommand {
+                SUBCOMMAND_STATUS => status(&http_cache).await,
+                SUBCOMMAND_SET_CAPACITY => set_capacity(&http_cache, args).await,
+                SUBCOMMAND_PURGE => purge(&http_cache, args).await,
+                _ => unreachable!(),
+            }
+        })
+        .await
+}
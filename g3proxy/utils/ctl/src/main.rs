@@ -26,6 +26,7 @@ mod common;
 mod proc;
 
 mod escaper;
+mod http_cache;
 mod resolver;
 mod server;
 mod user_group;
@@ -47,6 +48,7 @@ fn build_cli_args() -> Command {
         .subcommand(resolver::command())
         .subcommand(escaper::command())
         .subcommand(server::command())
+        .subcommand(http_cache::command())
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -98,6 +100,7 @@ async fn main() -> anyhow::Result<()> {
                 resolver::COMMAND => resolver::run(&proc_control, args).await,
                 escaper::COMMAND => escaper::run(&proc_control, args).await,
                 server::COMMAND => server::run(&proc_control, args).await,
+                http_cache::COMMAND => http_cache::run(&proc_control, args).await,
                 _ => Err(CommandError::Cli(anyhow!(
                     "unsupported command {subcommand}"
                 ))),
@@ -19,6 +19,7 @@ use clap::ArgMatches;
 use g3_ctl::CommandResult;
 
 use g3proxy_proto::escaper_capnp::escaper_control;
+use g3proxy_proto::http_cache_capnp::http_cache_control;
 use g3proxy_proto::proc_capnp::proc_control;
 use g3proxy_proto::resolver_capnp::resolver_control;
 use g3proxy_proto::server_capnp::server_control;
@@ -260,6 +261,14 @@ pub(crate) async fn get_escaper(
     parse_fetch_result(rsp.get()?.get_escaper()?)
 }
 
+pub(crate) async fn get_http_cache(
+    client: &proc_control::Client,
+) -> CommandResult<http_cache_control::Client> {
+    let req = client.get_http_cache_request();
+    let rsp = req.send().promise.await?;
+    parse_fetch_result(rsp.get()?.get_http_cache()?)
+}
+
 pub(crate) async fn get_server(
     client: &proc_control::Client,
     name: &str,
@@ -25,7 +25,7 @@ use g3_types::resolve::QueryStrategy as ResolverQueryStrategy;
 
 use g3proxy_proto::proc_capnp::proc_control;
 use g3proxy_proto::resolver_capnp::{
-    query_result, resolver_control, QueryStrategy as RpcQueryStrategy,
+    query_result, resolver_control, QueryStrategy as RpcQueryStrategy, QueryType as RpcQueryType,
 };
 
 pub const COMMAND: &str = "resolver";
@@ -36,6 +36,7 @@ const SUBCOMMAND_QUERY: &str = "query";
 const SUBCOMMAND_QUERY_ARG_DOMAIN: &str = "domain";
 const SUBCOMMAND_QUERY_ARG_STRATEGY: &str = "strategy";
 const SUBCOMMAND_QUERY_ARG_RESOLUTION_DELAY: &str = "resolution-delay";
+const SUBCOMMAND_QUERY_ARG_TYPE: &str = "type";
 
 pub fn command() -> Command {
     Command::new(COMMAND)
@@ -57,6 +58,14 @@ pub fn command() -> Command {
                         .num_args(1)
                         .value_parser(value_parser!(u16))
                         .default_value("50"),
+                )
+                .arg(
+                    Arg::new(SUBCOMMAND_QUERY_ARG_TYPE)
+                        .short('t')
+                        .long(SUBCOMMAND_QUERY_ARG_TYPE)
+                        .num_args(1)
+                        .value_parser(["ip", "srv", "https", "svcb", "txt"])
+                        .default_value("ip"),
                 ),
         )
 }
@@ -82,6 +91,17 @@ async fn query_domain(client: &resolver_control::Client, args: &ArgMatches) -> C
         req.get().set_strategy(qs);
     }
 
+    if let Some(qt) = args.get_one::<String>(SUBCOMMAND_QUERY_ARG_TYPE) {
+        let qt = match qt.as_str() {
+            "srv" => RpcQueryType::Srv,
+            "https" => RpcQueryType::Https,
+            "svcb" => RpcQueryType::Svcb,
+            "txt" => RpcQueryType::Txt,
+            _ => RpcQueryType::Ip,
+        };
+        req.get().set_query_type(qt);
+    }
+
     let rsp = req.send().promise.await?;
     let result = rsp.get()?.get_result()?;
     match result.which().unwrap() {
@@ -91,6 +111,11 @@ async fn query_domain(client: &resolver_control::Client, args: &ArgMatches) -> C
             g3_ctl::print_text_list("ip", ips)
         }
         query_result::Which::Err(reason) => g3_ctl::print_text("err", reason?),
+        query_result::Which::Record(records) => {
+            let records = records?;
+            println!("query results:");
+            g3_ctl::print_text_list("record", records)
+        }
     }
 }
 
@@ -31,10 +31,12 @@ pub const COMMAND: &str = "user-group";
 
 const COMMAND_ARG_NAME: &str = "name";
 const COMMAND_ARG_FILE: &str = "file";
+const COMMAND_ARG_USER: &str = "user";
 
 const SUBCOMMAND_LIST_STATIC_USER: &str = "list-static-user";
 const SUBCOMMAND_LIST_DYNAMIC_USER: &str = "list-dynamic-user";
 const SUBCOMMAND_PUBLISH_USER: &str = "publish-user";
+const SUBCOMMAND_QUERY_QUOTA: &str = "query-quota";
 
 pub fn command() -> Command {
     Command::new(COMMAND)
@@ -54,6 +56,11 @@ pub fn command() -> Command {
                         .value_hint(ValueHint::FilePath),
                 ),
         )
+        .subcommand(
+            Command::new(SUBCOMMAND_QUERY_QUOTA)
+                .about("Query the used and remaining quota of a user")
+                .arg(Arg::new(COMMAND_ARG_USER).required(true).num_args(1)),
+        )
 }
 
 pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
@@ -66,6 +73,7 @@ pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandRes
         SUBCOMMAND_LIST_STATIC_USER => list_static_user(&user_group).await,
         SUBCOMMAND_LIST_DYNAMIC_USER => list_dynamic_user(&user_group).await,
         SUBCOMMAND_PUBLISH_USER => publish_dynamic_user(&user_group, args).await,
+        SUBCOMMAND_QUERY_QUOTA => query_user_quota(&user_group, args).await,
         _ => unreachable!(),
     }
 }
@@ -108,3 +116,15 @@ async fn publish_dynamic_user(
     let rsp = req.send().promise.await?;
     parse_operation_result(rsp.get()?.get_result()?)
 }
+
+async fn query_user_quota(
+    client: &user_group_control::Client,
+    args: &ArgMatches,
+) -> CommandResult<()> {
+    let user = args.get_one::<String>(COMMAND_ARG_USER).unwrap();
+
+    let mut req = client.query_user_quota_request();
+    req.get().set_user(user.as_str());
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
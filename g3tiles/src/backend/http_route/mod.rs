@@ -0,0 +1,302 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context};
+use arc_swap::ArcSwapOption;
+use async_trait::async_trait;
+use futures_util::future::{AbortHandle, Abortable};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+use g3_types::collection::{SelectiveVec, SelectiveVecBuilder, WeightedValue};
+use g3_types::metrics::MetricsName;
+use g3_types::net::ConnectError;
+
+use super::{ArcBackend, Backend, BackendExt};
+use crate::config::backend::http_route::{
+    HttpForwardConfig, HttpRouteBackendConfig, HttpRouteConfig,
+};
+use crate::config::backend::{AnyBackendConfig, BackendConfig};
+use crate::module::http::{
+    HttpConnectError, HttpConnectResult, HttpRouteRequest, HttpUpstreamConnection,
+};
+use crate::module::stream::{
+    StreamBackendDurationRecorder, StreamBackendDurationStats, StreamBackendStats,
+    StreamConnectError,
+};
+use crate::serve::ServerTaskNotes;
+
+mod pool;
+use pool::HttpConnectionPool;
+
+struct HttpRoute {
+    config: HttpRouteConfig,
+    name: Arc<str>,
+    peers: Arc<ArcSwapOption<SelectiveVec<WeightedValue<SocketAddr>>>>,
+}
+
+impl HttpRoute {
+    fn new(config: &HttpRouteConfig) -> Self {
+        HttpRoute {
+            config: config.clone(),
+            name: Arc::from(config.name.as_str()),
+            peers: Arc::new(ArcSwapOption::new(None)),
+        }
+    }
+
+    fn select_peer<B>(&self, backend: &B, task_notes: &ServerTaskNotes) -> Option<SocketAddr>
+    where
+        B: BackendExt,
+    {
+        let guard = self.peers.load();
+        let peers = (*guard).as_ref()?;
+
+        let v = backend.select_consistent(peers.as_ref(), self.config.peer_pick_policy, task_notes);
+        Some(*v.inner())
+    }
+}
+
+pub(crate) struct HttpRouteBackend {
+    config: Arc<HttpRouteBackendConfig>,
+    stats: Arc<StreamBackendStats>,
+    duration_recorder: Arc<StreamBackendDurationRecorder>,
+    duration_stats: Arc<StreamBackendDurationStats>,
+    routes: Vec<HttpRoute>,
+    pool: Arc<HttpConnectionPool>,
+    discover_handles: Mutex<Vec<AbortHandle>>,
+}
+
+impl HttpRouteBackend {
+    fn new_obj(
+        config: Arc<HttpRouteBackendConfig>,
+        stats: Arc<StreamBackendStats>,
+        duration_recorder: Arc<StreamBackendDurationRecorder>,
+        duration_stats: Arc<StreamBackendDurationStats>,
+        pool: Arc<HttpConnectionPool>,
+    ) -> anyhow::Result<ArcBackend> {
+        // always update extra metrics tags
+        stats.set_extra_tags(config.extra_metrics_tags.clone());
+        duration_stats.set_extra_tags(config.extra_metrics_tags.clone());
+
+        let routes = config.routes.iter().map(HttpRoute::new).collect();
+
+        let backend = Arc::new(HttpRouteBackend {
+            config,
+            stats,
+            duration_recorder,
+            duration_stats,
+            routes,
+            pool,
+            discover_handles: Mutex::new(Vec::new()),
+        });
+        backend.update_discover()?;
+
+        Ok(backend)
+    }
+
+    pub(super) fn prepare_initial(config: HttpRouteBackendConfig) -> anyhow::Result<ArcBackend> {
+        let stats = Arc::new(StreamBackendStats::new(config.name()));
+        let (duration_recorder, duration_stats) =
+            StreamBackendDurationRecorder::new(config.name(), &config.duration_stats);
+        let duration_stats = Arc::new(duration_stats);
+
+        crate::stat::metrics::backend::stream::push_stream_stats(stats.clone());
+        crate::stat::metrics::backend::stream::push_stream_duration_stats(duration_stats.clone());
+
+        HttpRouteBackend::new_obj(
+            Arc::new(config),
+            stats,
+            Arc::new(duration_recorder),
+            duration_stats,
+            HttpConnectionPool::new_arc(),
+        )
+    }
+
+    fn prepare_reload(&self, config: HttpRouteBackendConfig) -> anyhow::Result<ArcBackend> {
+        // reuse the connection pool, so the idle connections can still be used
+        let new = HttpRouteBackend::new_obj(
+            Arc::new(config),
+            self.stats.clone(),
+            self.duration_recorder.clone(),
+            self.duration_stats.clone(),
+            self.pool.clone(),
+        )?;
+        self.stop_discover();
+        Ok(new)
+    }
+
+    fn stop_discover(&self) {
+        let mut guard = self.discover_handles.lock().unwrap();
+        for handle in guard.drain(..) {
+            handle.abort();
+        }
+    }
+
+    async fn tcp_connect(&self, peer: SocketAddr) -> Result<TcpStream, StreamConnectError> {
+        let socket = g3_socket::tcp::new_socket_to(
+            peer.ip(),
+            None,
+            &self.config.tcp_keepalive,
+            &Default::default(),
+            true,
+        )
+        .map_err(StreamConnectError::SetupSocketFailed)?;
+
+        match tokio::time::timeout(self.config.connect_timeout, socket.connect(peer)).await {
+            Ok(Ok(stream)) => Ok(stream),
+            Ok(Err(e)) => Err(ConnectError::from(e).into()),
+            Err(_) => Err(ConnectError::TimedOut.into()),
+        }
+    }
+}
+
+impl Drop for HttpRouteBackend {
+    fn drop(&mut self) {
+        self.stop_discover();
+    }
+}
+
+impl BackendExt for HttpRouteBackend {}
+
+#[async_trait]
+impl Backend for HttpRouteBackend {
+    fn _clone_config(&self) -> AnyBackendConfig {
+        AnyBackendConfig::HttpRoute(self.config.as_ref().clone())
+    }
+
+    fn _update_config_in_place(
+        &self,
+        _flags: u64,
+        _config: AnyBackendConfig,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn _lock_safe_reload(&self, config: AnyBackendConfig) -> anyhow::Result<ArcBackend> {
+        if let AnyBackendConfig::HttpRoute(c) = config {
+            self.prepare_reload(c)
+        } else {
+            Err(anyhow!("invalid backend config type"))
+        }
+    }
+
+    #[inline]
+    fn name(&self) -> &MetricsName {
+        self.config.name()
+    }
+
+    fn discover(&self) -> &MetricsName {
+        &self.config.discover
+    }
+    fn update_discover(&self) -> anyhow::Result<()> {
+        let discover_name = &self.config.discover;
+        let discover = crate::discover::get_discover(discover_name)?;
+
+        // register all routes first, so no job will be left if any of them failed
+        let mut receivers = Vec::with_capacity(self.routes.len());
+        for route in &self.routes {
+            let discover_receiver = discover
+                .register_data(&route.config.discover_data)
+                .context(format!(
+                    "failed to register route {} to discover {discover_name}",
+                    route.name
+                ))?;
+            receivers.push(discover_receiver);
+        }
+
+        let mut handles = Vec::with_capacity(self.routes.len());
+        for (route, mut discover_receiver) in self.routes.iter().zip(receivers) {
+            let peer_addrs_container = route.peers.clone();
+            let (abort_handle, abort_reg) = AbortHandle::new_pair();
+            let abort_fut = Abortable::new(
+                async move {
+                    while discover_receiver.changed().await.is_ok() {
+                        if let Ok(data) = discover_receiver.borrow().as_ref() {
+                            let mut builder = SelectiveVecBuilder::new();
+                            for v in data {
+                                builder.insert(*v);
+                            }
+                            peer_addrs_container.store(builder.build().map(Arc::new));
+                        }
+                    }
+                },
+                abort_reg,
+            );
+            tokio::spawn(abort_fut);
+            handles.push(abort_handle);
+        }
+
+        let mut guard = self.discover_handles.lock().unwrap();
+        let old_handles = std::mem::replace(&mut *guard, handles);
+        drop(guard);
+        for handle in old_handles {
+            handle.abort();
+        }
+
+        Ok(())
+    }
+
+    fn http_forward_config(&self) -> Option<Arc<HttpForwardConfig>> {
+        Some(self.config.http_forward.clone())
+    }
+
+    async fn http_connect(
+        &self,
+        task_notes: &ServerTaskNotes,
+        req: &HttpRouteRequest<'_>,
+    ) -> HttpConnectResult {
+        let Some(route) = self
+            .routes
+            .iter()
+            .find(|r| r.config.is_match(req.host, req.path, req.headers))
+        else {
+            return Err(HttpConnectError::NoRouteMatched);
+        };
+        let Some(peer) = route.select_peer(self, task_notes) else {
+            return Err(StreamConnectError::UpstreamNotResolved.into());
+        };
+
+        if let Some(mut conn) = self.pool.fetch(peer) {
+            conn.route = route.name.clone();
+            conn.reused = true;
+            return Ok(conn);
+        }
+
+        self.stats.add_conn_attempt();
+        let time_now = Instant::now();
+        let stream = self.tcp_connect(peer).await?;
+        self.stats.add_conn_established();
+        self.duration_recorder
+            .record_connect_time(time_now.elapsed());
+
+        Ok(HttpUpstreamConnection::new(
+            route.name.clone(),
+            peer,
+            stream,
+        ))
+    }
+
+    fn http_save_connection(&self, conn: HttpUpstreamConnection) {
+        self.pool.save(
+            conn,
+            self.config.idle_connection_max,
+            self.config.idle_connection_timeout,
+        );
+    }
+}
@@ -0,0 +1,106 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ahash::AHashMap;
+use tokio::time::Instant;
+
+use crate::module::http::HttpUpstreamConnection;
+
+const CLEAN_INTERVAL: Duration = Duration::from_secs(10);
+
+struct IdleConnection {
+    conn: HttpUpstreamConnection,
+    expire: Instant,
+}
+
+/// The idle upstream connections, grouped by peer address.
+///
+/// It will be kept across backend reload, so the existing connections can still be reused.
+pub(super) struct HttpConnectionPool {
+    idle_connections: Mutex<AHashMap<SocketAddr, VecDeque<IdleConnection>>>,
+}
+
+impl HttpConnectionPool {
+    pub(super) fn new_arc() -> Arc<Self> {
+        let pool = Arc::new(HttpConnectionPool {
+            idle_connections: Mutex::new(AHashMap::new()),
+        });
+
+        // the clean job will quit when the pool is dropped
+        let weak_pool = Arc::downgrade(&pool);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CLEAN_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let Some(pool) = weak_pool.upgrade() else {
+                    break;
+                };
+                pool.clean_expired();
+            }
+        });
+
+        pool
+    }
+
+    /// Get the most recently saved connection that is still alive
+    pub(super) fn fetch(&self, peer: SocketAddr) -> Option<HttpUpstreamConnection> {
+        let mut map = self.idle_connections.lock().unwrap();
+        let queue = map.get_mut(&peer)?;
+        let now = Instant::now();
+        while let Some(idle) = queue.pop_back() {
+            if idle.expire > now && idle.conn.is_alive() {
+                return Some(idle.conn);
+            }
+        }
+        None
+    }
+
+    pub(super) fn save(
+        &self,
+        conn: HttpUpstreamConnection,
+        max_idle_count: usize,
+        idle_timeout: Duration,
+    ) {
+        if max_idle_count == 0 {
+            return;
+        }
+
+        let mut map = self.idle_connections.lock().unwrap();
+        let queue = map.entry(conn.peer).or_default();
+        while queue.len() >= max_idle_count {
+            queue.pop_front();
+        }
+        queue.push_back(IdleConnection {
+            conn,
+            expire: Instant::now() + idle_timeout,
+        });
+    }
+
+    fn clean_expired(&self) {
+        let now = Instant::now();
+        let mut map = self.idle_connections.lock().unwrap();
+        map.retain(|_, queue| {
+            queue.retain(|idle| idle.expire > now);
+            !queue.is_empty()
+        });
+    }
+}
@@ -22,12 +22,15 @@ use async_trait::async_trait;
 use g3_types::collection::{SelectiveItem, SelectivePickPolicy, SelectiveVec};
 use g3_types::metrics::MetricsName;
 
+use crate::config::backend::http_route::HttpForwardConfig;
 use crate::config::backend::AnyBackendConfig;
+use crate::module::http::{HttpConnectResult, HttpRouteRequest, HttpUpstreamConnection};
 use crate::module::keyless::{KeylessRequest, KeylessResponse};
 use crate::module::stream::{StreamConnectError, StreamConnectResult};
 use crate::serve::ServerTaskNotes;
 
 mod dummy_close;
+mod http_route;
 #[cfg(feature = "quic")]
 mod keyless_quic;
 mod keyless_tcp;
@@ -40,6 +43,9 @@ pub(crate) use ops::{reload, update_dependency_to_discover};
 mod registry;
 pub(crate) use registry::{get_names, get_or_insert_default};
 
+#[cfg(test)]
+pub(crate) use registry::add as add_to_registry;
+
 #[async_trait]
 pub(crate) trait Backend {
     fn _clone_config(&self) -> AnyBackendConfig;
@@ -60,6 +66,22 @@ pub(crate) trait Backend {
     async fn keyless(&self, req: KeylessRequest) -> KeylessResponse {
         KeylessResponse::not_implemented(req.header())
     }
+
+    /// the http forward config, the client connection will be handled as http if set
+    fn http_forward_config(&self) -> Option<Arc<HttpForwardConfig>> {
+        None
+    }
+
+    async fn http_connect(
+        &self,
+        _task_notes: &ServerTaskNotes,
+        _req: &HttpRouteRequest<'_>,
+    ) -> HttpConnectResult {
+        Err(StreamConnectError::UpstreamNotResolved.into())
+    }
+
+    /// save the upstream connection for later reuse
+    fn http_save_connection(&self, _conn: HttpUpstreamConnection) {}
 }
 
 pub(crate) type ArcBackend = Arc<dyn Backend + Send + Sync>;
@@ -27,6 +27,7 @@ use super::{registry, ArcBackend};
 use crate::config::backend::{AnyBackendConfig, BackendConfigDiffAction};
 
 use super::dummy_close::DummyCloseBackend;
+use super::http_route::HttpRouteBackend;
 #[cfg(feature = "quic")]
 use super::keyless_quic::KeylessQuicBackend;
 use super::keyless_tcp::KeylessTcpBackend;
@@ -172,6 +173,7 @@ async fn spawn_new_unlocked(config: AnyBackendConfig) -> anyhow::Result<()> {
     let site = match config {
         AnyBackendConfig::DummyClose(c) => DummyCloseBackend::prepare_initial(c)?,
         AnyBackendConfig::StreamTcp(c) => StreamTcpBackend::prepare_initial(c)?,
+        AnyBackendConfig::HttpRoute(c) => HttpRouteBackend::prepare_initial(c)?,
         AnyBackendConfig::KeylessTcp(c) => KeylessTcpBackend::prepare_initial(c)?,
         #[cfg(feature = "quic")]
         AnyBackendConfig::KeylessQuic(c) => KeylessQuicBackend::prepare_initial(c)?,
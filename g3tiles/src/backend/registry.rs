@@ -29,7 +29,7 @@ use crate::config::backend::AnyBackendConfig;
 static RUNTIME_BACKEND_REGISTRY: Lazy<Mutex<HashMap<MetricsName, ArcBackend>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-pub(super) fn add(name: MetricsName, connector: ArcBackend) {
+pub(crate) fn add(name: MetricsName, connector: ArcBackend) {
     let mut ht = RUNTIME_BACKEND_REGISTRY.lock().unwrap();
     if let Some(_old) = ht.insert(name, connector) {}
 }
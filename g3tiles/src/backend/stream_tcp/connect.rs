@@ -0,0 +1,178 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use rustls::ServerName;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+use g3_openssl::SslConnector;
+use g3_types::net::{
+    ConnectError, Host, OpensslClientConfig, ProxyProtocolEncoder, RustlsClientConfig,
+};
+
+use crate::config::backend::stream_tcp::StreamTcpBackendConfig;
+use crate::config::backend::PeerHealthCheckMethod;
+use crate::module::stream::{StreamConnectError, StreamConnectResult};
+
+enum UpstreamTlsClient {
+    Rustls(RustlsClientConfig),
+    Openssl(OpensslClientConfig),
+}
+
+/// The connector used by both the stream tasks and the health check job.
+///
+/// It should only hold the config needed to connect,
+/// so the backend itself won't be referenced by the check job.
+pub(super) struct StreamTcpConnector {
+    config: Arc<StreamTcpBackendConfig>,
+    tls_client: Option<UpstreamTlsClient>,
+}
+
+impl StreamTcpConnector {
+    pub(super) fn new(config: Arc<StreamTcpBackendConfig>) -> anyhow::Result<Self> {
+        let tls_client = if let Some(builder) = &config.rustls_client {
+            let tls_config = builder
+                .build()
+                .map_err(|e| anyhow!("failed to build rustls client config: {e}"))?;
+            Some(UpstreamTlsClient::Rustls(tls_config))
+        } else if let Some(builder) = &config.openssl_client {
+            let tls_config = builder
+                .build()
+                .map_err(|e| anyhow!("failed to build openssl client config: {e}"))?;
+            Some(UpstreamTlsClient::Openssl(tls_config))
+        } else {
+            None
+        };
+        Ok(StreamTcpConnector { config, tls_client })
+    }
+
+    pub(super) async fn tcp_connect(
+        &self,
+        peer: SocketAddr,
+    ) -> Result<TcpStream, StreamConnectError> {
+        let socket = g3_socket::tcp::new_socket_to(
+            peer.ip(),
+            None,
+            &self.config.tcp_keepalive,
+            &Default::default(),
+            true,
+        )
+        .map_err(StreamConnectError::SetupSocketFailed)?;
+
+        match tokio::time::timeout(self.config.connect_timeout, socket.connect(peer)).await {
+            Ok(Ok(stream)) => Ok(stream),
+            Ok(Err(e)) => Err(ConnectError::from(e).into()),
+            Err(_) => Err(ConnectError::TimedOut.into()),
+        }
+    }
+
+    /// Send the PROXY protocol header if enabled
+    pub(super) async fn send_proxy_protocol(
+        &self,
+        stream: &mut TcpStream,
+        client_addr: SocketAddr,
+        server_addr: SocketAddr,
+    ) -> Result<(), StreamConnectError> {
+        if let Some(version) = self.config.proxy_protocol {
+            let mut encoder = ProxyProtocolEncoder::new(version);
+            let bytes = encoder.encode_tcp(client_addr, server_addr)?;
+            stream
+                .write_all(bytes)
+                .await
+                .map_err(StreamConnectError::ProxyProtocolWriteFailed)?;
+        }
+        Ok(())
+    }
+
+    /// Do tls handshake if enabled, and then split the stream
+    pub(super) async fn tls_connect(
+        &self,
+        stream: TcpStream,
+        peer: SocketAddr,
+    ) -> StreamConnectResult {
+        match &self.tls_client {
+            Some(UpstreamTlsClient::Rustls(tls_config)) => {
+                let tls_name = match &self.config.tls_name {
+                    Some(name) => ServerName::try_from(name)
+                        .map_err(|e| StreamConnectError::TlsHandshakeFailed(e.into()))?,
+                    None => ServerName::IpAddress(peer.ip()),
+                };
+                let connector = TlsConnector::from(tls_config.driver.clone());
+                match tokio::time::timeout(
+                    tls_config.handshake_timeout,
+                    connector.connect(tls_name, stream),
+                )
+                .await
+                {
+                    Ok(Ok(tls_stream)) => {
+                        let (ups_r, ups_w) = tokio::io::split(tls_stream);
+                        Ok((Box::new(ups_r), Box::new(ups_w)))
+                    }
+                    Ok(Err(e)) => Err(StreamConnectError::TlsHandshakeFailed(e.into())),
+                    Err(_) => Err(StreamConnectError::TlsHandshakeTimeout),
+                }
+            }
+            Some(UpstreamTlsClient::Openssl(tls_config)) => {
+                let tls_name = self.config.tls_name.clone().unwrap_or(Host::Ip(peer.ip()));
+                let ssl = tls_config
+                    .build_ssl(&tls_name, peer.port())
+                    .map_err(StreamConnectError::TlsHandshakeFailed)?;
+                let connector = SslConnector::new(ssl, stream)
+                    .map_err(|e| StreamConnectError::TlsHandshakeFailed(e.into()))?;
+                match tokio::time::timeout(tls_config.handshake_timeout, connector.connect()).await
+                {
+                    Ok(Ok(tls_stream)) => {
+                        let (ups_r, ups_w) = tokio::io::split(tls_stream);
+                        Ok((Box::new(ups_r), Box::new(ups_w)))
+                    }
+                    Ok(Err(e)) => Err(StreamConnectError::TlsHandshakeFailed(e.into())),
+                    Err(_) => Err(StreamConnectError::TlsHandshakeTimeout),
+                }
+            }
+            None => {
+                let (ups_r, ups_w) = stream.into_split();
+                Ok((Box::new(ups_r), Box::new(ups_w)))
+            }
+        }
+    }
+
+    pub(super) async fn probe(
+        &self,
+        peer: SocketAddr,
+        method: PeerHealthCheckMethod,
+    ) -> Result<(), StreamConnectError> {
+        let mut stream = self.tcp_connect(peer).await?;
+
+        match method {
+            PeerHealthCheckMethod::TcpConnect => Ok(()),
+            PeerHealthCheckMethod::TlsHandshake => {
+                // the peer may require the PROXY protocol header before the tls handshake
+                let local_addr = stream
+                    .local_addr()
+                    .map_err(StreamConnectError::SetupSocketFailed)?;
+                self.send_proxy_protocol(&mut stream, local_addr, peer)
+                    .await?;
+                self.tls_connect(stream, peer).await?;
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use arc_swap::{ArcSwap, ArcSwapOption, Guard};
+use futures_util::future::{AbortHandle, Abortable};
+use log::{info, warn};
+
+use g3_types::collection::{
+    build_available_selection, RiseFallHealth, SelectiveVec, WeightedValue,
+};
+use g3_types::metrics::MetricsName;
+use g3_types::net::ConnectError;
+
+use super::StreamTcpConnector;
+use crate::config::backend::{PeerHealthCheckConfig, PeerOutlierDetectionConfig};
+use crate::module::stream::StreamConnectError;
+
+struct PeerHealthState {
+    node: WeightedValue<SocketAddr>,
+    health: RiseFallHealth,
+    connect_failure: AtomicUsize,
+    ejected_until: Mutex<Option<Instant>>,
+}
+
+impl PeerHealthState {
+    fn new(node: WeightedValue<SocketAddr>) -> Self {
+        PeerHealthState {
+            node,
+            health: RiseFallHealth::default(),
+            connect_failure: AtomicUsize::new(0),
+            ejected_until: Mutex::new(None),
+        }
+    }
+
+    /// return true if the peer should be ejected
+    fn add_connect_failure(&self, config: &PeerOutlierDetectionConfig, now: Instant) -> bool {
+        let count = self.connect_failure.fetch_add(1, Ordering::Relaxed) + 1;
+        if count < config.consecutive_failures {
+            return false;
+        }
+        self.connect_failure.store(0, Ordering::Relaxed);
+        let mut ejected_until = self.ejected_until.lock().unwrap();
+        *ejected_until = Some(now + config.ejection_time);
+        true
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        if !self.health.is_healthy() {
+            return false;
+        }
+        match *self.ejected_until.lock().unwrap() {
+            Some(until) => until <= now,
+            None => true,
+        }
+    }
+}
+
+/// The peers of a stream tcp backend, with their health status.
+///
+/// Only healthy and not ejected peers will be present in the selective vec used for selection,
+/// and all peers will be used if none of them is available.
+/// The health status of existing peers will be kept when the discovered peers changed.
+pub(super) struct StreamTcpPeerSet {
+    peers: ArcSwap<Vec<Arc<PeerHealthState>>>,
+    selection: ArcSwapOption<SelectiveVec<WeightedValue<SocketAddr>>>,
+}
+
+impl StreamTcpPeerSet {
+    pub(super) fn new() -> Self {
+        StreamTcpPeerSet {
+            peers: ArcSwap::new(Arc::new(Vec::new())),
+            selection: ArcSwapOption::new(None),
+        }
+    }
+
+    #[inline]
+    pub(super) fn load_selection(
+        &self,
+    ) -> Guard<Option<Arc<SelectiveVec<WeightedValue<SocketAddr>>>>> {
+        self.selection.load()
+    }
+
+    pub(super) fn update_peers(&self, nodes: &[WeightedValue<SocketAddr>]) {
+        let old_peers = self.peers.load();
+        let peers = nodes
+            .iter()
+            .map(|node| {
+                old_peers
+                    .iter()
+                    .find(|p| p.node == *node)
+                    .cloned()
+                    .unwrap_or_else(|| Arc::new(PeerHealthState::new(*node)))
+            })
+            .collect::<Vec<_>>();
+        self.peers.store(Arc::new(peers));
+        self.rebuild_selection();
+    }
+
+    fn rebuild_selection(&self) {
+        let peers = self.peers.load();
+        let now = Instant::now();
+
+        let selection =
+            build_available_selection(peers.iter().map(|peer| (peer.node, peer.is_available(now))));
+        self.selection.store(selection.map(Arc::new));
+    }
+
+    pub(super) fn add_connect_success(&self, addr: SocketAddr) {
+        let peers = self.peers.load();
+        if let Some(peer) = peers.iter().find(|p| *p.node.inner() == addr) {
+            peer.connect_failure.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// return true if the peer is ejected by this failure
+    fn eject_on_failure(&self, addr: SocketAddr, config: &PeerOutlierDetectionConfig) -> bool {
+        let peers = self.peers.load();
+        let Some(peer) = peers.iter().find(|p| *p.node.inner() == addr) else {
+            return false;
+        };
+        if peer.add_connect_failure(config, Instant::now()) {
+            self.rebuild_selection();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(super) fn add_connect_failure(
+        self: &Arc<Self>,
+        backend: &MetricsName,
+        addr: SocketAddr,
+        config: &PeerOutlierDetectionConfig,
+    ) {
+        if !self.eject_on_failure(addr, config) {
+            return;
+        }
+
+        warn!(
+            "backend {backend}: peer {addr} is ejected for {:?} after {} consecutive connect failures",
+            config.ejection_time, config.consecutive_failures
+        );
+        let peers = Arc::downgrade(self);
+        let ejection_time = config.ejection_time;
+        tokio::spawn(async move {
+            tokio::time::sleep(ejection_time).await;
+            if let Some(peers) = peers.upgrade() {
+                peers.rebuild_selection();
+            }
+        });
+    }
+
+    async fn check_all(
+        &self,
+        backend: &MetricsName,
+        config: &PeerHealthCheckConfig,
+        connector: &StreamTcpConnector,
+    ) {
+        let peers = self.peers.load_full();
+        let results = futures_util::future::join_all(peers.iter().map(|peer| async {
+            let addr = *peer.node.inner();
+            match tokio::time::timeout(config.timeout, connector.probe(addr, config.method)).await {
+                Ok(r) => r,
+                Err(_) => Err(StreamConnectError::ConnectFailed(ConnectError::TimedOut)),
+            }
+        }))
+        .await;
+
+        let mut changed = false;
+        for (peer, result) in peers.iter().zip(results) {
+            let success = result.is_ok();
+            if peer.health.add_result(success, config.rise, config.fall) {
+                changed = true;
+                if success {
+                    info!(
+                        "backend {backend}: peer {} is healthy again",
+                        peer.node.inner()
+                    );
+                } else if let Err(e) = result {
+                    warn!(
+                        "backend {backend}: peer {} is unhealthy and will be removed from selection, last {} check error: {e}",
+                        peer.node.inner(),
+                        config.method.as_str()
+                    );
+                }
+            }
+        }
+        if changed {
+            self.rebuild_selection();
+        }
+    }
+
+    pub(super) fn spawn_check_job(
+        self: &Arc<Self>,
+        backend: MetricsName,
+        config: PeerHealthCheckConfig,
+        connector: Arc<StreamTcpConnector>,
+    ) -> AbortHandle {
+        let peers = Arc::clone(self);
+        let f = async move {
+            let mut interval = tokio::time::interval(config.interval);
+            loop {
+                interval.tick().await;
+                peers.check_all(&backend, &config, &connector).await;
+            }
+        };
+
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let future = Abortable::new(f, abort_registration);
+        tokio::spawn(future);
+        abort_handle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    fn new_set() -> StreamTcpPeerSet {
+        let nodes = vec![
+            WeightedValue::new(SocketAddr::from_str("127.0.0.1:443").unwrap()),
+            WeightedValue::new(SocketAddr::from_str("127.0.0.2:443").unwrap()),
+        ];
+        let set = StreamTcpPeerSet::new();
+        set.update_peers(&nodes);
+        set
+    }
+
+    fn selection_len(set: &StreamTcpPeerSet) -> usize {
+        let selection = set.load_selection();
+        (*selection)
+            .as_ref()
+            .unwrap()
+            .pick_serial_n(usize::MAX)
+            .len()
+    }
+
+    #[test]
+    fn eject_and_fallback() {
+        let set = new_set();
+        let config = PeerOutlierDetectionConfig {
+            consecutive_failures: 2,
+            ejection_time: Duration::from_secs(60),
+        };
+        let addr0 = SocketAddr::from_str("127.0.0.1:443").unwrap();
+        let addr1 = SocketAddr::from_str("127.0.0.2:443").unwrap();
+
+        assert!(!set.eject_on_failure(addr0, &config));
+        set.add_connect_success(addr0);
+        assert!(!set.eject_on_failure(addr0, &config));
+        assert!(set.eject_on_failure(addr0, &config));
+        assert_eq!(selection_len(&set), 1);
+        assert_eq!(
+            *(*set.load_selection())
+                .as_ref()
+                .unwrap()
+                .pick_serial()
+                .inner(),
+            addr1
+        );
+
+        // the ejection state should be kept if the peer is still present
+        set.update_peers(&[WeightedValue::new(addr0), WeightedValue::new(addr1)]);
+        assert_eq!(selection_len(&set), 1);
+
+        assert!(!set.eject_on_failure(addr1, &config));
+        assert!(set.eject_on_failure(addr1, &config));
+        assert_eq!(selection_len(&set), 2);
+    }
+}
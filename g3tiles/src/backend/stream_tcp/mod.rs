@@ -18,14 +18,11 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, Context};
-use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
 use futures_util::future::{AbortHandle, Abortable};
 use tokio::time::Instant;
 
-use g3_types::collection::{SelectiveVec, SelectiveVecBuilder, WeightedValue};
 use g3_types::metrics::MetricsName;
-use g3_types::net::ConnectError;
 
 use super::{ArcBackend, Backend, BackendExt};
 use crate::config::backend::stream_tcp::StreamTcpBackendConfig;
@@ -36,13 +33,21 @@ use crate::module::stream::{
 };
 use crate::serve::ServerTaskNotes;
 
+mod connect;
+use connect::StreamTcpConnector;
+
+mod health;
+use health::StreamTcpPeerSet;
+
 pub(crate) struct StreamTcpBackend {
     config: Arc<StreamTcpBackendConfig>,
     stats: Arc<StreamBackendStats>,
     duration_recorder: Arc<StreamBackendDurationRecorder>,
     duration_stats: Arc<StreamBackendDurationStats>,
-    peer_addrs: Arc<ArcSwapOption<SelectiveVec<WeightedValue<SocketAddr>>>>,
+    connector: Arc<StreamTcpConnector>,
+    peers: Arc<StreamTcpPeerSet>,
     discover_handle: Mutex<Option<AbortHandle>>,
+    health_check_handle: Option<AbortHandle>,
 }
 
 impl StreamTcpBackend {
@@ -51,20 +56,30 @@ impl StreamTcpBackend {
         stats: Arc<StreamBackendStats>,
         duration_recorder: Arc<StreamBackendDurationRecorder>,
         duration_stats: Arc<StreamBackendDurationStats>,
+        peers: Arc<StreamTcpPeerSet>,
     ) -> anyhow::Result<ArcBackend> {
-        let peer_addrs = Arc::new(ArcSwapOption::new(None));
-
         // always update extra metrics tags
         stats.set_extra_tags(config.extra_metrics_tags.clone());
         duration_stats.set_extra_tags(config.extra_metrics_tags.clone());
 
+        let connector = Arc::new(StreamTcpConnector::new(config.clone())?);
+        let health_check_handle = config.health_check.as_ref().map(|health_check| {
+            peers.spawn_check_job(
+                config.name().clone(),
+                health_check.clone(),
+                connector.clone(),
+            )
+        });
+
         let backend = Arc::new(StreamTcpBackend {
             config,
             stats,
             duration_recorder,
             duration_stats,
-            peer_addrs,
+            connector,
+            peers,
             discover_handle: Mutex::new(None),
+            health_check_handle,
         });
         backend.update_discover()?;
 
@@ -85,27 +100,52 @@ impl StreamTcpBackend {
             stats,
             Arc::new(duration_recorder),
             duration_stats,
+            Arc::new(StreamTcpPeerSet::new()),
         )
     }
 
     fn prepare_reload(&self, config: StreamTcpBackendConfig) -> anyhow::Result<ArcBackend> {
-        let stats = self.stats.clone();
-        // TODO reuse old connection pool?
-        StreamTcpBackend::new_obj(
+        // reuse the peer set, so the health status of the existing peers will be kept
+        let new = StreamTcpBackend::new_obj(
             Arc::new(config),
-            stats,
+            self.stats.clone(),
             self.duration_recorder.clone(),
             self.duration_stats.clone(),
-        )
+            self.peers.clone(),
+        )?;
+        self.stop_jobs();
+        Ok(new)
+    }
+
+    fn stop_jobs(&self) {
+        if let Some(handle) = self.discover_handle.lock().unwrap().take() {
+            handle.abort();
+        }
+        if let Some(handle) = &self.health_check_handle {
+            handle.abort();
+        }
     }
 
     fn select_peer(&self, task_notes: &ServerTaskNotes) -> Option<SocketAddr> {
-        let guard = self.peer_addrs.load();
+        let guard = self.peers.load_selection();
         let peers = (*guard).as_ref()?;
 
         let v = self.select_consistent(peers.as_ref(), self.config.peer_pick_policy, task_notes);
         Some(*v.inner())
     }
+
+    fn add_connect_failure(&self, peer: SocketAddr) {
+        if let Some(outlier_detection) = &self.config.outlier_detection {
+            self.peers
+                .add_connect_failure(self.config.name(), peer, outlier_detection);
+        }
+    }
+}
+
+impl Drop for StreamTcpBackend {
+    fn drop(&mut self) {
+        self.stop_jobs();
+    }
 }
 
 impl BackendExt for StreamTcpBackend {}
@@ -147,17 +187,13 @@ impl Backend for StreamTcpBackend {
             .register_data(&self.config.discover_data)
             .context("failed to register to discover {discover}")?;
 
-        let peer_addrs_container = self.peer_addrs.clone();
+        let peers = self.peers.clone();
         let (abort_handle, abort_reg) = AbortHandle::new_pair();
         let abort_fut = Abortable::new(
             async move {
                 while discover_receiver.changed().await.is_ok() {
                     if let Ok(data) = discover_receiver.borrow().as_ref() {
-                        let mut builder = SelectiveVecBuilder::new();
-                        for v in data {
-                            builder.insert(*v);
-                        }
-                        peer_addrs_container.store(builder.build().map(Arc::new));
+                        peers.update_peers(data);
                     }
                 }
             },
@@ -181,25 +217,34 @@ impl Backend for StreamTcpBackend {
         };
 
         self.stats.add_conn_attempt();
-        let socket = g3_socket::tcp::new_socket_to(
-            next_addr.ip(),
-            None,
-            &Default::default(),
-            &Default::default(),
-            true,
-        )
-        .map_err(StreamConnectError::SetupSocketFailed)?;
-
         let time_now = Instant::now();
-        let stream = socket
-            .connect(next_addr)
-            .await
-            .map_err(ConnectError::from)?;
+        let mut stream = match self.connector.tcp_connect(next_addr).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                self.add_connect_failure(next_addr);
+                return Err(e);
+            }
+        };
         let connect_dur = time_now.elapsed();
         self.stats.add_conn_established();
         self.duration_recorder.record_connect_time(connect_dur);
 
-        let (ups_r, ups_w) = stream.into_split();
-        Ok((Box::new(ups_r), Box::new(ups_w)))
+        self.connector
+            .send_proxy_protocol(
+                &mut stream,
+                task_notes.client_addr(),
+                task_notes.server_addr(),
+            )
+            .await?;
+        match self.connector.tls_connect(stream, next_addr).await {
+            Ok(connected) => {
+                self.peers.add_connect_success(next_addr);
+                Ok(connected)
+            }
+            Err(e) => {
+                self.add_connect_failure(next_addr);
+                Err(e)
+            }
+        }
     }
 }
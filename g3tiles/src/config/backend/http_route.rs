@@ -0,0 +1,446 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use http::HeaderName;
+use yaml_rust::{yaml, Yaml};
+
+use g3_histogram::HistogramMetricsConfig;
+use g3_types::collection::SelectivePickPolicy;
+use g3_types::metrics::{MetricsName, StaticMetricsTags};
+use g3_types::net::{HttpHeaderMap, TcpKeepAliveConfig};
+use g3_yaml::YamlDocPosition;
+
+use super::{AnyBackendConfig, BackendConfig, BackendConfigDiffAction};
+use crate::config::discover::DiscoverRegisterData;
+
+const BACKEND_CONFIG_TYPE: &str = "HttpRoute";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum HttpRouteHostMatch {
+    Exact(String),
+    /// the suffix contains the leading dot
+    Suffix(String),
+}
+
+impl HttpRouteHostMatch {
+    fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        let s = g3_yaml::value::as_string(v)?.to_ascii_lowercase();
+        if let Some(suffix) = s.strip_prefix("*.") {
+            if suffix.is_empty() {
+                return Err(anyhow!("empty domain suffix"));
+            }
+            Ok(HttpRouteHostMatch::Suffix(format!(".{suffix}")))
+        } else if s.is_empty() || s.contains('*') {
+            Err(anyhow!("invalid host match value {s}"))
+        } else {
+            Ok(HttpRouteHostMatch::Exact(s))
+        }
+    }
+
+    fn is_match(&self, host: &str) -> bool {
+        match self {
+            HttpRouteHostMatch::Exact(name) => name.eq_ignore_ascii_case(host),
+            HttpRouteHostMatch::Suffix(suffix) => {
+                host.len() > suffix.len()
+                    && host.is_char_boundary(host.len() - suffix.len())
+                    && host[host.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+            }
+        }
+    }
+}
+
+/// A single route of the http route backend.
+///
+/// All the match rules should pass for a request to use this route,
+/// and a route without any match rule will match all requests.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HttpRouteConfig {
+    pub(crate) name: String,
+    hosts: Vec<HttpRouteHostMatch>,
+    path_prefix: Option<String>,
+    headers: Vec<(HeaderName, String)>,
+    pub(crate) discover_data: DiscoverRegisterData,
+    pub(crate) peer_pick_policy: SelectivePickPolicy,
+}
+
+impl HttpRouteConfig {
+    fn new(index: usize) -> Self {
+        HttpRouteConfig {
+            name: format!("route-{index}"),
+            hosts: Vec::new(),
+            path_prefix: None,
+            headers: Vec::new(),
+            discover_data: DiscoverRegisterData::Null,
+            peer_pick_policy: SelectivePickPolicy::Random,
+        }
+    }
+
+    fn parse(index: usize, v: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = v else {
+            return Err(anyhow!("yaml value type for 'http route' should be 'map'"));
+        };
+
+        let mut route = HttpRouteConfig::new(index);
+        g3_yaml::foreach_kv(map, |k, v| route.set(k, v))?;
+        if matches!(route.discover_data, DiscoverRegisterData::Null) {
+            return Err(anyhow!("no discover data set"));
+        }
+        Ok(route)
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match g3_yaml::key::normalize(k).as_str() {
+            "name" => {
+                self.name = g3_yaml::value::as_string(v)?;
+                Ok(())
+            }
+            "host" | "hosts" => {
+                self.hosts = g3_yaml::value::as_list(v, HttpRouteHostMatch::parse)
+                    .context(format!("invalid host match list value for key {k}"))?;
+                Ok(())
+            }
+            "path_prefix" | "path" => {
+                let prefix = g3_yaml::value::as_string(v)?;
+                if !prefix.starts_with('/') {
+                    return Err(anyhow!("the path prefix should start with '/'"));
+                }
+                self.path_prefix = Some(prefix);
+                Ok(())
+            }
+            "header" | "headers" => {
+                let Yaml::Hash(map) = v else {
+                    return Err(anyhow!("yaml value type for key {k} should be 'map'"));
+                };
+                g3_yaml::foreach_kv(map, |name, value| {
+                    let name = HeaderName::from_bytes(name.as_bytes())
+                        .map_err(|e| anyhow!("invalid http header name {name}: {e}"))?;
+                    let value = g3_yaml::value::as_string(value)
+                        .context(format!("invalid string value for header {name}"))?;
+                    self.headers.push((name, value));
+                    Ok(())
+                })
+            }
+            "discover_data" => {
+                self.discover_data = DiscoverRegisterData::Yaml(v.clone());
+                Ok(())
+            }
+            "discover_data_json" => {
+                self.discover_data = DiscoverRegisterData::parse_json_str(v)
+                    .context(format!("invalid json string value for key {k}"))?;
+                Ok(())
+            }
+            "peer_pick_policy" => {
+                self.peer_pick_policy = g3_yaml::value::as_selective_pick_policy(v)?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    /// The port in the host should be stripped by the caller
+    pub(crate) fn is_match(&self, host: Option<&str>, path: &str, headers: &HttpHeaderMap) -> bool {
+        if !self.hosts.is_empty() {
+            let Some(host) = host else {
+                return false;
+            };
+            if !self.hosts.iter().any(|m| m.is_match(host)) {
+                return false;
+            }
+        }
+
+        if let Some(prefix) = &self.path_prefix {
+            if !path.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+
+        self.headers.iter().all(|(name, value)| {
+            headers
+                .get_all(name)
+                .iter()
+                .any(|v| v.to_str().eq(value.as_str()))
+        })
+    }
+}
+
+/// Protocol level config used by the server tasks when forwarding http requests
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HttpForwardConfig {
+    pub(crate) req_hdr_max_size: usize,
+    pub(crate) rsp_hdr_max_size: usize,
+    pub(crate) req_hdr_recv_timeout: Duration,
+    pub(crate) rsp_hdr_recv_timeout: Duration,
+    pub(crate) pipeline_read_idle_timeout: Duration,
+    pub(crate) body_line_max_len: usize,
+    pub(crate) h2_max_concurrent_streams: u32,
+    pub(crate) append_forwarded_headers: bool,
+    pub(crate) log_uri_max_chars: usize,
+}
+
+impl Default for HttpForwardConfig {
+    fn default() -> Self {
+        HttpForwardConfig {
+            req_hdr_max_size: 65536,
+            rsp_hdr_max_size: 65536,
+            req_hdr_recv_timeout: Duration::from_secs(30),
+            rsp_hdr_recv_timeout: Duration::from_secs(60),
+            pipeline_read_idle_timeout: Duration::from_secs(300),
+            body_line_max_len: 8192,
+            h2_max_concurrent_streams: 128,
+            append_forwarded_headers: true,
+            log_uri_max_chars: 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HttpRouteBackendConfig {
+    name: MetricsName,
+    position: Option<YamlDocPosition>,
+    pub(crate) discover: MetricsName,
+    pub(crate) routes: Vec<HttpRouteConfig>,
+    pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) duration_stats: HistogramMetricsConfig,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) tcp_keepalive: TcpKeepAliveConfig,
+    pub(crate) idle_connection_max: usize,
+    pub(crate) idle_connection_timeout: Duration,
+    pub(crate) http_forward: Arc<HttpForwardConfig>,
+}
+
+impl HttpRouteBackendConfig {
+    fn new(position: Option<YamlDocPosition>) -> Self {
+        HttpRouteBackendConfig {
+            name: MetricsName::default(),
+            position,
+            discover: MetricsName::default(),
+            routes: Vec::new(),
+            extra_metrics_tags: None,
+            duration_stats: HistogramMetricsConfig::default(),
+            connect_timeout: Duration::from_secs(10),
+            tcp_keepalive: TcpKeepAliveConfig::default(),
+            idle_connection_max: 32,
+            idle_connection_timeout: Duration::from_secs(30),
+            http_forward: Arc::new(HttpForwardConfig::default()),
+        }
+    }
+
+    pub(super) fn parse(
+        map: &yaml::Hash,
+        position: Option<YamlDocPosition>,
+    ) -> anyhow::Result<Self> {
+        let mut backend = HttpRouteBackendConfig::new(position);
+        g3_yaml::foreach_kv(map, |k, v| backend.set(k, v))?;
+        backend.check()?;
+        Ok(backend)
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.name.is_empty() {
+            return Err(anyhow!("name is not set"));
+        }
+        if self.discover.is_empty() {
+            return Err(anyhow!("no discover set"));
+        }
+        if self.routes.is_empty() {
+            return Err(anyhow!("no route set"));
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match k {
+            super::CONFIG_KEY_BACKEND_TYPE => Ok(()),
+            super::CONFIG_KEY_BACKEND_NAME => {
+                self.name = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "discover" => {
+                self.discover = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "routes" | "route" => {
+                let Yaml::Array(seq) = v else {
+                    return Err(anyhow!("yaml value type for key {k} should be 'seq'"));
+                };
+                for (i, v) in seq.iter().enumerate() {
+                    let route = HttpRouteConfig::parse(i, v)
+                        .context(format!("invalid http route value for {k}#{i}"))?;
+                    self.routes.push(route);
+                }
+                Ok(())
+            }
+            "extra_metrics_tags" => {
+                let tags = g3_yaml::value::as_static_metrics_tags(v)
+                    .context(format!("invalid static metrics tags value for key {k}"))?;
+                self.extra_metrics_tags = Some(Arc::new(tags));
+                Ok(())
+            }
+            "duration_stats" | "duration_metrics" => {
+                self.duration_stats = g3_yaml::value::as_histogram_metrics_config(v).context(
+                    format!("invalid histogram metrics config value for key {k}"),
+                )?;
+                Ok(())
+            }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "tcp_keepalive" => {
+                self.tcp_keepalive = g3_yaml::value::as_tcp_keepalive_config(v)
+                    .context(format!("invalid tcp keepalive config value for key {k}"))?;
+                Ok(())
+            }
+            "idle_connection_max" => {
+                self.idle_connection_max = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "idle_connection_timeout" => {
+                self.idle_connection_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "req_header_max_size" => {
+                Arc::make_mut(&mut self.http_forward).req_hdr_max_size =
+                    g3_yaml::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            "rsp_header_max_size" => {
+                Arc::make_mut(&mut self.http_forward).rsp_hdr_max_size =
+                    g3_yaml::humanize::as_usize(v)
+                        .context(format!("invalid humanize usize value for key {k}"))?;
+                Ok(())
+            }
+            "req_header_recv_timeout" => {
+                Arc::make_mut(&mut self.http_forward).req_hdr_recv_timeout =
+                    g3_yaml::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "rsp_header_recv_timeout" => {
+                Arc::make_mut(&mut self.http_forward).rsp_hdr_recv_timeout =
+                    g3_yaml::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "pipeline_read_idle_timeout" => {
+                Arc::make_mut(&mut self.http_forward).pipeline_read_idle_timeout =
+                    g3_yaml::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "body_line_max_length" | "body_line_max_len" => {
+                Arc::make_mut(&mut self.http_forward).body_line_max_len =
+                    g3_yaml::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "h2_max_concurrent_streams" => {
+                Arc::make_mut(&mut self.http_forward).h2_max_concurrent_streams =
+                    g3_yaml::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
+                Ok(())
+            }
+            "append_forwarded_headers" | "x_forwarded_headers" => {
+                Arc::make_mut(&mut self.http_forward).append_forwarded_headers =
+                    g3_yaml::value::as_bool(v)
+                        .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            "log_uri_max_chars" => {
+                Arc::make_mut(&mut self.http_forward).log_uri_max_chars =
+                    g3_yaml::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+}
+
+impl BackendConfig for HttpRouteBackendConfig {
+    fn name(&self) -> &MetricsName {
+        &self.name
+    }
+
+    fn position(&self) -> Option<YamlDocPosition> {
+        self.position.clone()
+    }
+
+    fn backend_type(&self) -> &'static str {
+        BACKEND_CONFIG_TYPE
+    }
+
+    fn diff_action(&self, new: &AnyBackendConfig) -> BackendConfigDiffAction {
+        let AnyBackendConfig::HttpRoute(new) = new else {
+            return BackendConfigDiffAction::SpawnNew;
+        };
+
+        if self.eq(new) {
+            return BackendConfigDiffAction::NoAction;
+        }
+
+        BackendConfigDiffAction::Reload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use g3_types::net::HttpHeaderValue;
+
+    fn load_route(s: &str) -> HttpRouteConfig {
+        let docs = yaml_rust::YamlLoader::load_from_str(s).unwrap();
+        HttpRouteConfig::parse(0, &docs[0]).unwrap()
+    }
+
+    #[test]
+    fn route_match() {
+        let route = load_route(
+            r#"
+            host:
+              - api.example.com
+              - "*.api.example.net"
+            path_prefix: /v1/
+            headers:
+              x-canary: "1"
+            discover_data: "127.0.0.1:80"
+            "#,
+        );
+
+        let mut headers = HttpHeaderMap::default();
+        assert!(!route.is_match(Some("api.example.com"), "/v1/user", &headers));
+        headers.append(
+            HeaderName::from_static("x-canary"),
+            HttpHeaderValue::from_static("1"),
+        );
+        assert!(route.is_match(Some("api.example.com"), "/v1/user", &headers));
+        assert!(route.is_match(Some("API.Example.com"), "/v1/user", &headers));
+        assert!(route.is_match(Some("a.api.example.net"), "/v1/", &headers));
+        assert!(!route.is_match(Some("api.example.net"), "/v1/user", &headers));
+        assert!(!route.is_match(Some("api.example.com"), "/v2/user", &headers));
+        assert!(!route.is_match(None, "/v1/user", &headers));
+
+        let route = load_route("discover_data: \"127.0.0.1:80\"");
+        assert!(route.is_match(None, "/", &HttpHeaderMap::default()));
+    }
+}
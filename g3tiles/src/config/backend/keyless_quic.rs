@@ -115,6 +115,11 @@ impl KeylessQuicBackendConfig {
                 self.discover_data = DiscoverRegisterData::Yaml(v.clone());
                 Ok(())
             }
+            "discover_data_json" => {
+                self.discover_data = DiscoverRegisterData::parse_json_str(v)
+                    .context(format!("invalid json string value for key {k}"))?;
+                Ok(())
+            }
             "extra_metrics_tags" => {
                 let tags = g3_yaml::value::as_static_metrics_tags(v)
                     .context(format!("invalid static metrics tags value for key {k}"))?;
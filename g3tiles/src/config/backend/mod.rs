@@ -23,11 +23,17 @@ use g3_types::metrics::MetricsName;
 use g3_yaml::{HybridParser, YamlDocPosition};
 
 pub(crate) mod dummy_close;
+pub(crate) mod http_route;
 #[cfg(feature = "quic")]
 pub(crate) mod keyless_quic;
 pub(crate) mod keyless_tcp;
 pub(crate) mod stream_tcp;
 
+mod peer_health;
+pub(crate) use peer_health::{
+    PeerHealthCheckConfig, PeerHealthCheckMethod, PeerOutlierDetectionConfig,
+};
+
 mod registry;
 pub(crate) use registry::{clear, get_all};
 
@@ -56,6 +62,7 @@ pub(crate) enum AnyBackendConfig {
     DummyClose(dummy_close::DummyCloseBackendConfig),
     StreamTcp(stream_tcp::StreamTcpBackendConfig),
     KeylessTcp(keyless_tcp::KeylessTcpBackendConfig),
+    HttpRoute(http_route::HttpRouteBackendConfig),
     #[cfg(feature = "quic")]
     KeylessQuic(keyless_quic::KeylessQuicBackendConfig),
 }
@@ -67,6 +74,7 @@ macro_rules! impl_transparent0 {
                 AnyBackendConfig::DummyClose(s) => s.$f(),
                 AnyBackendConfig::StreamTcp(s) => s.$f(),
                 AnyBackendConfig::KeylessTcp(s) => s.$f(),
+                AnyBackendConfig::HttpRoute(s) => s.$f(),
                 #[cfg(feature = "quic")]
                 AnyBackendConfig::KeylessQuic(s) => s.$f(),
             }
@@ -81,6 +89,7 @@ macro_rules! impl_transparent1 {
                 AnyBackendConfig::DummyClose(s) => s.$f(p),
                 AnyBackendConfig::StreamTcp(s) => s.$f(p),
                 AnyBackendConfig::KeylessTcp(s) => s.$f(p),
+                AnyBackendConfig::HttpRoute(s) => s.$f(p),
                 #[cfg(feature = "quic")]
                 AnyBackendConfig::KeylessQuic(s) => s.$f(p),
             }
@@ -137,6 +146,11 @@ fn load_backend(
                 .context("failed to load this KeylessTcp backend")?;
             Ok(AnyBackendConfig::KeylessTcp(backend))
         }
+        "http_route" | "httproute" => {
+            let backend = http_route::HttpRouteBackendConfig::parse(map, position)
+                .context("failed to load this HttpRoute backend")?;
+            Ok(AnyBackendConfig::HttpRoute(backend))
+        }
         #[cfg(feature = "quic")]
         "keyless_quic" | "keylessquic" => {
             let backend = keyless_quic::KeylessQuicBackendConfig::parse(map, position)
@@ -0,0 +1,190 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PeerHealthCheckMethod {
+    /// only check if a tcp connection can be established
+    TcpConnect,
+    /// complete the tls handshake with the upstream tls client config
+    TlsHandshake,
+}
+
+impl PeerHealthCheckMethod {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            PeerHealthCheckMethod::TcpConnect => "tcp_connect",
+            PeerHealthCheckMethod::TlsHandshake => "tls_handshake",
+        }
+    }
+}
+
+/// Active health check config for the peers of a backend
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PeerHealthCheckConfig {
+    pub(crate) method: PeerHealthCheckMethod,
+    pub(crate) interval: Duration,
+    pub(crate) timeout: Duration,
+    pub(crate) rise: usize,
+    pub(crate) fall: usize,
+}
+
+impl Default for PeerHealthCheckConfig {
+    fn default() -> Self {
+        PeerHealthCheckConfig {
+            method: PeerHealthCheckMethod::TcpConnect,
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(4),
+            rise: 2,
+            fall: 3,
+        }
+    }
+}
+
+impl PeerHealthCheckConfig {
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        match v {
+            Yaml::String(s) => {
+                let mut config = PeerHealthCheckConfig::default();
+                config.set_method(s)?;
+                Ok(config)
+            }
+            Yaml::Hash(map) => Self::parse_map(map),
+            _ => Err(anyhow!(
+                "yaml value type for 'peer health check' should be 'string' or 'map'"
+            )),
+        }
+    }
+
+    fn parse_map(map: &yaml::Hash) -> anyhow::Result<Self> {
+        let mut config = PeerHealthCheckConfig::default();
+
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "method" | "type" => {
+                let method = g3_yaml::value::as_string(v)?;
+                config.set_method(&method)
+            }
+            "interval" => {
+                config.interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "timeout" => {
+                config.timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "rise" | "healthy_threshold" => {
+                config.rise = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "fall" | "unhealthy_threshold" => {
+                config.fall = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        config.check()?;
+        Ok(config)
+    }
+
+    fn set_method(&mut self, method: &str) -> anyhow::Result<()> {
+        self.method = match g3_yaml::key::normalize(method).as_str() {
+            "tcp_connect" | "tcp" => PeerHealthCheckMethod::TcpConnect,
+            "tls_handshake" | "tls" => PeerHealthCheckMethod::TlsHandshake,
+            _ => return Err(anyhow!("unsupported peer health check method {method}")),
+        };
+        Ok(())
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.interval.is_zero() {
+            return Err(anyhow!("interval should not be zero"));
+        }
+        if self.timeout.is_zero() {
+            return Err(anyhow!("timeout should not be zero"));
+        }
+        if self.rise == 0 {
+            return Err(anyhow!("rise count should not be zero"));
+        }
+        if self.fall == 0 {
+            return Err(anyhow!("fall count should not be zero"));
+        }
+        Ok(())
+    }
+}
+
+/// Passive outlier detection config for the peers of a backend
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PeerOutlierDetectionConfig {
+    pub(crate) consecutive_failures: usize,
+    pub(crate) ejection_time: Duration,
+}
+
+impl Default for PeerOutlierDetectionConfig {
+    fn default() -> Self {
+        PeerOutlierDetectionConfig {
+            consecutive_failures: 5,
+            ejection_time: Duration::from_secs(30),
+        }
+    }
+}
+
+impl PeerOutlierDetectionConfig {
+    pub(crate) fn parse_yaml(v: &Yaml) -> anyhow::Result<Self> {
+        let mut config = PeerOutlierDetectionConfig::default();
+        match v {
+            Yaml::Integer(_) => {
+                config.consecutive_failures = g3_yaml::value::as_usize(v)?;
+            }
+            Yaml::Hash(map) => {
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "consecutive_failures" | "consecutive_failure" => {
+                        config.consecutive_failures = g3_yaml::value::as_usize(v)
+                            .context(format!("invalid usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    "ejection_time" | "eject_time" => {
+                        config.ejection_time = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+            }
+            _ => {
+                return Err(anyhow!(
+                    "yaml value type for 'peer outlier detection' should be 'usize' or 'map'"
+                ))
+            }
+        }
+
+        if config.consecutive_failures == 0 {
+            return Err(anyhow!("consecutive failures count should not be zero"));
+        }
+        if config.ejection_time.is_zero() {
+            return Err(anyhow!("ejection time should not be zero"));
+        }
+        Ok(config)
+    }
+}
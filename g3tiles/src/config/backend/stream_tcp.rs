@@ -15,6 +15,7 @@
  */
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context};
 use yaml_rust::{yaml, Yaml};
@@ -22,9 +23,16 @@ use yaml_rust::{yaml, Yaml};
 use g3_histogram::HistogramMetricsConfig;
 use g3_types::collection::SelectivePickPolicy;
 use g3_types::metrics::{MetricsName, StaticMetricsTags};
+use g3_types::net::{
+    Host, OpensslClientConfigBuilder, ProxyProtocolVersion, RustlsClientConfigBuilder,
+    TcpKeepAliveConfig,
+};
 use g3_yaml::YamlDocPosition;
 
-use super::{AnyBackendConfig, BackendConfig, BackendConfigDiffAction};
+use super::{
+    AnyBackendConfig, BackendConfig, BackendConfigDiffAction, PeerHealthCheckConfig,
+    PeerHealthCheckMethod, PeerOutlierDetectionConfig,
+};
 use crate::config::discover::DiscoverRegisterData;
 
 const BACKEND_CONFIG_TYPE: &str = "StreamTcp";
@@ -38,6 +46,14 @@ pub(crate) struct StreamTcpBackendConfig {
     pub(crate) peer_pick_policy: SelectivePickPolicy,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
     pub(crate) duration_stats: HistogramMetricsConfig,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) tcp_keepalive: TcpKeepAliveConfig,
+    pub(crate) proxy_protocol: Option<ProxyProtocolVersion>,
+    pub(crate) rustls_client: Option<RustlsClientConfigBuilder>,
+    pub(crate) openssl_client: Option<OpensslClientConfigBuilder>,
+    pub(crate) tls_name: Option<Host>,
+    pub(crate) health_check: Option<PeerHealthCheckConfig>,
+    pub(crate) outlier_detection: Option<PeerOutlierDetectionConfig>,
 }
 
 impl StreamTcpBackendConfig {
@@ -50,6 +66,14 @@ impl StreamTcpBackendConfig {
             peer_pick_policy: SelectivePickPolicy::Random,
             extra_metrics_tags: None,
             duration_stats: HistogramMetricsConfig::default(),
+            connect_timeout: Duration::from_secs(10),
+            tcp_keepalive: TcpKeepAliveConfig::default(),
+            proxy_protocol: None,
+            rustls_client: None,
+            openssl_client: None,
+            tls_name: None,
+            health_check: None,
+            outlier_detection: None,
         }
     }
 
@@ -73,9 +97,27 @@ impl StreamTcpBackendConfig {
         if matches!(self.discover_data, DiscoverRegisterData::Null) {
             return Err(anyhow!("no discover data set"));
         }
+        if self.rustls_client.is_some() && self.openssl_client.is_some() {
+            return Err(anyhow!(
+                "only one of rustls tls client and openssl tls client can be set"
+            ));
+        }
+        if let Some(health_check) = &self.health_check {
+            if health_check.method == PeerHealthCheckMethod::TlsHandshake && !self.tls_enabled() {
+                return Err(anyhow!(
+                    "tls client should be set if the health check method is {}",
+                    health_check.method.as_str()
+                ));
+            }
+        }
         Ok(())
     }
 
+    #[inline]
+    pub(crate) fn tls_enabled(&self) -> bool {
+        self.rustls_client.is_some() || self.openssl_client.is_some()
+    }
+
     fn set(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
         match k {
             super::CONFIG_KEY_BACKEND_TYPE => Ok(()),
@@ -91,6 +133,11 @@ impl StreamTcpBackendConfig {
                 self.discover_data = DiscoverRegisterData::Yaml(v.clone());
                 Ok(())
             }
+            "discover_data_json" => {
+                self.discover_data = DiscoverRegisterData::parse_json_str(v)
+                    .context(format!("invalid json string value for key {k}"))?;
+                Ok(())
+            }
             "peer_pick_policy" => {
                 self.peer_pick_policy = g3_yaml::value::as_selective_pick_policy(v)?;
                 Ok(())
@@ -107,6 +154,63 @@ impl StreamTcpBackendConfig {
                 )?;
                 Ok(())
             }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "tcp_keepalive" => {
+                self.tcp_keepalive = g3_yaml::value::as_tcp_keepalive_config(v)
+                    .context(format!("invalid tcp keepalive config value for key {k}"))?;
+                Ok(())
+            }
+            "proxy_protocol" => {
+                let p = g3_yaml::value::as_proxy_protocol_version(v)
+                    .context(format!("invalid proxy protocol version value for key {k}"))?;
+                self.proxy_protocol = Some(p);
+                Ok(())
+            }
+            "tls_client" | "rustls_client" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let builder = g3_yaml::value::as_rustls_client_config_builder(v, Some(lookup_dir))
+                    .context(format!(
+                        "invalid rustls tls client config value for key {k}"
+                    ))?;
+                self.rustls_client = Some(builder);
+                Ok(())
+            }
+            "openssl_client" | "openssl_tls_client" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let builder = g3_yaml::value::as_to_one_openssl_tls_client_config_builder(
+                    v,
+                    Some(lookup_dir),
+                )
+                .context(format!(
+                    "invalid openssl tls client config value for key {k}"
+                ))?;
+                self.openssl_client = Some(builder);
+                Ok(())
+            }
+            "tls_name" => {
+                let name = g3_yaml::value::as_host(v)
+                    .context(format!("invalid tls server name value for key {k}"))?;
+                self.tls_name = Some(name);
+                Ok(())
+            }
+            "health_check" => {
+                let config = PeerHealthCheckConfig::parse_yaml(v).context(format!(
+                    "invalid peer health check config value for key {k}"
+                ))?;
+                self.health_check = Some(config);
+                Ok(())
+            }
+            "outlier_detection" | "passive_health_check" => {
+                let config = PeerOutlierDetectionConfig::parse_yaml(v).context(format!(
+                    "invalid peer outlier detection config value for key {k}"
+                ))?;
+                self.outlier_detection = Some(config);
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -0,0 +1,111 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+use yaml_rust::Yaml;
+
+use g3_types::collection::WeightedValue;
+
+pub(crate) fn parse_yaml(input: &Yaml) -> anyhow::Result<Vec<WeightedValue<SocketAddr>>> {
+    let mut addrs = Vec::new();
+    match input {
+        Yaml::Array(seq) => {
+            for (i, v) in seq.iter().enumerate() {
+                let data = g3_yaml::value::as_weighted_sockaddr(v)
+                    .context(format!("invalid weighted socket address value for #{i}"))?;
+                addrs.push(data);
+            }
+        }
+        v => {
+            let data = g3_yaml::value::as_weighted_sockaddr(v)
+                .context("invalid weighted socket address value")?;
+            addrs.push(data);
+        }
+    }
+    Ok(addrs)
+}
+
+pub(crate) fn parse_json(input: &Value) -> anyhow::Result<Vec<WeightedValue<SocketAddr>>> {
+    let mut addrs = Vec::new();
+    match input {
+        Value::Array(seq) => {
+            for (i, v) in seq.iter().enumerate() {
+                let data = g3_json::value::as_weighted_sockaddr(v)
+                    .context(format!("invalid weighted socket address value for #{i}"))?;
+                addrs.push(data);
+            }
+        }
+        v => {
+            let data = g3_json::value::as_weighted_sockaddr(v)
+                .context("invalid weighted socket address value")?;
+            addrs.push(data);
+        }
+    }
+    Ok(addrs)
+}
+
+/// Parse the address list from the content of a yaml file, only the first doc will be used
+pub(crate) fn parse_yaml_str(content: &str) -> anyhow::Result<Vec<WeightedValue<SocketAddr>>> {
+    let docs = yaml_rust::YamlLoader::load_from_str(content)
+        .map_err(|e| anyhow!("invalid yaml content: {e}"))?;
+    match docs.first() {
+        Some(doc) => parse_yaml(doc),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Parse the address list from the content of a json file
+pub(crate) fn parse_json_str(content: &str) -> anyhow::Result<Vec<WeightedValue<SocketAddr>>> {
+    let v: Value =
+        serde_json::from_str(content).map_err(|e| anyhow!("invalid json content: {e}"))?;
+    parse_json(&v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_yaml_and_json() {
+        let docs = yaml_rust::YamlLoader::load_from_str(
+            r#"
+            - 127.0.0.1:80
+            - addr: "[::1]:8080"
+              weight: 2
+            "#,
+        )
+        .unwrap();
+        let yaml_addrs = parse_yaml(&docs[0]).unwrap();
+        assert_eq!(yaml_addrs.len(), 2);
+        assert_eq!(yaml_addrs[1].weight(), 2.0);
+
+        let v: Value =
+            serde_json::from_str(r#"["127.0.0.1:80", {"addr": "[::1]:8080", "weight": 2}]"#)
+                .unwrap();
+        let json_addrs = parse_json(&v).unwrap();
+        assert_eq!(json_addrs, yaml_addrs);
+
+        let content_addrs = parse_json_str(r#"[{"addr": "127.0.0.1:80", "weight": 1}]"#).unwrap();
+        assert_eq!(content_addrs.len(), 1);
+        assert!(parse_yaml_str("").unwrap().is_empty());
+
+        let v = Value::String("127.0.0.1".to_string());
+        assert!(parse_json(&v).is_err());
+    }
+}
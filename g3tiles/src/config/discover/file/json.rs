@@ -0,0 +1,31 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use super::{FileDiscoverConfig, FileDiscoverInput};
+
+impl FileDiscoverConfig {
+    pub(crate) fn parse_json_data(&self, input: &Value) -> anyhow::Result<FileDiscoverInput> {
+        let Value::String(s) = input else {
+            return Err(anyhow!("json value type for 'path' should be 'string'"));
+        };
+        FileDiscoverInput::new(PathBuf::from(s))
+    }
+}
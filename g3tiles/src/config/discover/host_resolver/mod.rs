@@ -25,6 +25,7 @@ use super::{
     CONFIG_KEY_DISCOVER_TYPE,
 };
 
+mod json;
 mod yaml;
 
 const DISCOVER_CONFIG_TYPE: &str = "HostResolver";
@@ -0,0 +1,159 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use http::Uri;
+
+use g3_types::metrics::MetricsName;
+use g3_types::net::UpstreamAddr;
+use g3_yaml::YamlDocPosition;
+
+use super::{
+    AnyDiscoverConfig, DiscoverConfig, DiscoverConfigDiffAction, CONFIG_KEY_DISCOVER_NAME,
+    CONFIG_KEY_DISCOVER_TYPE,
+};
+
+mod json;
+mod yaml;
+
+const DISCOVER_CONFIG_TYPE: &str = "Http";
+
+pub(crate) struct HttpDiscoverInput {
+    pub(crate) addr: UpstreamAddr,
+    pub(crate) host: String,
+    pub(crate) path: String,
+}
+
+impl HttpDiscoverInput {
+    fn parse_url(s: &str) -> anyhow::Result<Self> {
+        let uri = Uri::from_str(s).map_err(|e| anyhow!("invalid url {s}: {e}"))?;
+        match uri.scheme_str() {
+            Some(scheme) if scheme.eq_ignore_ascii_case("http") => {}
+            Some(scheme) => return Err(anyhow!("unsupported url scheme {scheme}")),
+            None => return Err(anyhow!("no scheme found in url {s}")),
+        }
+        let Some(authority) = uri.authority() else {
+            return Err(anyhow!("no host found in url {s}"));
+        };
+        let host = authority.host();
+        let host = host
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host);
+        let port = authority.port_u16().unwrap_or(80);
+        let addr = UpstreamAddr::from_host_str_and_port(host, port)?;
+        let path = uri
+            .path_and_query()
+            .map(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+            .unwrap_or("/")
+            .to_string();
+        Ok(HttpDiscoverInput {
+            addr,
+            host: authority.as_str().to_string(),
+            path,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct HttpDiscoverConfig {
+    name: MetricsName,
+    position: Option<YamlDocPosition>,
+    pub(crate) interval: Duration,
+    pub(crate) timeout: Duration,
+    pub(crate) rsp_header_max_size: usize,
+    pub(crate) rsp_body_max_size: usize,
+}
+
+impl HttpDiscoverConfig {
+    fn new(position: Option<YamlDocPosition>) -> Self {
+        HttpDiscoverConfig {
+            name: MetricsName::default(),
+            position,
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(5),
+            rsp_header_max_size: 16384,
+            rsp_body_max_size: 1 << 20,
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.name.is_empty() {
+            return Err(anyhow!("name is not set"));
+        }
+        if self.interval.is_zero() {
+            return Err(anyhow!("interval should not be zero"));
+        }
+        Ok(())
+    }
+}
+
+impl DiscoverConfig for HttpDiscoverConfig {
+    #[inline]
+    fn name(&self) -> &MetricsName {
+        &self.name
+    }
+
+    #[inline]
+    fn position(&self) -> Option<YamlDocPosition> {
+        self.position.clone()
+    }
+
+    #[inline]
+    fn discover_type(&self) -> &'static str {
+        DISCOVER_CONFIG_TYPE
+    }
+
+    fn diff_action(&self, new: &AnyDiscoverConfig) -> DiscoverConfigDiffAction {
+        let AnyDiscoverConfig::Http(new) = new else {
+            return DiscoverConfigDiffAction::SpawnNew;
+        };
+
+        if self.eq(new) {
+            DiscoverConfigDiffAction::NoAction
+        } else {
+            DiscoverConfigDiffAction::SpawnNew
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url() {
+        let input = HttpDiscoverInput::parse_url("http://127.0.0.1:8080/peers?svc=a").unwrap();
+        assert_eq!(input.addr.to_string(), "127.0.0.1:8080");
+        assert_eq!(input.host, "127.0.0.1:8080");
+        assert_eq!(input.path, "/peers?svc=a");
+
+        let input = HttpDiscoverInput::parse_url("http://[::1]").unwrap();
+        assert_eq!(input.addr.to_string(), "[::1]:80");
+        assert_eq!(input.host, "[::1]");
+        assert_eq!(input.path, "/");
+
+        let input = HttpDiscoverInput::parse_url("http://discover.local/v1/peers").unwrap();
+        assert_eq!(input.addr.to_string(), "discover.local:80");
+
+        assert!(HttpDiscoverInput::parse_url("https://discover.local/v1/peers").is_err());
+        assert!(HttpDiscoverInput::parse_url("/v1/peers").is_err());
+    }
+}
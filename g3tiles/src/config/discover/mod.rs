@@ -25,9 +25,14 @@ use g3_yaml::{HybridParser, YamlDocPosition};
 mod registry;
 pub(crate) use registry::{clear, get_all};
 
+pub(crate) mod file;
 pub(crate) mod host_resolver;
+pub(crate) mod http;
+pub(crate) mod srv;
 pub(crate) mod static_addr;
 
+pub(crate) mod addr_list;
+
 const CONFIG_KEY_DISCOVER_TYPE: &str = "type";
 const CONFIG_KEY_DISCOVER_NAME: &str = "name";
 
@@ -35,10 +40,18 @@ const CONFIG_KEY_DISCOVER_NAME: &str = "name";
 pub(crate) enum DiscoverRegisterData {
     Null,
     Yaml(Yaml),
-    #[allow(unused)]
     Json(serde_json::Value),
 }
 
+impl DiscoverRegisterData {
+    /// Parse the register data from a yaml string value which contains the json text
+    pub(crate) fn parse_json_str(v: &Yaml) -> anyhow::Result<Self> {
+        let s = g3_yaml::value::as_string(v)?;
+        let value = serde_json::from_str(&s).map_err(|e| anyhow!("invalid json string: {e}"))?;
+        Ok(DiscoverRegisterData::Json(value))
+    }
+}
+
 pub(crate) enum DiscoverConfigDiffAction {
     NoAction,
     SpawnNew,
@@ -59,6 +72,9 @@ pub(crate) trait DiscoverConfig {
 pub(crate) enum AnyDiscoverConfig {
     StaticAddr(static_addr::StaticAddrDiscoverConfig),
     HostResolver(host_resolver::HostResolverDiscoverConfig),
+    File(file::FileDiscoverConfig),
+    Http(http::HttpDiscoverConfig),
+    Srv(srv::SrvDiscoverConfig),
 }
 
 macro_rules! impl_transparent0 {
@@ -67,6 +83,9 @@ macro_rules! impl_transparent0 {
             match self {
                 AnyDiscoverConfig::StaticAddr(d) => d.$f(),
                 AnyDiscoverConfig::HostResolver(d) => d.$f(),
+                AnyDiscoverConfig::File(d) => d.$f(),
+                AnyDiscoverConfig::Http(d) => d.$f(),
+                AnyDiscoverConfig::Srv(d) => d.$f(),
             }
         }
     };
@@ -78,6 +97,9 @@ macro_rules! impl_transparent1 {
             match self {
                 AnyDiscoverConfig::StaticAddr(d) => d.$f(p),
                 AnyDiscoverConfig::HostResolver(d) => d.$f(p),
+                AnyDiscoverConfig::File(d) => d.$f(p),
+                AnyDiscoverConfig::Http(d) => d.$f(p),
+                AnyDiscoverConfig::Srv(d) => d.$f(p),
             }
         }
     };
@@ -128,6 +150,21 @@ fn load_discover(
                     .context("failed to load this HostResolver discover")?;
             Ok(AnyDiscoverConfig::HostResolver(discover))
         }
+        "file" => {
+            let discover = file::FileDiscoverConfig::parse_yaml_conf(map, position)
+                .context("failed to load this File discover")?;
+            Ok(AnyDiscoverConfig::File(discover))
+        }
+        "http" => {
+            let discover = http::HttpDiscoverConfig::parse_yaml_conf(map, position)
+                .context("failed to load this Http discover")?;
+            Ok(AnyDiscoverConfig::Http(discover))
+        }
+        "srv" => {
+            let discover = srv::SrvDiscoverConfig::parse_yaml_conf(map, position)
+                .context("failed to load this Srv discover")?;
+            Ok(AnyDiscoverConfig::Srv(discover))
+        }
         _ => Err(anyhow!("unsupported discover type {}", discover_type)),
     }
 }
@@ -0,0 +1,146 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use g3_types::metrics::MetricsName;
+use g3_types::net::DnsEncryptionConfigBuilder;
+use g3_yaml::YamlDocPosition;
+
+use super::{
+    AnyDiscoverConfig, DiscoverConfig, DiscoverConfigDiffAction, CONFIG_KEY_DISCOVER_NAME,
+    CONFIG_KEY_DISCOVER_TYPE,
+};
+
+mod json;
+mod yaml;
+
+const DISCOVER_CONFIG_TYPE: &str = "Srv";
+
+pub(crate) struct SrvDiscoverInput {
+    pub(crate) domain: String,
+}
+
+impl SrvDiscoverInput {
+    fn new(domain: String) -> anyhow::Result<Self> {
+        if domain.is_empty() {
+            return Err(anyhow!("empty srv domain"));
+        }
+        Ok(SrvDiscoverInput { domain })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct SrvDiscoverConfig {
+    name: MetricsName,
+    position: Option<YamlDocPosition>,
+    server: Option<IpAddr>,
+    server_port: Option<u16>,
+    pub(crate) encryption: Option<DnsEncryptionConfigBuilder>,
+    pub(crate) bind_ip: Option<IpAddr>,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) request_timeout: Duration,
+    pub(crate) positive_min_ttl: u32,
+    pub(crate) positive_max_ttl: u32,
+    pub(crate) negative_ttl: u32,
+}
+
+impl SrvDiscoverConfig {
+    fn new(position: Option<YamlDocPosition>) -> Self {
+        SrvDiscoverConfig {
+            name: MetricsName::default(),
+            position,
+            server: None,
+            server_port: None,
+            encryption: None,
+            bind_ip: None,
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(10),
+            positive_min_ttl: 30,
+            positive_max_ttl: 3600,
+            negative_ttl: 30,
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.name.is_empty() {
+            return Err(anyhow!("name is not set"));
+        }
+        if self.server.is_none() {
+            return Err(anyhow!("no dns server set"));
+        }
+        if self.positive_min_ttl == 0 {
+            return Err(anyhow!("positive min ttl should not be zero"));
+        }
+        if self.positive_max_ttl < self.positive_min_ttl {
+            return Err(anyhow!(
+                "positive max ttl should not be less than positive min ttl"
+            ));
+        }
+        if self.negative_ttl == 0 {
+            return Err(anyhow!("negative ttl should not be zero"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn server_addr(&self) -> anyhow::Result<SocketAddr> {
+        let ip = self.server.ok_or_else(|| anyhow!("no dns server set"))?;
+        let port = match self.server_port {
+            Some(port) => port,
+            None => match &self.encryption {
+                Some(c) => c.protocol().default_port(),
+                None => 53,
+            },
+        };
+        Ok(SocketAddr::new(ip, port))
+    }
+
+    pub(crate) fn bind_addr(&self) -> Option<SocketAddr> {
+        self.bind_ip.map(|ip| SocketAddr::new(ip, 0))
+    }
+}
+
+impl DiscoverConfig for SrvDiscoverConfig {
+    #[inline]
+    fn name(&self) -> &MetricsName {
+        &self.name
+    }
+
+    #[inline]
+    fn position(&self) -> Option<YamlDocPosition> {
+        self.position.clone()
+    }
+
+    #[inline]
+    fn discover_type(&self) -> &'static str {
+        DISCOVER_CONFIG_TYPE
+    }
+
+    fn diff_action(&self, new: &AnyDiscoverConfig) -> DiscoverConfigDiffAction {
+        let AnyDiscoverConfig::Srv(new) = new else {
+            return DiscoverConfigDiffAction::SpawnNew;
+        };
+
+        if self.eq(new) {
+            DiscoverConfigDiffAction::NoAction
+        } else {
+            DiscoverConfigDiffAction::SpawnNew
+        }
+    }
+}
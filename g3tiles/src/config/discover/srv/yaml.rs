@@ -0,0 +1,101 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+use g3_yaml::YamlDocPosition;
+
+use super::{SrvDiscoverConfig, SrvDiscoverInput};
+
+impl SrvDiscoverConfig {
+    pub(crate) fn parse_yaml_conf(
+        map: &yaml::Hash,
+        position: Option<YamlDocPosition>,
+    ) -> anyhow::Result<Self> {
+        let mut site = SrvDiscoverConfig::new(position);
+        g3_yaml::foreach_kv(map, |k, v| site.set_yaml(k, v))?;
+        site.check()?;
+        Ok(site)
+    }
+
+    fn set_yaml(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        match k {
+            super::CONFIG_KEY_DISCOVER_TYPE => Ok(()),
+            super::CONFIG_KEY_DISCOVER_NAME => {
+                self.name = g3_yaml::value::as_metrics_name(v)?;
+                Ok(())
+            }
+            "server" => {
+                let ip = g3_yaml::value::as_ipaddr(v)
+                    .context(format!("invalid ip address value for key {k}"))?;
+                self.server = Some(ip);
+                Ok(())
+            }
+            "server_port" => {
+                let port =
+                    g3_yaml::value::as_u16(v).context(format!("invalid u16 value for key {k}"))?;
+                self.server_port = Some(port);
+                Ok(())
+            }
+            "encryption" | "encrypt" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let config =
+                    g3_yaml::value::as_dns_encryption_protocol_builder(v, Some(lookup_dir))
+                        .context(format!("invalid dns encryption config value for key {k}"))?;
+                self.encryption = Some(config);
+                Ok(())
+            }
+            "bind_ip" => {
+                let ip = g3_yaml::value::as_ipaddr(v)
+                    .context(format!("invalid ip address value for key {k}"))?;
+                self.bind_ip = Some(ip);
+                Ok(())
+            }
+            "connect_timeout" => {
+                self.connect_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "request_timeout" => {
+                self.request_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "positive_min_ttl" => {
+                self.positive_min_ttl =
+                    g3_yaml::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
+                Ok(())
+            }
+            "positive_max_ttl" => {
+                self.positive_max_ttl =
+                    g3_yaml::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
+                Ok(())
+            }
+            "negative_ttl" => {
+                self.negative_ttl =
+                    g3_yaml::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        }
+    }
+
+    pub(crate) fn parse_yaml_data(&self, input: &Yaml) -> anyhow::Result<SrvDiscoverInput> {
+        let domain = g3_yaml::value::as_domain(input)?;
+        SrvDiscoverInput::new(domain)
+    }
+}
@@ -27,6 +27,7 @@ use super::{
     CONFIG_KEY_DISCOVER_TYPE,
 };
 
+mod json;
 mod yaml;
 
 const DISCOVER_CONFIG_TYPE: &str = "StaticAddr";
@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use anyhow::{anyhow, Context};
+use anyhow::anyhow;
 use yaml_rust::{yaml, Yaml};
 
 use g3_yaml::YamlDocPosition;
@@ -44,21 +44,7 @@ impl StaticAddrDiscoverConfig {
     }
 
     pub(crate) fn parse_yaml_data(&self, input: &Yaml) -> anyhow::Result<StaticAddrDiscoverInput> {
-        let mut parsed = StaticAddrDiscoverInput::default();
-        match input {
-            Yaml::Array(seq) => {
-                for (i, v) in seq.iter().enumerate() {
-                    let data = g3_yaml::value::as_weighted_sockaddr(v)
-                        .context(format!("invalid weighted socket address value for #{i}"))?;
-                    parsed.inner.push(data);
-                }
-            }
-            v => {
-                let data = g3_yaml::value::as_weighted_sockaddr(v)
-                    .context("invalid weighted socket address value")?;
-                parsed.inner.push(data);
-            }
-        }
-        Ok(parsed)
+        let inner = crate::config::discover::addr_list::parse_yaml(input)?;
+        Ok(StaticAddrDiscoverInput { inner })
     }
 }
@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+use std::time::Duration;
+
 use anyhow::{anyhow, Context};
 use openssl::ssl::{
     SslAcceptor, SslContext, SslContextBuilder, SslSessionCacheMode, SslVerifyMode,
@@ -33,10 +35,68 @@ use g3_yaml::{YamlDocPosition, YamlMapCallback};
 #[cfg(feature = "vendored-tongsuo")]
 use g3_types::net::OpensslTlcpCertificatePair;
 
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+struct OpensslKeylessCertificate {
+    leaf_cert: Vec<u8>,
+    chain_certs: Vec<Vec<u8>>,
+}
+
+impl OpensslKeylessCertificate {
+    fn new(certs: Vec<X509>) -> anyhow::Result<Self> {
+        let mut certs_iter = certs.into_iter();
+        let leaf_cert = certs_iter
+            .next()
+            .ok_or_else(|| anyhow!("no certificate found"))?
+            .to_der()
+            .map_err(|e| anyhow!("failed to encode leaf certificate: {e}"))?;
+        let mut chain_certs = Vec::new();
+        for (i, cert) in certs_iter.enumerate() {
+            let bytes = cert
+                .to_der()
+                .map_err(|e| anyhow!("failed to encode chain certificate #{i}: {e}"))?;
+            chain_certs.push(bytes);
+        }
+        Ok(OpensslKeylessCertificate {
+            leaf_cert,
+            chain_certs,
+        })
+    }
+
+    fn add_to_server_ssl_context(
+        &self,
+        ssl_builder: &mut SslContextBuilder,
+        id_ctx: &mut OpensslSessionIdContext,
+        backend: &MetricsName,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let leaf_cert = X509::from_der(self.leaf_cert.as_slice()).unwrap();
+        ssl_builder
+            .set_certificate(&leaf_cert)
+            .map_err(|e| anyhow!("failed to set certificate: {e}"))?;
+        id_ctx
+            .add_cert(&leaf_cert)
+            .map_err(|e| anyhow!("failed to add cert to session id context: {e}"))?;
+        for (i, cert) in self.chain_certs.iter().enumerate() {
+            let chain_cert = X509::from_der(cert.as_slice()).unwrap();
+            ssl_builder
+                .add_extra_chain_cert(chain_cert)
+                .map_err(|e| anyhow!("failed to add chain certificate #{i}: {e}"))?;
+        }
+        let key = crate::module::keyless::new_openssl_offload_key(backend, &leaf_cert, timeout)?;
+        ssl_builder
+            .set_private_key(&key)
+            .map_err(|e| anyhow!("failed to set keyless private key: {e}"))?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct OpensslHostConfig {
     name: String,
     cert_pairs: Vec<OpensslCertificatePair>,
+    keyless_certs: Vec<OpensslKeylessCertificate>,
+    keyless_backend: Option<MetricsName>,
+    keyless_request_timeout: Duration,
     #[cfg(feature = "vendored-tongsuo")]
     tlcp_cert_pairs: Vec<OpensslTlcpCertificatePair>,
     client_auth: bool,
@@ -49,6 +109,28 @@ pub(crate) struct OpensslHostConfig {
     pub(crate) backends: AlpnMatch<MetricsName>,
 }
 
+impl Default for OpensslHostConfig {
+    fn default() -> Self {
+        OpensslHostConfig {
+            name: String::new(),
+            cert_pairs: Vec::new(),
+            keyless_certs: Vec::new(),
+            keyless_backend: None,
+            keyless_request_timeout: Duration::from_secs(5),
+            #[cfg(feature = "vendored-tongsuo")]
+            tlcp_cert_pairs: Vec::new(),
+            client_auth: false,
+            client_auth_certs: Vec::new(),
+            session_id_context: String::new(),
+            request_alive_max: None,
+            request_rate_limit: None,
+            tcp_sock_speed_limit: None,
+            task_idle_max_count: None,
+            backends: AlpnMatch::default(),
+        }
+    }
+}
+
 impl NamedValue for OpensslHostConfig {
     type Name = str;
     type NameOwned = String;
@@ -125,8 +207,12 @@ impl OpensslHostConfig {
         Ok(())
     }
 
+    pub(crate) fn use_keyless(&self) -> bool {
+        !self.keyless_certs.is_empty()
+    }
+
     pub(crate) fn build_ssl_context(&self) -> anyhow::Result<Option<SslContext>> {
-        if self.cert_pairs.is_empty() {
+        if self.cert_pairs.is_empty() && self.keyless_certs.is_empty() {
             return Ok(None);
         }
 
@@ -157,6 +243,17 @@ impl OpensslHostConfig {
             pair.add_to_server_ssl_context(&mut ssl_builder, &mut id_ctx)
                 .context(format!("failed to add cert pair #{i} to ssl context"))?;
         }
+        if let Some(backend) = &self.keyless_backend {
+            for (i, cert) in self.keyless_certs.iter().enumerate() {
+                cert.add_to_server_ssl_context(
+                    &mut ssl_builder,
+                    &mut id_ctx,
+                    backend,
+                    self.keyless_request_timeout,
+                )
+                .context(format!("failed to add keyless cert #{i} to ssl context"))?;
+            }
+        }
 
         id_ctx
             .build_set(&mut ssl_builder)
@@ -268,6 +365,28 @@ impl YamlMapCallback for OpensslHostConfig {
                 ))?;
                 Ok(())
             }
+            "keyless_certificates" | "keyless_certs" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(doc)?;
+                self.keyless_certs = g3_yaml::value::as_list(value, |v| {
+                    let certs = g3_yaml::value::as_openssl_certificates(v, Some(lookup_dir))?;
+                    OpensslKeylessCertificate::new(certs)
+                })
+                .context(format!(
+                    "invalid openssl certificate chain list value for key {key}"
+                ))?;
+                Ok(())
+            }
+            "keyless_backend" => {
+                let name = g3_yaml::value::as_metrics_name(value)
+                    .context(format!("invalid metrics name value for key {key}"))?;
+                self.keyless_backend = Some(name);
+                Ok(())
+            }
+            "keyless_request_timeout" => {
+                self.keyless_request_timeout = g3_yaml::humanize::as_duration(value)
+                    .context(format!("invalid humanize duration value for key {key}"))?;
+                Ok(())
+            }
             "enable_client_auth" => {
                 self.client_auth = g3_yaml::value::as_bool(value)
                     .context(format!("invalid value for key {key}"))?;
@@ -321,13 +440,28 @@ impl YamlMapCallback for OpensslHostConfig {
             return Err(anyhow!("no name set"));
         }
         #[cfg(not(feature = "vendored-tongsuo"))]
-        if self.cert_pairs.is_empty() {
+        if self.cert_pairs.is_empty() && self.keyless_certs.is_empty() {
             return Err(anyhow!("no certificate set"));
         }
         #[cfg(feature = "vendored-tongsuo")]
-        if self.cert_pairs.is_empty() && self.tlcp_cert_pairs.is_empty() {
+        if self.cert_pairs.is_empty()
+            && self.keyless_certs.is_empty()
+            && self.tlcp_cert_pairs.is_empty()
+        {
             return Err(anyhow!("neither tls nor tlcp certificate set"));
         }
+        if !self.keyless_certs.is_empty() && self.keyless_backend.is_none() {
+            return Err(anyhow!("no keyless backend set for keyless certificates"));
+        }
+        #[cfg(not(feature = "openssl-async-job"))]
+        if self.use_keyless()
+            && g3_daemon::runtime::config::get_runtime_config().is_current_thread()
+        {
+            // the keyless requests will block the handshake thread, see KeylessRemoteKey
+            return Err(anyhow!(
+                "keyless certificates can not be used with a single thread main runtime"
+            ));
+        }
         if self.backends.is_empty() {
             return Err(anyhow!("no backend service set"));
         }
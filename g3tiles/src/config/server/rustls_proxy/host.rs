@@ -35,6 +35,9 @@ use g3_yaml::{YamlDocPosition, YamlMapCallback};
 pub(crate) struct RustlsHostConfig {
     name: String,
     cert_pairs: Vec<RustlsCertificatePair>,
+    keyless_certs: Vec<Vec<Certificate>>,
+    keyless_backend: Option<MetricsName>,
+    keyless_request_timeout: Duration,
     client_auth: bool,
     client_auth_certs: Vec<Certificate>,
     use_session_ticket: bool,
@@ -51,6 +54,9 @@ impl Default for RustlsHostConfig {
         RustlsHostConfig {
             name: String::new(),
             cert_pairs: Vec::with_capacity(1),
+            keyless_certs: Vec::new(),
+            keyless_backend: None,
+            keyless_request_timeout: Duration::from_secs(5),
             client_auth: false,
             client_auth_certs: Vec::new(),
             use_session_ticket: false,
@@ -108,6 +114,17 @@ impl RustlsHostConfig {
                 .push_cert_pair(pair)
                 .context(format!("failed to add cert pair {i}"))?;
         }
+        if let Some(backend) = &self.keyless_backend {
+            for (i, certs) in self.keyless_certs.iter().enumerate() {
+                let ck = crate::module::keyless::new_rustls_offload_key(
+                    backend,
+                    certs,
+                    self.keyless_request_timeout,
+                )
+                .context(format!("failed to add keyless cert {i}"))?;
+                cert_resolver.push_certified_key(ck);
+            }
+        }
         let mut config = config_builder.with_cert_resolver(Arc::new(cert_resolver));
 
         config.session_storage = Arc::new(RustlsServerSessionCache::default());
@@ -151,6 +168,27 @@ impl YamlMapCallback for RustlsHostConfig {
                 .context(format!("invalid rustls cert pair list value for key {key}"))?;
                 Ok(())
             }
+            "keyless_certificates" | "keyless_certs" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(doc)?;
+                self.keyless_certs = g3_yaml::value::as_list(value, |v| {
+                    g3_yaml::value::as_rustls_certificates(v, Some(lookup_dir))
+                })
+                .context(format!(
+                    "invalid rustls certificate chain list value for key {key}"
+                ))?;
+                Ok(())
+            }
+            "keyless_backend" => {
+                let name = g3_yaml::value::as_metrics_name(value)
+                    .context(format!("invalid metrics name value for key {key}"))?;
+                self.keyless_backend = Some(name);
+                Ok(())
+            }
+            "keyless_request_timeout" => {
+                self.keyless_request_timeout = g3_yaml::humanize::as_duration(value)
+                    .context(format!("invalid humanize duration value for key {key}"))?;
+                Ok(())
+            }
             "enable_client_auth" => {
                 self.client_auth = g3_yaml::value::as_bool(value)
                     .context(format!("invalid value for key {key}"))?;
@@ -212,9 +250,20 @@ impl YamlMapCallback for RustlsHostConfig {
         if self.name.is_empty() {
             return Err(anyhow!("no name set"));
         }
-        if self.cert_pairs.is_empty() {
+        if self.cert_pairs.is_empty() && self.keyless_certs.is_empty() {
             return Err(anyhow!("no certificate set"));
         }
+        if !self.keyless_certs.is_empty() && self.keyless_backend.is_none() {
+            return Err(anyhow!("no keyless backend set for keyless certificates"));
+        }
+        if !self.keyless_certs.is_empty()
+            && g3_daemon::runtime::config::get_runtime_config().is_current_thread()
+        {
+            // the keyless requests will block the handshake thread, see KeylessRemoteKey
+            return Err(anyhow!(
+                "keyless certificates can not be used with a single thread main runtime"
+            ));
+        }
         if self.backends.is_empty() {
             return Err(anyhow!("no backend service set"));
         }
@@ -0,0 +1,107 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+use tokio::sync::watch;
+use yaml_rust::Yaml;
+
+use super::{ArcDiscover, Discover, DiscoverResult};
+use crate::config::discover::addr_list;
+use crate::config::discover::file::{FileDiscoverConfig, FileDiscoverInput};
+use crate::config::discover::{AnyDiscoverConfig, DiscoverConfig};
+
+mod watcher;
+use watcher::FileWatcher;
+
+pub(crate) struct FileDiscover {
+    config: FileDiscoverConfig,
+}
+
+impl FileDiscover {
+    pub(crate) fn new_obj(config: FileDiscoverConfig) -> ArcDiscover {
+        Arc::new(FileDiscover { config })
+    }
+
+    fn register_input(
+        &self,
+        input: FileDiscoverInput,
+    ) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let mut watcher = FileWatcher::new(&input.path);
+        let reload_interval = self.config.reload_interval;
+
+        let (sender, receiver) = watch::channel(Ok(Vec::new()));
+        tokio::spawn(async move {
+            // the first tick will complete immediately, so the file will be loaded at start
+            let mut interval = tokio::time::interval(reload_interval);
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = sender.closed() => break,
+                    _ = watcher.changed() => {}
+                    _ = interval.tick() => {}
+                }
+                let r = load_file(&input).await;
+                super::update_result(&sender, r);
+            }
+        });
+        Ok(receiver)
+    }
+}
+
+async fn load_file(input: &FileDiscoverInput) -> DiscoverResult {
+    let content = tokio::fs::read_to_string(&input.path)
+        .await
+        .map_err(|e| anyhow!("failed to read file {}: {e}", input.path.display()))?;
+    let r = if input.is_json() {
+        addr_list::parse_json_str(&content)
+    } else {
+        addr_list::parse_yaml_str(&content)
+    };
+    r.context(format!(
+        "invalid address list in file {}",
+        input.path.display()
+    ))
+}
+
+impl Discover for FileDiscover {
+    fn _clone_config(&self) -> AnyDiscoverConfig {
+        AnyDiscoverConfig::File(self.config.clone())
+    }
+
+    fn _update_config_in_place(&self, _config: AnyDiscoverConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn register_yaml(&self, data: &Yaml) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_yaml_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+
+    fn register_json(&self, data: &Value) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_json_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+
+#[cfg(target_os = "linux")]
+use std::ffi::OsString;
+
+#[cfg(target_os = "linux")]
+use anyhow::anyhow;
+#[cfg(target_os = "linux")]
+use futures_util::StreamExt;
+#[cfg(target_os = "linux")]
+use inotify::{EventStream, Inotify, WatchMask};
+#[cfg(target_os = "linux")]
+use log::warn;
+
+/// Watch the parent directory for the file changes, as the file may be replaced by rename
+#[cfg(target_os = "linux")]
+pub(super) struct FileWatcher {
+    file_name: OsString,
+    event_stream: Option<EventStream<[u8; 1024]>>,
+}
+
+#[cfg(target_os = "linux")]
+impl FileWatcher {
+    pub(super) fn new(path: &Path) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|v| v.to_os_string())
+            .unwrap_or_default();
+        let event_stream = match Self::watch_parent_dir(path) {
+            Ok(stream) => Some(stream),
+            Err(e) => {
+                warn!(
+                    "failed to watch file {}, only periodic reload will be used: {e:?}",
+                    path.display()
+                );
+                None
+            }
+        };
+        FileWatcher {
+            file_name,
+            event_stream,
+        }
+    }
+
+    fn watch_parent_dir(path: &Path) -> anyhow::Result<EventStream<[u8; 1024]>> {
+        let Some(dir) = path.parent() else {
+            return Err(anyhow!("no parent directory found"));
+        };
+
+        let inotify =
+            Inotify::init().map_err(|e| anyhow!("failed to init inotify instance: {e}"))?;
+        inotify
+            .watches()
+            .add(dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)
+            .map_err(|e| anyhow!("failed to watch directory {}: {e}", dir.display()))?;
+        inotify
+            .into_event_stream([0u8; 1024])
+            .map_err(|e| anyhow!("failed to create inotify event stream: {e}"))
+    }
+
+    /// Wait until the file has been written or moved in
+    pub(super) async fn changed(&mut self) {
+        if let Some(stream) = &mut self.event_stream {
+            loop {
+                match stream.next().await {
+                    Some(Ok(event)) => {
+                        if event.name.as_deref() == Some(self.file_name.as_os_str()) {
+                            return;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        warn!("inotify watch failed: {e}");
+                        break;
+                    }
+                    None => {
+                        warn!("inotify watch ended unexpected");
+                        break;
+                    }
+                }
+            }
+            self.event_stream = None;
+        }
+        std::future::pending().await
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) struct FileWatcher {}
+
+#[cfg(not(target_os = "linux"))]
+impl FileWatcher {
+    pub(super) fn new(_path: &Path) -> Self {
+        FileWatcher {}
+    }
+
+    pub(super) async fn changed(&mut self) {
+        std::future::pending().await
+    }
+}
@@ -18,13 +18,16 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
+use serde_json::Value;
 use tokio::sync::watch;
 use yaml_rust::Yaml;
 
 use g3_types::collection::WeightedValue;
 
 use super::{ArcDiscover, Discover, DiscoverResult};
-use crate::config::discover::host_resolver::HostResolverDiscoverConfig;
+use crate::config::discover::host_resolver::{
+    HostResolverDiscoverConfig, HostResolverDiscoverInput,
+};
 use crate::config::discover::{AnyDiscoverConfig, DiscoverConfig};
 
 pub(crate) struct HostResolverDiscover {
@@ -35,22 +38,11 @@ impl HostResolverDiscover {
     pub(crate) fn new_obj(config: HostResolverDiscoverConfig) -> ArcDiscover {
         Arc::new(HostResolverDiscover { config })
     }
-}
-
-impl Discover for HostResolverDiscover {
-    fn _clone_config(&self) -> AnyDiscoverConfig {
-        AnyDiscoverConfig::HostResolver(self.config.clone())
-    }
-
-    fn _update_config_in_place(&self, _config: AnyDiscoverConfig) -> anyhow::Result<()> {
-        Ok(())
-    }
 
-    fn register_yaml(&self, data: &Yaml) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
-        let input = self.config.parse_yaml_data(data).context(format!(
-            "invalid input data for discover {}",
-            self.config.name()
-        ))?;
+    fn register_input(
+        &self,
+        input: HostResolverDiscoverInput,
+    ) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
         let (sender, receiver) = watch::channel(Ok(Vec::new()));
         tokio::spawn(async move {
             let addr = input.addr.to_string();
@@ -71,3 +63,29 @@ impl Discover for HostResolverDiscover {
         Ok(receiver)
     }
 }
+
+impl Discover for HostResolverDiscover {
+    fn _clone_config(&self) -> AnyDiscoverConfig {
+        AnyDiscoverConfig::HostResolver(self.config.clone())
+    }
+
+    fn _update_config_in_place(&self, _config: AnyDiscoverConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn register_yaml(&self, data: &Yaml) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_yaml_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+
+    fn register_json(&self, data: &Value) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_json_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+}
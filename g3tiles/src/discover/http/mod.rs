@@ -0,0 +1,160 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use yaml_rust::Yaml;
+
+use g3_http::client::HttpFetchRequest;
+
+use super::{ArcDiscover, Discover, DiscoverResult, DiscoveredData};
+use crate::config::discover::addr_list;
+use crate::config::discover::http::{HttpDiscoverConfig, HttpDiscoverInput};
+use crate::config::discover::{AnyDiscoverConfig, DiscoverConfig};
+
+pub(crate) struct HttpDiscover {
+    config: Arc<HttpDiscoverConfig>,
+}
+
+impl HttpDiscover {
+    pub(crate) fn new_obj(config: HttpDiscoverConfig) -> ArcDiscover {
+        Arc::new(HttpDiscover {
+            config: Arc::new(config),
+        })
+    }
+
+    fn register_input(
+        &self,
+        input: HttpDiscoverInput,
+    ) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let config = self.config.clone();
+
+        let (sender, receiver) = watch::channel(Ok(Vec::new()));
+        tokio::spawn(async move {
+            let mut fetcher = HttpFetcher::new(config.clone(), input);
+            let mut interval = tokio::time::interval(config.interval);
+            loop {
+                tokio::select! {
+                    biased;
+
+                    _ = sender.closed() => break,
+                    _ = interval.tick() => {}
+                }
+                match tokio::time::timeout(config.timeout, fetcher.fetch()).await {
+                    Ok(Ok(Some(data))) => super::update_result(&sender, Ok(data)),
+                    Ok(Ok(None)) => {}
+                    Ok(Err(e)) => super::update_result(&sender, Err(e)),
+                    Err(_) => super::update_result(
+                        &sender,
+                        Err(anyhow!(
+                            "timed out to fetch data from {}",
+                            fetcher.input.addr
+                        )),
+                    ),
+                }
+            }
+        });
+        Ok(receiver)
+    }
+}
+
+struct HttpFetcher {
+    config: Arc<HttpDiscoverConfig>,
+    input: HttpDiscoverInput,
+    etag: Option<String>,
+}
+
+impl HttpFetcher {
+    fn new(config: Arc<HttpDiscoverConfig>, input: HttpDiscoverInput) -> Self {
+        HttpFetcher {
+            config,
+            input,
+            etag: None,
+        }
+    }
+
+    /// Fetch the latest data, or None if it is not modified since the last fetch
+    async fn fetch(&mut self) -> anyhow::Result<Option<DiscoveredData>> {
+        let addr = &self.input.addr;
+        let stream = TcpStream::connect(addr.to_string())
+            .await
+            .map_err(|e| anyhow!("failed to connect to {addr}: {e}"))?;
+
+        let mut req = HttpFetchRequest::new(
+            &self.input.path,
+            &self.input.host,
+            "application/json, application/yaml",
+        );
+        if let Some(etag) = &self.etag {
+            req.set_etag(etag);
+        }
+        req.set_rsp_header_max_size(self.config.rsp_header_max_size);
+        req.set_rsp_body_max_size(self.config.rsp_body_max_size);
+        let Some(rsp) = req
+            .send(stream)
+            .await
+            .map_err(|e| anyhow!("failed to fetch data from {addr}: {e}"))?
+        else {
+            return Ok(None);
+        };
+        let content = std::str::from_utf8(&rsp.body)
+            .map_err(|e| anyhow!("response body from {addr} is not valid utf-8: {e}"))?;
+
+        let is_json = rsp
+            .content_type
+            .map(|v| v.to_ascii_lowercase().contains("json"))
+            .unwrap_or(false);
+        let data = if is_json {
+            addr_list::parse_json_str(content)
+        } else {
+            addr_list::parse_yaml_str(content)
+        }
+        .context(format!("invalid address list in response from {addr}"))?;
+
+        self.etag = rsp.etag;
+        Ok(Some(data))
+    }
+}
+
+impl Discover for HttpDiscover {
+    fn _clone_config(&self) -> AnyDiscoverConfig {
+        AnyDiscoverConfig::Http(self.config.as_ref().clone())
+    }
+
+    fn _update_config_in_place(&self, _config: AnyDiscoverConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn register_yaml(&self, data: &Yaml) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_yaml_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+
+    fn register_json(&self, data: &Value) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_json_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+}
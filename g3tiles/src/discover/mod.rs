@@ -18,6 +18,7 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use serde_json::Value;
 use tokio::sync::watch;
 use yaml_rust::Yaml;
 
@@ -25,7 +26,10 @@ use g3_types::collection::WeightedValue;
 
 use crate::config::discover::{AnyDiscoverConfig, DiscoverRegisterData};
 
+mod file;
 mod host_resolver;
+mod http;
+mod srv;
 mod static_addr;
 
 mod ops;
@@ -43,6 +47,7 @@ pub(crate) trait Discover {
     fn _update_config_in_place(&self, config: AnyDiscoverConfig) -> anyhow::Result<()>;
 
     fn register_yaml(&self, data: &Yaml) -> anyhow::Result<watch::Receiver<DiscoverResult>>;
+    fn register_json(&self, data: &Value) -> anyhow::Result<watch::Receiver<DiscoverResult>>;
 
     fn register_data(
         &self,
@@ -51,11 +56,22 @@ pub(crate) trait Discover {
         match data {
             DiscoverRegisterData::Null => Err(anyhow!("no valid register data found")),
             DiscoverRegisterData::Yaml(v) => self.register_yaml(v),
-            DiscoverRegisterData::Json(_) => {
-                todo!()
-            }
+            DiscoverRegisterData::Json(v) => self.register_json(v),
         }
     }
 }
 
 pub(crate) type ArcDiscover = Arc<dyn Discover + Send + Sync>;
+
+/// Update the discovered data, the receivers will only be notified if it is really changed
+fn update_result(sender: &watch::Sender<DiscoverResult>, result: DiscoverResult) {
+    sender.send_if_modified(|old| {
+        if let (Ok(old_data), Ok(new_data)) = (&*old, &result) {
+            if old_data == new_data {
+                return false;
+            }
+        }
+        *old = result;
+        true
+    });
+}
@@ -26,7 +26,10 @@ use g3_yaml::YamlDocPosition;
 use super::{registry, ArcDiscover};
 use crate::config::discover::{AnyDiscoverConfig, DiscoverConfigDiffAction};
 
+use super::file::FileDiscover;
 use super::host_resolver::HostResolverDiscover;
+use super::http::HttpDiscover;
+use super::srv::SrvDiscover;
 use super::static_addr::StaticAddrDiscover;
 
 static DISCOVER_OPS_LOCK: Mutex<()> = Mutex::const_new(());
@@ -138,6 +141,9 @@ async fn spawn_new_unlocked(config: AnyDiscoverConfig) -> anyhow::Result<()> {
     let discover = match config {
         AnyDiscoverConfig::StaticAddr(c) => StaticAddrDiscover::new_obj(c),
         AnyDiscoverConfig::HostResolver(c) => HostResolverDiscover::new_obj(c),
+        AnyDiscoverConfig::File(c) => FileDiscover::new_obj(c),
+        AnyDiscoverConfig::Http(c) => HttpDiscover::new_obj(c),
+        AnyDiscoverConfig::Srv(c) => SrvDiscover::new_obj(c)?,
     };
     registry::add(name.clone(), discover);
     crate::backend::update_dependency_to_discover(&name, "spawned").await;
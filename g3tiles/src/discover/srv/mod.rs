@@ -0,0 +1,121 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use hickory_proto::rr::Name;
+use serde_json::Value;
+use tokio::sync::watch;
+use yaml_rust::Yaml;
+
+use g3_hickory_client::DnsClientConfig;
+
+use super::{ArcDiscover, Discover, DiscoverResult};
+use crate::config::discover::srv::{SrvDiscoverConfig, SrvDiscoverInput};
+use crate::config::discover::{AnyDiscoverConfig, DiscoverConfig};
+
+mod query;
+use query::SrvQuery;
+
+pub(crate) struct SrvDiscover {
+    config: SrvDiscoverConfig,
+    client_config: Arc<DnsClientConfig>,
+}
+
+impl SrvDiscover {
+    pub(crate) fn new_obj(config: SrvDiscoverConfig) -> anyhow::Result<ArcDiscover> {
+        let encryption = match &config.encryption {
+            Some(builder) => Some(
+                builder
+                    .build()
+                    .context("failed to build dns encryption config")?,
+            ),
+            None => None,
+        };
+        let client_config = DnsClientConfig {
+            target: config.server_addr()?,
+            bind: config.bind_addr(),
+            encryption,
+            connect_timeout: config.connect_timeout,
+            request_timeout: config.request_timeout,
+        };
+        Ok(Arc::new(SrvDiscover {
+            config,
+            client_config: Arc::new(client_config),
+        }))
+    }
+
+    fn register_input(
+        &self,
+        input: SrvDiscoverInput,
+    ) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let name = Name::from_ascii(&input.domain)
+            .map_err(|e| anyhow!("invalid domain {}: {e}", input.domain))?;
+        let mut query = SrvQuery::new(self.client_config.clone());
+        let min_ttl = self.config.positive_min_ttl;
+        let max_ttl = self.config.positive_max_ttl;
+        let negative_ttl = self.config.negative_ttl;
+
+        let (sender, receiver) = watch::channel(Ok(Vec::new()));
+        tokio::spawn(async move {
+            loop {
+                let ttl = match query.run(&name).await {
+                    Ok((data, ttl)) => {
+                        super::update_result(&sender, Ok(data));
+                        ttl.clamp(min_ttl, max_ttl)
+                    }
+                    Err(e) => {
+                        super::update_result(&sender, Err(e));
+                        negative_ttl
+                    }
+                };
+                match tokio::time::timeout(Duration::from_secs(ttl as u64), sender.closed()).await {
+                    Ok(_) => break,
+                    Err(_) => continue,
+                }
+            }
+        });
+        Ok(receiver)
+    }
+}
+
+impl Discover for SrvDiscover {
+    fn _clone_config(&self) -> AnyDiscoverConfig {
+        AnyDiscoverConfig::Srv(self.config.clone())
+    }
+
+    fn _update_config_in_place(&self, _config: AnyDiscoverConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn register_yaml(&self, data: &Yaml) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_yaml_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+
+    fn register_json(&self, data: &Value) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_json_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
+    }
+}
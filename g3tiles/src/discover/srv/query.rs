@@ -0,0 +1,287 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use hickory_client::client::{AsyncClient, ClientHandle};
+use hickory_proto::op::{Message, ResponseCode};
+use hickory_proto::rr::{DNSClass, Name, RData, Record, RecordType};
+use log::warn;
+
+use g3_hickory_client::DnsClientConfig;
+use g3_types::collection::WeightedValue;
+
+use crate::discover::DiscoveredData;
+
+/// The weight that will be used for records with weight 0, if other records in the same tier
+/// have a positive weight, so they will have a very small chance to be selected
+const ZERO_WEIGHT: f64 = 0.01;
+
+struct SrvTarget {
+    priority: u16,
+    weight: u16,
+    port: u16,
+    addrs: Vec<IpAddr>,
+}
+
+pub(super) struct SrvQuery {
+    config: Arc<DnsClientConfig>,
+    client: Option<AsyncClient>,
+}
+
+impl SrvQuery {
+    pub(super) fn new(config: Arc<DnsClientConfig>) -> Self {
+        SrvQuery {
+            config,
+            client: None,
+        }
+    }
+
+    /// Query the SRV records and resolve the targets, return the addresses in the highest
+    /// priority tier that is not empty, and the min TTL of the SRV and the target address records
+    pub(super) async fn run(&mut self, name: &Name) -> anyhow::Result<(DiscoveredData, u32)> {
+        let msg = self.query(name, RecordType::SRV).await?;
+
+        let mut ttl = u32::MAX;
+        let mut records = Vec::new();
+        for r in msg.answers() {
+            if let Some(RData::SRV(srv)) = r.data() {
+                ttl = ttl.min(r.ttl());
+                records.push(srv.clone());
+            }
+        }
+        if records.is_empty() {
+            return Err(anyhow!("no SRV record found for {name}"));
+        }
+
+        let mut resolved: Vec<(Name, Vec<IpAddr>, u32)> = Vec::new();
+        for r in msg.additionals() {
+            if let Some(ip) = record_to_ip(r) {
+                match resolved.iter_mut().find(|(n, _, _)| n.eq(r.name())) {
+                    Some((_, addrs, addr_ttl)) => {
+                        addrs.push(ip);
+                        *addr_ttl = (*addr_ttl).min(r.ttl());
+                    }
+                    None => resolved.push((r.name().clone(), vec![ip], r.ttl())),
+                }
+            }
+        }
+
+        let mut targets = Vec::with_capacity(records.len());
+        for srv in records {
+            let target = srv.target();
+            if target.is_root() {
+                // the service is decidedly not available at this domain
+                continue;
+            }
+
+            let addrs = match resolved.iter().find(|(n, _, _)| n.eq(target)) {
+                Some((_, addrs, addr_ttl)) => {
+                    ttl = ttl.min(*addr_ttl);
+                    addrs.clone()
+                }
+                None => {
+                    let (addrs, addr_ttl) = self.resolve_target(target).await;
+                    ttl = ttl.min(addr_ttl);
+                    resolved.push((target.clone(), addrs.clone(), addr_ttl));
+                    addrs
+                }
+            };
+            targets.push(SrvTarget {
+                priority: srv.priority(),
+                weight: srv.weight(),
+                port: srv.port(),
+                addrs,
+            });
+        }
+
+        match build_tiers(targets).into_values().find(|v| !v.is_empty()) {
+            Some(data) => Ok((data, ttl)),
+            None => Err(anyhow!("no address resolved for SRV targets of {name}")),
+        }
+    }
+
+    /// Resolve the A and AAAA records of the target, return the addresses and the min TTL of them
+    async fn resolve_target(&mut self, target: &Name) -> (Vec<IpAddr>, u32) {
+        let mut ttl = u32::MAX;
+        let mut addrs = Vec::new();
+        for rtype in [RecordType::A, RecordType::AAAA] {
+            match self.query(target, rtype).await {
+                Ok(msg) => {
+                    for r in msg.answers() {
+                        if let Some(ip) = record_to_ip(r) {
+                            ttl = ttl.min(r.ttl());
+                            addrs.push(ip);
+                        }
+                    }
+                }
+                Err(e) => warn!("failed to resolve SRV target {target}: {e:?}"),
+            }
+        }
+        (addrs, ttl)
+    }
+
+    async fn query(&mut self, name: &Name, rtype: RecordType) -> anyhow::Result<Message> {
+        let mut client = match &self.client {
+            Some(client) => client.clone(),
+            None => {
+                let client = self.config.build_async_client().await?;
+                self.client = Some(client.clone());
+                client
+            }
+        };
+
+        let rsp = match client.query(name.clone(), DNSClass::IN, rtype).await {
+            Ok(rsp) => rsp,
+            Err(e) => {
+                // create a new client in the next query
+                self.client = None;
+                return Err(anyhow!("{rtype} query for {name} failed: {e}"));
+            }
+        };
+        let (mut msg, _) = rsp.into_parts();
+
+        if msg.truncated() && self.config.retry_tcp() {
+            let mut tcp_client = self.config.new_dns_over_tcp_client().await?;
+            let rsp = tcp_client
+                .query(name.clone(), DNSClass::IN, rtype)
+                .await
+                .map_err(|e| anyhow!("{rtype} query for {name} over tcp failed: {e}"))?;
+            (msg, _) = rsp.into_parts();
+        }
+
+        let response_code = msg.response_code();
+        if response_code != ResponseCode::NoError {
+            return Err(anyhow!(
+                "{rtype} query for {name} got response code {response_code}"
+            ));
+        }
+        Ok(msg)
+    }
+}
+
+fn record_to_ip(r: &Record) -> Option<IpAddr> {
+    match r.data()? {
+        RData::A(v) => Some(IpAddr::V4(v.0)),
+        RData::AAAA(v) => Some(IpAddr::V6(v.0)),
+        _ => None,
+    }
+}
+
+/// Group the addresses by priority, the weight of each target will be split among its addresses
+fn build_tiers(targets: Vec<SrvTarget>) -> BTreeMap<u16, DiscoveredData> {
+    let mut tier_targets: BTreeMap<u16, Vec<SrvTarget>> = BTreeMap::new();
+    for t in targets {
+        if !t.addrs.is_empty() {
+            tier_targets.entry(t.priority).or_default().push(t);
+        }
+    }
+
+    let mut tiers = BTreeMap::new();
+    for (priority, targets) in tier_targets {
+        let all_zero = targets.iter().all(|t| t.weight == 0);
+
+        let mut data = Vec::new();
+        for t in targets {
+            let weight = if all_zero {
+                WeightedValue::<SocketAddr>::DEFAULT_WEIGHT
+            } else if t.weight == 0 {
+                ZERO_WEIGHT
+            } else {
+                t.weight as f64
+            };
+            let each_weight = weight / t.addrs.len() as f64;
+            for ip in t.addrs {
+                data.push(WeightedValue::with_weight(
+                    SocketAddr::new(ip, t.port),
+                    each_weight,
+                ));
+            }
+        }
+        tiers.insert(priority, data);
+    }
+    tiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn target(priority: u16, weight: u16, addrs: &[&str]) -> SrvTarget {
+        SrvTarget {
+            priority,
+            weight,
+            port: 8080,
+            addrs: addrs.iter().map(|s| IpAddr::from_str(s).unwrap()).collect(),
+        }
+    }
+
+    fn active(targets: Vec<SrvTarget>) -> DiscoveredData {
+        build_tiers(targets)
+            .into_values()
+            .find(|v| !v.is_empty())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn tier_fallback() {
+        let data = active(vec![
+            target(20, 1, &["192.168.1.3"]),
+            target(10, 1, &[]),
+            target(10, 3, &[]),
+        ]);
+        assert_eq!(data.len(), 1);
+        assert_eq!(
+            *data[0].inner(),
+            SocketAddr::from_str("192.168.1.3:8080").unwrap()
+        );
+
+        let data = active(vec![
+            target(20, 1, &["192.168.1.3"]),
+            target(10, 1, &["192.168.1.1"]),
+        ]);
+        assert_eq!(data.len(), 1);
+        assert_eq!(
+            *data[0].inner(),
+            SocketAddr::from_str("192.168.1.1:8080").unwrap()
+        );
+
+        assert!(active(vec![target(10, 1, &[])]).is_empty());
+    }
+
+    #[test]
+    fn tier_weight() {
+        let data = active(vec![
+            target(10, 4, &["192.168.1.1", "192.168.1.2"]),
+            target(10, 0, &["192.168.1.3"]),
+        ]);
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].weight(), 2.0);
+        assert_eq!(data[1].weight(), 2.0);
+        assert_eq!(data[2].weight(), ZERO_WEIGHT);
+
+        let data = active(vec![
+            target(10, 0, &["192.168.1.1"]),
+            target(10, 0, &["192.168.1.2"]),
+        ]);
+        assert_eq!(data[0].weight(), 1.0);
+        assert_eq!(data[1].weight(), 1.0);
+    }
+}
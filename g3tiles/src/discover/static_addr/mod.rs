@@ -17,11 +17,12 @@
 use std::sync::Arc;
 
 use anyhow::Context;
+use serde_json::Value;
 use tokio::sync::watch;
 use yaml_rust::Yaml;
 
 use super::{ArcDiscover, Discover, DiscoverResult};
-use crate::config::discover::static_addr::StaticAddrDiscoverConfig;
+use crate::config::discover::static_addr::{StaticAddrDiscoverConfig, StaticAddrDiscoverInput};
 use crate::config::discover::{AnyDiscoverConfig, DiscoverConfig};
 
 pub(crate) struct StaticAddrDiscover {
@@ -32,6 +33,16 @@ impl StaticAddrDiscover {
     pub(crate) fn new_obj(config: StaticAddrDiscoverConfig) -> ArcDiscover {
         Arc::new(StaticAddrDiscover { config })
     }
+
+    fn register_input(
+        &self,
+        input: StaticAddrDiscoverInput,
+    ) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let (sender, mut receiver) = watch::channel(Ok(input.inner));
+        receiver.mark_changed();
+        tokio::spawn(async move { sender.closed().await });
+        Ok(receiver)
+    }
 }
 
 impl Discover for StaticAddrDiscover {
@@ -48,9 +59,14 @@ impl Discover for StaticAddrDiscover {
             "invalid input data for discover {}",
             self.config.name()
         ))?;
-        let (sender, mut receiver) = watch::channel(Ok(input.inner));
-        receiver.mark_changed();
-        tokio::spawn(async move { sender.closed().await });
-        Ok(receiver)
+        self.register_input(input)
+    }
+
+    fn register_json(&self, data: &Value) -> anyhow::Result<watch::Receiver<DiscoverResult>> {
+        let input = self.config.parse_json_data(data).context(format!(
+            "invalid input data for discover {}",
+            self.config.name()
+        ))?;
+        self.register_input(input)
     }
 }
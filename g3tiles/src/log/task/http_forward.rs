@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use slog::{slog_info, Logger};
+
+use g3_slog_types::{LtDateTime, LtDuration, LtHttpMethod, LtHttpUri, LtUuid};
+use g3_types::metrics::MetricsName;
+
+use crate::module::http::HttpForwardTaskNotes;
+use crate::serve::{ServerTaskError, ServerTaskNotes};
+
+pub(crate) struct TaskLogForHttpForward<'a> {
+    pub(crate) task_notes: &'a ServerTaskNotes,
+    pub(crate) http_notes: &'a HttpForwardTaskNotes,
+    pub(crate) backend: &'a MetricsName,
+}
+
+impl TaskLogForHttpForward<'_> {
+    pub(crate) fn log(&self, logger: &Logger, e: &ServerTaskError) {
+        slog_info!(logger, "{}", e;
+            "task_type" => "HttpForward",
+            "task_id" => LtUuid(&self.task_notes.id),
+            "stage" => self.task_notes.stage.brief(),
+            "start_at" => LtDateTime(&self.task_notes.start_at),
+            "server_addr" => self.task_notes.server_addr(),
+            "client_addr" => self.task_notes.client_addr(),
+            "backend" => self.backend.as_str(),
+            "route" => self.http_notes.route.as_deref(),
+            "next_peer_addr" => self.http_notes.peer,
+            "reason" => e.brief(),
+            "reuse_connection" => self.http_notes.reuse_connection,
+            "http_version" => format!("{:?}", self.http_notes.version),
+            "method" => LtHttpMethod(&self.http_notes.method),
+            "uri" => LtHttpUri::new(&self.http_notes.uri, self.http_notes.uri_log_max_chars),
+            "rsp_status" => self.http_notes.rsp_status,
+            "origin_status" => self.http_notes.origin_status,
+            "wait_time" => LtDuration(self.task_notes.wait_time),
+            "ready_time" => LtDuration(self.task_notes.ready_time),
+            "dur_req_send_all" => LtDuration(self.http_notes.dur_req_send_all),
+            "dur_rsp_recv_hdr" => LtDuration(self.http_notes.dur_rsp_recv_hdr),
+            "dur_rsp_recv_all" => LtDuration(self.http_notes.dur_rsp_recv_all),
+            "total_time" => LtDuration(self.task_notes.time_elapsed()),
+        )
+    }
+}
@@ -22,6 +22,8 @@ use super::shared::SharedLoggerType;
 
 pub(crate) mod tcp_connect;
 
+pub(crate) mod http_forward;
+
 pub(crate) mod keyless;
 
 pub(crate) fn get_logger(server_type: &str, server_name: &MetricsName) -> Logger {
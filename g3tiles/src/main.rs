@@ -48,6 +48,8 @@ fn main() -> anyhow::Result<()> {
     g3_daemon::daemonize::check_enter(&proc_args.daemon_config)?;
 
     let stat_join = if let Some(stat_config) = g3_daemon::stat::config::get_global_stat_config() {
+        g3_daemon::stat::prometheus::spawn_exporter_thread()?;
+        g3_daemon::stat::otlp::spawn_exporter_thread()?;
         Some(
             g3tiles::stat::spawn_working_threads(stat_config)
                 .context("failed to start stat thread")?,
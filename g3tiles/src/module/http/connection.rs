@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::io::BufReader;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+
+pub(crate) struct HttpUpstreamConnection {
+    pub(crate) route: Arc<str>,
+    pub(crate) peer: SocketAddr,
+    pub(crate) reader: BufReader<OwnedReadHalf>,
+    pub(crate) writer: OwnedWriteHalf,
+    pub(crate) reused: bool,
+}
+
+impl HttpUpstreamConnection {
+    pub(crate) fn new(route: Arc<str>, peer: SocketAddr, stream: TcpStream) -> Self {
+        let (r, w) = stream.into_split();
+        HttpUpstreamConnection {
+            route,
+            peer,
+            reader: BufReader::new(r),
+            writer: w,
+            reused: false,
+        }
+    }
+
+    /// Check if the connection is still usable for the next request.
+    ///
+    /// An idle connection should neither have pending data nor be closed by the peer.
+    pub(crate) fn is_alive(&self) -> bool {
+        if !self.reader.buffer().is_empty() {
+            return false;
+        }
+        let mut buf = [0u8; 1];
+        match self.reader.get_ref().try_read(&mut buf) {
+            Ok(_) => false,
+            Err(e) => e.kind() == io::ErrorKind::WouldBlock,
+        }
+    }
+}
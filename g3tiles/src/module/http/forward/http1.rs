@@ -0,0 +1,446 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use http::{Method, StatusCode};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::time::Instant;
+
+use g3_http::client::HttpTransparentResponse;
+use g3_http::server::{HttpRequestParseError, HttpTransparentRequest};
+use g3_http::{HttpBodyReader, HttpBodyType};
+use g3_io_ext::{LimitedCopy, LimitedCopyConfig, LimitedCopyError};
+
+use super::{local_reply_status, HttpForwardTaskContext};
+use crate::module::http::header::{append_forwarded_headers, host_without_port};
+use crate::module::http::{HttpForwardTaskNotes, HttpRouteRequest, HttpUpstreamConnection};
+use crate::serve::{ServerTaskError, ServerTaskNotes, ServerTaskResult, ServerTaskStage};
+
+/// Forward HTTP/1.x requests on a single client connection to the upstream.
+///
+/// Pipelined requests will be handled one by one, and each request will be logged as a task.
+pub(crate) struct HttpForwardH1Task {
+    ctx: HttpForwardTaskContext,
+}
+
+impl HttpForwardH1Task {
+    pub(crate) fn new(ctx: HttpForwardTaskContext) -> Self {
+        HttpForwardH1Task { ctx }
+    }
+
+    pub(crate) async fn into_running<S>(self, stream: S, wait_time: Duration)
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (clt_r, mut clt_w) = tokio::io::split(stream);
+        let mut clt_r = BufReader::new(clt_r);
+
+        let mut wait_time = wait_time;
+        loop {
+            if !self.wait_next_request(&mut clt_r).await {
+                break;
+            }
+
+            let mut task_notes = ServerTaskNotes::new(self.ctx.cc_info.clone(), wait_time);
+            wait_time = Duration::ZERO;
+
+            let Some(req) = self.recv_request(&mut clt_r, &mut clt_w).await else {
+                break;
+            };
+            let mut http_notes = HttpForwardTaskNotes::new(
+                req.version,
+                req.method.clone(),
+                req.uri.clone(),
+                self.ctx.config.log_uri_max_chars,
+            );
+
+            match self
+                .forward(
+                    &mut clt_r,
+                    &mut clt_w,
+                    req,
+                    &mut task_notes,
+                    &mut http_notes,
+                )
+                .await
+            {
+                Ok(keep_alive) => {
+                    task_notes.stage = ServerTaskStage::Finished;
+                    self.ctx
+                        .log(&task_notes, &http_notes, &ServerTaskError::Finished);
+                    if !keep_alive {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    if http_notes.rsp_status == 0 {
+                        if let Some(status) = local_reply_status(&e) {
+                            if send_local_reply(&mut clt_w, status).await.is_ok() {
+                                http_notes.rsp_status = status.as_u16();
+                            }
+                        }
+                    }
+                    self.ctx.log(&task_notes, &http_notes, &e);
+                    break;
+                }
+            }
+        }
+
+        let _ = clt_w.shutdown().await;
+    }
+
+    /// Wait for the data of the next request, return false if the connection should be closed
+    async fn wait_next_request<R>(&self, clt_r: &mut R) -> bool
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        let idle_duration = self.ctx.idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let idle_since = Instant::now();
+        loop {
+            tokio::select! {
+                biased;
+
+                r = clt_r.fill_buf() => {
+                    return matches!(r, Ok(buf) if !buf.is_empty());
+                }
+                _ = idle_interval.tick() => {
+                    if idle_since.elapsed() >= self.ctx.config.pipeline_read_idle_timeout {
+                        return false;
+                    }
+
+                    if self.ctx.server_quit_policy.force_quit() || !self.ctx.server_stats.is_online() {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn recv_request<R, W>(
+        &self,
+        clt_r: &mut R,
+        clt_w: &mut W,
+    ) -> Option<HttpTransparentRequest>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        match tokio::time::timeout(
+            self.ctx.config.req_hdr_recv_timeout,
+            HttpTransparentRequest::parse(clt_r, self.ctx.config.req_hdr_max_size),
+        )
+        .await
+        {
+            Ok(Ok((req, _head_bytes))) => Some(req),
+            Ok(Err(HttpRequestParseError::ClientClosed | HttpRequestParseError::IoFailed(_))) => {
+                None
+            }
+            Ok(Err(e)) => {
+                let status = e.status_code().unwrap_or(StatusCode::BAD_REQUEST);
+                let _ = send_local_reply(clt_w, status).await;
+                None
+            }
+            Err(_) => {
+                let _ = send_local_reply(clt_w, StatusCode::REQUEST_TIMEOUT).await;
+                None
+            }
+        }
+    }
+
+    /// Forward the request and the response, return whether the client connection can be reused
+    async fn forward<CR, CW>(
+        &self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        mut req: HttpTransparentRequest,
+        task_notes: &mut ServerTaskNotes,
+        http_notes: &mut HttpForwardTaskNotes,
+    ) -> ServerTaskResult<bool>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        task_notes.stage = ServerTaskStage::Preparing;
+
+        // the 100-continue expectation will be handled locally
+        let expect_continue = req
+            .end_to_end_headers
+            .remove(http::header::EXPECT)
+            .map(|v| v.to_str().eq_ignore_ascii_case("100-continue"))
+            .unwrap_or(false);
+        if self.ctx.config.append_forwarded_headers {
+            append_forwarded_headers(&mut req.end_to_end_headers, task_notes.client_ip());
+        }
+
+        task_notes.stage = ServerTaskStage::Connecting;
+        let route_req = HttpRouteRequest {
+            host: req
+                .end_to_end_headers
+                .get(http::header::HOST)
+                .map(|v| host_without_port(v.to_str())),
+            path: req.uri.path(),
+            headers: &req.end_to_end_headers,
+        };
+        let mut ups_c = self
+            .ctx
+            .backend
+            .http_connect(task_notes, &route_req)
+            .await?;
+        http_notes.set_connection(&ups_c);
+        task_notes.stage = ServerTaskStage::Connected;
+
+        task_notes.mark_relaying();
+        self.send_request(clt_r, clt_w, &req, expect_continue, &mut ups_c)
+            .await?;
+        http_notes.mark_req_send_all();
+
+        let mut rsp = self
+            .recv_final_response(clt_w, &mut ups_c, &req.method, req.keep_alive())
+            .await?;
+        http_notes.origin_status = rsp.code;
+        http_notes.mark_rsp_recv_hdr();
+
+        if rsp.code == 101 {
+            if !req.upgrade {
+                return Err(ServerTaskError::UnclassifiedError(anyhow!(
+                    "unexpected 101 response to a non upgrade request"
+                )));
+            }
+            task_notes.stage = ServerTaskStage::Replying;
+            clt_w
+                .write_all(rsp.serialize().as_ref())
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+            http_notes.rsp_status = rsp.code;
+            http_notes.mark_rsp_no_body();
+            return self.relay_upgraded(clt_r, clt_w, ups_c).await;
+        }
+
+        let body_type = rsp.body_type(&req.method);
+        let keep_alive = req.keep_alive()
+            && rsp.keep_alive()
+            && !matches!(body_type, Some(HttpBodyType::ReadUntilEnd));
+        if !keep_alive {
+            rsp.set_no_keep_alive();
+        }
+
+        task_notes.stage = ServerTaskStage::Replying;
+        clt_w
+            .write_all(rsp.serialize().as_ref())
+            .await
+            .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+        http_notes.rsp_status = rsp.code;
+
+        if let Some(body_type) = body_type {
+            let mut body_reader = HttpBodyReader::new(
+                &mut ups_c.reader,
+                body_type,
+                self.ctx.config.body_line_max_len,
+            );
+            let copy_config = LimitedCopyConfig::default();
+            let copy = LimitedCopy::new(&mut body_reader, clt_w, &copy_config);
+            self.ctx.run_transfer(copy).await?.map_err(|e| match e {
+                LimitedCopyError::ReadFailed(e) => ServerTaskError::UpstreamReadFailed(e),
+                LimitedCopyError::WriteFailed(e) => ServerTaskError::ClientTcpWriteFailed(e),
+            })?;
+            http_notes.mark_rsp_recv_all();
+        } else {
+            clt_w
+                .flush()
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+            http_notes.mark_rsp_no_body();
+        }
+
+        if keep_alive {
+            self.ctx.backend.http_save_connection(ups_c);
+        }
+        Ok(keep_alive)
+    }
+
+    async fn send_request<CR, CW>(
+        &self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        req: &HttpTransparentRequest,
+        expect_continue: bool,
+        ups_c: &mut HttpUpstreamConnection,
+    ) -> ServerTaskResult<()>
+    where
+        CR: AsyncBufRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        ups_c
+            .writer
+            .write_all(req.serialize_for_origin().as_ref())
+            .await
+            .map_err(ServerTaskError::UpstreamWriteFailed)?;
+
+        let Some(body_type) = req.body_type() else {
+            return ups_c
+                .writer
+                .flush()
+                .await
+                .map_err(ServerTaskError::UpstreamWriteFailed);
+        };
+
+        if expect_continue {
+            clt_w
+                .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+            clt_w
+                .flush()
+                .await
+                .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+        }
+
+        let mut body_reader =
+            HttpBodyReader::new(clt_r, body_type, self.ctx.config.body_line_max_len);
+        let copy_config = LimitedCopyConfig::default();
+        let copy = LimitedCopy::new(&mut body_reader, &mut ups_c.writer, &copy_config);
+        self.ctx.run_transfer(copy).await?.map_err(|e| match e {
+            LimitedCopyError::ReadFailed(e) => ServerTaskError::ClientTcpReadFailed(e),
+            LimitedCopyError::WriteFailed(e) => ServerTaskError::UpstreamWriteFailed(e),
+        })?;
+        Ok(())
+    }
+
+    /// Receive the final response, the informational responses will be sent to the client directly
+    async fn recv_final_response<CW>(
+        &self,
+        clt_w: &mut CW,
+        ups_c: &mut HttpUpstreamConnection,
+        method: &Method,
+        keep_alive: bool,
+    ) -> ServerTaskResult<HttpTransparentResponse>
+    where
+        CW: AsyncWrite + Unpin,
+    {
+        loop {
+            let rsp = match tokio::time::timeout(
+                self.ctx.config.rsp_hdr_recv_timeout,
+                HttpTransparentResponse::parse(
+                    &mut ups_c.reader,
+                    method,
+                    keep_alive,
+                    self.ctx.config.rsp_hdr_max_size,
+                ),
+            )
+            .await
+            {
+                Ok(Ok((rsp, _head_bytes))) => rsp,
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => {
+                    return Err(ServerTaskError::UpstreamAppTimeout(
+                        "timeout to receive response header",
+                    ))
+                }
+            };
+
+            if rsp.code < 200 && rsp.code != 101 {
+                clt_w
+                    .write_all(rsp.serialize().as_ref())
+                    .await
+                    .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                clt_w
+                    .flush()
+                    .await
+                    .map_err(ServerTaskError::ClientTcpWriteFailed)?;
+                continue;
+            }
+            return Ok(rsp);
+        }
+    }
+
+    async fn relay_upgraded<CR, CW>(
+        &self,
+        clt_r: &mut CR,
+        clt_w: &mut CW,
+        mut ups_c: HttpUpstreamConnection,
+    ) -> ServerTaskResult<bool>
+    where
+        CR: AsyncRead + Unpin,
+        CW: AsyncWrite + Unpin,
+    {
+        let copy_config = LimitedCopyConfig::default();
+        let mut clt_to_ups = LimitedCopy::new(clt_r, &mut ups_c.writer, &copy_config);
+        let mut ups_to_clt = LimitedCopy::new(&mut ups_c.reader, clt_w, &copy_config);
+
+        let idle_duration = self.ctx.idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        loop {
+            tokio::select! {
+                biased;
+
+                r = &mut clt_to_ups => {
+                    let _ = ups_to_clt.write_flush().await;
+                    return match r {
+                        Ok(_) => Err(ServerTaskError::ClosedByClient),
+                        Err(LimitedCopyError::ReadFailed(e)) => Err(ServerTaskError::ClientTcpReadFailed(e)),
+                        Err(LimitedCopyError::WriteFailed(e)) => Err(ServerTaskError::UpstreamWriteFailed(e)),
+                    };
+                }
+                r = &mut ups_to_clt => {
+                    let _ = clt_to_ups.write_flush().await;
+                    return match r {
+                        Ok(_) => Err(ServerTaskError::ClosedByUpstream),
+                        Err(LimitedCopyError::ReadFailed(e)) => Err(ServerTaskError::UpstreamReadFailed(e)),
+                        Err(LimitedCopyError::WriteFailed(e)) => Err(ServerTaskError::ClientTcpWriteFailed(e)),
+                    };
+                }
+                _ = idle_interval.tick() => {
+                    if clt_to_ups.is_idle() && ups_to_clt.is_idle() {
+                        idle_count += 1;
+
+                        if idle_count >= self.ctx.idle_max_count {
+                            return Err(ServerTaskError::Idle(idle_duration, idle_count));
+                        }
+                    } else {
+                        idle_count = 0;
+
+                        clt_to_ups.reset_active();
+                        ups_to_clt.reset_active();
+                    }
+
+                    if self.ctx.server_quit_policy.force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit)
+                    }
+                }
+            };
+        }
+    }
+}
+
+async fn send_local_reply<W>(clt_w: &mut W, status: StatusCode) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let rsp = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("")
+    );
+    clt_w.write_all(rsp.as_bytes()).await?;
+    clt_w.flush().await
+}
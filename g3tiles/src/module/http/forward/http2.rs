@@ -0,0 +1,420 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use h2::server::{Connection, SendResponse};
+use h2::{Reason, RecvStream, SendStream};
+use http::{Method, Request, Response, StatusCode, Version};
+use log::debug;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::time::Instant;
+
+use g3_h2::{
+    H2BodyEncodeTransfer, H2StreamBodyEncodeTransferError, H2StreamFromChunkedTransfer,
+    H2StreamFromChunkedTransferError, H2StreamToChunkedTransfer, H2StreamToChunkedTransferError,
+};
+use g3_http::client::HttpTransparentResponse;
+use g3_http::{HttpBodyReader, HttpBodyType};
+use g3_io_ext::LimitedCopyConfig;
+
+use super::{local_reply_status, HttpForwardTaskContext};
+use crate::module::http::header::{
+    append_forwarded_headers, h2_request_headers, host_without_port, serialize_h1_request,
+};
+use crate::module::http::{HttpForwardTaskNotes, HttpRouteRequest, HttpUpstreamConnection};
+use crate::serve::{ServerTaskError, ServerTaskNotes, ServerTaskResult, ServerTaskStage};
+
+/// Serve a HTTP/2 client connection, each stream will be forwarded as a HTTP/1.1 request.
+pub(crate) struct HttpForwardH2ConnectionTask {
+    ctx: Arc<HttpForwardTaskContext>,
+    alive_streams: Arc<AtomicI32>,
+}
+
+impl HttpForwardH2ConnectionTask {
+    pub(crate) fn new(ctx: HttpForwardTaskContext) -> Self {
+        HttpForwardH2ConnectionTask {
+            ctx: Arc::new(ctx),
+            alive_streams: Arc::new(AtomicI32::new(0)),
+        }
+    }
+
+    pub(crate) async fn into_running<S>(self, stream: S, wait_time: Duration)
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let config = &self.ctx.config;
+
+        let mut server_builder = h2::server::Builder::new();
+        server_builder
+            .max_header_list_size(u32::try_from(config.req_hdr_max_size).unwrap_or(u32::MAX))
+            .max_concurrent_streams(config.h2_max_concurrent_streams);
+
+        let mut h2c = match tokio::time::timeout(
+            config.req_hdr_recv_timeout,
+            server_builder.handshake::<S, Bytes>(stream),
+        )
+        .await
+        {
+            Ok(Ok(h2c)) => h2c,
+            Ok(Err(e)) => {
+                debug!(
+                    "{} - {} h2 handshake error: {e}",
+                    self.ctx.cc_info.sock_local_addr(),
+                    self.ctx.cc_info.sock_peer_addr()
+                );
+                return;
+            }
+            Err(_) => {
+                debug!(
+                    "{} - {} h2 handshake timeout",
+                    self.ctx.cc_info.sock_local_addr(),
+                    self.ctx.cc_info.sock_peer_addr()
+                );
+                return;
+            }
+        };
+
+        // only the first stream will take the wait time of the connection
+        let mut wait_time = wait_time;
+        let idle_duration = self.ctx.idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_since = Instant::now();
+        let mut graceful_shutdown = false;
+
+        loop {
+            tokio::select! {
+                biased;
+
+                r = h2c.accept() => {
+                    match r {
+                        Some(Ok((req, send_rsp))) => {
+                            self.handle_stream(req, send_rsp, wait_time);
+                            wait_time = Duration::ZERO;
+                        }
+                        Some(Err(e)) => {
+                            debug!(
+                                "{} - {} h2 connection error: {e}",
+                                self.ctx.cc_info.sock_local_addr(),
+                                self.ctx.cc_info.sock_peer_addr()
+                            );
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+                _ = idle_interval.tick() => {
+                    if self.alive_streams.load(Ordering::Relaxed) > 0 {
+                        idle_since = Instant::now();
+                    } else if !graceful_shutdown
+                        && idle_since.elapsed() >= config.pipeline_read_idle_timeout
+                    {
+                        h2c.graceful_shutdown();
+                        graceful_shutdown = true;
+                    }
+
+                    if self.ctx.server_quit_policy.force_quit() {
+                        abrupt_shutdown(h2c, Reason::CANCEL).await;
+                        break;
+                    }
+
+                    if !graceful_shutdown && !self.ctx.server_stats.is_online() {
+                        h2c.graceful_shutdown();
+                        graceful_shutdown = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_stream(
+        &self,
+        req: Request<RecvStream>,
+        mut send_rsp: SendResponse<Bytes>,
+        wait_time: Duration,
+    ) {
+        let (parts, clt_r) = req.into_parts();
+        if parts.method == Method::CONNECT {
+            send_local_response(&mut send_rsp, StatusCode::NOT_IMPLEMENTED);
+            return;
+        }
+
+        let task = HttpForwardH2StreamTask {
+            ctx: Arc::clone(&self.ctx),
+            task_notes: ServerTaskNotes::new(self.ctx.cc_info.clone(), wait_time),
+            http_notes: HttpForwardTaskNotes::new(
+                Version::HTTP_2,
+                parts.method.clone(),
+                parts.uri.clone(),
+                self.ctx.config.log_uri_max_chars,
+            ),
+        };
+        let alive_streams = Arc::clone(&self.alive_streams);
+        alive_streams.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(async move {
+            task.into_running(parts, clt_r, send_rsp).await;
+            alive_streams.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+}
+
+struct HttpForwardH2StreamTask {
+    ctx: Arc<HttpForwardTaskContext>,
+    task_notes: ServerTaskNotes,
+    http_notes: HttpForwardTaskNotes,
+}
+
+impl HttpForwardH2StreamTask {
+    async fn into_running(
+        mut self,
+        parts: http::request::Parts,
+        clt_r: RecvStream,
+        mut send_rsp: SendResponse<Bytes>,
+    ) {
+        match self.run(parts, clt_r, &mut send_rsp).await {
+            Ok(_) => {
+                self.task_notes.stage = ServerTaskStage::Finished;
+                self.ctx.log(
+                    &self.task_notes,
+                    &self.http_notes,
+                    &ServerTaskError::Finished,
+                );
+            }
+            Err(e) => {
+                if self.http_notes.rsp_status == 0 {
+                    if let Some(status) = local_reply_status(&e) {
+                        if send_local_response(&mut send_rsp, status) {
+                            self.http_notes.rsp_status = status.as_u16();
+                        }
+                    }
+                }
+                self.ctx.log(&self.task_notes, &self.http_notes, &e);
+            }
+        }
+    }
+
+    async fn run(
+        &mut self,
+        parts: http::request::Parts,
+        mut clt_r: RecvStream,
+        send_rsp: &mut SendResponse<Bytes>,
+    ) -> ServerTaskResult<()> {
+        self.task_notes.stage = ServerTaskStage::Preparing;
+
+        let has_body = !clt_r.is_end_stream();
+        let mut headers = h2_request_headers(&parts, has_body);
+        if self.ctx.config.append_forwarded_headers {
+            append_forwarded_headers(&mut headers, self.task_notes.client_ip());
+        }
+
+        self.task_notes.stage = ServerTaskStage::Connecting;
+        let route_req = HttpRouteRequest {
+            host: headers
+                .get(http::header::HOST)
+                .map(|v| host_without_port(v.to_str())),
+            path: parts.uri.path(),
+            headers: &headers,
+        };
+        let mut ups_c = self
+            .ctx
+            .backend
+            .http_connect(&self.task_notes, &route_req)
+            .await?;
+        self.http_notes.set_connection(&ups_c);
+        self.task_notes.stage = ServerTaskStage::Connected;
+
+        self.task_notes.mark_relaying();
+        let head = serialize_h1_request(&parts.method, &parts.uri, &headers, has_body);
+        ups_c
+            .writer
+            .write_all(head.as_ref())
+            .await
+            .map_err(ServerTaskError::UpstreamWriteFailed)?;
+        if has_body {
+            let copy_config = LimitedCopyConfig::default();
+            let transfer = H2StreamToChunkedTransfer::new(
+                &mut clt_r,
+                &mut ups_c.writer,
+                copy_config.yield_size(),
+            );
+            self.ctx
+                .run_transfer(transfer)
+                .await?
+                .map_err(|e| match e {
+                    H2StreamToChunkedTransferError::WriteError(e) => {
+                        ServerTaskError::UpstreamWriteFailed(e)
+                    }
+                    H2StreamToChunkedTransferError::RecvDataFailed(e)
+                    | H2StreamToChunkedTransferError::RecvTrailerFailed(e) => {
+                        ServerTaskError::ClientTcpReadFailed(io::Error::other(e))
+                    }
+                })?;
+        }
+        ups_c
+            .writer
+            .flush()
+            .await
+            .map_err(ServerTaskError::UpstreamWriteFailed)?;
+        self.http_notes.mark_req_send_all();
+
+        let rsp = self.recv_final_response(&mut ups_c, &parts.method).await?;
+        self.http_notes.origin_status = rsp.code;
+        self.http_notes.mark_rsp_recv_hdr();
+
+        let status = StatusCode::from_u16(rsp.code).map_err(|_| {
+            ServerTaskError::UnclassifiedError(anyhow!("invalid status code {}", rsp.code))
+        })?;
+        let body_type = rsp.body_type(&parts.method);
+        let mut response = Response::new(());
+        *response.status_mut() = status;
+        *response.headers_mut() = rsp.end_to_end_headers.to_h2_map();
+
+        self.task_notes.stage = ServerTaskStage::Replying;
+        let mut send_stream = send_rsp
+            .send_response(response, body_type.is_none())
+            .map_err(|e| ServerTaskError::ClientTcpWriteFailed(io::Error::other(e)))?;
+        self.http_notes.rsp_status = rsp.code;
+
+        let keep_alive = rsp.keep_alive();
+        match body_type {
+            Some(body_type) => {
+                self.send_response_body(&mut ups_c, &mut send_stream, body_type)
+                    .await?;
+                self.http_notes.mark_rsp_recv_all();
+            }
+            None => self.http_notes.mark_rsp_no_body(),
+        }
+
+        if keep_alive && !matches!(body_type, Some(HttpBodyType::ReadUntilEnd)) {
+            self.ctx.backend.http_save_connection(ups_c);
+        }
+        Ok(())
+    }
+
+    /// Receive the final response, the informational responses will be dropped
+    async fn recv_final_response(
+        &self,
+        ups_c: &mut HttpUpstreamConnection,
+        method: &Method,
+    ) -> ServerTaskResult<HttpTransparentResponse> {
+        loop {
+            let rsp = match tokio::time::timeout(
+                self.ctx.config.rsp_hdr_recv_timeout,
+                HttpTransparentResponse::parse(
+                    &mut ups_c.reader,
+                    method,
+                    true,
+                    self.ctx.config.rsp_hdr_max_size,
+                ),
+            )
+            .await
+            {
+                Ok(Ok((rsp, _head_bytes))) => rsp,
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => {
+                    return Err(ServerTaskError::UpstreamAppTimeout(
+                        "timeout to receive response header",
+                    ))
+                }
+            };
+
+            if rsp.code == 101 {
+                return Err(ServerTaskError::UnclassifiedError(anyhow!(
+                    "unexpected 101 response to a HTTP/2 request"
+                )));
+            }
+            if rsp.code >= 200 {
+                return Ok(rsp);
+            }
+        }
+    }
+
+    async fn send_response_body(
+        &self,
+        ups_c: &mut HttpUpstreamConnection,
+        send_stream: &mut SendStream<Bytes>,
+        body_type: HttpBodyType,
+    ) -> ServerTaskResult<()> {
+        let copy_config = LimitedCopyConfig::default();
+        if body_type == HttpBodyType::Chunked {
+            let transfer = H2StreamFromChunkedTransfer::new(
+                &mut ups_c.reader,
+                send_stream,
+                &copy_config,
+                self.ctx.config.body_line_max_len,
+                self.ctx.config.rsp_hdr_max_size,
+            );
+            self.ctx.run_transfer(transfer).await?.map_err(|e| match e {
+                H2StreamFromChunkedTransferError::ReadError(e) => {
+                    ServerTaskError::UpstreamReadFailed(e)
+                }
+                H2StreamFromChunkedTransferError::SendDataFailed(e)
+                | H2StreamFromChunkedTransferError::SendTrailerFailed(e) => {
+                    ServerTaskError::ClientTcpWriteFailed(io::Error::other(e))
+                }
+            })
+        } else {
+            let mut body_reader = HttpBodyReader::new(
+                &mut ups_c.reader,
+                body_type,
+                self.ctx.config.body_line_max_len,
+            );
+            let transfer = H2BodyEncodeTransfer::new(&mut body_reader, send_stream, &copy_config);
+            self.ctx
+                .run_transfer(transfer)
+                .await?
+                .map_err(|e| match e {
+                    H2StreamBodyEncodeTransferError::ReadError(e) => {
+                        ServerTaskError::UpstreamReadFailed(e)
+                    }
+                    H2StreamBodyEncodeTransferError::SendDataFailed(e) => {
+                        ServerTaskError::ClientTcpWriteFailed(io::Error::other(e))
+                    }
+                })?;
+            send_stream
+                .send_data(Bytes::new(), true)
+                .map_err(|e| ServerTaskError::ClientTcpWriteFailed(io::Error::other(e)))
+        }
+    }
+}
+
+fn send_local_response(send_rsp: &mut SendResponse<Bytes>, status: StatusCode) -> bool {
+    let mut rsp = Response::new(());
+    *rsp.status_mut() = status;
+    send_rsp.send_response(rsp, true).is_ok()
+}
+
+async fn abrupt_shutdown<T>(mut h2c: Connection<T, Bytes>, reason: Reason)
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    h2c.abrupt_shutdown(reason);
+
+    while let Some(r) = h2c.accept().await {
+        match r {
+            Ok((_req, mut send_rsp)) => {
+                send_rsp.send_reset(reason);
+            }
+            Err(_) => break,
+        }
+    }
+}
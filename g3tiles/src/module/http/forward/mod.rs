@@ -0,0 +1,184 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::StatusCode;
+use slog::Logger;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+
+use g3_daemon::server::{ClientConnectionInfo, ServerQuitPolicy};
+use g3_h2::{H2BodyEncodeTransfer, H2StreamFromChunkedTransfer, H2StreamToChunkedTransfer};
+use g3_io_ext::LimitedCopy;
+use g3_types::net::ConnectError;
+
+use super::HttpForwardTaskNotes;
+use crate::backend::ArcBackend;
+use crate::config::backend::http_route::HttpForwardConfig;
+use crate::log::task::http_forward::TaskLogForHttpForward;
+use crate::serve::{ArcServerStats, ServerTaskError, ServerTaskNotes, ServerTaskResult};
+
+mod http1;
+pub(crate) use http1::HttpForwardH1Task;
+
+mod http2;
+pub(crate) use http2::HttpForwardH2ConnectionTask;
+
+pub(crate) struct HttpForwardTaskContext {
+    pub(crate) backend: ArcBackend,
+    pub(crate) config: Arc<HttpForwardConfig>,
+    pub(crate) cc_info: ClientConnectionInfo,
+    pub(crate) task_logger: Logger,
+    pub(crate) server_stats: ArcServerStats,
+    pub(crate) server_quit_policy: Arc<ServerQuitPolicy>,
+    pub(crate) idle_check_duration: Duration,
+    pub(crate) idle_max_count: i32,
+}
+
+impl HttpForwardTaskContext {
+    fn log(
+        &self,
+        task_notes: &ServerTaskNotes,
+        http_notes: &HttpForwardTaskNotes,
+        e: &ServerTaskError,
+    ) {
+        TaskLogForHttpForward {
+            task_notes,
+            http_notes,
+            backend: self.backend.name(),
+        }
+        .log(&self.task_logger, e);
+    }
+
+    /// Drive the transfer to the end, the returned error will only be idle or quit error
+    async fn run_transfer<T>(&self, mut transfer: T) -> ServerTaskResult<T::Output>
+    where
+        T: IdleCheckTransfer,
+    {
+        let idle_duration = self.idle_check_duration;
+        let mut idle_interval =
+            tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+        let mut idle_count = 0;
+        loop {
+            tokio::select! {
+                biased;
+
+                r = &mut transfer => return Ok(r),
+                _ = idle_interval.tick() => {
+                    if transfer.is_idle() {
+                        idle_count += 1;
+
+                        if idle_count >= self.idle_max_count {
+                            return Err(ServerTaskError::Idle(idle_duration, idle_count));
+                        }
+                    } else {
+                        idle_count = 0;
+
+                        transfer.reset_active();
+                    }
+
+                    if self.server_quit_policy.force_quit() {
+                        return Err(ServerTaskError::CanceledAsServerQuit);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The status code of the local reply if no response has been sent to the client yet
+fn local_reply_status(e: &ServerTaskError) -> Option<StatusCode> {
+    match e {
+        ServerTaskError::HttpRouteNotMatched => Some(StatusCode::NOT_FOUND),
+        ServerTaskError::UpstreamNotResolved | ServerTaskError::CanceledAsServerQuit => {
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        ServerTaskError::UpstreamNotConnected(ConnectError::TimedOut)
+        | ServerTaskError::UpstreamAppTimeout(_) => Some(StatusCode::GATEWAY_TIMEOUT),
+        ServerTaskError::UpstreamNotConnected(_)
+        | ServerTaskError::UpstreamTlsHandshakeFailed(_)
+        | ServerTaskError::UpstreamTlsHandshakeTimeout
+        | ServerTaskError::UpstreamReadFailed(_)
+        | ServerTaskError::UpstreamWriteFailed(_)
+        | ServerTaskError::InvalidUpstreamResponse(_)
+        | ServerTaskError::ClosedByUpstream => Some(StatusCode::BAD_GATEWAY),
+        ServerTaskError::InternalServerError(_) | ServerTaskError::UnclassifiedError(_) => {
+            Some(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        _ => None,
+    }
+}
+
+trait IdleCheckTransfer: Future + Unpin {
+    fn is_idle(&self) -> bool;
+    fn reset_active(&mut self);
+}
+
+impl<R, W> IdleCheckTransfer for LimitedCopy<'_, R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    fn is_idle(&self) -> bool {
+        LimitedCopy::is_idle(self)
+    }
+
+    fn reset_active(&mut self) {
+        LimitedCopy::reset_active(self)
+    }
+}
+
+impl<W> IdleCheckTransfer for H2StreamToChunkedTransfer<'_, W>
+where
+    W: AsyncWrite + Unpin,
+{
+    fn is_idle(&self) -> bool {
+        H2StreamToChunkedTransfer::is_idle(self)
+    }
+
+    fn reset_active(&mut self) {
+        H2StreamToChunkedTransfer::reset_active(self)
+    }
+}
+
+impl<R> IdleCheckTransfer for H2StreamFromChunkedTransfer<'_, R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    fn is_idle(&self) -> bool {
+        H2StreamFromChunkedTransfer::is_idle(self)
+    }
+
+    fn reset_active(&mut self) {
+        H2StreamFromChunkedTransfer::reset_active(self)
+    }
+}
+
+impl<R> IdleCheckTransfer for H2BodyEncodeTransfer<'_, R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn is_idle(&self) -> bool {
+        H2BodyEncodeTransfer::is_idle(self)
+    }
+
+    fn reset_active(&mut self) {
+        H2BodyEncodeTransfer::reset_active(self)
+    }
+}
@@ -0,0 +1,197 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::Write;
+use std::net::IpAddr;
+
+use bytes::BufMut;
+use http::{HeaderName, Method, Uri};
+
+use g3_types::net::{HttpHeaderMap, HttpHeaderValue};
+
+const HEADER_X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const HEADER_X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+const HEADER_X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// Append the client ip to X-Forwarded-For, and set X-Forwarded-Proto and X-Forwarded-Host
+pub(super) fn append_forwarded_headers(headers: &mut HttpHeaderMap, client_ip: IpAddr) {
+    let mut forwarded_for = String::with_capacity(64);
+    for v in headers.get_all(HEADER_X_FORWARDED_FOR) {
+        forwarded_for.push_str(v.to_str());
+        forwarded_for.push_str(", ");
+    }
+    forwarded_for.push_str(&client_ip.to_string());
+    // the existing values and the ip address are all valid header value chars
+    headers.insert(HEADER_X_FORWARDED_FOR, unsafe {
+        HttpHeaderValue::from_string_unchecked(forwarded_for)
+    });
+
+    // the client connection is always secured by tls
+    headers.insert(
+        HEADER_X_FORWARDED_PROTO,
+        HttpHeaderValue::from_static("https"),
+    );
+
+    if let Some(host) = headers.get(http::header::HOST) {
+        // create a new value, or the original header name of Host will be used
+        let host = unsafe { HttpHeaderValue::from_string_unchecked(host.to_str().to_string()) };
+        headers.insert(HEADER_X_FORWARDED_HOST, host);
+    }
+}
+
+/// Convert the headers of a HTTP/2 request to HTTP/1.1 end-to-end headers.
+///
+/// The body will always be sent in chunked encoding, so the content-length header is removed.
+/// The split cookie fields will be concatenated into one, see RFC 9113 Section 8.2.3.
+pub(super) fn h2_request_headers(parts: &http::request::Parts, has_body: bool) -> HttpHeaderMap {
+    let mut headers = HttpHeaderMap::default();
+    let mut cookie = Vec::new();
+    for (name, value) in &parts.headers {
+        match name.as_str() {
+            "connection" | "keep-alive" | "proxy-connection" | "transfer-encoding" | "upgrade"
+            | "te" => continue,
+            "content-length" if has_body => continue,
+            "cookie" => {
+                if !cookie.is_empty() {
+                    cookie.extend_from_slice(b"; ");
+                }
+                cookie.extend_from_slice(value.as_bytes());
+                continue;
+            }
+            _ => {}
+        }
+        // the h2 header value can be used directly in HTTP/1.1
+        let value = unsafe { HttpHeaderValue::from_buf_unchecked(value.as_bytes().to_vec()) };
+        headers.append(name.clone(), value);
+    }
+    if !cookie.is_empty() {
+        // the h2 header values and the separator are all valid header value chars
+        let cookie = unsafe { HttpHeaderValue::from_buf_unchecked(cookie) };
+        headers.insert(http::header::COOKIE, cookie);
+    }
+    if !headers.contains_key(http::header::HOST) {
+        if let Some(authority) = parts.uri.authority() {
+            let host = unsafe { HttpHeaderValue::from_string_unchecked(authority.to_string()) };
+            headers.insert(http::header::HOST, host);
+        }
+    }
+    headers
+}
+
+pub(super) fn serialize_h1_request(
+    method: &Method,
+    uri: &Uri,
+    headers: &HttpHeaderMap,
+    chunked_body: bool,
+) -> Vec<u8> {
+    let mut buf = Vec::<u8>::with_capacity(1024);
+    match uri.path_and_query() {
+        Some(pa) => {
+            let _ = write!(buf, "{method} {pa} HTTP/1.1\r\n");
+        }
+        None => {
+            let _ = write!(buf, "{method} / HTTP/1.1\r\n");
+        }
+    }
+    headers.for_each(|name, value| value.write_to_buf(name, &mut buf));
+    if chunked_body {
+        buf.put_slice(b"Transfer-Encoding: chunked\r\n");
+    }
+    buf.put_slice(b"\r\n");
+    buf
+}
+
+/// Get the host name without port from the Host header value or the uri authority
+pub(super) fn host_without_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        // ipv6 address
+        match host.find(']') {
+            Some(p) => &host[1..p],
+            None => host,
+        }
+    } else {
+        match host.rfind(':') {
+            Some(p) => &host[..p],
+            None => host,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn forwarded_headers() {
+        let mut headers = HttpHeaderMap::default();
+        headers.append(
+            http::header::HOST,
+            HttpHeaderValue::from_static("www.example.com:8443"),
+        );
+        headers.append(
+            HEADER_X_FORWARDED_FOR,
+            HttpHeaderValue::from_static("192.0.2.1"),
+        );
+        append_forwarded_headers(&mut headers, IpAddr::from_str("192.0.2.2").unwrap());
+
+        assert_eq!(
+            headers.get(HEADER_X_FORWARDED_FOR).unwrap().to_str(),
+            "192.0.2.1, 192.0.2.2"
+        );
+        assert_eq!(
+            headers.get(HEADER_X_FORWARDED_PROTO).unwrap().to_str(),
+            "https"
+        );
+        assert_eq!(
+            headers.get(HEADER_X_FORWARDED_HOST).unwrap().to_str(),
+            "www.example.com:8443"
+        );
+    }
+
+    #[test]
+    fn h2_split_cookie() {
+        let req = http::Request::builder()
+            .uri("https://www.example.com/")
+            .header(http::header::COOKIE, "a=b")
+            .header(http::header::ACCEPT, "*/*")
+            .header(http::header::COOKIE, "c=d")
+            .header(http::header::COOKIE, "e=f")
+            .body(())
+            .unwrap();
+        let (parts, _) = req.into_parts();
+        let headers = h2_request_headers(&parts, false);
+
+        assert_eq!(headers.get_all(http::header::COOKIE).iter().count(), 1);
+        assert_eq!(
+            headers.get(http::header::COOKIE).unwrap().to_str(),
+            "a=b; c=d; e=f"
+        );
+        assert_eq!(headers.get(http::header::ACCEPT).unwrap().to_str(), "*/*");
+        assert_eq!(
+            headers.get(http::header::HOST).unwrap().to_str(),
+            "www.example.com"
+        );
+    }
+
+    #[test]
+    fn strip_port() {
+        assert_eq!(host_without_port("www.example.com:8443"), "www.example.com");
+        assert_eq!(host_without_port("www.example.com"), "www.example.com");
+        assert_eq!(host_without_port("[2001:db8::1]:443"), "2001:db8::1");
+        assert_eq!(host_without_port("192.0.2.1"), "192.0.2.1");
+    }
+}
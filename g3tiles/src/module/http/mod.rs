@@ -0,0 +1,41 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_types::net::HttpHeaderMap;
+
+mod connection;
+pub(crate) use connection::HttpUpstreamConnection;
+
+mod error;
+pub(crate) use error::HttpConnectError;
+
+mod header;
+
+mod notes;
+pub(crate) use notes::HttpForwardTaskNotes;
+
+mod forward;
+pub(crate) use forward::{HttpForwardH1Task, HttpForwardH2ConnectionTask, HttpForwardTaskContext};
+
+pub(crate) type HttpConnectResult = Result<HttpUpstreamConnection, HttpConnectError>;
+
+/// The request info used to select the route
+pub(crate) struct HttpRouteRequest<'a> {
+    /// the host without port
+    pub(crate) host: Option<&'a str>,
+    pub(crate) path: &'a str,
+    pub(crate) headers: &'a HttpHeaderMap,
+}
@@ -0,0 +1,87 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{Method, Uri, Version};
+use tokio::time::Instant;
+
+use super::HttpUpstreamConnection;
+
+pub(crate) struct HttpForwardTaskNotes {
+    pub(crate) version: Version,
+    pub(crate) method: Method,
+    pub(crate) uri: Uri,
+    pub(crate) uri_log_max_chars: usize,
+    pub(crate) route: Option<Arc<str>>,
+    pub(crate) peer: Option<SocketAddr>,
+    pub(crate) reuse_connection: bool,
+    pub(crate) rsp_status: u16,
+    pub(crate) origin_status: u16,
+    create_ins: Instant,
+    pub(crate) dur_req_send_all: Duration,
+    pub(crate) dur_rsp_recv_hdr: Duration,
+    pub(crate) dur_rsp_recv_all: Duration,
+}
+
+impl HttpForwardTaskNotes {
+    pub(crate) fn new(
+        version: Version,
+        method: Method,
+        uri: Uri,
+        uri_log_max_chars: usize,
+    ) -> Self {
+        HttpForwardTaskNotes {
+            version,
+            method,
+            uri,
+            uri_log_max_chars,
+            route: None,
+            peer: None,
+            reuse_connection: false,
+            rsp_status: 0,
+            origin_status: 0,
+            create_ins: Instant::now(),
+            dur_req_send_all: Duration::default(),
+            dur_rsp_recv_hdr: Duration::default(),
+            dur_rsp_recv_all: Duration::default(),
+        }
+    }
+
+    pub(crate) fn set_connection(&mut self, connection: &HttpUpstreamConnection) {
+        self.route = Some(connection.route.clone());
+        self.peer = Some(connection.peer);
+        self.reuse_connection = connection.reused;
+    }
+
+    pub(crate) fn mark_req_send_all(&mut self) {
+        self.dur_req_send_all = self.create_ins.elapsed();
+    }
+
+    pub(crate) fn mark_rsp_recv_hdr(&mut self) {
+        self.dur_rsp_recv_hdr = self.create_ins.elapsed();
+    }
+
+    pub(crate) fn mark_rsp_no_body(&mut self) {
+        self.dur_rsp_recv_all = self.dur_rsp_recv_hdr;
+    }
+
+    pub(crate) fn mark_rsp_recv_all(&mut self) {
+        self.dur_rsp_recv_all = self.create_ins.elapsed();
+    }
+}
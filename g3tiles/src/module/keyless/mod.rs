@@ -20,6 +20,9 @@ pub(crate) use protocol::*;
 mod stats;
 pub(crate) use stats::{KeylessRelaySnapshot, KeylessRelayStats};
 
+mod offload;
+pub(crate) use offload::{new_openssl_offload_key, new_rustls_offload_key};
+
 mod backend;
 #[cfg(feature = "quic")]
 pub(crate) use backend::KeylessUpstreamConnection;
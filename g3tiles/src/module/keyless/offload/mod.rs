@@ -0,0 +1,140 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use openssl::pkey::{HasPublic, PKey};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::sync::Semaphore;
+
+use g3_tls_cert::ext::PublicKeyExt;
+use g3_types::metrics::MetricsName;
+
+use super::KeylessRequest;
+
+mod openssl_key;
+pub(crate) use openssl_key::new_openssl_offload_key;
+
+mod rustls_key;
+pub(crate) use rustls_key::new_rustls_offload_key;
+
+mod opcode {
+    pub(super) const RSA_DECRYPT: u8 = 0x01;
+    pub(super) const RSA_SIGN_SHA256: u8 = 0x05;
+    pub(super) const RSA_SIGN_SHA384: u8 = 0x06;
+    pub(super) const RSA_SIGN_SHA512: u8 = 0x07;
+    pub(super) const RSA_DECRYPT_RAW: u8 = 0x08;
+    pub(super) const ECDSA_SIGN_SHA1: u8 = 0x13;
+    pub(super) const ECDSA_SIGN_SHA224: u8 = 0x14;
+    pub(super) const ECDSA_SIGN_SHA256: u8 = 0x15;
+    pub(super) const ECDSA_SIGN_SHA384: u8 = 0x16;
+    pub(super) const ECDSA_SIGN_SHA512: u8 = 0x17;
+    pub(super) const ED25519_SIGN: u8 = 0x18;
+    pub(super) const RSA_PSS_SIGN_SHA256: u8 = 0x35;
+    pub(super) const RSA_PSS_SIGN_SHA384: u8 = 0x36;
+    pub(super) const RSA_PSS_SIGN_SHA512: u8 = 0x37;
+}
+
+/// The max number of keyless requests that may block their handshake threads at the same time
+const MAX_BLOCKING_WAITS: usize = 64;
+
+static BLOCKING_WAIT_SEMAPHORE: Semaphore = Semaphore::const_new(MAX_BLOCKING_WAITS);
+
+/// A private key which is held by the keyless server behind the keyless backend
+struct KeylessRemoteKey {
+    backend: MetricsName,
+    ski: Vec<u8>,
+    timeout: Duration,
+}
+
+impl KeylessRemoteKey {
+    fn new<T: HasPublic>(
+        backend: &MetricsName,
+        public_key: &PKey<T>,
+        timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        let ski = public_key
+            .ski()
+            .map_err(|e| anyhow!("failed to get SKI of the public key: {e}"))?;
+        Ok(KeylessRemoteKey {
+            backend: backend.clone(),
+            ski: ski.to_vec(),
+            timeout,
+        })
+    }
+
+    /// Send the request to the keyless backend and wait for the response.
+    ///
+    /// If we are running inside an OpenSSL async job, the job will be paused until the response
+    /// is received, so the handshake thread won't be blocked.
+    ///
+    /// Otherwise the TLS libraries only support sync key operations, and the current thread will
+    /// be blocked. If we are on a multi-thread runtime, the other tasks on this worker will be
+    /// moved to other threads before blocking, so the keyless backend task won't be starved.
+    /// The number of concurrent blocking waits is limited by [MAX_BLOCKING_WAITS].
+    fn request(&self, opcode: u8, payload: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let req = KeylessRequest::new(opcode, &self.ski, payload)
+            .ok_or_else(|| anyhow!("too large keyless request payload"))?;
+        let rt_handle = g3_daemon::runtime::main_handle()
+            .ok_or_else(|| anyhow!("no main runtime available"))?;
+        let backend = crate::backend::get_or_insert_default(&self.backend);
+
+        #[cfg(feature = "openssl-async-job")]
+        if let Some((pauser, waker)) = g3_openssl::async_job::AsyncJobPauser::new()
+            .map_err(|e| anyhow!("failed to pause the openssl async job: {e}"))?
+        {
+            let (rsp_sender, rsp_receiver) = mpsc::sync_channel(1);
+            let timeout = self.timeout;
+            rt_handle.spawn(async move {
+                let rsp = tokio::time::timeout(timeout, backend.keyless(req)).await;
+                let _ = rsp_sender.send(rsp);
+                waker.wake();
+            });
+
+            let rsp = pauser
+                .pause_until(|| rsp_receiver.try_recv().ok())
+                .ok_or_else(|| anyhow!("keyless request task dropped"))?
+                .map_err(|_| anyhow!("no keyless response received in time"))?;
+            return rsp
+                .into_data()
+                .context(format!("keyless backend {} failed", self.backend));
+        }
+
+        let _permit = BLOCKING_WAIT_SEMAPHORE
+            .try_acquire()
+            .map_err(|_| anyhow!("too many concurrent blocking keyless requests"))?;
+
+        let (rsp_sender, rsp_receiver) = mpsc::sync_channel(1);
+        rt_handle.spawn(async move {
+            let rsp = backend.keyless(req).await;
+            let _ = rsp_sender.send(rsp);
+        });
+
+        let wait = || {
+            rsp_receiver
+                .recv_timeout(self.timeout)
+                .map_err(|_| anyhow!("no keyless response received in time"))
+        };
+        let rsp = match Handle::try_current().map(|h| h.runtime_flavor()) {
+            Ok(RuntimeFlavor::MultiThread) => tokio::task::block_in_place(wait)?,
+            _ => wait()?,
+        };
+        rsp.into_data()
+            .context(format!("keyless backend {} failed", self.backend))
+    }
+}
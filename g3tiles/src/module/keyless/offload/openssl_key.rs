@@ -0,0 +1,79 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use openssl::pkey::{PKey, Private};
+use openssl::x509::X509Ref;
+
+#[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+use g3_openssl::pkey::PrivateKeyOffload;
+use g3_types::metrics::MetricsName;
+
+#[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+use super::{opcode, KeylessRemoteKey};
+
+#[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+impl PrivateKeyOffload for KeylessRemoteKey {
+    fn rsa_private_raw(&self, from: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.request(opcode::RSA_DECRYPT_RAW, from)
+    }
+
+    fn rsa_decrypt_pkcs1(&self, from: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.request(opcode::RSA_DECRYPT, from)
+    }
+
+    fn ecdsa_sign(&self, digest: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let opcode = match digest.len() {
+            20 => opcode::ECDSA_SIGN_SHA1,
+            28 => opcode::ECDSA_SIGN_SHA224,
+            32 => opcode::ECDSA_SIGN_SHA256,
+            48 => opcode::ECDSA_SIGN_SHA384,
+            64 => opcode::ECDSA_SIGN_SHA512,
+            n => return Err(anyhow!("unsupported digest length {n}")),
+        };
+        self.request(opcode, digest)
+    }
+}
+
+/// Create a private key for the certificate, all private key operations will be sent to the
+/// keyless backend
+#[cfg(not(any(feature = "vendored-aws-lc", feature = "vendored-boringssl")))]
+pub(crate) fn new_openssl_offload_key(
+    backend: &MetricsName,
+    cert: &X509Ref,
+    timeout: Duration,
+) -> anyhow::Result<PKey<Private>> {
+    let public_key = cert
+        .public_key()
+        .map_err(|e| anyhow!("failed to get public key from certificate: {e}"))?;
+    let key = KeylessRemoteKey::new(backend, &public_key, timeout)?;
+    g3_openssl::pkey::new_offload_private_key(cert, Arc::new(key))
+}
+
+#[cfg(any(feature = "vendored-aws-lc", feature = "vendored-boringssl"))]
+pub(crate) fn new_openssl_offload_key(
+    _backend: &MetricsName,
+    _cert: &X509Ref,
+    _timeout: Duration,
+) -> anyhow::Result<PKey<Private>> {
+    Err(anyhow!(
+        "keyless private key is not supported by the current openssl variant"
+    ))
+}
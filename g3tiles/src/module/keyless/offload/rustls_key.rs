@@ -0,0 +1,343 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::Id;
+use openssl::x509::X509;
+use rustls::sign::{CertifiedKey, Signer, SigningKey};
+use rustls::{Certificate, SignatureAlgorithm, SignatureScheme};
+
+use g3_types::metrics::MetricsName;
+
+use super::{opcode, KeylessRemoteKey};
+
+const RSA_SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::RSA_PSS_SHA512,
+    SignatureScheme::RSA_PSS_SHA384,
+    SignatureScheme::RSA_PSS_SHA256,
+    SignatureScheme::RSA_PKCS1_SHA512,
+    SignatureScheme::RSA_PKCS1_SHA384,
+    SignatureScheme::RSA_PKCS1_SHA256,
+];
+
+struct KeylessSigningKey {
+    key: Arc<KeylessRemoteKey>,
+    algorithm: SignatureAlgorithm,
+    schemes: &'static [SignatureScheme],
+}
+
+impl SigningKey for KeylessSigningKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        self.schemes
+            .iter()
+            .find(|scheme| offered.contains(scheme))
+            .map(|scheme| {
+                Box::new(KeylessSigner {
+                    key: self.key.clone(),
+                    scheme: *scheme,
+                }) as Box<dyn Signer>
+            })
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+}
+
+struct KeylessSigner {
+    key: Arc<KeylessRemoteKey>,
+    scheme: SignatureScheme,
+}
+
+impl KeylessSigner {
+    fn sign_request(&self, message: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let (opcode, digest) = match self.scheme {
+            SignatureScheme::RSA_PKCS1_SHA256 => {
+                (opcode::RSA_SIGN_SHA256, Some(MessageDigest::sha256()))
+            }
+            SignatureScheme::RSA_PKCS1_SHA384 => {
+                (opcode::RSA_SIGN_SHA384, Some(MessageDigest::sha384()))
+            }
+            SignatureScheme::RSA_PKCS1_SHA512 => {
+                (opcode::RSA_SIGN_SHA512, Some(MessageDigest::sha512()))
+            }
+            SignatureScheme::RSA_PSS_SHA256 => {
+                (opcode::RSA_PSS_SIGN_SHA256, Some(MessageDigest::sha256()))
+            }
+            SignatureScheme::RSA_PSS_SHA384 => {
+                (opcode::RSA_PSS_SIGN_SHA384, Some(MessageDigest::sha384()))
+            }
+            SignatureScheme::RSA_PSS_SHA512 => {
+                (opcode::RSA_PSS_SIGN_SHA512, Some(MessageDigest::sha512()))
+            }
+            SignatureScheme::ECDSA_NISTP256_SHA256 => {
+                (opcode::ECDSA_SIGN_SHA256, Some(MessageDigest::sha256()))
+            }
+            SignatureScheme::ECDSA_NISTP384_SHA384 => {
+                (opcode::ECDSA_SIGN_SHA384, Some(MessageDigest::sha384()))
+            }
+            SignatureScheme::ECDSA_NISTP521_SHA512 => {
+                (opcode::ECDSA_SIGN_SHA512, Some(MessageDigest::sha512()))
+            }
+            SignatureScheme::ED25519 => (opcode::ED25519_SIGN, None),
+            scheme => return Err(anyhow!("unsupported signature scheme {scheme:?}")),
+        };
+
+        match digest {
+            Some(md) => {
+                let digest = openssl::hash::hash(md, message)
+                    .map_err(|e| anyhow!("failed to get message digest: {e}"))?;
+                self.key.request(opcode, &digest)
+            }
+            None => self.key.request(opcode, message),
+        }
+    }
+}
+
+impl Signer for KeylessSigner {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        self.sign_request(message)
+            .map_err(|e| rustls::Error::General(format!("keyless sign failed: {e:?}")))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+/// Create a certified key for the certificate chain, the signing will be done on the
+/// keyless backend
+pub(crate) fn new_rustls_offload_key(
+    backend: &MetricsName,
+    certs: &[Certificate],
+    timeout: Duration,
+) -> anyhow::Result<CertifiedKey> {
+    let leaf_cert = certs
+        .first()
+        .ok_or_else(|| anyhow!("no certificate found"))?;
+    let leaf_cert = X509::from_der(&leaf_cert.0)
+        .map_err(|e| anyhow!("failed to parse leaf certificate: {e}"))?;
+    let public_key = leaf_cert
+        .public_key()
+        .map_err(|e| anyhow!("failed to get public key from certificate: {e}"))?;
+
+    let (algorithm, schemes): (SignatureAlgorithm, &'static [SignatureScheme]) =
+        match public_key.id() {
+            Id::RSA => (SignatureAlgorithm::RSA, RSA_SCHEMES),
+            Id::EC => {
+                let ec_key = public_key
+                    .ec_key()
+                    .map_err(|e| anyhow!("failed to get ec public key: {e}"))?;
+                let scheme = match ec_key.group().curve_name() {
+                    Some(Nid::X9_62_PRIME256V1) => &[SignatureScheme::ECDSA_NISTP256_SHA256],
+                    Some(Nid::SECP384R1) => &[SignatureScheme::ECDSA_NISTP384_SHA384],
+                    Some(Nid::SECP521R1) => &[SignatureScheme::ECDSA_NISTP521_SHA512],
+                    _ => return Err(anyhow!("unsupported ec curve")),
+                };
+                (SignatureAlgorithm::ECDSA, scheme)
+            }
+            Id::ED25519 => (SignatureAlgorithm::ED25519, &[SignatureScheme::ED25519]),
+            id => return Err(anyhow!("unsupported public key type {id:?}")),
+        };
+
+    let key = KeylessRemoteKey::new(backend, &public_key, timeout)?;
+    let signing_key = KeylessSigningKey {
+        key: Arc::new(key),
+        algorithm,
+        schemes,
+    };
+    Ok(CertifiedKey::new(certs.to_vec(), Arc::new(signing_key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use async_trait::async_trait;
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::ecdsa::EcdsaSig;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use rustls::{ClientConfig, RootCertStore, ServerConfig, ServerName};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+    use g3_types::net::MultipleCertResolver;
+
+    use crate::backend::{ArcBackend, Backend};
+    use crate::config::backend::dummy_close::DummyCloseBackendConfig;
+    use crate::config::backend::AnyBackendConfig;
+    use crate::module::keyless::{KeylessRequest, KeylessResponse, KeylessUpstreamResponse};
+
+    const SERVER_NAME: &str = "keyless.example.net";
+
+    /// A keyless backend which holds the private key locally
+    struct MockKeylessBackend {
+        config: DummyCloseBackendConfig,
+        key: EcKey<Private>,
+    }
+
+    #[async_trait]
+    impl Backend for MockKeylessBackend {
+        fn _clone_config(&self) -> AnyBackendConfig {
+            AnyBackendConfig::DummyClose(self.config.clone())
+        }
+
+        fn _update_config_in_place(
+            &self,
+            _flags: u64,
+            _config: AnyBackendConfig,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn _lock_safe_reload(&self, _config: AnyBackendConfig) -> anyhow::Result<ArcBackend> {
+            Err(anyhow!("reload is not supported"))
+        }
+
+        fn name(&self) -> &MetricsName {
+            self.config.name()
+        }
+
+        fn discover(&self) -> &MetricsName {
+            Default::default()
+        }
+
+        fn update_discover(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn keyless(&self, req: KeylessRequest) -> KeylessResponse {
+            let mut msg = Vec::new();
+            req.send(&mut msg).await.unwrap();
+
+            let mut op = 0;
+            let mut payload: &[u8] = &[];
+            let mut items = &msg[8..];
+            while items.len() >= 3 {
+                let len = u16::from_be_bytes([items[1], items[2]]) as usize;
+                let value = &items[3..3 + len];
+                match items[0] {
+                    0x11 => op = value[0],
+                    0x12 => payload = value,
+                    _ => {}
+                }
+                items = &items[3 + len..];
+            }
+            assert_eq!(op, opcode::ECDSA_SIGN_SHA256);
+            let sig = EcdsaSig::sign(payload, &self.key)
+                .unwrap()
+                .to_der()
+                .unwrap();
+
+            let mut rsp = msg[..8].to_vec();
+            rsp[2..4].copy_from_slice(&(7 + sig.len() as u16).to_be_bytes());
+            rsp.extend_from_slice(&[0x11, 0x00, 0x01, 0xF0, 0x12]);
+            rsp.extend_from_slice(&(sig.len() as u16).to_be_bytes());
+            rsp.extend_from_slice(&sig);
+            let rsp = KeylessUpstreamResponse::recv(&mut rsp.as_slice())
+                .await
+                .unwrap();
+            KeylessResponse::Upstream(rsp)
+        }
+    }
+
+    fn build_cert(key: &PKey<Private>) -> Certificate {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, SERVER_NAME)
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns(SERVER_NAME)
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(key, MessageDigest::sha256()).unwrap();
+        Certificate(builder.build().to_der().unwrap())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn full_handshake() {
+        g3_daemon::runtime::set_main_handle();
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let cert = build_cert(&PKey::from_ec_key(ec_key.clone()).unwrap());
+
+        let backend_name = MetricsName::from_str("keyless-mock").unwrap();
+        let backend = MockKeylessBackend {
+            config: DummyCloseBackendConfig::new(&backend_name, None),
+            key: ec_key,
+        };
+        crate::backend::add_to_registry(backend_name.clone(), Arc::new(backend));
+
+        let ck =
+            new_rustls_offload_key(&backend_name, &[cert.clone()], Duration::from_secs(4)).unwrap();
+        let mut cert_resolver = MultipleCertResolver::with_capacity(1);
+        cert_resolver.push_certified_key(ck);
+        let server_config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(cert_resolver));
+
+        let mut root_store = RootCertStore::empty();
+        root_store.add(&cert).unwrap();
+        let client_config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        let (client_io, server_io) = tokio::io::duplex(16384);
+        // the handshake will be done in a worker thread of the multi-thread runtime
+        let server = tokio::spawn(async move {
+            let acceptor = TlsAcceptor::from(Arc::new(server_config));
+            let mut stream = acceptor.accept(server_io).await.unwrap();
+            stream.write_all(b"ok").await.unwrap();
+            stream.shutdown().await.unwrap();
+        });
+
+        let connector = TlsConnector::from(Arc::new(client_config));
+        let server_name = ServerName::try_from(SERVER_NAME).unwrap();
+        let mut stream = connector.connect(server_name, client_io).await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"ok");
+        server.await.unwrap();
+    }
+}
@@ -29,3 +29,13 @@ pub(crate) enum KeylessRecvMessageError {
     #[error("invalid payload length, only received {0} of {1}")]
     InvalidPayloadLength(usize, usize),
 }
+
+#[derive(Debug, Error)]
+pub(crate) enum KeylessResponseError {
+    #[error("corrupted message")]
+    CorruptedMessage,
+    #[error("unexpected opcode {0:#04x}")]
+    UnexpectedOpCode(u8),
+    #[error("error code {0}")]
+    ErrorCode(u8),
+}
@@ -22,6 +22,13 @@ pub(crate) struct KeylessHeader {
 }
 
 impl KeylessHeader {
+    pub(super) fn new(payload_len: u16) -> Self {
+        let len = payload_len.to_be_bytes();
+        KeylessHeader {
+            bytes: [0x01, 0x00, len[0], len[1], 0x00, 0x00, 0x00, 0x00],
+        }
+    }
+
     pub(super) fn payload_len(&self) -> u16 {
         u16::from_be_bytes([self.bytes[2], self.bytes[3]])
     }
@@ -15,7 +15,7 @@
  */
 
 mod error;
-pub(crate) use error::KeylessRecvMessageError;
+pub(crate) use error::{KeylessRecvMessageError, KeylessResponseError};
 
 mod header;
 mod request;
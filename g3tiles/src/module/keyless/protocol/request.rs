@@ -29,6 +29,27 @@ pub(crate) struct KeylessRequest {
 }
 
 impl KeylessRequest {
+    /// Build a request with the key identified by the SKI
+    pub(crate) fn new(opcode: u8, ski: &[u8], payload: &[u8]) -> Option<Self> {
+        let ski_len = u16::try_from(ski.len()).ok()?;
+        let payload_len = u16::try_from(payload.len()).ok()?;
+        let total_len = u16::try_from(4 + 3 + ski.len() + 3 + payload.len()).ok()?;
+
+        let mut buf = Vec::with_capacity(total_len as usize);
+        buf.extend_from_slice(&[0x11, 0x00, 0x01, opcode]); // OpCode
+        buf.push(0x04); // SKI
+        buf.extend_from_slice(&ski_len.to_be_bytes());
+        buf.extend_from_slice(ski);
+        buf.push(0x12); // Payload
+        buf.extend_from_slice(&payload_len.to_be_bytes());
+        buf.extend_from_slice(payload);
+
+        Some(KeylessRequest {
+            header: KeylessHeader::new(total_len),
+            payload: buf,
+        })
+    }
+
     #[inline]
     pub(crate) fn header(&self) -> KeylessHeader {
         self.header
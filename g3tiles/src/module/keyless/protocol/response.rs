@@ -19,8 +19,39 @@ use std::io::{self, IoSlice};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use g3_io_ext::LimitedWriteExt;
+use g3_types::net::{T1L2BVParse, TlvParse};
 
-use super::{KeylessHeader, KeylessRecvMessageError};
+use super::{KeylessHeader, KeylessRecvMessageError, KeylessResponseError};
+
+#[derive(Default)]
+struct KeylessResponseItems<'a> {
+    opcode: u8,
+    payload: &'a [u8],
+}
+
+impl<'a> T1L2BVParse<'a> for KeylessResponseItems<'a> {
+    type Error = KeylessResponseError;
+
+    fn no_enough_data() -> Self::Error {
+        KeylessResponseError::CorruptedMessage
+    }
+
+    fn parse_value(&mut self, tag: u8, v: &'a [u8]) -> Result<(), Self::Error> {
+        match tag {
+            // OPCODE
+            0x11 => {
+                if v.len() != 1 {
+                    return Err(KeylessResponseError::CorruptedMessage);
+                }
+                self.opcode = v[0];
+            }
+            // PAYLOAD
+            0x12 => self.payload = v,
+            _ => {}
+        }
+        Ok(())
+    }
+}
 
 pub(crate) struct KeylessUpstreamResponse {
     header: KeylessHeader,
@@ -63,6 +94,20 @@ impl KeylessUpstreamResponse {
         Ok(KeylessUpstreamResponse { header, payload })
     }
 
+    fn into_data(self) -> Result<Vec<u8>, KeylessResponseError> {
+        let mut items = KeylessResponseItems::default();
+        items.parse_tlv(&self.payload)?;
+        match items.opcode {
+            // RESPONSE
+            0xF0 => Ok(items.payload.to_vec()),
+            // ERROR
+            0xFF => Err(KeylessResponseError::ErrorCode(
+                items.payload.first().copied().unwrap_or_default(),
+            )),
+            op => Err(KeylessResponseError::UnexpectedOpCode(op)),
+        }
+    }
+
     pub(crate) async fn send<W>(&self, writer: &mut W) -> io::Result<()>
     where
         W: AsyncWrite + Unpin,
@@ -96,6 +141,10 @@ impl KeylessInternalErrorResponse {
         }
     }
 
+    fn error_code(&self) -> u8 {
+        self.buf[super::KEYLESS_HEADER_LEN + 7]
+    }
+
     pub(crate) async fn send<W>(&self, writer: &mut W) -> io::Result<()>
     where
         W: AsyncWrite + Unpin,
@@ -115,6 +164,14 @@ impl KeylessResponse {
         KeylessResponse::Local(KeylessInternalErrorResponse::new(header))
     }
 
+    /// Get the payload data of a successful response
+    pub(crate) fn into_data(self) -> Result<Vec<u8>, KeylessResponseError> {
+        match self {
+            KeylessResponse::Upstream(u) => u.into_data(),
+            KeylessResponse::Local(l) => Err(KeylessResponseError::ErrorCode(l.error_code())),
+        }
+    }
+
     pub(crate) async fn send<W>(&self, writer: &mut W) -> io::Result<()>
     where
         W: AsyncWrite + Unpin,
@@ -125,3 +182,34 @@ impl KeylessResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::keyless::KeylessRequest;
+
+    #[test]
+    fn request_and_response() {
+        let req = KeylessRequest::new(0x05, &[0xAA; 20], &[0xBB; 32]).unwrap();
+        let header = req.header();
+        assert_eq!(header.payload_len(), 4 + 3 + 20 + 3 + 32);
+
+        let mut payload = vec![0x11, 0x00, 0x01, 0xF0, 0x12, 0x00, 0x03];
+        payload.extend_from_slice(b"sig");
+        let rsp = KeylessResponse::Upstream(KeylessUpstreamResponse { header, payload });
+        assert_eq!(rsp.into_data().unwrap(), b"sig");
+
+        let payload = vec![0x11, 0x00, 0x01, 0xFF, 0x12, 0x00, 0x01, 0x02];
+        let rsp = KeylessResponse::Upstream(KeylessUpstreamResponse { header, payload });
+        assert!(matches!(
+            rsp.into_data(),
+            Err(KeylessResponseError::ErrorCode(2))
+        ));
+
+        let rsp = KeylessResponse::not_implemented(header);
+        assert!(matches!(
+            rsp.into_data(),
+            Err(KeylessResponseError::ErrorCode(8))
+        ));
+    }
+}
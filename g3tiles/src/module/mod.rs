@@ -16,4 +16,6 @@
 
 pub(crate) mod stream;
 
+pub(crate) mod http;
+
 pub(crate) mod keyless;
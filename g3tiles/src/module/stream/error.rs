@@ -18,7 +18,7 @@ use std::io;
 
 use thiserror::Error;
 
-use g3_types::net::ConnectError;
+use g3_types::net::{ConnectError, ProxyProtocolEncodeError};
 
 #[derive(Debug, Error)]
 pub(crate) enum StreamConnectError {
@@ -28,4 +28,12 @@ pub(crate) enum StreamConnectError {
     SetupSocketFailed(io::Error),
     #[error("connect failed: {0}")]
     ConnectFailed(#[from] ConnectError),
+    #[error("proxy protocol encode failed: {0}")]
+    ProxyProtocolEncodeFailed(#[from] ProxyProtocolEncodeError),
+    #[error("proxy protocol write failed: {0:?}")]
+    ProxyProtocolWriteFailed(io::Error),
+    #[error("tls handshake failed: {0}")]
+    TlsHandshakeFailed(anyhow::Error),
+    #[error("tls handshake timeout")]
+    TlsHandshakeTimeout,
 }
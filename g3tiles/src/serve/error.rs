@@ -19,8 +19,10 @@ use std::time::Duration;
 
 use thiserror::Error;
 
+use g3_http::client::HttpResponseParseError;
 use g3_types::net::ConnectError;
 
+use crate::module::http::HttpConnectError;
 use crate::module::stream::StreamConnectError;
 
 #[derive(Error, Debug)]
@@ -33,14 +35,26 @@ pub(crate) enum ServerTaskError {
     ClientTcpWriteFailed(io::Error),
     #[error("invalid client protocol: {0}")]
     InvalidClientProtocol(&'static str),
+    #[error("client app timeout: {0}")]
+    ClientAppTimeout(&'static str),
+    #[error("no http route matched")]
+    HttpRouteNotMatched,
     #[error("upstream not resolved")]
     UpstreamNotResolved,
     #[error("upstream not connected: {0}")]
     UpstreamNotConnected(ConnectError),
+    #[error("upstream tls handshake failed: {0}")]
+    UpstreamTlsHandshakeFailed(anyhow::Error),
+    #[error("upstream tls handshake timeout")]
+    UpstreamTlsHandshakeTimeout,
     #[error("read from upstream: {0:?}")]
     UpstreamReadFailed(io::Error),
     #[error("write to upstream: {0:?}")]
     UpstreamWriteFailed(io::Error),
+    #[error("invalid upstream response: {0}")]
+    InvalidUpstreamResponse(HttpResponseParseError),
+    #[error("upstream app timeout: {0}")]
+    UpstreamAppTimeout(&'static str),
     #[error("closed by upstream")]
     ClosedByUpstream,
     #[error("closed by client")]
@@ -49,7 +63,6 @@ pub(crate) enum ServerTaskError {
     CanceledAsServerQuit,
     #[error("idle after {0:?} x {1}")]
     Idle(Duration, i32),
-    #[allow(unused)]
     #[error("finished")]
     Finished, // this isn't an error, for log only
     #[error("unclassified error: {0:?}")]
@@ -63,10 +76,16 @@ impl ServerTaskError {
             ServerTaskError::ClientTcpReadFailed(_) => "ClientTcpReadFailed",
             ServerTaskError::ClientTcpWriteFailed(_) => "ClientTcpWriteFailed",
             ServerTaskError::InvalidClientProtocol(_) => "InvalidClientProtocol",
+            ServerTaskError::ClientAppTimeout(_) => "ClientAppTimeout",
+            ServerTaskError::HttpRouteNotMatched => "HttpRouteNotMatched",
             ServerTaskError::UpstreamNotResolved => "UpstreamNotResolved",
             ServerTaskError::UpstreamNotConnected(_) => "UpstreamNotConnected",
+            ServerTaskError::UpstreamTlsHandshakeFailed(_) => "UpstreamTlsHandshakeFailed",
+            ServerTaskError::UpstreamTlsHandshakeTimeout => "UpstreamTlsHandshakeTimeout",
             ServerTaskError::UpstreamReadFailed(_) => "UpstreamReadFailed",
             ServerTaskError::UpstreamWriteFailed(_) => "UpstreamWriteFailed",
+            ServerTaskError::InvalidUpstreamResponse(_) => "InvalidUpstreamResponse",
+            ServerTaskError::UpstreamAppTimeout(_) => "UpstreamAppTimeout",
             ServerTaskError::ClosedByUpstream => "ClosedByUpstream",
             ServerTaskError::ClosedByClient => "ClosedByClient",
             ServerTaskError::CanceledAsServerQuit => "CanceledAsServerQuit",
@@ -87,6 +106,35 @@ impl From<StreamConnectError> for ServerTaskError {
                 "failed to setup local socket for remote connection",
             ),
             StreamConnectError::ConnectFailed(e) => ServerTaskError::UpstreamNotConnected(e),
+            StreamConnectError::ProxyProtocolEncodeFailed(_) => {
+                ServerTaskError::InternalServerError("failed to encode PROXY protocol header")
+            }
+            StreamConnectError::ProxyProtocolWriteFailed(e) => {
+                ServerTaskError::UpstreamWriteFailed(e)
+            }
+            StreamConnectError::TlsHandshakeFailed(e) => {
+                ServerTaskError::UpstreamTlsHandshakeFailed(e)
+            }
+            StreamConnectError::TlsHandshakeTimeout => ServerTaskError::UpstreamTlsHandshakeTimeout,
+        }
+    }
+}
+
+impl From<HttpConnectError> for ServerTaskError {
+    fn from(value: HttpConnectError) -> Self {
+        match value {
+            HttpConnectError::NoRouteMatched => ServerTaskError::HttpRouteNotMatched,
+            HttpConnectError::StreamConnectFailed(e) => e.into(),
+        }
+    }
+}
+
+impl From<HttpResponseParseError> for ServerTaskError {
+    fn from(value: HttpResponseParseError) -> Self {
+        match value {
+            HttpResponseParseError::RemoteClosed => ServerTaskError::ClosedByUpstream,
+            HttpResponseParseError::IoFailed(e) => ServerTaskError::UpstreamReadFailed(e),
+            e => ServerTaskError::InvalidUpstreamResponse(e),
         }
     }
 }
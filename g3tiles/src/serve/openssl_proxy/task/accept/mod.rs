@@ -137,6 +137,18 @@ impl OpensslAcceptTask {
             .acquire_request_semaphore()
             .map_err(|_| anyhow!("host level alive limit reached"))?;
 
+        // run the keyless key operations in openssl async jobs, which should not be moved between
+        // threads, so only do this on single thread runtimes
+        #[cfg(feature = "openssl-async-job")]
+        if host.config.use_keyless()
+            && tokio::runtime::Handle::current().runtime_flavor()
+                == tokio::runtime::RuntimeFlavor::CurrentThread
+        {
+            lazy_acceptor
+                .ssl_mut()
+                .set_mode(openssl::ssl::SslMode::ASYNC);
+        }
+
         let acceptor = lazy_acceptor
             .into_acceptor(Some(ssl_context))
             .map_err(|e| anyhow!("failed to set final ssl context: {e}"))?;
@@ -28,8 +28,10 @@ use g3_types::limit::GaugeSemaphorePermit;
 
 use super::{CommonTaskContext, OpensslRelayTaskCltWrapperStats};
 use crate::backend::ArcBackend;
+use crate::config::backend::http_route::HttpForwardConfig;
 use crate::config::server::ServerConfig;
 use crate::log::task::tcp_connect::TaskLogForTcpConnect;
+use crate::module::http::{HttpForwardH1Task, HttpForwardH2ConnectionTask, HttpForwardTaskContext};
 use crate::serve::openssl_proxy::OpensslHost;
 use crate::serve::{ServerTaskError, ServerTaskNotes, ServerTaskResult, ServerTaskStage};
 
@@ -117,6 +119,12 @@ impl OpensslRelayTask {
                 ServerTaskError::InternalServerError("failed to set client socket options")
             })?;
 
+        if let Some(http_config) = self.backend.http_forward_config() {
+            // each http request will be logged separately
+            self.run_http(ssl_stream, http_config).await;
+            return Ok(());
+        }
+
         self.task_notes.stage = ServerTaskStage::Connecting;
 
         let (ups_r, ups_w) = self.backend.stream_connect(&self.task_notes).await?;
@@ -126,6 +134,41 @@ impl OpensslRelayTask {
         self.run_connected(ssl_stream, ups_r, ups_w).await
     }
 
+    async fn run_http<S>(
+        &mut self,
+        mut ssl_stream: SslStream<LimitedStream<S>>,
+        http_config: Arc<HttpForwardConfig>,
+    ) where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        self.reset_clt_limit_and_stats(&mut ssl_stream);
+
+        let is_h2 = matches!(ssl_stream.ssl().selected_alpn_protocol(), Some(b"h2"));
+        let ctx = HttpForwardTaskContext {
+            backend: self.backend.clone(),
+            config: http_config,
+            cc_info: self.ctx.cc_info.clone(),
+            task_logger: self.ctx.task_logger.clone(),
+            server_stats: self.ctx.server_stats.clone(),
+            server_quit_policy: self.ctx.server_quit_policy.clone(),
+            idle_check_duration: self.ctx.server_config.task_idle_check_duration,
+            idle_max_count: self
+                .host
+                .config
+                .task_idle_max_count
+                .unwrap_or(self.ctx.server_config.task_idle_max_count),
+        };
+        if is_h2 {
+            HttpForwardH2ConnectionTask::new(ctx)
+                .into_running(ssl_stream, self.task_notes.wait_time)
+                .await
+        } else {
+            HttpForwardH1Task::new(ctx)
+                .into_running(ssl_stream, self.task_notes.wait_time)
+                .await
+        }
+    }
+
     async fn run_connected<S, UR, UW>(
         &mut self,
         ssl_stream: SslStream<LimitedStream<S>>,
@@ -29,10 +29,8 @@ pub(crate) enum ServerTaskStage {
     Preparing,
     Connecting,
     Connected,
-    #[allow(unused)]
     Replying,
     Relaying,
-    #[allow(unused)]
     Finished,
 }
 
@@ -23,6 +23,7 @@ use yaml_rust::Yaml;
 use g3_fluentd::FluentdClientConfig;
 #[cfg(target_os = "linux")]
 use g3_journal::JournalConfig;
+use g3_otlp::OtlpExporterConfig;
 use g3_syslog::SyslogBuilder;
 
 const DEFAULT_CHANNEL_SIZE: usize = 4096;
@@ -36,6 +37,7 @@ pub enum LogConfigDriver {
     Journal(JournalConfig),
     Syslog(SyslogBuilder),
     Fluentd(Arc<FluentdClientConfig>),
+    Otlp(Arc<OtlpExporterConfig>),
 }
 
 #[derive(Clone)]
@@ -84,6 +86,13 @@ impl LogConfig {
         )
     }
 
+    pub fn default_otlp(program_name: &'static str) -> Self {
+        Self::with_driver(
+            LogConfigDriver::Otlp(Arc::new(OtlpExporterConfig::default())),
+            program_name,
+        )
+    }
+
     pub fn parse(
         v: &Yaml,
         conf_dir: &Path,
@@ -96,6 +105,7 @@ impl LogConfig {
                 "journal" => Ok(LogConfig::default_journal(program_name)),
                 "syslog" => Ok(LogConfig::default_syslog(program_name)),
                 "fluentd" => Ok(LogConfig::default_fluentd(program_name)),
+                "otlp" => Ok(LogConfig::default_otlp(program_name)),
                 _ => Err(anyhow!("invalid log config")),
             },
             Yaml::Hash(map) => {
@@ -108,8 +118,9 @@ impl LogConfig {
                         Ok(())
                     }
                     "syslog" => {
-                        let builder = g3_yaml::value::as_syslog_builder(v, program_name)
-                            .context("invalid syslog config")?;
+                        let builder =
+                            g3_yaml::value::as_syslog_builder(v, program_name, Some(conf_dir))
+                                .context("invalid syslog config")?;
                         config.driver = LogConfigDriver::Syslog(builder);
                         Ok(())
                     }
@@ -119,6 +130,12 @@ impl LogConfig {
                         config.driver = LogConfigDriver::Fluentd(Arc::new(client));
                         Ok(())
                     }
+                    "otlp" => {
+                        let exporter = g3_yaml::value::as_otlp_exporter_config(v, Some(conf_dir))
+                            .context("invalid otlp config")?;
+                        config.driver = LogConfigDriver::Otlp(Arc::new(exporter));
+                        Ok(())
+                    }
                     "async_channel_size" | "channel_size" => {
                         let channel_size = g3_yaml::value::as_usize(v)
                             .context(format!("invalid usize value for key {k}"))?;
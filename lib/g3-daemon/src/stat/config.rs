@@ -15,14 +15,23 @@
  */
 
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::anyhow;
-use yaml_rust::Yaml;
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
 
-use g3_statsd_client::StatsdClientConfig;
+use g3_statsd_client::{StatsdClientConfig, StatsdMetricsStore};
 use g3_types::metrics::MetricsName;
 
+use super::otlp::OtlpMetricsExporterConfig;
+use super::prometheus::PrometheusExporterConfig;
+
+const DEFAULT_SERIES_EXPIRE: Duration = Duration::from_secs(300);
+
 static mut GLOBAL_STAT_CONFIG: Option<StatsdClientConfig> = None;
+static mut GLOBAL_PROMETHEUS_CONFIG: Option<PrometheusExporterConfig> = None;
+static mut GLOBAL_OTLP_CONFIG: Option<OtlpMetricsExporterConfig> = None;
 
 pub fn get_global_stat_config() -> Option<StatsdClientConfig> {
     unsafe { GLOBAL_STAT_CONFIG.clone() }
@@ -32,10 +41,78 @@ fn set_global_stat_config(config: StatsdClientConfig) {
     unsafe { GLOBAL_STAT_CONFIG = Some(config) }
 }
 
-pub fn load(v: &Yaml, prefix: &'static str) -> anyhow::Result<()> {
-    let prefix = MetricsName::from_str(prefix)
+pub(super) fn get_global_prometheus_config() -> Option<PrometheusExporterConfig> {
+    unsafe { GLOBAL_PROMETHEUS_CONFIG.clone() }
+}
+
+fn set_global_prometheus_config(config: PrometheusExporterConfig) {
+    unsafe { GLOBAL_PROMETHEUS_CONFIG = Some(config) }
+}
+
+pub(super) fn get_global_otlp_config() -> Option<OtlpMetricsExporterConfig> {
+    unsafe { GLOBAL_OTLP_CONFIG.clone() }
+}
+
+fn set_global_otlp_config(config: OtlpMetricsExporterConfig) {
+    unsafe { GLOBAL_OTLP_CONFIG = Some(config) }
+}
+
+pub fn load(v: &Yaml, daemon_name: &'static str) -> anyhow::Result<()> {
+    let prefix = MetricsName::from_str(daemon_name)
         .map_err(|e| anyhow!("invalid default metrics prefix: {e}"))?;
-    let config = g3_yaml::value::as_statsd_client_config(v, prefix)?;
+
+    let Yaml::Hash(map) = v else {
+        let config = g3_yaml::value::as_statsd_client_config(v, prefix)?;
+        set_global_stat_config(config);
+        return Ok(());
+    };
+
+    let mut statsd_map = yaml::Hash::new();
+    let mut prometheus_config: Option<PrometheusExporterConfig> = None;
+    let mut otlp_config: Option<OtlpMetricsExporterConfig> = None;
+    let mut has_backend = false;
+    g3_yaml::foreach_kv(map, |k, v| {
+        let key = g3_yaml::key::normalize(k);
+        match key.as_str() {
+            "prometheus" | "prometheus_exporter" => {
+                let config = PrometheusExporterConfig::parse(v).context(format!(
+                    "invalid prometheus exporter config value for key {k}"
+                ))?;
+                prometheus_config = Some(config);
+            }
+            "otlp" | "otlp_exporter" => {
+                let config = OtlpMetricsExporterConfig::parse(v, daemon_name)
+                    .context(format!("invalid otlp exporter config value for key {k}"))?;
+                otlp_config = Some(config);
+            }
+            _ => {
+                if key.starts_with("target") || key.starts_with("backend") {
+                    has_backend = true;
+                }
+                statsd_map.insert(Yaml::String(k.to_string()), v.clone());
+            }
+        }
+        Ok(())
+    })?;
+
+    let mut config = g3_yaml::value::as_statsd_client_config(&Yaml::Hash(statsd_map), prefix)?;
+    if prometheus_config.is_some() || otlp_config.is_some() {
+        let series_expire = prometheus_config
+            .as_ref()
+            .map(|c| c.series_expire)
+            .unwrap_or(DEFAULT_SERIES_EXPIRE);
+        config.set_store(Arc::new(StatsdMetricsStore::new(series_expire)));
+        if !has_backend {
+            // only use the exporters if no statsd backend is set explicitly
+            config.disable_backend();
+        }
+    }
+    if let Some(prometheus_config) = prometheus_config {
+        set_global_prometheus_config(prometheus_config);
+    }
+    if let Some(otlp_config) = otlp_config {
+        set_global_otlp_config(otlp_config);
+    }
     set_global_stat_config(config);
     Ok(())
 }
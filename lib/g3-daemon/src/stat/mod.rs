@@ -15,6 +15,8 @@
  */
 
 pub mod config;
+pub mod otlp;
+pub mod prometheus;
 
 pub mod remote;
 pub mod task;
@@ -0,0 +1,92 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use yaml_rust::{yaml, Yaml};
+
+use g3_otlp::OtlpExporterConfig;
+use g3_statsd_client::StatsdMetricKind;
+
+#[derive(Clone)]
+pub struct OtlpMetricsExporterConfig {
+    exporter: Arc<OtlpExporterConfig>,
+    export_interval: Duration,
+    service_name: &'static str,
+}
+
+impl OtlpMetricsExporterConfig {
+    pub(crate) fn parse(v: &Yaml, service_name: &'static str) -> anyhow::Result<Self> {
+        let mut export_interval = Duration::from_secs(10);
+        let exporter = if let Yaml::Hash(map) = v {
+            let mut exporter_map = yaml::Hash::new();
+            g3_yaml::foreach_kv(map, |k, v| {
+                match g3_yaml::key::normalize(k).as_str() {
+                    "export_interval" | "interval" => {
+                        export_interval = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                    }
+                    _ => {
+                        exporter_map.insert(Yaml::String(k.to_string()), v.clone());
+                    }
+                }
+                Ok(())
+            })?;
+            g3_yaml::value::as_otlp_exporter_config(&Yaml::Hash(exporter_map), None)?
+        } else {
+            g3_yaml::value::as_otlp_exporter_config(v, None)?
+        };
+
+        Ok(OtlpMetricsExporterConfig {
+            exporter: Arc::new(exporter),
+            export_interval,
+            service_name,
+        })
+    }
+}
+
+/// Spawn the otlp metrics exporter thread if it is enabled in the stat config.
+///
+/// This should be called after entering daemon mode.
+pub fn spawn_exporter_thread() -> anyhow::Result<()> {
+    let Some(config) = super::config::get_global_otlp_config() else {
+        return Ok(());
+    };
+    let Some(store) = super::config::get_global_stat_config().and_then(|c| c.store().cloned())
+    else {
+        return Ok(());
+    };
+
+    let exporter =
+        g3_otlp::new_metrics_exporter(&config.exporter, config.service_name, "otlp-metrics-io");
+    std::thread::Builder::new()
+        .name("otlp-metrics".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(config.export_interval);
+
+            let now = SystemTime::now();
+            let mut req = exporter.new_request();
+            store.foreach_series(|name, kind, tags, created, value| match kind {
+                StatsdMetricKind::Counter => req.add_sum(name, tags, created, now, value),
+                StatsdMetricKind::Gauge => req.add_gauge(name, tags, now, value),
+            });
+            exporter.export(req);
+        })
+        .context("failed to spawn otlp metrics exporter thread")?;
+    Ok(())
+}
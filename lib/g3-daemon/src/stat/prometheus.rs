@@ -0,0 +1,262 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use log::{debug, warn};
+use yaml_rust::Yaml;
+
+use g3_statsd_client::StatsdMetricsStore;
+
+const MAX_REQUEST_HEAD_SIZE: usize = 8192;
+const REQUEST_HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_ALIVE_CONNECTIONS: usize = 16;
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+#[derive(Debug, Clone)]
+pub struct PrometheusExporterConfig {
+    listen: SocketAddr,
+    path: String,
+    pub(crate) series_expire: Duration,
+}
+
+impl Default for PrometheusExporterConfig {
+    fn default() -> Self {
+        PrometheusExporterConfig {
+            listen: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+            path: "/metrics".to_string(),
+            series_expire: Duration::from_secs(300),
+        }
+    }
+}
+
+impl PrometheusExporterConfig {
+    pub(crate) fn parse(v: &Yaml) -> anyhow::Result<Self> {
+        let mut config = PrometheusExporterConfig::default();
+        match v {
+            Yaml::Hash(map) => {
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "listen" => {
+                        config.listen = g3_yaml::value::as_env_sockaddr(v)
+                            .context(format!("invalid socket address value for key {k}"))?;
+                        Ok(())
+                    }
+                    "path" => {
+                        let path = g3_yaml::value::as_string(v)?;
+                        if !path.starts_with('/') {
+                            return Err(anyhow!("the path should be started with '/'"));
+                        }
+                        config.path = path;
+                        Ok(())
+                    }
+                    "series_expire" => {
+                        config.series_expire = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+            }
+            Yaml::Integer(_) => {
+                let port = g3_yaml::value::as_u16(v)?;
+                config.listen.set_port(port);
+            }
+            Yaml::String(s) => {
+                config.listen =
+                    SocketAddr::from_str(s).map_err(|e| anyhow!("invalid SocketAddr: {e}"))?;
+            }
+            _ => return Err(anyhow!("invalid yaml value type for prometheus exporter")),
+        }
+        if config.listen.port() == 0 {
+            return Err(anyhow!("no listen port set for prometheus exporter"));
+        }
+        Ok(config)
+    }
+}
+
+/// Spawn the prometheus exporter thread if it is enabled in the stat config.
+///
+/// This should be called after entering daemon mode.
+pub fn spawn_exporter_thread() -> anyhow::Result<()> {
+    let Some(config) = super::config::get_global_prometheus_config() else {
+        return Ok(());
+    };
+    let Some(store) = super::config::get_global_stat_config().and_then(|c| c.store().cloned())
+    else {
+        return Ok(());
+    };
+
+    let listener = TcpListener::bind(config.listen).map_err(|e| {
+        anyhow!(
+            "failed to bind prometheus exporter to {}: {e}",
+            config.listen
+        )
+    })?;
+    std::thread::Builder::new()
+        .name("prom-exporter".to_string())
+        .spawn(move || {
+            let path = Arc::new(config.path);
+            let alive_connections = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        // serve each connection in its own thread, so a slow client won't block
+                        // the others, and limit the number of them
+                        if alive_connections.fetch_add(1, Ordering::Relaxed)
+                            >= MAX_ALIVE_CONNECTIONS
+                        {
+                            alive_connections.fetch_sub(1, Ordering::Relaxed);
+                            debug!("prometheus exporter: too many alive connections");
+                            continue;
+                        }
+                        let path = path.clone();
+                        let store = store.clone();
+                        let alive_connections = alive_connections.clone();
+                        let r = std::thread::Builder::new()
+                            .name("prom-conn".to_string())
+                            .spawn(move || {
+                                if let Err(e) = serve_connection(stream, &path, &store) {
+                                    debug!("prometheus exporter connection error: {e}");
+                                }
+                                alive_connections.fetch_sub(1, Ordering::Relaxed);
+                            });
+                        if let Err(e) = r {
+                            alive_connections.fetch_sub(1, Ordering::Relaxed);
+                            warn!("prometheus exporter failed to spawn connection thread: {e}");
+                        }
+                    }
+                    Err(e) => warn!("prometheus exporter accept error: {e}"),
+                }
+            }
+        })
+        .context("failed to spawn prometheus exporter thread")?;
+    Ok(())
+}
+
+fn serve_connection(
+    mut stream: TcpStream,
+    path: &str,
+    store: &Arc<StatsdMetricsStore>,
+) -> io::Result<()> {
+    stream.set_write_timeout(Some(REQUEST_HEAD_TIMEOUT))?;
+
+    // the timeout is for the whole request head, not for each read
+    let deadline = Instant::now() + REQUEST_HEAD_TIMEOUT;
+    let mut buf = Vec::with_capacity(1024);
+    let mut tmp = [0u8; 1024];
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out to read request head",
+            ));
+        }
+        stream.set_read_timeout(Some(left))?;
+        let nr = stream.read(&mut tmp)?;
+        if nr == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&tmp[..nr]);
+        if buf.windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+        if buf.len() > MAX_REQUEST_HEAD_SIZE {
+            return write_response(&mut stream, "431 Request Header Fields Too Large", None);
+        }
+    }
+
+    let line_end = buf.iter().position(|c| *c == b'\r').unwrap_or(buf.len());
+    let line = String::from_utf8_lossy(&buf[..line_end]);
+    let mut parts = line.split(' ');
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let target_path = target.split_once('?').map(|(p, _)| p).unwrap_or(target);
+
+    if target_path != path {
+        write_response(&mut stream, "404 Not Found", None)
+    } else if method != "GET" && method != "HEAD" {
+        write_response(&mut stream, "405 Method Not Allowed", None)
+    } else {
+        let body = store.render_openmetrics();
+        if method == "HEAD" {
+            write_response(&mut stream, "200 OK", Some((body.len(), "")))
+        } else {
+            write_response(&mut stream, "200 OK", Some((body.len(), &body)))
+        }
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    body: Option<(usize, &str)>,
+) -> io::Result<()> {
+    let head = match body {
+        Some((len, _)) => format!(
+            "HTTP/1.1 {status}\r\nContent-Type: {OPENMETRICS_CONTENT_TYPE}\r\n\
+             Content-Length: {len}\r\nConnection: close\r\n\r\n"
+        ),
+        None => format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+    };
+    stream.write_all(head.as_bytes())?;
+    if let Some((_, body)) = body {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<PrometheusExporterConfig> {
+        let doc = YamlLoader::load_from_str(s).unwrap();
+        PrometheusExporterConfig::parse(&doc[0])
+    }
+
+    #[test]
+    fn parse_listen() {
+        let config = parse("9900").unwrap();
+        assert_eq!(
+            config.listen,
+            SocketAddr::from_str("127.0.0.1:9900").unwrap()
+        );
+
+        let config = parse("\"[::]:9900\"").unwrap();
+        assert_eq!(config.listen, SocketAddr::from_str("[::]:9900").unwrap());
+
+        let config = parse("{listen: \"127.0.0.1:9900\", path: /stats}").unwrap();
+        assert_eq!(
+            config.listen,
+            SocketAddr::from_str("127.0.0.1:9900").unwrap()
+        );
+        assert_eq!(config.path, "/stats");
+    }
+
+    #[test]
+    fn parse_no_port() {
+        assert!(parse("{path: /metrics}").is_err());
+        assert!(parse("\"127.0.0.1:0\"").is_err());
+    }
+}
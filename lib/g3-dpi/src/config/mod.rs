@@ -27,6 +27,15 @@ pub use http::{H1InterceptionConfig, H2InterceptionConfig};
 mod smtp;
 pub use smtp::SmtpInterceptionConfig;
 
+mod imap;
+pub use imap::ImapInterceptionConfig;
+
+mod pop3;
+pub use pop3::Pop3InterceptionConfig;
+
+mod websocket;
+pub use websocket::WebSocketInterceptionConfig;
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum ProtocolInspectPolicy {
     #[default]
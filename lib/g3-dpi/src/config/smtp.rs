@@ -20,6 +20,10 @@ use std::time::Duration;
 pub struct SmtpInterceptionConfig {
     pub greeting_timeout: Duration,
     pub quit_wait_timeout: Duration,
+    pub command_wait_timeout: Duration,
+    pub response_wait_timeout: Duration,
+    pub data_initiation_timeout: Duration,
+    pub data_termination_timeout: Duration,
 }
 
 impl Default for SmtpInterceptionConfig {
@@ -27,6 +31,10 @@ impl Default for SmtpInterceptionConfig {
         SmtpInterceptionConfig {
             greeting_timeout: Duration::from_secs(300),
             quit_wait_timeout: Duration::from_secs(60),
+            command_wait_timeout: Duration::from_secs(300),
+            response_wait_timeout: Duration::from_secs(300),
+            data_initiation_timeout: Duration::from_secs(120),
+            data_termination_timeout: Duration::from_secs(600),
         }
     }
 }
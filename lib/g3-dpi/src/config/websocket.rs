@@ -0,0 +1,34 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebSocketInterceptionConfig {
+    /// max size of the whole message after defragmentation and decompression
+    pub max_message_size: usize,
+    /// timeout for the ICAP REQMOD adaptation of a sampled text message
+    pub message_adaptation_timeout: Duration,
+}
+
+impl Default for WebSocketInterceptionConfig {
+    fn default() -> Self {
+        WebSocketInterceptionConfig {
+            max_message_size: 16 * 1024 * 1024,
+            message_adaptation_timeout: Duration::from_secs(30),
+        }
+    }
+}
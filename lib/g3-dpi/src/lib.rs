@@ -24,6 +24,7 @@ pub use protocol::{
 
 mod config;
 pub use config::{
-    H1InterceptionConfig, H2InterceptionConfig, ProtocolInspectPolicy, ProtocolInspectionConfig,
-    ProtocolInspectionSizeLimit, SmtpInterceptionConfig,
+    H1InterceptionConfig, H2InterceptionConfig, ImapInterceptionConfig, Pop3InterceptionConfig,
+    ProtocolInspectPolicy, ProtocolInspectionConfig, ProtocolInspectionSizeLimit,
+    SmtpInterceptionConfig, WebSocketInterceptionConfig,
 };
@@ -0,0 +1,209 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use hickory_client::client::AsyncClient;
+use hickory_proto::iocompat::AsyncIoTokioAsStd;
+use rustls::{ClientConfig, ServerName};
+use tokio::net::{TcpStream, UdpSocket};
+
+use g3_types::net::{DnsEncryptionConfig, DnsEncryptionProtocol};
+
+/// The config to build a hickory async dns client to the target server
+#[derive(Clone)]
+pub struct DnsClientConfig {
+    pub target: SocketAddr,
+    pub bind: Option<SocketAddr>,
+    pub encryption: Option<DnsEncryptionConfig>,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+}
+
+impl DnsClientConfig {
+    /// Whether to retry with tcp if the udp response is truncated
+    pub fn retry_tcp(&self) -> bool {
+        self.encryption.is_none()
+    }
+
+    pub async fn build_async_client(&self) -> anyhow::Result<AsyncClient> {
+        if let Some(ec) = &self.encryption {
+            let tls_client = ec.tls_client().driver.as_ref().clone();
+
+            match ec.protocol() {
+                DnsEncryptionProtocol::Tls => {
+                    self.new_dns_over_tls_client(tls_client, ec.tls_name().clone())
+                        .await
+                }
+                DnsEncryptionProtocol::Https => {
+                    self.new_dns_over_h2_client(tls_client, ec.tls_name().clone())
+                        .await
+                }
+                #[cfg(feature = "quic")]
+                DnsEncryptionProtocol::Quic => {
+                    self.new_dns_over_quic_client(tls_client, ec.tls_name())
+                        .await
+                }
+                #[cfg(feature = "quic")]
+                DnsEncryptionProtocol::H3 => {
+                    self.new_dns_over_h3_client(tls_client, ec.tls_name()).await
+                }
+            }
+        } else {
+            self.new_dns_over_udp_client().await
+        }
+    }
+
+    async fn new_dns_over_udp_client(&self) -> anyhow::Result<AsyncClient> {
+        // random port is used here
+        let client_connect =
+            hickory_client::udp::UdpClientStream::<UdpSocket>::with_bind_addr_and_timeout(
+                self.target,
+                self.bind,
+                self.request_timeout,
+            );
+
+        let (client, bg) = AsyncClient::connect(client_connect)
+            .await
+            .map_err(|e| anyhow!("failed to create udp async client: {e}"))?;
+        tokio::spawn(bg);
+        Ok(client)
+    }
+
+    pub async fn new_dns_over_tcp_client(&self) -> anyhow::Result<AsyncClient> {
+        let (stream, sender) =
+            hickory_client::tcp::TcpClientStream::<AsyncIoTokioAsStd<TcpStream>>::with_bind_addr_and_timeout(
+                self.target,
+                self.bind,
+                self.connect_timeout,
+            );
+
+        let (client, bg) = AsyncClient::with_timeout(stream, sender, self.request_timeout, None)
+            .await
+            .map_err(|e| anyhow!("failed to create tcp async client: {e}"))?;
+        tokio::spawn(bg);
+        Ok(client)
+    }
+
+    async fn new_dns_over_tls_client(
+        &self,
+        tls_client: ClientConfig,
+        tls_name: ServerName,
+    ) -> anyhow::Result<AsyncClient> {
+        use hickory_proto::BufDnsStreamHandle;
+
+        let (message_sender, outbound_messages) = BufDnsStreamHandle::new(self.target);
+
+        let tls_connect = crate::io::tls::connect(
+            self.target,
+            self.bind,
+            tls_client,
+            tls_name,
+            outbound_messages,
+            self.connect_timeout,
+        );
+
+        let (client, bg) = AsyncClient::with_timeout(
+            Box::pin(tls_connect),
+            message_sender,
+            self.request_timeout,
+            None,
+        )
+        .await
+        .map_err(|e| anyhow!("failed to create tls async client: {e}"))?;
+        tokio::spawn(bg);
+        Ok(client)
+    }
+
+    async fn new_dns_over_h2_client(
+        &self,
+        tls_client: ClientConfig,
+        tls_name: ServerName,
+    ) -> anyhow::Result<AsyncClient> {
+        let client_connect = crate::io::h2::connect(
+            self.target,
+            self.bind,
+            tls_client,
+            tls_name,
+            self.connect_timeout,
+            self.request_timeout,
+        );
+
+        let (client, bg) = AsyncClient::connect(Box::pin(client_connect))
+            .await
+            .map_err(|e| anyhow!("failed to create h2 async client: {e}"))?;
+        tokio::spawn(bg);
+        Ok(client)
+    }
+
+    #[cfg(feature = "quic")]
+    async fn new_dns_over_quic_client(
+        &self,
+        tls_client: ClientConfig,
+        tls_name: &ServerName,
+    ) -> anyhow::Result<AsyncClient> {
+        let tls_name = match tls_name {
+            ServerName::DnsName(domain) => domain.as_ref().to_string(),
+            ServerName::IpAddress(ip) => ip.to_string(),
+            _ => return Err(anyhow!("unsupported tls server name type")),
+        };
+
+        let client_connect = crate::io::quic::connect(
+            self.target,
+            self.bind,
+            tls_client,
+            tls_name,
+            self.connect_timeout,
+            self.request_timeout,
+        );
+
+        let (client, bg) = AsyncClient::connect(Box::pin(client_connect))
+            .await
+            .map_err(|e| anyhow!("failed to create quic async client: {e}"))?;
+        tokio::spawn(bg);
+        Ok(client)
+    }
+
+    #[cfg(feature = "quic")]
+    async fn new_dns_over_h3_client(
+        &self,
+        tls_client: ClientConfig,
+        tls_name: &ServerName,
+    ) -> anyhow::Result<AsyncClient> {
+        let tls_name = match tls_name {
+            ServerName::DnsName(domain) => domain.as_ref().to_string(),
+            ServerName::IpAddress(ip) => ip.to_string(),
+            _ => return Err(anyhow!("unsupported tls server name type")),
+        };
+
+        let client_connect = crate::io::h3::connect(
+            self.target,
+            self.bind,
+            tls_client,
+            tls_name,
+            self.connect_timeout,
+            self.request_timeout,
+        );
+
+        let (client, bg) = AsyncClient::connect(Box::pin(client_connect))
+            .await
+            .map_err(|e| anyhow!("failed to create h3 async client: {e}"))?;
+        tokio::spawn(bg);
+        Ok(client)
+    }
+}
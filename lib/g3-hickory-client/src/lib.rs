@@ -16,3 +16,6 @@
 
 pub mod connect;
 pub mod io;
+
+mod client;
+pub use client::DnsClientConfig;
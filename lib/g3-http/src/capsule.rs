@@ -0,0 +1,157 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Capsule Protocol, see RFC 9297
+
+pub const CAPSULE_TYPE_DATAGRAM: u64 = 0x00;
+
+pub const CAPSULE_PROTOCOL_HEADER_VALUE: &str = "?1";
+
+const VARINT_MAX: u64 = (1 << 62) - 1;
+
+/// max length of the header added by [`encode_datagram_header`]
+pub const DATAGRAM_HEADER_MAX_LEN: usize = 1 + 8 + 1;
+
+/// get the encoded length of a variable-length integer, see RFC 9000 Section 16
+pub fn varint_len(v: u64) -> usize {
+    if v < (1 << 6) {
+        1
+    } else if v < (1 << 14) {
+        2
+    } else if v < (1 << 30) {
+        4
+    } else {
+        8
+    }
+}
+
+/// encode a variable-length integer, return the encoded length
+///
+/// `None` will be returned if the value is too large, or the buffer is too small
+pub fn encode_varint(v: u64, buf: &mut [u8]) -> Option<usize> {
+    if v > VARINT_MAX {
+        return None;
+    }
+    let len = varint_len(v);
+    if buf.len() < len {
+        return None;
+    }
+    match len {
+        1 => buf[0] = v as u8,
+        2 => buf[0..2].copy_from_slice(&((v as u16) | 0x4000).to_be_bytes()),
+        4 => buf[0..4].copy_from_slice(&((v as u32) | 0x8000_0000).to_be_bytes()),
+        _ => buf[0..8].copy_from_slice(&(v | 0xc000_0000_0000_0000).to_be_bytes()),
+    }
+    Some(len)
+}
+
+/// decode a variable-length integer, return the value and the encoded length
+///
+/// `None` will be returned if there is no enough data
+pub fn decode_varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let first = *buf.first()?;
+    let len = 1usize << (first >> 6);
+    if buf.len() < len {
+        return None;
+    }
+    let mut v = u64::from(first & 0x3f);
+    for b in &buf[1..len] {
+        v = (v << 8) | u64::from(*b);
+    }
+    Some((v, len))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapsuleHeader {
+    pub capsule_type: u64,
+    pub length: u64,
+    pub encoded_len: usize,
+}
+
+impl CapsuleHeader {
+    /// parse the capsule header, `None` will be returned if there is no enough data
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        let (capsule_type, type_len) = decode_varint(buf)?;
+        let (length, length_len) = decode_varint(&buf[type_len..])?;
+        Some(CapsuleHeader {
+            capsule_type,
+            length,
+            encoded_len: type_len + length_len,
+        })
+    }
+
+    #[inline]
+    pub fn is_datagram(&self) -> bool {
+        self.capsule_type == CAPSULE_TYPE_DATAGRAM
+    }
+}
+
+/// encode the header of a DATAGRAM capsule with context id 0, which is used by connect-udp,
+/// return the encoded length
+///
+/// the buffer should be at least [`DATAGRAM_HEADER_MAX_LEN`] long
+pub fn encode_datagram_header(payload_len: usize, buf: &mut [u8]) -> Option<usize> {
+    // the context id is part of the capsule value
+    let length = payload_len as u64 + 1;
+    let mut offset = encode_varint(CAPSULE_TYPE_DATAGRAM, buf)?;
+    offset += encode_varint(length, &mut buf[offset..])?;
+    offset += encode_varint(0, &mut buf[offset..])?;
+    Some(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint() {
+        // examples from RFC 9000 Appendix A.1
+        let cases: &[(&[u8], u64)] = &[
+            (
+                &[0xc2, 0x19, 0x7c, 0x5e, 0xff, 0x14, 0xe8, 0x8c],
+                151288809941952652,
+            ),
+            (&[0x9d, 0x7f, 0x3e, 0x7d], 494878333),
+            (&[0x7b, 0xbd], 15293),
+            (&[0x25], 37),
+        ];
+        for (data, value) in cases {
+            assert_eq!(decode_varint(data), Some((*value, data.len())));
+
+            let mut buf = [0u8; 8];
+            let len = encode_varint(*value, &mut buf).unwrap();
+            assert_eq!(&buf[..len], *data);
+        }
+
+        assert_eq!(decode_varint(&[0x7b]), None);
+        assert_eq!(encode_varint(1 << 62, &mut [0u8; 8]), None);
+        assert_eq!(encode_varint(15293, &mut [0u8; 1]), None);
+    }
+
+    #[test]
+    fn datagram_header() {
+        let mut buf = [0u8; DATAGRAM_HEADER_MAX_LEN];
+        let len = encode_datagram_header(1200, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &[0x00, 0x44, 0xb1, 0x00]);
+
+        let header = CapsuleHeader::parse(&buf[..len]).unwrap();
+        assert!(header.is_datagram());
+        assert_eq!(header.length, 1201);
+        assert_eq!(header.encoded_len, 3);
+
+        assert_eq!(CapsuleHeader::parse(&buf[..2]), None);
+    }
+}
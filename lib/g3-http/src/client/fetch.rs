@@ -0,0 +1,245 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+
+use http::{header, Method};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::{HttpResponseParseError, HttpTransparentResponse};
+use crate::HttpBodyReader;
+
+const BODY_LINE_MAX_LENGTH: usize = 8192;
+
+#[derive(Debug, Error)]
+pub enum HttpFetchError {
+    #[error("failed to send request: {0:?}")]
+    WriteFailed(io::Error),
+    #[error("failed to recv response: {0}")]
+    InvalidResponse(#[from] HttpResponseParseError),
+    #[error("unexpected response status code {0}")]
+    UnexpectedStatus(u16),
+    #[error("failed to recv response body: {0:?}")]
+    ReadBodyFailed(io::Error),
+    #[error("too large response body, should be less than {0}")]
+    TooLargeBody(usize),
+}
+
+/// A GET request to fetch some remote resource, with the cache validators of the last fetch.
+///
+/// The request will be sent in HTTP/1.1, and the connection will be closed after the response.
+pub struct HttpFetchRequest<'a> {
+    path_and_query: &'a str,
+    host: &'a str,
+    accept: &'a str,
+    etag: Option<&'a str>,
+    last_modified: Option<&'a str>,
+    rsp_header_max_size: usize,
+    rsp_body_max_size: usize,
+}
+
+pub struct HttpFetchResponse {
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl<'a> HttpFetchRequest<'a> {
+    pub fn new(path_and_query: &'a str, host: &'a str, accept: &'a str) -> Self {
+        HttpFetchRequest {
+            path_and_query,
+            host,
+            accept,
+            etag: None,
+            last_modified: None,
+            rsp_header_max_size: 8192,
+            rsp_body_max_size: 64 * 1024 * 1024,
+        }
+    }
+
+    #[inline]
+    pub fn set_etag(&mut self, etag: &'a str) {
+        self.etag = Some(etag);
+    }
+
+    #[inline]
+    pub fn set_last_modified(&mut self, last_modified: &'a str) {
+        self.last_modified = Some(last_modified);
+    }
+
+    #[inline]
+    pub fn set_rsp_header_max_size(&mut self, size: usize) {
+        self.rsp_header_max_size = size;
+    }
+
+    #[inline]
+    pub fn set_rsp_body_max_size(&mut self, size: usize) {
+        self.rsp_body_max_size = size;
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(256);
+        buf.extend_from_slice(b"GET ");
+        buf.extend_from_slice(self.path_and_query.as_bytes());
+        buf.extend_from_slice(b" HTTP/1.1\r\nHost: ");
+        buf.extend_from_slice(self.host.as_bytes());
+        buf.extend_from_slice(b"\r\nAccept: ");
+        buf.extend_from_slice(self.accept.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+        if let Some(etag) = self.etag {
+            buf.extend_from_slice(b"If-None-Match: ");
+            buf.extend_from_slice(etag.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+        if let Some(last_modified) = self.last_modified {
+            buf.extend_from_slice(b"If-Modified-Since: ");
+            buf.extend_from_slice(last_modified.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+        buf.extend_from_slice(b"Connection: close\r\n\r\n");
+        buf
+    }
+
+    /// Send the request and receive the response, return None if not modified
+    pub async fn send<S>(&self, stream: S) -> Result<Option<HttpFetchResponse>, HttpFetchError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut stream = BufReader::new(stream);
+
+        let writer = stream.get_mut();
+        writer
+            .write_all(&self.serialize())
+            .await
+            .map_err(HttpFetchError::WriteFailed)?;
+        writer.flush().await.map_err(HttpFetchError::WriteFailed)?;
+
+        let (rsp, _) = HttpTransparentResponse::parse(
+            &mut stream,
+            &Method::GET,
+            false,
+            self.rsp_header_max_size,
+        )
+        .await?;
+        match rsp.code {
+            200 => {}
+            304 => return Ok(None),
+            code => return Err(HttpFetchError::UnexpectedStatus(code)),
+        }
+
+        let mut body = Vec::new();
+        if let Some(body_type) = rsp.body_type(&Method::GET) {
+            let body_reader = HttpBodyReader::new(&mut stream, body_type, BODY_LINE_MAX_LENGTH);
+            body_reader
+                .take(self.rsp_body_max_size as u64 + 1)
+                .read_to_end(&mut body)
+                .await
+                .map_err(HttpFetchError::ReadBodyFailed)?;
+            if body.len() > self.rsp_body_max_size {
+                return Err(HttpFetchError::TooLargeBody(self.rsp_body_max_size));
+            }
+        }
+
+        let get_header = |name| {
+            rsp.end_to_end_headers
+                .get(name)
+                .map(|v| v.to_str().to_string())
+        };
+        Ok(Some(HttpFetchResponse {
+            content_type: get_header(header::CONTENT_TYPE),
+            etag: get_header(header::ETAG),
+            last_modified: get_header(header::LAST_MODIFIED),
+            body,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncBufReadExt;
+
+    async fn fetch(
+        req: &HttpFetchRequest<'_>,
+        response: &'static [u8],
+    ) -> (String, Result<Option<HttpFetchResponse>, HttpFetchError>) {
+        let (client, server) = tokio::io::duplex(4096);
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                server.read_line(&mut line).await.unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            server.write_all(response).await.unwrap();
+            server.shutdown().await.unwrap();
+            head
+        });
+        let r = req.send(client).await;
+        (server.await.unwrap(), r)
+    }
+
+    #[tokio::test]
+    async fn fetch_with_etag() {
+        let mut req = HttpFetchRequest::new("/data?a=b", "example.net:8080", "application/json");
+        let (head, r) = fetch(
+            &req,
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nETag: \"v1\"\r\n\
+            Content-Length: 2\r\n\r\n[]",
+        )
+        .await;
+        assert!(head.starts_with("GET /data?a=b HTTP/1.1\r\nHost: example.net:8080\r\n"));
+        assert!(!head.contains("If-None-Match"));
+        let rsp = r.unwrap().unwrap();
+        assert_eq!(rsp.body, b"[]");
+        assert_eq!(rsp.content_type.as_deref(), Some("application/json"));
+        assert_eq!(rsp.etag.as_deref(), Some("\"v1\""));
+        assert!(rsp.last_modified.is_none());
+
+        req.set_etag("\"v1\"");
+        let (head, r) = fetch(&req, b"HTTP/1.1 304 Not Modified\r\n\r\n").await;
+        assert!(head.contains("If-None-Match: \"v1\"\r\n"));
+        assert!(r.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn fetch_chunked() {
+        let req = HttpFetchRequest::new("/", "example.net", "*/*");
+        let (_, r) = fetch(
+            &req,
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n[]\r\n0\r\n\r\n",
+        )
+        .await;
+        assert_eq!(r.unwrap().unwrap().body, b"[]");
+    }
+
+    #[tokio::test]
+    async fn fetch_failed() {
+        let mut req = HttpFetchRequest::new("/", "example.net", "*/*");
+        req.set_rsp_body_max_size(1);
+        let (_, r) = fetch(&req, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n[]").await;
+        assert!(matches!(r, Err(HttpFetchError::TooLargeBody(1))));
+
+        let (_, r) = fetch(&req, b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await;
+        assert!(matches!(r, Err(HttpFetchError::UnexpectedStatus(404))));
+    }
+}
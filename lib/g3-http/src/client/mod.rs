@@ -25,3 +25,6 @@ pub use transparent::HttpTransparentResponse;
 
 mod adaptation;
 pub use adaptation::HttpAdaptedResponse;
+
+mod fetch;
+pub use fetch::{HttpFetchError, HttpFetchRequest, HttpFetchResponse};
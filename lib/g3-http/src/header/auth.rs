@@ -29,6 +29,10 @@ pub fn proxy_authorization_basic(username: &Username, password: &Password) -> St
     )
 }
 
+pub fn proxy_authorization_bearer(token: &str) -> String {
+    format!("Proxy-Authorization: Bearer {token}\r\n")
+}
+
 pub fn proxy_authenticate_basic(realm: &str) -> String {
     format!("Proxy-Authenticate: Basic realm=\"{realm}\"\r\n")
 }
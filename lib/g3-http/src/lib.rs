@@ -25,6 +25,7 @@ pub use body::{
     HttpBodyType, PreviewData, PreviewDataState, PreviewError, TrailerReadError, TrailerReader,
 };
 
+pub mod capsule;
 pub mod client;
 pub mod connect;
 pub mod header;
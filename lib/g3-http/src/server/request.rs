@@ -22,7 +22,7 @@ use http::{header, HeaderName, Method, Uri, Version};
 use tokio::io::AsyncBufRead;
 
 use g3_io_ext::LimitedBufReadExt;
-use g3_types::net::{HttpAuth, HttpHeaderMap, HttpHeaderValue, UpstreamAddr};
+use g3_types::net::{HttpAuth, HttpHeaderMap, HttpHeaderValue, HttpUpgradeToken, UpstreamAddr};
 
 use super::{HttpAdaptedRequest, HttpRequestParseError};
 use crate::header::Connection;
@@ -46,6 +46,7 @@ pub struct HttpProxyClientRequest {
     has_transfer_encoding: bool,
     has_content_length: bool,
     has_trailer: bool,
+    upgrade_connect_udp: bool,
 }
 
 impl HttpProxyClientRequest {
@@ -67,6 +68,7 @@ impl HttpProxyClientRequest {
             has_transfer_encoding: false,
             has_content_length: false,
             has_trailer: false,
+            upgrade_connect_udp: false,
         }
     }
 
@@ -93,6 +95,29 @@ impl HttpProxyClientRequest {
             has_transfer_encoding: false,
             has_content_length: false,
             has_trailer: false,
+            upgrade_connect_udp: self.upgrade_connect_udp,
+        }
+    }
+
+    pub fn clone_with_headers(&self, end_to_end_headers: HttpHeaderMap) -> Self {
+        HttpProxyClientRequest {
+            version: self.version,
+            method: self.method.clone(),
+            uri: self.uri.clone(),
+            end_to_end_headers,
+            hop_by_hop_headers: self.hop_by_hop_headers.clone(),
+            auth_info: HttpAuth::None,
+            host: self.host.clone(),
+            original_connection_name: self.original_connection_name.clone(),
+            extra_connection_headers: self.extra_connection_headers.clone(),
+            origin_header_size: self.origin_header_size,
+            keep_alive: self.keep_alive,
+            content_length: self.content_length,
+            chunked_transfer: self.chunked_transfer,
+            has_transfer_encoding: self.has_transfer_encoding,
+            has_content_length: self.has_content_length,
+            has_trailer: self.has_trailer,
+            upgrade_connect_udp: self.upgrade_connect_udp,
         }
     }
 
@@ -121,6 +146,12 @@ impl HttpProxyClientRequest {
         }
     }
 
+    /// if this is a connect-udp upgrade request, see RFC 9298
+    #[inline]
+    pub fn is_connect_udp(&self) -> bool {
+        self.upgrade_connect_udp
+    }
+
     pub fn has_auth_info(&self) -> bool {
         !matches!(self.auth_info, HttpAuth::None)
     }
@@ -224,6 +255,66 @@ impl HttpProxyClientRequest {
         Ok(req)
     }
 
+    /// Build from the request head of a HTTP/2 stream, so it can be forwarded by using HTTP/1.1.
+    ///
+    /// The request body, if present, should be sent in chunked encoding.
+    pub fn from_h2<F>(
+        parts: &http::request::Parts,
+        has_body: bool,
+        parse_more_header: F,
+    ) -> Result<Self, HttpRequestParseError>
+    where
+        F: Fn(&mut Self, HeaderName, &HttpHeaderLine) -> Result<(), HttpRequestParseError>,
+    {
+        let mut req =
+            HttpProxyClientRequest::new(parts.method.clone(), parts.uri.clone(), Version::HTTP_11);
+        req.keep_alive = true;
+
+        let mut header_size: usize = 0;
+        for (name, value) in &parts.headers {
+            let value = value.to_str().map_err(|_| {
+                HttpRequestParseError::InvalidHeaderLine(HttpLineParseError::InvalidHeaderValue)
+            })?;
+            header_size += name.as_str().len() + value.len() + 4;
+            let header = HttpHeaderLine {
+                name: name.as_str(),
+                value,
+            };
+            req.handle_header(header, &parse_more_header)?;
+        }
+
+        if req.host.is_none() {
+            if let Some(authority) = parts.uri.authority() {
+                let host = UpstreamAddr::from_str(authority.as_str())
+                    .map_err(|_| HttpRequestParseError::InvalidHost)?;
+                let value = HttpHeaderValue::from_str(authority.as_str())
+                    .map_err(|_| HttpRequestParseError::InvalidHost)?;
+                header_size += authority.as_str().len() + 8;
+                req.end_to_end_headers.insert(header::HOST, value);
+                req.host = Some(host);
+            }
+        }
+
+        if has_body {
+            // the data frames will be sent in chunked encoding
+            req.end_to_end_headers.remove(header::CONTENT_LENGTH);
+            req.content_length = 0;
+            req.has_content_length = false;
+            req.chunked_transfer = true;
+            req.has_transfer_encoding = true;
+            req.hop_by_hop_headers.insert(
+                header::TRANSFER_ENCODING,
+                HttpHeaderValue::from_static("chunked"),
+            );
+        } else if req.content_length > 0 {
+            return Err(HttpRequestParseError::InvalidContentLength);
+        }
+        req.origin_header_size = header_size;
+
+        req.post_check_and_fix();
+        Ok(req)
+    }
+
     /// do some necessary check and fix
     fn post_check_and_fix(&mut self) {
         if self.has_trailer && !self.chunked_transfer {
@@ -360,8 +451,18 @@ impl HttpProxyClientRequest {
                 return self.insert_hop_by_hop_header(name, &header);
             }
             "upgrade" => {
-                // TODO we have no support for it right now
-                return Err(HttpRequestParseError::UpgradeIsNotSupported);
+                // only connect-udp is supported right now
+                let is_connect_udp = header.value.split(',').any(|v| {
+                    matches!(
+                        HttpUpgradeToken::from_str(v.trim()),
+                        Ok(HttpUpgradeToken::ConnectUdp)
+                    )
+                });
+                if !is_connect_udp {
+                    return Err(HttpRequestParseError::UpgradeIsNotSupported);
+                }
+                self.upgrade_connect_udp = true;
+                return self.insert_hop_by_hop_header(name, &header);
             }
             "trailer" => {
                 self.has_trailer = true;
@@ -500,6 +601,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn from_h2_post() {
+        let req = http::Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/upload")
+            .header(header::CONTENT_LENGTH, "4")
+            .header(header::USER_AGENT, "curl/8.0")
+            .body(())
+            .unwrap();
+        let (parts, _) = req.into_parts();
+        let request = HttpProxyClientRequest::from_h2(&parts, true, parse_more_header).unwrap();
+        assert_eq!(request.version, Version::HTTP_11);
+        assert_eq!(request.method, Method::POST);
+        assert!(request.keep_alive());
+        assert_eq!(request.body_type(), Some(HttpBodyType::Chunked));
+        assert!(!request
+            .end_to_end_headers
+            .contains_key(header::CONTENT_LENGTH));
+        assert!(request
+            .hop_by_hop_headers
+            .contains_key(header::TRANSFER_ENCODING));
+        let host = request.end_to_end_headers.get(header::HOST).unwrap();
+        assert_eq!(host.to_str(), "example.com");
+        assert_eq!(request.host.unwrap().host_str(), "example.com");
+    }
+
     #[tokio::test]
     async fn read_get() {
         let content = b"GET http://example.com/v/a/x HTTP/1.1\r\n\
@@ -546,4 +673,39 @@ mod tests {
                 .unwrap();
         assert!(!request.keep_alive());
     }
+
+    #[tokio::test]
+    async fn upgrade_connect_udp() {
+        let content =
+            b"GET https://proxy.example.org/.well-known/masque/udp/192.0.2.6/443/ HTTP/1.1\r\n\
+            Host: proxy.example.org\r\n\
+            Connection: Upgrade\r\n\
+            Upgrade: connect-udp\r\n\
+            Capsule-Protocol: ?1\r\n\r\n";
+        let stream = tokio_stream::iter(vec![Result::Ok(Bytes::from_static(content))]);
+        let stream = StreamReader::new(stream);
+        let mut buf_stream = BufReader::new(stream);
+        let mut version = Version::HTTP_11;
+        let request =
+            HttpProxyClientRequest::parse(&mut buf_stream, 4096, &mut version, parse_more_header)
+                .await
+                .unwrap();
+        assert!(request.is_connect_udp());
+        assert!(request.hop_by_hop_headers.contains_key(header::UPGRADE));
+
+        let content = b"GET http://example.com/chat HTTP/1.1\r\n\
+            Host: example.com\r\n\
+            Connection: Upgrade\r\n\
+            Upgrade: websocket\r\n\r\n";
+        let stream = tokio_stream::iter(vec![Result::Ok(Bytes::from_static(content))]);
+        let stream = StreamReader::new(stream);
+        let mut buf_stream = BufReader::new(stream);
+        let result =
+            HttpProxyClientRequest::parse(&mut buf_stream, 4096, &mut version, parse_more_header)
+                .await;
+        assert!(matches!(
+            result,
+            Err(HttpRequestParseError::UpgradeIsNotSupported)
+        ));
+    }
 }
@@ -0,0 +1,338 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{IoSlice, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::BufMut;
+use tokio::io::AsyncBufRead;
+use tokio::time::Instant;
+
+use g3_http::server::HttpAdaptedRequest;
+use g3_http::{ChunkedTransfer, HttpBodyReader, HttpBodyType};
+use g3_io_ext::{
+    IdleCheck, LimitedBufReadExt, LimitedCopyConfig, LimitedCopyError, LimitedWriteExt,
+};
+
+use super::h1::HttpAdapterErrorResponse;
+use super::response::ReqmodResponse;
+use super::{IcapReqmodClient, IcapReqmodResponsePayload};
+use crate::{IcapClientConnection, IcapClientReader, IcapClientWriter, IcapServiceClient};
+
+mod error;
+pub use error::SmtpAdaptationError;
+
+const SMTP_MESSAGE_HTTP_HEADER: &[u8] =
+    b"PUT / HTTP/1.1\r\nContent-Type: message/rfc822\r\nTransfer-Encoding: chunked\r\n\r\n";
+
+impl IcapReqmodClient {
+    pub async fn smtp_message_adapter<I: IdleCheck>(
+        &self,
+        copy_config: LimitedCopyConfig,
+        idle_checker: I,
+    ) -> anyhow::Result<SmtpMessageAdapter<I>> {
+        let icap_client = self.inner.clone();
+        let (icap_connection, _icap_options) = icap_client.fetch_connection().await?;
+        Ok(SmtpMessageAdapter {
+            icap_client,
+            icap_connection,
+            copy_config,
+            idle_checker,
+            client_addr: None,
+            client_username: None,
+        })
+    }
+}
+
+/// Send the SMTP mail message to the ICAP REQMOD service as a `message/rfc822` HTTP request body
+pub struct SmtpMessageAdapter<I: IdleCheck> {
+    icap_client: Arc<IcapServiceClient>,
+    icap_connection: IcapClientConnection,
+    copy_config: LimitedCopyConfig,
+    idle_checker: I,
+    client_addr: Option<SocketAddr>,
+    client_username: Option<String>,
+}
+
+pub enum SmtpAdaptationEndState {
+    /// the adapted message, which should be sent to the upstream server
+    AdaptedMessage(SmtpAdaptedMessageBody),
+    /// the message is blocked by the ICAP server
+    HttpErrResponse(HttpAdapterErrorResponse),
+}
+
+pub struct SmtpAdaptedMessageBody {
+    icap_client: Arc<IcapServiceClient>,
+    icap_keepalive: bool,
+    icap_connection: IcapClientConnection,
+}
+
+impl SmtpAdaptedMessageBody {
+    pub fn body_reader(&mut self) -> HttpBodyReader<'_, impl AsyncBufRead> {
+        HttpBodyReader::new(&mut self.icap_connection.1, HttpBodyType::Chunked, 1024)
+    }
+
+    pub async fn save_connection(self) {
+        if self.icap_keepalive {
+            self.icap_client.save_connection(self.icap_connection).await;
+        }
+    }
+}
+
+impl<I: IdleCheck> SmtpMessageAdapter<I> {
+    pub fn set_client_addr(&mut self, addr: SocketAddr) {
+        self.client_addr = Some(addr);
+    }
+
+    pub fn set_client_username(&mut self, user: &str) {
+        self.client_username = Some(user.to_string());
+    }
+
+    fn push_extended_headers(&self, data: &mut Vec<u8>) {
+        if let Some(addr) = self.client_addr {
+            crate::serialize::add_client_addr(data, addr);
+        }
+        if let Some(user) = &self.client_username {
+            crate::serialize::add_client_username(data, user);
+        }
+    }
+
+    fn build_forward_all_request(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(self.icap_client.partial_request_header.len() + 128);
+        header.extend_from_slice(&self.icap_client.partial_request_header);
+        self.push_extended_headers(&mut header);
+        let _ = write!(
+            header,
+            "Encapsulated: req-hdr=0, req-body={}\r\n",
+            SMTP_MESSAGE_HTTP_HEADER.len()
+        );
+        header.put_slice(b"\r\n");
+        header
+    }
+
+    /// Send the whole message to the ICAP server, the `msg_reader` should return EOF at the end of
+    /// the message.
+    pub async fn xfer<CR>(
+        mut self,
+        msg_reader: &mut CR,
+    ) -> Result<SmtpAdaptationEndState, SmtpAdaptationError>
+    where
+        CR: AsyncBufRead + Unpin,
+    {
+        let icap_header = self.build_forward_all_request();
+
+        let icap_w = &mut self.icap_connection.0;
+        icap_w
+            .write_all_vectored([
+                IoSlice::new(&icap_header),
+                IoSlice::new(SMTP_MESSAGE_HTTP_HEADER),
+            ])
+            .await
+            .map_err(SmtpAdaptationError::IcapServerWriteFailed)?;
+
+        let mut body_transfer = ChunkedTransfer::new(
+            msg_reader,
+            &mut self.icap_connection.0,
+            HttpBodyType::ReadUntilEnd,
+            1024,
+            self.copy_config,
+        );
+        let early_rsp = transfer_and_wait(
+            &mut body_transfer,
+            &mut self.icap_connection.1,
+            &self.idle_checker,
+        )
+        .await?;
+        if early_rsp {
+            // the icap server may send response before the end of the message,
+            // we should still send all the left data to it
+            finish_transfer(&mut body_transfer, &self.idle_checker).await?;
+        }
+        let body_transfer_finished = body_transfer.finished();
+        drop(body_transfer);
+
+        let rsp = ReqmodResponse::parse(
+            &mut self.icap_connection.1,
+            self.icap_client.config.icap_max_header_size,
+            &self.icap_client.config.respond_shared_names,
+        )
+        .await?;
+        match rsp.code {
+            204 | 206 => {
+                return Err(SmtpAdaptationError::IcapServerErrorResponse(
+                    rsp.code, rsp.reason,
+                ))
+            }
+            n if (200..300).contains(&n) => {}
+            _ => {
+                return Err(SmtpAdaptationError::IcapServerErrorResponse(
+                    rsp.code, rsp.reason,
+                ))
+            }
+        }
+
+        match rsp.payload {
+            IcapReqmodResponsePayload::NoPayload => {
+                if rsp.keep_alive && body_transfer_finished {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                // there should be a payload
+                Err(SmtpAdaptationError::IcapServerErrorResponse(
+                    rsp.code, rsp.reason,
+                ))
+            }
+            IcapReqmodResponsePayload::HttpRequestWithoutBody(_) => Err(
+                SmtpAdaptationError::NotImplemented("adapted smtp message without body"),
+            ),
+            IcapReqmodResponsePayload::HttpRequestWithBody(header_size) => {
+                let _http_req =
+                    HttpAdaptedRequest::parse(&mut self.icap_connection.1, header_size, true)
+                        .await?;
+                Ok(SmtpAdaptationEndState::AdaptedMessage(
+                    SmtpAdaptedMessageBody {
+                        icap_client: self.icap_client,
+                        icap_keepalive: rsp.keep_alive,
+                        icap_connection: self.icap_connection,
+                    },
+                ))
+            }
+            IcapReqmodResponsePayload::HttpResponseWithoutBody(header_size) => {
+                let http_rsp =
+                    HttpAdapterErrorResponse::parse(&mut self.icap_connection.1, header_size)
+                        .await?;
+                if rsp.keep_alive && body_transfer_finished {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                Ok(SmtpAdaptationEndState::HttpErrResponse(http_rsp))
+            }
+            IcapReqmodResponsePayload::HttpResponseWithBody(header_size) => {
+                // the response body is not needed, so the connection won't be reused
+                let http_rsp =
+                    HttpAdapterErrorResponse::parse(&mut self.icap_connection.1, header_size)
+                        .await?;
+                Ok(SmtpAdaptationEndState::HttpErrResponse(http_rsp))
+            }
+        }
+    }
+}
+
+/// return true if the icap response arrives before the end of the message transfer
+async fn transfer_and_wait<CR, I>(
+    mut body_transfer: &mut ChunkedTransfer<'_, CR, IcapClientWriter>,
+    icap_reader: &mut IcapClientReader,
+    idle_checker: &I,
+) -> Result<bool, SmtpAdaptationError>
+where
+    CR: AsyncBufRead + Unpin,
+    I: IdleCheck,
+{
+    let idle_duration = idle_checker.idle_duration();
+    let mut idle_interval = tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+    let mut idle_count = 0;
+
+    loop {
+        tokio::select! {
+            biased;
+
+            r = &mut body_transfer => {
+                return match r {
+                    Ok(_) => Ok(false),
+                    Err(LimitedCopyError::ReadFailed(e)) => Err(SmtpAdaptationError::SmtpClientReadFailed(e)),
+                    Err(LimitedCopyError::WriteFailed(e)) => Err(SmtpAdaptationError::IcapServerWriteFailed(e)),
+                };
+            }
+            r = icap_reader.fill_wait_data() => {
+                return match r {
+                    Ok(true) => Ok(true),
+                    Ok(false) => Err(SmtpAdaptationError::IcapServerConnectionClosed),
+                    Err(e) => Err(SmtpAdaptationError::IcapServerReadFailed(e)),
+                };
+            }
+            _ = idle_interval.tick() => {
+                if body_transfer.is_idle() {
+                    idle_count += 1;
+
+                    let quit = idle_checker.check_quit(idle_count);
+                    if quit {
+                        return if body_transfer.no_cached_data() {
+                            Err(SmtpAdaptationError::SmtpClientReadIdle)
+                        } else {
+                            Err(SmtpAdaptationError::IcapServerWriteIdle)
+                        };
+                    }
+                } else {
+                    idle_count = 0;
+
+                    body_transfer.reset_active();
+                }
+
+                if let Some(reason) = idle_checker.check_force_quit() {
+                    return Err(SmtpAdaptationError::IdleForceQuit(reason));
+                }
+            }
+        }
+    }
+}
+
+async fn finish_transfer<CR, I>(
+    mut body_transfer: &mut ChunkedTransfer<'_, CR, IcapClientWriter>,
+    idle_checker: &I,
+) -> Result<(), SmtpAdaptationError>
+where
+    CR: AsyncBufRead + Unpin,
+    I: IdleCheck,
+{
+    let idle_duration = idle_checker.idle_duration();
+    let mut idle_interval = tokio::time::interval_at(Instant::now() + idle_duration, idle_duration);
+    let mut idle_count = 0;
+
+    loop {
+        tokio::select! {
+            biased;
+
+            r = &mut body_transfer => {
+                return match r {
+                    Ok(_) => Ok(()),
+                    Err(LimitedCopyError::ReadFailed(e)) => Err(SmtpAdaptationError::SmtpClientReadFailed(e)),
+                    Err(LimitedCopyError::WriteFailed(e)) => Err(SmtpAdaptationError::IcapServerWriteFailed(e)),
+                };
+            }
+            _ = idle_interval.tick() => {
+                if body_transfer.is_idle() {
+                    idle_count += 1;
+
+                    let quit = idle_checker.check_quit(idle_count);
+                    if quit {
+                        return if body_transfer.no_cached_data() {
+                            Err(SmtpAdaptationError::SmtpClientReadIdle)
+                        } else {
+                            Err(SmtpAdaptationError::IcapServerWriteIdle)
+                        };
+                    }
+                } else {
+                    idle_count = 0;
+
+                    body_transfer.reset_active();
+                }
+
+                if let Some(reason) = idle_checker.check_force_quit() {
+                    return Err(SmtpAdaptationError::IdleForceQuit(reason));
+                }
+            }
+        }
+    }
+}
@@ -28,6 +28,8 @@ mod response;
 
 pub mod h1;
 pub mod h2;
+pub mod mail;
+pub mod websocket;
 
 #[derive(Clone)]
 pub struct IcapReqmodClient {
@@ -0,0 +1,229 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{IoSlice, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::BufMut;
+use tokio::io::AsyncWriteExt;
+
+use g3_http::server::HttpAdaptedRequest;
+use g3_io_ext::LimitedWriteExt;
+use g3_types::net::UpstreamAddr;
+
+use super::h1::HttpAdapterErrorResponse;
+use super::response::ReqmodResponse;
+use super::{IcapReqmodClient, IcapReqmodResponsePayload};
+use crate::options::IcapServiceOptions;
+use crate::{IcapClientConnection, IcapServiceClient};
+
+mod error;
+pub use error::WebSocketAdaptationError;
+
+impl IcapReqmodClient {
+    pub async fn websocket_message_adapter(
+        &self,
+        upstream: &UpstreamAddr,
+    ) -> anyhow::Result<WebSocketMessageAdapter> {
+        let icap_client = self.inner.clone();
+        let (icap_connection, icap_options) = icap_client.fetch_connection().await?;
+        Ok(WebSocketMessageAdapter {
+            icap_client,
+            icap_connection,
+            icap_options,
+            upstream: upstream.clone(),
+            client_addr: None,
+            client_username: None,
+        })
+    }
+}
+
+/// Send a complete WebSocket text message to the ICAP REQMOD service as a `text/plain`
+/// HTTP request body.
+///
+/// Only the decision of the ICAP server is used, modified messages won't be sent to the
+/// WebSocket peer.
+pub struct WebSocketMessageAdapter {
+    icap_client: Arc<IcapServiceClient>,
+    icap_connection: IcapClientConnection,
+    icap_options: Arc<IcapServiceOptions>,
+    upstream: UpstreamAddr,
+    client_addr: Option<SocketAddr>,
+    client_username: Option<String>,
+}
+
+pub enum WebSocketAdaptationEndState {
+    /// the original message should be sent
+    OriginalMessage,
+    /// the message is blocked by the ICAP server
+    HttpErrResponse(HttpAdapterErrorResponse),
+}
+
+impl WebSocketMessageAdapter {
+    pub fn set_client_addr(&mut self, addr: SocketAddr) {
+        self.client_addr = Some(addr);
+    }
+
+    pub fn set_client_username(&mut self, user: &str) {
+        self.client_username = Some(user.to_string());
+    }
+
+    fn push_extended_headers(&self, data: &mut Vec<u8>) {
+        if let Some(addr) = self.client_addr {
+            crate::serialize::add_client_addr(data, addr);
+        }
+        if let Some(user) = &self.client_username {
+            crate::serialize::add_client_username(data, user);
+        }
+    }
+
+    fn build_http_header(&self, body_len: usize) -> Vec<u8> {
+        let mut header = Vec::with_capacity(128);
+        let _ = write!(
+            header,
+            "POST / HTTP/1.1\r\nHost: {}\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nContent-Length: {body_len}\r\n\r\n",
+            self.upstream
+        );
+        header
+    }
+
+    fn build_forward_all_request(&self, http_header_len: usize) -> Vec<u8> {
+        let mut header = Vec::with_capacity(self.icap_client.partial_request_header.len() + 128);
+        header.extend_from_slice(&self.icap_client.partial_request_header);
+        self.push_extended_headers(&mut header);
+        if self.icap_options.support_204 {
+            header.put_slice(b"Allow: 204\r\n");
+        }
+        let _ = write!(
+            header,
+            "Encapsulated: req-hdr=0, req-body={http_header_len}\r\n",
+        );
+        header.put_slice(b"\r\n");
+        header
+    }
+
+    /// Send the whole message payload, which should already be unmasked and decompressed,
+    /// to the ICAP server.
+    pub async fn xfer(
+        mut self,
+        message: &[u8],
+    ) -> Result<WebSocketAdaptationEndState, WebSocketAdaptationError> {
+        let http_header = self.build_http_header(message.len());
+        let icap_header = self.build_forward_all_request(http_header.len());
+        let chunk_header = format!("{:x}\r\n", message.len());
+
+        let icap_w = &mut self.icap_connection.0;
+        let r = if message.is_empty() {
+            icap_w
+                .write_all_vectored([
+                    IoSlice::new(&icap_header),
+                    IoSlice::new(&http_header),
+                    IoSlice::new(b"0\r\n\r\n"),
+                ])
+                .await
+        } else {
+            icap_w
+                .write_all_vectored([
+                    IoSlice::new(&icap_header),
+                    IoSlice::new(&http_header),
+                    IoSlice::new(chunk_header.as_bytes()),
+                    IoSlice::new(message),
+                    IoSlice::new(b"\r\n0\r\n\r\n"),
+                ])
+                .await
+        };
+        r.map_err(WebSocketAdaptationError::IcapServerWriteFailed)?;
+        icap_w
+            .flush()
+            .await
+            .map_err(WebSocketAdaptationError::IcapServerWriteFailed)?;
+
+        let rsp = ReqmodResponse::parse(
+            &mut self.icap_connection.1,
+            self.icap_client.config.icap_max_header_size,
+            &self.icap_client.config.respond_shared_names,
+        )
+        .await?;
+        match rsp.code {
+            204 => {
+                if rsp.keep_alive {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                return Ok(WebSocketAdaptationEndState::OriginalMessage);
+            }
+            206 => {
+                return Err(WebSocketAdaptationError::IcapServerErrorResponse(
+                    rsp.code, rsp.reason,
+                ))
+            }
+            n if (200..300).contains(&n) => {}
+            _ => {
+                if rsp.keep_alive && rsp.payload == IcapReqmodResponsePayload::NoPayload {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                return Err(WebSocketAdaptationError::IcapServerErrorResponse(
+                    rsp.code, rsp.reason,
+                ));
+            }
+        }
+
+        match rsp.payload {
+            IcapReqmodResponsePayload::NoPayload => {
+                if rsp.keep_alive {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                // there should be a payload
+                Err(WebSocketAdaptationError::IcapServerErrorResponse(
+                    rsp.code, rsp.reason,
+                ))
+            }
+            IcapReqmodResponsePayload::HttpRequestWithoutBody(header_size) => {
+                let _http_req =
+                    HttpAdaptedRequest::parse(&mut self.icap_connection.1, header_size, true)
+                        .await?;
+                if rsp.keep_alive {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                Ok(WebSocketAdaptationEndState::OriginalMessage)
+            }
+            IcapReqmodResponsePayload::HttpRequestWithBody(header_size) => {
+                // the adapted body is not needed, so the connection won't be reused
+                let _http_req =
+                    HttpAdaptedRequest::parse(&mut self.icap_connection.1, header_size, true)
+                        .await?;
+                Ok(WebSocketAdaptationEndState::OriginalMessage)
+            }
+            IcapReqmodResponsePayload::HttpResponseWithoutBody(header_size) => {
+                let http_rsp =
+                    HttpAdapterErrorResponse::parse(&mut self.icap_connection.1, header_size)
+                        .await?;
+                if rsp.keep_alive {
+                    self.icap_client.save_connection(self.icap_connection).await;
+                }
+                Ok(WebSocketAdaptationEndState::HttpErrResponse(http_rsp))
+            }
+            IcapReqmodResponsePayload::HttpResponseWithBody(header_size) => {
+                // the response body is not needed, so the connection won't be reused
+                let http_rsp =
+                    HttpAdapterErrorResponse::parse(&mut self.icap_connection.1, header_size)
+                        .await?;
+                Ok(WebSocketAdaptationEndState::HttpErrResponse(http_rsp))
+            }
+        }
+    }
+}
@@ -151,6 +151,9 @@ impl IcapServiceConfig {
                     basic_auth.encoded_value()
                 );
             }
+            HttpAuth::Bearer(bearer_auth) => {
+                let _ = write!(header, "Authorization: Bearer {}\r\n", bearer_auth.token());
+            }
         }
     }
 }
@@ -0,0 +1,284 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CommandLineError {
+    #[error("no trailing sequence")]
+    NoTrailingSequence,
+    #[error("invalid tag")]
+    InvalidTag,
+    #[error("no command")]
+    NoCommand,
+    #[error("invalid utf-8 command")]
+    InvalidUtf8Command,
+    #[error("invalid argument")]
+    InvalidArgument,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParsedCommand {
+    Capability,
+    Noop,
+    Logout,
+    StartTls,
+    Authenticate(String),
+    /// the user name will be `None` if it's sent as a literal
+    Login(Option<String>),
+    /// the mailbox name will be `None` if it's sent as a literal
+    Select(Option<String>),
+    /// the mailbox name will be `None` if it's sent as a literal
+    Examine(Option<String>),
+    Fetch {
+        uid: bool,
+        sequence: String,
+    },
+    Idle,
+    Compress,
+    /// other commands, with the upper case command name set
+    Other(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Command {
+    pub tag: String,
+    pub parsed: ParsedCommand,
+}
+
+impl Command {
+    /// the recommended line size limit, see RFC 7162 Section 4
+    pub const MAX_LINE_SIZE: usize = 8192;
+    /// the max size of a continuation line for AUTHENTICATE or IDLE command
+    pub const MAX_CONTINUE_LINE_SIZE: usize = 12288;
+
+    pub fn parse_line(line: &[u8]) -> Result<Self, CommandLineError> {
+        let line = line
+            .strip_suffix(b"\r\n")
+            .ok_or(CommandLineError::NoTrailingSequence)?;
+
+        let Some(p) = memchr::memchr(b' ', line) else {
+            return if line.is_empty() {
+                Err(CommandLineError::InvalidTag)
+            } else {
+                Err(CommandLineError::NoCommand)
+            };
+        };
+        let tag = &line[..p];
+        if tag.is_empty() || !tag.iter().all(|c| is_tag_char(*c)) {
+            return Err(CommandLineError::InvalidTag);
+        }
+        let tag = str::from_utf8(tag).map_err(|_| CommandLineError::InvalidTag)?;
+
+        let (cmd, args) = split_token(&line[p + 1..]);
+        if cmd.is_empty() {
+            return Err(CommandLineError::NoCommand);
+        }
+        let cmd = str::from_utf8(cmd).map_err(|_| CommandLineError::InvalidUtf8Command)?;
+        let upper_cmd = cmd.to_uppercase();
+
+        let parsed = match upper_cmd.as_str() {
+            "CAPABILITY" => ParsedCommand::Capability,
+            "NOOP" => ParsedCommand::Noop,
+            "LOGOUT" => ParsedCommand::Logout,
+            "STARTTLS" => ParsedCommand::StartTls,
+            "AUTHENTICATE" => {
+                let (mechanism, _) = split_token(args);
+                if mechanism.is_empty() {
+                    return Err(CommandLineError::InvalidArgument);
+                }
+                let mechanism =
+                    str::from_utf8(mechanism).map_err(|_| CommandLineError::InvalidArgument)?;
+                ParsedCommand::Authenticate(mechanism.to_uppercase())
+            }
+            "LOGIN" => ParsedCommand::Login(parse_astring(args)?),
+            "SELECT" => ParsedCommand::Select(parse_astring(args)?),
+            "EXAMINE" => ParsedCommand::Examine(parse_astring(args)?),
+            "FETCH" => ParsedCommand::Fetch {
+                uid: false,
+                sequence: parse_sequence(args)?,
+            },
+            "IDLE" => ParsedCommand::Idle,
+            "COMPRESS" => ParsedCommand::Compress,
+            "UID" => {
+                let (sub_cmd, args) = split_token(args);
+                let sub_cmd =
+                    str::from_utf8(sub_cmd).map_err(|_| CommandLineError::InvalidUtf8Command)?;
+                let upper_sub_cmd = sub_cmd.to_uppercase();
+                match upper_sub_cmd.as_str() {
+                    "FETCH" => ParsedCommand::Fetch {
+                        uid: true,
+                        sequence: parse_sequence(args)?,
+                    },
+                    "" => return Err(CommandLineError::NoCommand),
+                    _ => ParsedCommand::Other(format!("UID {upper_sub_cmd}")),
+                }
+            }
+            _ => ParsedCommand::Other(upper_cmd),
+        };
+        Ok(Command {
+            tag: tag.to_string(),
+            parsed,
+        })
+    }
+}
+
+fn split_token(buf: &[u8]) -> (&[u8], &[u8]) {
+    match memchr::memchr(b' ', buf) {
+        Some(p) => (&buf[..p], &buf[p + 1..]),
+        None => (buf, &[]),
+    }
+}
+
+fn parse_sequence(args: &[u8]) -> Result<String, CommandLineError> {
+    let (sequence, _) = split_token(args);
+    if sequence.is_empty()
+        || !sequence
+            .iter()
+            .all(|c| matches!(c, b'0'..=b'9' | b':' | b',' | b'*' | b'$'))
+    {
+        return Err(CommandLineError::InvalidArgument);
+    }
+    let sequence = str::from_utf8(sequence).map_err(|_| CommandLineError::InvalidArgument)?;
+    Ok(sequence.to_string())
+}
+
+/// Parse the leading astring argument, `None` will be returned if it's a literal
+fn parse_astring(args: &[u8]) -> Result<Option<String>, CommandLineError> {
+    match args.first() {
+        Some(b'"') => {
+            let mut value = Vec::with_capacity(args.len());
+            let mut escaped = false;
+            for c in &args[1..] {
+                if escaped {
+                    value.push(*c);
+                    escaped = false;
+                    continue;
+                }
+                match c {
+                    b'\\' => escaped = true,
+                    b'"' => {
+                        let s = String::from_utf8(value)
+                            .map_err(|_| CommandLineError::InvalidArgument)?;
+                        return Ok(Some(s));
+                    }
+                    _ => value.push(*c),
+                }
+            }
+            Err(CommandLineError::InvalidArgument)
+        }
+        Some(b'{') | Some(b'~') => Ok(None),
+        Some(_) => {
+            let (atom, _) = split_token(args);
+            let s = str::from_utf8(atom).map_err(|_| CommandLineError::InvalidArgument)?;
+            Ok(Some(s.to_string()))
+        }
+        None => Err(CommandLineError::InvalidArgument),
+    }
+}
+
+/// tag = 1*<any ASTRING-CHAR except "+">
+fn is_tag_char(c: u8) -> bool {
+    match c {
+        b'(' | b')' | b'{' | b'%' | b'*' | b'"' | b'\\' | b'+' => false,
+        0x21..=0x7e => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_tls() {
+        let cmd = Command::parse_line(b"a001 starttls\r\n").unwrap();
+        assert_eq!(cmd.tag, "a001");
+        assert_eq!(cmd.parsed, ParsedCommand::StartTls);
+    }
+
+    #[test]
+    fn login() {
+        let cmd = Command::parse_line(b"a001 LOGIN SMITH SESAME\r\n").unwrap();
+        assert_eq!(cmd.parsed, ParsedCommand::Login(Some("SMITH".to_string())));
+
+        let cmd = Command::parse_line(b"a001 login \"J \\\"S\\\"\" pass\r\n").unwrap();
+        assert_eq!(
+            cmd.parsed,
+            ParsedCommand::Login(Some("J \"S\"".to_string()))
+        );
+
+        let cmd = Command::parse_line(b"a001 LOGIN {11+}\r\n").unwrap();
+        assert_eq!(cmd.parsed, ParsedCommand::Login(None));
+    }
+
+    #[test]
+    fn select() {
+        let cmd = Command::parse_line(b"A142 SELECT INBOX\r\n").unwrap();
+        assert_eq!(cmd.tag, "A142");
+        assert_eq!(cmd.parsed, ParsedCommand::Select(Some("INBOX".to_string())));
+
+        let cmd = Command::parse_line(b"A932 EXAMINE \"blurdybloop\"\r\n").unwrap();
+        assert_eq!(
+            cmd.parsed,
+            ParsedCommand::Examine(Some("blurdybloop".to_string()))
+        );
+    }
+
+    #[test]
+    fn fetch() {
+        let cmd =
+            Command::parse_line(b"A654 FETCH 2:4 (FLAGS BODY[HEADER.FIELDS (DATE FROM)])\r\n")
+                .unwrap();
+        assert_eq!(
+            cmd.parsed,
+            ParsedCommand::Fetch {
+                uid: false,
+                sequence: "2:4".to_string()
+            }
+        );
+
+        let cmd = Command::parse_line(b"A999 UID FETCH 4827313:4828442 FLAGS\r\n").unwrap();
+        assert_eq!(
+            cmd.parsed,
+            ParsedCommand::Fetch {
+                uid: true,
+                sequence: "4827313:4828442".to_string()
+            }
+        );
+
+        let cmd = Command::parse_line(b"A003 UID STORE 1 +FLAGS (\\Deleted)\r\n").unwrap();
+        assert_eq!(cmd.parsed, ParsedCommand::Other("UID STORE".to_string()));
+    }
+
+    #[test]
+    fn other() {
+        let cmd = Command::parse_line(b"A003 APPEND saved-messages (\\Seen) {310}\r\n").unwrap();
+        assert_eq!(cmd.parsed, ParsedCommand::Other("APPEND".to_string()));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Command::parse_line(b"A001 NOOP").is_err());
+        assert!(Command::parse_line(b"A001\r\n").is_err());
+        assert!(Command::parse_line(b"+001 NOOP\r\n").is_err());
+        assert!(Command::parse_line(b"A001 \r\n").is_err());
+        assert!(Command::parse_line(b"A001 FETCH abc\r\n").is_err());
+        assert!(Command::parse_line(b"A001 LOGIN \"abc\r\n").is_err());
+    }
+}
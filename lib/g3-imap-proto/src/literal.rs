@@ -0,0 +1,91 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str;
+
+/// The literal string at the end of a command or response line, see RFC 9051 Section 4.3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Literal {
+    pub size: u64,
+    /// the sender should wait for a continuation response before sending the literal data
+    pub wait_continuation: bool,
+}
+
+impl Literal {
+    /// Parse the literal at the end of the line, the trailing CRLF should be present
+    pub fn parse_line_end(line: &[u8]) -> Option<Self> {
+        let line = line.strip_suffix(b"\r\n")?;
+        let line = line.strip_suffix(b"}")?;
+        let p = memchr::memrchr(b'{', line)?;
+        let mut size = &line[p + 1..];
+        let mut wait_continuation = true;
+        if let Some(s) = size.strip_suffix(b"+") {
+            // LITERAL+ or LITERAL-, see RFC 7888
+            size = s;
+            wait_continuation = false;
+        }
+        if size.is_empty() || !size.iter().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let size = str::from_utf8(size).ok()?.parse::<u64>().ok()?;
+        Some(Literal {
+            size,
+            wait_continuation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync() {
+        let literal = Literal::parse_line_end(b"A003 APPEND saved-messages (\\Seen) {310}\r\n");
+        assert_eq!(
+            literal,
+            Some(Literal {
+                size: 310,
+                wait_continuation: true
+            })
+        );
+    }
+
+    #[test]
+    fn non_sync() {
+        let literal = Literal::parse_line_end(b"A001 LOGIN {11+}\r\n");
+        assert_eq!(
+            literal,
+            Some(Literal {
+                size: 11,
+                wait_continuation: false
+            })
+        );
+    }
+
+    #[test]
+    fn binary() {
+        let literal = Literal::parse_line_end(b"* 12 FETCH (BINARY[1] ~{1024}\r\n");
+        assert_eq!(literal.map(|l| l.size), Some(1024));
+    }
+
+    #[test]
+    fn no_literal() {
+        assert!(Literal::parse_line_end(b"A001 LOGIN user pass\r\n").is_none());
+        assert!(Literal::parse_line_end(b"A001 LOGIN user {}\r\n").is_none());
+        assert!(Literal::parse_line_end(b"A001 LOGIN user {12}").is_none());
+    }
+}
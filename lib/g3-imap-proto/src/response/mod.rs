@@ -0,0 +1,174 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ResponseLineError {
+    #[error("no trailing sequence")]
+    NoTrailingSequence,
+    #[error("invalid tag")]
+    InvalidTag,
+    #[error("no status or keyword")]
+    NoKeyword,
+    #[error("invalid status")]
+    InvalidStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServerStatus {
+    Ok,
+    No,
+    Bad,
+    PreAuth,
+    Bye,
+}
+
+impl ServerStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServerStatus::Ok => "OK",
+            ServerStatus::No => "NO",
+            ServerStatus::Bad => "BAD",
+            ServerStatus::PreAuth => "PREAUTH",
+            ServerStatus::Bye => "BYE",
+        }
+    }
+
+    fn parse(s: &[u8]) -> Option<Self> {
+        match s.to_ascii_uppercase().as_slice() {
+            b"OK" => Some(ServerStatus::Ok),
+            b"NO" => Some(ServerStatus::No),
+            b"BAD" => Some(ServerStatus::Bad),
+            b"PREAUTH" => Some(ServerStatus::PreAuth),
+            b"BYE" => Some(ServerStatus::Bye),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Response {
+    /// command continuation request
+    Continuation,
+    /// untagged status response
+    UntaggedStatus(ServerStatus),
+    /// untagged data response, with the upper case keyword set.
+    /// the leading number will be skipped for message data
+    UntaggedData(String),
+    /// tagged status response, which indicates the completion of a command
+    Tagged { tag: String, status: ServerStatus },
+}
+
+impl Response {
+    pub const MAX_LINE_SIZE: usize = 8192;
+
+    pub fn parse_line(line: &[u8]) -> Result<Self, ResponseLineError> {
+        let line = line
+            .strip_suffix(b"\r\n")
+            .ok_or(ResponseLineError::NoTrailingSequence)?;
+
+        match line.first() {
+            Some(b'+') => return Ok(Response::Continuation),
+            Some(b'*') => {}
+            Some(_) => {
+                let (tag, left) = split_token(line);
+                let tag = str::from_utf8(tag).map_err(|_| ResponseLineError::InvalidTag)?;
+                let (status, _) = split_token(left);
+                let status = ServerStatus::parse(status).ok_or(ResponseLineError::InvalidStatus)?;
+                return match status {
+                    ServerStatus::Ok | ServerStatus::No | ServerStatus::Bad => {
+                        Ok(Response::Tagged {
+                            tag: tag.to_string(),
+                            status,
+                        })
+                    }
+                    _ => Err(ResponseLineError::InvalidStatus),
+                };
+            }
+            None => return Err(ResponseLineError::InvalidTag),
+        }
+
+        let Some(left) = line[1..].strip_prefix(b" ") else {
+            return Err(ResponseLineError::InvalidTag);
+        };
+        let (mut keyword, left) = split_token(left);
+        if keyword.iter().all(|c| c.is_ascii_digit()) {
+            keyword = split_token(left).0;
+        }
+        if keyword.is_empty() {
+            return Err(ResponseLineError::NoKeyword);
+        }
+        if let Some(status) = ServerStatus::parse(keyword) {
+            return Ok(Response::UntaggedStatus(status));
+        }
+        let keyword = str::from_utf8(keyword).map_err(|_| ResponseLineError::NoKeyword)?;
+        Ok(Response::UntaggedData(keyword.to_uppercase()))
+    }
+}
+
+fn split_token(buf: &[u8]) -> (&[u8], &[u8]) {
+    match memchr::memchr(b' ', buf) {
+        Some(p) => (&buf[..p], &buf[p + 1..]),
+        None => (buf, &[]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greeting() {
+        let rsp = Response::parse_line(b"* OK IMAP4rev2 Service Ready\r\n").unwrap();
+        assert_eq!(rsp, Response::UntaggedStatus(ServerStatus::Ok));
+
+        let rsp =
+            Response::parse_line(b"* PREAUTH IMAP4rev2 server logged in as Smith\r\n").unwrap();
+        assert_eq!(rsp, Response::UntaggedStatus(ServerStatus::PreAuth));
+    }
+
+    #[test]
+    fn untagged_data() {
+        let rsp = Response::parse_line(b"* CAPABILITY IMAP4rev2 STARTTLS AUTH=GSSAPI\r\n").unwrap();
+        assert_eq!(rsp, Response::UntaggedData("CAPABILITY".to_string()));
+
+        let rsp = Response::parse_line(b"* 172 EXISTS\r\n").unwrap();
+        assert_eq!(rsp, Response::UntaggedData("EXISTS".to_string()));
+    }
+
+    #[test]
+    fn tagged() {
+        let rsp = Response::parse_line(b"a002 OK Begin TLS negotiation now\r\n").unwrap();
+        assert_eq!(
+            rsp,
+            Response::Tagged {
+                tag: "a002".to_string(),
+                status: ServerStatus::Ok
+            }
+        );
+
+        assert!(Response::parse_line(b"a002 BYE\r\n").is_err());
+    }
+
+    #[test]
+    fn continuation() {
+        let rsp = Response::parse_line(b"+ Ready for additional command text\r\n").unwrap();
+        assert_eq!(rsp, Response::Continuation);
+    }
+}
@@ -37,6 +37,22 @@ pub fn as_usize(v: &Value) -> anyhow::Result<usize> {
     }
 }
 
+pub fn as_u64(v: &Value) -> anyhow::Result<u64> {
+    match v {
+        Value::String(s) => {
+            let v = s.parse::<Bytes>()?;
+            let v = u64::try_from(v.size()).map_err(|e| anyhow!("invalid u64 value: {e}"))?;
+            Ok(v)
+        }
+        Value::Number(n) => n
+            .as_u64()
+            .ok_or_else(|| anyhow!("out of range json value for u64")),
+        _ => Err(anyhow!(
+            "json value type for humanize u64 should be 'string' or 'integer'"
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,4 +84,16 @@ mod tests {
         let j = json!({"v": ["1"]});
         assert!(as_usize(&j["v"]).is_err());
     }
+
+    #[test]
+    fn t_u64() {
+        let j = json!({"v": "1GiB"});
+        assert_eq!(as_u64(&j["v"]).unwrap(), 1 << 30);
+
+        let j = json!({"v": 1024});
+        assert_eq!(as_u64(&j["v"]).unwrap(), 1024);
+
+        let j = json!({"v": -1024});
+        assert!(as_u64(&j["v"]).is_err());
+    }
 }
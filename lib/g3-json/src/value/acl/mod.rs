@@ -27,6 +27,7 @@ mod exact_port;
 mod network;
 mod proxy_request;
 mod regex_set;
+mod url;
 mod user_agent;
 
 pub(crate) use child_domain::as_child_domain_rule_builder;
@@ -37,6 +38,7 @@ pub(crate) use regex_set::as_regex_set_rule_builder;
 pub use exact_port::as_exact_port_rule;
 pub use network::{as_egress_network_rule_builder, as_ingress_network_rule_builder};
 pub use proxy_request::as_proxy_request_rule;
+pub use url::as_url_rule_builder;
 pub use user_agent::as_user_agent_rule;
 
 fn as_action(value: &Value) -> anyhow::Result<AclAction> {
@@ -0,0 +1,105 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use regex::Regex;
+use serde_json::Value;
+
+use g3_types::acl::{AclAction, AclUrlRuleBuilder};
+
+use super::AclRuleJsonParser;
+
+impl AclRuleJsonParser for AclUrlRuleBuilder {
+    #[inline]
+    fn get_default_found_action(&self) -> AclAction {
+        AclAction::Forbid
+    }
+
+    #[inline]
+    fn set_missed_action(&mut self, _action: AclAction) {
+        self.set_missed_action(_action);
+    }
+
+    fn add_rule_for_action(&mut self, action: AclAction, value: &Value) -> anyhow::Result<()> {
+        match value {
+            Value::String(s) => self.add_url_prefix(s, action),
+            Value::Object(map) => {
+                let mut scheme: Option<String> = None;
+                let mut host = None;
+                let mut path: Option<String> = None;
+                let mut regex: Option<Regex> = None;
+
+                for (k, v) in map {
+                    match crate::key::normalize(k).as_str() {
+                        "scheme" => {
+                            let s = crate::value::as_string(v)
+                                .context(format!("invalid string value for key {k}"))?;
+                            if s != "*" {
+                                scheme = Some(s);
+                            }
+                        }
+                        "host" => {
+                            if matches!(v, Value::String(s) if s == "*") {
+                                continue;
+                            }
+                            let h = crate::value::as_host(v)
+                                .context(format!("invalid host value for key {k}"))?;
+                            host = Some(h);
+                        }
+                        "path" | "path_prefix" | "prefix" => {
+                            let s = crate::value::as_string(v)
+                                .context(format!("invalid string value for key {k}"))?;
+                            if !s.starts_with('/') {
+                                return Err(anyhow!("path prefix should start with '/'"));
+                            }
+                            path = Some(s);
+                        }
+                        "regex" | "path_regex" => {
+                            let s = crate::value::as_string(v)
+                                .context(format!("invalid string value for key {k}"))?;
+                            let r =
+                                Regex::new(&s).map_err(|e| anyhow!("invalid regex value: {e}"))?;
+                            regex = Some(r);
+                        }
+                        _ => return Err(anyhow!("invalid key {k}")),
+                    }
+                }
+
+                match (path, regex) {
+                    (Some(_), Some(_)) => Err(anyhow!(
+                        "path prefix and path regex should not be set at the same time"
+                    )),
+                    (None, Some(regex)) => {
+                        self.add_path_regex(scheme.as_deref(), host, &regex, action);
+                        Ok(())
+                    }
+                    (path, None) => {
+                        let path = path.as_deref().unwrap_or("/");
+                        self.add_path_prefix(scheme.as_deref(), host, path, action);
+                        Ok(())
+                    }
+                }
+            }
+            _ => Err(anyhow!("invalid value type")),
+        }
+    }
+}
+
+pub fn as_url_rule_builder(value: &Value) -> anyhow::Result<AclUrlRuleBuilder> {
+    let mut builder = AclUrlRuleBuilder::new(AclAction::Permit);
+    builder.parse(value)?;
+    Ok(builder)
+}
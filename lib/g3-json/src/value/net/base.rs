@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
@@ -23,8 +23,44 @@ use serde_json::Value;
 #[cfg(feature = "acl-rule")]
 use ip_network::IpNetwork;
 
+use g3_types::collection::WeightedValue;
 use g3_types::net::{EgressArea, Host, UpstreamAddr};
 
+pub fn as_sockaddr(v: &Value) -> anyhow::Result<SocketAddr> {
+    if let Value::String(s) = v {
+        SocketAddr::from_str(s).map_err(|e| anyhow!("invalid socket address: {e}"))
+    } else {
+        Err(anyhow!(
+            "json value type for 'SocketAddr' should be 'string'"
+        ))
+    }
+}
+
+pub fn as_weighted_sockaddr(v: &Value) -> anyhow::Result<WeightedValue<SocketAddr>> {
+    const KEY_ADDR: &str = "addr";
+    const KEY_WEIGHT: &str = "weight";
+
+    match v {
+        Value::Object(map) => {
+            let v = crate::map::get_required(map, KEY_ADDR)?;
+            let addr = as_sockaddr(v)
+                .context(format!("invalid sockaddr string value for key {KEY_ADDR}"))?;
+
+            if let Ok(v) = crate::map::get_required(map, KEY_WEIGHT) {
+                let weight = crate::value::as_f64(v)
+                    .context(format!("invalid f64 value for key {KEY_WEIGHT}"))?;
+                Ok(WeightedValue::<SocketAddr>::with_weight(addr, weight))
+            } else {
+                Ok(WeightedValue::new(addr))
+            }
+        }
+        _ => {
+            let s = as_sockaddr(v).context("invalid sockaddr string value")?;
+            Ok(WeightedValue::new(s))
+        }
+    }
+}
+
 pub fn as_ipaddr(v: &Value) -> anyhow::Result<IpAddr> {
     match v {
         Value::String(s) => {
@@ -23,7 +23,10 @@ mod udp;
 #[cfg(feature = "http")]
 mod http;
 
-pub use base::{as_domain, as_egress_area, as_host, as_ipaddr, as_upstream_addr};
+pub use base::{
+    as_domain, as_egress_area, as_host, as_ipaddr, as_sockaddr, as_upstream_addr,
+    as_weighted_sockaddr,
+};
 pub use ports::as_ports;
 pub use proxy::as_proxy_request_type;
 pub use tcp::{as_tcp_connect_config, as_tcp_keepalive_config, as_tcp_misc_sock_opts};
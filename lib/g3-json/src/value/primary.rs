@@ -120,6 +120,18 @@ pub fn as_usize(v: &Value) -> anyhow::Result<usize> {
     }
 }
 
+pub fn as_u64(v: &Value) -> anyhow::Result<u64> {
+    match v {
+        Value::String(s) => Ok(u64::from_str(s)?),
+        Value::Number(n) => n
+            .as_u64()
+            .ok_or_else(|| anyhow!("out of range json value for u64")),
+        _ => Err(anyhow!(
+            "json value type for 'u64' should be 'string' or 'positive integer'"
+        )),
+    }
+}
+
 pub fn as_f64(v: &Value) -> anyhow::Result<f64> {
     match v {
         Value::String(s) => Ok(f64::from_str(s)?),
@@ -0,0 +1,157 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::ptr;
+
+use libc::{c_int, c_void};
+
+use crate::ffi;
+
+static WAIT_FD_KEY: u8 = 0;
+
+fn wait_fd_key() -> *const c_void {
+    &WAIT_FD_KEY as *const u8 as *const c_void
+}
+
+unsafe extern "C" fn cleanup_wait_fd(
+    _ctx: *mut ffi::ASYNC_WAIT_CTX,
+    _key: *const c_void,
+    _fd: c_int,
+    custom_data: *mut c_void,
+) {
+    if !custom_data.is_null() {
+        drop(Box::from_raw(custom_data as *mut UnixStream));
+    }
+}
+
+/// Pause the current OpenSSL async job until the paired [AsyncJobWaker] is called.
+///
+/// A fresh socket pair is registered as the wait fd of the job for each pause, so the async
+/// poller that drives the job only resumes it after the waker has been called.
+pub struct AsyncJobPauser {
+    reader: UnixStream,
+    /// the wait fd used by the previous pause, which should be closed only after the poller has
+    /// seen it deleted from the wait ctx
+    previous: Option<Box<UnixStream>>,
+}
+
+/// Resume the paired [AsyncJobPauser], which can be called in any thread
+pub struct AsyncJobWaker {
+    writer: UnixStream,
+}
+
+impl AsyncJobWaker {
+    pub fn wake(self) {
+        let _ = (&self.writer).write(&[1]);
+    }
+}
+
+impl AsyncJobPauser {
+    /// Create a pauser and waker pair for the current async job.
+    ///
+    /// Return `Ok(None)` if we are not running inside an OpenSSL async job.
+    pub fn new() -> io::Result<Option<(AsyncJobPauser, AsyncJobWaker)>> {
+        let job = unsafe { ffi::ASYNC_get_current_job() };
+        if job.is_null() {
+            return Ok(None);
+        }
+        let wait_ctx = unsafe { ffi::ASYNC_get_wait_ctx(job) };
+        if wait_ctx.is_null() {
+            return Ok(None);
+        }
+
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+        let wait_reader = Box::new(reader.try_clone()?);
+
+        let mut old_fd: c_int = -1;
+        let mut old_data: *mut c_void = ptr::null_mut();
+        let previous = unsafe {
+            let r = ffi::ASYNC_WAIT_CTX_get_fd(wait_ctx, wait_fd_key(), &mut old_fd, &mut old_data);
+            if r == 1 {
+                // clear without calling the cleanup function, we will close it after resume
+                ffi::ASYNC_WAIT_CTX_clear_fd(wait_ctx, wait_fd_key());
+                if old_data.is_null() {
+                    None
+                } else {
+                    Some(Box::from_raw(old_data as *mut UnixStream))
+                }
+            } else {
+                None
+            }
+        };
+
+        let fd = wait_reader.as_raw_fd();
+        let r = unsafe {
+            ffi::ASYNC_WAIT_CTX_set_wait_fd(
+                wait_ctx,
+                wait_fd_key(),
+                fd,
+                Box::into_raw(wait_reader) as *mut c_void,
+                Some(cleanup_wait_fd),
+            )
+        };
+        if r != 1 {
+            return Err(io::Error::other("failed to set wait fd for the async job"));
+        }
+
+        Ok(Some((
+            AsyncJobPauser { reader, previous },
+            AsyncJobWaker { writer },
+        )))
+    }
+
+    /// Pause the async job until `poll` returns some value.
+    ///
+    /// Return `None` if the waker is dropped without being called and `poll` returns nothing.
+    pub fn pause_until<T, F>(mut self, mut poll: F) -> Option<T>
+    where
+        F: FnMut() -> Option<T>,
+    {
+        let mut buf = [0u8; 8];
+        loop {
+            if let Some(v) = poll() {
+                return Some(v);
+            }
+
+            unsafe { ffi::ASYNC_pause_job() };
+
+            match (&self.reader).read(&mut buf) {
+                Ok(0) => return poll(),
+                Ok(_) => {
+                    // resumed by the poller, so it has already untracked the previous wait fd
+                    self.previous.take();
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    // the pause is blocked, fallback to wait in the current thread
+                    if self.reader.set_nonblocking(false).is_err() {
+                        return poll();
+                    }
+                    match (&self.reader).read(&mut buf) {
+                        Ok(0) | Err(_) => return poll(),
+                        Ok(_) => {}
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return poll(),
+            }
+        }
+    }
+}
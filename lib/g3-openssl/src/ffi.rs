@@ -18,8 +18,8 @@
 
 use std::ptr;
 
-use libc::{c_int, c_long, c_void};
-use openssl_sys::{SSL, SSL_CTX};
+use libc::{c_int, c_long, c_uchar, c_uint, c_void};
+use openssl_sys::{BIGNUM, BN_CTX, ECDSA_SIG, EC_KEY, RSA, SSL, SSL_CTX};
 
 pub const ASYNC_ERR: c_int = 0;
 pub const ASYNC_NO_JOBS: c_int = 1;
@@ -37,6 +37,68 @@ pub enum ASYNC_JOB {}
 #[allow(non_camel_case_types)]
 pub enum ASYNC_WAIT_CTX {}
 
+pub const CRYPTO_EX_INDEX_EC_KEY: c_int = 8;
+pub const CRYPTO_EX_INDEX_RSA: c_int = 9;
+
+pub const RSA_METHOD_FLAG_NO_CHECK: c_int = 0x0001;
+
+#[allow(non_camel_case_types)]
+pub enum RSA_METHOD {}
+
+#[allow(non_camel_case_types)]
+pub enum EC_KEY_METHOD {}
+
+#[allow(non_camel_case_types)]
+pub enum CRYPTO_EX_DATA {}
+
+#[allow(non_camel_case_types)]
+pub type CRYPTO_EX_free = unsafe extern "C" fn(
+    parent: *mut c_void,
+    ptr: *mut c_void,
+    ad: *mut CRYPTO_EX_DATA,
+    idx: c_int,
+    argl: c_long,
+    argp: *mut c_void,
+);
+
+#[allow(non_camel_case_types)]
+pub type RSA_priv_op_fn = unsafe extern "C" fn(
+    flen: c_int,
+    from: *const c_uchar,
+    to: *mut c_uchar,
+    rsa: *mut RSA,
+    padding: c_int,
+) -> c_int;
+
+#[allow(non_camel_case_types)]
+pub type EC_KEY_sign_fn = unsafe extern "C" fn(
+    type_: c_int,
+    dgst: *const c_uchar,
+    dlen: c_int,
+    sig: *mut c_uchar,
+    siglen: *mut c_uint,
+    kinv: *const BIGNUM,
+    r: *const BIGNUM,
+    eckey: *mut EC_KEY,
+) -> c_int;
+
+#[allow(non_camel_case_types)]
+pub type EC_KEY_sign_setup_fn = unsafe extern "C" fn(
+    eckey: *mut EC_KEY,
+    ctx_in: *mut BN_CTX,
+    kinvp: *mut *mut BIGNUM,
+    rp: *mut *mut BIGNUM,
+) -> c_int;
+
+#[allow(non_camel_case_types)]
+pub type EC_KEY_sign_sig_fn = unsafe extern "C" fn(
+    dgst: *const c_uchar,
+    dgst_len: c_int,
+    in_kinv: *const BIGNUM,
+    in_r: *const BIGNUM,
+    eckey: *mut EC_KEY,
+) -> *mut ECDSA_SIG;
+
 #[allow(non_camel_case_types)]
 #[cfg(ossl300)]
 pub type ASYNC_callback_fn = Option<unsafe extern "C" fn(arg: *mut c_void) -> c_int>;
@@ -136,4 +198,45 @@ extern "C" {
     pub fn SSL_set_async_callback_arg(s: *mut SSL, arg: *mut c_void) -> c_int;
     #[cfg(ossl300)]
     pub fn SSL_get_async_status(s: *mut SSL) -> c_int;
+
+    pub fn CRYPTO_get_ex_new_index(
+        class_index: c_int,
+        argl: c_long,
+        argp: *mut c_void,
+        new_func: *mut c_void,
+        dup_func: *mut c_void,
+        free_func: Option<CRYPTO_EX_free>,
+    ) -> c_int;
+
+    pub fn RSA_PKCS1_OpenSSL() -> *const RSA_METHOD;
+    pub fn RSA_meth_dup(meth: *const RSA_METHOD) -> *mut RSA_METHOD;
+    pub fn RSA_meth_free(meth: *mut RSA_METHOD);
+    pub fn RSA_meth_get_flags(meth: *const RSA_METHOD) -> c_int;
+    pub fn RSA_meth_set_flags(meth: *mut RSA_METHOD, flags: c_int) -> c_int;
+    pub fn RSA_meth_set_priv_enc(meth: *mut RSA_METHOD, priv_enc: Option<RSA_priv_op_fn>) -> c_int;
+    pub fn RSA_meth_set_priv_dec(meth: *mut RSA_METHOD, priv_dec: Option<RSA_priv_op_fn>) -> c_int;
+    pub fn RSA_set_method(rsa: *mut RSA, meth: *const RSA_METHOD) -> c_int;
+    pub fn RSA_set_ex_data(rsa: *mut RSA, idx: c_int, arg: *mut c_void) -> c_int;
+    pub fn RSA_get_ex_data(rsa: *const RSA, idx: c_int) -> *mut c_void;
+    pub fn RSA_size(rsa: *const RSA) -> c_int;
+    pub fn RSA_padding_add_PKCS1_type_1(
+        to: *mut c_uchar,
+        tlen: c_int,
+        f: *const c_uchar,
+        fl: c_int,
+    ) -> c_int;
+
+    pub fn EC_KEY_OpenSSL() -> *const EC_KEY_METHOD;
+    pub fn EC_KEY_METHOD_new(meth: *const EC_KEY_METHOD) -> *mut EC_KEY_METHOD;
+    pub fn EC_KEY_METHOD_free(meth: *mut EC_KEY_METHOD);
+    pub fn EC_KEY_METHOD_set_sign(
+        meth: *mut EC_KEY_METHOD,
+        sign: Option<EC_KEY_sign_fn>,
+        sign_setup: Option<EC_KEY_sign_setup_fn>,
+        sign_sig: Option<EC_KEY_sign_sig_fn>,
+    );
+    pub fn EC_KEY_set_method(key: *mut EC_KEY, meth: *const EC_KEY_METHOD) -> c_int;
+    pub fn EC_KEY_set_ex_data(key: *mut EC_KEY, idx: c_int, arg: *mut c_void) -> c_int;
+    pub fn EC_KEY_get_ex_data(key: *const EC_KEY, idx: c_int) -> *mut c_void;
+    pub fn ECDSA_size(key: *const EC_KEY) -> c_int;
 }
@@ -21,3 +21,6 @@ pub use ssl::{SslAcceptor, SslConnector, SslLazyAcceptor, SslStream};
 
 #[cfg(feature = "async-job")]
 pub mod async_job;
+
+#[cfg(not(any(feature = "aws-lc", feature = "boringssl")))]
+pub mod pkey;
@@ -0,0 +1,135 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::OnceLock;
+use std::{ptr, slice};
+
+use libc::{c_int, c_uchar, c_uint};
+use openssl::ec::EcKey;
+use openssl::ecdsa::EcdsaSig;
+use openssl::error::ErrorStack;
+use openssl::foreign_types::ForeignType;
+use openssl::pkey::{PKey, Private, Public};
+use openssl_sys::{BIGNUM, ECDSA_SIG, EC_KEY};
+
+use super::{ex_data, ArcPrivateKeyOffload};
+use crate::ffi;
+
+fn ex_index() -> Result<c_int, ErrorStack> {
+    static INDEX: OnceLock<Result<c_int, ErrorStack>> = OnceLock::new();
+    INDEX
+        .get_or_init(|| ex_data::new_index(ffi::CRYPTO_EX_INDEX_EC_KEY))
+        .clone()
+}
+
+fn offload_method() -> Result<*const ffi::EC_KEY_METHOD, ErrorStack> {
+    static METHOD: OnceLock<Result<usize, ErrorStack>> = OnceLock::new();
+    METHOD
+        .get_or_init(|| new_offload_method().map(|meth| meth as usize))
+        .clone()
+        .map(|meth| meth as *const ffi::EC_KEY_METHOD)
+}
+
+fn new_offload_method() -> Result<*mut ffi::EC_KEY_METHOD, ErrorStack> {
+    unsafe {
+        let meth = ffi::EC_KEY_METHOD_new(ffi::EC_KEY_OpenSSL());
+        if meth.is_null() {
+            return Err(ErrorStack::get());
+        }
+        ffi::EC_KEY_METHOD_set_sign(meth, Some(offload_sign), None, Some(offload_sign_sig));
+        Ok(meth)
+    }
+}
+
+pub(super) fn new_offload_key(
+    ec_key: EcKey<Public>,
+    offload: ArcPrivateKeyOffload,
+) -> Result<PKey<Private>, ErrorStack> {
+    let idx = ex_index()?;
+    let meth = offload_method()?;
+
+    unsafe {
+        // the method should be set before assigned to EVP_PKEY,
+        // so the key will be marked as foreign and go through the legacy code path
+        if ffi::EC_KEY_set_method(ec_key.as_ptr(), meth) != 1 {
+            return Err(ErrorStack::get());
+        }
+        let data = ex_data::into_raw(offload);
+        if ffi::EC_KEY_set_ex_data(ec_key.as_ptr(), idx, data) != 1 {
+            ex_data::drop_raw(data);
+            return Err(ErrorStack::get());
+        }
+
+        let ec_key = EcKey::<Private>::from_ptr(ec_key.into_ptr());
+        PKey::from_ec_key(ec_key)
+    }
+}
+
+unsafe fn get_offload<'a>(eckey: *const EC_KEY) -> Option<&'a ArcPrivateKeyOffload> {
+    let idx = ex_index().ok()?;
+    ex_data::as_ref(ffi::EC_KEY_get_ex_data(eckey, idx))
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn offload_sign(
+    _type: c_int,
+    dgst: *const c_uchar,
+    dlen: c_int,
+    sig: *mut c_uchar,
+    siglen: *mut c_uint,
+    _kinv: *const BIGNUM,
+    _r: *const BIGNUM,
+    eckey: *mut EC_KEY,
+) -> c_int {
+    *siglen = 0;
+    let Some(offload) = get_offload(eckey) else {
+        return 0;
+    };
+    let Ok(size) = usize::try_from(ffi::ECDSA_size(eckey)) else {
+        return 0;
+    };
+    let digest = slice::from_raw_parts(dgst, dlen as usize);
+
+    match offload.ecdsa_sign(digest) {
+        Ok(data) if data.len() <= size => {
+            ptr::copy_nonoverlapping(data.as_ptr(), sig, data.len());
+            *siglen = data.len() as c_uint;
+            1
+        }
+        _ => 0,
+    }
+}
+
+unsafe extern "C" fn offload_sign_sig(
+    dgst: *const c_uchar,
+    dgst_len: c_int,
+    _in_kinv: *const BIGNUM,
+    _in_r: *const BIGNUM,
+    eckey: *mut EC_KEY,
+) -> *mut ECDSA_SIG {
+    let Some(offload) = get_offload(eckey) else {
+        return ptr::null_mut();
+    };
+    let digest = slice::from_raw_parts(dgst, dgst_len as usize);
+
+    match offload.ecdsa_sign(digest) {
+        Ok(data) => match EcdsaSig::from_der(&data) {
+            Ok(sig) => sig.into_ptr(),
+            Err(_) => ptr::null_mut(),
+        },
+        Err(_) => ptr::null_mut(),
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::ptr;
+
+use libc::{c_int, c_long, c_void};
+use openssl::error::ErrorStack;
+
+use super::ArcPrivateKeyOffload;
+use crate::ffi;
+
+pub(super) fn new_index(class_index: c_int) -> Result<c_int, ErrorStack> {
+    let idx = unsafe {
+        ffi::CRYPTO_get_ex_new_index(
+            class_index,
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            Some(free_offload),
+        )
+    };
+    if idx < 0 {
+        Err(ErrorStack::get())
+    } else {
+        Ok(idx)
+    }
+}
+
+pub(super) fn into_raw(offload: ArcPrivateKeyOffload) -> *mut c_void {
+    Box::into_raw(Box::new(offload)) as *mut c_void
+}
+
+/// # Safety
+///
+/// The pointer should be returned by `into_raw` and should not have been set as ex data
+pub(super) unsafe fn drop_raw(p: *mut c_void) {
+    drop(Box::from_raw(p as *mut ArcPrivateKeyOffload));
+}
+
+/// # Safety
+///
+/// The pointer should be null or be returned by `into_raw`
+pub(super) unsafe fn as_ref<'a>(p: *mut c_void) -> Option<&'a ArcPrivateKeyOffload> {
+    (p as *const ArcPrivateKeyOffload).as_ref()
+}
+
+unsafe extern "C" fn free_offload(
+    _parent: *mut c_void,
+    ptr: *mut c_void,
+    _ad: *mut ffi::CRYPTO_EX_DATA,
+    _idx: c_int,
+    _argl: c_long,
+    _argp: *mut c_void,
+) {
+    if !ptr.is_null() {
+        drop_raw(ptr);
+    }
+}
@@ -0,0 +1,71 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use openssl::pkey::{Id, PKey, Private};
+use openssl::x509::X509Ref;
+
+mod ec;
+mod ex_data;
+mod rsa;
+
+/// Private key operations that are done outside of the OpenSSL library.
+///
+/// All methods will be called synchronously in the handshake process,
+/// so the caller thread will be blocked until they return.
+pub trait PrivateKeyOffload {
+    /// Do the raw RSA private key operation, the input has already been padded
+    fn rsa_private_raw(&self, from: &[u8]) -> anyhow::Result<Vec<u8>>;
+
+    /// Do the RSA decryption with PKCS#1 v1.5 padding
+    fn rsa_decrypt_pkcs1(&self, from: &[u8]) -> anyhow::Result<Vec<u8>>;
+
+    /// Sign the digest with ECDSA, the returned signature should be DER encoded
+    fn ecdsa_sign(&self, digest: &[u8]) -> anyhow::Result<Vec<u8>>;
+}
+
+pub type ArcPrivateKeyOffload = Arc<dyn PrivateKeyOffload + Send + Sync>;
+
+/// Create a private key for the certificate, with all private key operations offloaded.
+///
+/// Only RSA and EC keys are supported.
+pub fn new_offload_private_key(
+    cert: &X509Ref,
+    offload: ArcPrivateKeyOffload,
+) -> anyhow::Result<PKey<Private>> {
+    let public_key = cert
+        .public_key()
+        .map_err(|e| anyhow!("failed to get public key from certificate: {e}"))?;
+    match public_key.id() {
+        Id::RSA => {
+            let rsa = public_key
+                .rsa()
+                .map_err(|e| anyhow!("failed to get rsa public key: {e}"))?;
+            rsa::new_offload_key(rsa, offload)
+                .map_err(|e| anyhow!("failed to create rsa offload key: {e}"))
+        }
+        Id::EC => {
+            let ec_key = public_key
+                .ec_key()
+                .map_err(|e| anyhow!("failed to get ec public key: {e}"))?;
+            ec::new_offload_key(ec_key, offload)
+                .map_err(|e| anyhow!("failed to create ec offload key: {e}"))
+        }
+        id => Err(anyhow!("unsupported public key type {id:?}")),
+    }
+}
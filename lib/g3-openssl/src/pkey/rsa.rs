@@ -0,0 +1,168 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use std::{ptr, slice};
+
+use libc::{c_int, c_uchar};
+use openssl::error::ErrorStack;
+use openssl::foreign_types::ForeignType;
+use openssl::pkey::{PKey, Private, Public};
+use openssl::rsa::Rsa;
+use openssl_sys::{RSA, RSA_NO_PADDING, RSA_PKCS1_PADDING};
+
+use super::{ex_data, ArcPrivateKeyOffload};
+use crate::ffi;
+
+fn ex_index() -> Result<c_int, ErrorStack> {
+    static INDEX: OnceLock<Result<c_int, ErrorStack>> = OnceLock::new();
+    INDEX
+        .get_or_init(|| ex_data::new_index(ffi::CRYPTO_EX_INDEX_RSA))
+        .clone()
+}
+
+fn offload_method() -> Result<*const ffi::RSA_METHOD, ErrorStack> {
+    static METHOD: OnceLock<Result<usize, ErrorStack>> = OnceLock::new();
+    METHOD
+        .get_or_init(|| new_offload_method().map(|meth| meth as usize))
+        .clone()
+        .map(|meth| meth as *const ffi::RSA_METHOD)
+}
+
+fn new_offload_method() -> Result<*mut ffi::RSA_METHOD, ErrorStack> {
+    unsafe {
+        let meth = ffi::RSA_meth_dup(ffi::RSA_PKCS1_OpenSSL());
+        if meth.is_null() {
+            return Err(ErrorStack::get());
+        }
+
+        // skip the private key check as we only have the public part
+        let flags = ffi::RSA_meth_get_flags(meth) | ffi::RSA_METHOD_FLAG_NO_CHECK;
+        if ffi::RSA_meth_set_flags(meth, flags) != 1
+            || ffi::RSA_meth_set_priv_enc(meth, Some(offload_priv_enc)) != 1
+            || ffi::RSA_meth_set_priv_dec(meth, Some(offload_priv_dec)) != 1
+        {
+            let e = ErrorStack::get();
+            ffi::RSA_meth_free(meth);
+            return Err(e);
+        }
+        Ok(meth)
+    }
+}
+
+pub(super) fn new_offload_key(
+    rsa: Rsa<Public>,
+    offload: ArcPrivateKeyOffload,
+) -> Result<PKey<Private>, ErrorStack> {
+    let idx = ex_index()?;
+    let meth = offload_method()?;
+
+    unsafe {
+        // the method should be set before assigned to EVP_PKEY,
+        // so the key will be marked as foreign and go through the legacy code path
+        if ffi::RSA_set_method(rsa.as_ptr(), meth) != 1 {
+            return Err(ErrorStack::get());
+        }
+        let data = ex_data::into_raw(offload);
+        if ffi::RSA_set_ex_data(rsa.as_ptr(), idx, data) != 1 {
+            ex_data::drop_raw(data);
+            return Err(ErrorStack::get());
+        }
+
+        let rsa = Rsa::<Private>::from_ptr(rsa.into_ptr());
+        PKey::from_rsa(rsa)
+    }
+}
+
+unsafe fn get_offload<'a>(rsa: *const RSA) -> Option<&'a ArcPrivateKeyOffload> {
+    let idx = ex_index().ok()?;
+    ex_data::as_ref(ffi::RSA_get_ex_data(rsa, idx))
+}
+
+unsafe extern "C" fn offload_priv_enc(
+    flen: c_int,
+    from: *const c_uchar,
+    to: *mut c_uchar,
+    rsa: *mut RSA,
+    padding: c_int,
+) -> c_int {
+    let Some(offload) = get_offload(rsa) else {
+        return -1;
+    };
+    let Ok(size) = usize::try_from(ffi::RSA_size(rsa)) else {
+        return -1;
+    };
+    let from = slice::from_raw_parts(from, flen as usize);
+
+    let input = match padding {
+        RSA_NO_PADDING => Cow::Borrowed(from),
+        RSA_PKCS1_PADDING => {
+            let mut buf = vec![0u8; size];
+            let r = ffi::RSA_padding_add_PKCS1_type_1(
+                buf.as_mut_ptr(),
+                size as c_int,
+                from.as_ptr(),
+                flen,
+            );
+            if r != 1 {
+                return -1;
+            }
+            Cow::Owned(buf)
+        }
+        _ => return -1,
+    };
+    if input.len() != size {
+        return -1;
+    }
+
+    match offload.rsa_private_raw(&input) {
+        Ok(data) if data.len() <= size => {
+            ptr::copy_nonoverlapping(data.as_ptr(), to, data.len());
+            data.len() as c_int
+        }
+        _ => -1,
+    }
+}
+
+unsafe extern "C" fn offload_priv_dec(
+    flen: c_int,
+    from: *const c_uchar,
+    to: *mut c_uchar,
+    rsa: *mut RSA,
+    padding: c_int,
+) -> c_int {
+    let Some(offload) = get_offload(rsa) else {
+        return -1;
+    };
+    let Ok(size) = usize::try_from(ffi::RSA_size(rsa)) else {
+        return -1;
+    };
+    let from = slice::from_raw_parts(from, flen as usize);
+
+    let r = match padding {
+        RSA_NO_PADDING => offload.rsa_private_raw(from),
+        RSA_PKCS1_PADDING => offload.rsa_decrypt_pkcs1(from),
+        _ => return -1,
+    };
+    match r {
+        Ok(data) if data.len() <= size => {
+            ptr::copy_nonoverlapping(data.as_ptr(), to, data.len());
+            data.len() as c_int
+        }
+        _ => -1,
+    }
+}
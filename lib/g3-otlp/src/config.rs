@@ -0,0 +1,280 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use http::{HeaderName, HeaderValue};
+use tokio::io::BufReader;
+use tokio_rustls::rustls::ServerName;
+use tokio_rustls::TlsConnector;
+
+use g3_types::net::{
+    AlpnProtocol, RustlsClientConfig, RustlsClientConfigBuilder, TcpKeepAliveConfig,
+};
+
+use super::OtlpConnection;
+
+const OTLP_GRPC_DEFAULT_PORT: u16 = 4317;
+const OTLP_HTTP_DEFAULT_PORT: u16 = 4318;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    HttpProtobuf,
+    Grpc,
+}
+
+impl FromStr for OtlpProtocol {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "http" | "http/protobuf" | "http_protobuf" => Ok(OtlpProtocol::HttpProtobuf),
+            "grpc" => Ok(OtlpProtocol::Grpc),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OtlpSignal {
+    Logs,
+    Metrics,
+}
+
+impl OtlpSignal {
+    fn grpc_path(&self) -> &'static str {
+        match self {
+            OtlpSignal::Logs => "/opentelemetry.proto.collector.logs.v1.LogsService/Export",
+            OtlpSignal::Metrics => {
+                "/opentelemetry.proto.collector.metrics.v1.MetricsService/Export"
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OtlpExporterConfig {
+    protocol: OtlpProtocol,
+    server_addr: Option<SocketAddr>,
+    bind_ip: Option<IpAddr>,
+    tcp_keepalive: TcpKeepAliveConfig,
+    tls_client: Option<RustlsClientConfig>,
+    tls_name: Option<ServerName>,
+    host: Option<String>,
+    logs_path: String,
+    metrics_path: String,
+    pub(crate) headers: Vec<(HeaderName, HeaderValue)>,
+    pub(crate) resource_attributes: Vec<(String, String)>,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) connect_delay: Duration,
+    pub(crate) write_timeout: Duration,
+    pub(crate) flush_interval: Duration,
+    pub(crate) max_batch_size: usize,
+    pub(crate) retry_queue_len: usize,
+}
+
+impl Default for OtlpExporterConfig {
+    fn default() -> Self {
+        OtlpExporterConfig::new(OtlpProtocol::HttpProtobuf)
+    }
+}
+
+impl OtlpExporterConfig {
+    pub fn new(protocol: OtlpProtocol) -> Self {
+        let hostname = gethostname::gethostname().to_string_lossy().to_string();
+        OtlpExporterConfig {
+            protocol,
+            server_addr: None,
+            bind_ip: None,
+            tcp_keepalive: TcpKeepAliveConfig::default_enabled(),
+            tls_client: None,
+            tls_name: None,
+            host: None,
+            logs_path: "/v1/logs".to_string(),
+            metrics_path: "/v1/metrics".to_string(),
+            headers: Vec::new(),
+            resource_attributes: vec![("host.name".to_string(), hostname)],
+            connect_timeout: Duration::from_secs(10),
+            connect_delay: Duration::from_secs(10),
+            write_timeout: Duration::from_secs(10),
+            flush_interval: Duration::from_secs(1),
+            max_batch_size: 512,
+            retry_queue_len: 10,
+        }
+    }
+
+    pub fn set_protocol(&mut self, protocol: OtlpProtocol) {
+        self.protocol = protocol;
+    }
+
+    pub fn set_server_addr(&mut self, addr: SocketAddr) {
+        self.server_addr = Some(addr);
+    }
+
+    pub fn set_bind_ip(&mut self, ip: IpAddr) {
+        self.bind_ip = Some(ip);
+    }
+
+    pub fn set_tcp_keepalive(&mut self, keepalive: TcpKeepAliveConfig) {
+        self.tcp_keepalive = keepalive;
+    }
+
+    /// Set the tls client config. This should be called after the protocol is set,
+    /// as the ALPN protocol depends on it.
+    pub fn set_tls_client(&mut self, tls_config: RustlsClientConfigBuilder) -> anyhow::Result<()> {
+        let alpn_protocol = match self.protocol {
+            OtlpProtocol::HttpProtobuf => AlpnProtocol::Http11,
+            OtlpProtocol::Grpc => AlpnProtocol::Http2,
+        };
+        let tls_client = tls_config
+            .build_with_alpn_protocols(Some(vec![alpn_protocol]))
+            .context("failed to build tls client config")?;
+        self.tls_client = Some(tls_client);
+        Ok(())
+    }
+
+    pub fn set_tls_name(&mut self, tls_name: ServerName) {
+        self.tls_name = Some(tls_name);
+    }
+
+    pub fn set_host(&mut self, host: String) {
+        self.host = Some(host);
+    }
+
+    pub fn set_logs_path(&mut self, path: String) {
+        self.logs_path = path;
+    }
+
+    pub fn set_metrics_path(&mut self, path: String) {
+        self.metrics_path = path;
+    }
+
+    pub fn add_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.headers.push((name, value));
+    }
+
+    /// Add a resource attribute, which will override the one with the same key
+    pub fn add_resource_attribute(&mut self, key: String, value: String) {
+        self.resource_attributes.retain(|(k, _)| k != &key);
+        self.resource_attributes.push((key, value));
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+    }
+
+    pub fn set_connect_delay(&mut self, delay: Duration) {
+        self.connect_delay = delay;
+    }
+
+    pub fn set_write_timeout(&mut self, timeout: Duration) {
+        self.write_timeout = timeout;
+    }
+
+    pub fn set_flush_interval(&mut self, interval: Duration) {
+        self.flush_interval = interval;
+    }
+
+    pub fn set_max_batch_size(&mut self, size: usize) {
+        self.max_batch_size = size.max(1);
+    }
+
+    pub fn set_retry_queue_len(&mut self, len: usize) {
+        self.retry_queue_len = len;
+    }
+
+    pub(crate) fn server_addr(&self) -> SocketAddr {
+        self.server_addr.unwrap_or_else(|| {
+            let port = match self.protocol {
+                OtlpProtocol::HttpProtobuf => OTLP_HTTP_DEFAULT_PORT,
+                OtlpProtocol::Grpc => OTLP_GRPC_DEFAULT_PORT,
+            };
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+        })
+    }
+
+    pub(crate) fn host(&self) -> String {
+        if let Some(host) = &self.host {
+            return host.clone();
+        }
+        if let Some(ServerName::DnsName(name)) = &self.tls_name {
+            return name.as_ref().to_string();
+        }
+        self.server_addr().to_string()
+    }
+
+    pub(crate) fn scheme(&self) -> &'static str {
+        if self.tls_client.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    pub(crate) fn path(&self, signal: OtlpSignal) -> &str {
+        match self.protocol {
+            OtlpProtocol::HttpProtobuf => match signal {
+                OtlpSignal::Logs => &self.logs_path,
+                OtlpSignal::Metrics => &self.metrics_path,
+            },
+            OtlpProtocol::Grpc => signal.grpc_path(),
+        }
+    }
+
+    pub(crate) async fn new_connection(&self) -> anyhow::Result<OtlpConnection> {
+        let server_addr = self.server_addr();
+        let socket = g3_socket::tcp::new_socket_to(
+            server_addr.ip(),
+            self.bind_ip,
+            &self.tcp_keepalive,
+            &Default::default(),
+            false,
+        )
+        .map_err(|e| anyhow!("failed to setup socket: {e:?}"))?;
+        let tcp_stream = socket
+            .connect(server_addr)
+            .await
+            .map_err(|e| anyhow!("failed to tcp connect to peer {server_addr}: {e:?}"))?;
+
+        if let Some(tls_client) = &self.tls_client {
+            let tls_name = self
+                .tls_name
+                .clone()
+                .unwrap_or_else(|| ServerName::IpAddress(server_addr.ip()));
+            let tls_connect =
+                TlsConnector::from(tls_client.driver.clone()).connect(tls_name, tcp_stream);
+
+            let tls_stream =
+                match tokio::time::timeout(tls_client.handshake_timeout, tls_connect).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => return Err(anyhow!("failed to tls connect to peer: {e}")),
+                    Err(_) => return Err(anyhow!("tls connect to peer timedout")),
+                };
+            match self.protocol {
+                OtlpProtocol::HttpProtobuf => Ok(OtlpConnection::Https(BufReader::new(tls_stream))),
+                OtlpProtocol::Grpc => OtlpConnection::new_grpc(tls_stream).await,
+            }
+        } else {
+            match self.protocol {
+                OtlpProtocol::HttpProtobuf => Ok(OtlpConnection::Http(BufReader::new(tcp_stream))),
+                OtlpProtocol::Grpc => OtlpConnection::new_grpc(tcp_stream).await,
+            }
+        }
+    }
+}
@@ -0,0 +1,237 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use bytes::{BufMut, Bytes, BytesMut};
+use h2::client::SendRequest;
+use http::{Method, Request, StatusCode};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+
+use g3_http::client::HttpTransparentResponse;
+use g3_http::HttpBodyReader;
+
+use super::{OtlpExporterConfig, OtlpSignal};
+
+const RESPONSE_MAX_HEADER_SIZE: usize = 4096;
+const RESPONSE_MAX_BODY_SIZE: u64 = 65536;
+
+pub(crate) enum ExportError {
+    /// The connection is not usable any more, or the server is busy.
+    /// The data should be retried later.
+    Retry(anyhow::Error),
+    /// The data is rejected by the server and should be dropped.
+    Rejected(anyhow::Error),
+}
+
+pub(crate) enum OtlpConnection {
+    Http(BufReader<TcpStream>),
+    Https(BufReader<TlsStream<TcpStream>>),
+    Grpc(SendRequest<Bytes>),
+}
+
+impl OtlpConnection {
+    pub(crate) async fn new_grpc<T>(stream: T) -> anyhow::Result<Self>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (send_request, connection) = h2::client::handshake(stream)
+            .await
+            .map_err(|e| anyhow!("h2 handshake failed: {e}"))?;
+        tokio::spawn(async move {
+            let _ = connection.await;
+        });
+        Ok(OtlpConnection::Grpc(send_request))
+    }
+
+    pub(crate) fn is_multiplexed(&self) -> bool {
+        matches!(self, OtlpConnection::Grpc(_))
+    }
+
+    /// Export the encoded request, return whether the connection can be reused
+    pub(crate) async fn export(
+        &mut self,
+        config: &OtlpExporterConfig,
+        signal: OtlpSignal,
+        data: &[u8],
+    ) -> Result<bool, ExportError> {
+        match self {
+            OtlpConnection::Http(stream) => http_export(stream, config, signal, data).await,
+            OtlpConnection::Https(stream) => http_export(stream, config, signal, data).await,
+            OtlpConnection::Grpc(send_request) => {
+                grpc_export(send_request, config, signal, data).await?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+async fn http_export<T>(
+    stream: &mut BufReader<T>,
+    config: &OtlpExporterConfig,
+    signal: OtlpSignal,
+    data: &[u8],
+) -> Result<bool, ExportError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut head = Vec::with_capacity(256);
+    head.extend_from_slice(b"POST ");
+    head.extend_from_slice(config.path(signal).as_bytes());
+    head.extend_from_slice(b" HTTP/1.1\r\nHost: ");
+    head.extend_from_slice(config.host().as_bytes());
+    head.extend_from_slice(b"\r\nContent-Type: application/x-protobuf\r\nContent-Length: ");
+    head.extend_from_slice(data.len().to_string().as_bytes());
+    head.extend_from_slice(b"\r\n");
+    for (name, value) in &config.headers {
+        head.extend_from_slice(name.as_str().as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value.as_bytes());
+        head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(b"\r\n");
+
+    let writer = stream.get_mut();
+    writer
+        .write_all(&head)
+        .await
+        .map_err(|e| ExportError::Retry(anyhow!("failed to write request header: {e}")))?;
+    writer
+        .write_all(data)
+        .await
+        .map_err(|e| ExportError::Retry(anyhow!("failed to write request body: {e}")))?;
+    writer
+        .flush()
+        .await
+        .map_err(|e| ExportError::Retry(anyhow!("failed to flush request: {e}")))?;
+
+    let (rsp, _) =
+        HttpTransparentResponse::parse(stream, &Method::POST, true, RESPONSE_MAX_HEADER_SIZE)
+            .await
+            .map_err(|e| ExportError::Retry(anyhow!("failed to read response: {e}")))?;
+
+    let mut keep_alive = rsp.keep_alive();
+    let mut body = Vec::new();
+    if let Some(body_type) = rsp.body_type(&Method::POST) {
+        let mut body_reader = HttpBodyReader::new(stream, body_type, 1024);
+        let r = (&mut body_reader)
+            .take(RESPONSE_MAX_BODY_SIZE)
+            .read_to_end(&mut body)
+            .await;
+        if r.is_err() || !body_reader.finished() {
+            keep_alive = false;
+        }
+    }
+
+    match rsp.code {
+        200..=299 => Ok(keep_alive),
+        429 | 502 | 503 | 504 => Err(ExportError::Retry(anyhow!(
+            "server is not available now, response code {}",
+            rsp.code
+        ))),
+        code => Err(ExportError::Rejected(anyhow!(
+            "request rejected by server, response code {code}"
+        ))),
+    }
+}
+
+async fn grpc_export(
+    send_request: &mut SendRequest<Bytes>,
+    config: &OtlpExporterConfig,
+    signal: OtlpSignal,
+    data: &[u8],
+) -> Result<(), ExportError> {
+    let mut send_request = send_request
+        .clone()
+        .ready()
+        .await
+        .map_err(|e| ExportError::Retry(anyhow!("h2 connection is not ready: {e}")))?;
+
+    let uri = format!(
+        "{}://{}{}",
+        config.scheme(),
+        config.host(),
+        config.path(signal)
+    );
+    let mut req = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(http::header::CONTENT_TYPE, "application/grpc")
+        .header(http::header::TE, "trailers")
+        .body(())
+        .map_err(|e| ExportError::Rejected(anyhow!("failed to build grpc request: {e}")))?;
+    for (name, value) in &config.headers {
+        req.headers_mut().append(name.clone(), value.clone());
+    }
+
+    let (rsp_fut, mut send_stream) = send_request
+        .send_request(req, false)
+        .map_err(|e| ExportError::Retry(anyhow!("failed to send grpc request: {e}")))?;
+
+    // uncompressed length-prefixed message
+    let mut body = BytesMut::with_capacity(data.len() + 5);
+    body.put_u8(0);
+    body.put_u32(data.len() as u32);
+    body.put_slice(data);
+    send_stream
+        .send_data(body.freeze(), true)
+        .map_err(|e| ExportError::Retry(anyhow!("failed to send grpc request body: {e}")))?;
+
+    let rsp = rsp_fut
+        .await
+        .map_err(|e| ExportError::Retry(anyhow!("failed to recv grpc response: {e}")))?;
+    if rsp.status() != StatusCode::OK {
+        return Err(ExportError::Retry(anyhow!(
+            "unexpected grpc response status {}",
+            rsp.status()
+        )));
+    }
+    if let Some(status) = rsp.headers().get("grpc-status") {
+        // trailers only response
+        return check_grpc_status(status.as_bytes());
+    }
+
+    let mut recv_stream = rsp.into_body();
+    while let Some(r) = recv_stream.data().await {
+        let data =
+            r.map_err(|e| ExportError::Retry(anyhow!("failed to recv grpc response: {e}")))?;
+        let _ = recv_stream.flow_control().release_capacity(data.len());
+    }
+    let trailers = recv_stream
+        .trailers()
+        .await
+        .map_err(|e| ExportError::Retry(anyhow!("failed to recv grpc trailers: {e}")))?;
+    match trailers.as_ref().and_then(|t| t.get("grpc-status")) {
+        Some(status) => check_grpc_status(status.as_bytes()),
+        None => Err(ExportError::Retry(anyhow!("no grpc-status in response"))),
+    }
+}
+
+fn check_grpc_status(status: &[u8]) -> Result<(), ExportError> {
+    // see https://opentelemetry.io/docs/specs/otlp/#failures for retryable codes
+    match status {
+        b"0" => Ok(()),
+        b"1" | b"4" | b"8" | b"10" | b"11" | b"14" | b"15" => Err(ExportError::Retry(anyhow!(
+            "grpc request failed with retryable status {}",
+            String::from_utf8_lossy(status)
+        ))),
+        _ => Err(ExportError::Rejected(anyhow!(
+            "grpc request rejected with status {}",
+            String::from_utf8_lossy(status)
+        ))),
+    }
+}
@@ -0,0 +1,228 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use flume::Receiver;
+use log::warn;
+
+use g3_types::log::LogStats;
+
+use super::{ExportError, OtlpConnection, OtlpExporterConfig, OtlpSignal};
+
+/// Encode a batch of items into an export service request
+pub(crate) trait OtlpRequestEncoder {
+    fn encode(&self, items: &[Vec<u8>]) -> Vec<u8>;
+}
+
+struct PendingRequest {
+    data: Vec<u8>,
+    item_count: usize,
+}
+
+pub(crate) struct AsyncIoThread<E: OtlpRequestEncoder> {
+    config: Arc<OtlpExporterConfig>,
+    signal: OtlpSignal,
+    encoder: E,
+    receiver: Receiver<Vec<u8>>,
+    stats: Arc<LogStats>,
+    batch: Vec<Vec<u8>>,
+    retry_queue: VecDeque<PendingRequest>,
+}
+
+impl<E: OtlpRequestEncoder> AsyncIoThread<E> {
+    pub(crate) fn new(
+        config: Arc<OtlpExporterConfig>,
+        signal: OtlpSignal,
+        encoder: E,
+        receiver: Receiver<Vec<u8>>,
+        stats: Arc<LogStats>,
+    ) -> Self {
+        let batch = Vec::with_capacity(config.max_batch_size);
+        let retry_queue = VecDeque::with_capacity(config.retry_queue_len);
+        AsyncIoThread {
+            config,
+            signal,
+            encoder,
+            receiver,
+            stats,
+            batch,
+            retry_queue,
+        }
+    }
+
+    pub(crate) async fn run_to_end(mut self) {
+        loop {
+            match tokio::time::timeout(self.config.connect_timeout, self.config.new_connection())
+                .await
+            {
+                Ok(Ok(connection)) => match self.run_with_connection(connection).await {
+                    Ok(true) => break,
+                    Ok(false) => {}
+                    Err(e) => warn!("lost connection to otlp server: {e:?}"),
+                },
+                Ok(Err(e)) => {
+                    warn!("failed to connect to otlp server: {e:?}");
+                    match self.run_without_connection().await {
+                        Ok(_) => break,
+                        Err(e) => warn!("{e:?}"),
+                    }
+                }
+                Err(_) => {
+                    warn!("timed out to connect to otlp server");
+                    match self.run_without_connection().await {
+                        Ok(_) => break,
+                        Err(e) => warn!("{e:?}"),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn run_without_connection(&mut self) -> anyhow::Result<()> {
+        let drop_count = Arc::new(AtomicUsize::new(0));
+        let drop_count_i = drop_count.clone();
+        match tokio::time::timeout(self.config.connect_delay, async {
+            while let Ok(data) = self.receiver.recv_async().await {
+                self.batch.push(data);
+                if self.batch.len() >= self.config.max_batch_size {
+                    let req = self.encode_batch();
+                    drop_count_i.fetch_add(self.push_to_retry(req), Ordering::Relaxed);
+                }
+            }
+        })
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(anyhow!(
+                "will retry connect again. {} records dropped during this period",
+                drop_count.load(Ordering::Relaxed)
+            )),
+        }
+    }
+
+    /// Return Ok(true) if the receiver is closed, or Ok(false) if we need a new connection
+    async fn run_with_connection(
+        &mut self,
+        mut connection: OtlpConnection,
+    ) -> anyhow::Result<bool> {
+        let mut flush_interval = tokio::time::interval(self.config.flush_interval);
+
+        while let Some(req) = self.retry_queue.pop_front() {
+            if !self.send(&mut connection, req).await? {
+                return Ok(false);
+            }
+        }
+
+        loop {
+            tokio::select! {
+                r = self.receiver.recv_async() => {
+                    match r {
+                        Ok(data) => {
+                            self.batch.push(data);
+                            if self.batch.len() >= self.config.max_batch_size {
+                                let req = self.encode_batch();
+                                if !self.send(&mut connection, req).await? {
+                                    return Ok(false);
+                                }
+                            }
+                        }
+                        Err(_) => {
+                            if !self.batch.is_empty() {
+                                let req = self.encode_batch();
+                                self.send(&mut connection, req).await?;
+                            }
+                            return Ok(true);
+                        }
+                    }
+                }
+                _ = flush_interval.tick() => {
+                    if !self.batch.is_empty() {
+                        let req = self.encode_batch();
+                        if !self.send(&mut connection, req).await? {
+                            return Ok(false);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Return whether the connection can be reused
+    async fn send(
+        &mut self,
+        connection: &mut OtlpConnection,
+        req: PendingRequest,
+    ) -> anyhow::Result<bool> {
+        match tokio::time::timeout(
+            self.config.write_timeout,
+            connection.export(&self.config, self.signal, &req.data),
+        )
+        .await
+        {
+            Ok(Ok(reuse)) => {
+                for _ in 0..req.item_count {
+                    self.stats.io.add_passed();
+                }
+                self.stats.io.add_size(req.data.len());
+                Ok(reuse)
+            }
+            Ok(Err(ExportError::Retry(e))) => {
+                self.push_to_retry(req);
+                Err(e.context("export failed"))
+            }
+            Ok(Err(ExportError::Rejected(e))) => {
+                for _ in 0..req.item_count {
+                    self.stats.drop.add_peer_unreachable();
+                }
+                warn!("otlp export dropped: {e:?}");
+                // the response body may be left unread for http/1.1
+                Ok(connection.is_multiplexed())
+            }
+            Err(_) => {
+                // drop directly on write timeout
+                for _ in 0..req.item_count {
+                    self.stats.drop.add_peer_unreachable();
+                }
+                Err(anyhow!("export timed out"))
+            }
+        }
+    }
+
+    fn encode_batch(&mut self) -> PendingRequest {
+        let data = self.encoder.encode(&self.batch);
+        let item_count = self.batch.len();
+        self.batch.clear();
+        PendingRequest { data, item_count }
+    }
+
+    /// Return the count of dropped items
+    fn push_to_retry(&mut self, req: PendingRequest) -> usize {
+        self.retry_queue.push_back(req);
+        if self.retry_queue.len() > self.config.retry_queue_len {
+            if let Some(req) = self.retry_queue.pop_front() {
+                for _ in 0..req.item_count {
+                    self.stats.drop.add_peer_unreachable();
+                }
+                return req.item_count;
+            }
+        }
+        0
+    }
+}
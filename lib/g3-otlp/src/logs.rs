@@ -0,0 +1,253 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::fmt::{Arguments, Write};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use slog::{Level, OwnedKVList, Record, Serializer, KV};
+
+use g3_types::log::{AsyncLogConfig, AsyncLogFormatter, AsyncLogger, LogStats};
+
+use super::proto::{self, AnyValue};
+use super::{AsyncIoThread, OtlpExporterConfig, OtlpRequestEncoder, OtlpSignal};
+
+thread_local! {
+    static TL_BUF: RefCell<String> = RefCell::new(String::with_capacity(128))
+}
+
+/// Create an async logger that send logs to the OTLP server.
+///
+/// The `service_name` will be set as the `service.name` resource attribute if not set in config,
+/// and the `scope_name` will be used as the name of the instrumentation scope.
+pub fn new_async_logger(
+    async_conf: &AsyncLogConfig,
+    otlp_conf: &Arc<OtlpExporterConfig>,
+    service_name: &str,
+    scope_name: String,
+) -> AsyncLogger<Vec<u8>, OtlpLogFormatter> {
+    let (sender, receiver) = flume::bounded::<Vec<u8>>(async_conf.channel_capacity);
+
+    let stats = Arc::new(LogStats::default());
+
+    for i in 0..async_conf.thread_number {
+        let encoder = LogsRequestEncoder::new(otlp_conf, service_name, &scope_name);
+        let io_thread = AsyncIoThread::new(
+            Arc::clone(otlp_conf),
+            OtlpSignal::Logs,
+            encoder,
+            receiver.clone(),
+            Arc::clone(&stats),
+        );
+
+        let _detached_thread = std::thread::Builder::new()
+            .name(format!("{}#{i}", async_conf.thread_name))
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                rt.block_on(io_thread.run_to_end());
+            });
+    }
+
+    AsyncLogger::new(sender, OtlpLogFormatter {}, stats)
+}
+
+pub(crate) fn resource_attributes(
+    config: &OtlpExporterConfig,
+    service_name: &str,
+) -> Vec<(String, String)> {
+    let mut attributes = vec![("service.name".to_string(), service_name.to_string())];
+    for (k, v) in &config.resource_attributes {
+        attributes.retain(|(ek, _)| ek != k);
+        attributes.push((k.clone(), v.clone()));
+    }
+    attributes
+}
+
+struct LogsRequestEncoder {
+    resource: Vec<u8>,
+    scope: Vec<u8>,
+}
+
+impl LogsRequestEncoder {
+    fn new(config: &OtlpExporterConfig, service_name: &str, scope_name: &str) -> Self {
+        let mut resource = Vec::with_capacity(256);
+        proto::put_resource(&mut resource, 1, &resource_attributes(config, service_name));
+        let mut scope = Vec::with_capacity(64);
+        proto::put_scope(&mut scope, 1, scope_name, "");
+        LogsRequestEncoder { resource, scope }
+    }
+}
+
+impl OtlpRequestEncoder for LogsRequestEncoder {
+    fn encode(&self, items: &[Vec<u8>]) -> Vec<u8> {
+        let size = items.iter().map(|v| v.len() + 4).sum::<usize>() + 1024;
+        let mut buf = Vec::with_capacity(size);
+        // ExportLogsServiceRequest.resource_logs
+        proto::put_message_field(&mut buf, 1, |buf| {
+            buf.extend_from_slice(&self.resource);
+            // ResourceLogs.scope_logs
+            proto::put_message_field(buf, 2, |buf| {
+                buf.extend_from_slice(&self.scope);
+                for record in items {
+                    // ScopeLogs.log_records
+                    proto::put_bytes_field(buf, 2, record);
+                }
+            });
+        });
+        buf
+    }
+}
+
+pub struct OtlpLogFormatter {}
+
+impl OtlpLogFormatter {
+    fn encode(&self, record: &Record, logger_values: &OwnedKVList) -> Result<Vec<u8>, slog::Error> {
+        let time_nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        let (severity_number, severity_text) = severity(record.level());
+
+        let mut buf = Vec::<u8>::with_capacity(1024);
+        proto::put_fixed64_field(&mut buf, 1, time_nanos);
+        proto::put_varint_field(&mut buf, 2, severity_number);
+        proto::put_bytes_field(&mut buf, 3, severity_text.as_bytes());
+        TL_BUF.with_borrow_mut(|s| {
+            s.clear();
+            let _ = s.write_fmt(*record.msg());
+            proto::put_message_field(&mut buf, 5, |buf| {
+                proto::put_bytes_field(buf, 1, s.as_bytes());
+            });
+        });
+        {
+            let mut kv_formatter = FormatterKv(&mut buf);
+            logger_values.serialize(record, &mut kv_formatter)?;
+            record.kv().serialize(record, &mut kv_formatter)?;
+        }
+        proto::put_fixed64_field(&mut buf, 11, time_nanos);
+        Ok(buf)
+    }
+}
+
+impl AsyncLogFormatter<Vec<u8>> for OtlpLogFormatter {
+    fn format_slog(
+        &self,
+        record: &Record,
+        logger_values: &OwnedKVList,
+    ) -> Result<Vec<u8>, slog::Error> {
+        self.encode(record, logger_values)
+    }
+}
+
+fn severity(level: Level) -> (u64, &'static str) {
+    match level {
+        Level::Critical => (21, "FATAL"),
+        Level::Error => (17, "ERROR"),
+        Level::Warning => (13, "WARN"),
+        Level::Info => (9, "INFO"),
+        Level::Debug => (5, "DEBUG"),
+        Level::Trace => (1, "TRACE"),
+    }
+}
+
+/// Encode all key-values as `LogRecord.attributes`
+struct FormatterKv<'a>(&'a mut Vec<u8>);
+
+impl<'a> FormatterKv<'a> {
+    fn put(&mut self, key: slog::Key, value: AnyValue<'_>) -> slog::Result {
+        proto::put_key_value(self.0, 6, key, value);
+        Ok(())
+    }
+}
+
+impl<'a> Serializer for FormatterKv<'a> {
+    fn emit_usize(&mut self, key: slog::Key, value: usize) -> slog::Result {
+        self.emit_u64(key, value as u64)
+    }
+    fn emit_isize(&mut self, key: slog::Key, value: isize) -> slog::Result {
+        self.put(key, AnyValue::Int(value as i64))
+    }
+    fn emit_u8(&mut self, key: slog::Key, value: u8) -> slog::Result {
+        self.put(key, AnyValue::Int(value as i64))
+    }
+    fn emit_i8(&mut self, key: slog::Key, value: i8) -> slog::Result {
+        self.put(key, AnyValue::Int(value as i64))
+    }
+    fn emit_u16(&mut self, key: slog::Key, value: u16) -> slog::Result {
+        self.put(key, AnyValue::Int(value as i64))
+    }
+    fn emit_i16(&mut self, key: slog::Key, value: i16) -> slog::Result {
+        self.put(key, AnyValue::Int(value as i64))
+    }
+    fn emit_u32(&mut self, key: slog::Key, value: u32) -> slog::Result {
+        self.put(key, AnyValue::Int(value as i64))
+    }
+    fn emit_i32(&mut self, key: slog::Key, value: i32) -> slog::Result {
+        self.put(key, AnyValue::Int(value as i64))
+    }
+    fn emit_u64(&mut self, key: slog::Key, value: u64) -> slog::Result {
+        match i64::try_from(value) {
+            Ok(v) => self.put(key, AnyValue::Int(v)),
+            Err(_) => self.emit_arguments(key, &format_args!("{value}")),
+        }
+    }
+    fn emit_i64(&mut self, key: slog::Key, value: i64) -> slog::Result {
+        self.put(key, AnyValue::Int(value))
+    }
+    fn emit_f32(&mut self, key: slog::Key, value: f32) -> slog::Result {
+        self.put(key, AnyValue::Double(value as f64))
+    }
+    fn emit_f64(&mut self, key: slog::Key, value: f64) -> slog::Result {
+        self.put(key, AnyValue::Double(value))
+    }
+    fn emit_bool(&mut self, key: slog::Key, value: bool) -> slog::Result {
+        self.put(key, AnyValue::Bool(value))
+    }
+    fn emit_char(&mut self, key: slog::Key, value: char) -> slog::Result {
+        self.emit_str(key, value.encode_utf8(&mut [0u8; 4]))
+    }
+    fn emit_none(&mut self, _key: slog::Key) -> slog::Result {
+        Ok(())
+    }
+    fn emit_str(&mut self, key: slog::Key, value: &str) -> slog::Result {
+        self.put(key, AnyValue::String(value))
+    }
+
+    fn emit_arguments(&mut self, key: slog::Key, value: &Arguments) -> slog::Result {
+        if let Some(s) = value.as_str() {
+            self.emit_str(key, s)
+        } else {
+            TL_BUF.with_borrow_mut(|buf| {
+                buf.clear();
+
+                buf.write_fmt(*value).unwrap();
+
+                self.emit_str(key, buf.as_str())
+            })
+        }
+    }
+
+    fn emit_serde(&mut self, key: slog::Key, value: &dyn slog::SerdeValue) -> slog::Result {
+        let s = serde_json::to_string(value.as_serde()).map_err(|e| {
+            std::io::Error::other(format!("serde serialization error for key {key}: {e}"))
+        })?;
+        self.emit_str(key, &s)
+    }
+}
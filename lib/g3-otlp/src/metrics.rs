@@ -0,0 +1,267 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use flume::{Sender, TrySendError};
+
+use g3_types::log::LogStats;
+
+use super::proto::{self, AnyValue};
+use super::{AsyncIoThread, OtlpExporterConfig, OtlpRequestEncoder, OtlpSignal};
+
+/// Create a metrics exporter which will send the metrics in a new thread.
+///
+/// The `service_name` will be set as the `service.name` resource attribute if not set in config.
+pub fn new_metrics_exporter(
+    config: &Arc<OtlpExporterConfig>,
+    service_name: &str,
+    thread_name: &str,
+) -> OtlpMetricsExporter {
+    let (sender, receiver) = flume::bounded::<Vec<u8>>(16);
+
+    let stats = Arc::new(LogStats::default());
+
+    let io_thread = AsyncIoThread::new(
+        Arc::clone(config),
+        OtlpSignal::Metrics,
+        MetricsRequestEncoder {},
+        receiver,
+        Arc::clone(&stats),
+    );
+    let _detached_thread = std::thread::Builder::new()
+        .name(thread_name.to_string())
+        .spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(io_thread.run_to_end());
+        });
+
+    let mut resource = Vec::with_capacity(256);
+    proto::put_resource(
+        &mut resource,
+        1,
+        &crate::logs::resource_attributes(config, service_name),
+    );
+    let mut scope = Vec::with_capacity(64);
+    proto::put_scope(&mut scope, 1, service_name, "");
+
+    OtlpMetricsExporter {
+        sender,
+        resource,
+        scope,
+        stats,
+    }
+}
+
+/// The encoded items are already complete export service requests,
+/// and the concatenation of them is also a valid request
+struct MetricsRequestEncoder {}
+
+impl OtlpRequestEncoder for MetricsRequestEncoder {
+    fn encode(&self, items: &[Vec<u8>]) -> Vec<u8> {
+        items.concat()
+    }
+}
+
+pub struct OtlpMetricsExporter {
+    sender: Sender<Vec<u8>>,
+    resource: Vec<u8>,
+    scope: Vec<u8>,
+    stats: Arc<LogStats>,
+}
+
+impl OtlpMetricsExporter {
+    pub fn get_stats(&self) -> Arc<LogStats> {
+        Arc::clone(&self.stats)
+    }
+
+    pub fn new_request(&self) -> OtlpMetricsRequest {
+        OtlpMetricsRequest::default()
+    }
+
+    /// Queue the request to be sent, it will be dropped if the queue is full
+    pub fn export(&self, mut req: OtlpMetricsRequest) {
+        req.finish_metric();
+        if req.metrics.is_empty() {
+            return;
+        }
+
+        let mut buf = Vec::with_capacity(req.metrics.len() + 1024);
+        // ExportMetricsServiceRequest.resource_metrics
+        proto::put_message_field(&mut buf, 1, |buf| {
+            buf.extend_from_slice(&self.resource);
+            // ResourceMetrics.scope_metrics
+            proto::put_message_field(buf, 2, |buf| {
+                buf.extend_from_slice(&self.scope);
+                buf.extend_from_slice(&req.metrics);
+            });
+        });
+
+        self.stats.io.add_total();
+        match self.sender.try_send(buf) {
+            Ok(_) => {}
+            Err(TrySendError::Full(_)) => self.stats.drop.add_channel_overflow(),
+            Err(TrySendError::Disconnected(_)) => self.stats.drop.add_channel_closed(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MetricType {
+    Sum,
+    Gauge,
+}
+
+/// Builder for all metrics in a single export request.
+///
+/// Data points with the same name and type added in sequence will be put in the same metric.
+#[derive(Default)]
+pub struct OtlpMetricsRequest {
+    metrics: Vec<u8>,
+    current: Option<(String, MetricType)>,
+    data_points: Vec<u8>,
+}
+
+impl OtlpMetricsRequest {
+    /// Add a monotonic cumulative sum data point
+    pub fn add_sum(
+        &mut self,
+        name: &str,
+        attributes: &[(String, String)],
+        start_time: SystemTime,
+        time: SystemTime,
+        value: f64,
+    ) {
+        self.switch_metric(name, MetricType::Sum);
+        self.put_data_point(attributes, Some(start_time), time, value);
+    }
+
+    /// Add a gauge data point
+    pub fn add_gauge(
+        &mut self,
+        name: &str,
+        attributes: &[(String, String)],
+        time: SystemTime,
+        value: f64,
+    ) {
+        self.switch_metric(name, MetricType::Gauge);
+        self.put_data_point(attributes, None, time, value);
+    }
+
+    fn switch_metric(&mut self, name: &str, metric_type: MetricType) {
+        if let Some((cur_name, cur_type)) = &self.current {
+            if cur_name == name && *cur_type == metric_type {
+                return;
+            }
+        }
+        self.finish_metric();
+        self.current = Some((name.to_string(), metric_type));
+    }
+
+    fn put_data_point(
+        &mut self,
+        attributes: &[(String, String)],
+        start_time: Option<SystemTime>,
+        time: SystemTime,
+        value: f64,
+    ) {
+        // NumberDataPoint
+        proto::put_message_field(&mut self.data_points, 1, |buf| {
+            if let Some(start_time) = start_time {
+                proto::put_fixed64_field(buf, 2, unix_nanos(start_time));
+            }
+            proto::put_fixed64_field(buf, 3, unix_nanos(time));
+            proto::put_double_field(buf, 4, value);
+            for (k, v) in attributes {
+                proto::put_key_value(buf, 7, k, AnyValue::String(v));
+            }
+        });
+    }
+
+    fn finish_metric(&mut self) {
+        let Some((name, metric_type)) = self.current.take() else {
+            return;
+        };
+        // ScopeMetrics.metrics
+        proto::put_message_field(&mut self.metrics, 2, |buf| {
+            proto::put_bytes_field(buf, 1, name.as_bytes());
+            match metric_type {
+                MetricType::Sum => {
+                    proto::put_message_field(buf, 7, |buf| {
+                        buf.extend_from_slice(&self.data_points);
+                        // AGGREGATION_TEMPORALITY_CUMULATIVE
+                        proto::put_varint_field(buf, 2, 2);
+                        proto::put_varint_field(buf, 3, 1);
+                    });
+                }
+                MetricType::Gauge => {
+                    proto::put_message_field(buf, 5, |buf| {
+                        buf.extend_from_slice(&self.data_points);
+                    });
+                }
+            }
+        });
+        self.data_points.clear();
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn group_data_points() {
+        let t = SystemTime::UNIX_EPOCH + Duration::from_nanos(1);
+        let mut req = OtlpMetricsRequest::default();
+        req.add_gauge("g", &[], t, 1.0);
+        req.add_gauge("g", &[("a".to_string(), "b".to_string())], t, 2.0);
+        req.add_sum("s", &[], t, t, 3.0);
+        req.finish_metric();
+
+        let point = |v: f64, attr: bool| {
+            let mut p = vec![0x19, 1, 0, 0, 0, 0, 0, 0, 0, 0x21];
+            p.extend_from_slice(&v.to_le_bytes());
+            if attr {
+                p.extend_from_slice(&[0x3a, 0x08, 0x0a, 0x01, b'a', 0x12, 0x03, 0x0a, 0x01, b'b']);
+            }
+            p
+        };
+        let p1 = point(1.0, false);
+        let p2 = point(2.0, true);
+
+        let mut expected = vec![0x12, 55, 0x0a, 0x01, b'g', 0x2a, 50];
+        expected.extend_from_slice(&[0x0a, p1.len() as u8]);
+        expected.extend_from_slice(&p1);
+        expected.extend_from_slice(&[0x0a, p2.len() as u8]);
+        expected.extend_from_slice(&p2);
+        assert_eq!(&req.metrics[..expected.len()], expected.as_slice());
+
+        let sum = &req.metrics[expected.len()..];
+        assert_eq!(&sum[2..5], &[0x0a, 0x01, b's']);
+        assert_eq!(&sum[sum.len() - 4..], &[0x10, 0x02, 0x18, 0x01]);
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Minimal protobuf encoder for the OTLP messages we need.
+//!
+//! See <https://github.com/open-telemetry/opentelemetry-proto> for the message definitions.
+
+const WIRE_TYPE_VARINT: u8 = 0;
+const WIRE_TYPE_I64: u8 = 1;
+const WIRE_TYPE_LEN: u8 = 2;
+
+fn put_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn put_tag(buf: &mut Vec<u8>, field: u32, wire_type: u8) {
+    put_varint(buf, ((field as u64) << 3) | wire_type as u64);
+}
+
+pub(crate) fn put_varint_field(buf: &mut Vec<u8>, field: u32, v: u64) {
+    put_tag(buf, field, WIRE_TYPE_VARINT);
+    put_varint(buf, v);
+}
+
+pub(crate) fn put_fixed64_field(buf: &mut Vec<u8>, field: u32, v: u64) {
+    put_tag(buf, field, WIRE_TYPE_I64);
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+pub(crate) fn put_double_field(buf: &mut Vec<u8>, field: u32, v: f64) {
+    put_tag(buf, field, WIRE_TYPE_I64);
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+pub(crate) fn put_bytes_field(buf: &mut Vec<u8>, field: u32, v: &[u8]) {
+    put_tag(buf, field, WIRE_TYPE_LEN);
+    put_varint(buf, v.len() as u64);
+    buf.extend_from_slice(v);
+}
+
+/// Encode a nested message, the content of which is written by `f`
+pub(crate) fn put_message_field<F>(buf: &mut Vec<u8>, field: u32, f: F)
+where
+    F: FnOnce(&mut Vec<u8>),
+{
+    let mut msg = Vec::with_capacity(64);
+    f(&mut msg);
+    put_bytes_field(buf, field, &msg);
+}
+
+/// Values for `opentelemetry.proto.common.v1.AnyValue`
+pub(crate) enum AnyValue<'a> {
+    String(&'a str),
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+}
+
+impl<'a> AnyValue<'a> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            AnyValue::String(s) => put_bytes_field(buf, 1, s.as_bytes()),
+            AnyValue::Bool(b) => put_varint_field(buf, 2, *b as u64),
+            AnyValue::Int(i) => put_varint_field(buf, 3, *i as u64),
+            AnyValue::Double(f) => put_double_field(buf, 4, *f),
+        }
+    }
+}
+
+/// Encode a `opentelemetry.proto.common.v1.KeyValue` message
+pub(crate) fn put_key_value(buf: &mut Vec<u8>, field: u32, key: &str, value: AnyValue<'_>) {
+    put_message_field(buf, field, |buf| {
+        put_bytes_field(buf, 1, key.as_bytes());
+        put_message_field(buf, 2, |buf| value.encode(buf));
+    });
+}
+
+/// Encode a `opentelemetry.proto.resource.v1.Resource` message
+pub(crate) fn put_resource(buf: &mut Vec<u8>, field: u32, attributes: &[(String, String)]) {
+    put_message_field(buf, field, |buf| {
+        for (k, v) in attributes {
+            put_key_value(buf, 1, k, AnyValue::String(v));
+        }
+    });
+}
+
+/// Encode a `opentelemetry.proto.common.v1.InstrumentationScope` message
+pub(crate) fn put_scope(buf: &mut Vec<u8>, field: u32, name: &str, version: &str) {
+    put_message_field(buf, field, |buf| {
+        put_bytes_field(buf, 1, name.as_bytes());
+        if !version.is_empty() {
+            put_bytes_field(buf, 2, version.as_bytes());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint() {
+        let mut buf = Vec::new();
+        put_varint(&mut buf, 1);
+        put_varint(&mut buf, 300);
+        put_varint(&mut buf, u64::MAX);
+        assert_eq!(
+            buf,
+            [0x01, 0xac, 0x02, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+    }
+
+    #[test]
+    fn key_value() {
+        let mut buf = Vec::new();
+        put_key_value(&mut buf, 6, "a", AnyValue::String("bc"));
+        assert_eq!(
+            buf,
+            [0x32, 0x09, 0x0a, 0x01, b'a', 0x12, 0x04, 0x0a, 0x02, b'b', b'c']
+        );
+
+        let mut buf = Vec::new();
+        put_key_value(&mut buf, 1, "n", AnyValue::Int(-1));
+        assert_eq!(
+            buf,
+            [
+                0x0a, 0x10, 0x0a, 0x01, b'n', 0x12, 0x0b, 0x18, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0x01
+            ]
+        );
+    }
+
+    #[test]
+    fn double_and_fixed() {
+        let mut buf = Vec::new();
+        put_double_field(&mut buf, 4, 1.0);
+        put_fixed64_field(&mut buf, 3, 2);
+        assert_eq!(
+            buf,
+            [0x21, 0, 0, 0, 0, 0, 0, 0xf0, 0x3f, 0x19, 2, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+}
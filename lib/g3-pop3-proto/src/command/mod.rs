@@ -0,0 +1,198 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::{self, FromStr};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CommandLineError {
+    #[error("no trailing sequence")]
+    NoTrailingSequence,
+    #[error("no command")]
+    NoCommand,
+    #[error("invalid utf-8 command")]
+    InvalidUtf8Command,
+    #[error("invalid argument")]
+    InvalidArgument,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Capability,
+    StartTls,
+    Quit,
+    User(String),
+    Pass,
+    Apop(String),
+    /// the mechanism will be `None` if no argument is given
+    Auth(Option<String>),
+    /// the message number will be `None` if no argument is given
+    List(Option<u32>),
+    /// the message number will be `None` if no argument is given
+    Uidl(Option<u32>),
+    Retrieve(u32),
+    Top(u32),
+    /// other commands, with the upper case command name set
+    Other(String),
+}
+
+impl Command {
+    /// the max command line size, see RFC 2449 Section 4
+    pub const MAX_LINE_SIZE: usize = 255;
+    /// the max size of the client response line to SASL challenge
+    pub const MAX_CONTINUE_LINE_SIZE: usize = 12288;
+
+    pub fn parse_line(line: &[u8]) -> Result<Self, CommandLineError> {
+        let line = line
+            .strip_suffix(b"\r\n")
+            .ok_or(CommandLineError::NoTrailingSequence)?;
+
+        let (cmd, args) = split_token(line);
+        if cmd.is_empty() {
+            return Err(CommandLineError::NoCommand);
+        }
+        let cmd = str::from_utf8(cmd).map_err(|_| CommandLineError::InvalidUtf8Command)?;
+        let upper_cmd = cmd.to_uppercase();
+
+        match upper_cmd.as_str() {
+            "CAPA" => Ok(Command::Capability),
+            "STLS" => Ok(Command::StartTls),
+            "QUIT" => Ok(Command::Quit),
+            "USER" => {
+                let user = str::from_utf8(args).map_err(|_| CommandLineError::InvalidArgument)?;
+                if user.is_empty() {
+                    return Err(CommandLineError::InvalidArgument);
+                }
+                Ok(Command::User(user.to_string()))
+            }
+            "PASS" => Ok(Command::Pass),
+            "APOP" => {
+                let (user, _) = split_token(args);
+                let user = str::from_utf8(user).map_err(|_| CommandLineError::InvalidArgument)?;
+                if user.is_empty() {
+                    return Err(CommandLineError::InvalidArgument);
+                }
+                Ok(Command::Apop(user.to_string()))
+            }
+            "AUTH" => {
+                let (mechanism, _) = split_token(args);
+                if mechanism.is_empty() {
+                    return Ok(Command::Auth(None));
+                }
+                let mechanism =
+                    str::from_utf8(mechanism).map_err(|_| CommandLineError::InvalidArgument)?;
+                Ok(Command::Auth(Some(mechanism.to_uppercase())))
+            }
+            "LIST" => Ok(Command::List(parse_optional_msg(args)?)),
+            "UIDL" => Ok(Command::Uidl(parse_optional_msg(args)?)),
+            "RETR" => Ok(Command::Retrieve(parse_msg(args)?)),
+            "TOP" => Ok(Command::Top(parse_msg(args)?)),
+            _ => Ok(Command::Other(upper_cmd)),
+        }
+    }
+
+    /// Check if a multi-line response will follow the positive status line
+    pub fn has_multi_line_response(&self) -> bool {
+        matches!(
+            self,
+            Command::Capability
+                | Command::Auth(None)
+                | Command::List(None)
+                | Command::Uidl(None)
+                | Command::Retrieve(_)
+                | Command::Top(_)
+        )
+    }
+}
+
+fn split_token(buf: &[u8]) -> (&[u8], &[u8]) {
+    match memchr::memchr(b' ', buf) {
+        Some(p) => (&buf[..p], &buf[p + 1..]),
+        None => (buf, &[]),
+    }
+}
+
+fn parse_msg(args: &[u8]) -> Result<u32, CommandLineError> {
+    parse_optional_msg(args)?.ok_or(CommandLineError::InvalidArgument)
+}
+
+fn parse_optional_msg(args: &[u8]) -> Result<Option<u32>, CommandLineError> {
+    let (msg, _) = split_token(args);
+    if msg.is_empty() {
+        return Ok(None);
+    }
+    let msg = str::from_utf8(msg).map_err(|_| CommandLineError::InvalidArgument)?;
+    let msg = u32::from_str(msg).map_err(|_| CommandLineError::InvalidArgument)?;
+    Ok(Some(msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple() {
+        let cmd = Command::parse_line(b"stls\r\n").unwrap();
+        assert_eq!(cmd, Command::StartTls);
+
+        let cmd = Command::parse_line(b"CAPA\r\n").unwrap();
+        assert_eq!(cmd, Command::Capability);
+
+        let cmd = Command::parse_line(b"NOOP\r\n").unwrap();
+        assert_eq!(cmd, Command::Other("NOOP".to_string()));
+    }
+
+    #[test]
+    fn user() {
+        let cmd = Command::parse_line(b"USER mrose\r\n").unwrap();
+        assert_eq!(cmd, Command::User("mrose".to_string()));
+
+        let cmd = Command::parse_line(b"APOP mrose c4c9334bac560ecc979e58001b3e22fb\r\n").unwrap();
+        assert_eq!(cmd, Command::Apop("mrose".to_string()));
+
+        let cmd = Command::parse_line(b"AUTH PLAIN dGVzdAB0ZXN0AHRlc3Q=\r\n").unwrap();
+        assert_eq!(cmd, Command::Auth(Some("PLAIN".to_string())));
+        assert!(!cmd.has_multi_line_response());
+    }
+
+    #[test]
+    fn message() {
+        let cmd = Command::parse_line(b"RETR 1\r\n").unwrap();
+        assert_eq!(cmd, Command::Retrieve(1));
+        assert!(cmd.has_multi_line_response());
+
+        let cmd = Command::parse_line(b"TOP 10 0\r\n").unwrap();
+        assert_eq!(cmd, Command::Top(10));
+
+        let cmd = Command::parse_line(b"LIST\r\n").unwrap();
+        assert_eq!(cmd, Command::List(None));
+        assert!(cmd.has_multi_line_response());
+
+        let cmd = Command::parse_line(b"UIDL 2\r\n").unwrap();
+        assert_eq!(cmd, Command::Uidl(Some(2)));
+        assert!(!cmd.has_multi_line_response());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Command::parse_line(b"QUIT").is_err());
+        assert!(Command::parse_line(b" QUIT\r\n").is_err());
+        assert!(Command::parse_line(b"RETR\r\n").is_err());
+        assert!(Command::parse_line(b"RETR a\r\n").is_err());
+        assert!(Command::parse_line(b"USER\r\n").is_err());
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ResponseLineError {
+    #[error("no trailing sequence")]
+    NoTrailingSequence,
+    #[error("invalid status indicator")]
+    InvalidStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// positive status indicator "+OK"
+    Ok,
+    /// negative status indicator "-ERR"
+    Err,
+    /// SASL continuation "+ ", see RFC 5034
+    Continuation,
+}
+
+impl Response {
+    /// the max response line size, see RFC 2449 Section 4
+    pub const MAX_LINE_SIZE: usize = 512;
+
+    pub fn parse_line(line: &[u8]) -> Result<Self, ResponseLineError> {
+        let line = line
+            .strip_suffix(b"\r\n")
+            .ok_or(ResponseLineError::NoTrailingSequence)?;
+
+        let indicator = match memchr::memchr(b' ', line) {
+            Some(p) => &line[..p],
+            None => line,
+        };
+        match indicator {
+            b"+OK" => Ok(Response::Ok),
+            b"-ERR" => Ok(Response::Err),
+            b"+" => Ok(Response::Continuation),
+            _ => Err(ResponseLineError::InvalidStatus),
+        }
+    }
+
+    /// Check if this is the termination line of a multi-line response
+    pub fn is_multi_line_end(line: &[u8]) -> bool {
+        line == b".\r\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status() {
+        let rsp =
+            Response::parse_line(b"+OK POP3 server ready <1896.697170952@dbc.mtview.ca.us>\r\n")
+                .unwrap();
+        assert_eq!(rsp, Response::Ok);
+
+        let rsp = Response::parse_line(b"-ERR\r\n").unwrap();
+        assert_eq!(rsp, Response::Err);
+
+        let rsp = Response::parse_line(b"+ \r\n").unwrap();
+        assert_eq!(rsp, Response::Continuation);
+
+        assert!(Response::parse_line(b"OK\r\n").is_err());
+    }
+
+    #[test]
+    fn multi_line_end() {
+        assert!(Response::is_multi_line_end(b".\r\n"));
+        assert!(!Response::is_multi_line_end(b"..\r\n"));
+    }
+}
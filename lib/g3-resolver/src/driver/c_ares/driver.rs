@@ -14,18 +14,23 @@
  * limitations under the License.
  */
 
+use std::future::Future;
 use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use c_ares::{AAAAResults, AResults};
-use c_ares_resolver::{CAresFuture, FutureResolver};
+use c_ares_resolver::FutureResolver;
 use tokio::sync::mpsc;
 use tokio::time::Instant;
 
+use super::wire;
 use crate::config::ResolverRuntimeConfig;
 use crate::message::ResolveDriverResponse;
-use crate::{ResolveDriver, ResolveError, ResolvedRecord};
+use crate::{
+    ResolveDriver, ResolveDriverError, ResolveError, ResolvedRecord, SrvRecord, SvcbRecord,
+    TxtRecord,
+};
 
 pub(super) struct CAresResolver {
     pub(super) inner: FutureResolver,
@@ -53,12 +58,12 @@ impl CAresResolver {
     }
 }
 
-trait ResultConverter {
-    fn finalize(self) -> (u32, Vec<IpAddr>);
+trait ResultConverter<R> {
+    fn finalize(self) -> Result<(u32, Vec<R>), ResolveDriverError>;
 }
 
-impl ResultConverter for AResults {
-    fn finalize(self) -> (u32, Vec<IpAddr>) {
+impl ResultConverter<IpAddr> for AResults {
+    fn finalize(self) -> Result<(u32, Vec<IpAddr>), ResolveDriverError> {
         let mut ttl: i32 = 0; // see rfc2181
         let mut addrs = Vec::<IpAddr>::new();
         for result in self.iter() {
@@ -67,12 +72,12 @@ impl ResultConverter for AResults {
         }
         let ttl = u32::try_from(ttl).unwrap_or_default();
 
-        (ttl, addrs)
+        Ok((ttl, addrs))
     }
 }
 
-impl ResultConverter for AAAAResults {
-    fn finalize(self) -> (u32, Vec<IpAddr>) {
+impl ResultConverter<IpAddr> for AAAAResults {
+    fn finalize(self) -> Result<(u32, Vec<IpAddr>), ResolveDriverError> {
         let mut ttl: i32 = 0; // see rfc2181
         let mut addrs = Vec::<IpAddr>::new();
         for result in self.iter() {
@@ -81,31 +86,70 @@ impl ResultConverter for AAAAResults {
         }
         let ttl = u32::try_from(ttl).unwrap_or_default();
 
-        (ttl, addrs)
+        Ok((ttl, addrs))
     }
 }
 
-async fn resolve<T>(
-    query_future: CAresFuture<T>,
-    domain: Arc<str>,
-    config: JobConfig,
-) -> ResolvedRecord
+struct SrvAnswer(Vec<u8>);
+
+impl ResultConverter<SrvRecord> for SrvAnswer {
+    fn finalize(self) -> Result<(u32, Vec<SrvRecord>), ResolveDriverError> {
+        wire::parse_srv(&self.0)
+    }
+}
+
+struct HttpsAnswer(Vec<u8>);
+
+impl ResultConverter<SvcbRecord> for HttpsAnswer {
+    fn finalize(self) -> Result<(u32, Vec<SvcbRecord>), ResolveDriverError> {
+        wire::parse_https(&self.0)
+    }
+}
+
+struct SvcbAnswer(Vec<u8>);
+
+impl ResultConverter<SvcbRecord> for SvcbAnswer {
+    fn finalize(self) -> Result<(u32, Vec<SvcbRecord>), ResolveDriverError> {
+        wire::parse_svcb(&self.0)
+    }
+}
+
+struct TxtAnswer(Vec<u8>);
+
+impl ResultConverter<TxtRecord> for TxtAnswer {
+    fn finalize(self) -> Result<(u32, Vec<TxtRecord>), ResolveDriverError> {
+        wire::parse_txt(&self.0)
+    }
+}
+
+async fn resolve<T, R, F>(query_future: F, domain: Arc<str>, config: JobConfig) -> ResolvedRecord<R>
 where
-    T: ResultConverter,
+    T: ResultConverter<R>,
+    F: Future<Output = c_ares::Result<T>>,
 {
     let created = Instant::now();
     match query_future.await {
-        Ok(results) => {
-            let (ttl, addrs) = results.finalize();
-            let ttl = ttl.clamp(config.positive_min_ttl, config.positive_max_ttl);
-            let expire = created.checked_add(Duration::from_secs(ttl as u64));
-            ResolvedRecord {
-                domain,
-                created,
-                expire,
-                result: Ok(addrs),
+        Ok(results) => match results.finalize() {
+            Ok((ttl, data)) => {
+                let ttl = ttl.clamp(config.positive_min_ttl, config.positive_max_ttl);
+                let expire = created.checked_add(Duration::from_secs(ttl as u64));
+                ResolvedRecord {
+                    domain,
+                    created,
+                    expire,
+                    result: Ok(data),
+                }
             }
-        }
+            Err(e) => {
+                let expire = created.checked_add(Duration::from_secs(config.negative_ttl as u64));
+                ResolvedRecord {
+                    domain,
+                    created,
+                    expire,
+                    result: Err(e.into()),
+                }
+            }
+        },
         Err(e) => {
             let expire = created.checked_add(Duration::from_secs(config.negative_ttl as u64));
             if let Some(e) = ResolveError::from_cares_error(e) {
@@ -127,13 +171,14 @@ where
     }
 }
 
-async fn resolve_protective<T>(
-    query_future: CAresFuture<T>,
+async fn resolve_protective<T, R, F>(
+    query_future: F,
     domain: Arc<str>,
     config: JobConfig,
-) -> ResolvedRecord
+) -> ResolvedRecord<R>
 where
-    T: ResultConverter,
+    T: ResultConverter<R>,
+    F: Future<Output = c_ares::Result<T>>,
 {
     tokio::time::timeout(
         config.timeout,
@@ -173,4 +218,76 @@ impl ResolveDriver for CAresResolver {
             let _ = sender.send(ResolveDriverResponse::V6(record)); // TODO log error
         });
     }
+
+    fn query_srv(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let job_config = self.build_job_config(config);
+        let query = self
+            .inner
+            .query(&domain, wire::DNS_CLASS_IN, wire::RR_TYPE_SRV);
+        tokio::spawn(async move {
+            let query = async move { query.await.map(SrvAnswer) };
+            let record = resolve_protective(query, domain, job_config).await;
+
+            let _ = sender.send(ResolveDriverResponse::Srv(record)); // TODO log error
+        });
+    }
+
+    fn query_https(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let job_config = self.build_job_config(config);
+        let query = self
+            .inner
+            .query(&domain, wire::DNS_CLASS_IN, wire::RR_TYPE_HTTPS);
+        tokio::spawn(async move {
+            let query = async move { query.await.map(HttpsAnswer) };
+            let record = resolve_protective(query, domain, job_config).await;
+
+            let _ = sender.send(ResolveDriverResponse::Https(record)); // TODO log error
+        });
+    }
+
+    fn query_svcb(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let job_config = self.build_job_config(config);
+        let query = self
+            .inner
+            .query(&domain, wire::DNS_CLASS_IN, wire::RR_TYPE_SVCB);
+        tokio::spawn(async move {
+            let query = async move { query.await.map(SvcbAnswer) };
+            let record = resolve_protective(query, domain, job_config).await;
+
+            let _ = sender.send(ResolveDriverResponse::Svcb(record)); // TODO log error
+        });
+    }
+
+    fn query_txt(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let job_config = self.build_job_config(config);
+        let query = self
+            .inner
+            .query(&domain, wire::DNS_CLASS_IN, wire::RR_TYPE_TXT);
+        tokio::spawn(async move {
+            let query = async move { query.await.map(TxtAnswer) };
+            let record = resolve_protective(query, domain, job_config).await;
+
+            let _ = sender.send(ResolveDriverResponse::Txt(record)); // TODO log error
+        });
+    }
 }
@@ -21,3 +21,4 @@ mod config;
 pub use config::CAresDriverConfig;
 
 mod error;
+mod wire;
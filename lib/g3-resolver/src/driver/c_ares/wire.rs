@@ -0,0 +1,313 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Minimal parser for the raw DNS responses returned by c-ares,
+//! as the c-ares bindings has no typed api for some of the record types
+
+use crate::{ResolveDriverError, SrvRecord, SvcbRecord, TxtRecord};
+
+pub(super) const DNS_CLASS_IN: u16 = 1;
+pub(super) const RR_TYPE_TXT: u16 = 16;
+pub(super) const RR_TYPE_SRV: u16 = 33;
+pub(super) const RR_TYPE_SVCB: u16 = 64;
+pub(super) const RR_TYPE_HTTPS: u16 = 65;
+
+const MAX_NAME_POINTERS: usize = 16;
+
+struct WireReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> WireReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        WireReader { buf, pos: 0 }
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], ResolveDriverError> {
+        let end = self.pos + len;
+        if end > self.buf.len() {
+            return Err(ResolveDriverError::BadResp);
+        }
+        let s = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(s)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ResolveDriverError> {
+        let s = self.read_slice(1)?;
+        Ok(s[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, ResolveDriverError> {
+        let s = self.read_slice(2)?;
+        Ok(u16::from_be_bytes([s[0], s[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ResolveDriverError> {
+        let s = self.read_slice(4)?;
+        Ok(u32::from_be_bytes([s[0], s[1], s[2], s[3]]))
+    }
+
+    /// Read a domain name, the compression pointers will be followed
+    fn read_name(&mut self) -> Result<String, ResolveDriverError> {
+        let mut name = String::new();
+        let mut pos = self.pos;
+        let mut jumped = false;
+        let mut pointers = 0;
+        loop {
+            let len = *self.buf.get(pos).ok_or(ResolveDriverError::BadResp)? as usize;
+            match len & 0xC0 {
+                0x00 => {
+                    pos += 1;
+                    if len == 0 {
+                        break;
+                    }
+                    let label = self
+                        .buf
+                        .get(pos..pos + len)
+                        .ok_or(ResolveDriverError::BadResp)?;
+                    if !name.is_empty() {
+                        name.push('.');
+                    }
+                    for c in label {
+                        name.push(char::from(*c));
+                    }
+                    pos += len;
+                }
+                0xC0 => {
+                    let low = *self.buf.get(pos + 1).ok_or(ResolveDriverError::BadResp)? as usize;
+                    if !jumped {
+                        self.pos = pos + 2;
+                        jumped = true;
+                    }
+                    pointers += 1;
+                    if pointers > MAX_NAME_POINTERS {
+                        return Err(ResolveDriverError::BadResp);
+                    }
+                    pos = ((len & 0x3F) << 8) | low;
+                }
+                _ => return Err(ResolveDriverError::BadResp),
+            }
+        }
+        if !jumped {
+            self.pos = pos;
+        }
+        Ok(name)
+    }
+}
+
+/// Parse all answers with the specified record type, return the min ttl and the parsed records
+fn parse_answers<T, F>(
+    buf: &[u8],
+    rr_type: u16,
+    parse_rdata: F,
+) -> Result<(u32, Vec<T>), ResolveDriverError>
+where
+    F: Fn(&mut WireReader<'_>, usize) -> Result<T, ResolveDriverError>,
+{
+    let mut r = WireReader::new(buf);
+    let _id = r.read_u16()?;
+    let _flags = r.read_u16()?;
+    let qd_count = r.read_u16()?;
+    let an_count = r.read_u16()?;
+    let _ns_count = r.read_u16()?;
+    let _ar_count = r.read_u16()?;
+
+    for _ in 0..qd_count {
+        r.read_name()?;
+        let _q_type = r.read_u16()?;
+        let _q_class = r.read_u16()?;
+    }
+
+    let mut ttl: Option<u32> = None;
+    let mut records = Vec::with_capacity(an_count as usize);
+    for _ in 0..an_count {
+        r.read_name()?;
+        let rr_type_got = r.read_u16()?;
+        let rr_class = r.read_u16()?;
+        let rr_ttl = r.read_u32()?;
+        let rd_len = r.read_u16()? as usize;
+        let rd_end = r.pos + rd_len;
+        if rd_end > buf.len() {
+            return Err(ResolveDriverError::BadResp);
+        }
+        if rr_type_got == rr_type && rr_class == DNS_CLASS_IN {
+            let record = parse_rdata(&mut r, rd_end)?;
+            if r.pos != rd_end {
+                return Err(ResolveDriverError::BadResp);
+            }
+            records.push(record);
+            ttl = Some(ttl.map(|v| v.min(rr_ttl)).unwrap_or(rr_ttl));
+        }
+        // skip cname and any other records
+        r.pos = rd_end;
+    }
+
+    // see rfc2181, the highest bit should be zero
+    let ttl = ttl.map(|v| if v > i32::MAX as u32 { 0 } else { v });
+    Ok((ttl.unwrap_or_default(), records))
+}
+
+pub(super) fn parse_srv(buf: &[u8]) -> Result<(u32, Vec<SrvRecord>), ResolveDriverError> {
+    parse_answers(buf, RR_TYPE_SRV, |r, _| {
+        let priority = r.read_u16()?;
+        let weight = r.read_u16()?;
+        let port = r.read_u16()?;
+        let target = r.read_name()?;
+        Ok(SrvRecord {
+            priority,
+            weight,
+            port,
+            target,
+        })
+    })
+}
+
+fn parse_svcb_rdata(r: &mut WireReader<'_>, end: usize) -> Result<SvcbRecord, ResolveDriverError> {
+    let mut record = SvcbRecord {
+        priority: r.read_u16()?,
+        target: r.read_name()?,
+        ..Default::default()
+    };
+    while r.pos < end {
+        let key = r.read_u16()?;
+        let len = r.read_u16()? as usize;
+        let value = r.read_slice(len)?;
+        record.set_param(key, value)?;
+    }
+    Ok(record)
+}
+
+pub(super) fn parse_https(buf: &[u8]) -> Result<(u32, Vec<SvcbRecord>), ResolveDriverError> {
+    parse_answers(buf, RR_TYPE_HTTPS, parse_svcb_rdata)
+}
+
+pub(super) fn parse_svcb(buf: &[u8]) -> Result<(u32, Vec<SvcbRecord>), ResolveDriverError> {
+    parse_answers(buf, RR_TYPE_SVCB, parse_svcb_rdata)
+}
+
+pub(super) fn parse_txt(buf: &[u8]) -> Result<(u32, Vec<TxtRecord>), ResolveDriverError> {
+    parse_answers(buf, RR_TYPE_TXT, |r, end| {
+        let mut data = Vec::new();
+        while r.pos < end {
+            let len = r.read_u8()? as usize;
+            data.push(r.read_slice(len)?.to_vec());
+        }
+        Ok(TxtRecord { data })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const HEADER: &[u8] = &[
+        0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn build_response(q_name: &[u8], q_type: u16, answers: &[&[u8]]) -> Vec<u8> {
+        let mut buf = HEADER.to_vec();
+        buf[7] = answers.len() as u8;
+        buf.extend_from_slice(q_name);
+        buf.extend_from_slice(&q_type.to_be_bytes());
+        buf.extend_from_slice(&DNS_CLASS_IN.to_be_bytes());
+        for a in answers {
+            buf.extend_from_slice(a);
+        }
+        buf
+    }
+
+    #[test]
+    fn srv() {
+        let q_name = b"\x05_xmpp\x04_tcp\x07example\x03com\x00";
+        let answer: &[u8] = &[
+            0xC0, 0x0C, // pointer to the question name
+            0x00, 0x21, 0x00, 0x01, // SRV IN
+            0x00, 0x00, 0x01, 0x2C, // ttl 300
+            0x00, 0x0D, // rdlength
+            0x00, 0x0A, 0x00, 0x05, 0x14, 0x66, // priority 10, weight 5, port 5222
+            0x04, b'x', b'm', b'p', b'p', 0xC0, 0x17, // xmpp + pointer to example.com
+        ];
+        let buf = build_response(q_name, RR_TYPE_SRV, &[answer]);
+        let (ttl, records) = parse_srv(&buf).unwrap();
+        assert_eq!(ttl, 300);
+        assert_eq!(
+            records,
+            vec![SrvRecord {
+                priority: 10,
+                weight: 5,
+                port: 5222,
+                target: "xmpp.example.com".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn https() {
+        let q_name = b"\x07example\x03com\x00";
+        let cname: &[u8] = &[
+            0xC0, 0x0C, 0x00, 0x05, 0x00, 0x01, // CNAME IN
+            0x00, 0x00, 0x00, 0x3C, // ttl 60
+            0x00, 0x02, 0xC0, 0x0C,
+        ];
+        let answer: &[u8] = &[
+            0xC0, 0x0C, 0x00, 0x41, 0x00, 0x01, // HTTPS IN
+            0x00, 0x00, 0x00, 0x78, // ttl 120
+            0x00, 0x1B, // rdlength
+            0x00, 0x01, 0x00, // priority 1, target "."
+            0x00, 0x01, 0x00, 0x06, 0x02, b'h', b'2', 0x02, b'h', b'3', // alpn h2,h3
+            0x00, 0x03, 0x00, 0x02, 0x01, 0xBB, // port 443
+            0x00, 0x04, 0x00, 0x04, 192, 0, 2, 1, // ipv4hint
+        ];
+        let buf = build_response(q_name, RR_TYPE_HTTPS, &[cname, answer]);
+        let (ttl, records) = parse_https(&buf).unwrap();
+        assert_eq!(ttl, 120);
+        assert_eq!(records.len(), 1);
+        let r = &records[0];
+        assert!(!r.is_alias());
+        assert!(r.target.is_empty());
+        assert_eq!(r.alpn, vec!["h2".to_string(), "h3".to_string()]);
+        assert_eq!(r.port, Some(443));
+        assert_eq!(r.ipv4_hint, vec![Ipv4Addr::new(192, 0, 2, 1)]);
+        assert!(r.ech_config.is_none());
+    }
+
+    #[test]
+    fn txt() {
+        let q_name = b"\x07example\x03com\x00";
+        let answer: &[u8] = &[
+            0xC0, 0x0C, 0x00, 0x10, 0x00, 0x01, // TXT IN
+            0x00, 0x00, 0x00, 0x3C, // ttl 60
+            0x00, 0x08, 0x03, b'a', b'b', b'c', 0x03, b'd', b'e', b'f',
+        ];
+        let buf = build_response(q_name, RR_TYPE_TXT, &[answer]);
+        let (ttl, records) = parse_txt(&buf).unwrap();
+        assert_eq!(ttl, 60);
+        assert_eq!(records[0].to_text(), "abcdef");
+    }
+
+    #[test]
+    fn malformed() {
+        let q_name = b"\x07example\x03com\x00";
+        let answer: &[u8] = &[
+            0xC0, 0x0C, 0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x0E, 0x00,
+        ];
+        let buf = build_response(q_name, RR_TYPE_SRV, &[answer]);
+        assert!(parse_srv(&buf).is_err());
+    }
+}
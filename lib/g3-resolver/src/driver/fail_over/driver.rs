@@ -33,26 +33,26 @@ pub(super) struct FailOverResolver {
     pub(super) conf: FailOverDriverStaticConfig,
 }
 
-struct FailOverResolverJob {
-    primary: Option<ResolveJob>,
-    standby: Option<ResolveJob>,
+struct FailOverResolverJob<T> {
+    primary: Option<ResolveJob<T>>,
+    standby: Option<ResolveJob<T>>,
     job_timeout: Duration,
     config: FailOverDriverStaticConfig,
 }
 
-impl FailOverResolverJob {
+impl<T: Clone> FailOverResolverJob<T> {
     fn normalize_job_recv_result(
         &self,
         domain: Arc<str>,
-        result: ResolveJobRecvResult,
-    ) -> ResolvedRecord {
+        result: ResolveJobRecvResult<T>,
+    ) -> ResolvedRecord<T> {
         match result {
             Ok((r, _)) => r.as_ref().clone(),
             Err(e) => ResolvedRecord::failed(domain, self.config.negative_ttl, e.into()),
         }
     }
 
-    fn record_is_valid(&self, r: &ResolvedRecord) -> bool {
+    fn record_is_valid(&self, r: &ResolvedRecord<T>) -> bool {
         if self.config.retry_empty_record {
             r.is_usable()
         } else {
@@ -60,7 +60,7 @@ impl FailOverResolverJob {
         }
     }
 
-    async fn resolve(mut self, domain: Arc<str>) -> ResolvedRecord {
+    async fn resolve(mut self, domain: Arc<str>) -> ResolvedRecord<T> {
         let primary = self.primary.take();
         let standby = self.standby.take();
         match (primary, standby) {
@@ -120,7 +120,7 @@ impl FailOverResolverJob {
         }
     }
 
-    async fn resolve_protective(self, domain: Arc<str>) -> ResolvedRecord {
+    async fn resolve_protective(self, domain: Arc<str>) -> ResolvedRecord<T> {
         let protective_cache_ttl = self.config.negative_ttl;
         tokio::time::timeout(self.job_timeout, self.resolve(domain.clone()))
             .await
@@ -128,23 +128,27 @@ impl FailOverResolverJob {
     }
 }
 
-impl ResolveDriver for FailOverResolver {
-    fn query_v4(
+impl FailOverResolver {
+    fn spawn_job<T, G, R>(
         &self,
         domain: Arc<str>,
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
-    ) {
+        get_job: G,
+        build_rsp: R,
+    ) where
+        T: Clone + Send + Sync + 'static,
+        G: Fn(&ResolverHandle, Arc<str>) -> Result<ResolveJob<T>, ResolveLocalError>,
+        R: FnOnce(ResolvedRecord<T>) -> ResolveDriverResponse + Send + 'static,
+    {
         let job_primary = self
             .primary
             .as_ref()
-            .map(|handle| handle.get_v4(domain.clone()).map(Some).unwrap_or(None))
-            .unwrap_or(None);
+            .and_then(|handle| get_job(handle, domain.clone()).ok());
         let job_standby = self
             .standby
             .as_ref()
-            .map(|handle| handle.get_v4(domain.clone()).map(Some).unwrap_or(None))
-            .unwrap_or(None);
+            .and_then(|handle| get_job(handle, domain.clone()).ok());
         let job = FailOverResolverJob {
             primary: job_primary,
             standby: job_standby,
@@ -153,9 +157,26 @@ impl ResolveDriver for FailOverResolver {
         };
         tokio::spawn(async move {
             let record = job.resolve_protective(domain).await;
-            let _ = sender.send(ResolveDriverResponse::V4(record)); // TODO log error
+            let _ = sender.send(build_rsp(record)); // TODO log error
         });
     }
+}
+
+impl ResolveDriver for FailOverResolver {
+    fn query_v4(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        self.spawn_job(
+            domain,
+            config,
+            sender,
+            ResolverHandle::get_v4,
+            ResolveDriverResponse::V4,
+        );
+    }
 
     fn query_v6(
         &self,
@@ -163,25 +184,72 @@ impl ResolveDriver for FailOverResolver {
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     ) {
-        let job_primary = self
-            .primary
-            .as_ref()
-            .map(|handle| handle.get_v6(domain.clone()).map(Some).unwrap_or(None))
-            .unwrap_or(None);
-        let job_standby = self
-            .standby
-            .as_ref()
-            .map(|handle| handle.get_v6(domain.clone()).map(Some).unwrap_or(None))
-            .unwrap_or(None);
-        let job = FailOverResolverJob {
-            primary: job_primary,
-            standby: job_standby,
-            job_timeout: config.protective_query_timeout,
-            config: self.conf,
-        };
-        tokio::spawn(async move {
-            let record = job.resolve_protective(domain).await;
-            let _ = sender.send(ResolveDriverResponse::V6(record)); // TODO log error
-        });
+        self.spawn_job(
+            domain,
+            config,
+            sender,
+            ResolverHandle::get_v6,
+            ResolveDriverResponse::V6,
+        );
+    }
+
+    fn query_srv(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        self.spawn_job(
+            domain,
+            config,
+            sender,
+            ResolverHandle::get_srv,
+            ResolveDriverResponse::Srv,
+        );
+    }
+
+    fn query_https(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        self.spawn_job(
+            domain,
+            config,
+            sender,
+            ResolverHandle::get_https,
+            ResolveDriverResponse::Https,
+        );
+    }
+
+    fn query_svcb(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        self.spawn_job(
+            domain,
+            config,
+            sender,
+            ResolverHandle::get_svcb,
+            ResolveDriverResponse::Svcb,
+        );
+    }
+
+    fn query_txt(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        self.spawn_job(
+            domain,
+            config,
+            sender,
+            ResolverHandle::get_txt,
+            ResolveDriverResponse::Txt,
+        );
     }
 }
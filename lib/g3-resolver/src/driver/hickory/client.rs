@@ -14,21 +14,16 @@
  * limitations under the License.
  */
 
-use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::anyhow;
 use async_recursion::async_recursion;
 use hickory_client::client::{AsyncClient, ClientHandle};
-use hickory_proto::iocompat::AsyncIoTokioAsStd;
 use hickory_proto::rr::{DNSClass, Name, RData, RecordType};
-use rustls::{ClientConfig, ServerName};
-use tokio::net::{TcpStream, UdpSocket};
 use tokio::sync::mpsc;
 
-use g3_types::net::{DnsEncryptionConfig, DnsEncryptionProtocol};
+use g3_hickory_client::DnsClientConfig;
 
 use crate::{ResolveDriverError, ResolveError, ResolvedRecord};
 
@@ -52,6 +47,34 @@ impl DnsRequest {
             rtype: RecordType::A,
         }
     }
+
+    pub(super) fn query_srv(domain: Arc<str>) -> Self {
+        DnsRequest {
+            domain,
+            rtype: RecordType::SRV,
+        }
+    }
+
+    pub(super) fn query_https(domain: Arc<str>) -> Self {
+        DnsRequest {
+            domain,
+            rtype: RecordType::HTTPS,
+        }
+    }
+
+    pub(super) fn query_svcb(domain: Arc<str>) -> Self {
+        DnsRequest {
+            domain,
+            rtype: RecordType::SVCB,
+        }
+    }
+
+    pub(super) fn query_txt(domain: Arc<str>) -> Self {
+        DnsRequest {
+            domain,
+            rtype: RecordType::TXT,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -78,7 +101,7 @@ pub(super) struct HickoryClient {
 
 impl HickoryClient {
     pub(super) async fn new(config: HickoryClientConfig) -> anyhow::Result<Self> {
-        let client = config.build_async_client().await?;
+        let client = config.client.build_async_client().await?;
         Ok(HickoryClient {
             config: Arc::new(config),
             state: Arc::new(HickoryClientState::default()),
@@ -88,7 +111,7 @@ impl HickoryClient {
 
     pub(super) async fn run(
         mut self,
-        req_receiver: flume::Receiver<(DnsRequest, mpsc::Sender<ResolvedRecord>)>,
+        req_receiver: flume::Receiver<(DnsRequest, mpsc::Sender<ResolvedRecord<RData>>)>,
     ) {
         let (client_sender, mut client_receiver) = mpsc::channel(1);
         let mut check_interval = tokio::time::interval(Duration::from_secs(60));
@@ -104,7 +127,7 @@ impl HickoryClient {
                         config: self.config.clone(),
                         state: self.state.clone(),
                         try_failed: self.config.each_tries,
-                        try_truncated: self.config.retry_tcp(),
+                        try_truncated: self.config.client.retry_tcp(),
                     };
                     let async_client = self.client.clone();
                     tokio::spawn(async move {
@@ -117,7 +140,7 @@ impl HickoryClient {
                         let client_sender = client_sender.clone();
                         let client_config = self.config.clone();
                         tokio::spawn(async move {
-                            if let Ok(client) = client_config.build_async_client().await {
+                            if let Ok(client) = client_config.client.build_async_client().await {
                                 let _ = client_sender.try_send(client);
                             }
                         });
@@ -143,7 +166,11 @@ pub(super) struct HickoryClientJob {
 
 impl HickoryClientJob {
     #[async_recursion]
-    async fn run(mut self, mut async_client: AsyncClient, req: DnsRequest) -> ResolvedRecord {
+    async fn run(
+        mut self,
+        mut async_client: AsyncClient,
+        req: DnsRequest,
+    ) -> ResolvedRecord<RData> {
         let Ok(mut name) = Name::from_ascii(&req.domain) else {
             return ResolvedRecord::failed(
                 req.domain,
@@ -167,13 +194,13 @@ impl HickoryClientJob {
 
                     if msg.truncated() && self.try_truncated {
                         self.try_truncated = false;
-                        if let Ok(client) = self.config.new_dns_over_tcp_client().await {
+                        if let Ok(client) = self.config.client.new_dns_over_tcp_client().await {
                             return self.run(client, req).await;
                         }
                     }
 
                     let mut has_cname = false;
-                    let mut ips = Vec::with_capacity(4);
+                    let mut answers = Vec::with_capacity(4);
                     let mut ttl = 0;
                     for r in msg.take_answers() {
                         ttl = r.ttl();
@@ -181,42 +208,36 @@ impl HickoryClientJob {
                             continue;
                         };
                         match rdata {
-                            RData::A(v) => {
-                                if req.rtype == RecordType::A {
-                                    ips.push(IpAddr::V4(v.0));
-                                }
-                            }
-                            RData::AAAA(v) => {
-                                if req.rtype == RecordType::AAAA {
-                                    ips.push(IpAddr::V6(v.0))
-                                }
-                            }
                             RData::CNAME(v) => {
                                 if name.eq(r.name()) {
                                     has_cname = true;
                                     name = v.0.clone();
                                 }
                             }
-                            _ => {}
+                            _ => {
+                                if rdata.record_type() == req.rtype {
+                                    answers.push(rdata.clone());
+                                }
+                            }
                         }
                     }
-                    return if ips.is_empty() {
+                    return if answers.is_empty() {
                         if has_cname {
                             self.try_truncated = true;
                             continue;
                         }
-                        ResolvedRecord::resolved(req.domain, self.config.negative_ttl, ips)
+                        ResolvedRecord::resolved(req.domain, self.config.negative_ttl, answers)
                     } else {
                         let ttl =
                             ttl.clamp(self.config.positive_min_ttl, self.config.positive_max_ttl);
-                        ResolvedRecord::resolved(req.domain, ttl, ips)
+                        ResolvedRecord::resolved(req.domain, ttl, answers)
                     };
                 }
                 Err(e) => {
                     self.state.add_failed();
                     self.try_failed -= 1;
                     if self.try_failed > 0 {
-                        if let Ok(client) = self.config.build_async_client().await {
+                        if let Ok(client) = self.config.client.build_async_client().await {
                             return self.run(client, req).await;
                         }
                     }
@@ -229,185 +250,9 @@ impl HickoryClientJob {
 
 #[derive(Clone)]
 pub(super) struct HickoryClientConfig {
-    pub(super) target: SocketAddr,
-    pub(super) bind: Option<SocketAddr>,
-    pub(super) encryption: Option<DnsEncryptionConfig>,
-    pub(super) connect_timeout: Duration,
-    pub(super) request_timeout: Duration,
+    pub(super) client: DnsClientConfig,
     pub(super) each_tries: i32,
     pub(super) positive_min_ttl: u32,
     pub(super) positive_max_ttl: u32,
     pub(super) negative_ttl: u32,
 }
-
-impl HickoryClientConfig {
-    fn retry_tcp(&self) -> bool {
-        self.encryption.is_none()
-    }
-
-    async fn build_async_client(&self) -> anyhow::Result<AsyncClient> {
-        if let Some(ec) = &self.encryption {
-            let tls_client = ec.tls_client().driver.as_ref().clone();
-
-            match ec.protocol() {
-                DnsEncryptionProtocol::Tls => {
-                    self.new_dns_over_tls_client(tls_client, ec.tls_name().clone())
-                        .await
-                }
-                DnsEncryptionProtocol::Https => {
-                    self.new_dns_over_h2_client(tls_client, ec.tls_name().clone())
-                        .await
-                }
-                #[cfg(feature = "quic")]
-                DnsEncryptionProtocol::Quic => {
-                    self.new_dns_over_quic_client(tls_client, ec.tls_name())
-                        .await
-                }
-                #[cfg(feature = "quic")]
-                DnsEncryptionProtocol::H3 => {
-                    self.new_dns_over_h3_client(tls_client, ec.tls_name()).await
-                }
-            }
-        } else {
-            self.new_dns_over_udp_client().await
-        }
-    }
-
-    async fn new_dns_over_udp_client(&self) -> anyhow::Result<AsyncClient> {
-        // random port is used here
-        let client_connect =
-            hickory_client::udp::UdpClientStream::<UdpSocket>::with_bind_addr_and_timeout(
-                self.target,
-                self.bind,
-                self.request_timeout,
-            );
-
-        let (client, bg) = AsyncClient::connect(client_connect)
-            .await
-            .map_err(|e| anyhow!("failed to create udp async client: {e}"))?;
-        tokio::spawn(bg);
-        Ok(client)
-    }
-
-    async fn new_dns_over_tcp_client(&self) -> anyhow::Result<AsyncClient> {
-        let (stream, sender) =
-            hickory_client::tcp::TcpClientStream::<AsyncIoTokioAsStd<TcpStream>>::with_bind_addr_and_timeout(
-                self.target,
-                self.bind,
-                self.connect_timeout,
-            );
-
-        let (client, bg) = AsyncClient::with_timeout(stream, sender, self.request_timeout, None)
-            .await
-            .map_err(|e| anyhow!("failed to create tcp async client: {e}"))?;
-        tokio::spawn(bg);
-        Ok(client)
-    }
-
-    async fn new_dns_over_tls_client(
-        &self,
-        tls_client: ClientConfig,
-        tls_name: ServerName,
-    ) -> anyhow::Result<AsyncClient> {
-        use hickory_proto::BufDnsStreamHandle;
-
-        let (message_sender, outbound_messages) = BufDnsStreamHandle::new(self.target);
-
-        let tls_connect = g3_hickory_client::io::tls::connect(
-            self.target,
-            self.bind,
-            tls_client,
-            tls_name,
-            outbound_messages,
-            self.connect_timeout,
-        );
-
-        let (client, bg) = AsyncClient::with_timeout(
-            Box::pin(tls_connect),
-            message_sender,
-            self.request_timeout,
-            None,
-        )
-        .await
-        .map_err(|e| anyhow!("failed to create tls async client: {e}"))?;
-        tokio::spawn(bg);
-        Ok(client)
-    }
-
-    async fn new_dns_over_h2_client(
-        &self,
-        tls_client: ClientConfig,
-        tls_name: ServerName,
-    ) -> anyhow::Result<AsyncClient> {
-        let client_connect = g3_hickory_client::io::h2::connect(
-            self.target,
-            self.bind,
-            tls_client,
-            tls_name,
-            self.connect_timeout,
-            self.request_timeout,
-        );
-
-        let (client, bg) = AsyncClient::connect(Box::pin(client_connect))
-            .await
-            .map_err(|e| anyhow!("failed to create h2 async client: {e}"))?;
-        tokio::spawn(bg);
-        Ok(client)
-    }
-
-    #[cfg(feature = "quic")]
-    async fn new_dns_over_quic_client(
-        &self,
-        tls_client: ClientConfig,
-        tls_name: &ServerName,
-    ) -> anyhow::Result<AsyncClient> {
-        let tls_name = match tls_name {
-            ServerName::DnsName(domain) => domain.as_ref().to_string(),
-            ServerName::IpAddress(ip) => ip.to_string(),
-            _ => return Err(anyhow!("unsupported tls server name type")),
-        };
-
-        let client_connect = g3_hickory_client::io::quic::connect(
-            self.target,
-            self.bind,
-            tls_client,
-            tls_name,
-            self.connect_timeout,
-            self.request_timeout,
-        );
-
-        let (client, bg) = AsyncClient::connect(Box::pin(client_connect))
-            .await
-            .map_err(|e| anyhow!("failed to create udp async client: {e}"))?;
-        tokio::spawn(bg);
-        Ok(client)
-    }
-
-    #[cfg(feature = "quic")]
-    async fn new_dns_over_h3_client(
-        &self,
-        tls_client: ClientConfig,
-        tls_name: &ServerName,
-    ) -> anyhow::Result<AsyncClient> {
-        let tls_name = match tls_name {
-            ServerName::DnsName(domain) => domain.as_ref().to_string(),
-            ServerName::IpAddress(ip) => ip.to_string(),
-            _ => return Err(anyhow!("unsupported tls server name type")),
-        };
-
-        let client_connect = g3_hickory_client::io::h3::connect(
-            self.target,
-            self.bind,
-            tls_client,
-            tls_name,
-            self.connect_timeout,
-            self.request_timeout,
-        );
-
-        let (client, bg) = AsyncClient::connect(Box::pin(client_connect))
-            .await
-            .map_err(|e| anyhow!("failed to create h3 async client: {e}"))?;
-        tokio::spawn(bg);
-        Ok(client)
-    }
-}
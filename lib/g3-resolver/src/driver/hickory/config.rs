@@ -19,6 +19,7 @@ use std::time::Duration;
 
 use anyhow::anyhow;
 
+use g3_hickory_client::DnsClientConfig;
 use g3_types::net::DnsEncryptionConfigBuilder;
 
 use super::{HickoryClient, HickoryClientConfig, HickoryResolver};
@@ -153,11 +154,13 @@ impl HickoryDriverConfig {
 
         for ip in &self.servers {
             let client_config = HickoryClientConfig {
-                target: SocketAddr::new(*ip, port),
-                bind,
-                encryption: encryption.clone(),
-                connect_timeout: self.connect_timeout,
-                request_timeout: self.request_timeout,
+                client: DnsClientConfig {
+                    target: SocketAddr::new(*ip, port),
+                    bind,
+                    encryption: encryption.clone(),
+                    connect_timeout: self.connect_timeout,
+                    request_timeout: self.request_timeout,
+                },
                 each_tries: self.each_tries,
                 positive_min_ttl: self.positive_min_ttl,
                 positive_max_ttl: self.positive_max_ttl,
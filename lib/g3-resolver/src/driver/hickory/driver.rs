@@ -14,23 +14,30 @@
  * limitations under the License.
  */
 
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use hickory_proto::rr::rdata::svcb::{SvcParamValue, SVCB};
+use hickory_proto::rr::rdata::HTTPS;
+use hickory_proto::rr::{Name, RData};
 use tokio::sync::mpsc;
 use tokio::time::Instant;
 
 use super::DnsRequest;
 use crate::config::ResolverRuntimeConfig;
 use crate::message::ResolveDriverResponse;
-use crate::{ResolveDriver, ResolveDriverError, ResolveLocalError, ResolvedRecord};
+use crate::{
+    ResolveDriver, ResolveDriverError, ResolveLocalError, ResolvedRecord, SrvRecord, SvcbRecord,
+    TxtRecord,
+};
 
 #[derive(Clone)]
 pub struct HickoryResolver {
     each_timeout: Duration,
     retry_interval: Duration,
     negative_min_ttl: u32,
-    clients: Vec<flume::Sender<(DnsRequest, mpsc::Sender<ResolvedRecord>)>>,
+    clients: Vec<flume::Sender<(DnsRequest, mpsc::Sender<ResolvedRecord<RData>>)>>,
 }
 
 impl ResolveDriver for HickoryResolver {
@@ -46,7 +53,7 @@ impl ResolveDriver for HickoryResolver {
         let timeout = config.protective_query_timeout;
         tokio::spawn(async move {
             let r = run_timed(job, timeout, domain, request).await;
-            let _ = sender.send(ResolveDriverResponse::V4(r));
+            let _ = sender.send(ResolveDriverResponse::V4(convert_record(r, rdata_to_ipv4)));
         });
     }
 
@@ -62,7 +69,77 @@ impl ResolveDriver for HickoryResolver {
         let timeout = config.protective_query_timeout;
         tokio::spawn(async move {
             let r = run_timed(job, timeout, domain, request).await;
-            let _ = sender.send(ResolveDriverResponse::V6(r));
+            let _ = sender.send(ResolveDriverResponse::V6(convert_record(r, rdata_to_ipv6)));
+        });
+    }
+
+    fn query_srv(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let request = DnsRequest::query_srv(domain.clone());
+
+        let job = self.clone();
+        let timeout = config.protective_query_timeout;
+        tokio::spawn(async move {
+            let r = run_timed(job, timeout, domain, request).await;
+            let _ = sender.send(ResolveDriverResponse::Srv(convert_record(r, rdata_to_srv)));
+        });
+    }
+
+    fn query_https(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let request = DnsRequest::query_https(domain.clone());
+
+        let job = self.clone();
+        let timeout = config.protective_query_timeout;
+        tokio::spawn(async move {
+            let r = run_timed(job, timeout, domain, request).await;
+            let _ = sender.send(ResolveDriverResponse::Https(convert_record(
+                r,
+                rdata_to_svcb,
+            )));
+        });
+    }
+
+    fn query_svcb(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let request = DnsRequest::query_svcb(domain.clone());
+
+        let job = self.clone();
+        let timeout = config.protective_query_timeout;
+        tokio::spawn(async move {
+            let r = run_timed(job, timeout, domain, request).await;
+            let _ = sender.send(ResolveDriverResponse::Svcb(convert_record(
+                r,
+                rdata_to_svcb,
+            )));
+        });
+    }
+
+    fn query_txt(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    ) {
+        let request = DnsRequest::query_txt(domain.clone());
+
+        let job = self.clone();
+        let timeout = config.protective_query_timeout;
+        tokio::spawn(async move {
+            let r = run_timed(job, timeout, domain, request).await;
+            let _ = sender.send(ResolveDriverResponse::Txt(convert_record(r, rdata_to_txt)));
         });
     }
 }
@@ -72,7 +149,7 @@ async fn run_timed(
     timeout: Duration,
     domain: Arc<str>,
     request: DnsRequest,
-) -> ResolvedRecord {
+) -> ResolvedRecord<RData> {
     let error_ttl = job.negative_min_ttl;
     match tokio::time::timeout(timeout, job.run(domain.clone(), request)).await {
         Ok(r) => r,
@@ -96,13 +173,13 @@ impl HickoryResolver {
 
     pub(super) fn push_client(
         &mut self,
-        req_sender: flume::Sender<(DnsRequest, mpsc::Sender<ResolvedRecord>)>,
+        req_sender: flume::Sender<(DnsRequest, mpsc::Sender<ResolvedRecord<RData>>)>,
     ) {
         self.clients.push(req_sender);
     }
 
-    async fn run(self, domain: Arc<str>, request: DnsRequest) -> ResolvedRecord {
-        let (rsp_sender, mut rsp_receiver) = mpsc::channel::<ResolvedRecord>(1);
+    async fn run(self, domain: Arc<str>, request: DnsRequest) -> ResolvedRecord<RData> {
+        let (rsp_sender, mut rsp_receiver) = mpsc::channel::<ResolvedRecord<RData>>(1);
 
         let mut wait_left = self.clients.len();
         let mut clients = self.clients.into_iter();
@@ -121,7 +198,7 @@ impl HickoryResolver {
             wait_left -= 1;
         }
 
-        let mut last_err: Option<ResolvedRecord> = None;
+        let mut last_err: Option<ResolvedRecord<RData>> = None;
         let mut interval =
             tokio::time::interval_at(Instant::now() + self.retry_interval, self.retry_interval);
         loop {
@@ -177,3 +254,84 @@ impl HickoryResolver {
         last_err.unwrap_or(end_err)
     }
 }
+
+fn convert_record<T, F>(r: ResolvedRecord<RData>, f: F) -> ResolvedRecord<T>
+where
+    F: Fn(RData) -> Option<T>,
+{
+    ResolvedRecord {
+        domain: r.domain,
+        created: r.created,
+        expire: r.expire,
+        result: r.result.map(|v| v.into_iter().filter_map(f).collect()),
+    }
+}
+
+fn name_to_string(name: &Name) -> String {
+    let mut s = name.to_ascii();
+    if s.ends_with('.') {
+        s.pop();
+    }
+    s
+}
+
+fn rdata_to_ipv4(rdata: RData) -> Option<IpAddr> {
+    match rdata {
+        RData::A(v) => Some(IpAddr::V4(v.0)),
+        _ => None,
+    }
+}
+
+fn rdata_to_ipv6(rdata: RData) -> Option<IpAddr> {
+    match rdata {
+        RData::AAAA(v) => Some(IpAddr::V6(v.0)),
+        _ => None,
+    }
+}
+
+fn rdata_to_srv(rdata: RData) -> Option<SrvRecord> {
+    match rdata {
+        RData::SRV(v) => Some(SrvRecord {
+            priority: v.priority(),
+            weight: v.weight(),
+            port: v.port(),
+            target: name_to_string(v.target()),
+        }),
+        _ => None,
+    }
+}
+
+fn rdata_to_svcb(rdata: RData) -> Option<SvcbRecord> {
+    let svcb: SVCB = match rdata {
+        RData::SVCB(v) => v,
+        RData::HTTPS(HTTPS(v)) => v,
+        _ => return None,
+    };
+
+    let mut r = SvcbRecord {
+        priority: svcb.svc_priority(),
+        target: name_to_string(svcb.target_name()),
+        ..Default::default()
+    };
+    for (_, value) in svcb.svc_params() {
+        match value {
+            SvcParamValue::Alpn(v) => r.alpn.extend(v.0.iter().cloned()),
+            SvcParamValue::NoDefaultAlpn => r.no_default_alpn = true,
+            SvcParamValue::Port(v) => r.port = Some(*v),
+            SvcParamValue::Ipv4Hint(v) => r.ipv4_hint.extend(v.0.iter().map(|a| a.0)),
+            SvcParamValue::EchConfig(v) => r.ech_config = Some(v.0.clone()),
+            SvcParamValue::Ipv6Hint(v) => r.ipv6_hint.extend(v.0.iter().map(|a| a.0)),
+            _ => {}
+        }
+    }
+    Some(r)
+}
+
+fn rdata_to_txt(rdata: RData) -> Option<TxtRecord> {
+    match rdata {
+        RData::TXT(v) => Some(TxtRecord {
+            data: v.txt_data().iter().map(|s| s.to_vec()).collect(),
+        }),
+        _ => None,
+    }
+}
@@ -63,6 +63,30 @@ pub(crate) trait ResolveDriver {
         config: &ResolverRuntimeConfig,
         sender: mpsc::UnboundedSender<ResolveDriverResponse>,
     );
+    fn query_srv(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    );
+    fn query_https(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    );
+    fn query_svcb(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    );
+    fn query_txt(
+        &self,
+        domain: Arc<str>,
+        config: &ResolverRuntimeConfig,
+        sender: mpsc::UnboundedSender<ResolveDriverResponse>,
+    );
 }
 
 pub(crate) type BoxResolverDriver = Box<dyn ResolveDriver>;
@@ -15,13 +15,16 @@
  */
 
 use std::future::{poll_fn, Future};
+use std::net::IpAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use tokio::sync::{mpsc, oneshot};
 
-use super::{ArcResolvedRecord, ResolveLocalError, ResolvedRecordSource};
+use super::{
+    ArcResolvedRecord, ResolveLocalError, ResolvedRecordSource, SrvRecord, SvcbRecord, TxtRecord,
+};
 use crate::message::ResolveDriverRequest;
 
 #[derive(Clone, Debug)]
@@ -63,16 +66,56 @@ impl ResolverHandle {
             Err(_) => Err(ResolveLocalError::NoResolverRunning),
         }
     }
+
+    pub fn get_srv(&self, domain: Arc<str>) -> Result<ResolveJob<SrvRecord>, ResolveLocalError> {
+        let (sender, receiver) = oneshot::channel();
+        let req = ResolveDriverRequest::GetSrv(domain, sender);
+        let sender = self.req_sender.clone();
+        match sender.send(req) {
+            Ok(_) => Ok(ResolveJob { receiver }),
+            Err(_) => Err(ResolveLocalError::NoResolverRunning),
+        }
+    }
+
+    pub fn get_https(&self, domain: Arc<str>) -> Result<ResolveJob<SvcbRecord>, ResolveLocalError> {
+        let (sender, receiver) = oneshot::channel();
+        let req = ResolveDriverRequest::GetHttps(domain, sender);
+        let sender = self.req_sender.clone();
+        match sender.send(req) {
+            Ok(_) => Ok(ResolveJob { receiver }),
+            Err(_) => Err(ResolveLocalError::NoResolverRunning),
+        }
+    }
+
+    pub fn get_svcb(&self, domain: Arc<str>) -> Result<ResolveJob<SvcbRecord>, ResolveLocalError> {
+        let (sender, receiver) = oneshot::channel();
+        let req = ResolveDriverRequest::GetSvcb(domain, sender);
+        let sender = self.req_sender.clone();
+        match sender.send(req) {
+            Ok(_) => Ok(ResolveJob { receiver }),
+            Err(_) => Err(ResolveLocalError::NoResolverRunning),
+        }
+    }
+
+    pub fn get_txt(&self, domain: Arc<str>) -> Result<ResolveJob<TxtRecord>, ResolveLocalError> {
+        let (sender, receiver) = oneshot::channel();
+        let req = ResolveDriverRequest::GetTxt(domain, sender);
+        let sender = self.req_sender.clone();
+        match sender.send(req) {
+            Ok(_) => Ok(ResolveJob { receiver }),
+            Err(_) => Err(ResolveLocalError::NoResolverRunning),
+        }
+    }
 }
 
-pub struct ResolveJob {
-    receiver: oneshot::Receiver<(ArcResolvedRecord, ResolvedRecordSource)>,
+pub struct ResolveJob<T = IpAddr> {
+    receiver: oneshot::Receiver<(ArcResolvedRecord<T>, ResolvedRecordSource)>,
 }
-pub type ResolveJobRecvResult =
-    Result<(ArcResolvedRecord, ResolvedRecordSource), ResolveLocalError>;
+pub type ResolveJobRecvResult<T = IpAddr> =
+    Result<(ArcResolvedRecord<T>, ResolvedRecordSource), ResolveLocalError>;
 
-impl ResolveJob {
-    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<ResolveJobRecvResult> {
+impl<T> ResolveJob<T> {
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<ResolveJobRecvResult<T>> {
         match Pin::new(&mut self.receiver).poll(cx) {
             Poll::Pending => Poll::Pending,
             Poll::Ready(Ok(ret)) => Poll::Ready(Ok(ret)),
@@ -80,7 +123,7 @@ impl ResolveJob {
         }
     }
 
-    pub async fn recv(&mut self) -> ResolveJobRecvResult {
+    pub async fn recv(&mut self) -> ResolveJobRecvResult<T> {
         poll_fn(|cx| self.poll_recv(cx)).await
     }
 }
@@ -24,6 +24,7 @@ mod error;
 mod handle;
 mod message;
 mod query;
+mod rdata;
 mod record;
 mod resolver;
 mod runtime;
@@ -33,6 +34,7 @@ pub use config::{ResolverConfig, ResolverRuntimeConfig};
 pub use error::{ResolveDriverError, ResolveError, ResolveLocalError, ResolveServerError};
 pub use handle::{ResolveJob, ResolveJobRecvResult, ResolverHandle};
 pub use query::ResolveQueryType;
+pub use rdata::{SrvRecord, SvcbRecord, TxtRecord};
 pub use record::{ArcResolvedRecord, ResolvedRecord, ResolvedRecordSource};
 pub use resolver::{Resolver, ResolverBuilder};
 pub use stats::{ResolverMemorySnapshot, ResolverQuerySnapshot, ResolverSnapshot, ResolverStats};
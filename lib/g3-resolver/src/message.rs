@@ -14,11 +14,15 @@
  * limitations under the License.
  */
 
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use tokio::sync::oneshot;
 
-use super::{ArcResolvedRecord, ResolvedRecord, ResolvedRecordSource, ResolverConfig};
+use super::{
+    ArcResolvedRecord, ResolvedRecord, ResolvedRecordSource, ResolverConfig, SrvRecord, SvcbRecord,
+    TxtRecord,
+};
 
 #[derive(Clone, Debug)]
 pub(crate) enum ResolverCommand {
@@ -26,18 +30,23 @@ pub(crate) enum ResolverCommand {
     Update(Box<ResolverConfig>),
 }
 
+pub(crate) type ResolvedRecordSender<T> =
+    oneshot::Sender<(ArcResolvedRecord<T>, ResolvedRecordSource)>;
+
 pub(crate) enum ResolveDriverRequest {
-    GetV4(
-        Arc<str>,
-        oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>,
-    ),
-    GetV6(
-        Arc<str>,
-        oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>,
-    ),
+    GetV4(Arc<str>, ResolvedRecordSender<IpAddr>),
+    GetV6(Arc<str>, ResolvedRecordSender<IpAddr>),
+    GetSrv(Arc<str>, ResolvedRecordSender<SrvRecord>),
+    GetHttps(Arc<str>, ResolvedRecordSender<SvcbRecord>),
+    GetSvcb(Arc<str>, ResolvedRecordSender<SvcbRecord>),
+    GetTxt(Arc<str>, ResolvedRecordSender<TxtRecord>),
 }
 
 pub(crate) enum ResolveDriverResponse {
     V4(ResolvedRecord),
     V6(ResolvedRecord),
+    Srv(ResolvedRecord<SrvRecord>),
+    Https(ResolvedRecord<SvcbRecord>),
+    Svcb(ResolvedRecord<SvcbRecord>),
+    Txt(ResolvedRecord<TxtRecord>),
 }
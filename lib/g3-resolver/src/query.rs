@@ -15,11 +15,16 @@
  */
 
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ResolveQueryType {
     A,
     Aaaa,
+    Srv,
+    Https,
+    Svcb,
+    Txt,
 }
 
 impl ResolveQueryType {
@@ -27,6 +32,26 @@ impl ResolveQueryType {
         match self {
             ResolveQueryType::A => "A",
             ResolveQueryType::Aaaa => "AAAA",
+            ResolveQueryType::Srv => "SRV",
+            ResolveQueryType::Https => "HTTPS",
+            ResolveQueryType::Svcb => "SVCB",
+            ResolveQueryType::Txt => "TXT",
+        }
+    }
+}
+
+impl FromStr for ResolveQueryType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "A" => Ok(ResolveQueryType::A),
+            "AAAA" => Ok(ResolveQueryType::Aaaa),
+            "SRV" => Ok(ResolveQueryType::Srv),
+            "HTTPS" => Ok(ResolveQueryType::Https),
+            "SVCB" => Ok(ResolveQueryType::Svcb),
+            "TXT" => Ok(ResolveQueryType::Txt),
+            _ => Err(()),
         }
     }
 }
@@ -0,0 +1,127 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::ResolveDriverError;
+
+/// SRV record data, see rfc2782
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    /// the target domain name, without the trailing dot
+    pub target: String,
+}
+
+impl SrvRecord {
+    /// A target of "." means that the service is decidedly not available at this domain
+    pub fn is_available(&self) -> bool {
+        !self.target.is_empty()
+    }
+}
+
+/// SVCB / HTTPS record data, see rfc9460
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SvcbRecord {
+    pub priority: u16,
+    /// the target domain name, without the trailing dot.
+    /// An empty value means the owner name for service mode records.
+    pub target: String,
+    pub alpn: Vec<String>,
+    pub no_default_alpn: bool,
+    pub port: Option<u16>,
+    pub ipv4_hint: Vec<Ipv4Addr>,
+    pub ipv6_hint: Vec<Ipv6Addr>,
+    /// the raw ECHConfigList value
+    pub ech_config: Option<Vec<u8>>,
+}
+
+impl SvcbRecord {
+    pub const PARAM_KEY_ALPN: u16 = 1;
+    pub const PARAM_KEY_NO_DEFAULT_ALPN: u16 = 2;
+    pub const PARAM_KEY_PORT: u16 = 3;
+    pub const PARAM_KEY_IPV4_HINT: u16 = 4;
+    pub const PARAM_KEY_ECH: u16 = 5;
+    pub const PARAM_KEY_IPV6_HINT: u16 = 6;
+
+    pub fn is_alias(&self) -> bool {
+        self.priority == 0
+    }
+
+    /// Set the SvcParam value, unknown keys will be ignored
+    pub fn set_param(&mut self, key: u16, value: &[u8]) -> Result<(), ResolveDriverError> {
+        match key {
+            Self::PARAM_KEY_ALPN => {
+                let mut left = value;
+                while let Some((len, data)) = left.split_first() {
+                    let len = *len as usize;
+                    if len == 0 || data.len() < len {
+                        return Err(ResolveDriverError::BadResp);
+                    }
+                    let id = std::str::from_utf8(&data[..len])
+                        .map_err(|_| ResolveDriverError::BadResp)?;
+                    self.alpn.push(id.to_string());
+                    left = &data[len..];
+                }
+            }
+            Self::PARAM_KEY_NO_DEFAULT_ALPN => self.no_default_alpn = true,
+            Self::PARAM_KEY_PORT => {
+                let port: [u8; 2] = value.try_into().map_err(|_| ResolveDriverError::BadResp)?;
+                self.port = Some(u16::from_be_bytes(port));
+            }
+            Self::PARAM_KEY_IPV4_HINT => {
+                if value.len() % 4 != 0 {
+                    return Err(ResolveDriverError::BadResp);
+                }
+                for c in value.chunks_exact(4) {
+                    let ip: [u8; 4] = c.try_into().unwrap();
+                    self.ipv4_hint.push(Ipv4Addr::from(ip));
+                }
+            }
+            Self::PARAM_KEY_ECH => self.ech_config = Some(value.to_vec()),
+            Self::PARAM_KEY_IPV6_HINT => {
+                if value.len() % 16 != 0 {
+                    return Err(ResolveDriverError::BadResp);
+                }
+                for c in value.chunks_exact(16) {
+                    let ip: [u8; 16] = c.try_into().unwrap();
+                    self.ipv6_hint.push(Ipv6Addr::from(ip));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// TXT record data, see rfc1035
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TxtRecord {
+    pub data: Vec<Vec<u8>>,
+}
+
+impl TxtRecord {
+    /// Get all the character-strings joined together as text
+    pub fn to_text(&self) -> String {
+        let mut s = String::new();
+        for v in &self.data {
+            s.push_str(&String::from_utf8_lossy(v));
+        }
+        s
+    }
+}
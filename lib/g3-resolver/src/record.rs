@@ -46,16 +46,16 @@ impl fmt::Display for ResolvedRecordSource {
 }
 
 #[derive(Clone, Debug)]
-pub struct ResolvedRecord {
+pub struct ResolvedRecord<T = IpAddr> {
     pub domain: Arc<str>,
     pub created: Instant,
     pub expire: Option<Instant>,
-    pub result: Result<Vec<IpAddr>, ResolveError>,
+    pub result: Result<Vec<T>, ResolveError>,
 }
 
-pub type ArcResolvedRecord = Arc<ResolvedRecord>;
+pub type ArcResolvedRecord<T = IpAddr> = Arc<ResolvedRecord<T>>;
 
-impl ResolvedRecord {
+impl<T> ResolvedRecord<T> {
     pub fn is_ok(&self) -> bool {
         self.result.is_ok()
     }
@@ -76,14 +76,14 @@ impl ResolvedRecord {
         )
     }
 
-    pub fn resolved(domain: Arc<str>, ttl: u32, ips: Vec<IpAddr>) -> Self {
+    pub fn resolved(domain: Arc<str>, ttl: u32, data: Vec<T>) -> Self {
         let created = Instant::now();
         let expire = created.checked_add(Duration::from_secs(ttl as u64));
         ResolvedRecord {
             domain,
             created,
             expire,
-            result: Ok(ips),
+            result: Ok(data),
         }
     }
 
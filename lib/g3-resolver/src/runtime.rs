@@ -16,26 +16,148 @@
 
 use std::collections::hash_map;
 use std::future::Future;
+use std::net::IpAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use ahash::AHashMap;
 use log::{trace, warn};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::mpsc;
 use tokio::time::Instant;
 use tokio_util::time::{delay_queue, DelayQueue};
 
-use super::stats::{ResolverMemoryStats, ResolverStats};
-use super::{ArcResolvedRecord, BoxResolverDriver, ResolvedRecordSource, ResolverConfig};
-use crate::message::{ResolveDriverRequest, ResolveDriverResponse, ResolverCommand};
+use super::stats::{ResolverMemoryStats, ResolverQueryStats, ResolverStats};
+use super::{
+    ArcResolvedRecord, BoxResolverDriver, ResolveQueryType, ResolvedRecord, ResolvedRecordSource,
+    ResolverConfig, SrvRecord, SvcbRecord, TxtRecord,
+};
+use crate::message::{
+    ResolveDriverRequest, ResolveDriverResponse, ResolvedRecordSender, ResolverCommand,
+};
 
-struct CachedRecord {
-    inner: ArcResolvedRecord,
+struct CachedRecord<T> {
+    inner: ArcResolvedRecord<T>,
     expire_at: Instant,
     expire_key: Option<delay_queue::Key>,
 }
 
+struct RecordCache<T> {
+    query_type: ResolveQueryType,
+    expired: DelayQueue<Arc<str>>,
+    cache: AHashMap<Arc<str>, CachedRecord<T>>,
+    doing: AHashMap<Arc<str>, Vec<ResolvedRecordSender<T>>>,
+}
+
+impl<T> RecordCache<T> {
+    fn with_capacity(query_type: ResolveQueryType, capacity: usize) -> Self {
+        RecordCache {
+            query_type,
+            expired: DelayQueue::with_capacity(capacity),
+            cache: AHashMap::with_capacity(capacity),
+            doing: AHashMap::with_capacity(capacity),
+        }
+    }
+
+    fn update_cache(&mut self, record: ArcResolvedRecord<T>, expire_at: Instant) {
+        match self.cache.entry(record.domain.clone()) {
+            hash_map::Entry::Occupied(mut o) => {
+                let v = o.get_mut();
+                let expire_key = match v.expire_key.take() {
+                    Some(expire_key) => {
+                        self.expired.reset_at(&expire_key, expire_at);
+                        expire_key
+                    }
+                    None => self.expired.insert_at(record.domain.clone(), expire_at),
+                };
+                v.inner = record;
+                v.expire_at = expire_at;
+                v.expire_key = Some(expire_key);
+            }
+            hash_map::Entry::Vacant(v) => {
+                let expire_key = self.expired.insert_at(record.domain.to_owned(), expire_at);
+                v.insert(CachedRecord {
+                    inner: record,
+                    expire_at,
+                    expire_key: Some(expire_key),
+                });
+            }
+        }
+    }
+
+    fn handle_rsp(&mut self, record: ResolvedRecord<T>, stats: &ResolverQueryStats) {
+        stats.add_record(&record);
+        let record = Arc::new(record);
+        if let Some(mut vec) = self.doing.remove(&record.domain) {
+            if let Some(sender) = vec.pop() {
+                let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Query));
+                stats.add_query_cached_n(vec.len());
+                for sender in vec.into_iter() {
+                    let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Cache));
+                }
+            }
+        }
+        if let Some(expire_at) = record.expire {
+            self.update_cache(record, expire_at);
+        }
+    }
+
+    /// Reply with the cached record or wait for the running query.
+    /// Return true if a new driver query should be sent.
+    fn handle_req(
+        &mut self,
+        domain: &Arc<str>,
+        sender: ResolvedRecordSender<T>,
+        stats: &ResolverQueryStats,
+    ) -> bool {
+        stats.add_query_total();
+        match self.cache.get(domain) {
+            Some(r) => {
+                stats.add_query_cached();
+                let _ = sender.send((Arc::clone(&r.inner), ResolvedRecordSource::Cache));
+                false
+            }
+            None => match self.doing.entry(domain.clone()) {
+                hash_map::Entry::Occupied(mut o) => {
+                    // there is a query already
+                    o.get_mut().push(sender);
+                    false
+                }
+                hash_map::Entry::Vacant(v) => {
+                    v.insert(vec![sender]);
+                    stats.add_query_driver();
+                    true
+                }
+            },
+        }
+    }
+
+    /// Clean all expired records, return true if any has been removed
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> bool {
+        let mut cleaned = false;
+        loop {
+            match self.expired.poll_expired(cx) {
+                Poll::Pending => break,
+                Poll::Ready(None) => break, // all items fetched
+                Poll::Ready(Some(t)) => {
+                    cleaned = true;
+                    let domain = t.get_ref();
+                    trace!("clean expired {} for domain {domain}", self.query_type);
+                    self.cache.remove(domain);
+                }
+            }
+        }
+        cleaned
+    }
+
+    fn update_mem_stats(&self, stats: &ResolverMemoryStats) {
+        stats.set_cache_capacity(self.cache.capacity());
+        stats.set_cache_length(self.cache.len());
+        stats.set_doing_capacity(self.doing.capacity());
+        stats.set_doing_length(self.doing.len());
+    }
+}
+
 pub(crate) struct ResolverRuntime {
     config: ResolverConfig,
     stats: Arc<ResolverStats>,
@@ -43,12 +165,12 @@ pub(crate) struct ResolverRuntime {
     ctl_receiver: mpsc::UnboundedReceiver<ResolverCommand>,
     rsp_receiver: mpsc::UnboundedReceiver<ResolveDriverResponse>,
     rsp_sender: mpsc::UnboundedSender<ResolveDriverResponse>,
-    expired_v4: DelayQueue<Arc<str>>,
-    expired_v6: DelayQueue<Arc<str>>,
-    cache_v4: AHashMap<Arc<str>, CachedRecord>,
-    cache_v6: AHashMap<Arc<str>, CachedRecord>,
-    doing_v4: AHashMap<Arc<str>, Vec<oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>>>,
-    doing_v6: AHashMap<Arc<str>, Vec<oneshot::Sender<(ArcResolvedRecord, ResolvedRecordSource)>>>,
+    cache_v4: RecordCache<IpAddr>,
+    cache_v6: RecordCache<IpAddr>,
+    cache_srv: RecordCache<SrvRecord>,
+    cache_https: RecordCache<SvcbRecord>,
+    cache_svcb: RecordCache<SvcbRecord>,
+    cache_txt: RecordCache<TxtRecord>,
     driver: Option<BoxResolverDriver>,
 }
 
@@ -75,12 +197,13 @@ impl ResolverRuntime {
             ctl_receiver,
             rsp_receiver,
             rsp_sender,
-            expired_v4: DelayQueue::with_capacity(initial_cache_capacity),
-            expired_v6: DelayQueue::with_capacity(initial_cache_capacity),
-            cache_v4: AHashMap::with_capacity(initial_cache_capacity),
-            cache_v6: AHashMap::with_capacity(initial_cache_capacity),
-            doing_v4: AHashMap::with_capacity(initial_cache_capacity),
-            doing_v6: AHashMap::with_capacity(initial_cache_capacity),
+            cache_v4: RecordCache::with_capacity(ResolveQueryType::A, initial_cache_capacity),
+            cache_v6: RecordCache::with_capacity(ResolveQueryType::Aaaa, initial_cache_capacity),
+            // the service records are rarely queried, so let them grow on demand
+            cache_srv: RecordCache::with_capacity(ResolveQueryType::Srv, 0),
+            cache_https: RecordCache::with_capacity(ResolveQueryType::Https, 0),
+            cache_svcb: RecordCache::with_capacity(ResolveQueryType::Svcb, 0),
+            cache_txt: RecordCache::with_capacity(ResolveQueryType::Txt, 0),
             driver: None,
         }
     }
@@ -100,158 +223,103 @@ impl ResolverRuntime {
         }
     }
 
-    fn update_cache(
-        cache: &mut AHashMap<Arc<str>, CachedRecord>,
-        expire_queue: &mut DelayQueue<Arc<str>>,
-        record: ArcResolvedRecord,
-        expire_at: Instant,
-    ) {
-        match cache.entry(record.domain.clone()) {
-            hash_map::Entry::Occupied(mut o) => {
-                let v = o.get_mut();
-                let expire_key = match v.expire_key.take() {
-                    Some(expire_key) => {
-                        expire_queue.reset_at(&expire_key, expire_at);
-                        expire_key
-                    }
-                    None => expire_queue.insert_at(record.domain.clone(), expire_at),
-                };
-                v.inner = record;
-                v.expire_at = expire_at;
-                v.expire_key = Some(expire_key);
-            }
-            hash_map::Entry::Vacant(v) => {
-                let expire_key = expire_queue.insert_at(record.domain.to_owned(), expire_at);
-                v.insert(CachedRecord {
-                    inner: record,
-                    expire_at,
-                    expire_key: Some(expire_key),
-                });
-            }
-        }
-    }
-
     fn handle_rsp(&mut self, rsp: ResolveDriverResponse) {
         match rsp {
             ResolveDriverResponse::V4(record) => {
-                self.stats.query_a.add_record(&record);
-                let record = Arc::new(record);
-                if let Some(mut vec) = self.doing_v4.remove(&record.domain) {
-                    if let Some(sender) = vec.pop() {
-                        let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Query));
-                        self.stats.query_a.add_query_cached_n(vec.len());
-                        for sender in vec.into_iter() {
-                            let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Cache));
-                        }
-                    }
-                }
-                if let Some(expire_at) = record.expire {
-                    Self::update_cache(&mut self.cache_v4, &mut self.expired_v4, record, expire_at);
-                }
+                self.cache_v4.handle_rsp(record, &self.stats.query_a)
             }
             ResolveDriverResponse::V6(record) => {
-                self.stats.query_aaaa.add_record(&record);
-                let record = Arc::new(record);
-                if let Some(mut vec) = self.doing_v6.remove(&record.domain) {
-                    if let Some(sender) = vec.pop() {
-                        let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Query));
-                        self.stats.query_aaaa.add_query_cached_n(vec.len());
-                        for sender in vec.into_iter() {
-                            let _ = sender.send((Arc::clone(&record), ResolvedRecordSource::Cache));
-                        }
-                    }
-                }
-                if let Some(expire_at) = record.expire {
-                    Self::update_cache(&mut self.cache_v6, &mut self.expired_v6, record, expire_at);
-                }
+                self.cache_v6.handle_rsp(record, &self.stats.query_aaaa)
+            }
+            ResolveDriverResponse::Srv(record) => {
+                self.cache_srv.handle_rsp(record, &self.stats.query_srv)
+            }
+            ResolveDriverResponse::Https(record) => {
+                self.cache_https.handle_rsp(record, &self.stats.query_https)
+            }
+            ResolveDriverResponse::Svcb(record) => {
+                self.cache_svcb.handle_rsp(record, &self.stats.query_svcb)
+            }
+            ResolveDriverResponse::Txt(record) => {
+                self.cache_txt.handle_rsp(record, &self.stats.query_txt)
             }
         }
     }
 
-    fn handle_expired_v4(&mut self, domain: &str) {
-        trace!("clean expired v4 for domain {domain}");
-        self.cache_v4.remove(domain);
-    }
-    fn handle_expired_v6(&mut self, domain: &str) {
-        trace!("clean expired v6 for domain {domain}");
-        self.cache_v6.remove(domain);
-    }
-
     fn handle_req(&mut self, req: ResolveDriverRequest) {
+        let Some(driver) = &self.driver else {
+            unreachable!()
+        };
         match req {
             ResolveDriverRequest::GetV4(domain, sender) => {
-                self.stats.query_a.add_query_total();
-                match self.cache_v4.get(&domain) {
-                    Some(r) => {
-                        self.stats.query_a.add_query_cached();
-                        let _ = sender.send((Arc::clone(&r.inner), ResolvedRecordSource::Cache));
-                    }
-                    None => match self.doing_v4.entry(domain.to_owned()) {
-                        hash_map::Entry::Occupied(mut o) => {
-                            // there is a query already
-                            o.get_mut().push(sender);
-                        }
-                        hash_map::Entry::Vacant(v) => {
-                            v.insert(vec![sender]);
-                            if let Some(driver) = &self.driver {
-                                self.stats.query_a.add_query_driver();
-                                driver.query_v4(
-                                    domain,
-                                    &self.config.runtime,
-                                    self.rsp_sender.clone(),
-                                );
-                            } else {
-                                unreachable!()
-                            }
-                        }
-                    },
+                if self
+                    .cache_v4
+                    .handle_req(&domain, sender, &self.stats.query_a)
+                {
+                    driver.query_v4(domain, &self.config.runtime, self.rsp_sender.clone());
                 }
             }
             ResolveDriverRequest::GetV6(domain, sender) => {
-                self.stats.query_aaaa.add_query_total();
-                match self.cache_v6.get(&domain) {
-                    Some(r) => {
-                        self.stats.query_aaaa.add_query_cached();
-                        let _ = sender.send((Arc::clone(&r.inner), ResolvedRecordSource::Cache));
-                    }
-                    None => match self.doing_v6.entry(domain.to_owned()) {
-                        hash_map::Entry::Occupied(mut o) => {
-                            // there is a query already
-                            o.get_mut().push(sender);
-                        }
-                        hash_map::Entry::Vacant(v) => {
-                            v.insert(vec![sender]);
-                            if let Some(driver) = &self.driver {
-                                self.stats.query_aaaa.add_query_driver();
-                                driver.query_v6(
-                                    domain,
-                                    &self.config.runtime,
-                                    self.rsp_sender.clone(),
-                                );
-                            } else {
-                                unreachable!()
-                            }
-                        }
-                    },
+                if self
+                    .cache_v6
+                    .handle_req(&domain, sender, &self.stats.query_aaaa)
+                {
+                    driver.query_v6(domain, &self.config.runtime, self.rsp_sender.clone());
+                }
+            }
+            ResolveDriverRequest::GetSrv(domain, sender) => {
+                if self
+                    .cache_srv
+                    .handle_req(&domain, sender, &self.stats.query_srv)
+                {
+                    driver.query_srv(domain, &self.config.runtime, self.rsp_sender.clone());
+                }
+            }
+            ResolveDriverRequest::GetHttps(domain, sender) => {
+                if self
+                    .cache_https
+                    .handle_req(&domain, sender, &self.stats.query_https)
+                {
+                    driver.query_https(domain, &self.config.runtime, self.rsp_sender.clone());
+                }
+            }
+            ResolveDriverRequest::GetSvcb(domain, sender) => {
+                if self
+                    .cache_svcb
+                    .handle_req(&domain, sender, &self.stats.query_svcb)
+                {
+                    driver.query_svcb(domain, &self.config.runtime, self.rsp_sender.clone());
+                }
+            }
+            ResolveDriverRequest::GetTxt(domain, sender) => {
+                if self
+                    .cache_txt
+                    .handle_req(&domain, sender, &self.stats.query_txt)
+                {
+                    driver.query_txt(domain, &self.config.runtime, self.rsp_sender.clone());
                 }
             }
         }
     }
 
     fn update_mem_stats(&self) {
-        fn update<K, VC, VD>(
-            stats: &ResolverMemoryStats,
-            cache_ht: &AHashMap<K, VC>,
-            doing_ht: &AHashMap<K, VD>,
-        ) {
-            stats.set_cache_capacity(cache_ht.capacity());
-            stats.set_cache_length(cache_ht.len());
-            stats.set_doing_capacity(doing_ht.capacity());
-            stats.set_doing_length(doing_ht.len());
-        }
+        self.cache_v4.update_mem_stats(&self.stats.memory_a);
+        self.cache_v6.update_mem_stats(&self.stats.memory_aaaa);
+        self.cache_srv.update_mem_stats(&self.stats.memory_srv);
+        self.cache_https.update_mem_stats(&self.stats.memory_https);
+        self.cache_svcb.update_mem_stats(&self.stats.memory_svcb);
+        self.cache_txt.update_mem_stats(&self.stats.memory_txt);
+    }
 
-        update(&self.stats.memory_a, &self.cache_v4, &self.doing_v4);
-        update(&self.stats.memory_aaaa, &self.cache_v6, &self.doing_v6);
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> bool {
+        // poll all of them to register the wakers
+        let mut cleaned = self.cache_v4.poll_expired(cx);
+        cleaned |= self.cache_v6.poll_expired(cx);
+        cleaned |= self.cache_srv.poll_expired(cx);
+        cleaned |= self.cache_https.poll_expired(cx);
+        cleaned |= self.cache_svcb.poll_expired(cx);
+        cleaned |= self.cache_txt.poll_expired(cx);
+        cleaned
     }
 
     fn poll_loop(&mut self, cx: &mut Context<'_>) -> Poll<anyhow::Result<()>> {
@@ -288,25 +356,8 @@ impl ResolverRuntime {
             }
 
             // handle expired
-            loop {
-                match self.expired_v4.poll_expired(cx) {
-                    Poll::Pending => break,
-                    Poll::Ready(None) => break, // all items fetched
-                    Poll::Ready(Some(t)) => {
-                        update_mem_stats = true;
-                        self.handle_expired_v4(t.get_ref());
-                    }
-                }
-            }
-            loop {
-                match self.expired_v6.poll_expired(cx) {
-                    Poll::Pending => break,
-                    Poll::Ready(None) => break, // all items fetched
-                    Poll::Ready(Some(t)) => {
-                        update_mem_stats = true;
-                        self.handle_expired_v6(t.get_ref());
-                    }
-                }
+            if self.poll_expired(cx) {
+                update_mem_stats = true;
             }
 
             if update_mem_stats {
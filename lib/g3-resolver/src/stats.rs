@@ -117,7 +117,7 @@ impl ResolverQueryStats {
         self.server_serv_fail.fetch_add(1, Ordering::Relaxed);
     }
 
-    pub(crate) fn add_record(&self, record: &ResolvedRecord) {
+    pub(crate) fn add_record<T>(&self, record: &ResolvedRecord<T>) {
         if let Err(e) = &record.result {
             self.add_error(e);
         }
@@ -201,8 +201,16 @@ impl ResolverMemoryStats {
 pub struct ResolverStats {
     pub(crate) query_a: ResolverQueryStats,
     pub(crate) query_aaaa: ResolverQueryStats,
+    pub(crate) query_srv: ResolverQueryStats,
+    pub(crate) query_https: ResolverQueryStats,
+    pub(crate) query_svcb: ResolverQueryStats,
+    pub(crate) query_txt: ResolverQueryStats,
     pub(crate) memory_a: ResolverMemoryStats,
     pub(crate) memory_aaaa: ResolverMemoryStats,
+    pub(crate) memory_srv: ResolverMemoryStats,
+    pub(crate) memory_https: ResolverMemoryStats,
+    pub(crate) memory_svcb: ResolverMemoryStats,
+    pub(crate) memory_txt: ResolverMemoryStats,
 }
 
 impl ResolverStats {
@@ -210,8 +218,16 @@ impl ResolverStats {
         ResolverSnapshot {
             query_a: self.query_a.snapshot(),
             query_aaaa: self.query_aaaa.snapshot(),
+            query_srv: self.query_srv.snapshot(),
+            query_https: self.query_https.snapshot(),
+            query_svcb: self.query_svcb.snapshot(),
+            query_txt: self.query_txt.snapshot(),
             memory_a: self.memory_a.snapshot(),
             memory_aaaa: self.memory_aaaa.snapshot(),
+            memory_srv: self.memory_srv.snapshot(),
+            memory_https: self.memory_https.snapshot(),
+            memory_svcb: self.memory_svcb.snapshot(),
+            memory_txt: self.memory_txt.snapshot(),
         }
     }
 }
@@ -220,6 +236,14 @@ impl ResolverStats {
 pub struct ResolverSnapshot {
     pub query_a: ResolverQuerySnapshot,
     pub query_aaaa: ResolverQuerySnapshot,
+    pub query_srv: ResolverQuerySnapshot,
+    pub query_https: ResolverQuerySnapshot,
+    pub query_svcb: ResolverQuerySnapshot,
+    pub query_txt: ResolverQuerySnapshot,
     pub memory_a: ResolverMemorySnapshot,
     pub memory_aaaa: ResolverMemorySnapshot,
+    pub memory_srv: ResolverMemorySnapshot,
+    pub memory_https: ResolverMemorySnapshot,
+    pub memory_svcb: ResolverMemorySnapshot,
+    pub memory_txt: ResolverMemorySnapshot,
 }
@@ -52,6 +52,11 @@ impl BlendedRuntimeConfig {
             .max(1)
     }
 
+    /// Whether the runtime will be built with the current thread scheduler
+    pub fn is_current_thread(&self) -> bool {
+        matches!(self.thread_number, Some(0))
+    }
+
     pub fn set_thread_number(&mut self, num: usize) {
         self.thread_number = Some(num);
     }
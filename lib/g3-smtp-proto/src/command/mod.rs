@@ -30,11 +30,24 @@ pub enum CommandLineError {
     InvalidUtf8Command,
     #[error("invalid client domain/address field")]
     InvalidClientHost,
+    #[error("invalid reverse path")]
+    InvalidReversePath,
+    #[error("invalid forward path")]
+    InvalidForwardPath,
+    #[error("invalid chunk size")]
+    InvalidChunkSize,
+    #[error("unexpected parameter for command {0}")]
+    UnexpectedParameter(&'static str),
 }
 
 impl From<&CommandLineError> for ResponseEncoder {
-    fn from(_value: &CommandLineError) -> Self {
-        ResponseEncoder::SYNTAX_ERROR
+    fn from(value: &CommandLineError) -> Self {
+        match value {
+            CommandLineError::NoTrailingSequence | CommandLineError::InvalidUtf8Command => {
+                ResponseEncoder::SYNTAX_ERROR
+            }
+            _ => ResponseEncoder::SYNTAX_ERROR_IN_PARAMETERS,
+        }
     }
 }
 
@@ -43,11 +56,25 @@ pub enum Command {
     QUIT,
     ExtendHello(Host),
     Hello(Host),
+    StartTls,
+    Auth,
+    /// MAIL FROM, with the reverse path set. The null reverse path will be an empty string
+    MailFrom(String),
+    /// RCPT TO, with the forward path set
+    Recipient(String),
+    Data,
+    /// BDAT without the LAST parameter, with the chunk size set
+    BinaryData(usize),
+    /// BDAT with the LAST parameter, with the chunk size set
+    LastBinaryData(usize),
+    Reset,
     Unknown(String),
 }
 
 impl Command {
     pub const MAX_LINE_SIZE: usize = 512;
+    /// max line size for client responses in the AUTH exchange, see RFC 4954
+    pub const MAX_CONTINUE_LINE_SIZE: usize = 12288;
 
     pub fn parse_line(line: &[u8]) -> Result<Self, CommandLineError> {
         let line = line
@@ -59,16 +86,34 @@ impl Command {
             let cmd =
                 str::from_utf8(&line[0..p]).map_err(|_| CommandLineError::InvalidUtf8Command)?;
             let upper_cmd = cmd.to_uppercase();
+            let param = trim_start_space(&line[p + 1..]);
 
             match upper_cmd.as_bytes() {
                 b"EHLO" => {
-                    let host = hello_parse_host(&line[p..])?;
+                    let host = hello_parse_host(param)?;
                     Ok(Command::ExtendHello(host))
                 }
                 b"HELO" => {
-                    let host = hello_parse_host(&line[p..])?;
+                    let host = hello_parse_host(param)?;
                     Ok(Command::Hello(host))
                 }
+                b"AUTH" => Ok(Command::Auth),
+                b"MAIL" => {
+                    let path = parse_path_param(param, b"FROM:")
+                        .ok_or(CommandLineError::InvalidReversePath)?;
+                    Ok(Command::MailFrom(path))
+                }
+                b"RCPT" => {
+                    let path = parse_path_param(param, b"TO:")
+                        .ok_or(CommandLineError::InvalidForwardPath)?;
+                    if path.is_empty() {
+                        return Err(CommandLineError::InvalidForwardPath);
+                    }
+                    Ok(Command::Recipient(path))
+                }
+                b"BDAT" => bdat_parse_param(param),
+                b"DATA" => Err(CommandLineError::UnexpectedParameter("DATA")),
+                b"STARTTLS" => Err(CommandLineError::UnexpectedParameter("STARTTLS")),
                 _ => Ok(Command::Unknown(upper_cmd)),
             }
         } else {
@@ -78,12 +123,22 @@ impl Command {
 
             match upper_cmd.as_bytes() {
                 b"QUIT" => Ok(Command::QUIT),
+                b"STARTTLS" => Ok(Command::StartTls),
+                b"DATA" => Ok(Command::Data),
+                b"RSET" => Ok(Command::Reset),
                 _ => Ok(Command::Unknown(upper_cmd)),
             }
         }
     }
 }
 
+fn trim_start_space(buf: &[u8]) -> &[u8] {
+    match buf.iter().position(|c| *c != b' ') {
+        Some(p) => &buf[p..],
+        None => &[],
+    }
+}
+
 fn hello_parse_host(msg: &[u8]) -> Result<Host, CommandLineError> {
     let host_b = match memchr::memchr(b' ', msg) {
         Some(p) => &msg[..p],
@@ -91,3 +146,107 @@ fn hello_parse_host(msg: &[u8]) -> Result<Host, CommandLineError> {
     };
     Host::parse_smtp_host_address(host_b).ok_or(CommandLineError::InvalidClientHost)
 }
+
+/// parse the path in `FROM:<path> [params]` or `TO:<path> [params]`
+fn parse_path_param(param: &[u8], prefix: &[u8]) -> Option<String> {
+    if param.len() < prefix.len() || !param[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let left = trim_start_space(&param[prefix.len()..]);
+    let path = if let Some(left) = left.strip_prefix(b"<") {
+        let p = memchr::memchr(b'>', left)?;
+        &left[..p]
+    } else {
+        // some clients may not send the angle brackets
+        let path = match memchr::memchr(b' ', left) {
+            Some(p) => &left[..p],
+            None => left,
+        };
+        if path.is_empty() {
+            return None;
+        }
+        path
+    };
+    str::from_utf8(path).ok().map(|s| s.to_string())
+}
+
+fn bdat_parse_param(param: &[u8]) -> Result<Command, CommandLineError> {
+    let param = str::from_utf8(param).map_err(|_| CommandLineError::InvalidChunkSize)?;
+    let mut iter = param.split_ascii_whitespace();
+    let size = iter
+        .next()
+        .ok_or(CommandLineError::InvalidChunkSize)?
+        .parse::<usize>()
+        .map_err(|_| CommandLineError::InvalidChunkSize)?;
+    match iter.next() {
+        Some(s) => {
+            if !s.eq_ignore_ascii_case("LAST") || iter.next().is_some() {
+                return Err(CommandLineError::UnexpectedParameter("BDAT"));
+            }
+            Ok(Command::LastBinaryData(size))
+        }
+        None => Ok(Command::BinaryData(size)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hello() {
+        let cmd = Command::parse_line(b"EHLO bar.com\r\n").unwrap();
+        assert_eq!(
+            cmd,
+            Command::ExtendHello(Host::Domain("bar.com".to_string()))
+        );
+
+        let cmd = Command::parse_line(b"helo [192.0.2.1]\r\n").unwrap();
+        assert_eq!(cmd, Command::Hello(Host::Ip("192.0.2.1".parse().unwrap())));
+    }
+
+    #[test]
+    fn mail_from() {
+        let cmd = Command::parse_line(b"MAIL FROM:<Smith@bar.com>\r\n").unwrap();
+        assert_eq!(cmd, Command::MailFrom("Smith@bar.com".to_string()));
+
+        let cmd = Command::parse_line(b"mail from: <Smith@bar.com> SIZE=1000\r\n").unwrap();
+        assert_eq!(cmd, Command::MailFrom("Smith@bar.com".to_string()));
+
+        let cmd = Command::parse_line(b"MAIL FROM:<>\r\n").unwrap();
+        assert_eq!(cmd, Command::MailFrom(String::new()));
+
+        assert!(Command::parse_line(b"MAIL TO:<Smith@bar.com>\r\n").is_err());
+    }
+
+    #[test]
+    fn rcpt_to() {
+        let cmd = Command::parse_line(b"RCPT TO:<Jones@foo.com>\r\n").unwrap();
+        assert_eq!(cmd, Command::Recipient("Jones@foo.com".to_string()));
+
+        let cmd = Command::parse_line(b"RCPT TO:Jones@foo.com NOTIFY=NEVER\r\n").unwrap();
+        assert_eq!(cmd, Command::Recipient("Jones@foo.com".to_string()));
+
+        assert!(Command::parse_line(b"RCPT TO:<>\r\n").is_err());
+    }
+
+    #[test]
+    fn data() {
+        let cmd = Command::parse_line(b"DATA\r\n").unwrap();
+        assert_eq!(cmd, Command::Data);
+
+        assert!(Command::parse_line(b"DATA 1\r\n").is_err());
+    }
+
+    #[test]
+    fn bdat() {
+        let cmd = Command::parse_line(b"BDAT 86999\r\n").unwrap();
+        assert_eq!(cmd, Command::BinaryData(86999));
+
+        let cmd = Command::parse_line(b"BDAT 1000 LAST\r\n").unwrap();
+        assert_eq!(cmd, Command::LastBinaryData(1000));
+
+        assert!(Command::parse_line(b"BDAT 1000 FIRST\r\n").is_err());
+        assert!(Command::parse_line(b"BDAT LAST\r\n").is_err());
+    }
+}
@@ -0,0 +1,335 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TextState {
+    LineStart,
+    Normal,
+    CarriageReturn,
+    DotAtLineStart,
+    DotCarriageReturn,
+    End,
+}
+
+struct TextDataDetector {
+    decode: bool,
+    state: TextState,
+}
+
+impl TextDataDetector {
+    fn new(decode: bool) -> Self {
+        TextDataDetector {
+            decode,
+            state: TextState::LineStart,
+        }
+    }
+
+    /// feed the input data, and return the consumed input size and the produced output size
+    fn feed(&mut self, input: &[u8], output: &mut [u8]) -> (usize, usize) {
+        let mut consumed = 0;
+        let mut produced = 0;
+
+        while consumed < input.len() && produced < output.len() {
+            if self.state == TextState::End {
+                break;
+            }
+
+            if self.state == TextState::Normal {
+                // fast path for the normal content
+                let left = &input[consumed..];
+                let max = left.len().min(output.len() - produced);
+                let len = memchr::memchr(b'\r', &left[..max]).unwrap_or(max);
+                if len > 0 {
+                    output[produced..produced + len].copy_from_slice(&left[..len]);
+                    consumed += len;
+                    produced += len;
+                    continue;
+                }
+            }
+
+            let b = input[consumed];
+            let (next, consume, out) = self.step(b);
+            self.state = next;
+            if consume {
+                consumed += 1;
+            }
+            if let Some(o) = out {
+                output[produced] = o;
+                produced += 1;
+            }
+        }
+
+        (consumed, produced)
+    }
+
+    fn step(&self, b: u8) -> (TextState, bool, Option<u8>) {
+        match self.state {
+            TextState::LineStart => match b {
+                b'.' => {
+                    if self.decode {
+                        (TextState::DotAtLineStart, true, None)
+                    } else {
+                        (TextState::DotAtLineStart, true, Some(b))
+                    }
+                }
+                b'\r' => (TextState::CarriageReturn, true, Some(b)),
+                _ => (TextState::Normal, true, Some(b)),
+            },
+            TextState::Normal => match b {
+                b'\r' => (TextState::CarriageReturn, true, Some(b)),
+                _ => (TextState::Normal, true, Some(b)),
+            },
+            TextState::CarriageReturn => match b {
+                b'\n' => (TextState::LineStart, true, Some(b)),
+                b'\r' => (TextState::CarriageReturn, true, Some(b)),
+                _ => (TextState::Normal, true, Some(b)),
+            },
+            TextState::DotAtLineStart => match b {
+                b'\r' => {
+                    if self.decode {
+                        (TextState::DotCarriageReturn, true, None)
+                    } else {
+                        (TextState::DotCarriageReturn, true, Some(b))
+                    }
+                }
+                _ => (TextState::Normal, true, Some(b)),
+            },
+            TextState::DotCarriageReturn => match b {
+                b'\n' => {
+                    if self.decode {
+                        (TextState::End, true, None)
+                    } else {
+                        (TextState::End, true, Some(b))
+                    }
+                }
+                _ => {
+                    // not the ending sequence, handle the byte again in CR state
+                    if self.decode {
+                        (TextState::CarriageReturn, false, Some(b'\r'))
+                    } else {
+                        (TextState::CarriageReturn, false, None)
+                    }
+                }
+            },
+            TextState::End => (TextState::End, false, None),
+        }
+    }
+
+    #[inline]
+    fn finished(&self) -> bool {
+        self.state == TextState::End
+    }
+}
+
+/// Reader for the mail data after the DATA command, see RFC 5321 Section 4.5.2.
+///
+/// EOF will be returned after the ending `<CRLF>.<CRLF>` sequence.
+pub struct TextDataReader<'a, R> {
+    inner: &'a mut R,
+    detector: TextDataDetector,
+    read_size: u64,
+}
+
+impl<'a, R> TextDataReader<'a, R> {
+    /// The raw data, including the ending sequence, will be returned.
+    pub fn new(inner: &'a mut R) -> Self {
+        TextDataReader {
+            inner,
+            detector: TextDataDetector::new(false),
+            read_size: 0,
+        }
+    }
+
+    /// The transparency dots will be removed, and the ending line will not be returned.
+    pub fn new_decoded(inner: &'a mut R) -> Self {
+        TextDataReader {
+            inner,
+            detector: TextDataDetector::new(true),
+            read_size: 0,
+        }
+    }
+
+    #[inline]
+    pub fn finished(&self) -> bool {
+        self.detector.finished()
+    }
+
+    /// the size of the raw data that has been read
+    #[inline]
+    pub fn read_size(&self) -> u64 {
+        self.read_size
+    }
+}
+
+impl<'a, R> AsyncRead for TextDataReader<'a, R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+
+        while !me.detector.finished() && buf.remaining() > 0 {
+            let data = ready!(Pin::new(&mut *me.inner).poll_fill_buf(cx))?;
+            if data.is_empty() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed before the end of mail data",
+                )));
+            }
+
+            let (consumed, produced) = me.detector.feed(data, buf.initialize_unfilled());
+            Pin::new(&mut *me.inner).consume(consumed);
+            me.read_size += consumed as u64;
+            if produced > 0 {
+                buf.advance(produced);
+                break;
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Encoder for the mail data to be sent after the DATA command.
+///
+/// The transparency dots will be added, see RFC 5321 Section 4.5.2.
+pub struct TextDataEncoder {
+    tail: [u8; 2],
+}
+
+impl Default for TextDataEncoder {
+    fn default() -> Self {
+        TextDataEncoder {
+            tail: [b'\r', b'\n'],
+        }
+    }
+}
+
+impl TextDataEncoder {
+    pub fn encode(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        let mut left = input;
+        while !left.is_empty() {
+            if self.tail[1] == b'\n' && left[0] == b'.' {
+                output.push(b'.');
+            }
+            let len = match memchr::memchr(b'\n', left) {
+                Some(p) => p + 1,
+                None => left.len(),
+            };
+            output.extend_from_slice(&left[..len]);
+            if len >= 2 {
+                self.tail = [left[len - 2], left[len - 1]];
+            } else {
+                self.tail = [self.tail[1], left[0]];
+            }
+            left = &left[len..];
+        }
+    }
+
+    /// add the ending sequence
+    pub fn finish(&self, output: &mut Vec<u8>) {
+        if self.tail != *b"\r\n" {
+            output.extend_from_slice(b"\r\n");
+        }
+        output.extend_from_slice(b".\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect_all(data: &[u8], decode: bool) -> (usize, Vec<u8>, bool) {
+        let mut detector = TextDataDetector::new(decode);
+        let mut output = Vec::new();
+        let mut consumed = 0;
+        // feed byte by byte to test the state transition
+        while consumed < data.len() && !detector.finished() {
+            let mut buf = [0u8; 1];
+            let (nc, np) = detector.feed(&data[consumed..consumed + 1], &mut buf);
+            consumed += nc;
+            output.extend_from_slice(&buf[..np]);
+        }
+        (consumed, output, detector.finished())
+    }
+
+    #[test]
+    fn detect_raw() {
+        let data = b"Subject: test\r\n\r\n..line\r\nbody\r\n.\r\nQUIT\r\n";
+        let (consumed, output, finished) = detect_all(data, false);
+        assert!(finished);
+        assert_eq!(consumed, data.len() - 6);
+        assert_eq!(output.as_slice(), &data[..consumed]);
+
+        let mut detector = TextDataDetector::new(false);
+        let mut buf = [0u8; 64];
+        let (nc, np) = detector.feed(data, &mut buf);
+        assert!(detector.finished());
+        assert_eq!(nc, consumed);
+        assert_eq!(&buf[..np], &data[..consumed]);
+    }
+
+    #[test]
+    fn detect_decoded() {
+        let data = b"Subject: test\r\n\r\n..line\r\n.\rbody\r\n.\r\nQUIT\r\n";
+        let (consumed, output, finished) = detect_all(data, true);
+        assert!(finished);
+        assert_eq!(consumed, data.len() - 6);
+        assert_eq!(output, b"Subject: test\r\n\r\n.line\r\n\rbody\r\n");
+
+        let mut detector = TextDataDetector::new(true);
+        let mut buf = [0u8; 64];
+        let (nc, np) = detector.feed(data, &mut buf);
+        assert!(detector.finished());
+        assert_eq!(nc, consumed);
+        assert_eq!(&buf[..np], output.as_slice());
+    }
+
+    #[test]
+    fn detect_empty() {
+        let (consumed, output, finished) = detect_all(b".\r\n", true);
+        assert!(finished);
+        assert_eq!(consumed, 3);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn encode() {
+        let mut encoder = TextDataEncoder::default();
+        let mut output = Vec::new();
+        encoder.encode(b".line1\r", &mut output);
+        encoder.encode(b"\n.", &mut output);
+        encoder.encode(b"line2", &mut output);
+        encoder.finish(&mut output);
+        assert_eq!(output, b"..line1\r\n..line2\r\n.\r\n");
+
+        let mut encoder = TextDataEncoder::default();
+        let mut output = Vec::new();
+        encoder.encode(b"line\r\n", &mut output);
+        encoder.finish(&mut output);
+        assert_eq!(output, b"line\r\n.\r\n");
+    }
+}
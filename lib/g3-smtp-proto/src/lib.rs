@@ -15,4 +15,5 @@
  */
 
 pub mod command;
+pub mod io;
 pub mod response;
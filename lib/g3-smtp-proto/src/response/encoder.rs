@@ -35,6 +35,10 @@ macro_rules! impl_static {
 impl ResponseEncoder {
     impl_static!(SYNTAX_ERROR, "500 Syntax error\r\n");
     impl_static!(COMMAND_UNRECOGNIZED, "500 Command unrecognized\r\n");
+    impl_static!(
+        SYNTAX_ERROR_IN_PARAMETERS,
+        "501 Syntax error in parameters or arguments\r\n"
+    );
     impl_static!(COMMAND_LINE_TOO_LONG, "500 Line too long\r\n");
     impl_static!(COMMAND_NOT_IMPLEMENTED, "502 Command not implemented\r\n");
     impl_static!(BAD_SEQUENCE_OF_COMMANDS, "503 Bad sequence of commands\r\n");
@@ -42,6 +46,10 @@ impl ResponseEncoder {
         COMMAND_PARAMATER_NOT_IMPLEMENTED,
         "504 Command parameter not implemented\r\n"
     );
+    impl_static!(
+        START_MAIL_INPUT,
+        "354 Start mail input; end with <CRLF>.<CRLF>\r\n"
+    );
 
     pub fn local_service_closing(local_ip: IpAddr) -> Self {
         let msg = match local_ip {
@@ -59,6 +67,22 @@ impl ResponseEncoder {
         ResponseEncoder::Owned(msg)
     }
 
+    pub fn local_processing_error(local_ip: IpAddr, reason: &str) -> Self {
+        let msg = match local_ip {
+            IpAddr::V4(v4) => format!("451 [{v4}] local error in processing - {reason}\r\n"),
+            IpAddr::V6(v6) => format!("451 Ipv6:{v6} local error in processing - {reason}\r\n"),
+        };
+        ResponseEncoder::Owned(msg)
+    }
+
+    pub fn message_blocked(local_ip: IpAddr, reason: &str) -> Self {
+        let msg = match local_ip {
+            IpAddr::V4(v4) => format!("554 [{v4}] message blocked - {reason}\r\n"),
+            IpAddr::V6(v6) => format!("554 Ipv6:{v6} message blocked - {reason}\r\n"),
+        };
+        ResponseEncoder::Owned(msg)
+    }
+
     pub fn upstream_service_not_ready(local_ip: IpAddr, reason: &str) -> Self {
         let msg = match local_ip {
             IpAddr::V4(v4) => format!("554 [{v4}] upstream service not ready - {reason}\r\n"),
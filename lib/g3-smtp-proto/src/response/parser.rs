@@ -53,6 +53,8 @@ impl ReplyCode {
     def_const_code!(SERVICE_READY, b'2', b'2', b'0');
     def_const_code!(SERVICE_CLOSING, b'2', b'2', b'1');
     def_const_code!(OK, b'2', b'5', b'0');
+    def_const_code!(AUTH_CONTINUE, b'3', b'3', b'4');
+    def_const_code!(START_MAIL_INPUT, b'3', b'5', b'4');
 
     def_const_code!(BAD_SEQUENCE_OF_COMMANDS, b'5', b'0', b'3');
     def_const_code!(NO_SERVICE, b'5', b'5', b'4');
@@ -74,6 +76,11 @@ impl ReplyCode {
         self.a != 0
     }
 
+    /// 2yz, the requested action has been successfully completed
+    pub fn is_positive_completion(&self) -> bool {
+        self.a == b'2'
+    }
+
     pub fn as_u16(&self) -> u16 {
         (self.a - b'0') as u16 * 100 + (self.b - b'0') as u16 * 10 + (self.c - b'0') as u16
     }
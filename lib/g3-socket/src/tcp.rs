@@ -22,7 +22,7 @@ use std::os::unix::io::AsRawFd;
 use socket2::{Domain, SockAddr, Socket, TcpKeepalive, Type};
 use tokio::net::{TcpListener, TcpSocket};
 
-use g3_types::net::{TcpKeepAliveConfig, TcpListenConfig, TcpMiscSockOpts};
+use g3_types::net::{PortRange, TcpKeepAliveConfig, TcpListenConfig, TcpMiscSockOpts};
 
 #[cfg(target_os = "linux")]
 use super::sockopt::set_bind_address_no_port;
@@ -55,6 +55,62 @@ pub fn new_std_listener(config: &TcpListenConfig) -> io::Result<std::net::TcpLis
     Ok(std::net::TcpListener::from(socket))
 }
 
+/// create a listener that only need to accept a few connections, like the one used in socks tcp bind
+pub fn new_std_bind_listen(bind_ip: IpAddr) -> io::Result<(std::net::TcpListener, SocketAddr)> {
+    let socket = new_tcp_socket(AddressFamily::from(&bind_ip))?;
+    let bind_addr: SockAddr = SocketAddr::new(bind_ip, 0).into();
+    socket.bind(&bind_addr)?;
+    socket.listen(1)?;
+    let listener = std::net::TcpListener::from(socket);
+    let listen_addr = listener.local_addr()?;
+    Ok((listener, listen_addr))
+}
+
+/// the same as [`new_std_bind_listen`], but the port will be selected within the specified range
+pub fn new_std_in_range_bind_listen(
+    bind_ip: IpAddr,
+    port: PortRange,
+) -> io::Result<(std::net::TcpListener, SocketAddr)> {
+    let port_start = port.start();
+    let port_end = port.end();
+
+    debug_assert!(port_start <= port_end);
+
+    let socket = new_tcp_socket(AddressFamily::from(&bind_ip))?;
+
+    let mut bound = false;
+    // like what's has been done in new_std_in_range_bind_connect for udp
+    let tries = port.count().min(10);
+    for _i in 0..tries {
+        let port = fastrand::u16(port_start..=port_end);
+        let bind_addr: SockAddr = SocketAddr::new(bind_ip, port).into();
+        if socket.bind(&bind_addr).is_ok() {
+            bound = true;
+            break;
+        }
+    }
+    if !bound {
+        for port in port_start..=port_end {
+            let bind_addr: SockAddr = SocketAddr::new(bind_ip, port).into();
+            if socket.bind(&bind_addr).is_ok() {
+                bound = true;
+                break;
+            }
+        }
+    }
+    if !bound {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            "no port can be selected within specified range",
+        ));
+    }
+
+    socket.listen(1)?;
+    let listener = std::net::TcpListener::from(socket);
+    let listen_addr = listener.local_addr()?;
+    Ok((listener, listen_addr))
+}
+
 pub fn new_std_socket_to(
     peer_ip: IpAddr,
     bind_ip: Option<IpAddr>,
@@ -126,3 +182,52 @@ pub fn new_socket_to(
     let socket = new_std_socket_to(peer_ip, bind_ip, keepalive, misc_opts, default_set_nodelay)?;
     Ok(TcpSocket::from_std_stream(socket))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn bind_listen() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let (listener, listen_addr) = new_std_bind_listen(ip).unwrap();
+        assert_eq!(listen_addr.ip(), ip);
+        assert_ne!(listen_addr.port(), 0);
+        assert_eq!(listener.local_addr().unwrap(), listen_addr);
+
+        let _stream = std::net::TcpStream::connect(listen_addr).unwrap();
+    }
+
+    #[test]
+    fn bind_listen_in_range() {
+        let port_start = 61000;
+        let port_end = 65000;
+        let range = PortRange::new(port_start, port_end);
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let loop_len = 100usize;
+        let mut v = Vec::<std::net::TcpListener>::with_capacity(loop_len);
+        for _i in 0..loop_len {
+            let (listener, listen_addr) = new_std_in_range_bind_listen(ip, range).unwrap();
+            let port_real = listen_addr.port();
+            assert!(port_real >= port_start);
+            assert!(port_real <= port_end);
+            v.push(listener);
+        }
+    }
+
+    #[test]
+    fn bind_listen_in_single_port_range() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let (listener, listen_addr) = new_std_bind_listen(ip).unwrap();
+        let port = listen_addr.port();
+        drop(listener);
+
+        let range = PortRange::new(port, port);
+        let (_listener, listen_addr) = new_std_in_range_bind_listen(ip, range).unwrap();
+        assert_eq!(listen_addr.port(), port);
+
+        let err = new_std_in_range_bind_listen(ip, range).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrNotAvailable);
+    }
+}
@@ -80,7 +80,7 @@ impl SocksV4Reply {
 
         let ip_bytes: [u8; 4] = buf[4..8].try_into().unwrap();
 
-        let port = ((buf[3] as u16) << 8) + (buf[4] as u16);
+        let port = u16::from_be_bytes([buf[2], buf[3]]);
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::from(ip_bytes)), port);
 
         Ok(SocksV4Reply::new(code, addr))
@@ -90,7 +90,12 @@ impl SocksV4Reply {
     where
         W: AsyncWrite + Unpin,
     {
-        let buf: [u8; 8] = [0, self.code(), 0, 0, 0, 0, 0, 0];
+        let mut buf: [u8; 8] = [0, self.code(), 0, 0, 0, 0, 0, 0];
+        if let SocksV4Reply::RequestGranted(SocketAddr::V4(addr)) = self {
+            // only ipv4 address can be carried in the reply
+            buf[2..4].copy_from_slice(&addr.port().to_be_bytes());
+            buf[4..8].copy_from_slice(&addr.ip().octets());
+        }
         clt_w.write_all(&buf).await?;
         clt_w.flush().await?;
         Ok(())
@@ -100,3 +105,39 @@ impl SocksV4Reply {
         SocksV4Reply::RequestGranted(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv6Addr;
+
+    async fn encode(reply: SocksV4Reply) -> Vec<u8> {
+        let mut buf = Vec::new();
+        reply.send(&mut buf).await.unwrap();
+        buf
+    }
+
+    #[tokio::test]
+    async fn connect_granted() {
+        let buf = encode(SocksV4Reply::request_granted()).await;
+        assert_eq!(buf, [0x00, 90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn bind_granted() {
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), 1080);
+        let buf = encode(SocksV4Reply::RequestGranted(addr)).await;
+        assert_eq!(buf, [0x00, 90, 0x04, 0x38, 192, 0, 2, 1]);
+
+        // ipv6 address can not be carried
+        let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 1080);
+        let buf = encode(SocksV4Reply::RequestGranted(addr)).await;
+        assert_eq!(buf, [0x00, 90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn rejected() {
+        let buf = encode(SocksV4Reply::RequestRejectedOrFailed).await;
+        assert_eq!(buf, [0x00, 91, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    }
+}
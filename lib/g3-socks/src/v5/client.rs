@@ -22,7 +22,8 @@ use g3_types::net::{SocksAuth, UpstreamAddr};
 
 use super::{auth, Socks5Reply, Socks5Request, SocksAuthMethod, SocksCommand, SocksConnectError};
 
-async fn socks5_login<R, W>(
+/// negotiate the auth method with a socks5 proxy and do the login if needed
+pub async fn socks5_login<R, W>(
     reader: &mut R,
     writer: &mut W,
     auth: &SocksAuth,
@@ -168,3 +168,46 @@ impl Socks5Reply {
         clt_w.flush().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn encode(reply: Socks5Reply) -> Vec<u8> {
+        let mut buf = Vec::new();
+        reply.send(&mut buf).await.unwrap();
+        buf
+    }
+
+    #[tokio::test]
+    async fn bind_succeeded_v4() {
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), 1080);
+        let buf = encode(Socks5Reply::Succeeded(addr)).await;
+        assert_eq!(buf, [0x05, 0x00, 0x00, 0x01, 192, 0, 2, 1, 0x04, 0x38]);
+    }
+
+    #[tokio::test]
+    async fn bind_succeeded_v6() {
+        let ip6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let addr = SocketAddr::new(IpAddr::V6(ip6), 1080);
+        let buf = encode(Socks5Reply::Succeeded(addr)).await;
+        let mut expected = vec![0x05, 0x00, 0x00, 0x04];
+        expected.extend_from_slice(&ip6.octets());
+        expected.extend_from_slice(&[0x04, 0x38]);
+        assert_eq!(buf, expected);
+
+        // ipv4 mapped ipv6 address should be sent as ipv4
+        let addr = SocketAddr::new(
+            IpAddr::V6(Ipv4Addr::new(192, 0, 2, 1).to_ipv6_mapped()),
+            1080,
+        );
+        let buf = encode(Socks5Reply::Succeeded(addr)).await;
+        assert_eq!(buf, [0x05, 0x00, 0x00, 0x01, 192, 0, 2, 1, 0x04, 0x38]);
+    }
+
+    #[tokio::test]
+    async fn failure() {
+        let buf = encode(Socks5Reply::CommandNotSupported).await;
+        assert_eq!(buf, [0x05, 0x07, 0x00, 0x01, 0, 0, 0, 0, 0, 0]);
+    }
+}
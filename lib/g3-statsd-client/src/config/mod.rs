@@ -20,11 +20,12 @@ use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 use std::os::unix::net::UnixDatagram;
 #[cfg(unix)]
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use g3_types::metrics::MetricsName;
 
-use crate::{StatsdClient, StatsdMetricsSink};
+use crate::{StatsdClient, StatsdMetricsSink, StatsdMetricsStore};
 
 const UDP_DEFAULT_PORT: u16 = 8125;
 
@@ -44,8 +45,9 @@ impl Default for StatsdBackend {
 
 #[derive(Debug, Clone)]
 pub struct StatsdClientConfig {
-    backend: StatsdBackend,
+    backend: Option<StatsdBackend>,
     prefix: MetricsName,
+    store: Option<Arc<StatsdMetricsStore>>,
     pub emit_duration: Duration,
 }
 
@@ -58,14 +60,29 @@ impl Default for StatsdClientConfig {
 impl StatsdClientConfig {
     pub fn with_prefix(prefix: MetricsName) -> Self {
         StatsdClientConfig {
-            backend: StatsdBackend::default(),
+            backend: Some(StatsdBackend::default()),
             prefix,
+            store: None,
             emit_duration: Duration::from_millis(200),
         }
     }
 
     pub fn set_backend(&mut self, target: StatsdBackend) {
-        self.backend = target;
+        self.backend = Some(target);
+    }
+
+    /// Do not send metrics to any statsd backend, useful if only the store is needed
+    pub fn disable_backend(&mut self) {
+        self.backend = None;
+    }
+
+    /// Also record all metrics to the in-memory store
+    pub fn set_store(&mut self, store: Arc<StatsdMetricsStore>) {
+        self.store = Some(store);
+    }
+
+    pub fn store(&self) -> Option<&Arc<StatsdMetricsStore>> {
+        self.store.as_ref()
     }
 
     pub fn set_prefix(&mut self, prefix: MetricsName) {
@@ -73,8 +90,8 @@ impl StatsdClientConfig {
     }
 
     pub fn build(&self) -> io::Result<StatsdClient> {
-        let sink = match &self.backend {
-            StatsdBackend::Udp(addr, bind) => {
+        let mut sink = match &self.backend {
+            Some(StatsdBackend::Udp(addr, bind)) => {
                 let bind_ip = bind.unwrap_or_else(|| match addr {
                     SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
                     SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
@@ -83,11 +100,26 @@ impl StatsdClientConfig {
                 StatsdMetricsSink::udp_with_capacity(*addr, socket, 1024)
             }
             #[cfg(unix)]
-            StatsdBackend::Unix(path) => {
+            Some(StatsdBackend::Unix(path)) => {
                 let socket = UnixDatagram::unbound()?;
                 StatsdMetricsSink::unix_with_capacity(path.clone(), socket, 4096)
             }
+            None => {
+                let Some(store) = &self.store else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "neither statsd backend nor metrics store is set",
+                    ));
+                };
+                return Ok(StatsdClient::new(
+                    self.prefix.clone(),
+                    StatsdMetricsSink::store_only(store.clone(), 4096),
+                ));
+            }
         };
+        if let Some(store) = &self.store {
+            sink.set_store(store.clone());
+        }
 
         Ok(StatsdClient::new(self.prefix.clone(), sink))
     }
@@ -23,5 +23,8 @@ pub use client::StatsdClient;
 mod tag;
 pub use tag::StatsdTagGroup;
 
+mod store;
+pub use store::{StatsdMetricKind, StatsdMetricsStore};
+
 mod config;
 pub use config::{StatsdBackend, StatsdClientConfig};
@@ -22,9 +22,12 @@ use std::os::unix::net::UnixDatagram;
 use std::path::PathBuf;
 #[cfg(test)]
 use std::rc::Rc;
+use std::sync::Arc;
 #[cfg(test)]
 use std::sync::Mutex;
 
+use crate::StatsdMetricsStore;
+
 #[cfg(test)]
 mod buf;
 #[cfg(test)]
@@ -61,7 +64,8 @@ impl MetricsSinkIo {
 pub(crate) struct StatsdMetricsSink {
     cache_size: usize,
     buf: Vec<u8>,
-    io: MetricsSinkIo,
+    io: Option<MetricsSinkIo>,
+    store: Option<Arc<StatsdMetricsStore>>,
 }
 
 impl StatsdMetricsSink {
@@ -70,7 +74,8 @@ impl StatsdMetricsSink {
         StatsdMetricsSink {
             cache_size,
             buf: Vec::with_capacity(cache_size),
-            io: MetricsSinkIo::Buf(BufMetricsSink::new(buf)),
+            io: Some(MetricsSinkIo::Buf(BufMetricsSink::new(buf))),
+            store: None,
         }
     }
 
@@ -82,7 +87,8 @@ impl StatsdMetricsSink {
         StatsdMetricsSink {
             cache_size,
             buf: Vec::with_capacity(cache_size),
-            io: MetricsSinkIo::Udp(UdpMetricsSink::new(addr, socket)),
+            io: Some(MetricsSinkIo::Udp(UdpMetricsSink::new(addr, socket))),
+            store: None,
         }
     }
 
@@ -95,10 +101,24 @@ impl StatsdMetricsSink {
         StatsdMetricsSink {
             cache_size,
             buf: Vec::with_capacity(cache_size),
-            io: MetricsSinkIo::Unix(UnixMetricsSink::new(path, socket)),
+            io: Some(MetricsSinkIo::Unix(UnixMetricsSink::new(path, socket))),
+            store: None,
         }
     }
 
+    pub(crate) fn store_only(store: Arc<StatsdMetricsStore>, cache_size: usize) -> Self {
+        StatsdMetricsSink {
+            cache_size,
+            buf: Vec::with_capacity(cache_size),
+            io: None,
+            store: Some(store),
+        }
+    }
+
+    pub(crate) fn set_store(&mut self, store: Arc<StatsdMetricsStore>) {
+        self.store = Some(store);
+    }
+
     pub(super) fn emit<F>(&mut self, msg_len: usize, format: F) -> io::Result<()>
     where
         F: Fn(&mut Vec<u8>),
@@ -123,7 +143,19 @@ impl StatsdMetricsSink {
     }
 
     fn flush_buf(&mut self) -> io::Result<()> {
-        self.io.send_msg(&self.buf)?;
+        if let Some(store) = &self.store {
+            store.record(&self.buf);
+            // the buf should not be recorded again even if the send failed
+            let r = match &self.io {
+                Some(io) => io.send_msg(&self.buf).map(|_| ()),
+                None => Ok(()),
+            };
+            self.buf.clear();
+            return r;
+        }
+        if let Some(io) = &self.io {
+            io.send_msg(&self.buf)?;
+        }
         self.buf.clear();
         Ok(())
     }
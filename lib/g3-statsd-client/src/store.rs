@@ -0,0 +1,343 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsdMetricKind {
+    Counter,
+    Gauge,
+}
+
+impl StatsdMetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StatsdMetricKind::Counter => "counter",
+            StatsdMetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+struct MetricSample {
+    value: f64,
+    created: SystemTime,
+    updated: Instant,
+}
+
+struct MetricFamily {
+    kind: StatsdMetricKind,
+    series: BTreeMap<Vec<(String, String)>, MetricSample>,
+}
+
+/// In-memory copy of all metrics sent through the statsd clients,
+/// which can be rendered in OpenMetrics text format or be visited
+/// by other exporters.
+///
+/// Counters are accumulated from the emitted diff values and gauges
+/// keep the last value. Series not updated within `series_expire`
+/// will be dropped at the next visit.
+pub struct StatsdMetricsStore {
+    series_expire: Duration,
+    families: Mutex<BTreeMap<String, MetricFamily>>,
+}
+
+impl StatsdMetricsStore {
+    pub fn new(series_expire: Duration) -> Self {
+        StatsdMetricsStore {
+            series_expire,
+            families: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub(crate) fn record(&self, buf: &[u8]) {
+        let now = Instant::now();
+        let mut families = self.families.lock().unwrap();
+        for line in buf.split(|c| *c == b'\n') {
+            let Ok(line) = std::str::from_utf8(line) else {
+                continue;
+            };
+            let Some((name, value, kind, tags)) = parse_line(line) else {
+                continue;
+            };
+
+            let family = match families.get_mut(name) {
+                Some(family) => family,
+                None => families.entry(name.to_string()).or_insert(MetricFamily {
+                    kind,
+                    series: BTreeMap::new(),
+                }),
+            };
+            if family.kind != kind {
+                continue;
+            }
+
+            let tags = parse_tags(tags);
+            match family.series.get_mut(&tags) {
+                Some(sample) => {
+                    match kind {
+                        StatsdMetricKind::Counter => sample.value += value,
+                        StatsdMetricKind::Gauge => sample.value = value,
+                    }
+                    sample.updated = now;
+                }
+                None => {
+                    family.series.insert(
+                        tags,
+                        MetricSample {
+                            value,
+                            created: SystemTime::now(),
+                            updated: now,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Visit all the live series, with the metric name, the tags,
+    /// the time of the first record and the current value
+    pub fn foreach_series<F>(&self, mut f: F)
+    where
+        F: FnMut(&str, StatsdMetricKind, &[(String, String)], SystemTime, f64),
+    {
+        let mut families = self.families.lock().unwrap();
+        self.drop_expired(&mut families);
+        for (name, family) in families.iter() {
+            for (tags, sample) in family.series.iter() {
+                f(name, family.kind, tags, sample.created, sample.value);
+            }
+        }
+    }
+
+    /// Render all the live series in OpenMetrics text format
+    pub fn render_openmetrics(&self) -> String {
+        let mut families = self.families.lock().unwrap();
+        self.drop_expired(&mut families);
+
+        let mut out = String::with_capacity(4096);
+        for (name, family) in families.iter() {
+            let name = family_name(name, family.kind);
+            let _ = writeln!(out, "# TYPE {name} {}", family.kind.as_str());
+            let suffix = match family.kind {
+                StatsdMetricKind::Counter => "_total",
+                StatsdMetricKind::Gauge => "",
+            };
+            for (tags, sample) in family.series.iter() {
+                out.push_str(&name);
+                out.push_str(suffix);
+                push_labels(&mut out, tags);
+                let _ = writeln!(out, " {}", sample.value);
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+
+    fn drop_expired(&self, families: &mut BTreeMap<String, MetricFamily>) {
+        if self.series_expire.is_zero() {
+            return;
+        }
+        let now = Instant::now();
+        families.retain(|_, family| {
+            family
+                .series
+                .retain(|_, s| now.duration_since(s.updated) < self.series_expire);
+            !family.series.is_empty()
+        });
+    }
+}
+
+fn parse_line(line: &str) -> Option<(&str, f64, StatsdMetricKind, Option<&str>)> {
+    let (name, left) = line.split_once(':')?;
+    let mut parts = left.split('|');
+    let value = f64::from_str(parts.next()?).ok()?;
+    let kind = match parts.next()? {
+        "c" => StatsdMetricKind::Counter,
+        "g" => StatsdMetricKind::Gauge,
+        _ => return None,
+    };
+    let tags = parts.next().and_then(|s| s.strip_prefix('#'));
+    Some((name, value, kind, tags))
+}
+
+fn parse_tags(tags: Option<&str>) -> Vec<(String, String)> {
+    let Some(tags) = tags else {
+        return Vec::new();
+    };
+
+    let mut pairs: Vec<(String, String)> = tags
+        .split(',')
+        .filter_map(|tag| tag.split_once(':'))
+        .filter(|(k, _)| !k.is_empty())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    pairs.sort();
+    pairs
+}
+
+fn push_sanitized(out: &mut String, s: &str, allow_colon: bool) {
+    for (i, c) in s.chars().enumerate() {
+        if i == 0 && c.is_ascii_digit() {
+            out.push('_');
+        }
+        if c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':') {
+            out.push(c);
+        } else {
+            out.push('_');
+        }
+    }
+}
+
+fn family_name(name: &str, kind: StatsdMetricKind) -> String {
+    let mut s = String::with_capacity(name.len() + 1);
+    push_sanitized(&mut s, name, true);
+    if kind == StatsdMetricKind::Counter && s.ends_with("_total") {
+        s.truncate(s.len() - 6);
+    }
+    s
+}
+
+fn push_labels(s: &mut String, tags: &[(String, String)]) {
+    if tags.is_empty() {
+        return;
+    }
+
+    s.push('{');
+    for (i, (k, v)) in tags.iter().enumerate() {
+        if i > 0 {
+            s.push(',');
+        }
+        push_sanitized(s, k, false);
+        s.push_str("=\"");
+        for c in v.chars() {
+            match c {
+                '\\' => s.push_str("\\\\"),
+                '"' => s.push_str("\\\""),
+                '\n' => s.push_str("\\n"),
+                _ => s.push(c),
+            }
+        }
+        s.push('"');
+    }
+    s.push('}');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_accumulate() {
+        let store = StatsdMetricsStore::new(Duration::ZERO);
+        store.record(b"test.server.conn.total:2|c|#server:a,stat_id:1");
+        store.record(b"test.server.conn.total:3|c|#stat_id:1,server:a\ntest.count:1|c");
+
+        let text = store.render_openmetrics();
+        assert_eq!(
+            text,
+            "# TYPE test_count counter\n\
+             test_count_total 1\n\
+             # TYPE test_server_conn counter\n\
+             test_server_conn_total{server=\"a\",stat_id=\"1\"} 5\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn gauge_replace() {
+        let store = StatsdMetricsStore::new(Duration::ZERO);
+        store.record(b"test.alive:2|g|#online:y\ntest.ratio:0.5|g");
+        store.record(b"test.alive:1|g|#online:y");
+
+        let text = store.render_openmetrics();
+        assert_eq!(
+            text,
+            "# TYPE test_alive gauge\n\
+             test_alive{online=\"y\"} 1\n\
+             # TYPE test_ratio gauge\n\
+             test_ratio 0.5\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn label_escape() {
+        let store = StatsdMetricsStore::new(Duration::ZERO);
+        store.record(b"1st-gauge:7|g|#user:a\"b\\c,value_only,user-group:g");
+
+        let text = store.render_openmetrics();
+        assert_eq!(
+            text,
+            "# TYPE _1st_gauge gauge\n\
+             _1st_gauge{user=\"a\\\"b\\\\c\",user_group=\"g\"} 7\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn kind_conflict_and_invalid() {
+        let store = StatsdMetricsStore::new(Duration::ZERO);
+        store.record(b"test.a:1|g\ntest.a:1|c\ntest.b:x|c\ntest.c:1|ms");
+
+        let text = store.render_openmetrics();
+        assert_eq!(text, "# TYPE test_a gauge\ntest_a 1\n# EOF\n");
+    }
+
+    #[test]
+    fn visit_series() {
+        let store = StatsdMetricsStore::new(Duration::ZERO);
+        store.record(b"test.conn:2|c|#server:a,stat_id:1\ntest.alive:1|g");
+
+        let mut all = Vec::new();
+        store.foreach_series(|name, kind, tags, _, value| {
+            all.push((name.to_string(), kind, tags.to_vec(), value));
+        });
+        assert_eq!(
+            all,
+            vec![
+                (
+                    "test.alive".to_string(),
+                    StatsdMetricKind::Gauge,
+                    vec![],
+                    1.0
+                ),
+                (
+                    "test.conn".to_string(),
+                    StatsdMetricKind::Counter,
+                    vec![
+                        ("server".to_string(), "a".to_string()),
+                        ("stat_id".to_string(), "1".to_string())
+                    ],
+                    2.0
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn series_expire() {
+        let store = StatsdMetricsStore::new(Duration::from_millis(1));
+        store.record(b"test.a:1|g");
+        std::thread::sleep(Duration::from_millis(5));
+
+        let text = store.render_openmetrics();
+        assert_eq!(text, "# EOF\n");
+    }
+}
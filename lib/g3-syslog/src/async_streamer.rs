@@ -15,11 +15,12 @@
  */
 
 use std::cell::RefCell;
-use std::io::{self, Write};
+use std::collections::VecDeque;
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use flume::{Receiver, Sender, TrySendError};
+use flume::{Receiver, RecvTimeoutError, Sender, TrySendError};
 use log::warn;
 use slog::{Drain, OwnedKVList, Record};
 
@@ -117,33 +118,84 @@ struct AsyncIoThread {
 
 impl AsyncIoThread {
     fn run_to_end(self) {
-        let mut backend_container: Option<SyslogBackend> = self.build_backend();
-        let mut failed_instant = Instant::now();
-        while let Ok(s) = self.receiver.recv() {
-            if let Some(mut backend) = backend_container.take() {
-                if self.send_data(s, &mut backend).is_err() {
-                    self.stats.drop.add_peer_unreachable();
-                    if backend.need_reconnect() {
-                        failed_instant = Instant::now();
-                    } else {
-                        backend_container = Some(backend);
-                    }
+        let retry_queue_len = self.backend_builder.retry_queue_len();
+        let mut retry_queue: VecDeque<String> = VecDeque::with_capacity(retry_queue_len);
+        let mut backend_container: Option<SyslogBackend> = None;
+        let mut failed_count: u32 = 0;
+        let mut next_connect = Instant::now();
+
+        loop {
+            let msg = match self.receiver.recv_timeout(Duration::from_secs(1)) {
+                Ok(s) => Some(s),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            if backend_container.is_none() && Instant::now() >= next_connect {
+                backend_container = self.build_backend();
+                if backend_container.is_some() {
+                    failed_count = 0;
                 } else {
-                    backend_container = Some(backend);
+                    next_connect =
+                        Instant::now() + self.backend_builder.reconnect_delay(failed_count);
+                    failed_count = failed_count.saturating_add(1);
                 }
-            } else {
-                if failed_instant.elapsed() > Duration::from_secs(4) {
-                    // hard coded 4s for a minimal reconnect interval
-                    if let Some(mut backend) = self.build_backend() {
-                        if self.send_data(s, &mut backend).is_ok() {
-                            backend_container = Some(backend);
-                            continue;
-                        }
+            }
+
+            let Some(backend) = &mut backend_container else {
+                if let Some(s) = msg {
+                    self.push_retry(&mut retry_queue, retry_queue_len, s);
+                }
+                continue;
+            };
+
+            let mut sent = self.send_retry_queue(backend, &mut retry_queue);
+            if let Some(s) = msg {
+                if !sent {
+                    self.push_retry(&mut retry_queue, retry_queue_len, s);
+                } else if let Err(e) = self.send_data(&s, backend) {
+                    if backend.need_reconnect() {
+                        warn!("failed to send to syslog backend: {e:?}");
+                        self.push_retry(&mut retry_queue, retry_queue_len, s);
+                        sent = false;
+                    } else {
+                        self.stats.drop.add_peer_unreachable();
                     }
                 }
-                self.stats.drop.add_peer_unreachable();
             }
+            if !sent {
+                backend_container = None;
+                next_connect = Instant::now() + self.backend_builder.reconnect_delay(0);
+            }
+        }
+    }
+
+    fn push_retry(&self, retry_queue: &mut VecDeque<String>, max_len: usize, data: String) {
+        if max_len == 0 {
+            self.stats.drop.add_peer_unreachable();
+            return;
+        }
+        if retry_queue.len() >= max_len {
+            // drop the oldest one
+            retry_queue.pop_front();
+            self.stats.drop.add_peer_unreachable();
+        }
+        retry_queue.push_back(data);
+    }
+
+    fn send_retry_queue(
+        &self,
+        backend: &mut SyslogBackend,
+        retry_queue: &mut VecDeque<String>,
+    ) -> bool {
+        while let Some(data) = retry_queue.front() {
+            if let Err(e) = self.send_data(data, backend) {
+                warn!("failed to send queued data to syslog backend: {e:?}");
+                return false;
+            }
+            retry_queue.pop_front();
         }
+        true
     }
 
     fn build_backend(&self) -> Option<SyslogBackend> {
@@ -156,12 +208,10 @@ impl AsyncIoThread {
         }
     }
 
-    fn send_data(&self, data: String, backend: &mut SyslogBackend) -> io::Result<()> {
-        let size = data.len();
-        backend.write_all(data.as_bytes())?;
-        backend.flush()?;
+    fn send_data(&self, data: &str, backend: &mut SyslogBackend) -> io::Result<()> {
+        backend.send_msg(data.as_bytes())?;
         self.stats.io.add_passed();
-        self.stats.io.add_size(size);
+        self.stats.io.add_size(data.len());
         Ok(())
     }
 }
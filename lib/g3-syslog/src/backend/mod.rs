@@ -20,6 +20,11 @@ use std::net::{IpAddr, SocketAddr, UdpSocket};
 use std::os::unix::net::UnixDatagram;
 #[cfg(unix)]
 use std::path::PathBuf;
+use std::time::Duration;
+
+mod tcp;
+use tcp::SyslogStream;
+pub use tcp::SyslogTcpConfig;
 
 mod udp;
 #[cfg(unix)]
@@ -29,26 +34,22 @@ pub(super) enum SyslogBackend {
     Udp(UdpSocket),
     #[cfg(unix)]
     Unix(UnixDatagram),
+    Tcp(SyslogStream),
 }
 
 impl SyslogBackend {
     pub(super) fn need_reconnect(&self) -> bool {
-        false
+        matches!(self, SyslogBackend::Tcp(_))
     }
-}
 
-impl io::Write for SyslogBackend {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    pub(super) fn send_msg(&mut self, msg: &[u8]) -> io::Result<()> {
         match self {
-            SyslogBackend::Udp(s) => s.send(buf),
+            SyslogBackend::Udp(s) => s.send(msg).map(|_| ()),
             #[cfg(unix)]
-            SyslogBackend::Unix(s) => s.send(buf),
+            SyslogBackend::Unix(s) => s.send(msg).map(|_| ()),
+            SyslogBackend::Tcp(s) => s.send_msg(msg),
         }
     }
-
-    fn flush(&mut self) -> io::Result<()> {
-        Ok(())
-    }
 }
 
 #[derive(Clone, Debug)]
@@ -58,6 +59,8 @@ pub enum SyslogBackendBuilder {
     Unix(Option<PathBuf>),
     /// udp socket with optional bind ip and remote address
     Udp(Option<IpAddr>, SocketAddr),
+    /// tcp or tls stream with octet-counting framing
+    Tcp(SyslogTcpConfig),
 }
 
 #[cfg(unix)]
@@ -77,6 +80,27 @@ impl Default for SyslogBackendBuilder {
 }
 
 impl SyslogBackendBuilder {
+    /// max number of messages to hold while the backend is unreachable
+    pub(super) fn retry_queue_len(&self) -> usize {
+        match self {
+            SyslogBackendBuilder::Tcp(config) => config.retry_queue_len,
+            _ => 0,
+        }
+    }
+
+    /// delay before the next reconnect after `failed` continuous failures
+    pub(super) fn reconnect_delay(&self, failed: u32) -> Duration {
+        match self {
+            SyslogBackendBuilder::Tcp(config) => {
+                let delay = config
+                    .reconnect_min_delay
+                    .saturating_mul(1 << failed.min(16));
+                delay.min(config.reconnect_max_delay.max(config.reconnect_min_delay))
+            }
+            _ => Duration::from_secs(4),
+        }
+    }
+
     pub(super) fn build(&self) -> io::Result<SyslogBackend> {
         match self {
             #[cfg(unix)]
@@ -92,6 +116,10 @@ impl SyslogBackendBuilder {
                 let socket = udp::udp(*bind_ip, *server)?;
                 Ok(SyslogBackend::Udp(socket))
             }
+            SyslogBackendBuilder::Tcp(config) => {
+                let stream = config.connect()?;
+                Ok(SyslogBackend::Tcp(stream))
+            }
         }
     }
 }
@@ -0,0 +1,203 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::{ClientConnection, ServerName, StreamOwned};
+use socket2::{Domain, SockAddr, Socket, TcpKeepalive, Type};
+
+use g3_types::net::{RustlsClientConfig, TcpKeepAliveConfig};
+
+/// Config for syslog over tcp (RFC 6587) or tls (RFC 5425),
+/// the octet-counting framing method will be used for both
+#[derive(Clone)]
+pub struct SyslogTcpConfig {
+    server: SocketAddr,
+    bind_ip: Option<IpAddr>,
+    tcp_keepalive: TcpKeepAliveConfig,
+    tls_client: Option<RustlsClientConfig>,
+    tls_name: Option<ServerName>,
+    connect_timeout: Duration,
+    write_timeout: Duration,
+    pub(crate) reconnect_min_delay: Duration,
+    pub(crate) reconnect_max_delay: Duration,
+    pub(crate) retry_queue_len: usize,
+}
+
+impl fmt::Debug for SyslogTcpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyslogTcpConfig")
+            .field("server", &self.server)
+            .field("bind_ip", &self.bind_ip)
+            .field("tls", &self.tls_client.is_some())
+            .field("tls_name", &self.tls_name)
+            .finish()
+    }
+}
+
+impl Default for SyslogTcpConfig {
+    fn default() -> Self {
+        // 601 is the registered port for syslog-conn
+        SyslogTcpConfig::new(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 601))
+    }
+}
+
+impl SyslogTcpConfig {
+    pub fn new(server: SocketAddr) -> Self {
+        SyslogTcpConfig {
+            server,
+            bind_ip: None,
+            tcp_keepalive: TcpKeepAliveConfig::default_enabled(),
+            tls_client: None,
+            tls_name: None,
+            connect_timeout: Duration::from_secs(10),
+            write_timeout: Duration::from_secs(10),
+            reconnect_min_delay: Duration::from_secs(1),
+            reconnect_max_delay: Duration::from_secs(60),
+            retry_queue_len: 10,
+        }
+    }
+
+    pub fn set_server_addr(&mut self, addr: SocketAddr) {
+        self.server = addr;
+    }
+
+    pub fn set_bind_ip(&mut self, ip: IpAddr) {
+        self.bind_ip = Some(ip);
+    }
+
+    pub fn set_tcp_keepalive(&mut self, keepalive: TcpKeepAliveConfig) {
+        self.tcp_keepalive = keepalive;
+    }
+
+    pub fn set_tls_client(&mut self, tls_client: RustlsClientConfig) {
+        self.tls_client = Some(tls_client);
+    }
+
+    pub fn set_tls_name(&mut self, tls_name: ServerName) {
+        self.tls_name = Some(tls_name);
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+    }
+
+    pub fn set_write_timeout(&mut self, timeout: Duration) {
+        self.write_timeout = timeout;
+    }
+
+    pub fn set_reconnect_min_delay(&mut self, delay: Duration) {
+        self.reconnect_min_delay = delay;
+    }
+
+    pub fn set_reconnect_max_delay(&mut self, delay: Duration) {
+        self.reconnect_max_delay = delay;
+    }
+
+    pub fn set_retry_queue_len(&mut self, len: usize) {
+        self.retry_queue_len = len;
+    }
+
+    pub(super) fn connect(&self) -> io::Result<SyslogStream> {
+        let socket = Socket::new(Domain::for_address(self.server), Type::STREAM, None)?;
+        if let Some(ip) = self.bind_ip {
+            let addr: SockAddr = SocketAddr::new(ip, 0).into();
+            socket.bind(&addr)?;
+        }
+        if self.tcp_keepalive.is_enabled() {
+            let mut setting = TcpKeepalive::new().with_time(self.tcp_keepalive.idle_time());
+            if let Some(interval) = self.tcp_keepalive.probe_interval() {
+                setting = setting.with_interval(interval);
+            }
+            socket.set_tcp_keepalive(&setting)?;
+        }
+        socket.connect_timeout(&self.server.into(), self.connect_timeout)?;
+        let mut stream = TcpStream::from(socket);
+        stream.set_write_timeout(Some(self.write_timeout))?;
+
+        let Some(tls_client) = &self.tls_client else {
+            return Ok(SyslogStream::Tcp(stream));
+        };
+
+        let tls_name = self
+            .tls_name
+            .clone()
+            .unwrap_or(ServerName::IpAddress(self.server.ip()));
+        let mut conn = ClientConnection::new(tls_client.driver.clone(), tls_name)
+            .map_err(|e| io::Error::other(format!("failed to create tls connection: {e}")))?;
+        stream.set_read_timeout(Some(tls_client.handshake_timeout))?;
+        while conn.is_handshaking() {
+            conn.complete_io(&mut stream)?;
+        }
+        // the server should not send any data after handshake
+        stream.set_read_timeout(Some(self.write_timeout))?;
+        Ok(SyslogStream::Tls(Box::new(StreamOwned::new(conn, stream))))
+    }
+}
+
+pub(super) enum SyslogStream {
+    Tcp(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl SyslogStream {
+    /// Send a message with octet-counting framing
+    pub(super) fn send_msg(&mut self, msg: &[u8]) -> io::Result<()> {
+        let mut len_buf = itoa::Buffer::new();
+        let len = len_buf.format(msg.len());
+        match self {
+            SyslogStream::Tcp(s) => {
+                s.write_all(len.as_bytes())?;
+                s.write_all(b" ")?;
+                s.write_all(msg)?;
+                s.flush()
+            }
+            SyslogStream::Tls(s) => {
+                s.write_all(len.as_bytes())?;
+                s.write_all(b" ")?;
+                s.write_all(msg)?;
+                s.flush()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn octet_counting() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = SyslogTcpConfig::new(listener.local_addr().unwrap());
+        let mut stream = config.connect().unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+
+        stream.send_msg(b"<14>a b").unwrap();
+        stream.send_msg(b"<14>c").unwrap();
+        drop(stream);
+
+        let mut buf = Vec::new();
+        server.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"7 <14>a b5 <14>c");
+    }
+}
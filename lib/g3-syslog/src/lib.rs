@@ -27,7 +27,7 @@ pub use types::{Facility, Severity};
 
 use async_streamer::AsyncSyslogStreamer;
 
-pub use backend::SyslogBackendBuilder;
+pub use backend::{SyslogBackendBuilder, SyslogTcpConfig};
 
 use format::BoxSyslogFormatter;
 pub use format::SyslogFormatterKind;
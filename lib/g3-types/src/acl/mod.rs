@@ -26,6 +26,7 @@ mod network;
 mod proxy_request;
 mod radix_trie;
 mod regex_set;
+mod url;
 mod user_agent;
 
 use self::radix_trie::{AclRadixTrieRule, AclRadixTrieRuleBuilder};
@@ -38,6 +39,7 @@ pub use exact_port::AclExactPortRule;
 pub use network::{AclNetworkRule, AclNetworkRuleBuilder};
 pub use proxy_request::AclProxyRequestRule;
 pub use regex_set::{AclRegexSetRule, AclRegexSetRuleBuilder};
+pub use url::{AclUrlRule, AclUrlRuleBuilder};
 pub use user_agent::AclUserAgentRule;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd)]
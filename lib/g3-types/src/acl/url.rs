@@ -0,0 +1,471 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use ahash::AHashMap;
+use anyhow::anyhow;
+use regex::Regex;
+
+use super::{AclAction, AclRegexSetRule, AclRegexSetRuleBuilder};
+use crate::net::Host;
+use crate::route::UriPathMatch;
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct AclUrlRuleKey {
+    scheme: Option<String>,
+    host: Option<Host>,
+}
+
+impl AclUrlRuleKey {
+    fn new(scheme: Option<&str>, host: Option<Host>) -> Self {
+        AclUrlRuleKey {
+            scheme: scheme.map(|s| s.to_ascii_lowercase()),
+            host,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct AclUrlPathRuleBuilder {
+    prefix: BTreeMap<String, AclAction>,
+    regex: AclRegexSetRuleBuilder,
+}
+
+impl Default for AclUrlPathRuleBuilder {
+    fn default() -> Self {
+        AclUrlPathRuleBuilder {
+            prefix: BTreeMap::new(),
+            regex: AclRegexSetRuleBuilder::new(AclAction::Permit),
+        }
+    }
+}
+
+impl AclUrlPathRuleBuilder {
+    fn build(&self) -> AclUrlPathRule {
+        let mut prefix = UriPathMatch::default();
+        for (path, action) in &self.prefix {
+            prefix.add_prefix(path.to_string(), *action);
+        }
+
+        AclUrlPathRule {
+            prefix,
+            regex: self.regex.build(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AclUrlRuleBuilder {
+    inner: AHashMap<AclUrlRuleKey, AclUrlPathRuleBuilder>,
+    missed_action: AclAction,
+}
+
+impl AclUrlRuleBuilder {
+    pub fn new(missed_action: AclAction) -> Self {
+        AclUrlRuleBuilder {
+            inner: AHashMap::new(),
+            missed_action,
+        }
+    }
+
+    pub fn add_path_prefix(
+        &mut self,
+        scheme: Option<&str>,
+        host: Option<Host>,
+        prefix: &str,
+        action: AclAction,
+    ) {
+        let key = AclUrlRuleKey::new(scheme, host);
+        self.inner
+            .entry(key)
+            .or_default()
+            .prefix
+            .insert(prefix.to_string(), action);
+    }
+
+    pub fn add_path_regex(
+        &mut self,
+        scheme: Option<&str>,
+        host: Option<Host>,
+        regex: &Regex,
+        action: AclAction,
+    ) {
+        let key = AclUrlRuleKey::new(scheme, host);
+        self.inner
+            .entry(key)
+            .or_default()
+            .regex
+            .add_regex(regex, action);
+    }
+
+    /// Add a rule in url prefix form, like `https://example.net/upload`.
+    ///
+    /// The scheme part is optional, and the host part may be `*` to match all hosts.
+    /// The path prefix will be `/` if not set.
+    pub fn add_url_prefix(&mut self, url: &str, action: AclAction) -> anyhow::Result<()> {
+        let (scheme, left) = match url.split_once("://") {
+            Some((scheme, left)) => {
+                if scheme.is_empty() || scheme == "*" {
+                    (None, left)
+                } else {
+                    (Some(scheme), left)
+                }
+            }
+            None => (None, url),
+        };
+        let (host, path) = match memchr::memchr(b'/', left.as_bytes()) {
+            Some(p) => (&left[..p], &left[p..]),
+            None => (left, "/"),
+        };
+        let host = match host {
+            "" | "*" => None,
+            s => {
+                let host = Host::from_str(s).map_err(|e| anyhow!("invalid host {s}: {e}"))?;
+                Some(host)
+            }
+        };
+        self.add_path_prefix(scheme, host, path, action);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_missed_action(&mut self, action: AclAction) {
+        self.missed_action = action;
+    }
+
+    #[inline]
+    pub fn missed_action(&self) -> AclAction {
+        self.missed_action
+    }
+
+    pub fn build(&self) -> AclUrlRule {
+        let mut inner = AHashMap::with_capacity(self.inner.len());
+        for (k, v) in &self.inner {
+            inner.insert(k.clone(), v.build());
+        }
+
+        AclUrlRule {
+            inner,
+            missed_action: self.missed_action,
+        }
+    }
+}
+
+struct AclUrlPathRule {
+    prefix: UriPathMatch<AclAction>,
+    regex: AclRegexSetRule,
+}
+
+impl AclUrlPathRule {
+    fn check(&self, path: &str, path_and_query: &str) -> Option<AclAction> {
+        let prefix_action = self.prefix.get(path).copied();
+        match self.regex.check(path_and_query) {
+            (true, action) => Some(prefix_action.map(|v| v.restrict(action)).unwrap_or(action)),
+            (false, _) => prefix_action,
+        }
+    }
+}
+
+fn is_unreserved(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'-' | b'.' | b'_' | b'~')
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Normalize the path as described in RFC 3986 Section 6.2.2.
+///
+/// Percent-encoded unreserved characters will be decoded, hex digits in other percent-encodings
+/// will be uppercased, dot segments will be removed, and consecutive slashes will be merged.
+fn normalize_path(path: &str) -> Cow<'_, str> {
+    if !path.starts_with('/') || !(path.contains('%') || path.contains("//") || path.contains("/."))
+    {
+        return Cow::Borrowed(path);
+    }
+
+    let s = path.as_bytes();
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut offset = 0;
+    while offset < s.len() {
+        let c = s[offset];
+        if c == b'%' && offset + 2 < s.len() {
+            let (h, l) = (s[offset + 1], s[offset + 2]);
+            if let (Some(hv), Some(lv)) = (hex_value(h), hex_value(l)) {
+                let v = (hv << 4) | lv;
+                if is_unreserved(v) {
+                    decoded.push(v);
+                } else {
+                    decoded.extend_from_slice(&[
+                        b'%',
+                        h.to_ascii_uppercase(),
+                        l.to_ascii_uppercase(),
+                    ]);
+                }
+                offset += 3;
+                continue;
+            }
+        }
+        decoded.push(c);
+        offset += 1;
+    }
+
+    let mut segments: Vec<&[u8]> = Vec::new();
+    let mut trailing_slash = false;
+    for segment in decoded[1..].split(|c| *c == b'/') {
+        match segment {
+            b"" | b"." => trailing_slash = true,
+            b".." => {
+                segments.pop();
+                trailing_slash = true;
+            }
+            s => {
+                segments.push(s);
+                trailing_slash = false;
+            }
+        }
+    }
+
+    let mut normalized = Vec::with_capacity(decoded.len());
+    for segment in &segments {
+        normalized.push(b'/');
+        normalized.extend_from_slice(segment);
+    }
+    if trailing_slash || segments.is_empty() {
+        normalized.push(b'/');
+    }
+    // only ascii characters are decoded, so it's still valid utf-8
+    String::from_utf8(normalized)
+        .map(Cow::Owned)
+        .unwrap_or(Cow::Borrowed(path))
+}
+
+pub struct AclUrlRule {
+    inner: AHashMap<AclUrlRuleKey, AclUrlPathRule>,
+    missed_action: AclAction,
+}
+
+impl AclUrlRule {
+    /// Check the url by its scheme, host and path.
+    ///
+    /// The path will be normalized before matching, see RFC 3986 Section 6.2.2.
+    /// The path prefix rules will be matched against the path without query,
+    /// while the path regex rules will be matched against the path with query.
+    /// The strictest action will be returned if multiple rules match.
+    pub fn check(&self, scheme: &str, host: &Host, path_and_query: &str) -> (bool, AclAction) {
+        let (path, query) = match memchr::memchr(b'?', path_and_query.as_bytes()) {
+            Some(p) => (&path_and_query[..p], &path_and_query[p..]),
+            None => (path_and_query, ""),
+        };
+        let path = if path.is_empty() { "/" } else { path };
+        let path = normalize_path(path);
+        let path_and_query = match &path {
+            Cow::Borrowed(_) => Cow::Borrowed(path_and_query),
+            Cow::Owned(p) => Cow::Owned(format!("{p}{query}")),
+        };
+        let path = path.as_ref();
+        let path_and_query = path_and_query.as_ref();
+        let scheme = scheme.to_ascii_lowercase();
+
+        let keys = [
+            AclUrlRuleKey {
+                scheme: Some(scheme.clone()),
+                host: Some(host.clone()),
+            },
+            AclUrlRuleKey {
+                scheme: None,
+                host: Some(host.clone()),
+            },
+            AclUrlRuleKey {
+                scheme: Some(scheme),
+                host: None,
+            },
+            AclUrlRuleKey {
+                scheme: None,
+                host: None,
+            },
+        ];
+
+        let mut found_action: Option<AclAction> = None;
+        for key in &keys {
+            let Some(rule) = self.inner.get(key) else {
+                continue;
+            };
+            if let Some(action) = rule.check(path, path_and_query) {
+                found_action = Some(found_action.map(|v| v.restrict(action)).unwrap_or(action));
+            }
+        }
+
+        match found_action {
+            Some(action) => (true, action),
+            None => (false, self.missed_action),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_prefix() {
+        let mut builder = AclUrlRuleBuilder::new(AclAction::Permit);
+        builder
+            .add_url_prefix("https://upload.example.net/upload", AclAction::Forbid)
+            .unwrap();
+        builder
+            .add_url_prefix("upload.example.net/upload/public", AclAction::Permit)
+            .unwrap();
+        let rule = builder.build();
+
+        let host = Host::from_str("upload.example.net").unwrap();
+        assert_eq!(
+            rule.check("https", &host, "/upload/a.txt"),
+            (true, AclAction::Forbid)
+        );
+        assert_eq!(
+            rule.check("HTTPS", &host, "/upload?a=b"),
+            (true, AclAction::Forbid)
+        );
+        assert_eq!(
+            rule.check("https", &host, "/upload/public/a.txt"),
+            (true, AclAction::Forbid)
+        );
+        assert_eq!(
+            rule.check("http", &host, "/upload/public/a.txt"),
+            (true, AclAction::Permit)
+        );
+        assert_eq!(
+            rule.check("http", &host, "/upload/a.txt"),
+            (false, AclAction::Permit)
+        );
+        assert_eq!(rule.check("https", &host, "/"), (false, AclAction::Permit));
+
+        let host = Host::from_str("www.example.net").unwrap();
+        assert_eq!(
+            rule.check("https", &host, "/upload/a.txt"),
+            (false, AclAction::Permit)
+        );
+    }
+
+    #[test]
+    fn path_regex() {
+        let mut builder = AclUrlRuleBuilder::new(AclAction::Forbid);
+        let host = Host::from_str("api.example.net").unwrap();
+        builder.add_path_prefix(Some("https"), Some(host.clone()), "/v1/", AclAction::Permit);
+        let regex = Regex::new("^/v[0-9]+/admin").unwrap();
+        builder.add_path_regex(None, None, &regex, AclAction::ForbidAndLog);
+        let rule = builder.build();
+
+        assert_eq!(
+            rule.check("https", &host, "/v1/users"),
+            (true, AclAction::Permit)
+        );
+        assert_eq!(
+            rule.check("https", &host, "/v1/admin/users"),
+            (true, AclAction::ForbidAndLog)
+        );
+        assert_eq!(
+            rule.check("https", &host, "/v2/users"),
+            (false, AclAction::Forbid)
+        );
+        assert_eq!(rule.check("https", &host, ""), (false, AclAction::Forbid));
+    }
+
+    #[test]
+    fn url_prefix_any() {
+        let mut builder = AclUrlRuleBuilder::new(AclAction::Permit);
+        builder
+            .add_url_prefix("*/upload", AclAction::Forbid)
+            .unwrap();
+        builder
+            .add_url_prefix("http://*", AclAction::ForbidAndLog)
+            .unwrap();
+        assert!(builder
+            .add_url_prefix("https://[::1/", AclAction::Forbid)
+            .is_err());
+        let rule = builder.build();
+
+        let host = Host::from_str("192.168.1.1").unwrap();
+        assert_eq!(
+            rule.check("https", &host, "/upload"),
+            (true, AclAction::Forbid)
+        );
+        assert_eq!(
+            rule.check("http", &host, "/index.html"),
+            (true, AclAction::ForbidAndLog)
+        );
+        assert_eq!(
+            rule.check("https", &host, "/index.html"),
+            (false, AclAction::Permit)
+        );
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(normalize_path("/upload"), "/upload");
+        assert_eq!(normalize_path("/%75pload"), "/upload");
+        assert_eq!(normalize_path("/%2e%2E/upload"), "/upload");
+        assert_eq!(normalize_path("/a%2fb%3a"), "/a%2Fb%3A");
+        assert_eq!(normalize_path("//upload//a.txt"), "/upload/a.txt");
+        assert_eq!(normalize_path("/./upload/./"), "/upload/");
+        assert_eq!(normalize_path("/a/b/../../upload/c/.."), "/upload/");
+        assert_eq!(normalize_path("/../.."), "/");
+        assert_eq!(normalize_path("/50%"), "/50%");
+    }
+
+    #[test]
+    fn path_bypass() {
+        let mut builder = AclUrlRuleBuilder::new(AclAction::Permit);
+        builder
+            .add_url_prefix("upload.example.net/upload", AclAction::Forbid)
+            .unwrap();
+        let regex = Regex::new("^/admin/").unwrap();
+        builder.add_path_regex(None, None, &regex, AclAction::ForbidAndLog);
+        let rule = builder.build();
+
+        let host = Host::from_str("upload.example.net").unwrap();
+        for path in [
+            "/%75pload",
+            "//upload",
+            "/./upload",
+            "/public/../upload/a.txt",
+            "/%2E/upload?a=b",
+        ] {
+            assert_eq!(
+                rule.check("https", &host, path),
+                (true, AclAction::Forbid),
+                "path: {path}"
+            );
+        }
+        assert_eq!(
+            rule.check("https", &host, "/%61dmin//users?a=b"),
+            (true, AclAction::ForbidAndLog)
+        );
+        assert_eq!(
+            rule.check("https", &host, "/public/%2e/a.txt"),
+            (false, AclAction::Permit)
+        );
+    }
+}
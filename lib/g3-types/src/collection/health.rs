@@ -0,0 +1,138 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::{SelectiveItem, SelectiveVec, SelectiveVecBuilder};
+
+/// The health status of a peer, which is updated by the results of periodic checks.
+///
+/// The peer will be marked unhealthy after `fall` consecutive failures,
+/// and healthy again after `rise` consecutive successes.
+pub struct RiseFallHealth {
+    healthy: AtomicBool,
+    consecutive_success: AtomicUsize,
+    consecutive_failure: AtomicUsize,
+}
+
+impl Default for RiseFallHealth {
+    fn default() -> Self {
+        RiseFallHealth {
+            healthy: AtomicBool::new(true),
+            consecutive_success: AtomicUsize::new(0),
+            consecutive_failure: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl RiseFallHealth {
+    /// return true if the health status changed
+    pub fn add_result(&self, success: bool, rise: usize, fall: usize) -> bool {
+        if success {
+            self.consecutive_failure.store(0, Ordering::Relaxed);
+            let count = self.consecutive_success.fetch_add(1, Ordering::Relaxed) + 1;
+            if count >= rise && !self.healthy.load(Ordering::Relaxed) {
+                self.healthy.store(true, Ordering::Relaxed);
+                return true;
+            }
+        } else {
+            self.consecutive_success.store(0, Ordering::Relaxed);
+            let count = self.consecutive_failure.fetch_add(1, Ordering::Relaxed) + 1;
+            if count >= fall && self.healthy.load(Ordering::Relaxed) {
+                self.healthy.store(false, Ordering::Relaxed);
+                return true;
+            }
+        }
+        false
+    }
+
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn consecutive_success(&self) -> usize {
+        self.consecutive_success.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn consecutive_failure(&self) -> usize {
+        self.consecutive_failure.load(Ordering::Relaxed)
+    }
+}
+
+/// Build the selective vec with only the available nodes,
+/// or with all nodes if none of them is available.
+pub fn build_available_selection<T, I>(nodes: I) -> Option<SelectiveVec<T>>
+where
+    T: SelectiveItem + Clone,
+    I: IntoIterator<Item = (T, bool)>,
+{
+    let mut available_builder = SelectiveVecBuilder::new();
+    let mut all_builder = SelectiveVecBuilder::new();
+    for (node, available) in nodes {
+        if available {
+            available_builder.insert(node.clone());
+        }
+        all_builder.insert(node);
+    }
+    available_builder.build().or_else(|| all_builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::WeightedValue;
+
+    #[test]
+    fn rise_and_fall() {
+        let health = RiseFallHealth::default();
+        assert!(health.is_healthy());
+
+        assert!(!health.add_result(false, 2, 2));
+        assert!(!health.add_result(true, 2, 2));
+        assert_eq!(health.consecutive_success(), 1);
+        assert!(!health.add_result(false, 2, 2));
+        assert!(health.add_result(false, 2, 2));
+        assert!(!health.is_healthy());
+        assert_eq!(health.consecutive_failure(), 2);
+        assert!(!health.add_result(false, 2, 2));
+
+        assert!(!health.add_result(true, 2, 2));
+        assert!(health.add_result(true, 2, 2));
+        assert!(health.is_healthy());
+        assert_eq!(health.consecutive_failure(), 0);
+        assert!(!health.add_result(true, 2, 2));
+    }
+
+    #[test]
+    fn selection_fallback() {
+        let nodes = [WeightedValue::new(1u32), WeightedValue::new(2u32)];
+
+        let selection = build_available_selection(nodes.iter().map(|n| (*n, false))).unwrap();
+        assert_eq!(selection.pick_serial_n(usize::MAX).len(), 2);
+
+        let selection =
+            build_available_selection(nodes.iter().map(|n| (*n, *n.inner() == 2))).unwrap();
+        assert_eq!(selection.pick_serial_n(usize::MAX).len(), 1);
+        assert_eq!(*selection.pick_serial().inner(), 2);
+
+        assert!(
+            build_available_selection(std::iter::empty::<(WeightedValue<u32>, bool)>()).is_none()
+        );
+    }
+}
@@ -14,10 +14,12 @@
  * limitations under the License.
  */
 
+mod health;
 mod named_value;
 mod selective_vec;
 mod weighted_value;
 
+pub use health::{build_available_selection, RiseFallHealth};
 pub use named_value::NamedValue;
 pub use selective_vec::{SelectiveItem, SelectivePickPolicy, SelectiveVec, SelectiveVecBuilder};
 pub use weighted_value::WeightedValue;
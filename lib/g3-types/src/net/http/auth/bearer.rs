@@ -0,0 +1,52 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use crate::auth::AuthParseError;
+
+pub struct HttpBearerAuth {
+    token: String,
+}
+
+impl HttpBearerAuth {
+    #[inline]
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl FromStr for HttpBearerAuth {
+    type Err = AuthParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let token = s.trim(); // allow more space than spec
+
+        // token68 = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+        let value = token.trim_end_matches('=');
+        if value.is_empty()
+            || !value
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || b"-._~+/".contains(&c))
+        {
+            return Err(AuthParseError::InvalidToken);
+        }
+
+        Ok(HttpBearerAuth {
+            token: token.to_string(),
+        })
+    }
+}
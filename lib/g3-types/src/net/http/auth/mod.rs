@@ -24,9 +24,13 @@ use crate::net::HttpHeaderValue;
 mod basic;
 pub use basic::HttpBasicAuth;
 
+mod bearer;
+pub use bearer::HttpBearerAuth;
+
 pub enum HttpAuth {
     None,
     Basic(HttpBasicAuth),
+    Bearer(HttpBearerAuth),
 }
 
 impl HttpAuth {
@@ -37,6 +41,10 @@ impl HttpAuth {
                     let basic = HttpBasicAuth::from_str(&value[i + 1..])?;
                     Ok(HttpAuth::Basic(basic))
                 }
+                "bearer" => {
+                    let bearer = HttpBearerAuth::from_str(&value[i + 1..])?;
+                    Ok(HttpAuth::Bearer(bearer))
+                }
                 _ => Ok(HttpAuth::None),
             },
             None => Err(AuthParseError::UnsupportedAuthType),
@@ -98,6 +106,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_bearer() {
+        let value = "Bearer eyJhbGciOiJIUzI1NiJ9.e30.abc-_";
+        let info = HttpAuth::from_authorization(value).unwrap();
+        let HttpAuth::Bearer(bearer) = info else {
+            panic!("not bearer auth");
+        };
+        assert_eq!(bearer.token(), "eyJhbGciOiJIUzI1NiJ9.e30.abc-_");
+
+        let value = "Bearer a b";
+        assert!(HttpAuth::from_authorization(value).is_err());
+    }
+
     #[test]
     fn parse_scheme_only() {
         let value = "Basic ";
@@ -24,6 +24,8 @@ pub enum ProxyRequestType {
     HttpConnect,
     SocksTcpConnect,
     SocksUdpAssociate,
+    HttpConnectUdp,
+    SocksTcpBind,
 }
 
 impl FromStr for ProxyRequestType {
@@ -37,6 +39,10 @@ impl FromStr for ProxyRequestType {
             "httpconnect" | "http_connect" => Ok(ProxyRequestType::HttpConnect),
             "sockstcpconnect" | "socks_tcp_connect" => Ok(ProxyRequestType::SocksTcpConnect),
             "socksudpassociate" | "socks_udp_associate" => Ok(ProxyRequestType::SocksUdpAssociate),
+            "httpconnectudp" | "http_connect_udp" | "connect_udp" => {
+                Ok(ProxyRequestType::HttpConnectUdp)
+            }
+            "sockstcpbind" | "socks_tcp_bind" => Ok(ProxyRequestType::SocksTcpBind),
             _ => Err(()),
         }
     }
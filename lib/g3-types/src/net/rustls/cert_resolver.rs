@@ -41,6 +41,10 @@ impl MultipleCertResolver {
         self.keys.push(Arc::new(ck));
         Ok(())
     }
+
+    pub fn push_certified_key(&mut self, ck: CertifiedKey) {
+        self.keys.push(Arc::new(ck));
+    }
 }
 
 impl ResolvesServerCert for MultipleCertResolver {
@@ -22,6 +22,8 @@ use std::str::FromStr;
 pub enum TlsServiceType {
     Http = 0,
     Smtp = 1,
+    Imap = 2,
+    Pop3 = 3,
 }
 
 impl TlsServiceType {
@@ -29,6 +31,8 @@ impl TlsServiceType {
         match self {
             TlsServiceType::Http => "http",
             TlsServiceType::Smtp => "smtp",
+            TlsServiceType::Imap => "imap",
+            TlsServiceType::Pop3 => "pop3",
         }
     }
 }
@@ -54,6 +58,8 @@ impl TryFrom<u8> for TlsServiceType {
         match value {
             0 => Ok(TlsServiceType::Http),
             1 => Ok(TlsServiceType::Smtp),
+            2 => Ok(TlsServiceType::Imap),
+            3 => Ok(TlsServiceType::Pop3),
             _ => Err(InvalidServiceType),
         }
     }
@@ -66,6 +72,8 @@ impl FromStr for TlsServiceType {
         match s {
             "http" | "HTTP" => Ok(TlsServiceType::Http),
             "smtp" | "SMTP" => Ok(TlsServiceType::Smtp),
+            "imap" | "IMAP" => Ok(TlsServiceType::Imap),
+            "pop3" | "POP3" => Ok(TlsServiceType::Pop3),
             _ => Err(InvalidServiceType),
         }
     }
@@ -48,7 +48,7 @@ impl<T> UriPathMatch<T> {
 
     pub fn get(&self, path: &str) -> Option<&T> {
         if let Some(trie) = &self.prefix {
-            if let Some(v) = trie.get(path) {
+            if let Some(v) = trie.get_ancestor_value(path) {
                 return Some(v);
             }
         }
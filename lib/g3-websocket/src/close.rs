@@ -0,0 +1,147 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CloseFrameParseError {
+    #[error("too short payload")]
+    TooShortPayload,
+    #[error("invalid close code {0}")]
+    InvalidCode(u16),
+    #[error("invalid utf-8 reason")]
+    InvalidReason,
+}
+
+/// Status codes defined in RFC 6455 Section 7.4.1
+pub struct CloseCode;
+
+impl CloseCode {
+    pub const NORMAL: u16 = 1000;
+    pub const GOING_AWAY: u16 = 1001;
+    pub const PROTOCOL_ERROR: u16 = 1002;
+    pub const UNSUPPORTED_DATA: u16 = 1003;
+    pub const INVALID_PAYLOAD: u16 = 1007;
+    pub const POLICY_VIOLATION: u16 = 1008;
+    pub const MESSAGE_TOO_BIG: u16 = 1009;
+    pub const INTERNAL_ERROR: u16 = 1011;
+
+    fn is_valid(code: u16) -> bool {
+        match code {
+            1000..=1003 | 1007..=1011 => true,
+            3000..=4999 => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: Option<u16>,
+    pub reason: String,
+}
+
+impl CloseFrame {
+    pub fn new(code: u16, reason: &str) -> Self {
+        CloseFrame {
+            code: Some(code),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Parse the unmasked payload of a close frame
+    pub fn parse(payload: &[u8]) -> Result<Self, CloseFrameParseError> {
+        match payload.len() {
+            0 => Ok(CloseFrame {
+                code: None,
+                reason: String::new(),
+            }),
+            1 => Err(CloseFrameParseError::TooShortPayload),
+            _ => {
+                let code = u16::from_be_bytes([payload[0], payload[1]]);
+                if !CloseCode::is_valid(code) {
+                    return Err(CloseFrameParseError::InvalidCode(code));
+                }
+                let reason = std::str::from_utf8(&payload[2..])
+                    .map_err(|_| CloseFrameParseError::InvalidReason)?;
+                Ok(CloseFrame {
+                    code: Some(code),
+                    reason: reason.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Encode the payload of this close frame, the reason will be truncated to fit in
+    /// a control frame
+    pub fn encode_payload(&self) -> Vec<u8> {
+        let Some(code) = self.code else {
+            return Vec::new();
+        };
+
+        let mut buf = Vec::with_capacity(2 + self.reason.len());
+        buf.extend_from_slice(&code.to_be_bytes());
+        let mut reason_len = self.reason.len().min(123);
+        while !self.reason.is_char_boundary(reason_len) {
+            reason_len -= 1;
+        }
+        buf.extend_from_slice(&self.reason.as_bytes()[..reason_len]);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_empty() {
+        let frame = CloseFrame::parse(b"").unwrap();
+        assert_eq!(frame.code, None);
+        assert!(frame.encode_payload().is_empty());
+    }
+
+    #[test]
+    fn parse_with_reason() {
+        let frame = CloseFrame::parse(b"\x03\xe8bye").unwrap();
+        assert_eq!(frame.code, Some(CloseCode::NORMAL));
+        assert_eq!(frame.reason, "bye");
+        assert_eq!(frame.encode_payload().as_slice(), b"\x03\xe8bye");
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(
+            CloseFrame::parse(b"\x03").unwrap_err(),
+            CloseFrameParseError::TooShortPayload
+        );
+        assert_eq!(
+            CloseFrame::parse(b"\x03\xed").unwrap_err(),
+            CloseFrameParseError::InvalidCode(1005)
+        );
+        assert_eq!(
+            CloseFrame::parse(b"\x03\xe8\xff").unwrap_err(),
+            CloseFrameParseError::InvalidReason
+        );
+    }
+
+    #[test]
+    fn encode_long_reason() {
+        let reason = "a".repeat(200);
+        let frame = CloseFrame::new(CloseCode::POLICY_VIOLATION, &reason);
+        assert_eq!(frame.encode_payload().len(), 125);
+    }
+}
@@ -0,0 +1,168 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PerMessageDeflateParseError {
+    #[error("unsupported extension {0}")]
+    UnsupportedExtension(String),
+    #[error("duplicate permessage-deflate extension")]
+    DuplicateExtension,
+    #[error("invalid parameter {0}")]
+    InvalidParameter(String),
+}
+
+/// The negotiated permessage-deflate parameters, see RFC 7692
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PerMessageDeflateConfig {
+    pub server_no_context_takeover: bool,
+    pub client_no_context_takeover: bool,
+    pub server_max_window_bits: Option<u8>,
+    pub client_max_window_bits: Option<u8>,
+}
+
+impl PerMessageDeflateConfig {
+    /// Parse the `Sec-WebSocket-Extensions` header values in the server handshake response.
+    ///
+    /// Extensions other than permessage-deflate are not supported as we won't be able to
+    /// decode the frames then.
+    pub fn parse_response_extensions<'a, I>(
+        values: I,
+    ) -> Result<Option<Self>, PerMessageDeflateParseError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut config: Option<Self> = None;
+
+        for value in values {
+            for ext in value.split(',') {
+                let mut params = ext.split(';').map(|s| s.trim());
+                let Some(name) = params.next() else {
+                    continue;
+                };
+                if name.is_empty() {
+                    continue;
+                }
+                if !name.eq_ignore_ascii_case("permessage-deflate") {
+                    return Err(PerMessageDeflateParseError::UnsupportedExtension(
+                        name.to_string(),
+                    ));
+                }
+                if config.is_some() {
+                    return Err(PerMessageDeflateParseError::DuplicateExtension);
+                }
+
+                let mut c = PerMessageDeflateConfig::default();
+                for param in params {
+                    c.set_param(param)?;
+                }
+                config = Some(c);
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn set_param(&mut self, param: &str) -> Result<(), PerMessageDeflateParseError> {
+        let (name, value) = match param.split_once('=') {
+            Some((k, v)) => (k.trim(), Some(v.trim().trim_matches('"'))),
+            None => (param, None),
+        };
+
+        let invalid = || PerMessageDeflateParseError::InvalidParameter(param.to_string());
+        let parse_bits = |v: Option<&str>| -> Result<u8, PerMessageDeflateParseError> {
+            let bits = v
+                .ok_or_else(invalid)?
+                .parse::<u8>()
+                .map_err(|_| invalid())?;
+            if (8..=15).contains(&bits) {
+                Ok(bits)
+            } else {
+                Err(invalid())
+            }
+        };
+
+        match name.to_ascii_lowercase().as_str() {
+            "server_no_context_takeover" if value.is_none() => {
+                self.server_no_context_takeover = true
+            }
+            "client_no_context_takeover" if value.is_none() => {
+                self.client_no_context_takeover = true
+            }
+            "server_max_window_bits" => self.server_max_window_bits = Some(parse_bits(value)?),
+            "client_max_window_bits" => self.client_max_window_bits = Some(parse_bits(value)?),
+            _ => return Err(invalid()),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_none() {
+        let config = PerMessageDeflateConfig::parse_response_extensions([]).unwrap();
+        assert!(config.is_none());
+    }
+
+    #[test]
+    fn parse_simple() {
+        let config = PerMessageDeflateConfig::parse_response_extensions(["permessage-deflate"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(config, PerMessageDeflateConfig::default());
+    }
+
+    #[test]
+    fn parse_params() {
+        let config = PerMessageDeflateConfig::parse_response_extensions([
+            "permessage-deflate; server_no_context_takeover; client_max_window_bits=10",
+        ])
+        .unwrap()
+        .unwrap();
+        assert!(config.server_no_context_takeover);
+        assert!(!config.client_no_context_takeover);
+        assert_eq!(config.server_max_window_bits, None);
+        assert_eq!(config.client_max_window_bits, Some(10));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(
+            PerMessageDeflateConfig::parse_response_extensions(["x-webkit-deflate-frame"])
+                .unwrap_err(),
+            PerMessageDeflateParseError::UnsupportedExtension("x-webkit-deflate-frame".to_string())
+        );
+        assert_eq!(
+            PerMessageDeflateConfig::parse_response_extensions([
+                "permessage-deflate",
+                "permessage-deflate"
+            ])
+            .unwrap_err(),
+            PerMessageDeflateParseError::DuplicateExtension
+        );
+        assert_eq!(
+            PerMessageDeflateConfig::parse_response_extensions([
+                "permessage-deflate; server_max_window_bits=16"
+            ])
+            .unwrap_err(),
+            PerMessageDeflateParseError::InvalidParameter("server_max_window_bits=16".to_string())
+        );
+    }
+}
@@ -0,0 +1,332 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FrameParseError {
+    #[error("reserved opcode {0}")]
+    ReservedOpCode(u8),
+    #[error("invalid payload length")]
+    InvalidPayloadLength,
+    #[error("fragmented control frame")]
+    FragmentedControlFrame,
+    #[error("too large control frame")]
+    TooLargeControlFrame,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpCode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl OpCode {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0x0 => Some(OpCode::Continuation),
+            0x1 => Some(OpCode::Text),
+            0x2 => Some(OpCode::Binary),
+            0x8 => Some(OpCode::Close),
+            0x9 => Some(OpCode::Ping),
+            0xa => Some(OpCode::Pong),
+            _ => None,
+        }
+    }
+
+    fn as_u8(&self) -> u8 {
+        match self {
+            OpCode::Continuation => 0x0,
+            OpCode::Text => 0x1,
+            OpCode::Binary => 0x2,
+            OpCode::Close => 0x8,
+            OpCode::Ping => 0x9,
+            OpCode::Pong => 0xa,
+        }
+    }
+
+    pub fn is_control(&self) -> bool {
+        matches!(self, OpCode::Close | OpCode::Ping | OpCode::Pong)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpCode::Continuation => "continuation",
+            OpCode::Text => "text",
+            OpCode::Binary => "binary",
+            OpCode::Close => "close",
+            OpCode::Ping => "ping",
+            OpCode::Pong => "pong",
+        }
+    }
+}
+
+/// Max payload length of control frames, see RFC 6455 Section 5.5
+const MAX_CONTROL_PAYLOAD_LENGTH: u64 = 125;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub fin: bool,
+    /// the RSV1 bit, which is used as the `Per-Message Compressed` bit by permessage-deflate
+    pub rsv1: bool,
+    pub rsv2: bool,
+    pub rsv3: bool,
+    pub opcode: OpCode,
+    pub mask_key: Option<[u8; 4]>,
+    pub payload_len: u64,
+}
+
+impl FrameHeader {
+    pub fn new(opcode: OpCode, payload_len: u64) -> Self {
+        FrameHeader {
+            fin: true,
+            rsv1: false,
+            rsv2: false,
+            rsv3: false,
+            opcode,
+            mask_key: None,
+            payload_len,
+        }
+    }
+
+    /// Parse the frame header at the start of `buf`.
+    ///
+    /// Return `Ok(None)` if more data is needed, or the header and the header length.
+    pub fn parse(buf: &[u8]) -> Result<Option<(FrameHeader, usize)>, FrameParseError> {
+        if buf.len() < 2 {
+            return Ok(None);
+        }
+
+        let b0 = buf[0];
+        let b1 = buf[1];
+        let opcode =
+            OpCode::from_u8(b0 & 0x0f).ok_or(FrameParseError::ReservedOpCode(b0 & 0x0f))?;
+        let fin = b0 & 0x80 != 0;
+        let masked = b1 & 0x80 != 0;
+
+        let mut offset = 2;
+        let payload_len = match b1 & 0x7f {
+            126 => {
+                if buf.len() < offset + 2 {
+                    return Ok(None);
+                }
+                let len = u16::from_be_bytes([buf[2], buf[3]]);
+                offset += 2;
+                len as u64
+            }
+            127 => {
+                if buf.len() < offset + 8 {
+                    return Ok(None);
+                }
+                let mut len_buf = [0u8; 8];
+                len_buf.copy_from_slice(&buf[2..10]);
+                let len = u64::from_be_bytes(len_buf);
+                if len & 0x8000_0000_0000_0000 != 0 {
+                    return Err(FrameParseError::InvalidPayloadLength);
+                }
+                offset += 8;
+                len
+            }
+            n => n as u64,
+        };
+
+        if opcode.is_control() {
+            if !fin {
+                return Err(FrameParseError::FragmentedControlFrame);
+            }
+            if payload_len > MAX_CONTROL_PAYLOAD_LENGTH {
+                return Err(FrameParseError::TooLargeControlFrame);
+            }
+        }
+
+        let mask_key = if masked {
+            if buf.len() < offset + 4 {
+                return Ok(None);
+            }
+            let mut key = [0u8; 4];
+            key.copy_from_slice(&buf[offset..offset + 4]);
+            offset += 4;
+            Some(key)
+        } else {
+            None
+        };
+
+        let header = FrameHeader {
+            fin,
+            rsv1: b0 & 0x40 != 0,
+            rsv2: b0 & 0x20 != 0,
+            rsv3: b0 & 0x10 != 0,
+            opcode,
+            mask_key,
+            payload_len,
+        };
+        Ok(Some((header, offset)))
+    }
+
+    pub fn encoded_len(&self) -> usize {
+        let mut len = 2;
+        if self.payload_len > u16::MAX as u64 {
+            len += 8;
+        } else if self.payload_len >= 126 {
+            len += 2;
+        }
+        if self.mask_key.is_some() {
+            len += 4;
+        }
+        len
+    }
+
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        let mut b0 = self.opcode.as_u8();
+        if self.fin {
+            b0 |= 0x80;
+        }
+        if self.rsv1 {
+            b0 |= 0x40;
+        }
+        if self.rsv2 {
+            b0 |= 0x20;
+        }
+        if self.rsv3 {
+            b0 |= 0x10;
+        }
+        buf.push(b0);
+
+        let mask_bit = if self.mask_key.is_some() { 0x80 } else { 0 };
+        if self.payload_len > u16::MAX as u64 {
+            buf.push(mask_bit | 127);
+            buf.extend_from_slice(&self.payload_len.to_be_bytes());
+        } else if self.payload_len >= 126 {
+            buf.push(mask_bit | 126);
+            buf.extend_from_slice(&(self.payload_len as u16).to_be_bytes());
+        } else {
+            buf.push(mask_bit | self.payload_len as u8);
+        }
+
+        if let Some(key) = self.mask_key {
+            buf.extend_from_slice(&key);
+        }
+    }
+
+    /// Mask or unmask the payload data in place, `offset` is the position of `data`
+    /// in the whole payload.
+    pub fn apply_mask(&self, offset: usize, data: &mut [u8]) {
+        if let Some(key) = self.mask_key {
+            for (i, b) in data.iter_mut().enumerate() {
+                *b ^= key[(offset + i) & 0x03];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_unmasked_text() {
+        let buf = b"\x81\x05Hello";
+        let (header, len) = FrameHeader::parse(buf).unwrap().unwrap();
+        assert_eq!(len, 2);
+        assert!(header.fin);
+        assert!(!header.rsv1);
+        assert_eq!(header.opcode, OpCode::Text);
+        assert_eq!(header.mask_key, None);
+        assert_eq!(header.payload_len, 5);
+
+        let mut encoded = Vec::new();
+        header.encode(&mut encoded);
+        assert_eq!(encoded.as_slice(), &buf[..2]);
+    }
+
+    #[test]
+    fn parse_masked_text() {
+        let buf = b"\x81\x85\x37\xfa\x21\x3d\x7f\x9f\x4d\x51\x58";
+        let (header, len) = FrameHeader::parse(buf).unwrap().unwrap();
+        assert_eq!(len, 6);
+        assert_eq!(header.mask_key, Some([0x37, 0xfa, 0x21, 0x3d]));
+        assert_eq!(header.encoded_len(), 6);
+
+        let mut payload = buf[len..].to_vec();
+        header.apply_mask(0, &mut payload[..2]);
+        header.apply_mask(2, &mut payload[2..]);
+        assert_eq!(payload.as_slice(), b"Hello");
+    }
+
+    #[test]
+    fn parse_fragmented() {
+        let (header, _) = FrameHeader::parse(b"\x01\x03Hel").unwrap().unwrap();
+        assert!(!header.fin);
+        assert_eq!(header.opcode, OpCode::Text);
+
+        let (header, _) = FrameHeader::parse(b"\x80\x02lo").unwrap().unwrap();
+        assert!(header.fin);
+        assert_eq!(header.opcode, OpCode::Continuation);
+    }
+
+    #[test]
+    fn parse_extended_length() {
+        let buf = b"\x82\x7e\x01\x00";
+        let (header, len) = FrameHeader::parse(buf).unwrap().unwrap();
+        assert_eq!(len, 4);
+        assert_eq!(header.opcode, OpCode::Binary);
+        assert_eq!(header.payload_len, 256);
+        let mut encoded = Vec::new();
+        header.encode(&mut encoded);
+        assert_eq!(encoded.as_slice(), buf);
+
+        let buf = b"\x82\x7f\x00\x00\x00\x00\x00\x01\x00\x00";
+        let (header, len) = FrameHeader::parse(buf).unwrap().unwrap();
+        assert_eq!(len, 10);
+        assert_eq!(header.payload_len, 65536);
+        let mut encoded = Vec::new();
+        header.encode(&mut encoded);
+        assert_eq!(encoded.as_slice(), buf);
+
+        let buf = b"\x82\x7f\x80\x00\x00\x00\x00\x00\x00\x00";
+        assert_eq!(
+            FrameHeader::parse(buf).unwrap_err(),
+            FrameParseError::InvalidPayloadLength
+        );
+    }
+
+    #[test]
+    fn parse_partial() {
+        assert!(FrameHeader::parse(b"\x81").unwrap().is_none());
+        assert!(FrameHeader::parse(b"\x82\x7e\x01").unwrap().is_none());
+        assert!(FrameHeader::parse(b"\x81\x85\x37\xfa").unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_invalid_control() {
+        assert_eq!(
+            FrameHeader::parse(b"\x09\x00").unwrap_err(),
+            FrameParseError::FragmentedControlFrame
+        );
+        assert_eq!(
+            FrameHeader::parse(b"\x89\x7e\x00\x7e").unwrap_err(),
+            FrameParseError::TooLargeControlFrame
+        );
+        assert_eq!(
+            FrameHeader::parse(b"\x83\x00").unwrap_err(),
+            FrameParseError::ReservedOpCode(3)
+        );
+    }
+}
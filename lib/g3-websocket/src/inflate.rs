@@ -0,0 +1,167 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use flate2::{Decompress, FlushDecompress, Status};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MessageInflateError {
+    #[error("corrupted deflate data")]
+    CorruptedData,
+    #[error("decompressed size exceeds {0}")]
+    TooLarge(usize),
+}
+
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Decompress messages which are compressed by the permessage-deflate extension
+pub struct MessageInflater {
+    decompress: Decompress,
+    no_context_takeover: bool,
+}
+
+impl MessageInflater {
+    pub fn new(no_context_takeover: bool) -> Self {
+        MessageInflater {
+            decompress: Decompress::new(false),
+            no_context_takeover,
+        }
+    }
+
+    /// Decompress the (unmasked) payload data of a compressed message.
+    ///
+    /// At most `limit` bytes will be produced, and they will be appended to `out` if set.
+    /// Return the decompressed size.
+    pub fn inflate(
+        &mut self,
+        mut data: &[u8],
+        limit: usize,
+        mut out: Option<&mut Vec<u8>>,
+    ) -> Result<usize, MessageInflateError> {
+        let mut buf = [0u8; 8192];
+        let mut total = 0usize;
+
+        while !data.is_empty() {
+            let total_in = self.decompress.total_in();
+            let total_out = self.decompress.total_out();
+            let status = self
+                .decompress
+                .decompress(data, &mut buf, FlushDecompress::Sync)
+                .map_err(|_| MessageInflateError::CorruptedData)?;
+            let consumed = (self.decompress.total_in() - total_in) as usize;
+            let produced = (self.decompress.total_out() - total_out) as usize;
+            data = &data[consumed..];
+
+            total += produced;
+            if total > limit {
+                return Err(MessageInflateError::TooLarge(limit));
+            }
+            if let Some(out) = out.as_mut() {
+                out.extend_from_slice(&buf[..produced]);
+            }
+
+            match status {
+                Status::StreamEnd => {
+                    // the sender used a final block, the next one will start a new stream
+                    self.decompress.reset(false);
+                }
+                Status::Ok | Status::BufError => {
+                    if consumed == 0 && produced == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Finish the decompression of the current message.
+    ///
+    /// The tail of the deflate block, which has been removed by the sender, will be fed to
+    /// the decompressor, and the LZ77 window will be reset if context takeover is disabled.
+    pub fn finish_message(
+        &mut self,
+        limit: usize,
+        out: Option<&mut Vec<u8>>,
+    ) -> Result<usize, MessageInflateError> {
+        let r = self.inflate(&DEFLATE_TAIL, limit, out);
+        if self.no_context_takeover || r.is_err() {
+            self.decompress.reset(false);
+        }
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hello() {
+        // examples in RFC 7692 Section 7.2.3.1
+        let mut inflater = MessageInflater::new(false);
+        let mut out = Vec::new();
+        let data = b"\xf2\x48\xcd\xc9\xc9\x07\x00";
+        let n = inflater.inflate(data, 1024, Some(&mut out)).unwrap();
+        let n = n + inflater.finish_message(1024, Some(&mut out)).unwrap();
+        assert_eq!(n, 5);
+        assert_eq!(out.as_slice(), b"Hello");
+
+        // use the context of the previous message
+        out.clear();
+        let data = b"\xf2\x00\x11\x00\x00";
+        let n = inflater.inflate(data, 1024, Some(&mut out)).unwrap();
+        let n = n + inflater.finish_message(1024, Some(&mut out)).unwrap();
+        assert_eq!(n, 5);
+        assert_eq!(out.as_slice(), b"Hello");
+    }
+
+    #[test]
+    fn fragmented() {
+        let mut inflater = MessageInflater::new(true);
+        let mut out = Vec::new();
+        inflater
+            .inflate(b"\xf2\x48\xcd", 1024, Some(&mut out))
+            .unwrap();
+        inflater
+            .inflate(b"\xc9\xc9\x07\x00", 1024, Some(&mut out))
+            .unwrap();
+        inflater.finish_message(1024, Some(&mut out)).unwrap();
+        assert_eq!(out.as_slice(), b"Hello");
+    }
+
+    #[test]
+    fn too_large() {
+        let mut inflater = MessageInflater::new(true);
+        let data = b"\xf2\x48\xcd\xc9\xc9\x07\x00";
+        assert_eq!(
+            inflater.inflate(data, 4, None).unwrap_err(),
+            MessageInflateError::TooLarge(4)
+        );
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut inflater = MessageInflater::new(true);
+        assert_eq!(
+            inflater
+                .inflate(b"\xff\xff\xff\xff", 1024, None)
+                .unwrap_err(),
+            MessageInflateError::CorruptedData
+        );
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::prelude::*;
+
+use super::{XCryptParseError, XCryptParseResult};
+
+pub(super) const PREFIX_ARGON2ID: &str = "$argon2id$";
+pub(super) const PREFIX_ARGON2I: &str = "$argon2i$";
+pub(super) const PREFIX_ARGON2D: &str = "$argon2d$";
+
+/// 1GiB memory, the same as the max value used by yescrypt
+const M_COST_MAX: u32 = 1024 * 1024;
+const T_COST_MAX: u32 = 8;
+const P_COST_MAX: u32 = 16;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Argon2Crypt {
+    algorithm: Algorithm,
+    version: Version,
+    params: Params,
+    salt: Vec<u8>,
+    hash_bin: Vec<u8>,
+}
+
+impl Argon2Crypt {
+    /// parse the PHC string format: [v=<version>$]m=<m_cost>,t=<t_cost>,p=<p_cost>$<salt>$<hash>
+    pub(super) fn parse(algorithm: Algorithm, v: &str) -> XCryptParseResult<Self> {
+        let mut s = v;
+
+        let mut version = Version::V0x10;
+        if let Some(r) = s.strip_prefix("v=") {
+            let Some(d) = memchr::memchr(b'$', r.as_bytes()) else {
+                return Err(XCryptParseError::InvalidParams);
+            };
+            let n = u32::from_str(&r[0..d]).map_err(|_| XCryptParseError::InvalidParams)?;
+            version = Version::try_from(n).map_err(|_| XCryptParseError::UnsupportedParams)?;
+            s = &r[d + 1..];
+        }
+
+        let Some(d) = memchr::memchr(b'$', s.as_bytes()) else {
+            return Err(XCryptParseError::NoSaltFound);
+        };
+        let mut m_cost = None;
+        let mut t_cost = None;
+        let mut p_cost = None;
+        for kv in s[0..d].split(',') {
+            let Some((k, v)) = kv.split_once('=') else {
+                return Err(XCryptParseError::InvalidParams);
+            };
+            let v = u32::from_str(v).map_err(|_| XCryptParseError::InvalidParams)?;
+            match k {
+                "m" => m_cost = Some(v),
+                "t" => t_cost = Some(v),
+                "p" => p_cost = Some(v),
+                _ => return Err(XCryptParseError::InvalidParams),
+            }
+        }
+        let (Some(m_cost), Some(t_cost), Some(p_cost)) = (m_cost, t_cost, p_cost) else {
+            return Err(XCryptParseError::InvalidParams);
+        };
+        if m_cost > M_COST_MAX || t_cost > T_COST_MAX || p_cost > P_COST_MAX {
+            return Err(XCryptParseError::OutOfRangeRounds);
+        }
+        s = &s[d + 1..];
+
+        let Some(d) = memchr::memchr(b'$', s.as_bytes()) else {
+            return Err(XCryptParseError::NoSaltFound);
+        };
+        let salt = BASE64_STANDARD_NO_PAD
+            .decode(&s[0..d])
+            .map_err(|_| XCryptParseError::InvalidSalt)?;
+        if salt.len() < argon2::MIN_SALT_LEN {
+            return Err(XCryptParseError::InvalidSalt);
+        }
+        let hash_bin = BASE64_STANDARD_NO_PAD
+            .decode(&s[d + 1..])
+            .map_err(|_| XCryptParseError::InvalidHash)?;
+
+        let params = Params::new(m_cost, t_cost, p_cost, Some(hash_bin.len()))
+            .map_err(|_| XCryptParseError::OutOfRangeRounds)?;
+
+        Ok(Argon2Crypt {
+            algorithm,
+            version,
+            params,
+            salt,
+            hash_bin,
+        })
+    }
+
+    pub(super) fn verify(&self, phrase: &[u8]) -> bool {
+        let argon2 = Argon2::new(self.algorithm, self.version, self.params.clone());
+        let mut hash = vec![0u8; self.hash_bin.len()];
+        if argon2
+            .hash_password_into(phrase, &self.salt, &mut hash)
+            .is_err()
+        {
+            return false;
+        }
+        self.hash_bin.eq(&hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT_AND_HASH: &str = "c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8";
+
+    fn parse_params(params: &str) -> XCryptParseResult<Argon2Crypt> {
+        Argon2Crypt::parse(Algorithm::Argon2id, &format!("{params}${SALT_AND_HASH}"))
+    }
+
+    #[test]
+    fn params_in_range() {
+        let crypt = parse_params("v=19$m=65536,t=3,p=4").unwrap();
+        assert_eq!(crypt.version, Version::V0x13);
+        assert_eq!(crypt.params.m_cost(), 65536);
+        assert_eq!(crypt.params.t_cost(), 3);
+        assert_eq!(crypt.params.p_cost(), 4);
+
+        let crypt = parse_params("m=1048576,t=8,p=16").unwrap();
+        assert_eq!(crypt.params.m_cost(), M_COST_MAX);
+        assert_eq!(crypt.params.t_cost(), T_COST_MAX);
+        assert_eq!(crypt.params.p_cost(), P_COST_MAX);
+    }
+
+    #[test]
+    fn params_out_of_range() {
+        assert!(matches!(
+            parse_params("m=1048577,t=3,p=4"),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+        assert!(matches!(
+            parse_params("m=65536,t=9,p=4"),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+        assert!(matches!(
+            parse_params("m=65536,t=3,p=17"),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+        assert!(matches!(
+            parse_params("m=65536,t=0,p=4"),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+    }
+}
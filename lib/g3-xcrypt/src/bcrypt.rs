@@ -0,0 +1,189 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use blowfish::Blowfish;
+
+use super::{XCryptParseError, XCryptParseResult};
+
+pub(super) const PREFIX_2A: &str = "$2a$";
+pub(super) const PREFIX_2B: &str = "$2b$";
+pub(super) const PREFIX_2Y: &str = "$2y$";
+
+const COST_MIN: u32 = 4;
+/// libxcrypt allows up to 31, but that would take days to verify, so limit it to 65536 rounds
+const COST_MAX: u32 = 16;
+
+const SALT_BIN_LEN: usize = 16;
+const SALT_STR_LEN: usize = 22;
+
+const HASH_BIN_LEN: usize = 23;
+const HASH_STR_LEN: usize = 31;
+
+/// the key is the phrase with the trailing NUL, truncated to 72 bytes
+const KEY_LEN_MAX: usize = 72;
+
+/// "OrpheanBeholderScryDoubt" in big endian u32
+const MAGIC_CTEXT: [u32; 6] = [
+    0x4f727068, 0x65616e42, 0x65686f6c, 0x64657253, 0x63727944, 0x6f756274,
+];
+
+const BCRYPT_HASH64: &[u8] = b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+fn bcrypt_char_to_u32(c: u8) -> Option<u32> {
+    BCRYPT_HASH64
+        .iter()
+        .position(|v| *v == c)
+        .map(|pos| pos as u32)
+}
+
+/// decode with the bcrypt alphabet, the bits are used in big endian order
+fn bcrypt_b64_decode(input: &[u8], output: &mut [u8]) -> bool {
+    let mut bits: u32 = 0;
+    let mut bits_len = 0;
+    let mut offset = 0;
+    for c in input {
+        let Some(v) = bcrypt_char_to_u32(*c) else {
+            return false;
+        };
+        bits = (bits << 6) | v;
+        bits_len += 6;
+        if bits_len >= 8 {
+            bits_len -= 8;
+            if offset < output.len() {
+                output[offset] = (bits >> bits_len) as u8;
+                offset += 1;
+            }
+            bits &= (1 << bits_len) - 1;
+        }
+    }
+    offset == output.len()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BCrypt {
+    cost: u32,
+    salt: [u8; SALT_BIN_LEN],
+    hash_bin: [u8; HASH_BIN_LEN],
+}
+
+fn do_bcrypt_hash(phrase: &[u8], salt: &[u8], cost: u32) -> [u8; HASH_BIN_LEN] {
+    let mut key = Vec::with_capacity(phrase.len() + 1);
+    key.extend_from_slice(phrase);
+    key.push(0);
+    key.truncate(KEY_LEN_MAX);
+
+    let mut state = Blowfish::bc_init_state();
+    state.salted_expand_key(salt, &key);
+    for _ in 0..(1u64 << cost) {
+        state.bc_expand_key(&key);
+        state.bc_expand_key(salt);
+    }
+
+    let mut ctext = MAGIC_CTEXT;
+    for i in (0..ctext.len()).step_by(2) {
+        for _ in 0..64 {
+            let [l, r] = state.bc_encrypt([ctext[i], ctext[i + 1]]);
+            ctext[i] = l;
+            ctext[i + 1] = r;
+        }
+    }
+
+    let mut output = [0u8; 24];
+    for (i, v) in ctext.iter().enumerate() {
+        output[i * 4..i * 4 + 4].copy_from_slice(&v.to_be_bytes());
+    }
+
+    let mut hash = [0u8; HASH_BIN_LEN];
+    hash.copy_from_slice(&output[0..HASH_BIN_LEN]);
+    hash
+}
+
+impl BCrypt {
+    pub(super) fn parse(v: &str) -> XCryptParseResult<Self> {
+        let Some(d) = memchr::memchr(b'$', v.as_bytes()) else {
+            return Err(XCryptParseError::InvalidRounds);
+        };
+        if d != 2 {
+            return Err(XCryptParseError::InvalidRounds);
+        }
+        let cost = u32::from_str(&v[0..d]).map_err(|_| XCryptParseError::InvalidRounds)?;
+        if !(COST_MIN..=COST_MAX).contains(&cost) {
+            return Err(XCryptParseError::OutOfRangeRounds);
+        }
+
+        let s = &v.as_bytes()[d + 1..];
+        if s.len() < SALT_STR_LEN {
+            return Err(XCryptParseError::NoSaltFound);
+        }
+        if s.len() != SALT_STR_LEN + HASH_STR_LEN {
+            return Err(XCryptParseError::InvalidHashSize);
+        }
+
+        let mut salt = [0u8; SALT_BIN_LEN];
+        if !bcrypt_b64_decode(&s[0..SALT_STR_LEN], &mut salt) {
+            return Err(XCryptParseError::InvalidSalt);
+        }
+        let mut hash_bin = [0u8; HASH_BIN_LEN];
+        if !bcrypt_b64_decode(&s[SALT_STR_LEN..], &mut hash_bin) {
+            return Err(XCryptParseError::InvalidHash);
+        }
+
+        Ok(BCrypt {
+            cost,
+            salt,
+            hash_bin,
+        })
+    }
+
+    pub(super) fn verify(&self, phrase: &[u8]) -> bool {
+        let hash = do_bcrypt_hash(phrase, &self.salt, self.cost);
+        self.hash_bin.eq(&hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT_AND_HASH: &str = "abcdefghijklmnopqrstuu1Z..wm/IlVbtpza52hgqCBArL1cvSg6";
+
+    #[test]
+    fn cost_in_range() {
+        let crypt = BCrypt::parse(&format!("05${SALT_AND_HASH}")).unwrap();
+        assert_eq!(crypt.cost, 5);
+
+        let crypt = BCrypt::parse(&format!("16${SALT_AND_HASH}")).unwrap();
+        assert_eq!(crypt.cost, COST_MAX);
+    }
+
+    #[test]
+    fn cost_out_of_range() {
+        assert!(matches!(
+            BCrypt::parse(&format!("03${SALT_AND_HASH}")),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+        assert!(matches!(
+            BCrypt::parse(&format!("17${SALT_AND_HASH}")),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+        assert!(matches!(
+            BCrypt::parse(&format!("31${SALT_AND_HASH}")),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+    }
+}
@@ -30,6 +30,14 @@ pub enum XCryptParseError {
     SaltTooLong,
     #[error("invalid hash size")]
     InvalidHashSize,
+    #[error("invalid params")]
+    InvalidParams,
+    #[error("unsupported params")]
+    UnsupportedParams,
+    #[error("invalid salt")]
+    InvalidSalt,
+    #[error("invalid hash")]
+    InvalidHash,
 }
 
 pub type XCryptParseResult<T> = Result<T, XCryptParseError>;
@@ -21,15 +21,21 @@ pub use b64::B64CryptEncoder;
 mod error;
 pub use error::{XCryptParseError, XCryptParseResult};
 
+mod argon2;
+mod bcrypt;
 mod md5;
 mod sha256;
 mod sha512;
+mod yescrypt;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum XCryptHash {
     Md5(md5::Md5Crypt),
     Sha256(sha256::Sha256Crypt),
     Sha512(sha512::Sha512Crypt),
+    BCrypt(bcrypt::BCrypt),
+    YesCrypt(yescrypt::YesCrypt),
+    Argon2(argon2::Argon2Crypt),
 }
 
 impl XCryptHash {
@@ -43,6 +49,25 @@ impl XCryptHash {
         } else if let Some(s) = v.strip_prefix(sha512::PREFIX) {
             let v = sha512::Sha512Crypt::parse(s)?;
             Ok(XCryptHash::Sha512(v))
+        } else if let Some(s) = v
+            .strip_prefix(bcrypt::PREFIX_2B)
+            .or_else(|| v.strip_prefix(bcrypt::PREFIX_2A))
+            .or_else(|| v.strip_prefix(bcrypt::PREFIX_2Y))
+        {
+            let v = bcrypt::BCrypt::parse(s)?;
+            Ok(XCryptHash::BCrypt(v))
+        } else if let Some(s) = v.strip_prefix(yescrypt::PREFIX) {
+            let v = yescrypt::YesCrypt::parse(s)?;
+            Ok(XCryptHash::YesCrypt(v))
+        } else if let Some(s) = v.strip_prefix(argon2::PREFIX_ARGON2ID) {
+            let v = argon2::Argon2Crypt::parse(::argon2::Algorithm::Argon2id, s)?;
+            Ok(XCryptHash::Argon2(v))
+        } else if let Some(s) = v.strip_prefix(argon2::PREFIX_ARGON2I) {
+            let v = argon2::Argon2Crypt::parse(::argon2::Algorithm::Argon2i, s)?;
+            Ok(XCryptHash::Argon2(v))
+        } else if let Some(s) = v.strip_prefix(argon2::PREFIX_ARGON2D) {
+            let v = argon2::Argon2Crypt::parse(::argon2::Algorithm::Argon2d, s)?;
+            Ok(XCryptHash::Argon2(v))
         } else {
             Err(XCryptParseError::UnknownPrefix)
         }
@@ -53,8 +78,20 @@ impl XCryptHash {
             XCryptHash::Md5(this) => this.verify(phrase),
             XCryptHash::Sha256(this) => this.verify(phrase),
             XCryptHash::Sha512(this) => this.verify(phrase),
+            XCryptHash::BCrypt(this) => this.verify(phrase),
+            XCryptHash::YesCrypt(this) => this.verify(phrase),
+            XCryptHash::Argon2(this) => this.verify(phrase),
         }
     }
+
+    /// Check if the verification is slow enough to block the caller thread noticeably.
+    /// The result of these verifications should be cached by the caller.
+    pub fn is_slow(&self) -> bool {
+        !matches!(
+            self,
+            XCryptHash::Md5(_) | XCryptHash::Sha256(_) | XCryptHash::Sha512(_)
+        )
+    }
 }
 
 #[cfg(test)]
@@ -82,4 +119,66 @@ mod tests {
         let crypt = XCryptHash::parse(s).unwrap();
         assert!(crypt.verify("123456".as_bytes()));
     }
+
+    #[test]
+    fn bcrypt() {
+        let crypt =
+            XCryptHash::parse("$2b$05$abcdefghijklmnopqrstuu1Z..wm/IlVbtpza52hgqCBArL1cvSg6")
+                .unwrap();
+        assert!(crypt.verify("123456".as_bytes()));
+        assert!(!crypt.verify("1234567".as_bytes()));
+
+        let crypt =
+            XCryptHash::parse("$2y$05$SPjp1abrI5c9VS7.bIO.ueQ7PEGKW2jQ1Y.tXwZIRS//Vjf4SFmX6")
+                .unwrap();
+        assert!(crypt.verify("123456".as_bytes()));
+
+        assert!(
+            XCryptHash::parse("$2b$03$abcdefghijklmnopqrstuu1Z..wm/IlVbtpza52hgqCBArL1cvSg6")
+                .is_err()
+        );
+        assert!(
+            XCryptHash::parse("$2b$05$abcdefghijklmnopqrstuu1Z..wm/IlVbtpza52hgqCBArL1cvSg")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn yescrypt() {
+        let crypt = XCryptHash::parse(
+            "$y$j75$abcdefghijklmnop$F9QDaKuC6ezplJyySxOgVMX0/L/594IN8K7Y5R0vcY9",
+        )
+        .unwrap();
+        assert!(crypt.verify("123456".as_bytes()));
+        assert!(!crypt.verify("1234567".as_bytes()));
+
+        // the default params, with pre-hashing
+        let crypt = XCryptHash::parse(
+            "$y$j9T$8MDfgy.udE1TmPjp0sTaf1$slwJ8uWjUHrVxQ9ys4ZUhoIxwgx0ASjgMVBjENo8oK7",
+        )
+        .unwrap();
+        assert!(crypt.verify("123456".as_bytes()));
+
+        assert!(XCryptHash::parse("$y$j9T$$slwJ8uWjUHrVxQ9ys4ZUhoIxwgx0ASjgMVBjENo8oK7").is_err());
+        // the classic scrypt flavor is not supported
+        assert!(
+            XCryptHash::parse("$y$.3.$abcd$mOrXZu7Vhj.KQyBFx.A2ILZqFVZxAIrX1wdt.lB7P60").is_err()
+        );
+    }
+
+    #[test]
+    fn argon2() {
+        let s = "$argon2id$v=19$m=19456,t=2,p=1$0HPP3f+fVZ9CTmZVAIfoIA$\
+            B7UFgF5v24ldCtHigJB92eBzM0ryO7lKsp4UrODs+6E";
+        let crypt = XCryptHash::parse(s).unwrap();
+        assert!(crypt.verify("123456".as_bytes()));
+        assert!(!crypt.verify("1234567".as_bytes()));
+
+        let s = "$argon2i$v=19$m=4096,t=3,p=1$c2FsdHNhbHRzYWx0$\
+            5g6grdE/xHHZz68gvdiuqDpn7nHXohf5P5vQmstCt5Q";
+        let crypt = XCryptHash::parse(s).unwrap();
+        assert!(crypt.verify("123456".as_bytes()));
+
+        assert!(XCryptHash::parse("$argon2id$v=19$m=19456,t=2$0HPP3f+fVZ9CTmZVAIfoIA$B7UFgF5v24ldCtHigJB92eBzM0ryO7lKsp4UrODs+6E").is_err());
+    }
 }
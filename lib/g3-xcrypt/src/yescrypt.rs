@@ -0,0 +1,564 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use digest::Digest;
+use sha2::Sha256;
+
+use super::{XCryptParseError, XCryptParseResult};
+
+pub(super) const PREFIX: &str = "$y$";
+
+const YESCRYPT_RW: u32 = 0x002;
+const YESCRYPT_RW_FLAVOR_MASK: u32 = 0x3fc;
+/// RW, 6 rounds, gather 4, simple 2 and 12KiB sbox, which is the only flavor in common use
+const YESCRYPT_DEFAULTS: u32 = 0x0b6;
+
+const PWX_SIMPLE: usize = 2;
+const PWX_GATHER: usize = 4;
+const PWX_ROUNDS: usize = 6;
+const S_WIDTH: usize = 8;
+
+const PWX_WORDS: usize = PWX_GATHER * PWX_SIMPLE * 2;
+const S_BOX_WORDS: usize = (1 << S_WIDTH) * PWX_SIMPLE * 2;
+const S_WORDS: usize = 3 * S_BOX_WORDS;
+const S_MASK: u32 = (((1 << S_WIDTH) - 1) * PWX_SIMPLE * 8) as u32;
+
+/// the max value of N used by libxcrypt, which will use 1GiB memory with the max r
+const N_LOG2_MAX: u32 = 18;
+const R_MAX: u32 = 32;
+const T_MAX: u32 = 8;
+const SALT_BIN_LEN_MAX: usize = 64;
+
+const HASH_BIN_LEN: usize = 32;
+const HASH_STR_LEN: usize = 43;
+
+const YESCRYPT_HASH64: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+fn atoi64(c: u8) -> Option<u32> {
+    YESCRYPT_HASH64
+        .iter()
+        .position(|v| *v == c)
+        .map(|pos| pos as u32)
+}
+
+/// decode the variable length integer used in the params string
+fn decode64_u32(s: &[u8], offset: &mut usize, min: u32) -> Option<u32> {
+    let mut start: u32 = 0;
+    let mut end: u32 = 47;
+    let mut chars = 1;
+    let mut bits = 0;
+
+    let mut c = atoi64(*s.get(*offset)?)?;
+    *offset += 1;
+
+    let mut value = min;
+    while c > end {
+        value = value.checked_add((end + 1 - start) << bits)?;
+        start = end + 1;
+        end = start + (62 - end) / 2;
+        chars += 1;
+        bits += 6;
+    }
+    value = value.checked_add((c - start) << bits)?;
+
+    while chars > 1 {
+        c = atoi64(*s.get(*offset)?)?;
+        *offset += 1;
+        bits -= 6;
+        value = value.checked_add(c << bits)?;
+        chars -= 1;
+    }
+    Some(value)
+}
+
+/// decode with the crypt alphabet, the bits are used in little endian order
+fn decode64(s: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.chunks(4) {
+        if chunk.len() < 2 {
+            return None;
+        }
+        let mut value: u32 = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            value |= atoi64(*c)? << (i * 6);
+        }
+        for _ in 0..(chunk.len() * 6 / 8) {
+            output.push(value as u8);
+            value >>= 8;
+        }
+        if value != 0 {
+            return None;
+        }
+    }
+    Some(output)
+}
+
+fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> [u8; 32] {
+    let mut k = [0u8; 64];
+    if key.len() > k.len() {
+        k[0..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        k[0..key.len()].copy_from_slice(key);
+    }
+
+    let mut digest = Sha256::new();
+    digest.update(k.map(|v| v ^ 0x36));
+    for d in data {
+        digest.update(d);
+    }
+    let inner = digest.finalize();
+
+    let mut digest = Sha256::new();
+    digest.update(k.map(|v| v ^ 0x5c));
+    digest.update(inner);
+    digest.finalize().into()
+}
+
+/// PBKDF2-HMAC-SHA256 with only 1 iteration
+fn pbkdf2_sha256(passwd: &[u8], salt: &[u8], output: &mut [u8]) {
+    for (i, chunk) in output.chunks_mut(32).enumerate() {
+        let index = (i as u32 + 1).to_be_bytes();
+        let u = hmac_sha256(passwd, &[salt, &index]);
+        chunk.copy_from_slice(&u[0..chunk.len()]);
+    }
+}
+
+fn blk_xor(dst: &mut [u32], src: &[u32]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= *s;
+    }
+}
+
+/// the salsa20 core, with the input and output in SIMD shuffled order
+fn salsa20(b: &mut [u32], double_rounds: usize) {
+    let mut x = [0u32; 16];
+    for i in 0..16 {
+        x[i * 5 % 16] = b[i];
+    }
+
+    macro_rules! quarter {
+        ($a:expr, $b:expr, $c:expr, $d:expr) => {
+            x[$b] ^= x[$a].wrapping_add(x[$d]).rotate_left(7);
+            x[$c] ^= x[$b].wrapping_add(x[$a]).rotate_left(9);
+            x[$d] ^= x[$c].wrapping_add(x[$b]).rotate_left(13);
+            x[$a] ^= x[$d].wrapping_add(x[$c]).rotate_left(18);
+        };
+    }
+
+    for _ in 0..double_rounds {
+        quarter!(0, 4, 8, 12);
+        quarter!(5, 9, 13, 1);
+        quarter!(10, 14, 2, 6);
+        quarter!(15, 3, 7, 11);
+        quarter!(0, 1, 2, 3);
+        quarter!(5, 6, 7, 4);
+        quarter!(10, 11, 8, 9);
+        quarter!(15, 12, 13, 14);
+    }
+
+    for i in 0..16 {
+        b[i] = b[i].wrapping_add(x[i * 5 % 16]);
+    }
+}
+
+fn blockmix_salsa8(b: &mut [u32], y: &mut [u32], r: usize) {
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&b[(2 * r - 1) * 16..2 * r * 16]);
+
+    for i in 0..2 * r {
+        blk_xor(&mut x, &b[i * 16..(i + 1) * 16]);
+        salsa20(&mut x, 4);
+        y[i * 16..(i + 1) * 16].copy_from_slice(&x);
+    }
+
+    for i in 0..r {
+        b[i * 16..(i + 1) * 16].copy_from_slice(&y[i * 2 * 16..(i * 2 + 1) * 16]);
+    }
+    for i in 0..r {
+        b[(i + r) * 16..(i + r + 1) * 16].copy_from_slice(&y[(i * 2 + 1) * 16..(i * 2 + 2) * 16]);
+    }
+}
+
+struct PwxformCtx {
+    s: Vec<u32>,
+    s0: usize,
+    s1: usize,
+    s2: usize,
+    w: usize,
+}
+
+impl PwxformCtx {
+    fn new() -> Self {
+        PwxformCtx {
+            s: vec![0u32; S_WORDS],
+            s0: 2 * S_BOX_WORDS,
+            s1: S_BOX_WORDS,
+            s2: 0,
+            w: 0,
+        }
+    }
+
+    fn pwxform(&mut self, x: &mut [u32]) {
+        let mut w = self.w;
+
+        for i in 0..PWX_ROUNDS {
+            for j in 0..PWX_GATHER {
+                let xj = &mut x[j * PWX_SIMPLE * 2..(j + 1) * PWX_SIMPLE * 2];
+                let p0 = self.s0 + (xj[0] & S_MASK) as usize / 4;
+                let p1 = self.s1 + (xj[1] & S_MASK) as usize / 4;
+
+                for k in 0..PWX_SIMPLE {
+                    let s0 = ((self.s[p0 + k * 2 + 1] as u64) << 32) | self.s[p0 + k * 2] as u64;
+                    let s1 = ((self.s[p1 + k * 2 + 1] as u64) << 32) | self.s[p1 + k * 2] as u64;
+
+                    let v = ((xj[k * 2 + 1] as u64) * (xj[k * 2] as u64)).wrapping_add(s0) ^ s1;
+                    xj[k * 2] = v as u32;
+                    xj[k * 2 + 1] = (v >> 32) as u32;
+                }
+
+                if i != 0 && i != PWX_ROUNDS - 1 {
+                    let offset = self.s2 + w * PWX_SIMPLE * 2;
+                    self.s[offset..offset + PWX_SIMPLE * 2].copy_from_slice(xj);
+                    w += 1;
+                }
+            }
+        }
+
+        (self.s0, self.s1, self.s2) = (self.s2, self.s0, self.s1);
+        self.w = w & ((1 << S_WIDTH) - 1);
+    }
+
+    fn blockmix(&mut self, b: &mut [u32], r: usize) {
+        let r1 = 2 * r;
+
+        let mut x = [0u32; PWX_WORDS];
+        x.copy_from_slice(&b[(r1 - 1) * PWX_WORDS..r1 * PWX_WORDS]);
+
+        for i in 0..r1 {
+            blk_xor(&mut x, &b[i * PWX_WORDS..(i + 1) * PWX_WORDS]);
+            self.pwxform(&mut x);
+            b[i * PWX_WORDS..(i + 1) * PWX_WORDS].copy_from_slice(&x);
+        }
+
+        let i = r1 - 1;
+        salsa20(&mut b[i * 16..(i + 1) * 16], 1);
+    }
+}
+
+fn integerify(x: &[u32], r: usize) -> u64 {
+    let offset = (2 * r - 1) * 16;
+    ((x[offset + 13] as u64) << 32) | x[offset] as u64
+}
+
+fn p2floor(mut v: u64) -> u64 {
+    while v & (v - 1) != 0 {
+        v &= v - 1;
+    }
+    v
+}
+
+fn wrap(x: u64, i: u64) -> u64 {
+    let n = p2floor(i);
+    (x & (n - 1)) + (i - n)
+}
+
+fn load_block(b: &[u8], x: &mut [u32]) {
+    for k in 0..x.len() / 16 {
+        for i in 0..16 {
+            let offset = (k * 16 + (i * 5 % 16)) * 4;
+            x[k * 16 + i] = u32::from_le_bytes(b[offset..offset + 4].try_into().unwrap());
+        }
+    }
+}
+
+fn save_block(x: &[u32], b: &mut [u8]) {
+    for k in 0..x.len() / 16 {
+        for i in 0..16 {
+            let offset = (k * 16 + (i * 5 % 16)) * 4;
+            b[offset..offset + 4].copy_from_slice(&x[k * 16 + i].to_le_bytes());
+        }
+    }
+}
+
+fn smix1(
+    b: &mut [u8],
+    r: usize,
+    n: u64,
+    v: &mut [u32],
+    xy: &mut [u32],
+    mut ctx: Option<&mut PwxformCtx>,
+) {
+    let s = 32 * r;
+    let (x, y) = xy.split_at_mut(s);
+    let x = &mut x[0..s];
+
+    load_block(b, x);
+
+    for i in 0..n {
+        let vi = i as usize * s;
+        v[vi..vi + s].copy_from_slice(x);
+
+        if ctx.is_some() && i > 1 {
+            let j = wrap(integerify(x, r), i) as usize * s;
+            blk_xor(x, &v[j..j + s]);
+        }
+
+        match ctx.as_deref_mut() {
+            Some(ctx) => ctx.blockmix(x, r),
+            None => blockmix_salsa8(x, y, r),
+        }
+    }
+
+    save_block(x, b);
+}
+
+fn smix2(
+    b: &mut [u8],
+    r: usize,
+    n: u64,
+    n_loop: u64,
+    v: &mut [u32],
+    xy: &mut [u32],
+    ctx: &mut PwxformCtx,
+) {
+    if n_loop == 0 {
+        return;
+    }
+
+    let s = 32 * r;
+    let x = &mut xy[0..s];
+
+    load_block(b, x);
+
+    for _ in 0..n_loop {
+        let j = (integerify(x, r) & (n - 1)) as usize * s;
+        blk_xor(x, &v[j..j + s]);
+        v[j..j + s].copy_from_slice(x);
+
+        ctx.blockmix(x, r);
+    }
+
+    save_block(x, b);
+}
+
+fn smix(b: &mut [u8], r: usize, n: u64, t: u32, passwd: &mut [u8; 32]) {
+    let mut n_loop_rw = if t <= 1 {
+        let n = if t == 1 { n * 2 } else { n };
+        n.div_ceil(3)
+    } else {
+        n * (t as u64 - 1)
+    };
+    n_loop_rw = (n_loop_rw + 1) & !1;
+
+    let mut v = vec![0u32; n as usize * 32 * r];
+    let mut xy = vec![0u32; 64 * r];
+    let mut ctx = PwxformCtx::new();
+
+    smix1(
+        &mut b[0..128],
+        1,
+        (S_WORDS / 32) as u64,
+        &mut ctx.s,
+        &mut xy,
+        None,
+    );
+
+    *passwd = hmac_sha256(&b[128 * r - 64..128 * r], &[passwd.as_slice()]);
+
+    smix1(b, r, n, &mut v, &mut xy, Some(&mut ctx));
+    smix2(b, r, p2floor(n), n_loop_rw, &mut v, &mut xy, &mut ctx);
+}
+
+fn yescrypt_kdf_body(
+    passwd: &[u8],
+    salt: &[u8],
+    n: u64,
+    r: usize,
+    t: u32,
+    prehash: bool,
+) -> [u8; HASH_BIN_LEN] {
+    let key: &[u8] = if prehash {
+        b"yescrypt-prehash"
+    } else {
+        b"yescrypt"
+    };
+    let passwd = hmac_sha256(key, &[passwd]);
+
+    let mut b = vec![0u8; 128 * r];
+    pbkdf2_sha256(&passwd, salt, &mut b);
+
+    let mut passwd = [0u8; 32];
+    passwd.copy_from_slice(&b[0..32]);
+    smix(&mut b, r, n, t, &mut passwd);
+
+    let mut dk = [0u8; HASH_BIN_LEN];
+    pbkdf2_sha256(&passwd, &b, &mut dk);
+
+    if !prehash {
+        // the same as the StoredKey in SCRAM
+        let client_key = hmac_sha256(&dk, &[b"Client Key"]);
+        dk = Sha256::digest(client_key).into();
+    }
+    dk
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct YesCrypt {
+    n_log2: u32,
+    r: u32,
+    t: u32,
+    salt: Vec<u8>,
+    hash_bin: [u8; HASH_BIN_LEN],
+}
+
+impl YesCrypt {
+    pub(super) fn parse(v: &str) -> XCryptParseResult<Self> {
+        let s = v.as_bytes();
+        let mut offset = 0;
+
+        let flavor = decode64_u32(s, &mut offset, 0).ok_or(XCryptParseError::InvalidParams)?;
+        let flags = if flavor < YESCRYPT_RW {
+            flavor
+        } else if flavor <= YESCRYPT_RW + (YESCRYPT_RW_FLAVOR_MASK >> 2) {
+            YESCRYPT_RW + ((flavor - YESCRYPT_RW) << 2)
+        } else {
+            return Err(XCryptParseError::InvalidParams);
+        };
+        if flags != YESCRYPT_DEFAULTS {
+            return Err(XCryptParseError::UnsupportedParams);
+        }
+
+        let n_log2 = decode64_u32(s, &mut offset, 1).ok_or(XCryptParseError::InvalidParams)?;
+        if n_log2 > N_LOG2_MAX {
+            return Err(XCryptParseError::OutOfRangeRounds);
+        }
+        let r = decode64_u32(s, &mut offset, 1).ok_or(XCryptParseError::InvalidParams)?;
+        if r > R_MAX || (r as u64) << n_log2 > (usize::MAX / 128) as u64 {
+            return Err(XCryptParseError::OutOfRangeRounds);
+        }
+
+        let mut t = 0;
+        if s.get(offset) != Some(&b'$') {
+            let have = decode64_u32(s, &mut offset, 1).ok_or(XCryptParseError::InvalidParams)?;
+            if have & 1 != 0 {
+                let p = decode64_u32(s, &mut offset, 2).ok_or(XCryptParseError::InvalidParams)?;
+                if p != 1 {
+                    return Err(XCryptParseError::UnsupportedParams);
+                }
+            }
+            if have & 2 != 0 {
+                t = decode64_u32(s, &mut offset, 1).ok_or(XCryptParseError::InvalidParams)?;
+                if t > T_MAX {
+                    return Err(XCryptParseError::OutOfRangeRounds);
+                }
+            }
+            if have & !3 != 0 {
+                // the upgrade (g) and ROM params are not supported
+                return Err(XCryptParseError::UnsupportedParams);
+            }
+        }
+        if s.get(offset) != Some(&b'$') {
+            return Err(XCryptParseError::InvalidParams);
+        }
+        offset += 1;
+
+        let s = &s[offset..];
+        let Some(d) = memchr::memrchr(b'$', s) else {
+            return Err(XCryptParseError::NoSaltFound);
+        };
+        if d == 0 {
+            return Err(XCryptParseError::NoSaltFound);
+        }
+        let salt = decode64(&s[0..d]).ok_or(XCryptParseError::InvalidSalt)?;
+        if salt.len() > SALT_BIN_LEN_MAX {
+            return Err(XCryptParseError::SaltTooLong);
+        }
+
+        let hash = &s[d + 1..];
+        if hash.len() != HASH_STR_LEN {
+            return Err(XCryptParseError::InvalidHashSize);
+        }
+        let hash = decode64(hash).ok_or(XCryptParseError::InvalidHash)?;
+        let mut hash_bin = [0u8; HASH_BIN_LEN];
+        hash_bin.copy_from_slice(&hash);
+
+        Ok(YesCrypt {
+            n_log2,
+            r,
+            t,
+            salt,
+            hash_bin,
+        })
+    }
+
+    pub(super) fn verify(&self, phrase: &[u8]) -> bool {
+        let n = 1u64 << self.n_log2;
+        let r = self.r as usize;
+
+        let prehash_dk: [u8; HASH_BIN_LEN];
+        let passwd = if n >= 0x100 && n * r as u64 >= 0x20000 {
+            prehash_dk = yescrypt_kdf_body(phrase, &self.salt, n >> 6, r, 0, true);
+            prehash_dk.as_slice()
+        } else {
+            phrase
+        };
+
+        let hash = yescrypt_kdf_body(passwd, &self.salt, n, r, self.t, false);
+        self.hash_bin.eq(&hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT_AND_HASH: &str =
+        "8MDfgy.udE1TmPjp0sTaf1$slwJ8uWjUHrVxQ9ys4ZUhoIxwgx0ASjgMVBjENo8oK7";
+
+    fn parse_params(params: &str) -> XCryptParseResult<YesCrypt> {
+        YesCrypt::parse(&format!("{params}${SALT_AND_HASH}"))
+    }
+
+    #[test]
+    fn params_in_range() {
+        let crypt = parse_params("j9T").unwrap();
+        assert_eq!(crypt.n_log2, 12);
+        assert_eq!(crypt.r, 32);
+        assert_eq!(crypt.t, 0);
+
+        let crypt = parse_params("jFT").unwrap();
+        assert_eq!(crypt.n_log2, N_LOG2_MAX);
+
+        let crypt = parse_params("j9T/5").unwrap();
+        assert_eq!(crypt.t, T_MAX);
+    }
+
+    #[test]
+    fn params_out_of_range() {
+        assert!(matches!(
+            parse_params("jGT"),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+        assert!(matches!(
+            parse_params("j9U"),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+        assert!(matches!(
+            parse_params("j9T/6"),
+            Err(XCryptParseError::OutOfRangeRounds)
+        ));
+    }
+}
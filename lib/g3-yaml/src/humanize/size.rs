@@ -45,6 +45,20 @@ pub fn as_u32(v: &Yaml) -> anyhow::Result<u32> {
     }
 }
 
+pub fn as_u64(v: &Yaml) -> anyhow::Result<u64> {
+    match v {
+        Yaml::String(value) => {
+            let v = value.parse::<Bytes>()?;
+            let v = u64::try_from(v.size()).map_err(|e| anyhow!("invalid u64 value: {e}"))?;
+            Ok(v)
+        }
+        Yaml::Integer(value) => Ok(u64::try_from(*value)?),
+        _ => Err(anyhow!(
+            "yaml value type for humanize u64 should be 'string' or 'integer'"
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,4 +89,16 @@ mod tests {
         let v = Yaml::Array(vec![Yaml::Integer(1)]);
         assert!(as_usize(&v).is_err());
     }
+
+    #[test]
+    fn t_u64() {
+        let v = Yaml::String("1GiB".to_string());
+        assert_eq!(as_u64(&v).unwrap(), 1 << 30);
+
+        let v = Yaml::Integer(1024);
+        assert_eq!(as_u64(&v).unwrap(), 1024);
+
+        let v = Yaml::Integer(-1024);
+        assert!(as_u64(&v).is_err());
+    }
 }
@@ -0,0 +1,159 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use regex::Regex;
+use yaml_rust::Yaml;
+
+use g3_types::acl::{AclAction, AclUrlRuleBuilder};
+
+use super::AclRuleYamlParser;
+
+impl AclRuleYamlParser for AclUrlRuleBuilder {
+    #[inline]
+    fn get_default_found_action(&self) -> AclAction {
+        AclAction::Forbid
+    }
+
+    #[inline]
+    fn set_missed_action(&mut self, _action: AclAction) {
+        self.set_missed_action(_action);
+    }
+
+    fn add_rule_for_action(&mut self, action: AclAction, value: &Yaml) -> anyhow::Result<()> {
+        match value {
+            Yaml::String(s) => self.add_url_prefix(s, action),
+            Yaml::Hash(map) => {
+                let mut scheme: Option<String> = None;
+                let mut host = None;
+                let mut path: Option<String> = None;
+                let mut regex: Option<Regex> = None;
+
+                crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                    "scheme" => {
+                        let s = crate::value::as_string(v)
+                            .context(format!("invalid string value for key {k}"))?;
+                        if s != "*" {
+                            scheme = Some(s);
+                        }
+                        Ok(())
+                    }
+                    "host" => {
+                        if let Yaml::String(s) = v {
+                            if s == "*" {
+                                return Ok(());
+                            }
+                        }
+                        let h = crate::value::as_host(v)
+                            .context(format!("invalid host value for key {k}"))?;
+                        host = Some(h);
+                        Ok(())
+                    }
+                    "path" | "path_prefix" | "prefix" => {
+                        let s = crate::value::as_string(v)
+                            .context(format!("invalid string value for key {k}"))?;
+                        if !s.starts_with('/') {
+                            return Err(anyhow!("path prefix should start with '/'"));
+                        }
+                        path = Some(s);
+                        Ok(())
+                    }
+                    "regex" | "path_regex" => {
+                        let s = crate::value::as_string(v)
+                            .context(format!("invalid string value for key {k}"))?;
+                        let r = Regex::new(&s).map_err(|e| anyhow!("invalid regex value: {e}"))?;
+                        regex = Some(r);
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+
+                match (path, regex) {
+                    (Some(_), Some(_)) => Err(anyhow!(
+                        "path prefix and path regex should not be set at the same time"
+                    )),
+                    (None, Some(regex)) => {
+                        self.add_path_regex(scheme.as_deref(), host, &regex, action);
+                        Ok(())
+                    }
+                    (path, None) => {
+                        let path = path.as_deref().unwrap_or("/");
+                        self.add_path_prefix(scheme.as_deref(), host, path, action);
+                        Ok(())
+                    }
+                }
+            }
+            _ => Err(anyhow!("invalid value type")),
+        }
+    }
+}
+
+pub fn as_url_rule_builder(value: &Yaml) -> anyhow::Result<AclUrlRuleBuilder> {
+    let mut builder = AclUrlRuleBuilder::new(AclAction::Permit);
+    builder.parse(value)?;
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use g3_types::net::Host;
+    use std::str::FromStr;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn parse() {
+        let doc = r#"
+            default: forbid
+            permit:
+              - https://api.example.net/v1/
+              - scheme: https
+                host: api.example.net
+                path_regex: "^/v2/(users|groups)"
+            forbid_log:
+              - host: api.example.net
+                regex: "/admin"
+        "#;
+        let value = &YamlLoader::load_from_str(doc).unwrap()[0];
+        let rule = as_url_rule_builder(value).unwrap().build();
+
+        let host = Host::from_str("api.example.net").unwrap();
+        assert_eq!(
+            rule.check("https", &host, "/v1/users"),
+            (true, AclAction::Permit)
+        );
+        assert_eq!(
+            rule.check("https", &host, "/v2/groups?page=2"),
+            (true, AclAction::Permit)
+        );
+        assert_eq!(
+            rule.check("https", &host, "/v1/admin"),
+            (true, AclAction::ForbidAndLog)
+        );
+        assert_eq!(
+            rule.check("http", &host, "/v1/users"),
+            (false, AclAction::Forbid)
+        );
+
+        let doc = r#"
+            - host: api.example.net
+              path: /v1
+              regex: "^/v1"
+        "#;
+        let value = &YamlLoader::load_from_str(doc).unwrap()[0];
+        assert!(as_url_rule_builder(value).is_err());
+    }
+}
@@ -34,6 +34,26 @@ pub fn as_smtp_interception_config(value: &Yaml) -> anyhow::Result<SmtpIntercept
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "command_wait_timeout" => {
+                config.command_wait_timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "response_wait_timeout" => {
+                config.response_wait_timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "data_initiation_timeout" => {
+                config.data_initiation_timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "data_termination_timeout" => {
+                config.data_termination_timeout = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         })?;
 
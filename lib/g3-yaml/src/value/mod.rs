@@ -59,6 +59,11 @@ mod fluentd;
 #[cfg(feature = "fluentd")]
 pub use fluentd::as_fluentd_client_config;
 
+#[cfg(feature = "otlp")]
+mod otlp;
+#[cfg(feature = "otlp")]
+pub use otlp::as_otlp_exporter_config;
+
 #[cfg(feature = "statsd")]
 mod statsd;
 #[cfg(feature = "statsd")]
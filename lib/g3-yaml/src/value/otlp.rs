@@ -0,0 +1,174 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use http::HeaderValue;
+use yaml_rust::Yaml;
+
+use g3_otlp::{OtlpExporterConfig, OtlpProtocol};
+
+pub fn as_otlp_exporter_config(
+    value: &Yaml,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<OtlpExporterConfig> {
+    match value {
+        Yaml::Hash(map) => {
+            let mut config = OtlpExporterConfig::default();
+            let mut tls_client = None;
+
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "protocol" => {
+                    let s = crate::value::as_string(v)?;
+                    let protocol = OtlpProtocol::from_str(&s)
+                        .map_err(|_| anyhow!("invalid otlp protocol {s}"))?;
+                    config.set_protocol(protocol);
+                    Ok(())
+                }
+                "address" | "addr" => {
+                    let addr = crate::value::as_env_sockaddr(v)?;
+                    config.set_server_addr(addr);
+                    Ok(())
+                }
+                "bind_ip" | "bind" => {
+                    let ip = crate::value::as_ipaddr(v)?;
+                    config.set_bind_ip(ip);
+                    Ok(())
+                }
+                "tcp_keepalive" => {
+                    let keepalive = crate::value::as_tcp_keepalive_config(v)
+                        .context(format!("invalid tcp keepalive config value for key {k}"))?;
+                    config.set_tcp_keepalive(keepalive);
+                    Ok(())
+                }
+                "tls_client" => {
+                    let tls_config = crate::value::as_rustls_client_config_builder(v, lookup_dir)
+                        .context(format!(
+                        "invalid rustls tls client config value for key {k}"
+                    ))?;
+                    tls_client = Some(tls_config);
+                    Ok(())
+                }
+                "tls_name" => {
+                    let tls_name = crate::value::as_rustls_server_name(v)
+                        .context(format!("invalid rustls server name value for key {k}"))?;
+                    config.set_tls_name(tls_name);
+                    Ok(())
+                }
+                "host" => {
+                    let host = crate::value::as_string(v)?;
+                    config.set_host(host);
+                    Ok(())
+                }
+                "logs_path" => {
+                    let path = crate::value::as_http_path_and_query(v)
+                        .context(format!("invalid http path value for key {k}"))?;
+                    config.set_logs_path(path.to_string());
+                    Ok(())
+                }
+                "metrics_path" => {
+                    let path = crate::value::as_http_path_and_query(v)
+                        .context(format!("invalid http path value for key {k}"))?;
+                    config.set_metrics_path(path.to_string());
+                    Ok(())
+                }
+                "headers" => {
+                    if let Yaml::Hash(map) = v {
+                        for (hk, hv) in map.iter() {
+                            let name = crate::value::as_http_header_name(hk)
+                                .context(format!("invalid http header name {hk:?}"))?;
+                            let value = crate::value::as_string(hv)?;
+                            let value = HeaderValue::from_str(&value)
+                                .map_err(|e| anyhow!("invalid value for header {name}: {e}"))?;
+                            config.add_header(name, value);
+                        }
+                        Ok(())
+                    } else {
+                        Err(anyhow!("yaml value type for key {k} should be 'map'"))
+                    }
+                }
+                "resource" | "resource_attributes" => {
+                    if let Yaml::Hash(map) = v {
+                        crate::foreach_kv(map, |k, v| {
+                            let value = crate::value::as_string(v)
+                                .context(format!("invalid string value for key {k}"))?;
+                            config.add_resource_attribute(k.to_string(), value);
+                            Ok(())
+                        })
+                    } else {
+                        Err(anyhow!("yaml value type for key {k} should be 'map'"))
+                    }
+                }
+                "connect_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_connect_timeout(timeout);
+                    Ok(())
+                }
+                "connect_delay" => {
+                    let delay = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_connect_delay(delay);
+                    Ok(())
+                }
+                "write_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_write_timeout(timeout);
+                    Ok(())
+                }
+                "flush_interval" => {
+                    let interval = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_flush_interval(interval);
+                    Ok(())
+                }
+                "max_batch_size" | "batch_size" => {
+                    let size = crate::value::as_usize(v)?;
+                    config.set_max_batch_size(size);
+                    Ok(())
+                }
+                "retry_queue_len" => {
+                    let len = crate::value::as_usize(v)?;
+                    config.set_retry_queue_len(len);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+
+            // set tls client at last, as the alpn protocol depends on the otlp protocol
+            if let Some(tls_config) = tls_client {
+                config
+                    .set_tls_client(tls_config)
+                    .context("failed to set tls client config")?;
+            }
+
+            Ok(config)
+        }
+        Yaml::String(_) => {
+            let addr = crate::value::as_env_sockaddr(value)?;
+            let mut config = OtlpExporterConfig::default();
+            config.set_server_addr(addr);
+            Ok(config)
+        }
+        Yaml::Null => Ok(OtlpExporterConfig::default()),
+        _ => Err(anyhow!(
+            "yaml value type for 'OtlpExporterConfig' should be 'map'"
+        )),
+    }
+}
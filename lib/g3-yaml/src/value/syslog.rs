@@ -15,13 +15,13 @@
  */
 
 use std::net::{IpAddr, SocketAddr};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
-use g3_syslog::{SyslogBackendBuilder, SyslogBuilder, SyslogFormatterKind};
+use g3_syslog::{SyslogBackendBuilder, SyslogBuilder, SyslogFormatterKind, SyslogTcpConfig};
 
 fn as_syslog_format_rfc5424(value: &Yaml) -> anyhow::Result<SyslogFormatterKind> {
     let mut enterprise_id = 0i32;
@@ -93,6 +93,99 @@ fn as_syslog_backend_udp(value: &Yaml) -> anyhow::Result<SyslogBackendBuilder> {
     }
 }
 
+fn as_syslog_backend_tcp(
+    value: &Yaml,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<SyslogBackendBuilder> {
+    match value {
+        Yaml::Hash(map) => {
+            let mut config = SyslogTcpConfig::default();
+            let mut addr: Option<SocketAddr> = None;
+
+            crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+                "address" | "addr" => {
+                    addr = Some(crate::value::as_env_sockaddr(v).context(format!(
+                        "invalid syslog tcp peer socket address value for key {k}"
+                    ))?);
+                    Ok(())
+                }
+                "bind_ip" | "bind" => {
+                    let ip =
+                        crate::value::as_ipaddr(v).context(format!("invalid value for key {k}"))?;
+                    config.set_bind_ip(ip);
+                    Ok(())
+                }
+                "tcp_keepalive" => {
+                    let keepalive = crate::value::as_tcp_keepalive_config(v)
+                        .context(format!("invalid tcp keepalive config value for key {k}"))?;
+                    config.set_tcp_keepalive(keepalive);
+                    Ok(())
+                }
+                "tls_client" => {
+                    let builder = crate::value::as_rustls_client_config_builder(v, lookup_dir)
+                        .context(format!(
+                            "invalid rustls tls client config value for key {k}"
+                        ))?;
+                    let tls_client = builder
+                        .build()
+                        .context("failed to build tls client config")?;
+                    config.set_tls_client(tls_client);
+                    Ok(())
+                }
+                "tls_name" => {
+                    let tls_name = crate::value::as_rustls_server_name(v)
+                        .context(format!("invalid rustls server name value for key {k}"))?;
+                    config.set_tls_name(tls_name);
+                    Ok(())
+                }
+                "connect_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_connect_timeout(timeout);
+                    Ok(())
+                }
+                "write_timeout" => {
+                    let timeout = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_write_timeout(timeout);
+                    Ok(())
+                }
+                "reconnect_min_delay" => {
+                    let delay = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_reconnect_min_delay(delay);
+                    Ok(())
+                }
+                "reconnect_max_delay" => {
+                    let delay = crate::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    config.set_reconnect_max_delay(delay);
+                    Ok(())
+                }
+                "retry_queue_len" | "retry_queue_size" => {
+                    let len = crate::value::as_usize(v)
+                        .context(format!("invalid usize value for key {k}"))?;
+                    config.set_retry_queue_len(len);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+
+            if let Some(addr) = addr.take() {
+                config.set_server_addr(addr);
+                Ok(SyslogBackendBuilder::Tcp(config))
+            } else {
+                Err(anyhow!("no target address has been set"))
+            }
+        }
+        Yaml::String(_) => {
+            let addr = crate::value::as_env_sockaddr(value)?;
+            Ok(SyslogBackendBuilder::Tcp(SyslogTcpConfig::new(addr)))
+        }
+        _ => Err(anyhow!("invalid yaml value for tcp syslog backend")),
+    }
+}
+
 fn as_syslog_backend_unix(value: &Yaml) -> anyhow::Result<SyslogBackendBuilder> {
     match value {
         Yaml::Hash(map) => {
@@ -122,7 +215,11 @@ fn as_syslog_backend_unix(value: &Yaml) -> anyhow::Result<SyslogBackendBuilder>
     }
 }
 
-pub fn as_syslog_builder(value: &Yaml, ident: &'static str) -> anyhow::Result<SyslogBuilder> {
+pub fn as_syslog_builder(
+    value: &Yaml,
+    ident: &'static str,
+    lookup_dir: Option<&Path>,
+) -> anyhow::Result<SyslogBuilder> {
     match value {
         Yaml::Hash(map) => {
             let mut builder = SyslogBuilder::with_ident(ident);
@@ -142,6 +239,12 @@ pub fn as_syslog_builder(value: &Yaml, ident: &'static str) -> anyhow::Result<Sy
                     builder.set_backend(backend);
                     Ok(())
                 }
+                "target_tcp" | "backend_tcp" => {
+                    let backend = as_syslog_backend_tcp(v, lookup_dir)
+                        .context(format!("invalid value for key {k}"))?;
+                    builder.set_backend(backend);
+                    Ok(())
+                }
                 "target" | "backend" => {
                     if let Yaml::Hash(map) = v {
                         crate::hash::foreach_kv(map, |k, v| {
@@ -158,6 +261,12 @@ pub fn as_syslog_builder(value: &Yaml, ident: &'static str) -> anyhow::Result<Sy
                                     builder.set_backend(backend);
                                     Ok(())
                                 }
+                                "tcp" => {
+                                    let backend = as_syslog_backend_tcp(v, lookup_dir)
+                                        .context(format!("invalid value for key {k}"))?;
+                                    builder.set_backend(backend);
+                                    Ok(())
+                                }
                                 _ => Err(anyhow!("invalid key {k}")),
                             }
                         })